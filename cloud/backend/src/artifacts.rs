@@ -25,6 +25,10 @@ impl ArtifactStore {
     }
 
     pub async fn store_text(&self, content: &str, suffix: &str) -> Result<String, AppError> {
+        self.store_bytes(content.as_bytes(), suffix).await
+    }
+
+    pub async fn store_bytes(&self, content: &[u8], suffix: &str) -> Result<String, AppError> {
         let artifact_id = format!("{}.{}", Uuid::new_v4(), suffix);
         let path = self.path(&artifact_id);
         if let Some(parent) = path.parent() {
@@ -37,8 +41,13 @@ impl ArtifactStore {
     }
 
     pub async fn read_text(&self, artifact_id: &str) -> Result<String, AppError> {
+        let content = self.read_bytes(artifact_id).await?;
+        String::from_utf8(content).map_err(|_| AppError::bad_request("Artifact is not text"))
+    }
+
+    pub async fn read_bytes(&self, artifact_id: &str) -> Result<Vec<u8>, AppError> {
         let path = self.path(artifact_id);
-        match fs::read_to_string(&path).await {
+        match fs::read(&path).await {
             Ok(content) => Ok(content),
             Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
                 Err(AppError::not_found("Artifact not found"))
@@ -64,6 +73,18 @@ pub async fn read_artifact(store: &ArtifactStore, artifact_id: &str) -> Result<S
     store.read_text(artifact_id).await
 }
 
+/// Content type served for an artifact, derived from its suffix.
+pub fn content_type(artifact_id: &str) -> &'static str {
+    match artifact_id.rsplit_once('.').map(|(_, suffix)| suffix) {
+        Some("png") => "image/png",
+        Some("jpg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("diff" | "log" | "txt" | "md") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
 pub async fn artifact_url(
     store: &ArtifactStore,
     artifact_id: Option<&str>,
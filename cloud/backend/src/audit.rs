@@ -0,0 +1,214 @@
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+
+use axum::extract::{ConnectInfo, FromRef, FromRequestParts};
+use axum::http::request::Parts;
+use axum::http::{Extensions, HeaderMap};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::{QueryBuilder, Row, Sqlite, SqlitePool, Transaction};
+use uuid::Uuid;
+
+use crate::config::IpRange;
+use crate::error::AppError;
+use crate::models::{format_datetime, parse_datetime, AuditLogEntry};
+use crate::state::AppState;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// Request metadata captured alongside every audit record.
+#[derive(Debug, Clone)]
+pub struct AuditContext {
+    pub request_id: String,
+    pub ip: Option<String>,
+}
+
+impl<S> FromRequestParts<S> for AuditContext
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> impl Future<Output = Result<Self, Self::Rejection>> + Send {
+        let state = AppState::from_ref(state);
+        let request_id = parts
+            .headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        let ip = client_ip(
+            &parts.headers,
+            &parts.extensions,
+            &state.config.trusted_proxies,
+        );
+
+        async move { Ok(Self { request_id, ip }) }
+    }
+}
+
+/// Client address of a request. This is the peer address of the connection
+/// unless the peer is a trusted proxy, in which case `X-Forwarded-For` is
+/// walked from the right and the first hop that is not a trusted proxy wins.
+/// Hops further left are written by the client and are never believed.
+pub fn client_ip(
+    headers: &HeaderMap,
+    extensions: &Extensions,
+    trusted_proxies: &[IpRange],
+) -> Option<String> {
+    let ConnectInfo(peer) = extensions.get::<ConnectInfo<SocketAddr>>()?;
+    let trusted = |ip: &IpAddr| trusted_proxies.iter().any(|proxy| proxy.contains(ip));
+
+    let mut client = peer.ip();
+    if trusted(&client) {
+        let hops = headers
+            .get_all(FORWARDED_FOR_HEADER)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect::<Vec<_>>();
+        for hop in hops.into_iter().rev() {
+            let Ok(ip) = hop.parse::<IpAddr>() else {
+                break;
+            };
+            client = ip;
+            if !trusted(&ip) {
+                break;
+            }
+        }
+    }
+    Some(client.to_string())
+}
+
+/// A single state change to append to the audit log.
+#[derive(Debug)]
+pub struct AuditRecord<'a> {
+    pub actor_id: Option<Uuid>,
+    pub action: &'static str,
+    pub target_type: &'static str,
+    pub target_id: String,
+    pub before_status: Option<&'a str>,
+    pub after_status: Option<&'a str>,
+    pub details: Option<Value>,
+}
+
+/// Appends `record` to the audit log inside the transaction that makes the
+/// change, so the change and its record are committed or rolled back together.
+pub async fn record(
+    tx: &mut Transaction<'_, Sqlite>,
+    context: &AuditContext,
+    record: AuditRecord<'_>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_log (occurred_at, actor_id, action, target_type, target_id, before_status, after_status, details, request_id, ip)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(format_datetime(Utc::now()))
+    .bind(record.actor_id.map(|id| id.to_string()))
+    .bind(record.action)
+    .bind(record.target_type)
+    .bind(&record.target_id)
+    .bind(record.before_status)
+    .bind(record.after_status)
+    .bind(record.details.map(|details| details.to_string()))
+    .bind(&context.request_id)
+    .bind(&context.ip)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditFilter {
+    pub actor_id: Option<Uuid>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub request_id: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<u32>,
+}
+
+/// Returns matching entries in the order they were recorded. `limit` caps the
+/// number of rows when set.
+pub async fn query(
+    pool: &SqlitePool,
+    filter: &AuditFilter,
+    limit: Option<u32>,
+) -> Result<Vec<AuditLogEntry>, AppError> {
+    let mut builder = QueryBuilder::<Sqlite>::new(
+        "SELECT id, occurred_at, actor_id, action, target_type, target_id, before_status, after_status, details, request_id, ip FROM audit_log WHERE 1 = 1",
+    );
+    if let Some(actor_id) = filter.actor_id {
+        builder.push(" AND actor_id = ");
+        builder.push_bind(actor_id.to_string());
+    }
+    if let Some(action) = &filter.action {
+        builder.push(" AND action = ");
+        builder.push_bind(action.clone());
+    }
+    if let Some(target_type) = &filter.target_type {
+        builder.push(" AND target_type = ");
+        builder.push_bind(target_type.clone());
+    }
+    if let Some(target_id) = &filter.target_id {
+        builder.push(" AND target_id = ");
+        builder.push_bind(target_id.clone());
+    }
+    if let Some(request_id) = &filter.request_id {
+        builder.push(" AND request_id = ");
+        builder.push_bind(request_id.clone());
+    }
+    if let Some(since) = filter.since {
+        builder.push(" AND occurred_at >= ");
+        builder.push_bind(format_datetime(since));
+    }
+    if let Some(until) = filter.until {
+        builder.push(" AND occurred_at < ");
+        builder.push_bind(format_datetime(until));
+    }
+    builder.push(" ORDER BY id");
+    if let Some(limit) = limit {
+        builder.push(" LIMIT ");
+        builder.push_bind(i64::from(limit));
+    }
+
+    let rows = builder.build().fetch_all(pool).await?;
+    rows.into_iter()
+        .map(|row| {
+            let actor_id: Option<String> = row.try_get("actor_id")?;
+            let occurred_at: String = row.try_get("occurred_at")?;
+            let details: Option<String> = row.try_get("details")?;
+            Ok(AuditLogEntry {
+                id: row.try_get("id")?,
+                occurred_at: parse_datetime(&occurred_at)?,
+                actor_id: actor_id
+                    .map(|id| {
+                        Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid actor id"))
+                    })
+                    .transpose()?,
+                action: row.try_get("action")?,
+                target_type: row.try_get("target_type")?,
+                target_id: row.try_get("target_id")?,
+                before_status: row.try_get("before_status")?,
+                after_status: row.try_get("after_status")?,
+                details: details.and_then(|details| serde_json::from_str(&details).ok()),
+                request_id: row.try_get("request_id")?,
+                ip: row.try_get("ip")?,
+            })
+        })
+        .collect()
+}
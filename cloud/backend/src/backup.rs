@@ -0,0 +1,504 @@
+//! `backup` and `restore` subcommands.
+//!
+//! A backup is a tar archive holding a consistent copy of the SQLite database
+//! taken with SQLite's online backup API, every local artifact referenced by
+//! that copy, and a `manifest.json` with the size and SHA-256 checksum of each
+//! entry. Restoring verifies the checksums and the integrity of the database
+//! before anything is written to the configured database or artifacts
+//! directory.
+
+use std::collections::BTreeMap;
+use std::ffi::CStr;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::ptr::NonNull;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use libsqlite3_sys::{
+    sqlite3, sqlite3_backup_finish, sqlite3_backup_init, sqlite3_backup_step, sqlite3_errcode,
+    sqlite3_errmsg, SQLITE_BUSY, SQLITE_DONE, SQLITE_LOCKED, SQLITE_OK,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection, SqlitePool};
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+
+pub const FORMAT_VERSION: u32 = 1;
+
+const DATABASE_ENTRY: &str = "codex.db";
+const MANIFEST_ENTRY: &str = "manifest.json";
+const ARTIFACT_PREFIX: &str = "artifacts/";
+/// How often a backup step that found the database locked is retried.
+const BACKUP_RETRIES: u32 = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub created_at: DateTime<Utc>,
+    pub backend_version: String,
+    pub database: ManifestEntry,
+    pub artifacts: Vec<ManifestEntry>,
+    /// Artifacts referenced by the database that were not found on disk.
+    #[serde(default)]
+    pub missing_artifacts: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Removes a file or directory when dropped.
+struct Cleanup(PathBuf);
+
+impl Drop for Cleanup {
+    fn drop(&mut self) {
+        if self.0.is_dir() {
+            let _ = fs::remove_dir_all(&self.0);
+        } else {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+}
+
+/// Writes a backup of the database behind `pool` and its artifacts to
+/// `archive`. The database may be in use while the backup runs.
+pub async fn create(
+    pool: &SqlitePool,
+    config: &AppConfig,
+    archive: &Path,
+) -> Result<BackupManifest> {
+    let file_name = archive
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow!("invalid archive path {}", archive.display()))?;
+    let snapshot_path = archive.with_file_name(format!(".{file_name}.{}.db", Uuid::new_v4()));
+    let _snapshot_cleanup = Cleanup(snapshot_path.clone());
+
+    let mut source = pool.acquire().await?;
+    let mut target = SqliteConnectOptions::new()
+        .filename(&snapshot_path)
+        .create_if_missing(true)
+        .connect()
+        .await?;
+    {
+        let mut source = source.lock_handle().await?;
+        let mut target = target.lock_handle().await?;
+        copy_database(source.as_raw_handle(), target.as_raw_handle())
+            .context("failed to snapshot the database")?;
+    }
+    target.close().await?;
+    drop(source);
+    let mut snapshot = SqliteConnectOptions::new()
+        .filename(&snapshot_path)
+        .read_only(true)
+        .connect()
+        .await?;
+
+    // Only artifacts referenced by the snapshot are bundled, so the archive
+    // is consistent even if new artifacts are written meanwhile.
+    let artifact_ids = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT diff_artifact_id FROM task_attempts WHERE diff_artifact_id IS NOT NULL
+        UNION SELECT log_artifact_id FROM task_attempts WHERE log_artifact_id IS NOT NULL
+        UNION SELECT artifact_id FROM task_inputs
+        "#,
+    )
+    .fetch_all(&mut snapshot)
+    .await?;
+    snapshot.close().await?;
+
+    let artifacts_dir = config.artifacts_dir.clone();
+    let archive = archive.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        write_archive(&archive, &snapshot_path, &artifacts_dir, artifact_ids)
+    })
+    .await?
+}
+
+/// Copies the main database of `source` into `target` with the online backup
+/// API. All pages are copied in a single step, which holds one read
+/// transaction on the source and so yields a consistent snapshot while other
+/// connections keep writing.
+fn copy_database(source: NonNull<sqlite3>, target: NonNull<sqlite3>) -> Result<()> {
+    let main = c"main".as_ptr();
+    // SAFETY: both handles are open connections locked for exclusive use by
+    // the caller, and the backup object is finished before returning.
+    unsafe {
+        let backup = sqlite3_backup_init(target.as_ptr(), main, source.as_ptr(), main);
+        if backup.is_null() {
+            bail!("{}", error_message(target));
+        }
+        let mut retries = 0;
+        let step = loop {
+            match sqlite3_backup_step(backup, -1) {
+                SQLITE_BUSY | SQLITE_LOCKED if retries < BACKUP_RETRIES => {
+                    retries += 1;
+                    thread::sleep(Duration::from_millis(50));
+                }
+                code => break code,
+            }
+        };
+        let finish = sqlite3_backup_finish(backup);
+        if step != SQLITE_DONE {
+            bail!("backup step failed with code {step}");
+        }
+        if finish != SQLITE_OK || sqlite3_errcode(target.as_ptr()) != SQLITE_OK {
+            bail!("{}", error_message(target));
+        }
+    }
+    Ok(())
+}
+
+/// The message of the most recent error on `db`.
+///
+/// # Safety
+///
+/// `db` must be an open connection that is not used concurrently.
+unsafe fn error_message(db: NonNull<sqlite3>) -> String {
+    CStr::from_ptr(sqlite3_errmsg(db.as_ptr()))
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn write_archive(
+    archive: &Path,
+    snapshot_path: &Path,
+    artifacts_dir: &Path,
+    artifact_ids: Vec<String>,
+) -> Result<BackupManifest> {
+    let mut partial_path = archive.as_os_str().to_owned();
+    partial_path.push(".partial");
+    let partial_path = PathBuf::from(partial_path);
+    let partial = Cleanup(partial_path.clone());
+    let mut out = tar::Builder::new(BufWriter::new(
+        File::create(&partial_path)
+            .with_context(|| format!("failed to create {}", partial_path.display()))?,
+    ));
+
+    let database = append_file(&mut out, DATABASE_ENTRY, snapshot_path)?;
+    let mut artifacts = Vec::new();
+    let mut missing_artifacts = Vec::new();
+    for artifact_id in artifact_ids {
+        if !is_plain_file_name(&artifact_id) {
+            bail!("refusing to back up artifact with unsafe id {artifact_id:?}");
+        }
+        let path = artifacts_dir.join(&artifact_id);
+        if !path.is_file() {
+            tracing::warn!(%artifact_id, "referenced artifact is missing; skipping");
+            missing_artifacts.push(artifact_id);
+            continue;
+        }
+        artifacts.push(append_file(
+            &mut out,
+            &format!("{ARTIFACT_PREFIX}{artifact_id}"),
+            &path,
+        )?);
+    }
+
+    let manifest = BackupManifest {
+        format_version: FORMAT_VERSION,
+        created_at: Utc::now(),
+        backend_version: env!("CARGO_PKG_VERSION").to_string(),
+        database,
+        artifacts,
+        missing_artifacts,
+    };
+    let manifest_bytes = serde_json::to_vec_pretty(&manifest)?;
+    append_entry(
+        &mut out,
+        MANIFEST_ENTRY,
+        manifest_bytes.len() as u64,
+        manifest_bytes.as_slice(),
+    )?;
+    out.into_inner()?
+        .into_inner()
+        .map_err(|err| err.into_error())?
+        .sync_all()?;
+
+    fs::rename(&partial_path, archive)
+        .with_context(|| format!("failed to write {}", archive.display()))?;
+    std::mem::forget(partial);
+    Ok(manifest)
+}
+
+fn append_file(
+    out: &mut tar::Builder<impl Write>,
+    name: &str,
+    path: &Path,
+) -> Result<ManifestEntry> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let size = file.metadata()?.len();
+    let sha256 = append_entry(out, name, size, file)?;
+    Ok(ManifestEntry {
+        path: name.to_string(),
+        size,
+        sha256,
+    })
+}
+
+/// Appends a regular file entry and returns the SHA-256 of its contents.
+fn append_entry(
+    out: &mut tar::Builder<impl Write>,
+    name: &str,
+    size: u64,
+    body: impl Read,
+) -> Result<String> {
+    let mut header = tar::Header::new_ustar();
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_mode(0o644);
+    header.set_size(size);
+    header.set_mtime(Utc::now().timestamp().max(0) as u64);
+    let mut body = HashingReader {
+        inner: body.take(size),
+        hasher: Sha256::new(),
+        read: 0,
+    };
+    out.append_data(&mut header, name, &mut body)?;
+    if body.read != size {
+        bail!("{name} changed size while it was archived");
+    }
+    Ok(hex::encode(body.hasher.finalize()))
+}
+
+/// Verifies `archive` and restores it into the configured database and
+/// artifacts directory. The backend must not be serving while this runs.
+/// Refuses to overwrite a non-empty database unless `force` is set.
+pub async fn restore(config: &AppConfig, archive: &Path, force: bool) -> Result<BackupManifest> {
+    let database_path = config
+        .database_path()
+        .ok_or_else(|| anyhow!("restore requires a sqlite:// DATABASE_URL"))?
+        .to_path_buf();
+    let has_data = fs::metadata(&database_path)
+        .map(|metadata| metadata.len() > 0)
+        .unwrap_or(false);
+    if has_data && !force {
+        bail!(
+            "database {} already exists; pass --force to overwrite it",
+            database_path.display()
+        );
+    }
+
+    config.ensure_artifact_dir()?;
+    let staging = config
+        .artifacts_dir
+        .join(format!(".restore-{}", Uuid::new_v4()));
+    let _staging_cleanup = Cleanup(staging.clone());
+    let manifest = {
+        let archive = archive.to_path_buf();
+        let staging = staging.clone();
+        tokio::task::spawn_blocking(move || extract_archive(&archive, &staging)).await??
+    };
+
+    let staged_database = staging.join(DATABASE_ENTRY);
+    let mut staged = SqliteConnectOptions::new()
+        .filename(&staged_database)
+        .read_only(true)
+        .connect()
+        .await?;
+    let integrity = sqlx::query_scalar::<_, String>("PRAGMA integrity_check")
+        .fetch_all(&mut staged)
+        .await?;
+    if integrity != ["ok"] {
+        bail!("database in backup failed the integrity check: {integrity:?}");
+    }
+    staged.close().await?;
+
+    // Fold the write-ahead log of the database being replaced back into it,
+    // so removing its sidecar files below cannot lose committed data should
+    // the restore fail afterwards.
+    if has_data {
+        let mut existing = SqliteConnectOptions::new()
+            .filename(&database_path)
+            .connect()
+            .await?;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&mut existing)
+            .await?;
+        existing.close().await?;
+    }
+
+    // Copy next to the target first so that replacing it is a single rename.
+    let parent = database_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    fs::create_dir_all(parent)?;
+    let incoming = parent.join(format!(".restore-{}.db", Uuid::new_v4()));
+    let _incoming_cleanup = Cleanup(incoming.clone());
+    fs::copy(&staged_database, &incoming)?;
+    File::open(&incoming)?.sync_all()?;
+
+    // Artifacts go first: until the database is replaced they are just
+    // unreferenced files, and they are taken back if replacing it fails.
+    let mut installed = Vec::new();
+    let result = install_artifacts(config, &manifest, &staging, &mut installed)
+        .and_then(|()| replace_database(&incoming, &database_path));
+    if let Err(err) = result {
+        for (staged, installed) in installed.iter().rev() {
+            if let Err(err) = fs::rename(installed, staged) {
+                tracing::warn!(
+                    artifact = %installed.display(),
+                    error = %err,
+                    "failed to roll back restored artifact"
+                );
+            }
+        }
+        return Err(err);
+    }
+    Ok(manifest)
+}
+
+/// Moves the staged artifacts into the artifacts directory, recording each
+/// move in `installed` as `(staged, installed)`. Artifacts that already exist
+/// are kept, since an artifact id always names the same contents.
+fn install_artifacts(
+    config: &AppConfig,
+    manifest: &BackupManifest,
+    staging: &Path,
+    installed: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<()> {
+    for entry in &manifest.artifacts {
+        let artifact_id = &entry.path[ARTIFACT_PREFIX.len()..];
+        let staged = staging.join(&entry.path);
+        let target = config.artifacts_dir.join(artifact_id);
+        if target.exists() {
+            continue;
+        }
+        fs::rename(&staged, &target)
+            .with_context(|| format!("failed to write {}", target.display()))?;
+        installed.push((staged, target));
+    }
+    Ok(())
+}
+
+fn replace_database(incoming: &Path, database_path: &Path) -> Result<()> {
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let mut sidecar = database_path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        match fs::remove_file(PathBuf::from(sidecar)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+    }
+    fs::rename(incoming, database_path)
+        .with_context(|| format!("failed to write {}", database_path.display()))
+}
+
+/// Extracts `archive` into `staging` and checks every entry against the
+/// manifest.
+fn extract_archive(archive: &Path, staging: &Path) -> Result<BackupManifest> {
+    fs::create_dir_all(staging.join(ARTIFACT_PREFIX))?;
+    let mut input = tar::Archive::new(BufReader::new(
+        File::open(archive).with_context(|| format!("failed to open {}", archive.display()))?,
+    ));
+
+    let mut extracted = BTreeMap::new();
+    let mut manifest_bytes = None;
+    for entry in input.entries().context("backup archive is invalid")? {
+        let mut entry = entry.context("backup archive is invalid")?;
+        if entry.header().entry_type() != tar::EntryType::Regular {
+            bail!("backup archive contains an entry that is not a regular file");
+        }
+        let name = entry
+            .path()?
+            .to_str()
+            .ok_or_else(|| anyhow!("backup archive has an entry with an invalid name"))?
+            .to_string();
+        let size = entry.size();
+        if name == MANIFEST_ENTRY {
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+            manifest_bytes = Some(bytes);
+            continue;
+        }
+
+        let valid_name = name == DATABASE_ENTRY
+            || name
+                .strip_prefix(ARTIFACT_PREFIX)
+                .is_some_and(is_plain_file_name);
+        if !valid_name {
+            bail!("unexpected entry {name:?} in backup");
+        }
+        let mut file = HashingWriter {
+            inner: File::create(staging.join(&name))?,
+            hasher: Sha256::new(),
+        };
+        if io::copy(&mut entry, &mut file)? != size {
+            bail!("backup archive is truncated");
+        }
+        extracted.insert(name, (size, hex::encode(file.hasher.finalize())));
+    }
+
+    let manifest_bytes = manifest_bytes.ok_or_else(|| anyhow!("backup has no manifest"))?;
+    let manifest: BackupManifest =
+        serde_json::from_slice(&manifest_bytes).context("backup manifest is invalid")?;
+    if manifest.format_version != FORMAT_VERSION {
+        bail!(
+            "unsupported backup format version {}",
+            manifest.format_version
+        );
+    }
+
+    for entry in std::iter::once(&manifest.database).chain(&manifest.artifacts) {
+        match extracted.remove(&entry.path) {
+            Some((size, sha256)) if size == entry.size && sha256 == entry.sha256 => {}
+            Some(_) => bail!("checksum mismatch for {} in backup", entry.path),
+            None => bail!("backup is missing {}", entry.path),
+        }
+    }
+    if let Some(path) = extracted.keys().next() {
+        bail!("backup contains {path}, which is not listed in its manifest");
+    }
+    Ok(manifest)
+}
+
+/// Whether `name` is a single, normal path component.
+fn is_plain_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    )
+}
+
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+    read: u64,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        self.read += read as u64;
+        Ok(read)
+    }
+}
+
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
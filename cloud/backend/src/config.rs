@@ -1,7 +1,11 @@
 use std::env;
+use std::net::IpAddr;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::time::Duration;
 
+use crate::models::OrganizationRole;
+
 #[derive(Clone, Debug)]
 pub struct AppConfig {
     pub secret_key: String,
@@ -10,24 +14,307 @@ pub struct AppConfig {
     pub artifact_base_url: String,
     pub access_token_expire_minutes: u64,
     pub cors_origins: Vec<String>,
-    pub oidc: Option<OidcConfig>,
+    /// OpenID Connect providers, each served at `/auth/oidc/{name}/callback`.
+    pub oidc_providers: Vec<OidcConfig>,
+    pub task_retry: TaskRetrySettings,
+    pub webhooks: WebhookSettings,
+    pub scheduler: SchedulerSettings,
+    pub rate_limit: RateLimitSettings,
+    pub registration: RegistrationSettings,
+    /// Reverse proxies whose `X-Forwarded-For` header is believed. Requests
+    /// from any other peer are attributed to the peer address.
+    pub trusted_proxies: Vec<IpRange>,
+}
+
+/// An address range in CIDR notation. A bare address stands for itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IpRange {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl IpRange {
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(*ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(*ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid address range: {value}");
+        let (network, prefix) = match value.split_once('/') {
+            Some((network, prefix)) => (network, Some(prefix)),
+            None => (value, None),
+        };
+        let network = network.parse::<IpAddr>().map_err(|_| invalid())?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max)
+                .ok_or_else(invalid)?,
+            None => max,
+        };
+        Ok(Self { network, prefix })
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct OidcConfig {
+    /// Path segment that selects the provider in the callback URL.
+    pub name: String,
     pub issuer: String,
     pub client_id: String,
     pub client_secret: String,
     pub redirect_uri: String,
     pub jwks_cache: JwksCacheSettings,
+    /// ID token claim holding the user's groups. Dots descend into nested
+    /// objects, e.g. `realm_access.roles` for Keycloak realm roles.
+    pub groups_claim: String,
+    /// Organization memberships granted to members of each group. When
+    /// non-empty, every login replaces the memberships this provider granted
+    /// the user with the ones their current groups map to.
+    pub group_organizations: Vec<GroupOrganization>,
 }
 
+/// Membership of `organization` (by name) granted to members of `group`.
+#[derive(Clone, Debug)]
+pub struct GroupOrganization {
+    pub group: String,
+    pub organization: String,
+    pub role: OrganizationRole,
+}
+
+/// Name of the provider configured with the unprefixed `CODEX_OIDC_*`
+/// variables.
+pub const DEFAULT_OIDC_PROVIDER: &str = "default";
+
 #[derive(Clone, Debug)]
 pub struct JwksCacheSettings {
     pub ttl: Duration,
     pub refresh: Duration,
 }
 
+#[derive(Clone, Debug)]
+pub struct TaskRetrySettings {
+    /// Maximum number of failed attempts a task may have before it is marked as failed.
+    pub max_attempts: u32,
+    /// Delay applied after the first retryable failure; doubled for each subsequent failure.
+    pub backoff_base: Duration,
+    /// Upper bound for the delay between retries.
+    pub backoff_max: Duration,
+}
+
+impl Default for TaskRetrySettings {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff_base: Duration::from_secs(30),
+            backoff_max: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+impl TaskRetrySettings {
+    /// Returns the delay before a task becomes claimable again after `failures`
+    /// retryable failures.
+    pub fn backoff_for(&self, failures: u32) -> Duration {
+        let exponent = failures.saturating_sub(1).min(16);
+        self.backoff_base
+            .saturating_mul(1u32 << exponent)
+            .min(self.backoff_max)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WebhookSettings {
+    /// Delivery attempts before a delivery is marked as failed.
+    pub max_attempts: u32,
+    /// Delay after the first failed delivery; doubled for each subsequent failure.
+    pub backoff_base: Duration,
+    /// Upper bound for the delay between delivery attempts.
+    pub backoff_max: Duration,
+    /// How often the dispatcher looks for due deliveries when it is not woken up.
+    pub poll_interval: Duration,
+    /// Timeout for a single HTTP delivery.
+    pub request_timeout: Duration,
+    /// Allows subscriptions to loopback, private and link-local addresses,
+    /// which are otherwise rejected to keep webhooks from reaching internal
+    /// services.
+    pub allow_private_destinations: bool,
+}
+
+impl Default for WebhookSettings {
+    fn default() -> Self {
+        Self {
+            max_attempts: 6,
+            backoff_base: Duration::from_secs(10),
+            backoff_max: Duration::from_secs(60 * 60),
+            poll_interval: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(10),
+            allow_private_destinations: false,
+        }
+    }
+}
+
+impl WebhookSettings {
+    /// Returns the delay before the next delivery attempt after `failures`
+    /// failed attempts.
+    pub fn backoff_for(&self, failures: u32) -> Duration {
+        let exponent = failures.saturating_sub(1).min(16);
+        self.backoff_base
+            .saturating_mul(1u32 << exponent)
+            .min(self.backoff_max)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SchedulerSettings {
+    /// Whether this instance materializes tasks from schedules. Disable it on
+    /// all but one replica when several backends share a database.
+    pub enabled: bool,
+    /// How often the scheduler looks for due schedules when it is not woken up.
+    pub poll_interval: Duration,
+}
+
+impl Default for SchedulerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            poll_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Token bucket parameters: a bucket holds up to `burst` requests and refills
+/// at `per_minute` requests per minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub per_minute: u32,
+    pub burst: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct RateLimitSettings {
+    pub enabled: bool,
+    /// Applied per user to authenticated requests and per client IP otherwise.
+    pub default: RateLimit,
+    /// Applied per client IP to login and user registration.
+    pub auth: RateLimit,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            default: RateLimit {
+                per_minute: 600,
+                burst: 120,
+            },
+            auth: RateLimit {
+                per_minute: 10,
+                burst: 5,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RegistrationSettings {
+    /// Whether anyone may create an account with `POST /auth/users`. When
+    /// disabled, registration requires an invite from an admin.
+    pub open: bool,
+    /// How long an invite stays valid unless the admin asks for another expiry.
+    pub invite_ttl: Duration,
+    /// Sign-up page that invite links point to; the token is appended as the
+    /// `invite` query parameter.
+    pub invite_base_url: Option<String>,
+}
+
+impl Default for RegistrationSettings {
+    fn default() -> Self {
+        Self {
+            open: true,
+            invite_ttl: Duration::from_secs(72 * 60 * 60),
+            invite_base_url: None,
+        }
+    }
+}
+
+/// Reads one OIDC provider from the variables starting with `prefix`, or
+/// `None` when its issuer or client credentials are missing.
+fn oidc_from_env(name: &str, prefix: &str) -> Option<OidcConfig> {
+    let var = |suffix: &str| env::var(format!("{prefix}{suffix}")).ok();
+    let seconds = |suffix: &str, default: u64| {
+        var(suffix)
+            .and_then(|value| value.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(default))
+    };
+
+    let callback_path = if name == DEFAULT_OIDC_PROVIDER {
+        "/auth/oidc/callback".to_string()
+    } else {
+        format!("/auth/oidc/{name}/callback")
+    };
+    let group_organizations = var("GROUP_ORGANIZATIONS")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|pair| {
+            let (group, target) = pair.split_once('=')?;
+            let (organization, role) = match target.split_once(':') {
+                Some((organization, role)) => {
+                    (organization, OrganizationRole::from_str(role.trim()))
+                }
+                None => (target, Ok(OrganizationRole::Member)),
+            };
+            match role {
+                Ok(role) => Some(GroupOrganization {
+                    group: group.trim().to_string(),
+                    organization: organization.trim().to_string(),
+                    role,
+                }),
+                Err(_) => {
+                    tracing::warn!(provider = %name, %pair, "ignoring invalid OIDC group mapping");
+                    None
+                }
+            }
+        })
+        .collect();
+
+    Some(OidcConfig {
+        name: name.to_string(),
+        issuer: var("ISSUER")?,
+        client_id: var("CLIENT_ID")?,
+        client_secret: var("CLIENT_SECRET")?,
+        redirect_uri: var("REDIRECT_URI")
+            .unwrap_or_else(|| format!("http://localhost:8000{callback_path}")),
+        jwks_cache: JwksCacheSettings {
+            ttl: seconds("JWKS_CACHE_TTL", 60 * 60),
+            refresh: seconds("JWKS_CACHE_REFRESH", 60 * 5),
+        },
+        groups_claim: var("GROUPS_CLAIM").unwrap_or_else(|| "groups".to_string()),
+        group_organizations,
+    })
+}
+
 impl AppConfig {
     pub fn from_env() -> Self {
         let secret_key =
@@ -50,37 +337,153 @@ impl AppConfig {
             .filter(|origin| !origin.is_empty())
             .collect::<Vec<_>>();
 
-        let oidc = match (
-            env::var("CODEX_OIDC_ISSUER"),
-            env::var("CODEX_OIDC_CLIENT_ID"),
-            env::var("CODEX_OIDC_CLIENT_SECRET"),
-        ) {
-            (Ok(issuer), Ok(client_id), Ok(client_secret)) => {
-                let redirect_uri = env::var("CODEX_OIDC_REDIRECT_URI")
-                    .unwrap_or_else(|_| "http://localhost:8000/auth/oidc/callback".to_string());
-                let jwks_ttl = env::var("CODEX_OIDC_JWKS_CACHE_TTL")
-                    .ok()
-                    .and_then(|value| value.parse::<u64>().ok())
-                    .unwrap_or(60 * 60);
-                let jwks_refresh = env::var("CODEX_OIDC_JWKS_CACHE_REFRESH")
-                    .ok()
-                    .and_then(|value| value.parse::<u64>().ok())
-                    .unwrap_or(60 * 5);
-
-                Some(OidcConfig {
-                    issuer,
-                    client_id,
-                    client_secret,
-                    redirect_uri,
-                    jwks_cache: JwksCacheSettings {
-                        ttl: Duration::from_secs(jwks_ttl),
-                        refresh: Duration::from_secs(jwks_refresh),
-                    },
-                })
+        let mut oidc_providers = Vec::new();
+        if let Some(provider) = oidc_from_env(DEFAULT_OIDC_PROVIDER, "CODEX_OIDC_") {
+            oidc_providers.push(provider);
+        }
+        for name in env::var("CODEX_OIDC_PROVIDERS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|name| name.trim().to_lowercase())
+            .filter(|name| !name.is_empty())
+        {
+            let prefix = format!("CODEX_OIDC_{}_", name.to_uppercase().replace('-', "_"));
+            match oidc_from_env(&name, &prefix) {
+                Some(provider) => oidc_providers.push(provider),
+                None => tracing::warn!(
+                    provider = %name,
+                    "{prefix}ISSUER, {prefix}CLIENT_ID and {prefix}CLIENT_SECRET are required; skipping OIDC provider"
+                ),
             }
-            _ => None,
+        }
+
+        let retry_defaults = TaskRetrySettings::default();
+        let task_retry = TaskRetrySettings {
+            max_attempts: env::var("CODEX_TASK_MAX_ATTEMPTS")
+                .ok()
+                .and_then(|value| value.parse::<u32>().ok())
+                .unwrap_or(retry_defaults.max_attempts)
+                .max(1),
+            backoff_base: env::var("CODEX_TASK_RETRY_BACKOFF_SECONDS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(retry_defaults.backoff_base),
+            backoff_max: env::var("CODEX_TASK_RETRY_BACKOFF_MAX_SECONDS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(retry_defaults.backoff_max),
+        };
+
+        let webhook_defaults = WebhookSettings::default();
+        let webhook_seconds = |name: &str, default: Duration| {
+            env::var(name)
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(default)
+        };
+        let webhooks = WebhookSettings {
+            max_attempts: env::var("CODEX_WEBHOOK_MAX_ATTEMPTS")
+                .ok()
+                .and_then(|value| value.parse::<u32>().ok())
+                .unwrap_or(webhook_defaults.max_attempts)
+                .max(1),
+            backoff_base: webhook_seconds(
+                "CODEX_WEBHOOK_BACKOFF_SECONDS",
+                webhook_defaults.backoff_base,
+            ),
+            backoff_max: webhook_seconds(
+                "CODEX_WEBHOOK_BACKOFF_MAX_SECONDS",
+                webhook_defaults.backoff_max,
+            ),
+            poll_interval: webhook_seconds(
+                "CODEX_WEBHOOK_POLL_INTERVAL_SECONDS",
+                webhook_defaults.poll_interval,
+            ),
+            request_timeout: webhook_seconds(
+                "CODEX_WEBHOOK_TIMEOUT_SECONDS",
+                webhook_defaults.request_timeout,
+            ),
+            allow_private_destinations: env::var("CODEX_WEBHOOK_ALLOW_PRIVATE_DESTINATIONS")
+                .ok()
+                .and_then(|value| value.parse::<bool>().ok())
+                .unwrap_or(webhook_defaults.allow_private_destinations),
         };
 
+        let scheduler_defaults = SchedulerSettings::default();
+        let scheduler = SchedulerSettings {
+            enabled: env::var("CODEX_SCHEDULER_ENABLED")
+                .ok()
+                .and_then(|value| value.parse::<bool>().ok())
+                .unwrap_or(scheduler_defaults.enabled),
+            poll_interval: env::var("CODEX_SCHEDULER_POLL_INTERVAL_SECONDS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(scheduler_defaults.poll_interval),
+        };
+
+        let rate_limit_defaults = RateLimitSettings::default();
+        let rate_limit_value = |name: &str, default: u32| {
+            env::var(name)
+                .ok()
+                .and_then(|value| value.parse::<u32>().ok())
+                .unwrap_or(default)
+                .max(1)
+        };
+        let rate_limit = RateLimitSettings {
+            enabled: env::var("CODEX_RATE_LIMIT_ENABLED")
+                .ok()
+                .and_then(|value| value.parse::<bool>().ok())
+                .unwrap_or(rate_limit_defaults.enabled),
+            default: RateLimit {
+                per_minute: rate_limit_value(
+                    "CODEX_RATE_LIMIT_PER_MINUTE",
+                    rate_limit_defaults.default.per_minute,
+                ),
+                burst: rate_limit_value(
+                    "CODEX_RATE_LIMIT_BURST",
+                    rate_limit_defaults.default.burst,
+                ),
+            },
+            auth: RateLimit {
+                per_minute: rate_limit_value(
+                    "CODEX_AUTH_RATE_LIMIT_PER_MINUTE",
+                    rate_limit_defaults.auth.per_minute,
+                ),
+                burst: rate_limit_value(
+                    "CODEX_AUTH_RATE_LIMIT_BURST",
+                    rate_limit_defaults.auth.burst,
+                ),
+            },
+        };
+
+        let registration_defaults = RegistrationSettings::default();
+        let registration = RegistrationSettings {
+            open: env::var("CODEX_ALLOW_REGISTRATION")
+                .ok()
+                .and_then(|value| value.parse::<bool>().ok())
+                .unwrap_or(registration_defaults.open),
+            invite_ttl: env::var("CODEX_INVITE_EXPIRE_HOURS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .map(|hours| Duration::from_secs(hours.max(1) * 60 * 60))
+                .unwrap_or(registration_defaults.invite_ttl),
+            invite_base_url: env::var("CODEX_INVITE_BASE_URL")
+                .ok()
+                .filter(|value| !value.is_empty()),
+        };
+
+        let trusted_proxies = env::var("CODEX_TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|proxy| !proxy.is_empty())
+            .filter_map(|proxy| proxy.parse::<IpRange>().ok())
+            .collect::<Vec<_>>();
+
         Self {
             secret_key,
             database_url,
@@ -88,7 +491,13 @@ impl AppConfig {
             artifact_base_url,
             access_token_expire_minutes,
             cors_origins,
-            oidc,
+            oidc_providers,
+            task_retry,
+            webhooks,
+            scheduler,
+            rate_limit,
+            registration,
+            trusted_proxies,
         }
     }
 
@@ -123,6 +532,15 @@ impl AppConfig {
         self.cors_origins.clone()
     }
 
+    /// Link that accepts the invite with `token`, if a sign-up page is
+    /// configured.
+    pub fn invite_url(&self, token: &str) -> Option<String> {
+        self.registration.invite_base_url.as_ref().map(|base| {
+            let separator = if base.contains('?') { '&' } else { '?' };
+            format!("{base}{separator}invite={token}")
+        })
+    }
+
     pub fn database_path(&self) -> Option<&Path> {
         if let Some(path) = self.database_url.strip_prefix("sqlite://") {
             Some(Path::new(path))
@@ -1,10 +1,10 @@
 use chrono::Utc;
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteRow};
 use sqlx::{Executor, Row, SqlitePool};
 use std::str::FromStr;
 use uuid::Uuid;
 
-use crate::models::User;
+use crate::models::{User, UserRole};
 
 pub async fn connect(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
     let options = SqliteConnectOptions::from_str(database_url)?.create_if_missing(true);
@@ -21,7 +21,9 @@ pub async fn init_db(pool: &SqlitePool) -> Result<(), sqlx::Error> {
             name TEXT,
             password_hash TEXT NOT NULL,
             auth_provider TEXT NOT NULL,
-            created_at TEXT NOT NULL
+            created_at TEXT NOT NULL,
+            role TEXT NOT NULL DEFAULT 'member',
+            deactivated_at TEXT
         )
         "#,
     )
@@ -52,6 +54,13 @@ pub async fn init_db(pool: &SqlitePool) -> Result<(), sqlx::Error> {
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL,
             environment_id TEXT,
+            retry_after TEXT,
+            priority INTEGER NOT NULL DEFAULT 0,
+            best_of_n INTEGER,
+            branch TEXT,
+            qa_mode INTEGER NOT NULL DEFAULT 0,
+            group_id TEXT,
+            result_branch TEXT,
             FOREIGN KEY(repository_id) REFERENCES repositories(id),
             FOREIGN KEY(assignee_id) REFERENCES users(id),
             FOREIGN KEY(created_by) REFERENCES users(id)
@@ -69,6 +78,13 @@ pub async fn init_db(pool: &SqlitePool) -> Result<(), sqlx::Error> {
             status TEXT NOT NULL,
             diff_artifact_id TEXT,
             log_artifact_id TEXT,
+            failure_category TEXT,
+            input_tokens INTEGER,
+            cached_input_tokens INTEGER,
+            output_tokens INTEGER,
+            reasoning_output_tokens INTEGER,
+            wall_time_ms INTEGER,
+            cpu_time_ms INTEGER,
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL,
             FOREIGN KEY(task_id) REFERENCES tasks(id),
@@ -97,6 +113,7 @@ pub async fn init_db(pool: &SqlitePool) -> Result<(), sqlx::Error> {
             provider TEXT,
             owner TEXT,
             repo TEXT,
+            max_concurrency INTEGER,
             FOREIGN KEY(repository_id) REFERENCES repositories(id)
         )
         "#,
@@ -120,12 +137,339 @@ pub async fn init_db(pool: &SqlitePool) -> Result<(), sqlx::Error> {
     )
     .await?;
 
+    // Append-only record of state-changing API calls. The triggers reject any
+    // attempt to rewrite history.
+    pool.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            occurred_at TEXT NOT NULL,
+            actor_id TEXT,
+            action TEXT NOT NULL,
+            target_type TEXT NOT NULL,
+            target_id TEXT NOT NULL,
+            before_status TEXT,
+            after_status TEXT,
+            details TEXT,
+            request_id TEXT NOT NULL,
+            ip TEXT
+        )
+        "#,
+    )
+    .await?;
+
+    pool.execute(
+        r#"
+        CREATE INDEX IF NOT EXISTS idx_audit_log_target ON audit_log(target_type, target_id)
+        "#,
+    )
+    .await?;
+
+    pool.execute(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_update
+        BEFORE UPDATE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, 'audit_log is append-only');
+        END
+        "#,
+    )
+    .await?;
+
+    pool.execute(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_delete
+        BEFORE DELETE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, 'audit_log is append-only');
+        END
+        "#,
+    )
+    .await?;
+
+    // Webhook subscriptions and their persistent delivery queue.
+    pool.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS webhook_subscriptions (
+            id TEXT PRIMARY KEY,
+            url TEXT NOT NULL,
+            secret TEXT NOT NULL,
+            events TEXT NOT NULL,
+            repository_id TEXT,
+            organization_id TEXT,
+            active INTEGER NOT NULL DEFAULT 1,
+            created_by TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY(repository_id) REFERENCES repositories(id),
+            FOREIGN KEY(organization_id) REFERENCES organizations(id),
+            FOREIGN KEY(created_by) REFERENCES users(id)
+        )
+        "#,
+    )
+    .await?;
+
+    pool.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id TEXT PRIMARY KEY,
+            subscription_id TEXT NOT NULL,
+            event TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            status TEXT NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            response_status INTEGER,
+            last_error TEXT,
+            next_attempt_at TEXT,
+            delivered_at TEXT,
+            redelivery_of TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY(subscription_id) REFERENCES webhook_subscriptions(id)
+        )
+        "#,
+    )
+    .await?;
+
+    pool.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS organizations (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .await?;
+
+    pool.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS organization_members (
+            organization_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            role TEXT NOT NULL,
+            source TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY(organization_id, user_id, source),
+            FOREIGN KEY(organization_id) REFERENCES organizations(id),
+            FOREIGN KEY(user_id) REFERENCES users(id)
+        )
+        "#,
+    )
+    .await?;
+
+    pool.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS organization_quotas (
+            organization_id TEXT PRIMARY KEY,
+            monthly_token_limit INTEGER NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY(organization_id) REFERENCES organizations(id)
+        )
+        "#,
+    )
+    .await?;
+
+    pool.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS task_inputs (
+            id TEXT PRIMARY KEY,
+            task_id TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            name TEXT,
+            mime_type TEXT NOT NULL,
+            artifact_id TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY(task_id) REFERENCES tasks(id)
+        )
+        "#,
+    )
+    .await?;
+
+    pool.execute(
+        r#"
+        CREATE INDEX IF NOT EXISTS idx_task_inputs_task ON task_inputs(task_id, position)
+        "#,
+    )
+    .await?;
+
+    // Invites are looked up by the SHA-256 of their token; the token itself is
+    // only returned to the admin who created the invite.
+    pool.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS invites (
+            id TEXT PRIMARY KEY,
+            token_hash TEXT NOT NULL UNIQUE,
+            email TEXT,
+            role TEXT NOT NULL,
+            created_by TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            accepted_at TEXT,
+            accepted_by TEXT,
+            FOREIGN KEY(created_by) REFERENCES users(id),
+            FOREIGN KEY(accepted_by) REFERENCES users(id)
+        )
+        "#,
+    )
+    .await?;
+
+    // Task groups and the prerequisites between their tasks. A task only
+    // becomes claimable once every task it depends on has been applied.
+    pool.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS task_groups (
+            id TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            repository_id TEXT NOT NULL,
+            created_by TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY(repository_id) REFERENCES repositories(id),
+            FOREIGN KEY(created_by) REFERENCES users(id)
+        )
+        "#,
+    )
+    .await?;
+
+    pool.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS task_dependencies (
+            task_id TEXT NOT NULL,
+            depends_on_id TEXT NOT NULL,
+            PRIMARY KEY(task_id, depends_on_id),
+            FOREIGN KEY(task_id) REFERENCES tasks(id),
+            FOREIGN KEY(depends_on_id) REFERENCES tasks(id)
+        )
+        "#,
+    )
+    .await?;
+
+    pool.execute(
+        r#"
+        CREATE INDEX IF NOT EXISTS idx_task_dependencies_prerequisite ON task_dependencies(depends_on_id)
+        "#,
+    )
+    .await?;
+
+    pool.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS task_schedules (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            cron TEXT NOT NULL,
+            environment_id TEXT NOT NULL,
+            prompt TEXT NOT NULL,
+            best_of_n INTEGER,
+            priority INTEGER NOT NULL DEFAULT 0,
+            paused INTEGER NOT NULL DEFAULT 0,
+            next_run_at TEXT NOT NULL,
+            last_run_at TEXT,
+            last_task_id TEXT,
+            created_by TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY(environment_id) REFERENCES environments(id),
+            FOREIGN KEY(last_task_id) REFERENCES tasks(id),
+            FOREIGN KEY(created_by) REFERENCES users(id)
+        )
+        "#,
+    )
+    .await?;
+
+    pool.execute(
+        r#"
+        CREATE INDEX IF NOT EXISTS idx_task_schedules_due ON task_schedules(paused, next_run_at)
+        "#,
+    )
+    .await?;
+
+    pool.execute(
+        r#"
+        CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(status, next_attempt_at)
+        "#,
+    )
+    .await?;
+
     // Backfill environment_id column for existing databases; ignore the error
     // when the column already exists.
     let _ = pool
         .execute("ALTER TABLE tasks ADD COLUMN environment_id TEXT")
         .await;
 
+    // Retry bookkeeping: tasks record when they become claimable again after a
+    // failed attempt and attempts record why they failed.
+    let _ = pool
+        .execute("ALTER TABLE tasks ADD COLUMN retry_after TEXT")
+        .await;
+    let _ = pool
+        .execute("ALTER TABLE task_attempts ADD COLUMN failure_category TEXT")
+        .await;
+
+    // Scheduling: task priorities and per-environment concurrency caps.
+    let _ = pool
+        .execute("ALTER TABLE tasks ADD COLUMN priority INTEGER NOT NULL DEFAULT 0")
+        .await;
+    let _ = pool
+        .execute("ALTER TABLE environments ADD COLUMN max_concurrency INTEGER")
+        .await;
+
+    // Best-of-N requested by the Codex CLI or a task schedule.
+    let _ = pool
+        .execute("ALTER TABLE tasks ADD COLUMN best_of_n INTEGER")
+        .await;
+
+    // Git ref and QA mode requested through the Codex API.
+    let _ = pool
+        .execute("ALTER TABLE tasks ADD COLUMN branch TEXT")
+        .await;
+    let _ = pool
+        .execute("ALTER TABLE tasks ADD COLUMN qa_mode INTEGER NOT NULL DEFAULT 0")
+        .await;
+
+    // Usage reported by the supervisor when an attempt completes.
+    for column in [
+        "input_tokens",
+        "cached_input_tokens",
+        "output_tokens",
+        "reasoning_output_tokens",
+        "wall_time_ms",
+        "cpu_time_ms",
+    ] {
+        let _ = pool
+            .execute(format!("ALTER TABLE task_attempts ADD COLUMN {column} INTEGER").as_str())
+            .await;
+    }
+
+    // User roles and deactivation.
+    let _ = pool
+        .execute("ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'member'")
+        .await;
+    let _ = pool
+        .execute("ALTER TABLE users ADD COLUMN deactivated_at TEXT")
+        .await;
+
+    // Task groups and the branch an applied task landed on.
+    let _ = pool
+        .execute("ALTER TABLE tasks ADD COLUMN group_id TEXT")
+        .await;
+    let _ = pool
+        .execute("ALTER TABLE tasks ADD COLUMN result_branch TEXT")
+        .await;
+
+    pool.execute(
+        r#"
+        CREATE INDEX IF NOT EXISTS idx_tasks_group ON tasks(group_id)
+        "#,
+    )
+    .await?;
+
+    pool.execute(
+        r#"
+        CREATE INDEX IF NOT EXISTS idx_tasks_environment_status ON tasks(environment_id, status)
+        "#,
+    )
+    .await?;
+
     Ok(())
 }
 
@@ -173,7 +517,7 @@ pub async fn find_user_by_external_identity(
 ) -> Result<Option<User>, sqlx::Error> {
     let row = sqlx::query(
         r#"
-        SELECT u.id as user_id, u.email, u.name
+        SELECT u.id, u.email, u.name, u.role, u.deactivated_at
         FROM external_identities ei
         JOIN users u ON u.id = ei.user_id
         WHERE ei.issuer = ? AND ei.subject = ?
@@ -184,16 +528,28 @@ pub async fn find_user_by_external_identity(
     .fetch_optional(pool)
     .await?;
 
-    if let Some(row) = row {
-        let id_str: String = row.try_get("user_id")?;
-        let id = Uuid::parse_str(&id_str).map_err(|err| sqlx::Error::ColumnDecode {
-            index: "user_id".to_string(),
-            source: Box::new(err),
-        })?;
-        let email: String = row.try_get("email")?;
-        let name: Option<String> = row.try_get("name")?;
-        return Ok(Some(User { id, email, name }));
-    }
+    row.as_ref().map(row_to_user).transpose()
+}
 
-    Ok(None)
+/// Maps a row with the `id`, `email`, `name`, `role` and `deactivated_at`
+/// columns of `users`.
+pub fn row_to_user(row: &SqliteRow) -> Result<User, sqlx::Error> {
+    let id_str: String = row.try_get("id")?;
+    let id = Uuid::parse_str(&id_str).map_err(|err| sqlx::Error::ColumnDecode {
+        index: "id".to_string(),
+        source: Box::new(err),
+    })?;
+    let role: String = row.try_get("role")?;
+    let role = UserRole::from_str(&role).map_err(|err| sqlx::Error::ColumnDecode {
+        index: "role".to_string(),
+        source: Box::new(err),
+    })?;
+    let deactivated_at: Option<String> = row.try_get("deactivated_at")?;
+    Ok(User {
+        id,
+        email: row.try_get("email")?,
+        name: row.try_get("name")?,
+        role,
+        deactivated: deactivated_at.is_some(),
+    })
 }
@@ -1,10 +1,20 @@
 pub mod artifacts;
+pub mod audit;
+pub mod backup;
 pub mod config;
 pub mod db;
 pub mod error;
 pub mod models;
+pub mod openapi;
+pub mod organizations;
+pub mod ratelimit;
 pub mod routes;
+pub mod scheduler;
 pub mod security;
 pub mod state;
+pub mod task_groups;
+pub mod usage;
+pub mod users;
+pub mod webhooks;
 
 pub use routes::app_router;
@@ -1,6 +1,8 @@
 use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use clap::{Parser, Subcommand};
 use tokio::net::TcpListener;
 use tokio::signal;
@@ -8,6 +10,7 @@ use tracing::info;
 use tracing_subscriber::EnvFilter;
 use uuid::Uuid;
 
+use codex_cloud_backend::backup;
 use codex_cloud_backend::config::AppConfig;
 use codex_cloud_backend::db;
 use codex_cloud_backend::models::{format_datetime, CreateUserResponse};
@@ -29,13 +32,25 @@ enum Command {
         #[arg(long, default_value = "0.0.0.0:8000")]
         addr: String,
     },
-    /// Create a local admin user in the database
+    /// Create a local admin user, or grant the admin role to an existing user
     CreateAdmin {
         email: String,
         password: String,
         #[arg(long)]
         name: Option<String>,
     },
+    /// Write a consistent snapshot of the database and its artifacts to an archive
+    Backup {
+        /// Path of the archive to create
+        output: PathBuf,
+    },
+    /// Restore the database and artifacts from an archive made by `backup`
+    Restore {
+        archive: PathBuf,
+        /// Overwrite an existing database
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 #[tokio::main]
@@ -57,6 +72,8 @@ async fn main() -> Result<()> {
             password,
             name,
         } => create_admin(config, email, password, name).await?,
+        Command::Backup { output } => create_backup(config, output).await?,
+        Command::Restore { archive, force } => restore_backup(config, archive, force).await?,
     }
 
     Ok(())
@@ -72,9 +89,12 @@ async fn serve(config: AppConfig, addr: String) -> Result<()> {
     let listener = TcpListener::bind(&addr).await?;
     info!(%addr, "listening");
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
     Ok(())
 }
 
@@ -94,7 +114,11 @@ async fn create_admin(
         .await?;
 
     if existing > 0 {
-        println!("User already exists");
+        sqlx::query("UPDATE users SET role = 'admin' WHERE email = ?")
+            .bind(&email)
+            .execute(&pool)
+            .await?;
+        println!("User already exists; granted admin role");
         return Ok(());
     }
 
@@ -104,8 +128,8 @@ async fn create_admin(
 
     sqlx::query(
         r#"
-        INSERT INTO users (id, email, name, password_hash, auth_provider, created_at)
-        VALUES (?, ?, ?, ?, 'local', ?)
+        INSERT INTO users (id, email, name, password_hash, auth_provider, created_at, role)
+        VALUES (?, ?, ?, ?, 'local', ?, 'admin')
         "#,
     )
     .bind(user_id.to_string())
@@ -125,6 +149,35 @@ async fn create_admin(
     Ok(())
 }
 
+async fn create_backup(config: AppConfig, output: PathBuf) -> Result<()> {
+    if !config.database_path().is_some_and(|path| path.exists()) {
+        bail!("database {} does not exist", config.database_url);
+    }
+    let pool = db::connect(&config.database_url).await?;
+    let manifest = backup::create(&pool, &config, &output).await?;
+    pool.close().await;
+
+    println!(
+        "Backed up database and {} artifacts to {}",
+        manifest.artifacts.len(),
+        output.display()
+    );
+    for artifact_id in &manifest.missing_artifacts {
+        println!("Missing artifact: {artifact_id}");
+    }
+    Ok(())
+}
+
+async fn restore_backup(config: AppConfig, archive: PathBuf, force: bool) -> Result<()> {
+    let manifest = backup::restore(&config, &archive, force).await?;
+    println!(
+        "Restored backup from {} with {} artifacts",
+        manifest.created_at.to_rfc3339(),
+        manifest.artifacts.len()
+    );
+    Ok(())
+}
+
 fn prepare_environment(config: &AppConfig) -> Result<()> {
     if let Some(path) = config.database_path().and_then(|path| path.parent()) {
         if !path.exists() {
@@ -3,6 +3,7 @@ use std::str::FromStr;
 
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
 
 use crate::error::AppError;
@@ -10,11 +11,14 @@ use crate::error::AppError;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TaskStatus {
+    /// Part of a task group and waiting for its prerequisites to be applied.
+    Blocked,
     Pending,
     Claimed,
     Running,
     Review,
     Applied,
+    Failed,
 }
 
 pub fn format_datetime(value: DateTime<Utc>) -> String {
@@ -30,11 +34,13 @@ pub fn parse_datetime(value: &str) -> Result<DateTime<Utc>, AppError> {
 impl TaskStatus {
     pub fn as_str(&self) -> &'static str {
         match self {
+            Self::Blocked => "blocked",
             Self::Pending => "pending",
             Self::Claimed => "claimed",
             Self::Running => "running",
             Self::Review => "review",
             Self::Applied => "applied",
+            Self::Failed => "failed",
         }
     }
 }
@@ -50,11 +56,13 @@ impl FromStr for TaskStatus {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
+            "blocked" => Ok(Self::Blocked),
             "pending" => Ok(Self::Pending),
             "claimed" => Ok(Self::Claimed),
             "running" => Ok(Self::Running),
             "review" => Ok(Self::Review),
             "applied" => Ok(Self::Applied),
+            "failed" => Ok(Self::Failed),
             other => Err(AppError::bad_request(format!(
                 "Invalid task status: {other}"
             ))),
@@ -69,6 +77,9 @@ pub enum AttemptStatus {
     Running,
     Succeeded,
     Failed,
+    /// The supervisor gave the attempt up (for example while draining) and
+    /// released the task back to the queue.
+    Cancelled,
 }
 
 impl AttemptStatus {
@@ -78,6 +89,7 @@ impl AttemptStatus {
             Self::Running => "running",
             Self::Succeeded => "succeeded",
             Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
         }
     }
 }
@@ -97,6 +109,7 @@ impl FromStr for AttemptStatus {
             "running" => Ok(Self::Running),
             "succeeded" => Ok(Self::Succeeded),
             "failed" => Ok(Self::Failed),
+            "cancelled" => Ok(Self::Cancelled),
             other => Err(AppError::bad_request(format!(
                 "Invalid attempt status: {other}"
             ))),
@@ -104,11 +117,135 @@ impl FromStr for AttemptStatus {
     }
 }
 
+/// Classifies why an attempt failed so the backend can decide whether the task
+/// should be retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailureCategory {
+    /// The executor infrastructure failed (snapshot, network, cache, ...).
+    Infra,
+    /// The agent ran but could not complete the task.
+    Agent,
+    /// The attempt exceeded its execution deadline.
+    Timeout,
+}
+
+impl FailureCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Infra => "infra",
+            Self::Agent => "agent",
+            Self::Timeout => "timeout",
+        }
+    }
+
+    /// Infra failures and timeouts are transient; agent failures are not.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Infra | Self::Timeout)
+    }
+}
+
+impl fmt::Display for FailureCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for FailureCategory {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "infra" => Ok(Self::Infra),
+            "agent" => Ok(Self::Agent),
+            "timeout" => Ok(Self::Timeout),
+            other => Err(AppError::bad_request(format!(
+                "Invalid failure category: {other}"
+            ))),
+        }
+    }
+}
+
+/// Non-text input attached to a task by the Codex CLI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskInputKind {
+    Image,
+    File,
+}
+
+impl TaskInputKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Image => "image",
+            Self::File => "file",
+        }
+    }
+}
+
+impl fmt::Display for TaskInputKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TaskInputKind {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "image" => Ok(Self::Image),
+            "file" => Ok(Self::File),
+            other => Err(AppError::bad_request(format!(
+                "Invalid task input kind: {other}"
+            ))),
+        }
+    }
+}
+
+/// Users with the admin role may call the `/admin` endpoints.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserRole {
+    #[default]
+    Member,
+    Admin,
+}
+
+impl UserRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Member => "member",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for UserRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for UserRole {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "member" => Ok(Self::Member),
+            "admin" => Ok(Self::Admin),
+            other => Err(AppError::bad_request(format!("Invalid user role: {other}"))),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct User {
     pub id: Uuid,
     pub email: String,
     pub name: Option<String>,
+    pub role: UserRole,
+    pub deactivated: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -129,6 +266,7 @@ pub struct Environment {
     pub provider: Option<String>,
     pub owner: Option<String>,
     pub repo: Option<String>,
+    pub max_concurrency: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -143,6 +281,15 @@ pub struct Task {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub environment_id: Option<String>,
+    pub retry_after: Option<DateTime<Utc>>,
+    pub priority: i64,
+    pub best_of_n: Option<i64>,
+    /// Git ref requested for the run; the environment branch applies when unset.
+    pub branch: Option<String>,
+    pub qa_mode: bool,
+    pub group_id: Option<Uuid>,
+    /// Branch the applied change landed on, which dependent tasks start from.
+    pub result_branch: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -153,10 +300,38 @@ pub struct TaskAttempt {
     pub status: AttemptStatus,
     pub diff_artifact_id: Option<String>,
     pub log_artifact_id: Option<String>,
+    pub failure_category: Option<FailureCategory>,
+    pub usage: Option<AttemptUsage>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// Tokens and time consumed by an attempt, as reported by the supervisor.
+/// `cached_input_tokens` is a subset of `input_tokens` and
+/// `reasoning_output_tokens` a subset of `output_tokens`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttemptUsage {
+    #[serde(default)]
+    pub input_tokens: i64,
+    #[serde(default)]
+    pub cached_input_tokens: i64,
+    #[serde(default)]
+    pub output_tokens: i64,
+    #[serde(default)]
+    pub reasoning_output_tokens: i64,
+    #[serde(default)]
+    pub wall_time_ms: i64,
+    #[serde(default)]
+    pub cpu_time_ms: Option<i64>,
+}
+
+impl AttemptUsage {
+    /// Tokens counted against quotas.
+    pub fn billable_tokens(&self) -> i64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoginRequest {
     pub email: String,
@@ -168,6 +343,9 @@ pub struct CreateUserRequest {
     pub email: String,
     pub password: String,
     pub name: Option<String>,
+    /// Required when open registration is disabled.
+    #[serde(default)]
+    pub invite_token: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -235,6 +413,31 @@ pub struct EnvironmentCreate {
     pub provider: Option<String>,
     pub owner: Option<String>,
     pub repo: Option<String>,
+    /// Maximum number of tasks from this environment that may be claimed or
+    /// running at once. Unlimited when unset.
+    #[serde(default)]
+    pub max_concurrency: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnvironmentUpdate {
+    /// Left unchanged when absent; `null` removes the cap.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_concurrency: Option<Option<i64>>,
+}
+
+/// Deserializes a field that is present in the payload, even as `null`, into
+/// `Some`. Combined with `#[serde(default)]` an absent field stays `None`.
+fn deserialize_present<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -247,6 +450,7 @@ pub struct EnvironmentRead {
     pub provider: Option<String>,
     pub owner: Option<String>,
     pub repo: Option<String>,
+    pub max_concurrency: Option<i64>,
 }
 
 impl From<Environment> for EnvironmentRead {
@@ -260,6 +464,7 @@ impl From<Environment> for EnvironmentRead {
             provider: value.provider,
             owner: value.owner,
             repo: value.repo,
+            max_concurrency: value.max_concurrency,
         }
     }
 }
@@ -269,6 +474,9 @@ pub struct TaskCreate {
     pub title: String,
     pub description: Option<String>,
     pub repository_id: Uuid,
+    /// Higher priorities are scheduled first.
+    #[serde(default)]
+    pub priority: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -282,6 +490,13 @@ pub struct TaskRead {
     pub created_by: Uuid,
     pub updated_at: DateTime<Utc>,
     pub environment_id: Option<String>,
+    pub retry_after: Option<DateTime<Utc>>,
+    pub priority: i64,
+    pub best_of_n: Option<i64>,
+    pub branch: Option<String>,
+    pub qa_mode: bool,
+    pub group_id: Option<Uuid>,
+    pub result_branch: Option<String>,
 }
 
 impl From<Task> for TaskRead {
@@ -296,6 +511,13 @@ impl From<Task> for TaskRead {
             created_by: value.created_by,
             updated_at: value.updated_at,
             environment_id: value.environment_id,
+            retry_after: value.retry_after,
+            priority: value.priority,
+            best_of_n: value.best_of_n,
+            branch: value.branch,
+            qa_mode: value.qa_mode,
+            group_id: value.group_id,
+            result_branch: value.result_branch,
         }
     }
 }
@@ -308,6 +530,9 @@ pub struct TaskListResponse {
     pub repository_id: Uuid,
     pub updated_at: DateTime<Utc>,
     pub environment_id: Option<String>,
+    pub retry_after: Option<DateTime<Utc>>,
+    pub priority: i64,
+    pub group: Option<TaskGroupSummary>,
 }
 
 impl From<Task> for TaskListResponse {
@@ -319,6 +544,9 @@ impl From<Task> for TaskListResponse {
             repository_id: value.repository_id,
             updated_at: value.updated_at,
             environment_id: value.environment_id,
+            retry_after: value.retry_after,
+            priority: value.priority,
+            group: None,
         }
     }
 }
@@ -332,6 +560,15 @@ pub fn claim_expiration(minutes: i64) -> DateTime<Utc> {
     Utc::now() + Duration::minutes(minutes)
 }
 
+/// Optional body of `POST /tasks/{task_id}/apply`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TaskApply {
+    /// Branch the change was merged or pushed to. Defaults to the branch the
+    /// task ran on.
+    #[serde(default)]
+    pub branch: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AttemptCreate {
     pub environment_id: Option<String>,
@@ -346,6 +583,8 @@ pub struct AttemptRead {
     pub diff_url: Option<String>,
     pub log_artifact_id: Option<String>,
     pub log_url: Option<String>,
+    pub failure_category: Option<FailureCategory>,
+    pub usage: Option<AttemptUsage>,
     pub created_by: Uuid,
     pub updated_at: DateTime<Utc>,
 }
@@ -360,6 +599,8 @@ impl From<TaskAttempt> for AttemptRead {
             diff_url: None,
             log_artifact_id: value.log_artifact_id,
             log_url: None,
+            failure_category: value.failure_category,
+            usage: value.usage,
             created_by: value.created_by,
             updated_at: value.updated_at,
         }
@@ -384,6 +625,10 @@ pub struct AttemptCompleteRequest {
     pub status: AttemptStatus,
     pub diff: Option<String>,
     pub log: Option<String>,
+    #[serde(default)]
+    pub failure_category: Option<FailureCategory>,
+    #[serde(default)]
+    pub usage: Option<AttemptUsage>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -391,6 +636,8 @@ pub struct AttemptCompleteResponse {
     pub status: AttemptStatus,
     pub diff_url: Option<String>,
     pub log_url: Option<String>,
+    pub task_status: TaskStatus,
+    pub retry_after: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -404,7 +651,17 @@ pub struct TaskDetail {
     pub created_by: Uuid,
     pub updated_at: DateTime<Utc>,
     pub environment_id: Option<String>,
+    pub retry_after: Option<DateTime<Utc>>,
+    pub priority: i64,
+    pub best_of_n: Option<i64>,
+    pub branch: Option<String>,
+    pub qa_mode: bool,
+    pub result_branch: Option<String>,
+    /// Tasks that must be applied before this one can be claimed.
+    pub depends_on: Vec<Uuid>,
+    pub group: Option<TaskGroupSummary>,
     pub repository: Option<RepositoryRead>,
+    pub inputs: Vec<TaskInputRead>,
     pub attempts: Vec<AttemptRead>,
 }
 
@@ -412,6 +669,7 @@ impl TaskDetail {
     pub fn from_entities(
         task: Task,
         repository: Option<Repository>,
+        inputs: Vec<TaskInputRead>,
         attempts: Vec<AttemptRead>,
     ) -> Self {
         let repository = repository.map(RepositoryRead::from);
@@ -425,12 +683,136 @@ impl TaskDetail {
             created_by: task.created_by,
             updated_at: task.updated_at,
             environment_id: task.environment_id,
+            retry_after: task.retry_after,
+            priority: task.priority,
+            best_of_n: task.best_of_n,
+            branch: task.branch,
+            qa_mode: task.qa_mode,
+            result_branch: task.result_branch,
+            depends_on: Vec::new(),
+            group: None,
             repository,
+            inputs,
             attempts,
         }
     }
 }
 
+/// Overall state of a task group, derived from its tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskGroupStatus {
+    /// No task has started yet, or the started ones are applied and the next
+    /// ones are waiting to be claimed.
+    Pending,
+    /// At least one task is claimed or running.
+    Running,
+    /// At least one task awaits review and none is running.
+    Review,
+    /// Every task has been applied.
+    Applied,
+    /// A task failed for good, and its dependents failed with it.
+    Failed,
+}
+
+impl TaskGroupStatus {
+    pub fn rollup(statuses: impl IntoIterator<Item = TaskStatus>) -> Self {
+        let (mut total, mut applied) = (0, 0);
+        let (mut active, mut review, mut failed) = (false, false, false);
+        for status in statuses {
+            total += 1;
+            match status {
+                TaskStatus::Applied => applied += 1,
+                TaskStatus::Claimed | TaskStatus::Running => active = true,
+                TaskStatus::Review => review = true,
+                TaskStatus::Failed => failed = true,
+                TaskStatus::Blocked | TaskStatus::Pending => {}
+            }
+        }
+        if failed {
+            Self::Failed
+        } else if total > 0 && applied == total {
+            Self::Applied
+        } else if active {
+            Self::Running
+        } else if review {
+            Self::Review
+        } else {
+            Self::Pending
+        }
+    }
+}
+
+/// A task within `TaskGroupCreate`. `depends_on` lists the positions of
+/// earlier tasks in the same request.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskGroupTaskCreate {
+    pub title: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub priority: i64,
+    #[serde(default)]
+    pub depends_on: Vec<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskGroupCreate {
+    pub title: String,
+    pub repository_id: Uuid,
+    pub environment_id: Option<String>,
+    /// Branch the tasks without prerequisites start from.
+    pub branch: Option<String>,
+    /// Makes every task depend on the one before it, in addition to any
+    /// explicit `depends_on`.
+    #[serde(default)]
+    pub ordered: bool,
+    pub tasks: Vec<TaskGroupTaskCreate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskGroupSummary {
+    pub id: Uuid,
+    pub title: String,
+    pub status: TaskGroupStatus,
+    pub total_tasks: i64,
+    pub applied_tasks: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskGroupTaskRead {
+    pub id: Uuid,
+    pub title: String,
+    pub status: TaskStatus,
+    pub branch: Option<String>,
+    pub result_branch: Option<String>,
+    pub depends_on: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskGroupRead {
+    pub id: Uuid,
+    pub title: String,
+    pub repository_id: Uuid,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub status: TaskGroupStatus,
+    pub total_tasks: i64,
+    pub applied_tasks: i64,
+    /// Tasks in creation order.
+    pub tasks: Vec<TaskGroupTaskRead>,
+}
+
+/// An image or file stored as an artifact for the executor to hand to the
+/// agent, in the order it appeared in the request.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskInputRead {
+    pub kind: TaskInputKind,
+    pub name: Option<String>,
+    pub mime_type: String,
+    pub artifact_id: String,
+    pub url: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CodexEnvironmentSummary {
     pub id: String,
@@ -472,12 +854,24 @@ pub struct CodexInputContent {
     #[serde(rename = "content_type")]
     pub content_type: Option<String>,
     pub text: Option<String>,
+    /// `data:` URL carrying an inline image.
+    #[serde(default)]
+    pub image_url: Option<String>,
+    #[serde(default)]
+    pub file_name: Option<String>,
+    #[serde(default)]
+    pub mime_type: Option<String>,
+    /// Base64-encoded file contents.
+    #[serde(default)]
+    pub data: Option<String>,
 }
 
 #[derive(Debug, Default, Deserialize)]
 pub struct CodexTaskMetadata {
     #[serde(default)]
     pub best_of_n: Option<usize>,
+    #[serde(default)]
+    pub priority: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -493,3 +887,343 @@ pub struct CodexCreatedTask {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub attempt_total: Option<usize>,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    pub id: i64,
+    pub occurred_at: DateTime<Utc>,
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: String,
+    pub before_status: Option<String>,
+    pub after_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+    pub request_id: String,
+    pub ip: Option<String>,
+}
+
+/// Task lifecycle events that webhook subscriptions can filter on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookEvent {
+    #[serde(rename = "task.created")]
+    TaskCreated,
+    #[serde(rename = "attempt.succeeded")]
+    AttemptSucceeded,
+    #[serde(rename = "attempt.failed")]
+    AttemptFailed,
+    #[serde(rename = "task.review_requested")]
+    ReviewRequested,
+    #[serde(rename = "task.applied")]
+    TaskApplied,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TaskCreated => "task.created",
+            Self::AttemptSucceeded => "attempt.succeeded",
+            Self::AttemptFailed => "attempt.failed",
+            Self::ReviewRequested => "task.review_requested",
+            Self::TaskApplied => "task.applied",
+        }
+    }
+}
+
+impl fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WebhookEvent {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "task.created" => Ok(Self::TaskCreated),
+            "attempt.succeeded" => Ok(Self::AttemptSucceeded),
+            "attempt.failed" => Ok(Self::AttemptFailed),
+            "task.review_requested" => Ok(Self::ReviewRequested),
+            "task.applied" => Ok(Self::TaskApplied),
+            other => Err(AppError::bad_request(format!(
+                "Invalid webhook event: {other}"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl FromStr for DeliveryStatus {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "succeeded" => Ok(Self::Succeeded),
+            "failed" => Ok(Self::Failed),
+            other => Err(AppError::bad_request(format!(
+                "Invalid delivery status: {other}"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookCreate {
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    /// Restricts the subscription to tasks of one repository.
+    #[serde(default)]
+    pub repository_id: Option<Uuid>,
+    /// Restricts the subscription to tasks created by members of one
+    /// organization, which the caller must belong to. Subscriptions with
+    /// neither a repository nor an organization receive events for every task
+    /// and can only be created by admins.
+    #[serde(default)]
+    pub organization_id: Option<Uuid>,
+    /// Shared secret used to sign payloads. Generated when omitted.
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookRead {
+    pub id: Uuid,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub repository_id: Option<Uuid>,
+    pub organization_id: Option<Uuid>,
+    pub active: bool,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    /// Only returned when the subscription is created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookDeliveryRead {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub event: WebhookEvent,
+    pub status: DeliveryStatus,
+    pub attempts: i64,
+    pub response_status: Option<i64>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub redelivery_of: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScheduleCreate {
+    pub name: String,
+    /// Five-field cron expression evaluated in UTC, e.g. `0 6 * * MON`.
+    pub cron: String,
+    pub environment_id: String,
+    pub prompt: String,
+    #[serde(default)]
+    pub best_of_n: Option<i64>,
+    #[serde(default)]
+    pub priority: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScheduleRead {
+    pub id: Uuid,
+    pub name: String,
+    pub cron: String,
+    pub environment_id: String,
+    pub prompt: String,
+    pub best_of_n: Option<i64>,
+    pub priority: i64,
+    pub paused: bool,
+    pub next_run_at: DateTime<Utc>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_task_id: Option<Uuid>,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Usage totals for one group of a usage report.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UsageReportRow {
+    /// User id, repository id or UTC day (`YYYY-MM-DD`), depending on the grouping.
+    pub key: String,
+    pub attempts: i64,
+    pub input_tokens: i64,
+    pub cached_input_tokens: i64,
+    pub output_tokens: i64,
+    pub reasoning_output_tokens: i64,
+    pub wall_time_ms: i64,
+    pub cpu_time_ms: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuotaUpdate {
+    /// Billable tokens (input plus output) the tasks of an organization's
+    /// members may consume together per UTC calendar month. `null` removes
+    /// the quota.
+    pub monthly_token_limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuotaRead {
+    pub organization_id: Uuid,
+    pub monthly_token_limit: i64,
+    pub used_tokens: i64,
+    pub period_start: DateTime<Utc>,
+}
+
+/// Role of a user within an organization.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrganizationRole {
+    #[default]
+    Member,
+    Admin,
+}
+
+impl OrganizationRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Member => "member",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for OrganizationRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OrganizationRole {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "member" => Ok(Self::Member),
+            "admin" => Ok(Self::Admin),
+            other => Err(AppError::bad_request(format!(
+                "Invalid organization role: {other}"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrganizationCreate {
+    pub name: String,
+}
+
+/// Organizations group users for usage reporting and token quotas.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrganizationRead {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub members: Vec<OrganizationMemberRead>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrganizationMemberRead {
+    pub user_id: Uuid,
+    pub role: OrganizationRole,
+    /// `manual` for memberships managed through the admin API.
+    pub source: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrganizationMemberUpdate {
+    #[serde(default)]
+    pub role: OrganizationRole,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserRead {
+    pub id: Uuid,
+    pub email: String,
+    pub name: Option<String>,
+    pub role: UserRole,
+    pub auth_provider: String,
+    pub created_at: DateTime<Utc>,
+    pub deactivated_at: Option<DateTime<Utc>>,
+    pub external_identities: Vec<ExternalIdentityRead>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExternalIdentityRead {
+    pub id: i64,
+    pub issuer: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserUpdate {
+    #[serde(default)]
+    pub role: Option<UserRole>,
+    /// `false` deactivates the user, which rejects their existing tokens and
+    /// further logins; `true` reactivates them.
+    #[serde(default)]
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasswordReset {
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InviteCreate {
+    /// Restricts the invite to this address.
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub role: UserRole,
+    /// Defaults to `CODEX_INVITE_EXPIRE_HOURS`.
+    #[serde(default)]
+    pub expires_in_hours: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InviteRead {
+    pub id: Uuid,
+    pub email: Option<String>,
+    pub role: UserRole,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub accepted_at: Option<DateTime<Utc>>,
+    pub accepted_by: Option<Uuid>,
+    /// Only returned when the invite is created; pass it as `invite_token` to
+    /// `POST /auth/users`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Invite link, returned with the token when `CODEX_INVITE_BASE_URL` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
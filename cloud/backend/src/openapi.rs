@@ -0,0 +1,980 @@
+//! OpenAPI 3.1 description of the HTTP API, served at `/openapi.json` and
+//! committed as `openapi.json` for client generation.
+//!
+//! Schemas are declared next to each other below with `api_object!` and
+//! `api_enum!`. Both macros destructure or match the Rust type exhaustively, so
+//! adding, removing or retyping a field or variant without updating its
+//! declaration fails to compile. Operations are declared in `routes.rs` next
+//! to their handlers: routes are only added through [`ApiRouter::route`], which
+//! takes the handler together with its [`Operation`]. `tests/openapi.rs`
+//! checks that the committed document is current.
+
+use axum::handler::Handler;
+use axum::http::Method;
+use axum::routing::{get, on, MethodFilter};
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::audit::AuditFilter;
+use crate::models::{
+    AttemptCompleteRequest, AttemptCompleteResponse, AttemptCreate, AttemptRead, AttemptStatus,
+    AttemptUsage, AuditLogEntry, ClaimResponse, CodexCreatedTask, CodexEnvironmentSummary,
+    CodexInputContent, CodexInputItem, CodexNewTask, CodexTaskCreate, CodexTaskCreateResponse,
+    CodexTaskMetadata, CreateUserRequest, CreateUserResponse, DeliveryStatus, EnvironmentCreate,
+    EnvironmentRead, EnvironmentUpdate, ExternalIdentityRead, FailureCategory, InviteCreate,
+    InviteRead, LoginRequest, OrganizationCreate, OrganizationMemberRead, OrganizationMemberUpdate,
+    OrganizationRead, OrganizationRole, PasswordReset, QuotaRead, QuotaUpdate, RepositoryCreate,
+    RepositoryRead, ScheduleCreate, ScheduleRead, TaskApply, TaskCreate, TaskDetail,
+    TaskGroupCreate, TaskGroupRead, TaskGroupStatus, TaskGroupSummary, TaskGroupTaskCreate,
+    TaskGroupTaskRead, TaskInputKind, TaskInputRead, TaskListResponse, TaskRead, TaskStatus,
+    TokenResponse, UsageReportRow, UserRead, UserRole, UserUpdate, WebhookCreate,
+    WebhookDeliveryRead, WebhookEvent, WebhookRead,
+};
+use crate::ratelimit;
+use crate::routes::{OidcCallbackQuery, TaskFilter};
+use crate::state::AppState;
+use crate::usage::{UsageGrouping, UsageQuery};
+
+/// A type that can appear in a request or response body.
+pub trait ApiSchema {
+    /// Schema used where the type appears, a `$ref` for named components.
+    fn schema() -> Value;
+
+    /// Whether an object field of this type must be present.
+    fn required() -> bool {
+        true
+    }
+
+    /// Schema for a query or path parameter, which cannot be `null`.
+    fn parameter_schema() -> Value {
+        Self::schema()
+    }
+
+    /// Adds the named components this type refers to.
+    fn register(_components: &mut Map<String, Value>) {}
+}
+
+/// A struct whose fields are described individually, used both for body
+/// components and for query parameter lists.
+pub trait ApiObject {
+    fn properties() -> Vec<Property>;
+}
+
+pub struct Property {
+    name: &'static str,
+    schema: Value,
+    parameter_schema: Value,
+    required: bool,
+}
+
+impl Property {
+    pub fn new<T: ApiSchema>(name: &'static str, has_default: bool) -> Self {
+        Self {
+            name,
+            schema: T::schema(),
+            parameter_schema: T::parameter_schema(),
+            required: T::required() && !has_default,
+        }
+    }
+}
+
+fn object_schema(properties: Vec<Property>) -> Value {
+    let required = properties
+        .iter()
+        .filter(|property| property.required)
+        .map(|property| property.name)
+        .collect::<Vec<_>>();
+    let properties = properties
+        .into_iter()
+        .map(|property| (property.name.to_string(), property.schema))
+        .collect::<Map<_, _>>();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+fn enum_schema<T: Serialize>(variants: &[T]) -> Value {
+    let values = variants
+        .iter()
+        .map(|variant| serde_json::to_value(variant).expect("enum variants serialize"))
+        .collect::<Vec<_>>();
+    json!({ "type": "string", "enum": values })
+}
+
+fn reference(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+macro_rules! primitive_schema {
+    ($ty:ty => $schema:tt) => {
+        impl ApiSchema for $ty {
+            fn schema() -> Value {
+                json!($schema)
+            }
+        }
+    };
+}
+
+primitive_schema!(String => { "type": "string" });
+primitive_schema!(bool => { "type": "boolean" });
+primitive_schema!(i64 => { "type": "integer", "format": "int64" });
+primitive_schema!(u32 => { "type": "integer", "format": "int32", "minimum": 0 });
+primitive_schema!(usize => { "type": "integer", "minimum": 0 });
+primitive_schema!(Uuid => { "type": "string", "format": "uuid" });
+primitive_schema!(DateTime<Utc> => { "type": "string", "format": "date-time" });
+primitive_schema!(Value => {});
+
+impl<T: ApiSchema> ApiSchema for Option<T> {
+    fn schema() -> Value {
+        // `Option<Option<T>>` marks a field that may be omitted or `null`;
+        // the inner option already allows `null`.
+        if !T::required() {
+            return T::schema();
+        }
+        json!({ "anyOf": [T::schema(), { "type": "null" }] })
+    }
+
+    fn required() -> bool {
+        false
+    }
+
+    fn parameter_schema() -> Value {
+        T::parameter_schema()
+    }
+
+    fn register(components: &mut Map<String, Value>) {
+        T::register(components);
+    }
+}
+
+impl<T: ApiSchema> ApiSchema for Vec<T> {
+    fn schema() -> Value {
+        json!({ "type": "array", "items": T::schema() })
+    }
+
+    fn register(components: &mut Map<String, Value>) {
+        T::register(components);
+    }
+}
+
+/// Declares the schema of a struct. Fields marked `#[default]` are filled in
+/// by serde when missing; `field as "name"` documents a serde rename.
+macro_rules! api_object {
+    (@name $field:ident) => { stringify!($field) };
+    (@name $field:ident $rename:literal) => { $rename };
+    (@default) => { false };
+    (@default default) => { true };
+    ($name:ident {
+        $( $(#[$attr:ident])? $field:ident $(as $rename:literal)? : $ty:ty ),* $(,)?
+    }) => {
+        impl ApiObject for $name {
+            fn properties() -> Vec<Property> {
+                let _ = |value: &$name| {
+                    let $name { $($field),* } = value;
+                    $( let _: &$ty = $field; )*
+                };
+                vec![$(
+                    Property::new::<$ty>(
+                        api_object!(@name $field $($rename)?),
+                        api_object!(@default $($attr)?),
+                    )
+                ),*]
+            }
+        }
+
+        impl ApiSchema for $name {
+            fn schema() -> Value {
+                reference(stringify!($name))
+            }
+
+            fn register(components: &mut Map<String, Value>) {
+                if components.contains_key(stringify!($name)) {
+                    return;
+                }
+                components.insert(
+                    stringify!($name).to_string(),
+                    object_schema(<$name as ApiObject>::properties()),
+                );
+                $( <$ty as ApiSchema>::register(components); )*
+            }
+        }
+    };
+}
+
+/// Declares the schema of a unit-variant enum from its serde names.
+macro_rules! api_enum {
+    ($name:ident [ $($variant:ident),* $(,)? ]) => {
+        impl ApiSchema for $name {
+            fn schema() -> Value {
+                reference(stringify!($name))
+            }
+
+            fn parameter_schema() -> Value {
+                Self::definition()
+            }
+
+            fn register(components: &mut Map<String, Value>) {
+                components.insert(stringify!($name).to_string(), Self::definition());
+            }
+        }
+
+        impl $name {
+            fn definition() -> Value {
+                let _ = |value: $name| match value {
+                    $( $name::$variant => (), )*
+                };
+                enum_schema(&[$($name::$variant),*])
+            }
+        }
+    };
+}
+
+api_enum!(TaskStatus [Blocked, Pending, Claimed, Running, Review, Applied, Failed]);
+api_enum!(AttemptStatus [Queued, Running, Succeeded, Failed, Cancelled]);
+api_enum!(FailureCategory [Infra, Agent, Timeout]);
+api_enum!(TaskInputKind [Image, File]);
+api_enum!(WebhookEvent [TaskCreated, AttemptSucceeded, AttemptFailed, ReviewRequested, TaskApplied]);
+api_enum!(DeliveryStatus [Pending, Succeeded, Failed]);
+api_enum!(UsageGrouping [User, Organization, Repository, Day]);
+api_enum!(UserRole [Member, Admin]);
+api_enum!(OrganizationRole [Member, Admin]);
+api_enum!(TaskGroupStatus [Pending, Running, Review, Applied, Failed]);
+
+api_object!(LoginRequest {
+    email: String,
+    password: String,
+});
+
+api_object!(CreateUserRequest {
+    email: String,
+    password: String,
+    name: Option<String>,
+    invite_token: Option<String>,
+});
+
+api_object!(CreateUserResponse {
+    id: Uuid,
+    email: String,
+    name: Option<String>,
+});
+
+api_object!(TokenResponse {
+    access_token: String,
+    token_type: String,
+});
+
+api_object!(OidcCallbackQuery { code: String });
+
+api_object!(RepositoryCreate {
+    name: String,
+    git_url: String,
+    default_branch: String,
+});
+
+api_object!(RepositoryRead {
+    id: Uuid,
+    name: String,
+    git_url: String,
+    default_branch: String,
+});
+
+api_object!(EnvironmentCreate {
+    id: String,
+    label: Option<String>,
+    repository_id: Uuid,
+    branch: String,
+    #[default] is_pinned: bool,
+    provider: Option<String>,
+    owner: Option<String>,
+    repo: Option<String>,
+    max_concurrency: Option<i64>,
+});
+
+api_object!(EnvironmentUpdate {
+    max_concurrency: Option<Option<i64>>,
+});
+
+api_object!(EnvironmentRead {
+    id: String,
+    label: Option<String>,
+    repository_id: Uuid,
+    branch: String,
+    is_pinned: bool,
+    provider: Option<String>,
+    owner: Option<String>,
+    repo: Option<String>,
+    max_concurrency: Option<i64>,
+});
+
+api_object!(TaskFilter {
+    status: Option<TaskStatus>,
+    environment_id: Option<String>,
+    group_id: Option<Uuid>,
+});
+
+api_object!(TaskCreate {
+    title: String,
+    description: Option<String>,
+    repository_id: Uuid,
+    #[default] priority: i64,
+});
+
+api_object!(TaskRead {
+    id: Uuid,
+    title: String,
+    description: Option<String>,
+    status: TaskStatus,
+    repository_id: Uuid,
+    assignee_id: Option<Uuid>,
+    created_by: Uuid,
+    updated_at: DateTime<Utc>,
+    environment_id: Option<String>,
+    retry_after: Option<DateTime<Utc>>,
+    priority: i64,
+    best_of_n: Option<i64>,
+    branch: Option<String>,
+    qa_mode: bool,
+    group_id: Option<Uuid>,
+    result_branch: Option<String>,
+});
+
+api_object!(TaskListResponse {
+    id: Uuid,
+    title: String,
+    status: TaskStatus,
+    repository_id: Uuid,
+    updated_at: DateTime<Utc>,
+    environment_id: Option<String>,
+    retry_after: Option<DateTime<Utc>>,
+    priority: i64,
+    group: Option<TaskGroupSummary>,
+});
+
+api_object!(TaskDetail {
+    id: Uuid,
+    title: String,
+    description: Option<String>,
+    status: TaskStatus,
+    repository_id: Uuid,
+    assignee_id: Option<Uuid>,
+    created_by: Uuid,
+    updated_at: DateTime<Utc>,
+    environment_id: Option<String>,
+    retry_after: Option<DateTime<Utc>>,
+    priority: i64,
+    best_of_n: Option<i64>,
+    branch: Option<String>,
+    qa_mode: bool,
+    result_branch: Option<String>,
+    depends_on: Vec<Uuid>,
+    group: Option<TaskGroupSummary>,
+    repository: Option<RepositoryRead>,
+    inputs: Vec<TaskInputRead>,
+    attempts: Vec<AttemptRead>,
+});
+
+api_object!(TaskApply {
+    #[default] branch: Option<String>,
+});
+
+api_object!(TaskGroupTaskCreate {
+    title: String,
+    description: Option<String>,
+    #[default] priority: i64,
+    #[default] depends_on: Vec<usize>,
+});
+
+api_object!(TaskGroupCreate {
+    title: String,
+    repository_id: Uuid,
+    environment_id: Option<String>,
+    branch: Option<String>,
+    #[default] ordered: bool,
+    tasks: Vec<TaskGroupTaskCreate>,
+});
+
+api_object!(TaskGroupSummary {
+    id: Uuid,
+    title: String,
+    status: TaskGroupStatus,
+    total_tasks: i64,
+    applied_tasks: i64,
+});
+
+api_object!(TaskGroupTaskRead {
+    id: Uuid,
+    title: String,
+    status: TaskStatus,
+    branch: Option<String>,
+    result_branch: Option<String>,
+    depends_on: Vec<Uuid>,
+});
+
+api_object!(TaskGroupRead {
+    id: Uuid,
+    title: String,
+    repository_id: Uuid,
+    created_by: Uuid,
+    created_at: DateTime<Utc>,
+    status: TaskGroupStatus,
+    total_tasks: i64,
+    applied_tasks: i64,
+    tasks: Vec<TaskGroupTaskRead>,
+});
+
+api_object!(TaskInputRead {
+    kind: TaskInputKind,
+    name: Option<String>,
+    mime_type: String,
+    artifact_id: String,
+    url: String,
+});
+
+api_object!(ClaimResponse {
+    claim_expires_at: DateTime<Utc>,
+});
+
+api_object!(AttemptCreate {
+    environment_id: Option<String>,
+});
+
+api_object!(AttemptUsage {
+    #[default] input_tokens: i64,
+    #[default] cached_input_tokens: i64,
+    #[default] output_tokens: i64,
+    #[default] reasoning_output_tokens: i64,
+    #[default] wall_time_ms: i64,
+    cpu_time_ms: Option<i64>,
+});
+
+api_object!(AttemptRead {
+    id: Uuid,
+    task_id: Uuid,
+    status: AttemptStatus,
+    diff_artifact_id: Option<String>,
+    diff_url: Option<String>,
+    log_artifact_id: Option<String>,
+    log_url: Option<String>,
+    failure_category: Option<FailureCategory>,
+    usage: Option<AttemptUsage>,
+    created_by: Uuid,
+    updated_at: DateTime<Utc>,
+});
+
+api_object!(AttemptCompleteRequest {
+    status: AttemptStatus,
+    diff: Option<String>,
+    log: Option<String>,
+    failure_category: Option<FailureCategory>,
+    usage: Option<AttemptUsage>,
+});
+
+api_object!(AttemptCompleteResponse {
+    status: AttemptStatus,
+    diff_url: Option<String>,
+    log_url: Option<String>,
+    task_status: TaskStatus,
+    retry_after: Option<DateTime<Utc>>,
+});
+
+api_object!(CodexEnvironmentSummary {
+    id: String,
+    label: Option<String>,
+    #[default] is_pinned: bool,
+    task_count: Option<i64>,
+});
+
+api_object!(CodexTaskCreate {
+    new_task: CodexNewTask,
+    #[default] input_items: Vec<CodexInputItem>,
+    metadata: Option<CodexTaskMetadata>,
+});
+
+api_object!(CodexNewTask {
+    environment_id: String,
+    branch: Option<String>,
+    #[default] run_environment_in_qa_mode: bool,
+});
+
+api_object!(CodexInputItem {
+    kind as "type": String,
+    role: Option<String>,
+    #[default] content: Vec<CodexInputContent>,
+});
+
+api_object!(CodexInputContent {
+    content_type: Option<String>,
+    text: Option<String>,
+    image_url: Option<String>,
+    file_name: Option<String>,
+    mime_type: Option<String>,
+    data: Option<String>,
+});
+
+api_object!(CodexTaskMetadata {
+    best_of_n: Option<usize>,
+    priority: Option<i64>,
+});
+
+api_object!(CodexTaskCreateResponse {
+    task: CodexCreatedTask,
+});
+
+api_object!(CodexCreatedTask {
+    id: Uuid,
+    status: TaskStatus,
+    environment_id: Option<String>,
+    attempt_total: Option<usize>,
+});
+
+api_object!(WebhookCreate {
+    url: String,
+    events: Vec<WebhookEvent>,
+    repository_id: Option<Uuid>,
+    organization_id: Option<Uuid>,
+    secret: Option<String>,
+});
+
+api_object!(WebhookRead {
+    id: Uuid,
+    url: String,
+    events: Vec<WebhookEvent>,
+    repository_id: Option<Uuid>,
+    organization_id: Option<Uuid>,
+    active: bool,
+    created_by: Uuid,
+    created_at: DateTime<Utc>,
+    secret: Option<String>,
+});
+
+api_object!(WebhookDeliveryRead {
+    id: Uuid,
+    subscription_id: Uuid,
+    event: WebhookEvent,
+    status: DeliveryStatus,
+    attempts: i64,
+    response_status: Option<i64>,
+    last_error: Option<String>,
+    next_attempt_at: Option<DateTime<Utc>>,
+    delivered_at: Option<DateTime<Utc>>,
+    redelivery_of: Option<Uuid>,
+    created_at: DateTime<Utc>,
+});
+
+api_object!(ScheduleCreate {
+    name: String,
+    cron: String,
+    environment_id: String,
+    prompt: String,
+    best_of_n: Option<i64>,
+    #[default] priority: i64,
+});
+
+api_object!(ScheduleRead {
+    id: Uuid,
+    name: String,
+    cron: String,
+    environment_id: String,
+    prompt: String,
+    best_of_n: Option<i64>,
+    priority: i64,
+    paused: bool,
+    next_run_at: DateTime<Utc>,
+    last_run_at: Option<DateTime<Utc>>,
+    last_task_id: Option<Uuid>,
+    created_by: Uuid,
+    created_at: DateTime<Utc>,
+});
+
+api_object!(AuditFilter {
+    actor_id: Option<Uuid>,
+    action: Option<String>,
+    target_type: Option<String>,
+    target_id: Option<String>,
+    request_id: Option<String>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    limit: Option<u32>,
+});
+
+api_object!(AuditLogEntry {
+    id: i64,
+    occurred_at: DateTime<Utc>,
+    actor_id: Option<Uuid>,
+    action: String,
+    target_type: String,
+    target_id: String,
+    before_status: Option<String>,
+    after_status: Option<String>,
+    details: Option<Value>,
+    request_id: String,
+    ip: Option<String>,
+});
+
+api_object!(UsageQuery {
+    #[default] group_by: UsageGrouping,
+    user_id: Option<Uuid>,
+    organization_id: Option<Uuid>,
+    repository_id: Option<Uuid>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+});
+
+api_object!(UsageReportRow {
+    key: String,
+    attempts: i64,
+    input_tokens: i64,
+    cached_input_tokens: i64,
+    output_tokens: i64,
+    reasoning_output_tokens: i64,
+    wall_time_ms: i64,
+    cpu_time_ms: i64,
+});
+
+api_object!(QuotaUpdate {
+    monthly_token_limit: Option<i64>,
+});
+
+api_object!(QuotaRead {
+    organization_id: Uuid,
+    monthly_token_limit: i64,
+    used_tokens: i64,
+    period_start: DateTime<Utc>,
+});
+
+api_object!(OrganizationCreate { name: String });
+
+api_object!(OrganizationRead {
+    id: Uuid,
+    name: String,
+    created_at: DateTime<Utc>,
+    members: Vec<OrganizationMemberRead>,
+});
+
+api_object!(OrganizationMemberRead {
+    user_id: Uuid,
+    role: OrganizationRole,
+    source: String,
+});
+
+api_object!(OrganizationMemberUpdate {
+    #[default]
+    role: OrganizationRole,
+});
+
+api_object!(UserRead {
+    id: Uuid,
+    email: String,
+    name: Option<String>,
+    role: UserRole,
+    auth_provider: String,
+    created_at: DateTime<Utc>,
+    deactivated_at: Option<DateTime<Utc>>,
+    external_identities: Vec<ExternalIdentityRead>,
+});
+
+api_object!(ExternalIdentityRead {
+    id: i64,
+    issuer: String,
+    subject: String,
+    email: Option<String>,
+    created_at: DateTime<Utc>,
+});
+
+api_object!(UserUpdate {
+    role: Option<UserRole>,
+    active: Option<bool>,
+});
+
+api_object!(PasswordReset { password: String });
+
+api_object!(InviteCreate {
+    email: Option<String>,
+    #[default] role: UserRole,
+    expires_in_hours: Option<u32>,
+});
+
+api_object!(InviteRead {
+    id: Uuid,
+    email: Option<String>,
+    role: UserRole,
+    created_by: Uuid,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    accepted_at: Option<DateTime<Utc>>,
+    accepted_by: Option<Uuid>,
+    token: Option<String>,
+    url: Option<String>,
+});
+
+/// One documented route. Path parameters must be declared with
+/// [`Operation::path`] in the order they appear in the path.
+pub struct Operation {
+    method: Method,
+    path: String,
+    value: Map<String, Value>,
+    parameters: Vec<Value>,
+    responses: Map<String, Value>,
+    components: Map<String, Value>,
+}
+
+impl Operation {
+    fn new(method: Method, path: &str, summary: &str) -> Self {
+        let mut value = Map::new();
+        value.insert("summary".to_string(), json!(summary));
+        Self {
+            method,
+            path: path.to_string(),
+            value,
+            parameters: Vec::new(),
+            responses: Map::new(),
+            components: Map::new(),
+        }
+    }
+
+    pub fn get(path: &str, summary: &str) -> Self {
+        Self::new(Method::GET, path, summary)
+    }
+
+    pub fn post(path: &str, summary: &str) -> Self {
+        Self::new(Method::POST, path, summary)
+    }
+
+    pub fn put(path: &str, summary: &str) -> Self {
+        Self::new(Method::PUT, path, summary)
+    }
+
+    pub fn patch(path: &str, summary: &str) -> Self {
+        Self::new(Method::PATCH, path, summary)
+    }
+
+    pub fn delete(path: &str, summary: &str) -> Self {
+        Self::new(Method::DELETE, path, summary)
+    }
+
+    /// Requires a bearer token from `/auth/session` or the OIDC callback.
+    pub fn authenticated(mut self) -> Self {
+        self.value
+            .insert("security".to_string(), json!([{ "bearerAuth": [] }]));
+        self
+    }
+
+    pub fn path<T: ApiSchema>(mut self, name: &str) -> Self {
+        self.parameters.push(json!({
+            "name": name,
+            "in": "path",
+            "required": true,
+            "schema": T::parameter_schema(),
+        }));
+        self
+    }
+
+    pub fn query<T: ApiObject>(mut self) -> Self {
+        for property in T::properties() {
+            self.parameters.push(json!({
+                "name": property.name,
+                "in": "query",
+                "required": property.required,
+                "schema": property.parameter_schema,
+            }));
+        }
+        self
+    }
+
+    pub fn body<T: ApiSchema>(self) -> Self {
+        self.request_body::<T>(true)
+    }
+
+    /// A body the handler accepts as `Option<Json<T>>`.
+    pub fn optional_body<T: ApiSchema>(self) -> Self {
+        self.request_body::<T>(false)
+    }
+
+    fn request_body<T: ApiSchema>(mut self, required: bool) -> Self {
+        T::register(&mut self.components);
+        self.value.insert(
+            "requestBody".to_string(),
+            json!({
+                "required": required,
+                "content": { "application/json": { "schema": T::schema() } },
+            }),
+        );
+        self
+    }
+
+    pub fn json<T: ApiSchema>(mut self, status: u16, description: &str) -> Self {
+        T::register(&mut self.components);
+        self.responses.insert(
+            status.to_string(),
+            json!({
+                "description": description,
+                "content": { "application/json": { "schema": T::schema() } },
+            }),
+        );
+        self
+    }
+
+    pub fn content(mut self, status: u16, description: &str, content_type: &str) -> Self {
+        self.responses.insert(
+            status.to_string(),
+            json!({
+                "description": description,
+                "content": { content_type: { "schema": { "type": "string" } } },
+            }),
+        );
+        self
+    }
+
+    pub fn empty(mut self, status: u16, description: &str) -> Self {
+        self.responses
+            .insert(status.to_string(), json!({ "description": description }));
+        self
+    }
+
+    fn into_value(mut self) -> Value {
+        if !self.parameters.is_empty() {
+            self.value
+                .insert("parameters".to_string(), Value::Array(self.parameters));
+        }
+        if !ratelimit::EXEMPT_PATHS.contains(&self.path.as_str()) {
+            self.responses.insert(
+                "429".to_string(),
+                json!({
+                    "description": "Rate limit exceeded",
+                    "headers": {
+                        "Retry-After": {
+                            "description": "Seconds to wait before retrying",
+                            "schema": { "type": "integer" },
+                        },
+                    },
+                    "content": { "application/json": { "schema": reference("Error") } },
+                }),
+            );
+        }
+        self.responses.insert(
+            "default".to_string(),
+            json!({
+                "description": "Error",
+                "content": { "application/json": { "schema": reference("Error") } },
+            }),
+        );
+        self.value
+            .insert("responses".to_string(), Value::Object(self.responses));
+        Value::Object(self.value)
+    }
+}
+
+/// A router that takes every route together with its [`Operation`], so a
+/// route cannot be added without being documented. Operations are tagged
+/// with the tag of the router they are registered on.
+pub struct ApiRouter {
+    router: Router<AppState>,
+    tag: &'static str,
+    operations: Vec<Operation>,
+}
+
+impl ApiRouter {
+    pub fn new(tag: &'static str) -> Self {
+        Self {
+            router: Router::new(),
+            tag,
+            operations: Vec::new(),
+        }
+    }
+
+    /// Routes `operation`'s method and path to `handler`.
+    pub fn route<H, T>(mut self, handler: H, operation: Operation) -> Self
+    where
+        H: Handler<T, AppState>,
+        T: 'static,
+    {
+        let filter = MethodFilter::try_from(operation.method.clone())
+            .expect("documented methods can be routed");
+        self.router = self.router.route(&operation.path, on(filter, handler));
+        self.push(operation);
+        self
+    }
+
+    fn push(&mut self, mut operation: Operation) {
+        operation
+            .value
+            .insert("tags".to_string(), json!([self.tag]));
+        self.operations.push(operation);
+    }
+
+    /// Mounts `routes` under `prefix`, keeping the tags they were given.
+    pub fn nest(mut self, prefix: &str, routes: ApiRouter) -> Self {
+        self.router = self.router.nest(prefix, routes.router);
+        for mut operation in routes.operations {
+            operation.path = match operation.path.as_str() {
+                "/" => prefix.to_string(),
+                path => format!("{prefix}{path}"),
+            };
+            self.operations.push(operation);
+        }
+        self
+    }
+
+    /// Adds `/openapi.json`, serving the document built from every operation
+    /// registered so far, and returns the underlying router.
+    pub fn into_router(mut self) -> Router<AppState> {
+        self.push(Operation::get("/openapi.json", "This document").content(
+            200,
+            "OpenAPI document",
+            "application/json",
+        ));
+        let document = Json(document(self.operations));
+        self.router.route(
+            "/openapi.json",
+            get(move || {
+                let document = document.clone();
+                async move { document }
+            }),
+        )
+    }
+}
+
+/// Builds the OpenAPI document for `operations`.
+fn document(operations: Vec<Operation>) -> Value {
+    let mut components = Map::new();
+    components.insert(
+        "Error".to_string(),
+        json!({
+            "type": "object",
+            "properties": { "detail": { "type": "string" } },
+            "required": ["detail"],
+        }),
+    );
+
+    let mut paths = Map::new();
+    for mut operation in operations {
+        components.append(&mut operation.components);
+        let item = paths
+            .entry(operation.path.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        let method = operation.method.as_str().to_ascii_lowercase();
+        item.as_object_mut()
+            .expect("path items are objects")
+            .insert(method, operation.into_value());
+    }
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "Codex Cloud API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": components,
+            "securitySchemes": {
+                "bearerAuth": { "type": "http", "scheme": "bearer", "bearerFormat": "JWT" },
+            },
+        },
+    })
+}
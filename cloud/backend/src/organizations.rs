@@ -0,0 +1,236 @@
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+use chrono::Utc;
+use sqlx::{Row, Sqlite, SqlitePool, Transaction};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::{
+    format_datetime, parse_datetime, OrganizationCreate, OrganizationMemberRead, OrganizationRead,
+    OrganizationRole,
+};
+
+/// Source recorded for memberships managed through the admin API.
+pub const MANUAL_SOURCE: &str = "manual";
+
+fn parse_uuid(value: &str) -> Result<Uuid, AppError> {
+    Uuid::parse_str(value).map_err(|_| AppError::bad_request("Invalid identifier"))
+}
+
+pub async fn create_organization(
+    tx: &mut Transaction<'_, Sqlite>,
+    payload: OrganizationCreate,
+) -> Result<OrganizationRead, AppError> {
+    let name = payload.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::bad_request("Organization name must not be empty"));
+    }
+
+    let id = Uuid::new_v4();
+    let created_at = Utc::now();
+    let result = sqlx::query("INSERT INTO organizations (id, name, created_at) VALUES (?, ?, ?)")
+        .bind(id.to_string())
+        .bind(&name)
+        .bind(format_datetime(created_at))
+        .execute(&mut **tx)
+        .await;
+    match result {
+        Ok(_) => Ok(OrganizationRead {
+            id,
+            name,
+            created_at,
+            members: Vec::new(),
+        }),
+        Err(sqlx::Error::Database(db_err)) if db_err.message().contains("UNIQUE") => {
+            Err(AppError::conflict("Organization already exists"))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub async fn list_organizations(pool: &SqlitePool) -> Result<Vec<OrganizationRead>, AppError> {
+    let member_rows = sqlx::query(
+        r#"
+        SELECT organization_id, user_id, role, source
+        FROM organization_members
+        ORDER BY user_id, source
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut members: HashMap<String, Vec<OrganizationMemberRead>> = HashMap::new();
+    for row in member_rows {
+        let organization_id: String = row.try_get("organization_id")?;
+        let user_id: String = row.try_get("user_id")?;
+        let role: String = row.try_get("role")?;
+        members
+            .entry(organization_id)
+            .or_default()
+            .push(OrganizationMemberRead {
+                user_id: parse_uuid(&user_id)?,
+                role: OrganizationRole::from_str(&role)?,
+                source: row.try_get("source")?,
+            });
+    }
+
+    let rows = sqlx::query("SELECT id, name, created_at FROM organizations ORDER BY name")
+        .fetch_all(pool)
+        .await?;
+    rows.iter()
+        .map(|row| {
+            let id: String = row.try_get("id")?;
+            let created_at: String = row.try_get("created_at")?;
+            Ok(OrganizationRead {
+                id: parse_uuid(&id)?,
+                name: row.try_get("name")?,
+                created_at: parse_datetime(&created_at)?,
+                members: members.remove(&id).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+pub async fn ensure_exists(
+    tx: &mut Transaction<'_, Sqlite>,
+    organization_id: Uuid,
+) -> Result<(), AppError> {
+    let exists = sqlx::query_scalar::<_, i64>("SELECT 1 FROM organizations WHERE id = ?")
+        .bind(organization_id.to_string())
+        .fetch_optional(&mut **tx)
+        .await?;
+    match exists {
+        Some(_) => Ok(()),
+        None => Err(AppError::not_found("Organization not found")),
+    }
+}
+
+/// Whether `user_id` belongs to the organization through any source.
+pub async fn is_member(
+    tx: &mut Transaction<'_, Sqlite>,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, AppError> {
+    let member = sqlx::query_scalar::<_, i64>(
+        "SELECT 1 FROM organization_members WHERE organization_id = ? AND user_id = ? LIMIT 1",
+    )
+    .bind(organization_id.to_string())
+    .bind(user_id.to_string())
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(member.is_some())
+}
+
+/// Adds `user_id` to the organization or changes their role. Memberships are
+/// kept per source so that an identity provider only replaces its own rows.
+pub async fn set_member(
+    tx: &mut Transaction<'_, Sqlite>,
+    organization_id: Uuid,
+    user_id: Uuid,
+    role: OrganizationRole,
+    source: &str,
+) -> Result<(), AppError> {
+    ensure_exists(tx, organization_id).await?;
+    let user = sqlx::query_scalar::<_, i64>("SELECT 1 FROM users WHERE id = ?")
+        .bind(user_id.to_string())
+        .fetch_optional(&mut **tx)
+        .await?;
+    if user.is_none() {
+        return Err(AppError::not_found("User not found"));
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO organization_members (organization_id, user_id, role, source, created_at)
+        VALUES (?, ?, ?, ?, ?)
+        ON CONFLICT(organization_id, user_id, source) DO UPDATE SET role = excluded.role
+        "#,
+    )
+    .bind(organization_id.to_string())
+    .bind(user_id.to_string())
+    .bind(role.as_str())
+    .bind(source)
+    .bind(format_datetime(Utc::now()))
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+pub async fn remove_member(
+    tx: &mut Transaction<'_, Sqlite>,
+    organization_id: Uuid,
+    user_id: Uuid,
+    source: &str,
+) -> Result<(), AppError> {
+    let result = sqlx::query(
+        "DELETE FROM organization_members WHERE organization_id = ? AND user_id = ? AND source = ?",
+    )
+    .bind(organization_id.to_string())
+    .bind(user_id.to_string())
+    .bind(source)
+    .execute(&mut **tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Membership not found"));
+    }
+    Ok(())
+}
+
+/// Replaces the memberships `source` granted `user_id` with `memberships`,
+/// keyed by organization name. Organizations that do not exist are skipped.
+/// Returns whether anything changed.
+pub async fn sync_memberships(
+    tx: &mut Transaction<'_, Sqlite>,
+    user_id: Uuid,
+    source: &str,
+    memberships: &BTreeMap<String, OrganizationRole>,
+) -> Result<bool, AppError> {
+    let rows = sqlx::query(
+        "SELECT organization_id, role FROM organization_members WHERE user_id = ? AND source = ?",
+    )
+    .bind(user_id.to_string())
+    .bind(source)
+    .fetch_all(&mut **tx)
+    .await?;
+    let mut current = HashMap::new();
+    for row in rows {
+        let organization_id: String = row.try_get("organization_id")?;
+        let role: String = row.try_get("role")?;
+        current.insert(
+            parse_uuid(&organization_id)?,
+            OrganizationRole::from_str(&role)?,
+        );
+    }
+
+    let mut desired = HashMap::new();
+    for (name, role) in memberships {
+        let id = sqlx::query_scalar::<_, String>("SELECT id FROM organizations WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&mut **tx)
+            .await?;
+        match id {
+            Some(id) => {
+                desired.insert(parse_uuid(&id)?, *role);
+            }
+            None => {
+                warn!(organization = %name, %source, "Skipping membership of unknown organization")
+            }
+        }
+    }
+
+    let mut changed = false;
+    for organization_id in current.keys() {
+        if !desired.contains_key(organization_id) {
+            remove_member(tx, *organization_id, user_id, source).await?;
+            changed = true;
+        }
+    }
+    for (organization_id, role) in desired {
+        if current.get(&organization_id) != Some(&role) {
+            set_member(tx, organization_id, user_id, role, source).await?;
+            changed = true;
+        }
+    }
+    Ok(changed)
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{Request, State};
+use axum::http::header::{AUTHORIZATION, RETRY_AFTER};
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::json;
+
+use crate::audit::client_ip;
+use crate::config::{RateLimit, RateLimitSettings};
+use crate::security::decode_token;
+use crate::state::AppState;
+
+/// Probes and scrapes that are never limited.
+pub const EXEMPT_PATHS: &[&str] = &["/health", "/metrics"];
+
+/// Buckets are only pruned once this many keys are tracked.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Policy {
+    Default,
+    Auth,
+}
+
+impl Policy {
+    const ALL: [Self; 2] = [Self::Default, Self::Auth];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Auth => "auth",
+        }
+    }
+
+    /// Login and registration are limited per client IP with the stricter
+    /// auth policy; everything else uses the default policy.
+    pub fn for_request(method: &Method, path: &str) -> Self {
+        if method == Method::POST && matches!(path, "/auth/session" | "/auth/users") {
+            Self::Auth
+        } else {
+            Self::Default
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Self::Default => 0,
+            Self::Auth => 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+#[derive(Default)]
+struct PolicyCounters {
+    allowed: AtomicU64,
+    rejected: AtomicU64,
+}
+
+/// In-memory token buckets keyed by policy and client. Limits are per
+/// backend process.
+#[derive(Clone)]
+pub struct RateLimiter {
+    inner: Arc<RateLimiterInner>,
+}
+
+struct RateLimiterInner {
+    settings: RateLimitSettings,
+    buckets: Mutex<HashMap<(Policy, String), Bucket>>,
+    counters: [PolicyCounters; 2],
+}
+
+impl RateLimiter {
+    pub fn new(settings: RateLimitSettings) -> Self {
+        Self {
+            inner: Arc::new(RateLimiterInner {
+                settings,
+                buckets: Mutex::new(HashMap::new()),
+                counters: Default::default(),
+            }),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.inner.settings.enabled
+    }
+
+    fn limit(&self, policy: Policy) -> RateLimit {
+        match policy {
+            Policy::Default => self.inner.settings.default,
+            Policy::Auth => self.inner.settings.auth,
+        }
+    }
+
+    /// Takes a token from the bucket for `key`. When the bucket is empty,
+    /// returns how long the client has to wait for the next token.
+    pub fn check(&self, policy: Policy, key: &str, now: Instant) -> Result<(), Duration> {
+        let limit = self.limit(policy);
+        let burst = f64::from(limit.burst);
+        let per_second = f64::from(limit.per_minute) / 60.0;
+
+        let result = {
+            let mut buckets = self.inner.buckets.lock().unwrap();
+            if buckets.len() >= PRUNE_THRESHOLD {
+                buckets.retain(|(policy, _), bucket| {
+                    let limit = self.limit(*policy);
+                    let refill = now.duration_since(bucket.updated).as_secs_f64()
+                        * f64::from(limit.per_minute)
+                        / 60.0;
+                    bucket.tokens + refill < f64::from(limit.burst)
+                });
+            }
+
+            let bucket = buckets.entry((policy, key.to_string())).or_insert(Bucket {
+                tokens: burst,
+                updated: now,
+            });
+            let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * per_second).min(burst);
+            bucket.updated = now;
+
+            if bucket.tokens >= 1.0 {
+                bucket.tokens -= 1.0;
+                Ok(())
+            } else {
+                Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+            }
+        };
+
+        let counters = &self.inner.counters[policy.index()];
+        match result {
+            Ok(()) => counters.allowed.fetch_add(1, Ordering::Relaxed),
+            Err(_) => counters.rejected.fetch_add(1, Ordering::Relaxed),
+        };
+        result
+    }
+
+    /// Renders the limiter configuration and counters in the Prometheus text
+    /// exposition format.
+    pub fn metrics(&self) -> String {
+        let mut out = String::new();
+        let mut family = |name: &str, kind: &str, help: &str, value: &dyn Fn(Policy) -> u64| {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            for policy in Policy::ALL {
+                let _ = writeln!(
+                    out,
+                    "{name}{{policy=\"{}\"}} {}",
+                    policy.as_str(),
+                    value(policy)
+                );
+            }
+        };
+        family(
+            "codex_rate_limit_allowed_total",
+            "counter",
+            "Requests admitted by the rate limiter.",
+            &|policy| {
+                self.inner.counters[policy.index()]
+                    .allowed
+                    .load(Ordering::Relaxed)
+            },
+        );
+        family(
+            "codex_rate_limit_rejected_total",
+            "counter",
+            "Requests rejected with 429 Too Many Requests.",
+            &|policy| {
+                self.inner.counters[policy.index()]
+                    .rejected
+                    .load(Ordering::Relaxed)
+            },
+        );
+        family(
+            "codex_rate_limit_per_minute",
+            "gauge",
+            "Sustained requests per minute allowed for each key.",
+            &|policy| u64::from(self.limit(policy).per_minute),
+        );
+        family(
+            "codex_rate_limit_burst",
+            "gauge",
+            "Requests each key may send in a burst.",
+            &|policy| u64::from(self.limit(policy).burst),
+        );
+
+        let tracked = self.inner.buckets.lock().unwrap().len();
+        let _ = writeln!(
+            out,
+            "# HELP codex_rate_limit_tracked_keys Clients with a rate limit bucket in memory."
+        );
+        let _ = writeln!(out, "# TYPE codex_rate_limit_tracked_keys gauge");
+        let _ = writeln!(out, "codex_rate_limit_tracked_keys {tracked}");
+        out
+    }
+}
+
+/// Middleware applying the rate limits. Authenticated requests are keyed by
+/// user so that several tokens of one user share a bucket; anonymous requests
+/// and the auth policy are keyed by client IP.
+pub async fn enforce(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let limiter = &state.rate_limiter;
+    let path = request.uri().path();
+    if !limiter.enabled() || EXEMPT_PATHS.contains(&path) {
+        return next.run(request).await;
+    }
+
+    let policy = Policy::for_request(request.method(), path);
+    let user_key = match policy {
+        Policy::Auth => None,
+        Policy::Default => request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| decode_token(token, &state.config).ok())
+            .map(|user_id| format!("user:{user_id}")),
+    };
+    let key = user_key.unwrap_or_else(|| {
+        let ip = client_ip(
+            request.headers(),
+            request.extensions(),
+            &state.config.trusted_proxies,
+        );
+        format!("ip:{}", ip.as_deref().unwrap_or("unknown"))
+    });
+
+    match limiter.check(policy, &key, Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(RETRY_AFTER, retry_after.to_string())],
+                Json(json!({ "detail": "Rate limit exceeded" })),
+            )
+                .into_response()
+        }
+    }
+}
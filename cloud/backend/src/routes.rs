@@ -1,35 +1,55 @@
 use std::str::FromStr;
 
 use axum::extract::{Path, Query, State};
-use axum::http::{HeaderValue, Method, StatusCode};
-use axum::routing::{get, post};
+use axum::http::{header, HeaderValue, Method, StatusCode};
+use axum::middleware;
 use axum::Json;
 use axum::Router;
+use base64::prelude::{Engine as _, BASE64_STANDARD};
 use chrono::Utc;
 use serde::Deserialize;
+use serde_json::json;
 use sqlx::sqlite::SqliteRow;
-use sqlx::{QueryBuilder, Row, Sqlite, SqlitePool};
+use sqlx::{Executor, QueryBuilder, Row, Sqlite, SqlitePool, Transaction};
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use uuid::Uuid;
 
 use crate::artifacts;
+use crate::audit::{self, AuditContext, AuditFilter, AuditRecord};
 use crate::db;
 use crate::error::AppError;
 use crate::models::{
     claim_expiration, format_datetime, parse_datetime, AttemptCompleteRequest,
-    AttemptCompleteResponse, AttemptRead, AttemptStatus, ClaimResponse, CodexEnvironmentSummary,
-    CodexInputItem, CodexTaskCreate, CodexTaskCreateResponse, CreateUserRequest,
-    CreateUserResponse, Environment, EnvironmentCreate, EnvironmentRead, LoginRequest, Repository,
-    RepositoryCreate, RepositoryRead, Task, TaskAttempt, TaskCreate, TaskDetail, TaskListResponse,
-    TaskStatus, User,
+    AttemptCompleteResponse, AttemptCreate, AttemptRead, AttemptStatus, AttemptUsage,
+    AuditLogEntry, ClaimResponse, CodexEnvironmentSummary, CodexInputItem, CodexTaskCreate,
+    CodexTaskCreateResponse, CreateUserRequest, CreateUserResponse, Environment, EnvironmentCreate,
+    EnvironmentRead, EnvironmentUpdate, FailureCategory, InviteCreate, InviteRead, LoginRequest,
+    OrganizationCreate, OrganizationMemberUpdate, OrganizationRead, PasswordReset, QuotaRead,
+    QuotaUpdate, Repository, RepositoryCreate, RepositoryRead, ScheduleCreate, ScheduleRead, Task,
+    TaskApply, TaskAttempt, TaskCreate, TaskDetail, TaskGroupCreate, TaskGroupRead, TaskInputKind,
+    TaskInputRead, TaskListResponse, TaskRead, TaskStatus, TokenResponse, UsageReportRow, User,
+    UserRead, UserRole, UserUpdate, WebhookCreate, WebhookDeliveryRead, WebhookEvent, WebhookRead,
+};
+use crate::openapi::{ApiRouter, Operation};
+use crate::organizations;
+use crate::ratelimit;
+use crate::scheduler;
+use crate::security::{
+    create_access_token, ensure_owner_or_admin, hash_password, is_admin, verify_password,
+    AdminUser, CurrentUser,
 };
-use crate::security::{create_access_token, hash_password, verify_password, CurrentUser};
 use crate::state::AppState;
+use crate::task_groups;
+use crate::usage::{self, UsageQuery};
+use crate::users;
+use crate::webhooks;
 
 #[derive(Debug, Deserialize)]
-struct TaskFilter {
-    status: Option<TaskStatus>,
+pub(crate) struct TaskFilter {
+    pub(crate) status: Option<TaskStatus>,
+    pub(crate) environment_id: Option<String>,
+    pub(crate) group_id: Option<Uuid>,
 }
 
 pub fn app_router(state: AppState) -> Router {
@@ -44,63 +64,163 @@ pub fn app_router(state: AppState) -> Router {
             .collect::<Vec<_>>();
         CorsLayer::new()
             .allow_origin(origins)
-            .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+            .allow_methods([
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+                Method::OPTIONS,
+            ])
             .allow_headers(Any)
     };
 
-    Router::new()
-        .route("/health", get(|| async { StatusCode::OK }))
+    ApiRouter::new("meta")
+        .route(
+            || async { StatusCode::OK },
+            Operation::get("/health", "Liveness check").empty(200, "Healthy"),
+        )
+        .route(
+            metrics,
+            Operation::get("/metrics", "Prometheus metrics").content(
+                200,
+                "Metrics in the Prometheus text format",
+                "text/plain",
+            ),
+        )
         .nest("/auth", auth_routes())
         .nest("/repositories", repository_routes())
         .nest("/environments", environment_routes())
         .nest("/tasks", task_routes())
+        .nest("/task-groups", task_group_routes())
         .nest("/artifacts", artifact_routes())
         .nest("/api/codex", codex_routes())
-        .with_state(state)
+        .nest("/webhooks", webhook_routes())
+        .nest("/schedules", schedule_routes())
+        .nest("/admin", admin_routes())
+        .into_router()
+        .with_state(state.clone())
+        .layer(middleware::from_fn_with_state(state, ratelimit::enforce))
         .layer(cors_layer)
         .layer(TraceLayer::new_for_http())
 }
 
-fn auth_routes() -> Router<AppState> {
-    Router::new()
-        .route("/users", post(create_user))
-        .route("/session", post(login))
-        .route("/oidc/callback", get(oidc_callback))
+async fn metrics(
+    State(state): State<AppState>,
+) -> ([(header::HeaderName, &'static str); 1], String) {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.rate_limiter.metrics(),
+    )
+}
+
+fn auth_routes() -> ApiRouter {
+    ApiRouter::new("auth")
+        .route(
+            create_user,
+            Operation::post("/users", "Register a user")
+                .body::<CreateUserRequest>()
+                .json::<CreateUserResponse>(201, "User created"),
+        )
+        .route(
+            login,
+            Operation::post("/session", "Log in with a password")
+                .body::<LoginRequest>()
+                .json::<TokenResponse>(200, "Access token"),
+        )
+        .route(
+            oidc_callback,
+            Operation::get(
+                "/oidc/callback",
+                "Complete an OIDC login with the only configured provider",
+            )
+            .query::<OidcCallbackQuery>()
+            .json::<TokenResponse>(200, "Access token"),
+        )
+        .route(
+            oidc_provider_callback,
+            Operation::get(
+                "/oidc/{provider}/callback",
+                "Complete an OIDC login with a named provider",
+            )
+            .path::<String>("provider")
+            .query::<OidcCallbackQuery>()
+            .json::<TokenResponse>(200, "Access token"),
+        )
 }
 
 async fn create_user(
     State(state): State<AppState>,
+    audit_context: AuditContext,
     Json(payload): Json<CreateUserRequest>,
 ) -> Result<(StatusCode, Json<CreateUserResponse>), AppError> {
     let CreateUserRequest {
         email,
         password,
         name,
+        invite_token,
     } = payload;
     let password_hash = hash_password(&password)?;
     let user_id = Uuid::new_v4();
-    let now = format_datetime(Utc::now());
+    let now = Utc::now();
+    let mut tx = state.pool.begin().await?;
+    let invite = match invite_token.as_deref() {
+        Some(token) => Some(users::find_invite(&mut tx, token, &email, now).await?),
+        None if !state.config.registration.open => {
+            return Err(AppError::forbidden(
+                "Registration is disabled; an invite is required",
+            ));
+        }
+        None => None,
+    };
+    let role = invite
+        .as_ref()
+        .map(|invite| invite.role)
+        .unwrap_or_default();
 
     let result = sqlx::query(
         r#"
-        INSERT INTO users (id, email, name, password_hash, auth_provider, created_at)
-        VALUES (?, ?, ?, ?, 'local', ?)
+        INSERT INTO users (id, email, name, password_hash, auth_provider, created_at, role)
+        VALUES (?, ?, ?, ?, 'local', ?, ?)
         "#,
     )
     .bind(user_id.to_string())
     .bind(&email)
     .bind(&name)
     .bind(password_hash)
-    .bind(now)
-    .execute(&state.pool)
+    .bind(format_datetime(now))
+    .bind(role.as_str())
+    .execute(&mut *tx)
     .await;
 
     match result {
         Ok(_) => {
+            if let Some(invite) = &invite {
+                users::accept_invite(&mut tx, invite.id, user_id, now).await?;
+            }
+            audit::record(
+                &mut tx,
+                &audit_context,
+                AuditRecord {
+                    actor_id: Some(user_id),
+                    action: "user.create",
+                    target_type: "user",
+                    target_id: user_id.to_string(),
+                    before_status: None,
+                    after_status: None,
+                    details: invite
+                        .as_ref()
+                        .map(|invite| json!({ "invite_id": invite.id, "role": role })),
+                },
+            )
+            .await?;
+            tx.commit().await?;
             let user = User {
                 id: user_id,
                 email,
                 name,
+                role,
+                deactivated: false,
             };
             Ok((StatusCode::CREATED, Json(CreateUserResponse::from(user))))
         }
@@ -117,7 +237,7 @@ async fn login(
 ) -> Result<Json<crate::models::TokenResponse>, AppError> {
     let row = sqlx::query(
         r#"
-        SELECT id, password_hash, name
+        SELECT id, password_hash, name, deactivated_at
         FROM users
         WHERE email = ?
         "#,
@@ -131,6 +251,10 @@ async fn login(
     if !verify_password(&payload.password, &hashed) {
         return Err(AppError::unauthorized("Invalid credentials"));
     }
+    let deactivated_at: Option<String> = row.try_get("deactivated_at")?;
+    if deactivated_at.is_some() {
+        return Err(AppError::forbidden("Account is deactivated"));
+    }
 
     let id: String = row.try_get("id")?;
     let user_id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid user id"))?;
@@ -142,24 +266,70 @@ async fn login(
 }
 
 #[derive(Debug, Deserialize)]
-struct OidcCallbackQuery {
-    code: String,
+pub(crate) struct OidcCallbackQuery {
+    pub(crate) code: String,
 }
 
 async fn oidc_callback(
     State(state): State<AppState>,
+    audit_context: AuditContext,
     Query(query): Query<OidcCallbackQuery>,
 ) -> Result<Json<crate::models::TokenResponse>, AppError> {
-    let provider = state
-        .oidc
-        .as_ref()
-        .ok_or_else(|| AppError::bad_request("OpenID Connect not configured"))?;
+    complete_oidc_login(&state, &audit_context, None, &query.code).await
+}
+
+async fn oidc_provider_callback(
+    State(state): State<AppState>,
+    audit_context: AuditContext,
+    Path(provider): Path<String>,
+    Query(query): Query<OidcCallbackQuery>,
+) -> Result<Json<crate::models::TokenResponse>, AppError> {
+    complete_oidc_login(&state, &audit_context, Some(&provider), &query.code).await
+}
 
-    let id_token = provider.exchange_code(&query.code).await?;
+async fn complete_oidc_login(
+    state: &AppState,
+    audit_context: &AuditContext,
+    provider: Option<&str>,
+    code: &str,
+) -> Result<Json<crate::models::TokenResponse>, AppError> {
+    let provider = state.oidc_provider(provider)?;
+
+    let id_token = provider.exchange_code(code).await?;
     let claims = provider.validate_id_token(&id_token).await?;
     let user = db::find_user_by_external_identity(&state.pool, provider.issuer(), &claims.subject)
         .await?
         .ok_or_else(|| AppError::unauthorized("No account linked to external identity"))?;
+    if user.deactivated {
+        return Err(AppError::forbidden("Account is deactivated"));
+    }
+
+    // Memberships granted through the provider are recorded with its name as
+    // the source, so manual memberships and other providers' are left alone.
+    if let Some(memberships) = provider.organizations_for_groups(&claims.groups) {
+        let mut tx = state.pool.begin().await?;
+        if organizations::sync_memberships(&mut tx, user.id, provider.name(), &memberships).await? {
+            audit::record(
+                &mut tx,
+                audit_context,
+                AuditRecord {
+                    actor_id: Some(user.id),
+                    action: "organization.member_sync",
+                    target_type: "user",
+                    target_id: user.id.to_string(),
+                    before_status: None,
+                    after_status: None,
+                    details: Some(json!({
+                        "provider": provider.name(),
+                        "groups": claims.groups,
+                        "organizations": memberships,
+                    })),
+                },
+            )
+            .await?;
+        }
+        tx.commit().await?;
+    }
 
     let token = create_access_token(user.id, &state.config)?;
     Ok(Json(crate::models::TokenResponse {
@@ -168,20 +338,50 @@ async fn oidc_callback(
     }))
 }
 
-fn repository_routes() -> Router<AppState> {
-    Router::new().route("/", post(create_repository).get(list_repositories))
+fn repository_routes() -> ApiRouter {
+    ApiRouter::new("repositories")
+        .route(
+            create_repository,
+            Operation::post("/", "Register a repository")
+                .authenticated()
+                .body::<RepositoryCreate>()
+                .json::<RepositoryRead>(201, "Repository created"),
+        )
+        .route(
+            list_repositories,
+            Operation::get("/", "List repositories")
+                .authenticated()
+                .json::<Vec<RepositoryRead>>(200, "Repositories"),
+        )
 }
 
-fn environment_routes() -> Router<AppState> {
-    Router::new().route("/", post(create_environment))
+fn environment_routes() -> ApiRouter {
+    ApiRouter::new("environments")
+        .route(
+            create_environment,
+            Operation::post("/", "Create an environment")
+                .authenticated()
+                .body::<EnvironmentCreate>()
+                .json::<EnvironmentRead>(201, "Environment created"),
+        )
+        .route(
+            update_environment,
+            Operation::patch("/{environment_id}", "Update an environment")
+                .authenticated()
+                .path::<String>("environment_id")
+                .body::<EnvironmentUpdate>()
+                .json::<EnvironmentRead>(200, "Updated environment"),
+        )
 }
 
 async fn create_repository(
     State(state): State<AppState>,
-    CurrentUser(_user): CurrentUser,
+    CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
     Json(payload): Json<RepositoryCreate>,
 ) -> Result<(StatusCode, Json<RepositoryRead>), AppError> {
     let repository_id = Uuid::new_v4();
+    let mut tx = state.pool.begin().await?;
     let result = sqlx::query(
         r#"
         INSERT INTO repositories (id, name, git_url, default_branch)
@@ -192,11 +392,26 @@ async fn create_repository(
     .bind(&payload.name)
     .bind(&payload.git_url)
     .bind(&payload.default_branch)
-    .execute(&state.pool)
+    .execute(&mut *tx)
     .await;
 
     match result {
         Ok(_) => {
+            audit::record(
+                &mut tx,
+                &audit_context,
+                AuditRecord {
+                    actor_id: Some(user.id),
+                    action: "repository.create",
+                    target_type: "repository",
+                    target_id: repository_id.to_string(),
+                    before_status: None,
+                    after_status: None,
+                    details: Some(json!({ "git_url": payload.git_url })),
+                },
+            )
+            .await?;
+            tx.commit().await?;
             let repository = Repository {
                 id: repository_id,
                 name: payload.name,
@@ -239,7 +454,8 @@ async fn list_repositories(
 
 async fn create_environment(
     State(state): State<AppState>,
-    CurrentUser(_user): CurrentUser,
+    CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
     Json(payload): Json<EnvironmentCreate>,
 ) -> Result<(StatusCode, Json<EnvironmentRead>), AppError> {
     let EnvironmentCreate {
@@ -251,7 +467,9 @@ async fn create_environment(
         provider,
         owner,
         repo,
+        max_concurrency,
     } = payload;
+    validate_max_concurrency(max_concurrency)?;
 
     let repository = fetch_repository(&state.pool, repository_id).await?;
 
@@ -266,10 +484,11 @@ async fn create_environment(
         },
     };
 
+    let mut tx = state.pool.begin().await?;
     let result = sqlx::query(
         r#"
-        INSERT INTO environments (id, label, repository_id, branch, is_pinned, provider, owner, repo)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO environments (id, label, repository_id, branch, is_pinned, provider, owner, repo, max_concurrency)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
     )
     .bind(&id)
@@ -280,11 +499,30 @@ async fn create_environment(
     .bind(&provider)
     .bind(&owner)
     .bind(&repo)
-    .execute(&state.pool)
+    .bind(max_concurrency)
+    .execute(&mut *tx)
     .await;
 
     match result {
         Ok(_) => {
+            audit::record(
+                &mut tx,
+                &audit_context,
+                AuditRecord {
+                    actor_id: Some(user.id),
+                    action: "environment.create",
+                    target_type: "environment",
+                    target_id: id.clone(),
+                    before_status: None,
+                    after_status: None,
+                    details: Some(json!({
+                        "repository_id": repository_id,
+                        "max_concurrency": max_concurrency,
+                    })),
+                },
+            )
+            .await?;
+            tx.commit().await?;
             let environment = Environment {
                 id,
                 label,
@@ -294,6 +532,7 @@ async fn create_environment(
                 provider,
                 owner,
                 repo,
+                max_concurrency,
             };
             Ok((
                 StatusCode::CREATED,
@@ -307,13 +546,128 @@ async fn create_environment(
     }
 }
 
-fn task_routes() -> Router<AppState> {
-    Router::new()
-        .route("/", get(list_tasks).post(create_task))
-        .route("/{task_id}", get(get_task))
-        .route("/{task_id}/claim", post(claim_task))
-        .route("/{task_id}/attempts", post(create_attempt))
-        .route("/attempts/{attempt_id}/complete", post(complete_attempt))
+async fn update_environment(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    audit_context: AuditContext,
+    Path(environment_id): Path<String>,
+    Json(payload): Json<EnvironmentUpdate>,
+) -> Result<Json<EnvironmentRead>, AppError> {
+    let Some(max_concurrency) = payload.max_concurrency else {
+        let environment = fetch_environment(&state.pool, &environment_id).await?;
+        return Ok(Json(EnvironmentRead::from(environment)));
+    };
+    validate_max_concurrency(max_concurrency)?;
+
+    let mut tx = state.pool.begin().await?;
+    let result = sqlx::query("UPDATE environments SET max_concurrency = ? WHERE id = ?")
+        .bind(max_concurrency)
+        .bind(&environment_id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Environment not found"));
+    }
+
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(admin.id),
+            action: "environment.update",
+            target_type: "environment",
+            target_id: environment_id.clone(),
+            before_status: None,
+            after_status: None,
+            details: Some(json!({ "max_concurrency": max_concurrency })),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    let environment = fetch_environment(&state.pool, &environment_id).await?;
+    Ok(Json(EnvironmentRead::from(environment)))
+}
+
+fn validate_max_concurrency(value: Option<i64>) -> Result<(), AppError> {
+    match value {
+        Some(limit) if limit < 1 => {
+            Err(AppError::bad_request("max_concurrency must be at least 1"))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn task_routes() -> ApiRouter {
+    ApiRouter::new("tasks")
+        .route(
+            list_tasks,
+            Operation::get("/", "List tasks in scheduling order")
+                .authenticated()
+                .query::<TaskFilter>()
+                .json::<Vec<TaskListResponse>>(200, "Tasks"),
+        )
+        .route(
+            create_task,
+            Operation::post("/", "Create a task")
+                .authenticated()
+                .body::<TaskCreate>()
+                .json::<TaskDetail>(201, "Task created"),
+        )
+        .route(
+            get_task,
+            Operation::get("/{task_id}", "Get a task with its attempts")
+                .authenticated()
+                .path::<Uuid>("task_id")
+                .json::<TaskDetail>(200, "Task"),
+        )
+        .route(
+            claim_task,
+            Operation::post("/{task_id}/claim", "Claim a pending task")
+                .authenticated()
+                .path::<Uuid>("task_id")
+                .json::<ClaimResponse>(200, "Task claimed"),
+        )
+        .route(
+            apply_task,
+            Operation::post(
+                "/{task_id}/apply",
+                "Apply a reviewed task and release the tasks waiting on it",
+            )
+            .authenticated()
+            .path::<Uuid>("task_id")
+            .optional_body::<TaskApply>()
+            .json::<TaskRead>(200, "Task applied"),
+        )
+        .route(
+            create_attempt,
+            Operation::post("/{task_id}/attempts", "Start an attempt")
+                .authenticated()
+                .path::<Uuid>("task_id")
+                .body::<AttemptCreate>()
+                .json::<AttemptRead>(201, "Attempt created"),
+        )
+        .route(
+            complete_attempt,
+            Operation::post(
+                "/attempts/{attempt_id}/complete",
+                "Report the outcome of an attempt",
+            )
+            .authenticated()
+            .path::<Uuid>("attempt_id")
+            .body::<AttemptCompleteRequest>()
+            .json::<AttemptCompleteResponse>(200, "Attempt completed"),
+        )
+        .route(
+            release_attempt,
+            Operation::post(
+                "/attempts/{attempt_id}/release",
+                "Give an attempt up and requeue its task",
+            )
+            .authenticated()
+            .path::<Uuid>("attempt_id")
+            .empty(204, "Attempt released"),
+        )
 }
 
 async fn list_tasks(
@@ -321,25 +675,80 @@ async fn list_tasks(
     CurrentUser(_user): CurrentUser,
     Query(filter): Query<TaskFilter>,
 ) -> Result<Json<Vec<TaskListResponse>>, AppError> {
+    // The pending queue is ordered for workers: highest priority first, then
+    // round-robin across organizations and, within each organization, across
+    // task creators, so one team's or one user's batch cannot starve everyone
+    // else's tasks at the same priority. Creators in several organizations
+    // queue under the first one; creators in none queue on their own.
+    let queue_order = filter.status == Some(TaskStatus::Pending);
+
     let mut builder = QueryBuilder::<Sqlite>::new(
-        "SELECT id, title, description, repository_id, status, assignee_id, created_by, created_at, updated_at, environment_id FROM tasks",
+        "SELECT id, title, description, repository_id, status, assignee_id, created_by, created_at, updated_at, environment_id, retry_after, priority, best_of_n, branch, qa_mode, group_id, result_branch",
     );
+    if queue_order {
+        builder.push(
+            r#", ROW_NUMBER() OVER (PARTITION BY queue_owner, priority ORDER BY creator_rank, created_at) AS owner_rank
+            FROM (
+                SELECT *, ROW_NUMBER() OVER (PARTITION BY created_by, priority ORDER BY created_at) AS creator_rank
+                FROM (
+                    SELECT *, COALESCE(
+                        (SELECT MIN(organization_id) FROM organization_members WHERE user_id = tasks.created_by),
+                        created_by
+                    ) AS queue_owner
+                    FROM tasks"#,
+        );
+    } else {
+        builder.push(" FROM tasks");
+    }
 
+    let mut separator = " WHERE ";
     if let Some(status) = filter.status {
-        builder.push(" WHERE status = ");
+        builder.push(separator);
+        builder.push("status = ");
         builder.push_bind(status.as_str());
+        separator = " AND ";
+    }
+    if let Some(environment_id) = filter.environment_id {
+        builder.push(separator);
+        builder.push("environment_id = ");
+        builder.push_bind(environment_id);
+        separator = " AND ";
+    }
+    if let Some(group_id) = filter.group_id {
+        builder.push(separator);
+        builder.push("group_id = ");
+        builder.push_bind(group_id.to_string());
     }
 
-    builder.push(" ORDER BY updated_at DESC");
+    if queue_order {
+        builder.push(")) ORDER BY priority DESC, owner_rank, created_at");
+    } else {
+        builder.push(" ORDER BY updated_at DESC");
+    }
 
     let rows = builder.build().fetch_all(&state.pool).await?;
 
     let tasks = rows
         .into_iter()
         .map(row_to_task)
-        .collect::<Result<Vec<_>, _>>()?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut group_ids = tasks
+        .iter()
+        .filter_map(|task| task.group_id)
+        .collect::<Vec<_>>();
+    group_ids.sort();
+    group_ids.dedup();
+    let groups = task_groups::summaries(&state.pool, &group_ids).await?;
+
+    let tasks = tasks
         .into_iter()
-        .map(TaskListResponse::from)
+        .map(|task| {
+            let group = task.group_id.and_then(|id| groups.get(&id).cloned());
+            TaskListResponse {
+                group,
+                ..TaskListResponse::from(task)
+            }
+        })
         .collect();
 
     Ok(Json(tasks))
@@ -348,6 +757,7 @@ async fn list_tasks(
 async fn create_task(
     State(state): State<AppState>,
     CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
     Json(payload): Json<TaskCreate>,
 ) -> Result<(StatusCode, Json<TaskDetail>), AppError> {
     let repository_exists =
@@ -364,10 +774,11 @@ async fn create_task(
     let now = Utc::now();
     let now_str = format_datetime(now);
 
+    let mut tx = state.pool.begin().await?;
     sqlx::query(
         r#"
-        INSERT INTO tasks (id, title, description, repository_id, status, assignee_id, created_by, created_at, updated_at, environment_id)
-        VALUES (?, ?, ?, ?, ?, NULL, ?, ?, ?, ?)
+        INSERT INTO tasks (id, title, description, repository_id, status, assignee_id, created_by, created_at, updated_at, environment_id, priority)
+        VALUES (?, ?, ?, ?, ?, NULL, ?, ?, ?, ?, ?)
         "#,
     )
     .bind(task_id.to_string())
@@ -379,10 +790,24 @@ async fn create_task(
     .bind(now_str.clone())
     .bind(now_str.clone())
     .bind(Option::<String>::None)
-    .execute(&state.pool)
+    .bind(payload.priority)
+    .execute(&mut *tx)
     .await?;
 
-    let repository = fetch_repository(&state.pool, payload.repository_id).await?;
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(user.id),
+            action: "task.create",
+            target_type: "task",
+            target_id: task_id.to_string(),
+            before_status: None,
+            after_status: Some(TaskStatus::Pending.as_str()),
+            details: Some(json!({ "priority": payload.priority })),
+        },
+    )
+    .await?;
 
     let task = Task {
         id: task_id,
@@ -395,13 +820,26 @@ async fn create_task(
         created_at: now,
         updated_at: now,
         environment_id: None,
+        retry_after: None,
+        priority: payload.priority,
+        best_of_n: None,
+        branch: None,
+        qa_mode: false,
+        group_id: None,
+        result_branch: None,
     };
+    notify_webhooks(&mut tx, WebhookEvent::TaskCreated, &task, None).await?;
+    tx.commit().await?;
+    state.webhooks.wake();
+
+    let repository = fetch_repository(&state.pool, payload.repository_id).await?;
 
     Ok((
         StatusCode::CREATED,
         Json(TaskDetail::from_entities(
             task,
             Some(repository),
+            Vec::new(),
             Vec::<AttemptRead>::new(),
         )),
     ))
@@ -423,51 +861,290 @@ async fn get_task(
             artifacts::artifact_url(&state.artifacts, attempt.log_artifact_id.as_deref()).await?;
         attempt_reads.push(AttemptRead::from_attempt(attempt, diff_url, log_url));
     }
+    let inputs = fetch_task_inputs(&state, task.id).await?;
+    let depends_on = task_groups::prerequisites(&state.pool, task.id).await?;
+    let group = match task.group_id {
+        Some(group_id) => task_groups::summaries(&state.pool, &[group_id])
+            .await?
+            .remove(&group_id),
+        None => None,
+    };
 
-    Ok(Json(TaskDetail::from_entities(
-        task,
-        repository,
-        attempt_reads,
-    )))
+    Ok(Json(TaskDetail {
+        depends_on,
+        group,
+        ..TaskDetail::from_entities(task, repository, inputs, attempt_reads)
+    }))
 }
 
 async fn claim_task(
     State(state): State<AppState>,
     CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
     Path(task_id): Path<Uuid>,
 ) -> Result<Json<ClaimResponse>, AppError> {
     let mut task = fetch_task(&state.pool, task_id).await?;
     match task.status {
         TaskStatus::Pending | TaskStatus::Review => {}
+        TaskStatus::Blocked => {
+            return Err(AppError::conflict("Task is waiting on its prerequisites"));
+        }
+        TaskStatus::Failed => return Err(AppError::conflict("Task has failed")),
         _ => return Err(AppError::conflict("Task already claimed")),
     }
 
+    let now = Utc::now();
+    let previous_status = task.status;
     task.status = TaskStatus::Claimed;
     task.assignee_id = Some(user.id);
-    task.updated_at = Utc::now();
-
-    sqlx::query(
+    task.updated_at = now;
+    task.retry_after = None;
+
+    // The status, the retry backoff, the creator's quota and the
+    // environment's cap are checked by the claiming statement itself, so
+    // concurrent claims can neither take the same task, nor take it early, nor
+    // overrun a quota or push the environment past its cap.
+    let mut tx = state.pool.begin().await?;
+    let result = sqlx::query(&format!(
         r#"
         UPDATE tasks
-        SET assignee_id = ?, status = ?, updated_at = ?
-        WHERE id = ?
+        SET assignee_id = ?, status = ?, updated_at = ?, retry_after = NULL
+        WHERE id = ? AND status = ?
+          AND (retry_after IS NULL OR retry_after <= ?)
+          AND NOT {quota_exhausted}
+          AND NOT EXISTS (
+            SELECT 1
+            FROM environments e
+            WHERE e.id = tasks.environment_id
+              AND e.max_concurrency IS NOT NULL
+              AND (
+                SELECT COUNT(1)
+                FROM tasks active
+                WHERE active.environment_id = e.id AND active.status IN (?, ?)
+              ) >= e.max_concurrency
+          )
         "#,
-    )
+        quota_exhausted = usage::QUOTA_EXHAUSTED,
+    ))
     .bind(user.id.to_string())
     .bind(task.status.as_str())
     .bind(format_datetime(task.updated_at))
     .bind(task.id.to_string())
-    .execute(&state.pool)
+    .bind(previous_status.as_str())
+    .bind(format_datetime(now))
+    .bind(format_datetime(usage::period_start(now)))
+    .bind(TaskStatus::Claimed.as_str())
+    .bind(TaskStatus::Running.as_str())
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        drop(tx);
+        let current = fetch_task(&state.pool, task_id).await?;
+        if current.status != previous_status {
+            return Err(AppError::conflict("Task already claimed"));
+        }
+        if current
+            .retry_after
+            .is_some_and(|retry_after| retry_after > now)
+        {
+            return Err(AppError::conflict("Task is waiting to be retried"));
+        }
+        usage::ensure_within_quota(&state.pool, current.created_by, now).await?;
+        return Err(AppError::conflict("Environment concurrency limit reached"));
+    }
+
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(user.id),
+            action: "task.claim",
+            target_type: "task",
+            target_id: task.id.to_string(),
+            before_status: Some(previous_status.as_str()),
+            after_status: Some(task.status.as_str()),
+            details: None,
+        },
+    )
     .await?;
+    tx.commit().await?;
 
     Ok(Json(ClaimResponse {
         claim_expires_at: claim_expiration(30),
     }))
 }
 
+async fn apply_task(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
+    Path(task_id): Path<Uuid>,
+    payload: Option<Json<TaskApply>>,
+) -> Result<Json<TaskRead>, AppError> {
+    let Json(payload) = payload.unwrap_or_default();
+    let mut task = fetch_task(&state.pool, task_id).await?;
+    ensure_owner_or_admin(&user, task.created_by)?;
+    if task.status != TaskStatus::Review {
+        return Err(AppError::conflict("Task is not awaiting review"));
+    }
+
+    task.status = TaskStatus::Applied;
+    task.updated_at = Utc::now();
+    task.result_branch = payload.branch.or_else(|| task.branch.clone());
+
+    let mut tx = state.pool.begin().await?;
+    let result = sqlx::query(
+        r#"
+        UPDATE tasks SET status = ?, updated_at = ?, result_branch = ? WHERE id = ? AND status = ?
+        "#,
+    )
+    .bind(task.status.as_str())
+    .bind(format_datetime(task.updated_at))
+    .bind(&task.result_branch)
+    .bind(task.id.to_string())
+    .bind(TaskStatus::Review.as_str())
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::conflict("Task is not awaiting review"));
+    }
+
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(user.id),
+            action: "task.apply",
+            target_type: "task",
+            target_id: task.id.to_string(),
+            before_status: Some(TaskStatus::Review.as_str()),
+            after_status: Some(task.status.as_str()),
+            details: Some(json!({ "result_branch": task.result_branch })),
+        },
+    )
+    .await?;
+
+    for released in task_groups::release_dependents(&mut tx, task.id, task.updated_at).await? {
+        audit::record(
+            &mut tx,
+            &audit_context,
+            AuditRecord {
+                actor_id: Some(user.id),
+                action: "task.unblock",
+                target_type: "task",
+                target_id: released.id.to_string(),
+                before_status: Some(TaskStatus::Blocked.as_str()),
+                after_status: Some(TaskStatus::Pending.as_str()),
+                details: Some(json!({ "prerequisite": task.id, "branch": released.branch })),
+            },
+        )
+        .await?;
+    }
+    notify_webhooks(&mut tx, WebhookEvent::TaskApplied, &task, None).await?;
+    tx.commit().await?;
+    state.webhooks.wake();
+
+    Ok(Json(TaskRead::from(task)))
+}
+
+fn task_group_routes() -> ApiRouter {
+    ApiRouter::new("tasks")
+        .route(
+            create_task_group,
+            Operation::post(
+                "/",
+                "Create a group of tasks with ordered or DAG dependencies",
+            )
+            .authenticated()
+            .body::<TaskGroupCreate>()
+            .json::<TaskGroupRead>(201, "Task group created"),
+        )
+        .route(
+            get_task_group,
+            Operation::get("/{group_id}", "Get a task group with its status rollup")
+                .authenticated()
+                .path::<Uuid>("group_id")
+                .json::<TaskGroupRead>(200, "Task group"),
+        )
+}
+
+async fn create_task_group(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
+    Json(payload): Json<TaskGroupCreate>,
+) -> Result<(StatusCode, Json<TaskGroupRead>), AppError> {
+    fetch_repository(&state.pool, payload.repository_id).await?;
+    if let Some(environment_id) = payload.environment_id.as_deref() {
+        let environment = fetch_environment(&state.pool, environment_id).await?;
+        if environment.repository_id != payload.repository_id {
+            return Err(AppError::bad_request(
+                "Environment belongs to another repository",
+            ));
+        }
+    }
+    let ordered = payload.ordered;
+
+    let mut tx = state.pool.begin().await?;
+    let (group_id, tasks) = task_groups::create_group(&mut tx, user.id, payload).await?;
+
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(user.id),
+            action: "task_group.create",
+            target_type: "task_group",
+            target_id: group_id.to_string(),
+            before_status: None,
+            after_status: None,
+            details: Some(json!({
+                "ordered": ordered,
+                "task_ids": tasks.iter().map(|task| task.id).collect::<Vec<_>>(),
+            })),
+        },
+    )
+    .await?;
+    for task in &tasks {
+        audit::record(
+            &mut tx,
+            &audit_context,
+            AuditRecord {
+                actor_id: Some(user.id),
+                action: "task.create",
+                target_type: "task",
+                target_id: task.id.to_string(),
+                before_status: None,
+                after_status: Some(task.status.as_str()),
+                details: Some(json!({ "priority": task.priority, "group_id": group_id })),
+            },
+        )
+        .await?;
+    }
+    for task in &tasks {
+        notify_webhooks(&mut tx, WebhookEvent::TaskCreated, task, None).await?;
+    }
+    tx.commit().await?;
+    state.webhooks.wake();
+
+    let group = task_groups::get_group(&state.pool, group_id).await?;
+    Ok((StatusCode::CREATED, Json(group)))
+}
+
+async fn get_task_group(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(group_id): Path<Uuid>,
+) -> Result<Json<TaskGroupRead>, AppError> {
+    let group = task_groups::get_group(&state.pool, group_id).await?;
+    ensure_owner_or_admin(&user, group.created_by)?;
+    Ok(Json(group))
+}
+
 async fn create_attempt(
     State(state): State<AppState>,
     CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
     Path(task_id): Path<Uuid>,
     Json(_payload): Json<crate::models::AttemptCreate>,
 ) -> Result<(StatusCode, Json<AttemptRead>), AppError> {
@@ -476,6 +1153,7 @@ async fn create_attempt(
         return Err(AppError::forbidden("Task must be claimed"));
     }
 
+    let previous_status = task.status;
     task.status = TaskStatus::Running;
     task.updated_at = Utc::now();
 
@@ -483,6 +1161,7 @@ async fn create_attempt(
     let now = Utc::now();
     let now_str = format_datetime(now);
 
+    let mut tx = state.pool.begin().await?;
     sqlx::query(
         r#"
         INSERT INTO task_attempts (id, task_id, created_by, status, diff_artifact_id, log_artifact_id, created_at, updated_at)
@@ -495,7 +1174,7 @@ async fn create_attempt(
     .bind(AttemptStatus::Running.as_str())
     .bind(now_str.clone())
     .bind(now_str.clone())
-    .execute(&state.pool)
+    .execute(&mut *tx)
     .await?;
 
     sqlx::query(
@@ -506,8 +1185,24 @@ async fn create_attempt(
     .bind(task.status.as_str())
     .bind(format_datetime(task.updated_at))
     .bind(task.id.to_string())
-    .execute(&state.pool)
+    .execute(&mut *tx)
+    .await?;
+
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(user.id),
+            action: "attempt.create",
+            target_type: "attempt",
+            target_id: attempt_id.to_string(),
+            before_status: None,
+            after_status: Some(AttemptStatus::Running.as_str()),
+            details: Some(task_transition(task.id, previous_status, task.status)),
+        },
+    )
     .await?;
+    tx.commit().await?;
 
     let attempt = TaskAttempt {
         id: attempt_id,
@@ -516,6 +1211,8 @@ async fn create_attempt(
         status: AttemptStatus::Running,
         diff_artifact_id: None,
         log_artifact_id: None,
+        failure_category: None,
+        usage: None,
         created_at: now,
         updated_at: now,
     };
@@ -529,6 +1226,7 @@ async fn create_attempt(
 async fn complete_attempt(
     State(state): State<AppState>,
     CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
     Path(attempt_id): Path<Uuid>,
     Json(payload): Json<AttemptCompleteRequest>,
 ) -> Result<Json<AttemptCompleteResponse>, AppError> {
@@ -538,16 +1236,12 @@ async fn complete_attempt(
     if task.assignee_id != Some(user.id) {
         return Err(AppError::forbidden("Not assigned to task"));
     }
-
-    if let Some(diff) = payload.diff.as_ref() {
-        attempt.diff_artifact_id =
-            Some(artifacts::store_text_artifact(&state.artifacts, diff, "diff").await?);
-    }
-    if let Some(log) = payload.log.as_ref() {
-        attempt.log_artifact_id =
-            Some(artifacts::store_text_artifact(&state.artifacts, log, "log").await?);
+    if attempt.status != AttemptStatus::Running {
+        return Err(AppError::conflict("Attempt is not running"));
     }
 
+    let previous_attempt_status = attempt.status;
+    let previous_task_status = task.status;
     attempt.status = payload.status;
     attempt.updated_at = Utc::now();
     task.updated_at = attempt.updated_at;
@@ -555,40 +1249,169 @@ async fn complete_attempt(
     match attempt.status {
         AttemptStatus::Succeeded => {
             task.status = TaskStatus::Review;
+            task.retry_after = None;
         }
         AttemptStatus::Failed => {
-            task.status = TaskStatus::Pending;
+            // Older supervisors do not classify failures; treat them as
+            // infrastructure problems so the task keeps being retried.
+            let category = payload.failure_category.unwrap_or(FailureCategory::Infra);
+            attempt.failure_category = Some(category);
+
+            // Only failures spend the attempt budget: released attempts and
+            // earlier successes sent back from review do not.
+            let failures = count_failed_attempts(&state.pool, task.id).await? + 1;
+            let retry = &state.config.task_retry;
+            if category.is_retryable() && failures < i64::from(retry.max_attempts) {
+                let backoff = retry.backoff_for(failures as u32);
+                task.status = TaskStatus::Pending;
+                task.retry_after = Some(
+                    task.updated_at
+                        + chrono::Duration::from_std(backoff)
+                            .unwrap_or_else(|_| chrono::Duration::zero()),
+                );
+            } else {
+                task.status = TaskStatus::Failed;
+                task.retry_after = None;
+            }
+            task.assignee_id = None;
+        }
+        AttemptStatus::Queued | AttemptStatus::Running | AttemptStatus::Cancelled => {
+            return Err(AppError::bad_request(
+                "Attempts complete as succeeded or failed; release them to give them up",
+            ));
         }
-        _ => {}
     }
 
-    sqlx::query(
+    if let Some(diff) = payload.diff.as_ref() {
+        attempt.diff_artifact_id =
+            Some(artifacts::store_text_artifact(&state.artifacts, diff, "diff").await?);
+    }
+    if let Some(log) = payload.log.as_ref() {
+        attempt.log_artifact_id =
+            Some(artifacts::store_text_artifact(&state.artifacts, log, "log").await?);
+    }
+
+    if let Some(usage) = payload.usage {
+        usage::validate(&usage)?;
+        attempt.usage = Some(usage);
+    }
+
+    let mut tx = state.pool.begin().await?;
+    // A concurrent release or completion may have settled the attempt since
+    // it was read.
+    let updated = sqlx::query(
         r#"
         UPDATE task_attempts
-        SET status = ?, diff_artifact_id = ?, log_artifact_id = ?, updated_at = ?
-        WHERE id = ?
+        SET status = ?, diff_artifact_id = ?, log_artifact_id = ?, failure_category = ?,
+            input_tokens = ?, cached_input_tokens = ?, output_tokens = ?, reasoning_output_tokens = ?,
+            wall_time_ms = ?, cpu_time_ms = ?, updated_at = ?
+        WHERE id = ? AND status = ?
         "#,
     )
     .bind(attempt.status.as_str())
     .bind(&attempt.diff_artifact_id)
     .bind(&attempt.log_artifact_id)
+    .bind(attempt.failure_category.map(|category| category.as_str()))
+    .bind(attempt.usage.map(|usage| usage.input_tokens))
+    .bind(attempt.usage.map(|usage| usage.cached_input_tokens))
+    .bind(attempt.usage.map(|usage| usage.output_tokens))
+    .bind(attempt.usage.map(|usage| usage.reasoning_output_tokens))
+    .bind(attempt.usage.map(|usage| usage.wall_time_ms))
+    .bind(attempt.usage.and_then(|usage| usage.cpu_time_ms))
     .bind(format_datetime(attempt.updated_at))
     .bind(attempt.id.to_string())
-    .execute(&state.pool)
+    .bind(AttemptStatus::Running.as_str())
+    .execute(&mut *tx)
     .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::conflict("Attempt is not running"));
+    }
 
     sqlx::query(
         r#"
-        UPDATE tasks SET status = ?, updated_at = ? WHERE id = ?
+        UPDATE tasks SET status = ?, assignee_id = ?, retry_after = ?, updated_at = ? WHERE id = ?
         "#,
     )
     .bind(task.status.as_str())
+    .bind(task.assignee_id.map(|id| id.to_string()))
+    .bind(task.retry_after.map(format_datetime))
     .bind(format_datetime(task.updated_at))
     .bind(task.id.to_string())
-    .execute(&state.pool)
+    .execute(&mut *tx)
+    .await?;
+
+    let mut details = task_transition(task.id, previous_task_status, task.status);
+    if let Some(category) = attempt.failure_category {
+        details["failure_category"] = json!(category);
+    }
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(user.id),
+            action: "attempt.complete",
+            target_type: "attempt",
+            target_id: attempt.id.to_string(),
+            before_status: Some(previous_attempt_status.as_str()),
+            after_status: Some(attempt.status.as_str()),
+            details: Some(details),
+        },
+    )
     .await?;
+    if task.status == TaskStatus::Failed {
+        for dependent in task_groups::fail_dependents(&mut tx, task.id, task.updated_at).await? {
+            audit::record(
+                &mut tx,
+                &audit_context,
+                AuditRecord {
+                    actor_id: Some(user.id),
+                    action: "task.fail",
+                    target_type: "task",
+                    target_id: dependent.to_string(),
+                    before_status: Some(TaskStatus::Blocked.as_str()),
+                    after_status: Some(TaskStatus::Failed.as_str()),
+                    details: Some(json!({ "prerequisite": task.id })),
+                },
+            )
+            .await?;
+        }
+    }
+
+    let attempt_data = json!({
+        "id": attempt.id,
+        "status": attempt.status,
+        "failure_category": attempt.failure_category,
+    });
+    match attempt.status {
+        AttemptStatus::Succeeded => {
+            notify_webhooks(
+                &mut tx,
+                WebhookEvent::AttemptSucceeded,
+                &task,
+                Some(attempt_data),
+            )
+            .await?;
+        }
+        AttemptStatus::Failed => {
+            notify_webhooks(
+                &mut tx,
+                WebhookEvent::AttemptFailed,
+                &task,
+                Some(attempt_data),
+            )
+            .await?;
+        }
+        _ => {}
+    }
+    if task.status == TaskStatus::Review {
+        notify_webhooks(&mut tx, WebhookEvent::ReviewRequested, &task, None).await?;
+    }
+    tx.commit().await?;
+    state.webhooks.wake();
 
     Ok(Json(AttemptCompleteResponse {
+        task_status: task.status,
+        retry_after: task.retry_after,
         status: attempt.status,
         diff_url: artifacts::artifact_url(&state.artifacts, attempt.diff_artifact_id.as_deref())
             .await?,
@@ -597,54 +1420,1051 @@ async fn complete_attempt(
     }))
 }
 
-fn artifact_routes() -> Router<AppState> {
-    Router::new().route("/{artifact_id}", get(get_artifact))
-}
+async fn release_attempt(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
+    Path(attempt_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let attempt = fetch_attempt(&state.pool, attempt_id).await?;
+    let task = fetch_task(&state.pool, attempt.task_id).await?;
 
-fn codex_routes() -> Router<AppState> {
-    Router::new()
-        .route("/environments", get(list_codex_environments))
-        .route(
-            "/environments/by-repo/{provider}/{owner}/{repo}",
-            get(list_codex_environments_by_repo),
-        )
-        .route("/tasks", post(create_codex_task))
-}
+    if task.assignee_id != Some(user.id) {
+        return Err(AppError::forbidden("Not assigned to task"));
+    }
+    if attempt.status != AttemptStatus::Running {
+        return Err(AppError::conflict("Attempt is not running"));
+    }
 
-async fn get_artifact(
-    State(state): State<AppState>,
-    Path(artifact_id): Path<String>,
-) -> Result<(StatusCode, String), AppError> {
-    let content = artifacts::read_artifact(&state.artifacts, &artifact_id).await?;
-    Ok((StatusCode::OK, content))
-}
+    let now = format_datetime(Utc::now());
 
-async fn list_codex_environments(
-    State(state): State<AppState>,
-    CurrentUser(_user): CurrentUser,
-) -> Result<Json<Vec<CodexEnvironmentSummary>>, AppError> {
-    let rows = sqlx::query(
+    let mut tx = state.pool.begin().await?;
+    // A concurrent completion may have settled the attempt since it was read;
+    // releasing it now would send a finished task back to the queue.
+    let updated = sqlx::query(
         r#"
-        SELECT id, label, repository_id, branch, is_pinned, provider, owner, repo,
-               (SELECT COUNT(1) FROM tasks WHERE environment_id = environments.id) AS task_count
-        FROM environments
-        ORDER BY is_pinned DESC, COALESCE(label, id)
+        UPDATE task_attempts SET status = ?, updated_at = ? WHERE id = ? AND status = ?
         "#,
     )
-    .fetch_all(&state.pool)
+    .bind(AttemptStatus::Cancelled.as_str())
+    .bind(&now)
+    .bind(attempt.id.to_string())
+    .bind(AttemptStatus::Running.as_str())
+    .execute(&mut *tx)
     .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::conflict("Attempt is not running"));
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE tasks SET status = ?, assignee_id = NULL, retry_after = NULL, updated_at = ? WHERE id = ?
+        "#,
+    )
+    .bind(TaskStatus::Pending.as_str())
+    .bind(&now)
+    .bind(task.id.to_string())
+    .execute(&mut *tx)
+    .await?;
+
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(user.id),
+            action: "attempt.release",
+            target_type: "attempt",
+            target_id: attempt.id.to_string(),
+            before_status: Some(attempt.status.as_str()),
+            after_status: Some(AttemptStatus::Cancelled.as_str()),
+            details: Some(task_transition(task.id, task.status, TaskStatus::Pending)),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Queues webhook deliveries for `event` in `tx`. Callers wake the dispatcher
+/// once the transaction is committed.
+async fn notify_webhooks(
+    tx: &mut Transaction<'_, Sqlite>,
+    event: WebhookEvent,
+    task: &Task,
+    attempt: Option<serde_json::Value>,
+) -> Result<(), AppError> {
+    let mut data = json!({ "task": TaskRead::from(task.clone()) });
+    if let Some(attempt) = attempt {
+        data["attempt"] = attempt;
+    }
+    webhooks::enqueue(tx, event, task.repository_id, task.created_by, data).await
+}
+
+fn task_transition(task_id: Uuid, before: TaskStatus, after: TaskStatus) -> serde_json::Value {
+    json!({
+        "task_id": task_id,
+        "task_status_before": before,
+        "task_status_after": after,
+    })
+}
+
+fn artifact_routes() -> ApiRouter {
+    ApiRouter::new("artifacts").route(
+        get_artifact,
+        Operation::get("/{artifact_id}", "Download an artifact")
+            .authenticated()
+            .path::<String>("artifact_id")
+            .content(200, "Artifact contents", "application/octet-stream"),
+    )
+}
+
+fn codex_routes() -> ApiRouter {
+    ApiRouter::new("codex")
+        .route(
+            list_codex_environments,
+            Operation::get("/environments", "List environments for the Codex CLI")
+                .authenticated()
+                .json::<Vec<CodexEnvironmentSummary>>(200, "Environments"),
+        )
+        .route(
+            list_codex_environments_by_repo,
+            Operation::get(
+                "/environments/by-repo/{provider}/{owner}/{repo}",
+                "List environments for a repository",
+            )
+            .authenticated()
+            .path::<String>("provider")
+            .path::<String>("owner")
+            .path::<String>("repo")
+            .json::<Vec<CodexEnvironmentSummary>>(200, "Environments"),
+        )
+        .route(
+            create_codex_task,
+            Operation::post("/tasks", "Create a task from the Codex CLI")
+                .authenticated()
+                .body::<CodexTaskCreate>()
+                .json::<CodexTaskCreateResponse>(201, "Task created"),
+        )
+}
+
+fn webhook_routes() -> ApiRouter {
+    ApiRouter::new("webhooks")
+        .route(
+            create_webhook,
+            Operation::post("/", "Subscribe to task events")
+                .authenticated()
+                .body::<WebhookCreate>()
+                .json::<WebhookRead>(201, "Subscription created"),
+        )
+        .route(
+            list_webhooks,
+            Operation::get("/", "List subscriptions")
+                .authenticated()
+                .json::<Vec<WebhookRead>>(200, "Subscriptions"),
+        )
+        .route(
+            delete_webhook,
+            Operation::delete("/{webhook_id}", "Delete a subscription")
+                .authenticated()
+                .path::<Uuid>("webhook_id")
+                .empty(204, "Subscription deleted"),
+        )
+        .route(
+            list_webhook_deliveries,
+            Operation::get(
+                "/{webhook_id}/deliveries",
+                "List deliveries for a subscription",
+            )
+            .authenticated()
+            .path::<Uuid>("webhook_id")
+            .json::<Vec<WebhookDeliveryRead>>(200, "Deliveries"),
+        )
+        .route(
+            redeliver_webhook,
+            Operation::post(
+                "/deliveries/{delivery_id}/redeliver",
+                "Queue a delivery again",
+            )
+            .authenticated()
+            .path::<Uuid>("delivery_id")
+            .json::<WebhookDeliveryRead>(202, "Redelivery queued"),
+        )
+}
+
+async fn create_webhook(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
+    Json(payload): Json<WebhookCreate>,
+) -> Result<(StatusCode, Json<WebhookRead>), AppError> {
+    if let Some(repository_id) = payload.repository_id {
+        fetch_repository(&state.pool, repository_id).await?;
+    }
+    let mut tx = state.pool.begin().await?;
+    match payload.organization_id {
+        Some(organization_id) => {
+            organizations::ensure_exists(&mut tx, organization_id).await?;
+            if !is_admin(&user)
+                && !organizations::is_member(&mut tx, organization_id, user.id).await?
+            {
+                return Err(AppError::forbidden(
+                    "Only members of the organization can subscribe to its events",
+                ));
+            }
+        }
+        None if payload.repository_id.is_none() && !is_admin(&user) => {
+            return Err(AppError::forbidden(
+                "Only admins can subscribe to events for every task",
+            ));
+        }
+        None => {}
+    }
+    let webhook =
+        webhooks::create_subscription(&mut tx, &state.config.webhooks, user.id, payload).await?;
+
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(user.id),
+            action: "webhook.create",
+            target_type: "webhook",
+            target_id: webhook.id.to_string(),
+            before_status: None,
+            after_status: None,
+            details: Some(json!({
+                "url": webhook.url,
+                "events": webhook.events,
+                "repository_id": webhook.repository_id,
+                "organization_id": webhook.organization_id,
+            })),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+/// Lists the caller's webhooks, or every webhook for admins.
+async fn list_webhooks(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+) -> Result<Json<Vec<WebhookRead>>, AppError> {
+    let created_by = (!is_admin(&user)).then_some(user.id);
+    Ok(Json(
+        webhooks::list_subscriptions(&state.pool, created_by).await?,
+    ))
+}
+
+async fn delete_webhook(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
+    Path(webhook_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let webhook = webhooks::fetch_subscription(&state.pool, webhook_id).await?;
+    ensure_owner_or_admin(&user, webhook.created_by)?;
+    let mut tx = state.pool.begin().await?;
+    webhooks::deactivate_subscription(&mut tx, webhook_id).await?;
+
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(user.id),
+            action: "webhook.delete",
+            target_type: "webhook",
+            target_id: webhook_id.to_string(),
+            before_status: None,
+            after_status: None,
+            details: None,
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_webhook_deliveries(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(webhook_id): Path<Uuid>,
+) -> Result<Json<Vec<WebhookDeliveryRead>>, AppError> {
+    let webhook = webhooks::fetch_subscription(&state.pool, webhook_id).await?;
+    ensure_owner_or_admin(&user, webhook.created_by)?;
+    Ok(Json(
+        webhooks::list_deliveries(&state.pool, webhook_id).await?,
+    ))
+}
+
+async fn redeliver_webhook(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
+    Path(delivery_id): Path<Uuid>,
+) -> Result<(StatusCode, Json<WebhookDeliveryRead>), AppError> {
+    let webhook = webhooks::fetch_delivery_subscription(&state.pool, delivery_id).await?;
+    ensure_owner_or_admin(&user, webhook.created_by)?;
+    let mut tx = state.pool.begin().await?;
+    let delivery = webhooks::redeliver(&mut tx, delivery_id).await?;
+
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(user.id),
+            action: "webhook.redeliver",
+            target_type: "webhook_delivery",
+            target_id: delivery.id.to_string(),
+            before_status: None,
+            after_status: Some(delivery.status.as_str()),
+            details: Some(json!({ "redelivery_of": delivery_id })),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+    state.webhooks.wake();
+
+    Ok((StatusCode::ACCEPTED, Json(delivery)))
+}
+
+fn schedule_routes() -> ApiRouter {
+    ApiRouter::new("schedules")
+        .route(
+            create_schedule,
+            Operation::post("/", "Create a schedule")
+                .authenticated()
+                .body::<ScheduleCreate>()
+                .json::<ScheduleRead>(201, "Schedule created"),
+        )
+        .route(
+            list_schedules,
+            Operation::get("/", "List schedules")
+                .authenticated()
+                .json::<Vec<ScheduleRead>>(200, "Schedules"),
+        )
+        .route(
+            pause_schedule,
+            Operation::post("/{schedule_id}/pause", "Pause a schedule")
+                .authenticated()
+                .path::<Uuid>("schedule_id")
+                .json::<ScheduleRead>(200, "Paused schedule"),
+        )
+        .route(
+            resume_schedule,
+            Operation::post("/{schedule_id}/resume", "Resume a schedule")
+                .authenticated()
+                .path::<Uuid>("schedule_id")
+                .json::<ScheduleRead>(200, "Resumed schedule"),
+        )
+        .route(
+            trigger_schedule,
+            Operation::post("/{schedule_id}/trigger", "Run a schedule now")
+                .authenticated()
+                .path::<Uuid>("schedule_id")
+                .json::<TaskRead>(201, "Task created"),
+        )
+}
+
+async fn create_schedule(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
+    Json(payload): Json<ScheduleCreate>,
+) -> Result<(StatusCode, Json<ScheduleRead>), AppError> {
+    fetch_environment(&state.pool, &payload.environment_id).await?;
+    let mut tx = state.pool.begin().await?;
+    let schedule = scheduler::create_schedule(&mut tx, user.id, payload).await?;
+
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(user.id),
+            action: "schedule.create",
+            target_type: "schedule",
+            target_id: schedule.id.to_string(),
+            before_status: None,
+            after_status: None,
+            details: Some(json!({
+                "cron": schedule.cron,
+                "environment_id": schedule.environment_id,
+            })),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+    state.scheduler.wake();
+
+    Ok((StatusCode::CREATED, Json(schedule)))
+}
+
+async fn list_schedules(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+) -> Result<Json<Vec<ScheduleRead>>, AppError> {
+    let created_by = (!is_admin(&user)).then_some(user.id);
+    Ok(Json(
+        scheduler::list_schedules(&state.pool, created_by).await?,
+    ))
+}
+
+async fn pause_schedule(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
+    Path(schedule_id): Path<Uuid>,
+) -> Result<Json<ScheduleRead>, AppError> {
+    let schedule = scheduler::fetch_schedule(&state.pool, schedule_id).await?;
+    ensure_owner_or_admin(&user, schedule.created_by)?;
+    let mut tx = state.pool.begin().await?;
+    let schedule = scheduler::set_paused(&mut tx, schedule, true).await?;
+    record_schedule_change(
+        &mut tx,
+        &audit_context,
+        user.id,
+        "schedule.pause",
+        &schedule,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(Json(schedule))
+}
+
+async fn resume_schedule(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
+    Path(schedule_id): Path<Uuid>,
+) -> Result<Json<ScheduleRead>, AppError> {
+    let schedule = scheduler::fetch_schedule(&state.pool, schedule_id).await?;
+    ensure_owner_or_admin(&user, schedule.created_by)?;
+    let mut tx = state.pool.begin().await?;
+    let schedule = scheduler::set_paused(&mut tx, schedule, false).await?;
+    record_schedule_change(
+        &mut tx,
+        &audit_context,
+        user.id,
+        "schedule.resume",
+        &schedule,
+    )
+    .await?;
+    tx.commit().await?;
+    state.scheduler.wake();
+    Ok(Json(schedule))
+}
+
+/// Runs a schedule now without moving its next regular run.
+async fn trigger_schedule(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
+    Path(schedule_id): Path<Uuid>,
+) -> Result<(StatusCode, Json<TaskRead>), AppError> {
+    let schedule = scheduler::fetch_schedule(&state.pool, schedule_id).await?;
+    ensure_owner_or_admin(&user, schedule.created_by)?;
+    let owner_active =
+        sqlx::query_scalar::<_, i64>("SELECT 1 FROM users WHERE id = ? AND deactivated_at IS NULL")
+            .bind(schedule.created_by.to_string())
+            .fetch_optional(&state.pool)
+            .await?;
+    if owner_active.is_none() {
+        return Err(AppError::conflict("Schedule owner has been deactivated"));
+    }
+    let task = scheduler::materialize(
+        &state.pool,
+        &state.webhooks,
+        &schedule,
+        Some(user.id),
+        &audit_context,
+    )
+    .await?
+    .ok_or_else(|| AppError::conflict("Previous scheduled task is still in progress"))?;
+    Ok((StatusCode::CREATED, Json(TaskRead::from(task))))
+}
+
+async fn record_schedule_change(
+    tx: &mut Transaction<'_, Sqlite>,
+    audit_context: &AuditContext,
+    actor_id: Uuid,
+    action: &'static str,
+    schedule: &ScheduleRead,
+) -> Result<(), AppError> {
+    audit::record(
+        tx,
+        audit_context,
+        AuditRecord {
+            actor_id: Some(actor_id),
+            action,
+            target_type: "schedule",
+            target_id: schedule.id.to_string(),
+            before_status: None,
+            after_status: None,
+            details: Some(json!({ "next_run_at": schedule.next_run_at })),
+        },
+    )
+    .await
+}
+
+fn admin_routes() -> ApiRouter {
+    ApiRouter::new("admin")
+        .route(
+            list_audit_log,
+            Operation::get("/audit", "Query the audit log")
+                .authenticated()
+                .query::<AuditFilter>()
+                .json::<Vec<AuditLogEntry>>(200, "Audit log entries"),
+        )
+        .route(
+            export_audit_log,
+            Operation::get("/audit/export", "Export the audit log")
+                .authenticated()
+                .query::<AuditFilter>()
+                .content(200, "One audit log entry per line", "application/x-ndjson"),
+        )
+        .route(
+            usage_report,
+            Operation::get("/usage", "Report token and time usage")
+                .authenticated()
+                .query::<UsageQuery>()
+                .json::<Vec<UsageReportRow>>(200, "Usage totals per group"),
+        )
+        .route(
+            list_quotas,
+            Operation::get("/quotas", "List monthly token quotas")
+                .authenticated()
+                .json::<Vec<QuotaRead>>(200, "Quotas"),
+        )
+        .route(
+            update_quota,
+            Operation::put(
+                "/quotas/{organization_id}",
+                "Set or clear an organization's monthly token quota",
+            )
+            .authenticated()
+            .path::<Uuid>("organization_id")
+            .body::<QuotaUpdate>()
+            .empty(204, "Quota updated"),
+        )
+        .route(
+            create_organization,
+            Operation::post("/organizations", "Create an organization")
+                .authenticated()
+                .body::<OrganizationCreate>()
+                .json::<OrganizationRead>(201, "Organization created"),
+        )
+        .route(
+            list_organizations,
+            Operation::get("/organizations", "List organizations")
+                .authenticated()
+                .json::<Vec<OrganizationRead>>(200, "Organizations with their members"),
+        )
+        .route(
+            set_organization_member,
+            Operation::put(
+                "/organizations/{organization_id}/members/{user_id}",
+                "Add a user to an organization or change their role",
+            )
+            .authenticated()
+            .path::<Uuid>("organization_id")
+            .path::<Uuid>("user_id")
+            .body::<OrganizationMemberUpdate>()
+            .empty(204, "Membership updated"),
+        )
+        .route(
+            remove_organization_member,
+            Operation::delete(
+                "/organizations/{organization_id}/members/{user_id}",
+                "Remove a user from an organization",
+            )
+            .authenticated()
+            .path::<Uuid>("organization_id")
+            .path::<Uuid>("user_id")
+            .empty(204, "Membership removed"),
+        )
+        .route(
+            list_users,
+            Operation::get("/users", "List users")
+                .authenticated()
+                .json::<Vec<UserRead>>(200, "Users with their external identities"),
+        )
+        .route(
+            update_user,
+            Operation::patch(
+                "/users/{user_id}",
+                "Change a user's role or deactivate them",
+            )
+            .authenticated()
+            .path::<Uuid>("user_id")
+            .body::<UserUpdate>()
+            .json::<UserRead>(200, "Updated user"),
+        )
+        .route(
+            reset_password,
+            Operation::post("/users/{user_id}/password", "Reset a user's password")
+                .authenticated()
+                .path::<Uuid>("user_id")
+                .body::<PasswordReset>()
+                .empty(204, "Password reset"),
+        )
+        .route(
+            unlink_identity,
+            Operation::delete(
+                "/users/{user_id}/identities/{identity_id}",
+                "Unlink an external identity",
+            )
+            .authenticated()
+            .path::<Uuid>("user_id")
+            .path::<i64>("identity_id")
+            .empty(204, "Identity unlinked"),
+        )
+        .route(
+            create_invite,
+            Operation::post("/invites", "Invite a user")
+                .authenticated()
+                .body::<InviteCreate>()
+                .json::<InviteRead>(201, "Invite created with its token"),
+        )
+        .route(
+            list_invites,
+            Operation::get("/invites", "List invites")
+                .authenticated()
+                .json::<Vec<InviteRead>>(200, "Invites"),
+        )
+        .route(
+            revoke_invite,
+            Operation::delete("/invites/{invite_id}", "Revoke an unused invite")
+                .authenticated()
+                .path::<Uuid>("invite_id")
+                .empty(204, "Invite revoked"),
+        )
+}
+
+const AUDIT_PAGE_DEFAULT: u32 = 100;
+const AUDIT_PAGE_MAX: u32 = 1000;
+
+async fn list_audit_log(
+    State(state): State<AppState>,
+    AdminUser(_admin): AdminUser,
+    Query(filter): Query<AuditFilter>,
+) -> Result<Json<Vec<AuditLogEntry>>, AppError> {
+    let limit = filter
+        .limit
+        .unwrap_or(AUDIT_PAGE_DEFAULT)
+        .clamp(1, AUDIT_PAGE_MAX);
+    let entries = audit::query(&state.pool, &filter, Some(limit)).await?;
+    Ok(Json(entries))
+}
+
+/// Returns the whole (filtered) log as JSON Lines for SIEM ingestion.
+async fn export_audit_log(
+    State(state): State<AppState>,
+    AdminUser(_admin): AdminUser,
+    Query(filter): Query<AuditFilter>,
+) -> Result<([(header::HeaderName, &'static str); 1], String), AppError> {
+    let entries = audit::query(&state.pool, &filter, filter.limit).await?;
+    let mut body = String::new();
+    for entry in entries {
+        let line = serde_json::to_string(&entry)
+            .map_err(|err| AppError::bad_request(format!("Failed to encode entry: {err}")))?;
+        body.push_str(&line);
+        body.push('\n');
+    }
+    Ok(([(header::CONTENT_TYPE, "application/x-ndjson")], body))
+}
+
+async fn usage_report(
+    State(state): State<AppState>,
+    AdminUser(_admin): AdminUser,
+    Query(query): Query<UsageQuery>,
+) -> Result<Json<Vec<UsageReportRow>>, AppError> {
+    Ok(Json(usage::report(&state.pool, &query).await?))
+}
+
+async fn list_quotas(
+    State(state): State<AppState>,
+    AdminUser(_admin): AdminUser,
+) -> Result<Json<Vec<QuotaRead>>, AppError> {
+    Ok(Json(usage::list_quotas(&state.pool, Utc::now()).await?))
+}
+
+async fn update_quota(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    audit_context: AuditContext,
+    Path(organization_id): Path<Uuid>,
+    Json(payload): Json<QuotaUpdate>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = state.pool.begin().await?;
+    organizations::ensure_exists(&mut tx, organization_id).await?;
+    usage::set_quota(&mut tx, organization_id, payload.monthly_token_limit).await?;
+
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(admin.id),
+            action: "quota.update",
+            target_type: "organization",
+            target_id: organization_id.to_string(),
+            before_status: None,
+            after_status: None,
+            details: Some(json!({ "monthly_token_limit": payload.monthly_token_limit })),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn create_organization(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    audit_context: AuditContext,
+    Json(payload): Json<OrganizationCreate>,
+) -> Result<(StatusCode, Json<OrganizationRead>), AppError> {
+    let mut tx = state.pool.begin().await?;
+    let organization = organizations::create_organization(&mut tx, payload).await?;
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(admin.id),
+            action: "organization.create",
+            target_type: "organization",
+            target_id: organization.id.to_string(),
+            before_status: None,
+            after_status: None,
+            details: Some(json!({ "name": organization.name })),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(organization)))
+}
+
+async fn list_organizations(
+    State(state): State<AppState>,
+    AdminUser(_admin): AdminUser,
+) -> Result<Json<Vec<OrganizationRead>>, AppError> {
+    Ok(Json(organizations::list_organizations(&state.pool).await?))
+}
+
+async fn set_organization_member(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    audit_context: AuditContext,
+    Path((organization_id, user_id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<OrganizationMemberUpdate>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = state.pool.begin().await?;
+    organizations::set_member(
+        &mut tx,
+        organization_id,
+        user_id,
+        payload.role,
+        organizations::MANUAL_SOURCE,
+    )
+    .await?;
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(admin.id),
+            action: "organization.member_set",
+            target_type: "organization",
+            target_id: organization_id.to_string(),
+            before_status: None,
+            after_status: None,
+            details: Some(json!({ "user_id": user_id, "role": payload.role })),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn remove_organization_member(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    audit_context: AuditContext,
+    Path((organization_id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = state.pool.begin().await?;
+    organizations::remove_member(
+        &mut tx,
+        organization_id,
+        user_id,
+        organizations::MANUAL_SOURCE,
+    )
+    .await?;
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(admin.id),
+            action: "organization.member_remove",
+            target_type: "organization",
+            target_id: organization_id.to_string(),
+            before_status: None,
+            after_status: None,
+            details: Some(json!({ "user_id": user_id })),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_users(
+    State(state): State<AppState>,
+    AdminUser(_admin): AdminUser,
+) -> Result<Json<Vec<UserRead>>, AppError> {
+    Ok(Json(users::list_users(&state.pool).await?))
+}
+
+async fn update_user(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    audit_context: AuditContext,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<UserUpdate>,
+) -> Result<Json<UserRead>, AppError> {
+    // Keep at least the acting admin able to undo the change.
+    if user_id == admin.id
+        && (payload.active == Some(false) || payload.role == Some(UserRole::Member))
+    {
+        return Err(AppError::bad_request(
+            "Admins cannot deactivate or demote themselves",
+        ));
+    }
+    let mut tx = state.pool.begin().await?;
+    let before = users::get_user(&mut tx, user_id).await?;
+    let after = users::update_user(&mut tx, user_id, &payload).await?;
+
+    let status = |user: &UserRead| {
+        if user.deactivated_at.is_some() {
+            "deactivated"
+        } else {
+            "active"
+        }
+    };
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(admin.id),
+            action: "user.update",
+            target_type: "user",
+            target_id: user_id.to_string(),
+            before_status: Some(status(&before)),
+            after_status: Some(status(&after)),
+            details: Some(json!({ "role_before": before.role, "role_after": after.role })),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(Json(after))
+}
+
+async fn reset_password(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    audit_context: AuditContext,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<PasswordReset>,
+) -> Result<StatusCode, AppError> {
+    if payload.password.is_empty() {
+        return Err(AppError::bad_request("Password must not be empty"));
+    }
+    let password_hash = hash_password(&payload.password)?;
+    let mut tx = state.pool.begin().await?;
+    users::set_password_hash(&mut tx, user_id, &password_hash).await?;
+
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(admin.id),
+            action: "user.password_reset",
+            target_type: "user",
+            target_id: user_id.to_string(),
+            before_status: None,
+            after_status: None,
+            details: None,
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn unlink_identity(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    audit_context: AuditContext,
+    Path((user_id, identity_id)): Path<(Uuid, i64)>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = state.pool.begin().await?;
+    let identity = users::unlink_identity(&mut tx, user_id, identity_id).await?;
+
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(admin.id),
+            action: "user.identity_unlink",
+            target_type: "user",
+            target_id: user_id.to_string(),
+            before_status: None,
+            after_status: None,
+            details: Some(json!({
+                "issuer": identity.issuer,
+                "subject": identity.subject,
+            })),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn create_invite(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    audit_context: AuditContext,
+    Json(payload): Json<InviteCreate>,
+) -> Result<(StatusCode, Json<InviteRead>), AppError> {
+    let mut tx = state.pool.begin().await?;
+    let invite = users::create_invite(&mut tx, &state.config, admin.id, payload).await?;
+
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(admin.id),
+            action: "invite.create",
+            target_type: "invite",
+            target_id: invite.id.to_string(),
+            before_status: None,
+            after_status: None,
+            details: Some(json!({
+                "email": invite.email,
+                "role": invite.role,
+                "expires_at": invite.expires_at,
+            })),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(invite)))
+}
+
+async fn list_invites(
+    State(state): State<AppState>,
+    AdminUser(_admin): AdminUser,
+) -> Result<Json<Vec<InviteRead>>, AppError> {
+    Ok(Json(users::list_invites(&state.pool).await?))
+}
+
+async fn revoke_invite(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    audit_context: AuditContext,
+    Path(invite_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = state.pool.begin().await?;
+    users::revoke_invite(&mut tx, invite_id).await?;
+
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(admin.id),
+            action: "invite.revoke",
+            target_type: "invite",
+            target_id: invite_id.to_string(),
+            before_status: None,
+            after_status: None,
+            details: None,
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_artifact(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(artifact_id): Path<String>,
+) -> Result<([(header::HeaderName, &'static str); 1], Vec<u8>), AppError> {
+    // Task inputs are private to the task's creator and the worker it is
+    // assigned to.
+    let input = sqlx::query(
+        r#"
+        SELECT tasks.created_by, tasks.assignee_id
+        FROM task_inputs
+        JOIN tasks ON tasks.id = task_inputs.task_id
+        WHERE task_inputs.artifact_id = ?
+        "#,
+    )
+    .bind(&artifact_id)
+    .fetch_optional(&state.pool)
+    .await?;
+    if let Some(input) = input {
+        let created_by: String = input.try_get("created_by")?;
+        let assignee_id: Option<String> = input.try_get("assignee_id")?;
+        let user_id = user.id.to_string();
+        if created_by != user_id && assignee_id.as_deref() != Some(&user_id) && !is_admin(&user) {
+            return Err(AppError::forbidden("Not allowed to read this artifact"));
+        }
+    }
+    let content = state.artifacts.read_bytes(&artifact_id).await?;
+    Ok((
+        [(header::CONTENT_TYPE, artifacts::content_type(&artifact_id))],
+        content,
+    ))
+}
+
+async fn list_codex_environments(
+    State(state): State<AppState>,
+    CurrentUser(_user): CurrentUser,
+) -> Result<Json<Vec<CodexEnvironmentSummary>>, AppError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, label, repository_id, branch, is_pinned, provider, owner, repo, max_concurrency,
+               (SELECT COUNT(1) FROM tasks WHERE environment_id = environments.id) AS task_count
+        FROM environments
+        ORDER BY is_pinned DESC, COALESCE(label, id)
+        "#,
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    let environments = rows
+        .into_iter()
+        .map(row_to_environment_with_count)
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .map(|(environment, count)| codex_environment_summary(environment, Some(count)))
+        .collect();
+
+    Ok(Json(environments))
+}
 
-    let environments = rows
-        .into_iter()
-        .map(row_to_environment_with_count)
-        .collect::<Result<Vec<_>, _>>()?
-        .into_iter()
-        .map(|(environment, count)| codex_environment_summary(environment, Some(count)))
-        .collect();
-
-    Ok(Json(environments))
-}
-
 async fn list_codex_environments_by_repo(
     State(state): State<AppState>,
     CurrentUser(_user): CurrentUser,
@@ -656,7 +2476,7 @@ async fn list_codex_environments_by_repo(
 
     let rows = sqlx::query(
         r#"
-        SELECT id, label, repository_id, branch, is_pinned, provider, owner, repo,
+        SELECT id, label, repository_id, branch, is_pinned, provider, owner, repo, max_concurrency,
                (SELECT COUNT(1) FROM tasks WHERE environment_id = environments.id) AS task_count
         FROM environments
         WHERE provider = ? AND owner = ? AND repo = ?
@@ -683,6 +2503,7 @@ async fn list_codex_environments_by_repo(
 async fn create_codex_task(
     State(state): State<AppState>,
     CurrentUser(user): CurrentUser,
+    audit_context: AuditContext,
     Json(payload): Json<CodexTaskCreate>,
 ) -> Result<(StatusCode, Json<CodexTaskCreateResponse>), AppError> {
     let CodexTaskCreate {
@@ -693,16 +2514,35 @@ async fn create_codex_task(
 
     let environment = fetch_environment(&state.pool, &new_task.environment_id).await?;
     let prompt = extract_codex_prompt(&input_items)?;
+    let attachments = extract_codex_attachments(&input_items)?;
     let title = derive_codex_title(&prompt);
+    let metadata = metadata.unwrap_or_default();
+    let branch = new_task
+        .branch
+        .map(|branch| branch.trim().to_string())
+        .filter(|branch| !branch.is_empty());
+
+    // Attachments are written to storage before the transaction so it is not
+    // held open across file I/O.
+    let mut artifact_ids = Vec::with_capacity(attachments.len());
+    for attachment in &attachments {
+        artifact_ids.push(
+            state
+                .artifacts
+                .store_bytes(&attachment.content, attachment.suffix())
+                .await?,
+        );
+    }
 
     let task_id = Uuid::new_v4();
     let now = Utc::now();
     let now_str = format_datetime(now);
 
+    let mut tx = state.pool.begin().await?;
     sqlx::query(
         r#"
-        INSERT INTO tasks (id, title, description, repository_id, status, assignee_id, created_by, created_at, updated_at, environment_id)
-        VALUES (?, ?, ?, ?, ?, NULL, ?, ?, ?, ?)
+        INSERT INTO tasks (id, title, description, repository_id, status, assignee_id, created_by, created_at, updated_at, environment_id, priority, best_of_n, branch, qa_mode)
+        VALUES (?, ?, ?, ?, ?, NULL, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
     )
     .bind(task_id.to_string())
@@ -714,15 +2554,64 @@ async fn create_codex_task(
     .bind(now_str.clone())
     .bind(now_str.clone())
     .bind(Some(environment.id.clone()))
-    .execute(&state.pool)
+    .bind(metadata.priority.unwrap_or_default())
+    .bind(metadata.best_of_n.map(|best_of_n| best_of_n as i64))
+    .bind(&branch)
+    .bind(new_task.run_environment_in_qa_mode)
+    .execute(&mut *tx)
+    .await?;
+
+    for (position, (attachment, artifact_id)) in attachments.iter().zip(&artifact_ids).enumerate() {
+        sqlx::query(
+            r#"
+            INSERT INTO task_inputs (id, task_id, position, kind, name, mime_type, artifact_id, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(task_id.to_string())
+        .bind(position as i64)
+        .bind(attachment.kind.as_str())
+        .bind(&attachment.name)
+        .bind(&attachment.mime_type)
+        .bind(artifact_id)
+        .bind(now_str.clone())
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    audit::record(
+        &mut tx,
+        &audit_context,
+        AuditRecord {
+            actor_id: Some(user.id),
+            action: "task.create",
+            target_type: "task",
+            target_id: task_id.to_string(),
+            before_status: None,
+            after_status: Some(TaskStatus::Pending.as_str()),
+            details: Some(json!({
+                "environment_id": environment.id,
+                "priority": metadata.priority.unwrap_or_default(),
+                "branch": branch,
+                "qa_mode": new_task.run_environment_in_qa_mode,
+                "inputs": attachments.len(),
+            })),
+        },
+    )
     .await?;
 
+    let task = fetch_task(&mut *tx, task_id).await?;
+    notify_webhooks(&mut tx, WebhookEvent::TaskCreated, &task, None).await?;
+    tx.commit().await?;
+    state.webhooks.wake();
+
     let response = CodexTaskCreateResponse {
         task: crate::models::CodexCreatedTask {
             id: task_id,
             status: TaskStatus::Pending,
             environment_id: Some(environment.id),
-            attempt_total: metadata.and_then(|meta| meta.best_of_n),
+            attempt_total: metadata.best_of_n,
         },
     };
 
@@ -745,16 +2634,19 @@ async fn fetch_repository(pool: &SqlitePool, id: Uuid) -> Result<Repository, App
     row_to_repository(row)
 }
 
-async fn fetch_task(pool: &SqlitePool, id: Uuid) -> Result<Task, AppError> {
+async fn fetch_task<'c, E>(executor: E, id: Uuid) -> Result<Task, AppError>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
     let row = sqlx::query(
         r#"
-        SELECT id, title, description, repository_id, status, assignee_id, created_by, created_at, updated_at, environment_id
+        SELECT id, title, description, repository_id, status, assignee_id, created_by, created_at, updated_at, environment_id, retry_after, priority, best_of_n, branch, qa_mode, group_id, result_branch
         FROM tasks
         WHERE id = ?
         "#,
     )
     .bind(id.to_string())
-    .fetch_optional(pool)
+    .fetch_optional(executor)
     .await?;
 
     let row = row.ok_or_else(|| AppError::not_found("Task not found"))?;
@@ -764,7 +2656,7 @@ async fn fetch_task(pool: &SqlitePool, id: Uuid) -> Result<Task, AppError> {
 async fn fetch_attempt(pool: &SqlitePool, id: Uuid) -> Result<TaskAttempt, AppError> {
     let row = sqlx::query(
         r#"
-        SELECT id, task_id, created_by, status, diff_artifact_id, log_artifact_id, created_at, updated_at
+        SELECT id, task_id, created_by, status, diff_artifact_id, log_artifact_id, failure_category, input_tokens, cached_input_tokens, output_tokens, reasoning_output_tokens, wall_time_ms, cpu_time_ms, created_at, updated_at
         FROM task_attempts
         WHERE id = ?
         "#,
@@ -780,7 +2672,7 @@ async fn fetch_attempt(pool: &SqlitePool, id: Uuid) -> Result<TaskAttempt, AppEr
 async fn fetch_attempts(pool: &SqlitePool, task_id: Uuid) -> Result<Vec<TaskAttempt>, AppError> {
     let rows = sqlx::query(
         r#"
-        SELECT id, task_id, created_by, status, diff_artifact_id, log_artifact_id, created_at, updated_at
+        SELECT id, task_id, created_by, status, diff_artifact_id, log_artifact_id, failure_category, input_tokens, cached_input_tokens, output_tokens, reasoning_output_tokens, wall_time_ms, cpu_time_ms, created_at, updated_at
         FROM task_attempts
         WHERE task_id = ?
         ORDER BY created_at DESC
@@ -793,6 +2685,17 @@ async fn fetch_attempts(pool: &SqlitePool, task_id: Uuid) -> Result<Vec<TaskAtte
     rows.into_iter().map(row_to_attempt).collect()
 }
 
+async fn count_failed_attempts(pool: &SqlitePool, task_id: Uuid) -> Result<i64, AppError> {
+    let count = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(1) FROM task_attempts WHERE task_id = ? AND status = ?",
+    )
+    .bind(task_id.to_string())
+    .bind(AttemptStatus::Failed.as_str())
+    .fetch_one(pool)
+    .await?;
+    Ok(count)
+}
+
 fn row_to_repository(row: SqliteRow) -> Result<Repository, AppError> {
     let id: String = row.try_get("id")?;
     let name: String = row.try_get("name")?;
@@ -817,6 +2720,12 @@ fn row_to_task(row: SqliteRow) -> Result<Task, AppError> {
     let created_at: String = row.try_get("created_at")?;
     let updated_at: String = row.try_get("updated_at")?;
     let environment_id: Option<String> = row.try_get("environment_id")?;
+    let retry_after: Option<String> = row.try_get("retry_after")?;
+    let priority: i64 = row.try_get("priority")?;
+    let best_of_n: Option<i64> = row.try_get("best_of_n")?;
+    let branch: Option<String> = row.try_get("branch")?;
+    let qa_mode: i64 = row.try_get("qa_mode")?;
+    let group_id: Option<String> = row.try_get("group_id")?;
 
     Ok(Task {
         id: parse_uuid(&id, "task id")?,
@@ -829,6 +2738,13 @@ fn row_to_task(row: SqliteRow) -> Result<Task, AppError> {
         created_at: parse_datetime(&created_at)?,
         updated_at: parse_datetime(&updated_at)?,
         environment_id,
+        retry_after: retry_after.as_deref().map(parse_datetime).transpose()?,
+        priority,
+        best_of_n,
+        branch,
+        qa_mode: qa_mode != 0,
+        group_id: parse_optional_uuid(group_id, "group id")?,
+        result_branch: row.try_get("result_branch")?,
     })
 }
 
@@ -839,6 +2755,8 @@ fn row_to_attempt(row: SqliteRow) -> Result<TaskAttempt, AppError> {
     let status: String = row.try_get("status")?;
     let diff_artifact_id: Option<String> = row.try_get("diff_artifact_id")?;
     let log_artifact_id: Option<String> = row.try_get("log_artifact_id")?;
+    let failure_category: Option<String> = row.try_get("failure_category")?;
+    let input_tokens: Option<i64> = row.try_get("input_tokens")?;
     let created_at: String = row.try_get("created_at")?;
     let updated_at: String = row.try_get("updated_at")?;
 
@@ -849,6 +2767,29 @@ fn row_to_attempt(row: SqliteRow) -> Result<TaskAttempt, AppError> {
         status: AttemptStatus::from_str(&status)?,
         diff_artifact_id,
         log_artifact_id,
+        failure_category: failure_category
+            .as_deref()
+            .map(FailureCategory::from_str)
+            .transpose()?,
+        usage: match input_tokens {
+            Some(input_tokens) => Some(AttemptUsage {
+                input_tokens,
+                cached_input_tokens: row
+                    .try_get::<Option<i64>, _>("cached_input_tokens")?
+                    .unwrap_or_default(),
+                output_tokens: row
+                    .try_get::<Option<i64>, _>("output_tokens")?
+                    .unwrap_or_default(),
+                reasoning_output_tokens: row
+                    .try_get::<Option<i64>, _>("reasoning_output_tokens")?
+                    .unwrap_or_default(),
+                wall_time_ms: row
+                    .try_get::<Option<i64>, _>("wall_time_ms")?
+                    .unwrap_or_default(),
+                cpu_time_ms: row.try_get("cpu_time_ms")?,
+            }),
+            None => None,
+        },
         created_at: parse_datetime(&created_at)?,
         updated_at: parse_datetime(&updated_at)?,
     })
@@ -863,6 +2804,7 @@ fn row_to_environment(row: SqliteRow) -> Result<Environment, AppError> {
     let provider: Option<String> = row.try_get("provider")?;
     let owner: Option<String> = row.try_get("owner")?;
     let repo: Option<String> = row.try_get("repo")?;
+    let max_concurrency: Option<i64> = row.try_get("max_concurrency")?;
 
     Ok(Environment {
         id,
@@ -873,6 +2815,7 @@ fn row_to_environment(row: SqliteRow) -> Result<Environment, AppError> {
         provider,
         owner,
         repo,
+        max_concurrency,
     })
 }
 
@@ -897,7 +2840,7 @@ fn codex_environment_summary(
 async fn fetch_environment(pool: &SqlitePool, id: &str) -> Result<Environment, AppError> {
     let row = sqlx::query(
         r#"
-        SELECT id, label, repository_id, branch, is_pinned, provider, owner, repo
+        SELECT id, label, repository_id, branch, is_pinned, provider, owner, repo, max_concurrency
         FROM environments
         WHERE id = ?
         "#,
@@ -977,6 +2920,132 @@ fn extract_codex_prompt(items: &[CodexInputItem]) -> Result<String, AppError> {
     }
 }
 
+/// An image or file taken from the Codex input items, decoded and ready to be
+/// stored as an artifact.
+struct CodexAttachment {
+    kind: TaskInputKind,
+    name: Option<String>,
+    mime_type: String,
+    content: Vec<u8>,
+}
+
+impl CodexAttachment {
+    fn suffix(&self) -> &'static str {
+        match self.mime_type.as_str() {
+            "image/png" => "png",
+            "image/jpeg" => "jpg",
+            "image/gif" => "gif",
+            "image/webp" => "webp",
+            "text/plain" | "text/markdown" => "txt",
+            _ => "bin",
+        }
+    }
+}
+
+fn extract_codex_attachments(items: &[CodexInputItem]) -> Result<Vec<CodexAttachment>, AppError> {
+    let mut attachments = Vec::new();
+    for item in items {
+        if item.kind != "message" {
+            continue;
+        }
+        if let Some(role) = &item.role {
+            if !role.eq_ignore_ascii_case("user") {
+                continue;
+            }
+        }
+        for fragment in &item.content {
+            let Some(content_type) = fragment.content_type.as_deref() else {
+                continue;
+            };
+            if content_type.eq_ignore_ascii_case("image")
+                || content_type.eq_ignore_ascii_case("input_image")
+            {
+                let image_url = fragment
+                    .image_url
+                    .as_deref()
+                    .ok_or_else(|| AppError::bad_request("Image input requires image_url"))?;
+                let (mime_type, content) = decode_data_url(image_url)?;
+                if !mime_type.starts_with("image/") {
+                    return Err(AppError::bad_request(format!(
+                        "Unsupported image type: {mime_type}"
+                    )));
+                }
+                attachments.push(CodexAttachment {
+                    kind: TaskInputKind::Image,
+                    name: fragment.file_name.clone(),
+                    mime_type,
+                    content,
+                });
+            } else if content_type.eq_ignore_ascii_case("file") {
+                let data = fragment
+                    .data
+                    .as_deref()
+                    .ok_or_else(|| AppError::bad_request("File input requires data"))?;
+                attachments.push(CodexAttachment {
+                    kind: TaskInputKind::File,
+                    name: fragment.file_name.clone(),
+                    mime_type: fragment
+                        .mime_type
+                        .clone()
+                        .unwrap_or_else(|| "application/octet-stream".to_string()),
+                    content: decode_base64(data)?,
+                });
+            }
+        }
+    }
+    Ok(attachments)
+}
+
+/// Decodes a base64 `data:` URL into its media type and bytes. Remote image
+/// URLs are rejected: the executor may not be able to reach them.
+fn decode_data_url(url: &str) -> Result<(String, Vec<u8>), AppError> {
+    let (header, data) = url
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(','))
+        .ok_or_else(|| AppError::bad_request("Image inputs must be base64 data URLs"))?;
+    let mime_type = header
+        .strip_suffix(";base64")
+        .ok_or_else(|| AppError::bad_request("Image inputs must be base64 data URLs"))?;
+    Ok((mime_type.to_ascii_lowercase(), decode_base64(data)?))
+}
+
+fn decode_base64(data: &str) -> Result<Vec<u8>, AppError> {
+    BASE64_STANDARD
+        .decode(data.trim())
+        .map_err(|_| AppError::bad_request("Invalid base64 input data"))
+}
+
+async fn fetch_task_inputs(
+    state: &AppState,
+    task_id: Uuid,
+) -> Result<Vec<TaskInputRead>, AppError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT kind, name, mime_type, artifact_id
+        FROM task_inputs
+        WHERE task_id = ?
+        ORDER BY position
+        "#,
+    )
+    .bind(task_id.to_string())
+    .fetch_all(&state.pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            let kind: String = row.try_get("kind")?;
+            let artifact_id: String = row.try_get("artifact_id")?;
+            Ok(TaskInputRead {
+                kind: TaskInputKind::from_str(&kind)?,
+                name: row.try_get("name")?,
+                mime_type: row.try_get("mime_type")?,
+                url: state.artifacts.artifact_url(&artifact_id),
+                artifact_id,
+            })
+        })
+        .collect()
+}
+
 fn parse_repository_coordinates(git_url: &str) -> Option<(String, String, String)> {
     let trimmed = git_url.trim();
     let mut normalized = trimmed.trim_end_matches('/').to_string();
@@ -0,0 +1,422 @@
+use std::sync::{Arc, Weak};
+
+use chrono::{DateTime, Utc};
+use croner::Cron;
+use serde_json::json;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, Sqlite, SqlitePool, Transaction};
+use tokio::sync::Notify;
+use tokio::time::sleep;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::audit::{self, AuditContext, AuditRecord};
+use crate::config::SchedulerSettings;
+use crate::error::AppError;
+use crate::models::{
+    format_datetime, parse_datetime, ScheduleCreate, ScheduleRead, Task, TaskRead, TaskStatus,
+    WebhookEvent,
+};
+use crate::webhooks::{self, WebhookDispatcher};
+
+const SCHEDULE_COLUMNS: &str = "id, name, cron, environment_id, prompt, best_of_n, priority, paused, next_run_at, last_run_at, last_task_id, created_by, created_at";
+
+/// Handle to the background task that materializes due schedules. The task
+/// stops once every handle has been dropped.
+#[derive(Clone)]
+pub struct TaskScheduler {
+    wake: Arc<Notify>,
+}
+
+impl TaskScheduler {
+    pub fn spawn(
+        pool: SqlitePool,
+        webhooks: WebhookDispatcher,
+        settings: SchedulerSettings,
+    ) -> Self {
+        let wake = Arc::new(Notify::new());
+        if settings.enabled {
+            tokio::spawn(run(pool, webhooks, settings, Arc::downgrade(&wake)));
+        }
+        Self { wake }
+    }
+
+    /// Asks the scheduler to look for due schedules now.
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+}
+
+async fn run(
+    pool: SqlitePool,
+    webhooks: WebhookDispatcher,
+    settings: SchedulerSettings,
+    wake: Weak<Notify>,
+) {
+    loop {
+        if let Err(err) = run_due(&pool, &webhooks).await {
+            warn!(error = %err, "Schedule pass failed");
+        }
+
+        let Some(wake) = wake.upgrade() else {
+            break;
+        };
+        tokio::select! {
+            _ = wake.notified() => {}
+            _ = sleep(settings.poll_interval) => {}
+        }
+    }
+}
+
+pub fn parse_cron(expression: &str) -> Result<Cron, AppError> {
+    Cron::new(expression)
+        .parse()
+        .map_err(|err| AppError::bad_request(format!("Invalid cron expression: {err}")))
+}
+
+/// Returns the first occurrence strictly after `after`.
+pub fn next_run(cron: &Cron, after: DateTime<Utc>) -> Result<DateTime<Utc>, AppError> {
+    cron.find_next_occurrence(&after, false)
+        .map_err(|err| AppError::bad_request(format!("Cron expression never fires: {err}")))
+}
+
+async fn run_due(pool: &SqlitePool, dispatcher: &WebhookDispatcher) -> Result<(), AppError> {
+    let now = Utc::now();
+    // Schedules of deactivated users stay due but do not run until their
+    // owner is reactivated.
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT {SCHEDULE_COLUMNS}
+        FROM task_schedules
+        WHERE paused = 0 AND next_run_at <= ?
+          AND created_by IN (SELECT id FROM users WHERE deactivated_at IS NULL)
+        ORDER BY next_run_at
+        "#
+    ))
+    .bind(format_datetime(now))
+    .fetch_all(pool)
+    .await?;
+
+    for row in rows {
+        let schedule = row_to_schedule(row)?;
+        if let Err(err) = run_schedule(pool, dispatcher, &schedule, now).await {
+            warn!(schedule_id = %schedule.id, error = %err, "Scheduled run failed");
+        }
+    }
+    Ok(())
+}
+
+async fn run_schedule(
+    pool: &SqlitePool,
+    dispatcher: &WebhookDispatcher,
+    schedule: &ScheduleRead,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    // Occurrences missed while the backend was down collapse into this run.
+    let next_run_at = next_run(&parse_cron(&schedule.cron)?, now)?;
+
+    // Advancing `next_run_at` claims the occurrence, so a concurrent pass on
+    // another replica cannot materialize it twice.
+    let claimed = sqlx::query(
+        r#"
+        UPDATE task_schedules
+        SET next_run_at = ?, updated_at = ?
+        WHERE id = ? AND next_run_at = ? AND paused = 0
+        "#,
+    )
+    .bind(format_datetime(next_run_at))
+    .bind(format_datetime(now))
+    .bind(schedule.id.to_string())
+    .bind(format_datetime(schedule.next_run_at))
+    .execute(pool)
+    .await?;
+    if claimed.rows_affected() == 0 {
+        return Ok(());
+    }
+
+    let context = AuditContext {
+        request_id: Uuid::new_v4().to_string(),
+        ip: None,
+    };
+    match materialize(pool, dispatcher, schedule, None, &context).await? {
+        Some(task) => {
+            info!(schedule_id = %schedule.id, task_id = %task.id, "Scheduled task created")
+        }
+        None => info!(
+            schedule_id = %schedule.id,
+            "Skipping scheduled run; previous task is still in progress"
+        ),
+    }
+    Ok(())
+}
+
+/// Creates a pending task from `schedule` and records it as the schedule's
+/// latest run, together with its audit record. `actor_id` is `None` when the
+/// scheduler loop fires the run.
+///
+/// Returns `None` without creating anything while the task of the previous run
+/// has not finished; pending tasks waiting for a retry count as unfinished.
+pub async fn materialize(
+    pool: &SqlitePool,
+    dispatcher: &WebhookDispatcher,
+    schedule: &ScheduleRead,
+    actor_id: Option<Uuid>,
+    context: &AuditContext,
+) -> Result<Option<Task>, AppError> {
+    let repository_id =
+        sqlx::query_scalar::<_, String>("SELECT repository_id FROM environments WHERE id = ?")
+            .bind(&schedule.environment_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| AppError::bad_request("Environment not found"))?;
+
+    let now = Utc::now();
+    let task = Task {
+        id: Uuid::new_v4(),
+        title: schedule.name.clone(),
+        description: Some(schedule.prompt.clone()),
+        repository_id: Uuid::parse_str(&repository_id)
+            .map_err(|_| AppError::bad_request("Invalid repository id"))?,
+        status: TaskStatus::Pending,
+        assignee_id: None,
+        created_by: schedule.created_by,
+        created_at: now,
+        updated_at: now,
+        environment_id: Some(schedule.environment_id.clone()),
+        retry_after: None,
+        priority: schedule.priority,
+        best_of_n: schedule.best_of_n,
+        branch: None,
+        qa_mode: false,
+        group_id: None,
+        result_branch: None,
+    };
+    let now_str = format_datetime(now);
+
+    let mut tx = pool.begin().await?;
+    // Checking the previous run with the first write of the transaction keeps
+    // two concurrent runs from both seeing it finished.
+    let claimed = sqlx::query(
+        r#"
+        UPDATE task_schedules
+        SET updated_at = ?
+        WHERE id = ? AND NOT EXISTS (
+            SELECT 1 FROM tasks
+            WHERE tasks.id = task_schedules.last_task_id AND tasks.status IN (?, ?, ?)
+        )
+        "#,
+    )
+    .bind(&now_str)
+    .bind(schedule.id.to_string())
+    .bind(TaskStatus::Pending.as_str())
+    .bind(TaskStatus::Claimed.as_str())
+    .bind(TaskStatus::Running.as_str())
+    .execute(&mut *tx)
+    .await?;
+    if claimed.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO tasks (id, title, description, repository_id, status, assignee_id, created_by, created_at, updated_at, environment_id, priority, best_of_n)
+        VALUES (?, ?, ?, ?, ?, NULL, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(task.id.to_string())
+    .bind(&task.title)
+    .bind(&task.description)
+    .bind(task.repository_id.to_string())
+    .bind(task.status.as_str())
+    .bind(task.created_by.to_string())
+    .bind(&now_str)
+    .bind(&now_str)
+    .bind(&task.environment_id)
+    .bind(task.priority)
+    .bind(task.best_of_n)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "UPDATE task_schedules SET last_run_at = ?, last_task_id = ?, updated_at = ? WHERE id = ?",
+    )
+    .bind(&now_str)
+    .bind(task.id.to_string())
+    .bind(&now_str)
+    .bind(schedule.id.to_string())
+    .execute(&mut *tx)
+    .await?;
+
+    audit::record(
+        &mut tx,
+        context,
+        AuditRecord {
+            actor_id,
+            action: "task.create",
+            target_type: "task",
+            target_id: task.id.to_string(),
+            before_status: None,
+            after_status: Some(TaskStatus::Pending.as_str()),
+            details: Some(json!({
+                "environment_id": schedule.environment_id,
+                "priority": schedule.priority,
+                "schedule_id": schedule.id,
+            })),
+        },
+    )
+    .await?;
+
+    webhooks::enqueue(
+        &mut tx,
+        WebhookEvent::TaskCreated,
+        task.repository_id,
+        task.created_by,
+        json!({ "task": TaskRead::from(task.clone()) }),
+    )
+    .await?;
+    tx.commit().await?;
+    dispatcher.wake();
+
+    Ok(Some(task))
+}
+
+pub async fn create_schedule(
+    tx: &mut Transaction<'_, Sqlite>,
+    created_by: Uuid,
+    payload: ScheduleCreate,
+) -> Result<ScheduleRead, AppError> {
+    if payload.name.trim().is_empty() {
+        return Err(AppError::bad_request("Schedule name is required"));
+    }
+    if payload.prompt.trim().is_empty() {
+        return Err(AppError::bad_request("Schedule prompt is required"));
+    }
+    if payload.best_of_n.is_some_and(|best_of_n| best_of_n < 1) {
+        return Err(AppError::bad_request("best_of_n must be at least 1"));
+    }
+    let now = Utc::now();
+    let next_run_at = next_run(&parse_cron(&payload.cron)?, now)?;
+    let id = Uuid::new_v4();
+
+    sqlx::query(
+        r#"
+        INSERT INTO task_schedules (id, name, cron, environment_id, prompt, best_of_n, priority, paused, next_run_at, created_by, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(id.to_string())
+    .bind(&payload.name)
+    .bind(&payload.cron)
+    .bind(&payload.environment_id)
+    .bind(&payload.prompt)
+    .bind(payload.best_of_n)
+    .bind(payload.priority)
+    .bind(format_datetime(next_run_at))
+    .bind(created_by.to_string())
+    .bind(format_datetime(now))
+    .bind(format_datetime(now))
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(ScheduleRead {
+        id,
+        name: payload.name,
+        cron: payload.cron,
+        environment_id: payload.environment_id,
+        prompt: payload.prompt,
+        best_of_n: payload.best_of_n,
+        priority: payload.priority,
+        paused: false,
+        next_run_at,
+        last_run_at: None,
+        last_task_id: None,
+        created_by,
+        created_at: now,
+    })
+}
+
+/// Lists schedules, only those created by `created_by` when given.
+pub async fn list_schedules(
+    pool: &SqlitePool,
+    created_by: Option<Uuid>,
+) -> Result<Vec<ScheduleRead>, AppError> {
+    let rows = sqlx::query(&format!(
+        "SELECT {SCHEDULE_COLUMNS} FROM task_schedules WHERE (? IS NULL OR created_by = ?) ORDER BY created_at"
+    ))
+    .bind(created_by.map(|id| id.to_string()))
+    .bind(created_by.map(|id| id.to_string()))
+    .fetch_all(pool)
+    .await?;
+    rows.into_iter().map(row_to_schedule).collect()
+}
+
+pub async fn fetch_schedule(pool: &SqlitePool, id: Uuid) -> Result<ScheduleRead, AppError> {
+    let row = sqlx::query(&format!(
+        "SELECT {SCHEDULE_COLUMNS} FROM task_schedules WHERE id = ?"
+    ))
+    .bind(id.to_string())
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("Schedule not found"))?;
+    row_to_schedule(row)
+}
+
+/// Pauses or resumes a schedule. Resuming recomputes the next run from now so
+/// occurrences missed while paused are not fired.
+pub async fn set_paused(
+    tx: &mut Transaction<'_, Sqlite>,
+    schedule: ScheduleRead,
+    paused: bool,
+) -> Result<ScheduleRead, AppError> {
+    let now = Utc::now();
+    let next_run_at = if paused {
+        schedule.next_run_at
+    } else {
+        next_run(&parse_cron(&schedule.cron)?, now)?
+    };
+
+    sqlx::query(
+        "UPDATE task_schedules SET paused = ?, next_run_at = ?, updated_at = ? WHERE id = ?",
+    )
+    .bind(paused)
+    .bind(format_datetime(next_run_at))
+    .bind(format_datetime(now))
+    .bind(schedule.id.to_string())
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(ScheduleRead {
+        paused,
+        next_run_at,
+        ..schedule
+    })
+}
+
+fn row_to_schedule(row: SqliteRow) -> Result<ScheduleRead, AppError> {
+    let id: String = row.try_get("id")?;
+    let paused: i64 = row.try_get("paused")?;
+    let next_run_at: String = row.try_get("next_run_at")?;
+    let last_run_at: Option<String> = row.try_get("last_run_at")?;
+    let last_task_id: Option<String> = row.try_get("last_task_id")?;
+    let created_by: String = row.try_get("created_by")?;
+    let created_at: String = row.try_get("created_at")?;
+
+    Ok(ScheduleRead {
+        id: Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid schedule id"))?,
+        name: row.try_get("name")?,
+        cron: row.try_get("cron")?,
+        environment_id: row.try_get("environment_id")?,
+        prompt: row.try_get("prompt")?,
+        best_of_n: row.try_get("best_of_n")?,
+        priority: row.try_get("priority")?,
+        paused: paused != 0,
+        next_run_at: parse_datetime(&next_run_at)?,
+        last_run_at: last_run_at.as_deref().map(parse_datetime).transpose()?,
+        last_task_id: last_task_id
+            .map(|id| Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid task id")))
+            .transpose()?,
+        created_by: Uuid::parse_str(&created_by)
+            .map_err(|_| AppError::bad_request("Invalid created by"))?,
+        created_at: parse_datetime(&created_at)?,
+    })
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
@@ -6,15 +7,17 @@ use axum::http::{header::AUTHORIZATION, request::Parts};
 use jsonwebtoken::{self, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
-use sqlx::{Row, SqlitePool};
+use serde_json::{Map, Value};
+use sqlx::SqlitePool;
 use std::future::Future;
 use uuid::Uuid;
 
 use tokio::sync::RwLock;
 
-use crate::config::{AppConfig, JwksCacheSettings, OidcConfig};
+use crate::config::{AppConfig, GroupOrganization, JwksCacheSettings, OidcConfig};
+use crate::db;
 use crate::error::AppError;
-use crate::models::User;
+use crate::models::{OrganizationRole, User, UserRole};
 use crate::state::AppState;
 
 #[derive(Debug, Clone)]
@@ -22,11 +25,14 @@ pub struct OidcClaims {
     pub subject: String,
     pub email: Option<String>,
     pub name: Option<String>,
+    /// Values of the provider's groups claim.
+    pub groups: Vec<String>,
 }
 
 #[derive(Clone)]
 pub struct OidcProvider {
     client: reqwest::Client,
+    name: String,
     issuer: String,
     client_id: String,
     client_secret: String,
@@ -34,6 +40,8 @@ pub struct OidcProvider {
     metadata: Arc<OidcMetadata>,
     jwks_cache: Arc<RwLock<Option<CachedJwks>>>,
     cache_settings: JwksCacheSettings,
+    groups_claim: String,
+    group_organizations: Arc<Vec<GroupOrganization>>,
 }
 
 #[derive(Clone, Debug)]
@@ -80,6 +88,8 @@ struct IdTokenClaims {
     name: Option<String>,
     #[allow(dead_code)]
     exp: usize,
+    #[serde(flatten)]
+    other: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -122,6 +132,7 @@ impl OidcProvider {
 
         Ok(Self {
             client,
+            name: config.name,
             issuer: config.issuer,
             client_id: config.client_id,
             client_secret: config.client_secret,
@@ -132,13 +143,42 @@ impl OidcProvider {
             }),
             jwks_cache: Arc::new(RwLock::new(None)),
             cache_settings: config.jwks_cache,
+            groups_claim: config.groups_claim,
+            group_organizations: Arc::new(config.group_organizations),
         })
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn issuer(&self) -> &str {
         &self.issuer
     }
 
+    /// The organizations `groups` map to by name, each with the highest role
+    /// any of the groups grants, or `None` when the provider maps no groups
+    /// and memberships are managed in Codex instead.
+    pub fn organizations_for_groups(
+        &self,
+        groups: &[String],
+    ) -> Option<BTreeMap<String, OrganizationRole>> {
+        if self.group_organizations.is_empty() {
+            return None;
+        }
+        let mut organizations = BTreeMap::new();
+        for mapping in self.group_organizations.iter() {
+            if !groups.contains(&mapping.group) {
+                continue;
+            }
+            let role = organizations
+                .entry(mapping.organization.clone())
+                .or_insert(mapping.role);
+            *role = (*role).max(mapping.role);
+        }
+        Some(organizations)
+    }
+
     pub async fn exchange_code(&self, code: &str) -> Result<String, AppError> {
         let body = TokenEndpointRequest {
             grant_type: "authorization_code",
@@ -199,10 +239,12 @@ impl OidcProvider {
             return Err(AppError::unauthorized("Invalid issuer"));
         }
 
+        let groups = claim_strings(&claims.other, &self.groups_claim);
         Ok(OidcClaims {
             subject: claims.sub,
             email: claims.email,
             name: claims.name,
+            groups,
         })
     }
 
@@ -278,6 +320,29 @@ impl JsonWebKey {
     }
 }
 
+/// Reads a string or array-of-strings claim, following dots into nested
+/// objects. Missing or differently typed claims yield no values.
+fn claim_strings(claims: &Map<String, Value>, path: &str) -> Vec<String> {
+    let mut segments = path.split('.');
+    let Some(mut value) = segments.next().and_then(|first| claims.get(first)) else {
+        return Vec::new();
+    };
+    for segment in segments {
+        match value.get(segment) {
+            Some(nested) => value = nested,
+            None => return Vec::new(),
+        }
+    }
+    match value {
+        Value::String(value) => vec![value.clone()],
+        Value::Array(values) => values
+            .iter()
+            .filter_map(|value| value.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
 fn deserialize_audience<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -360,7 +425,7 @@ pub fn decode_token(token: &str, config: &AppConfig) -> Result<Uuid, AppError> {
 pub async fn fetch_user(pool: &SqlitePool, user_id: Uuid) -> Result<User, AppError> {
     let row = sqlx::query(
         r#"
-        SELECT id, email, name
+        SELECT id, email, name, role, deactivated_at
         FROM users
         WHERE id = ?
         "#,
@@ -370,14 +435,23 @@ pub async fn fetch_user(pool: &SqlitePool, user_id: Uuid) -> Result<User, AppErr
     .await?;
 
     let row = row.ok_or_else(|| AppError::not_found("User not found"))?;
-    let id: String = row.try_get("id")?;
-    let email: String = row.try_get("email")?;
-    let name: Option<String> = row.try_get("name")?;
-    Ok(User {
-        id: Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid user id"))?,
-        email,
-        name,
-    })
+    Ok(db::row_to_user(&row)?)
+}
+
+/// Whether `user` has the admin role.
+pub fn is_admin(user: &User) -> bool {
+    user.role == UserRole::Admin
+}
+
+/// Lets only the owner of a resource, or an admin, act on it.
+pub fn ensure_owner_or_admin(user: &User, owner_id: Uuid) -> Result<(), AppError> {
+    if user.id == owner_id || is_admin(user) {
+        Ok(())
+    } else {
+        Err(AppError::forbidden(
+            "Only the owner or an admin may do this",
+        ))
+    }
 }
 
 pub struct CurrentUser(pub User);
@@ -408,6 +482,34 @@ where
 
             let user_id = decode_token(token, &state.config)?;
             let user = fetch_user(&state.pool, user_id).await?;
+            if user.deactivated {
+                return Err(AppError::unauthorized("Account is deactivated"));
+            }
+            Ok(Self(user))
+        }
+    }
+}
+
+/// An authenticated user with the admin role.
+pub struct AdminUser(pub User);
+
+impl<S> FromRequestParts<S> for AdminUser
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> impl Future<Output = Result<Self, Self::Rejection>> + Send {
+        let current_user = CurrentUser::from_request_parts(parts, state);
+        async move {
+            let CurrentUser(user) = current_user.await?;
+            if !is_admin(&user) {
+                return Err(AppError::forbidden("Admin privileges required"));
+            }
             Ok(Self(user))
         }
     }
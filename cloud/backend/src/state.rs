@@ -1,35 +1,70 @@
+use std::sync::Arc;
+
 use axum::extract::FromRef;
 use sqlx::SqlitePool;
 
 use crate::artifacts::ArtifactStore;
 use crate::config::AppConfig;
 use crate::error::AppError;
+use crate::ratelimit::RateLimiter;
+use crate::scheduler::TaskScheduler;
 use crate::security::OidcProvider;
+use crate::webhooks::WebhookDispatcher;
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: SqlitePool,
     pub config: AppConfig,
     pub artifacts: ArtifactStore,
-    pub oidc: Option<OidcProvider>,
+    pub oidc: Arc<Vec<OidcProvider>>,
+    pub webhooks: WebhookDispatcher,
+    pub scheduler: TaskScheduler,
+    pub rate_limiter: RateLimiter,
 }
 
 impl AppState {
     pub async fn new(pool: SqlitePool, config: AppConfig) -> Result<Self, AppError> {
         let artifacts = ArtifactStore::new(&config);
-        let oidc = if let Some(oidc_config) = &config.oidc {
-            Some(OidcProvider::discover(oidc_config.clone()).await?)
-        } else {
-            None
-        };
+        let mut oidc = Vec::with_capacity(config.oidc_providers.len());
+        for oidc_config in &config.oidc_providers {
+            oidc.push(OidcProvider::discover(oidc_config.clone()).await?);
+        }
+        let oidc = Arc::new(oidc);
+
+        let webhooks = WebhookDispatcher::spawn(pool.clone(), config.webhooks.clone())?;
+        let scheduler =
+            TaskScheduler::spawn(pool.clone(), webhooks.clone(), config.scheduler.clone());
+        let rate_limiter = RateLimiter::new(config.rate_limit.clone());
 
         Ok(Self {
             pool,
             artifacts,
             config,
             oidc,
+            webhooks,
+            scheduler,
+            rate_limiter,
         })
     }
+
+    /// The OIDC provider named `name`, or the only configured provider when
+    /// `name` is `None`.
+    pub fn oidc_provider(&self, name: Option<&str>) -> Result<&OidcProvider, AppError> {
+        match name {
+            Some(name) => self
+                .oidc
+                .iter()
+                .find(|provider| provider.name() == name)
+                .ok_or_else(|| AppError::not_found("Unknown OpenID Connect provider")),
+            None => match self.oidc.as_slice() {
+                [] => Err(AppError::bad_request("OpenID Connect not configured")),
+                [provider] => Ok(provider),
+                _ => Err(AppError::bad_request(
+                    "Several OpenID Connect providers are configured; use /auth/oidc/{provider}/callback",
+                )),
+            },
+        }
+    }
 }
 
 impl FromRef<AppState> for SqlitePool {
@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use sqlx::{QueryBuilder, Row, Sqlite, SqlitePool, Transaction};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::{
+    format_datetime, parse_datetime, Task, TaskGroupCreate, TaskGroupRead, TaskGroupStatus,
+    TaskGroupSummary, TaskGroupTaskRead, TaskStatus,
+};
+
+fn parse_uuid(value: &str) -> Result<Uuid, AppError> {
+    Uuid::parse_str(value).map_err(|_| AppError::bad_request("Invalid identifier"))
+}
+
+/// Checks that every task only depends on tasks listed before it, which also
+/// rules out cycles, and returns the prerequisites of each task by position.
+fn prerequisites_by_position(payload: &TaskGroupCreate) -> Result<Vec<Vec<usize>>, AppError> {
+    if payload.tasks.is_empty() {
+        return Err(AppError::bad_request(
+            "A task group needs at least one task",
+        ));
+    }
+    payload
+        .tasks
+        .iter()
+        .enumerate()
+        .map(|(position, task)| {
+            let mut prerequisites = task.depends_on.clone();
+            if payload.ordered && position > 0 {
+                prerequisites.push(position - 1);
+            }
+            if prerequisites.iter().any(|&index| index >= position) {
+                return Err(AppError::bad_request(format!(
+                    "Task {position} may only depend on tasks listed before it"
+                )));
+            }
+            prerequisites.sort_unstable();
+            prerequisites.dedup();
+            Ok(prerequisites)
+        })
+        .collect()
+}
+
+/// Creates a group and its tasks. Tasks without prerequisites are queued
+/// immediately on `payload.branch`; the others start blocked.
+pub async fn create_group(
+    tx: &mut Transaction<'_, Sqlite>,
+    created_by: Uuid,
+    payload: TaskGroupCreate,
+) -> Result<(Uuid, Vec<Task>), AppError> {
+    let prerequisites = prerequisites_by_position(&payload)?;
+    let group_id = Uuid::new_v4();
+    let now = Utc::now();
+    let now_str = format_datetime(now);
+
+    sqlx::query(
+        r#"
+        INSERT INTO task_groups (id, title, repository_id, created_by, created_at)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(group_id.to_string())
+    .bind(&payload.title)
+    .bind(payload.repository_id.to_string())
+    .bind(created_by.to_string())
+    .bind(&now_str)
+    .execute(&mut **tx)
+    .await?;
+
+    let mut tasks: Vec<Task> = Vec::with_capacity(payload.tasks.len());
+    for (spec, prerequisites) in payload.tasks.into_iter().zip(prerequisites) {
+        let (status, branch) = if prerequisites.is_empty() {
+            (TaskStatus::Pending, payload.branch.clone())
+        } else {
+            (TaskStatus::Blocked, None)
+        };
+        let task = Task {
+            id: Uuid::new_v4(),
+            title: spec.title,
+            description: spec.description,
+            repository_id: payload.repository_id,
+            status,
+            assignee_id: None,
+            created_by,
+            created_at: now,
+            updated_at: now,
+            environment_id: payload.environment_id.clone(),
+            retry_after: None,
+            priority: spec.priority,
+            best_of_n: None,
+            branch,
+            qa_mode: false,
+            group_id: Some(group_id),
+            result_branch: None,
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO tasks (id, title, description, repository_id, status, assignee_id, created_by, created_at, updated_at, environment_id, priority, branch, group_id)
+            VALUES (?, ?, ?, ?, ?, NULL, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(task.id.to_string())
+        .bind(&task.title)
+        .bind(&task.description)
+        .bind(task.repository_id.to_string())
+        .bind(task.status.as_str())
+        .bind(created_by.to_string())
+        .bind(&now_str)
+        .bind(&now_str)
+        .bind(&task.environment_id)
+        .bind(task.priority)
+        .bind(&task.branch)
+        .bind(group_id.to_string())
+        .execute(&mut **tx)
+        .await?;
+
+        for index in prerequisites {
+            sqlx::query("INSERT INTO task_dependencies (task_id, depends_on_id) VALUES (?, ?)")
+                .bind(task.id.to_string())
+                .bind(tasks[index].id.to_string())
+                .execute(&mut **tx)
+                .await?;
+        }
+        tasks.push(task);
+    }
+
+    Ok((group_id, tasks))
+}
+
+pub async fn get_group(pool: &SqlitePool, group_id: Uuid) -> Result<TaskGroupRead, AppError> {
+    let group = sqlx::query(
+        r#"
+        SELECT id, title, repository_id, created_by, created_at
+        FROM task_groups
+        WHERE id = ?
+        "#,
+    )
+    .bind(group_id.to_string())
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("Task group not found"))?;
+
+    let dependency_rows = sqlx::query(
+        r#"
+        SELECT d.task_id, d.depends_on_id
+        FROM task_dependencies d
+        JOIN tasks t ON t.id = d.task_id
+        WHERE t.group_id = ?
+        "#,
+    )
+    .bind(group_id.to_string())
+    .fetch_all(pool)
+    .await?;
+    let mut dependencies: HashMap<String, Vec<Uuid>> = HashMap::new();
+    for row in dependency_rows {
+        let depends_on_id: String = row.try_get("depends_on_id")?;
+        dependencies
+            .entry(row.try_get("task_id")?)
+            .or_default()
+            .push(parse_uuid(&depends_on_id)?);
+    }
+
+    // Tasks of a group share their creation time, so the rowid keeps them in
+    // the order they were submitted.
+    let task_rows = sqlx::query(
+        r#"
+        SELECT id, title, status, branch, result_branch
+        FROM tasks
+        WHERE group_id = ?
+        ORDER BY created_at, rowid
+        "#,
+    )
+    .bind(group_id.to_string())
+    .fetch_all(pool)
+    .await?;
+    let tasks = task_rows
+        .iter()
+        .map(|row| {
+            let id: String = row.try_get("id")?;
+            let status: String = row.try_get("status")?;
+            let mut depends_on = dependencies.remove(&id).unwrap_or_default();
+            depends_on.sort();
+            Ok(TaskGroupTaskRead {
+                id: parse_uuid(&id)?,
+                title: row.try_get("title")?,
+                status: TaskStatus::from_str(&status)?,
+                branch: row.try_get("branch")?,
+                result_branch: row.try_get("result_branch")?,
+                depends_on,
+            })
+        })
+        .collect::<Result<Vec<_>, AppError>>()?;
+
+    let status = TaskGroupStatus::rollup(tasks.iter().map(|task| task.status));
+    let applied_tasks = tasks
+        .iter()
+        .filter(|task| task.status == TaskStatus::Applied)
+        .count() as i64;
+    let id: String = group.try_get("id")?;
+    let repository_id: String = group.try_get("repository_id")?;
+    let created_by: String = group.try_get("created_by")?;
+    let created_at: String = group.try_get("created_at")?;
+    Ok(TaskGroupRead {
+        id: parse_uuid(&id)?,
+        title: group.try_get("title")?,
+        repository_id: parse_uuid(&repository_id)?,
+        created_by: parse_uuid(&created_by)?,
+        created_at: parse_datetime(&created_at)?,
+        status,
+        total_tasks: tasks.len() as i64,
+        applied_tasks,
+        tasks,
+    })
+}
+
+/// Status rollups for the given groups, keyed by group id.
+pub async fn summaries(
+    pool: &SqlitePool,
+    group_ids: &[Uuid],
+) -> Result<HashMap<Uuid, TaskGroupSummary>, AppError> {
+    if group_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let mut builder = QueryBuilder::<Sqlite>::new(
+        "SELECT g.id, g.title, t.status FROM task_groups g JOIN tasks t ON t.group_id = g.id WHERE g.id IN (",
+    );
+    let mut separated = builder.separated(", ");
+    for group_id in group_ids {
+        separated.push_bind(group_id.to_string());
+    }
+    builder.push(")");
+    let rows = builder.build().fetch_all(pool).await?;
+
+    let mut groups: HashMap<Uuid, (String, Vec<TaskStatus>)> = HashMap::new();
+    for row in rows {
+        let id: String = row.try_get("id")?;
+        let title: String = row.try_get("title")?;
+        let status: String = row.try_get("status")?;
+        groups
+            .entry(parse_uuid(&id)?)
+            .or_insert_with(|| (title, Vec::new()))
+            .1
+            .push(TaskStatus::from_str(&status)?);
+    }
+    Ok(groups
+        .into_iter()
+        .map(|(id, (title, statuses))| {
+            let summary = TaskGroupSummary {
+                id,
+                title,
+                status: TaskGroupStatus::rollup(statuses.iter().copied()),
+                total_tasks: statuses.len() as i64,
+                applied_tasks: statuses
+                    .iter()
+                    .filter(|status| **status == TaskStatus::Applied)
+                    .count() as i64,
+            };
+            (id, summary)
+        })
+        .collect())
+}
+
+/// Tasks that must be applied before `task_id` can be claimed.
+pub async fn prerequisites(pool: &SqlitePool, task_id: Uuid) -> Result<Vec<Uuid>, AppError> {
+    let ids: Vec<String> = sqlx::query_scalar(
+        "SELECT depends_on_id FROM task_dependencies WHERE task_id = ? ORDER BY depends_on_id",
+    )
+    .bind(task_id.to_string())
+    .fetch_all(pool)
+    .await?;
+    ids.iter().map(|id| parse_uuid(id)).collect()
+}
+
+/// A blocked task that became claimable.
+pub struct ReleasedTask {
+    pub id: Uuid,
+    pub branch: Option<String>,
+}
+
+/// Queues the blocked dependents of `task_id` whose prerequisites have now
+/// all been applied. A dependent without a branch of its own starts from the
+/// result branch of its most recently applied prerequisite; prerequisites
+/// applied at the same time are broken by the order they were submitted in.
+pub async fn release_dependents(
+    tx: &mut Transaction<'_, Sqlite>,
+    task_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<Vec<ReleasedTask>, AppError> {
+    let ready: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT t.id
+        FROM task_dependencies d
+        JOIN tasks t ON t.id = d.task_id
+        WHERE d.depends_on_id = ?
+          AND t.status = ?
+          AND NOT EXISTS (
+            SELECT 1
+            FROM task_dependencies pd
+            JOIN tasks p ON p.id = pd.depends_on_id
+            WHERE pd.task_id = t.id AND p.status != ?
+          )
+        ORDER BY t.rowid
+        "#,
+    )
+    .bind(task_id.to_string())
+    .bind(TaskStatus::Blocked.as_str())
+    .bind(TaskStatus::Applied.as_str())
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let mut released = Vec::with_capacity(ready.len());
+    for id in ready {
+        let branch: Option<Option<String>> = sqlx::query_scalar(
+            r#"
+            UPDATE tasks
+            SET status = ?, updated_at = ?, branch = COALESCE(branch, (
+                SELECT p.result_branch
+                FROM task_dependencies pd
+                JOIN tasks p ON p.id = pd.depends_on_id
+                WHERE pd.task_id = tasks.id
+                ORDER BY p.updated_at DESC, p.rowid DESC
+                LIMIT 1
+            ))
+            WHERE id = ? AND status = ?
+            RETURNING branch
+            "#,
+        )
+        .bind(TaskStatus::Pending.as_str())
+        .bind(format_datetime(now))
+        .bind(&id)
+        .bind(TaskStatus::Blocked.as_str())
+        .fetch_optional(&mut **tx)
+        .await?;
+        if let Some(branch) = branch {
+            released.push(ReleasedTask {
+                id: parse_uuid(&id)?,
+                branch,
+            });
+        }
+    }
+    Ok(released)
+}
+
+/// Fails every blocked task that directly or transitively depends on
+/// `task_id`, which has failed for good, and returns their ids.
+pub async fn fail_dependents(
+    tx: &mut Transaction<'_, Sqlite>,
+    task_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<Vec<Uuid>, AppError> {
+    let ids: Vec<String> = sqlx::query_scalar(
+        r#"
+        WITH RECURSIVE dependents(id) AS (
+            SELECT task_id FROM task_dependencies WHERE depends_on_id = ?
+            UNION
+            SELECT d.task_id
+            FROM task_dependencies d
+            JOIN dependents ON d.depends_on_id = dependents.id
+        )
+        UPDATE tasks
+        SET status = ?, updated_at = ?
+        WHERE id IN (SELECT id FROM dependents) AND status = ?
+        RETURNING id
+        "#,
+    )
+    .bind(task_id.to_string())
+    .bind(TaskStatus::Failed.as_str())
+    .bind(format_datetime(now))
+    .bind(TaskStatus::Blocked.as_str())
+    .fetch_all(&mut **tx)
+    .await?;
+    let mut ids = ids
+        .iter()
+        .map(|id| parse_uuid(id))
+        .collect::<Result<Vec<_>, _>>()?;
+    ids.sort();
+    Ok(ids)
+}
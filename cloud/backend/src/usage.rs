@@ -0,0 +1,275 @@
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{QueryBuilder, Row, Sqlite, SqlitePool, Transaction};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::{format_datetime, AttemptUsage, QuotaRead, UsageReportRow};
+
+/// How a usage report groups attempts. Attempts are attributed to the user who
+/// created the task, to every organization that user belongs to and to the UTC
+/// day on which they completed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageGrouping {
+    #[default]
+    User,
+    Organization,
+    Repository,
+    Day,
+}
+
+impl UsageGrouping {
+    fn key_expression(&self) -> &'static str {
+        match self {
+            Self::User => "t.created_by",
+            Self::Organization => "m.organization_id",
+            Self::Repository => "t.repository_id",
+            Self::Day => "substr(a.updated_at, 1, 10)",
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct UsageQuery {
+    #[serde(default)]
+    pub group_by: UsageGrouping,
+    pub user_id: Option<Uuid>,
+    pub organization_id: Option<Uuid>,
+    pub repository_id: Option<Uuid>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// Attributes tasks to the organizations their creator belongs to. A user with
+/// several memberships in one organization is only counted once.
+const MEMBERSHIP_JOIN: &str = "
+        JOIN (SELECT DISTINCT organization_id, user_id FROM organization_members) m
+            ON m.user_id = t.created_by";
+
+fn parse_uuid(value: &str) -> Result<Uuid, AppError> {
+    Uuid::parse_str(value).map_err(|_| AppError::bad_request("Invalid identifier"))
+}
+
+pub fn validate(usage: &AttemptUsage) -> Result<(), AppError> {
+    let counters = [
+        usage.input_tokens,
+        usage.cached_input_tokens,
+        usage.output_tokens,
+        usage.reasoning_output_tokens,
+        usage.wall_time_ms,
+        usage.cpu_time_ms.unwrap_or_default(),
+    ];
+    if counters.iter().any(|value| *value < 0) {
+        return Err(AppError::bad_request("Usage counters must not be negative"));
+    }
+    Ok(())
+}
+
+/// Sums the usage of completed attempts that reported it.
+pub async fn report(
+    pool: &SqlitePool,
+    query: &UsageQuery,
+) -> Result<Vec<UsageReportRow>, AppError> {
+    let mut builder = QueryBuilder::<Sqlite>::new("SELECT ");
+    builder.push(query.group_by.key_expression());
+    builder.push(
+        r#" AS usage_key,
+            COUNT(1) AS attempts,
+            COALESCE(SUM(a.input_tokens), 0) AS input_tokens,
+            COALESCE(SUM(a.cached_input_tokens), 0) AS cached_input_tokens,
+            COALESCE(SUM(a.output_tokens), 0) AS output_tokens,
+            COALESCE(SUM(a.reasoning_output_tokens), 0) AS reasoning_output_tokens,
+            COALESCE(SUM(a.wall_time_ms), 0) AS wall_time_ms,
+            COALESCE(SUM(a.cpu_time_ms), 0) AS cpu_time_ms
+        FROM task_attempts a
+        JOIN tasks t ON t.id = a.task_id"#,
+    );
+    if query.group_by == UsageGrouping::Organization || query.organization_id.is_some() {
+        builder.push(MEMBERSHIP_JOIN);
+    }
+    builder.push(" WHERE a.input_tokens IS NOT NULL");
+    if let Some(user_id) = query.user_id {
+        builder.push(" AND t.created_by = ");
+        builder.push_bind(user_id.to_string());
+    }
+    if let Some(organization_id) = query.organization_id {
+        builder.push(" AND m.organization_id = ");
+        builder.push_bind(organization_id.to_string());
+    }
+    if let Some(repository_id) = query.repository_id {
+        builder.push(" AND t.repository_id = ");
+        builder.push_bind(repository_id.to_string());
+    }
+    if let Some(since) = query.since {
+        builder.push(" AND a.updated_at >= ");
+        builder.push_bind(format_datetime(since));
+    }
+    if let Some(until) = query.until {
+        builder.push(" AND a.updated_at < ");
+        builder.push_bind(format_datetime(until));
+    }
+    builder.push(" GROUP BY usage_key ORDER BY usage_key");
+
+    let rows = builder.build().fetch_all(pool).await?;
+    rows.into_iter()
+        .map(|row| {
+            Ok(UsageReportRow {
+                key: row.try_get("usage_key")?,
+                attempts: row.try_get("attempts")?,
+                input_tokens: row.try_get("input_tokens")?,
+                cached_input_tokens: row.try_get("cached_input_tokens")?,
+                output_tokens: row.try_get("output_tokens")?,
+                reasoning_output_tokens: row.try_get("reasoning_output_tokens")?,
+                wall_time_ms: row.try_get("wall_time_ms")?,
+                cpu_time_ms: row.try_get("cpu_time_ms")?,
+            })
+        })
+        .collect()
+}
+
+/// Start of the UTC calendar month containing `now`.
+pub fn period_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+        .single()
+        .unwrap_or(now)
+}
+
+/// Billable tokens consumed since `since` by tasks created by the current
+/// members of `organization_id`.
+pub async fn billable_tokens_since(
+    pool: &SqlitePool,
+    organization_id: Uuid,
+    since: DateTime<Utc>,
+) -> Result<i64, AppError> {
+    let used = sqlx::query_scalar::<_, i64>(&format!(
+        r#"
+        SELECT COALESCE(SUM(a.input_tokens + a.output_tokens), 0)
+        FROM task_attempts a
+        JOIN tasks t ON t.id = a.task_id{MEMBERSHIP_JOIN}
+        WHERE m.organization_id = ? AND a.input_tokens IS NOT NULL AND a.updated_at >= ?
+        "#
+    ))
+    .bind(organization_id.to_string())
+    .bind(format_datetime(since))
+    .fetch_one(pool)
+    .await?;
+    Ok(used)
+}
+
+/// SQL condition that holds when an organization the creator of the `tasks` row
+/// belongs to has used up its monthly quota, for statements that must check
+/// the quota atomically. Binds the start of the current period.
+pub const QUOTA_EXHAUSTED: &str = "
+        EXISTS (
+            SELECT 1
+            FROM organization_quotas q
+            JOIN organization_members creator ON creator.organization_id = q.organization_id
+            WHERE creator.user_id = tasks.created_by
+              AND (
+                SELECT COALESCE(SUM(a.input_tokens + a.output_tokens), 0)
+                FROM task_attempts a
+                JOIN tasks t ON t.id = a.task_id
+                JOIN (SELECT DISTINCT organization_id, user_id FROM organization_members) m
+                    ON m.user_id = t.created_by
+                WHERE m.organization_id = q.organization_id
+                  AND a.input_tokens IS NOT NULL
+                  AND a.updated_at >= ?
+              ) >= q.monthly_token_limit
+        )";
+
+/// Rejects work created by `user_id` when any organization they belong to has
+/// used up its monthly quota, naming the first such organization.
+pub async fn ensure_within_quota(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT q.organization_id, q.monthly_token_limit, o.name
+        FROM organization_quotas q
+        JOIN organizations o ON o.id = q.organization_id
+        WHERE q.organization_id IN (
+            SELECT organization_id FROM organization_members WHERE user_id = ?
+        )
+        ORDER BY o.name
+        "#,
+    )
+    .bind(user_id.to_string())
+    .fetch_all(pool)
+    .await?;
+    let period_start = period_start(now);
+    for row in rows {
+        let organization_id: String = row.try_get("organization_id")?;
+        let limit: i64 = row.try_get("monthly_token_limit")?;
+        let name: String = row.try_get("name")?;
+        let used = billable_tokens_since(pool, parse_uuid(&organization_id)?, period_start).await?;
+        if used >= limit {
+            return Err(AppError::conflict(format!(
+                "Monthly token quota of organization {name} exceeded"
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub async fn set_quota(
+    tx: &mut Transaction<'_, Sqlite>,
+    organization_id: Uuid,
+    monthly_token_limit: Option<i64>,
+) -> Result<(), AppError> {
+    let Some(limit) = monthly_token_limit else {
+        sqlx::query("DELETE FROM organization_quotas WHERE organization_id = ?")
+            .bind(organization_id.to_string())
+            .execute(&mut **tx)
+            .await?;
+        return Ok(());
+    };
+    if limit < 0 {
+        return Err(AppError::bad_request(
+            "monthly_token_limit must not be negative",
+        ));
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO organization_quotas (organization_id, monthly_token_limit, updated_at)
+        VALUES (?, ?, ?)
+        ON CONFLICT(organization_id) DO UPDATE SET
+            monthly_token_limit = excluded.monthly_token_limit,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(organization_id.to_string())
+    .bind(limit)
+    .bind(format_datetime(Utc::now()))
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+pub async fn list_quotas(
+    pool: &SqlitePool,
+    now: DateTime<Utc>,
+) -> Result<Vec<QuotaRead>, AppError> {
+    let rows = sqlx::query(
+        "SELECT organization_id, monthly_token_limit FROM organization_quotas ORDER BY organization_id",
+    )
+    .fetch_all(pool)
+    .await?;
+    let period_start = period_start(now);
+
+    let mut quotas = Vec::with_capacity(rows.len());
+    for row in rows {
+        let organization_id: String = row.try_get("organization_id")?;
+        let organization_id = parse_uuid(&organization_id)?;
+        quotas.push(QuotaRead {
+            organization_id,
+            monthly_token_limit: row.try_get("monthly_token_limit")?,
+            used_tokens: billable_tokens_since(pool, organization_id, period_start).await?,
+            period_start,
+        });
+    }
+    Ok(quotas)
+}
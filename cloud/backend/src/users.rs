@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, Sqlite, SqlitePool, Transaction};
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::models::{
+    format_datetime, parse_datetime, ExternalIdentityRead, InviteCreate, InviteRead, UserRead,
+    UserRole, UserUpdate,
+};
+
+/// Longest expiry an admin may request for an invite.
+const INVITE_MAX_HOURS: u32 = 24 * 30;
+
+fn parse_uuid(value: &str) -> Result<Uuid, AppError> {
+    Uuid::parse_str(value).map_err(|_| AppError::bad_request("Invalid identifier"))
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+pub async fn list_users(pool: &SqlitePool) -> Result<Vec<UserRead>, AppError> {
+    let identity_rows = sqlx::query(
+        r#"
+        SELECT id, issuer, subject, user_id, email, created_at
+        FROM external_identities
+        ORDER BY id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut identities: HashMap<String, Vec<ExternalIdentityRead>> = HashMap::new();
+    for row in identity_rows {
+        let user_id: String = row.try_get("user_id")?;
+        identities
+            .entry(user_id)
+            .or_default()
+            .push(row_to_identity(&row)?);
+    }
+
+    let rows = sqlx::query(
+        r#"
+        SELECT id, email, name, role, auth_provider, created_at, deactivated_at
+        FROM users
+        ORDER BY email
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    rows.iter()
+        .map(|row| {
+            let id: String = row.try_get("id")?;
+            let external_identities = identities.remove(&id).unwrap_or_default();
+            row_to_user_read(row, external_identities)
+        })
+        .collect()
+}
+
+pub async fn get_user(
+    tx: &mut Transaction<'_, Sqlite>,
+    user_id: Uuid,
+) -> Result<UserRead, AppError> {
+    let row = sqlx::query(
+        r#"
+        SELECT id, email, name, role, auth_provider, created_at, deactivated_at
+        FROM users
+        WHERE id = ?
+        "#,
+    )
+    .bind(user_id.to_string())
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or_else(|| AppError::not_found("User not found"))?;
+
+    let identity_rows = sqlx::query(
+        r#"
+        SELECT id, issuer, subject, email, created_at
+        FROM external_identities
+        WHERE user_id = ?
+        ORDER BY id
+        "#,
+    )
+    .bind(user_id.to_string())
+    .fetch_all(&mut **tx)
+    .await?;
+    let identities = identity_rows
+        .iter()
+        .map(row_to_identity)
+        .collect::<Result<Vec<_>, _>>()?;
+    row_to_user_read(&row, identities)
+}
+
+/// Applies a role change and/or (de)activation. Deactivating an already
+/// deactivated user keeps the original timestamp. Runs in the caller's
+/// transaction so the change and its audit record land together.
+pub async fn update_user(
+    tx: &mut Transaction<'_, Sqlite>,
+    user_id: Uuid,
+    update: &UserUpdate,
+) -> Result<UserRead, AppError> {
+    if let Some(role) = update.role {
+        sqlx::query("UPDATE users SET role = ? WHERE id = ?")
+            .bind(role.as_str())
+            .bind(user_id.to_string())
+            .execute(&mut **tx)
+            .await?;
+    }
+    match update.active {
+        Some(true) => {
+            sqlx::query("UPDATE users SET deactivated_at = NULL WHERE id = ?")
+                .bind(user_id.to_string())
+                .execute(&mut **tx)
+                .await?;
+        }
+        Some(false) => {
+            sqlx::query(
+                "UPDATE users SET deactivated_at = COALESCE(deactivated_at, ?) WHERE id = ?",
+            )
+            .bind(format_datetime(Utc::now()))
+            .bind(user_id.to_string())
+            .execute(&mut **tx)
+            .await?;
+        }
+        None => {}
+    }
+    get_user(tx, user_id).await
+}
+
+pub async fn set_password_hash(
+    tx: &mut Transaction<'_, Sqlite>,
+    user_id: Uuid,
+    password_hash: &str,
+) -> Result<(), AppError> {
+    let result = sqlx::query("UPDATE users SET password_hash = ? WHERE id = ?")
+        .bind(password_hash)
+        .bind(user_id.to_string())
+        .execute(&mut **tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("User not found"));
+    }
+    Ok(())
+}
+
+/// Removes an external identity from a user and returns it.
+pub async fn unlink_identity(
+    tx: &mut Transaction<'_, Sqlite>,
+    user_id: Uuid,
+    identity_id: i64,
+) -> Result<ExternalIdentityRead, AppError> {
+    let row = sqlx::query(
+        r#"
+        DELETE FROM external_identities
+        WHERE id = ? AND user_id = ?
+        RETURNING id, issuer, subject, email, created_at
+        "#,
+    )
+    .bind(identity_id)
+    .bind(user_id.to_string())
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or_else(|| AppError::not_found("External identity not found"))?;
+    row_to_identity(&row)
+}
+
+pub async fn create_invite(
+    tx: &mut Transaction<'_, Sqlite>,
+    config: &AppConfig,
+    created_by: Uuid,
+    payload: InviteCreate,
+) -> Result<InviteRead, AppError> {
+    let ttl = match payload.expires_in_hours {
+        Some(hours) if hours == 0 || hours > INVITE_MAX_HOURS => {
+            return Err(AppError::bad_request(format!(
+                "expires_in_hours must be between 1 and {INVITE_MAX_HOURS}"
+            )));
+        }
+        Some(hours) => Duration::hours(i64::from(hours)),
+        None => Duration::from_std(config.registration.invite_ttl)
+            .map_err(|_| AppError::bad_request("Invalid invite expiry"))?,
+    };
+    let email = payload
+        .email
+        .map(|email| email.trim().to_string())
+        .filter(|email| !email.is_empty());
+
+    let id = Uuid::new_v4();
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let created_at = Utc::now();
+    let expires_at = created_at + ttl;
+    sqlx::query(
+        r#"
+        INSERT INTO invites (id, token_hash, email, role, created_by, created_at, expires_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(id.to_string())
+    .bind(hash_token(&token))
+    .bind(&email)
+    .bind(payload.role.as_str())
+    .bind(created_by.to_string())
+    .bind(format_datetime(created_at))
+    .bind(format_datetime(expires_at))
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(InviteRead {
+        id,
+        email,
+        role: payload.role,
+        created_by,
+        created_at,
+        expires_at,
+        accepted_at: None,
+        accepted_by: None,
+        url: config.invite_url(&token),
+        token: Some(token),
+    })
+}
+
+pub async fn list_invites(pool: &SqlitePool) -> Result<Vec<InviteRead>, AppError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, email, role, created_by, created_at, expires_at, accepted_at, accepted_by
+        FROM invites
+        ORDER BY created_at DESC
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    rows.iter().map(row_to_invite).collect()
+}
+
+/// Deletes an invite that has not been accepted yet.
+pub async fn revoke_invite(
+    tx: &mut Transaction<'_, Sqlite>,
+    invite_id: Uuid,
+) -> Result<(), AppError> {
+    let accepted_at: Option<Option<String>> =
+        sqlx::query_scalar("SELECT accepted_at FROM invites WHERE id = ?")
+            .bind(invite_id.to_string())
+            .fetch_optional(&mut **tx)
+            .await?;
+    match accepted_at {
+        None => Err(AppError::not_found("Invite not found")),
+        Some(Some(_)) => Err(AppError::conflict("Invite has already been accepted")),
+        Some(None) => {
+            sqlx::query("DELETE FROM invites WHERE id = ? AND accepted_at IS NULL")
+                .bind(invite_id.to_string())
+                .execute(&mut **tx)
+                .await?;
+            Ok(())
+        }
+    }
+}
+
+/// An invite that is valid for a registration.
+pub struct PendingInvite {
+    pub id: Uuid,
+    pub role: UserRole,
+}
+
+/// Looks up an unused, unexpired invite for `email`.
+pub async fn find_invite(
+    tx: &mut Transaction<'_, Sqlite>,
+    token: &str,
+    email: &str,
+    now: DateTime<Utc>,
+) -> Result<PendingInvite, AppError> {
+    let row = sqlx::query(
+        r#"
+        SELECT id, email, role, expires_at, accepted_at
+        FROM invites
+        WHERE token_hash = ?
+        "#,
+    )
+    .bind(hash_token(token))
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or_else(|| AppError::forbidden("Invalid invite"))?;
+
+    let accepted_at: Option<String> = row.try_get("accepted_at")?;
+    if accepted_at.is_some() {
+        return Err(AppError::forbidden("Invite has already been used"));
+    }
+    let expires_at: String = row.try_get("expires_at")?;
+    if parse_datetime(&expires_at)? <= now {
+        return Err(AppError::forbidden("Invite has expired"));
+    }
+    let invite_email: Option<String> = row.try_get("email")?;
+    if invite_email.is_some_and(|invite_email| !invite_email.eq_ignore_ascii_case(email)) {
+        return Err(AppError::forbidden("Invite was issued for another email"));
+    }
+
+    let id: String = row.try_get("id")?;
+    let role: String = row.try_get("role")?;
+    Ok(PendingInvite {
+        id: parse_uuid(&id)?,
+        role: UserRole::from_str(&role)?,
+    })
+}
+
+/// Marks an invite as used by `user_id`. Fails if another registration used
+/// it concurrently.
+pub async fn accept_invite(
+    tx: &mut Transaction<'_, Sqlite>,
+    invite_id: Uuid,
+    user_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let result = sqlx::query(
+        r#"
+        UPDATE invites
+        SET accepted_at = ?, accepted_by = ?
+        WHERE id = ? AND accepted_at IS NULL
+        "#,
+    )
+    .bind(format_datetime(now))
+    .bind(user_id.to_string())
+    .bind(invite_id.to_string())
+    .execute(&mut **tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::forbidden("Invite has already been used"));
+    }
+    Ok(())
+}
+
+fn row_to_user_read(
+    row: &SqliteRow,
+    external_identities: Vec<ExternalIdentityRead>,
+) -> Result<UserRead, AppError> {
+    let id: String = row.try_get("id")?;
+    let role: String = row.try_get("role")?;
+    let created_at: String = row.try_get("created_at")?;
+    let deactivated_at: Option<String> = row.try_get("deactivated_at")?;
+    Ok(UserRead {
+        id: parse_uuid(&id)?,
+        email: row.try_get("email")?,
+        name: row.try_get("name")?,
+        role: UserRole::from_str(&role)?,
+        auth_provider: row.try_get("auth_provider")?,
+        created_at: parse_datetime(&created_at)?,
+        deactivated_at: deactivated_at.as_deref().map(parse_datetime).transpose()?,
+        external_identities,
+    })
+}
+
+fn row_to_identity(row: &SqliteRow) -> Result<ExternalIdentityRead, AppError> {
+    let created_at: String = row.try_get("created_at")?;
+    Ok(ExternalIdentityRead {
+        id: row.try_get("id")?,
+        issuer: row.try_get("issuer")?,
+        subject: row.try_get("subject")?,
+        email: row.try_get("email")?,
+        created_at: parse_datetime(&created_at)?,
+    })
+}
+
+fn row_to_invite(row: &SqliteRow) -> Result<InviteRead, AppError> {
+    let id: String = row.try_get("id")?;
+    let role: String = row.try_get("role")?;
+    let created_by: String = row.try_get("created_by")?;
+    let created_at: String = row.try_get("created_at")?;
+    let expires_at: String = row.try_get("expires_at")?;
+    let accepted_at: Option<String> = row.try_get("accepted_at")?;
+    let accepted_by: Option<String> = row.try_get("accepted_by")?;
+    Ok(InviteRead {
+        id: parse_uuid(&id)?,
+        email: row.try_get("email")?,
+        role: UserRole::from_str(&role)?,
+        created_by: parse_uuid(&created_by)?,
+        created_at: parse_datetime(&created_at)?,
+        expires_at: parse_datetime(&expires_at)?,
+        accepted_at: accepted_at.as_deref().map(parse_datetime).transpose()?,
+        accepted_by: accepted_by.as_deref().map(parse_uuid).transpose()?,
+        token: None,
+        url: None,
+    })
+}
@@ -0,0 +1,706 @@
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Arc, Weak};
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{redirect, Url};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Executor, Row, Sqlite, SqlitePool, Transaction};
+use tokio::net::lookup_host;
+use tokio::sync::{Notify, Semaphore};
+use tokio::time::sleep;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::WebhookSettings;
+use crate::error::AppError;
+use crate::models::{
+    format_datetime, parse_datetime, DeliveryStatus, WebhookCreate, WebhookDeliveryRead,
+    WebhookEvent, WebhookRead,
+};
+
+pub const EVENT_HEADER: &str = "x-codex-event";
+pub const DELIVERY_HEADER: &str = "x-codex-delivery";
+/// `sha256=<hex>` HMAC of the raw request body keyed with the subscription secret.
+pub const SIGNATURE_HEADER: &str = "x-codex-signature";
+
+const DELIVERY_BATCH: i64 = 50;
+/// Deliveries in flight at once, so that a slow receiver only holds up its own.
+const DELIVERY_CONCURRENCY: usize = 16;
+
+/// Handle to the background task that drains the delivery queue. The task
+/// stops once every handle has been dropped.
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    wake: Arc<Notify>,
+}
+
+impl WebhookDispatcher {
+    pub fn spawn(pool: SqlitePool, settings: WebhookSettings) -> Result<Self, AppError> {
+        // Redirects are not followed, since they could lead to an address the
+        // destination check would have rejected.
+        let mut builder = reqwest::Client::builder()
+            .timeout(settings.request_timeout)
+            .redirect(redirect::Policy::none());
+        if !settings.allow_private_destinations {
+            builder = builder.dns_resolver(Arc::new(PublicResolver));
+        }
+        let client = builder.build()?;
+        let wake = Arc::new(Notify::new());
+        tokio::spawn(run(pool, client, settings, Arc::downgrade(&wake)));
+        Ok(Self { wake })
+    }
+
+    /// Asks the dispatcher to look for due deliveries now.
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+}
+
+async fn run(
+    pool: SqlitePool,
+    client: reqwest::Client,
+    settings: WebhookSettings,
+    wake: Weak<Notify>,
+) {
+    let permits = Arc::new(Semaphore::new(DELIVERY_CONCURRENCY));
+    loop {
+        if let Err(err) = deliver_due(&pool, &client, &settings, &permits).await {
+            warn!(error = %err, "Webhook delivery pass failed");
+        }
+
+        let Some(wake) = wake.upgrade() else {
+            break;
+        };
+        tokio::select! {
+            _ = wake.notified() => {}
+            _ = sleep(settings.poll_interval) => {}
+        }
+    }
+}
+
+/// Resolves webhook hosts to public addresses only, so a host that passed the
+/// check at subscription time cannot later be pointed at an internal service.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{host} does not resolve to a public address").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Whether `ip` is reachable on the public internet, as opposed to loopback,
+/// private, link-local and other special-purpose ranges.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || first == 0
+                // Carrier-grade NAT, 100.64.0.0/10.
+                || (first == 100 && second & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(ip));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local, fc00::/7.
+                || first & 0xfe00 == 0xfc00
+                // Link-local, fe80::/10.
+                || first & 0xffc0 == 0xfe80)
+        }
+    }
+}
+
+/// Explains why deliveries to `url` are not allowed, if they are not.
+async fn destination_problem(url: &Url) -> Option<&'static str> {
+    let host = url.host_str()?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<IpAddr> = match host.parse::<IpAddr>() {
+        Ok(ip) => vec![ip],
+        Err(_) => {
+            let port = url.port_or_known_default().unwrap_or(80);
+            match lookup_host((host, port)).await {
+                Ok(addrs) => addrs.map(|addr| addr.ip()).collect(),
+                Err(_) => return Some("Webhook host could not be resolved"),
+            }
+        }
+    };
+    if addrs.is_empty() || !addrs.into_iter().all(is_public) {
+        return Some("Webhook URL must point to a public address");
+    }
+    None
+}
+
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Queues `event` for every active subscription that matches the task, as
+/// part of the transaction that produced the event so that a committed change
+/// is never left without its deliveries. The dispatcher has to be woken once
+/// the transaction is committed.
+pub async fn enqueue(
+    tx: &mut Transaction<'_, Sqlite>,
+    event: WebhookEvent,
+    repository_id: Uuid,
+    created_by: Uuid,
+    data: Value,
+) -> Result<(), AppError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, events
+        FROM webhook_subscriptions
+        WHERE active = 1
+          AND (repository_id IS NULL OR repository_id = ?)
+          AND (organization_id IS NULL OR organization_id IN (
+              SELECT organization_id FROM organization_members WHERE user_id = ?
+          ))
+        "#,
+    )
+    .bind(repository_id.to_string())
+    .bind(created_by.to_string())
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let now = format_datetime(Utc::now());
+    let payload = json!({
+        "event": event,
+        "occurred_at": now,
+        "data": data,
+    })
+    .to_string();
+
+    for row in rows {
+        let subscription_id: String = row.try_get("id")?;
+        let events: String = row.try_get("events")?;
+        if !parse_events(&events)?.contains(&event) {
+            continue;
+        }
+        insert_delivery(&mut **tx, &subscription_id, event, &payload, None).await?;
+    }
+    Ok(())
+}
+
+async fn insert_delivery<'c, E>(
+    executor: E,
+    subscription_id: &str,
+    event: WebhookEvent,
+    payload: &str,
+    redelivery_of: Option<Uuid>,
+) -> Result<Uuid, AppError>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    let delivery_id = Uuid::new_v4();
+    let now = format_datetime(Utc::now());
+    sqlx::query(
+        r#"
+        INSERT INTO webhook_deliveries (id, subscription_id, event, payload, status, attempts, next_attempt_at, redelivery_of, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, 0, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(delivery_id.to_string())
+    .bind(subscription_id)
+    .bind(event.as_str())
+    .bind(payload)
+    .bind(DeliveryStatus::Pending.as_str())
+    .bind(&now)
+    .bind(redelivery_of.map(|id| id.to_string()))
+    .bind(&now)
+    .bind(&now)
+    .execute(executor)
+    .await?;
+    Ok(delivery_id)
+}
+
+async fn deliver_due(
+    pool: &SqlitePool,
+    client: &reqwest::Client,
+    settings: &WebhookSettings,
+    permits: &Arc<Semaphore>,
+) -> Result<(), AppError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT d.id, d.event, d.payload, d.attempts, d.next_attempt_at, s.url, s.secret
+        FROM webhook_deliveries d
+        JOIN webhook_subscriptions s ON s.id = d.subscription_id
+        WHERE d.status = ? AND d.next_attempt_at <= ?
+        ORDER BY d.next_attempt_at
+        LIMIT ?
+        "#,
+    )
+    .bind(DeliveryStatus::Pending.as_str())
+    .bind(format_datetime(Utc::now()))
+    .bind(DELIVERY_BATCH)
+    .fetch_all(pool)
+    .await?;
+
+    for row in rows {
+        let permit = Arc::clone(permits)
+            .acquire_owned()
+            .await
+            .expect("delivery semaphore is never closed");
+        let delivery = PendingDelivery {
+            id: row.try_get("id")?,
+            event: row.try_get("event")?,
+            payload: row.try_get("payload")?,
+            attempts: row.try_get("attempts")?,
+            url: row.try_get("url")?,
+            secret: row.try_get("secret")?,
+        };
+        let due: String = row.try_get("next_attempt_at")?;
+
+        // Moving the delivery out of the due window while it is in flight keeps
+        // later passes from sending it twice. Should the process stop before
+        // the outcome is recorded, the delivery becomes due again once the
+        // lease runs out.
+        let lease = Utc::now()
+            + chrono::Duration::from_std(settings.request_timeout * 2)
+                .unwrap_or_else(|_| chrono::Duration::zero());
+        let leased = sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET next_attempt_at = ?
+            WHERE id = ? AND status = ? AND next_attempt_at = ?
+            "#,
+        )
+        .bind(format_datetime(lease))
+        .bind(&delivery.id)
+        .bind(DeliveryStatus::Pending.as_str())
+        .bind(&due)
+        .execute(pool)
+        .await?;
+        if leased.rows_affected() == 0 {
+            continue;
+        }
+
+        let pool = pool.clone();
+        let client = client.clone();
+        let settings = settings.clone();
+        tokio::spawn(async move {
+            let id = delivery.id.clone();
+            if let Err(err) = deliver(&pool, &client, &settings, delivery).await {
+                warn!(delivery_id = %id, error = %err, "Failed to record webhook delivery");
+            }
+            drop(permit);
+        });
+    }
+
+    Ok(())
+}
+
+struct PendingDelivery {
+    id: String,
+    event: String,
+    payload: String,
+    attempts: i64,
+    url: String,
+    secret: String,
+}
+
+async fn deliver(
+    pool: &SqlitePool,
+    client: &reqwest::Client,
+    settings: &WebhookSettings,
+    delivery: PendingDelivery,
+) -> Result<(), AppError> {
+    let PendingDelivery {
+        id,
+        event,
+        payload,
+        attempts,
+        url,
+        secret,
+    } = delivery;
+
+    // Addresses in the URL itself never reach the resolver, so they are
+    // checked here as well.
+    let problem = match Url::parse(&url) {
+        _ if settings.allow_private_destinations => None,
+        Ok(parsed) => destination_problem(&parsed).await,
+        Err(_) => Some("Invalid webhook URL"),
+    };
+    let (response_status, error) = match problem {
+        Some(problem) => (None, Some(problem.to_string())),
+        None => {
+            let result = client
+                .post(&url)
+                .header(CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, &event)
+                .header(DELIVERY_HEADER, &id)
+                .header(SIGNATURE_HEADER, sign(&secret, payload.as_bytes()))
+                .body(payload)
+                .send()
+                .await;
+            match result {
+                Ok(response) if response.status().is_success() => {
+                    (Some(i64::from(response.status().as_u16())), None)
+                }
+                Ok(response) => (
+                    Some(i64::from(response.status().as_u16())),
+                    Some(format!("Receiver responded with {}", response.status())),
+                ),
+                Err(err) => (None, Some(err.to_string())),
+            }
+        }
+    };
+
+    let attempts = attempts + 1;
+    let now = Utc::now();
+    let (status, next_attempt_at, delivered_at) = match &error {
+        None => (DeliveryStatus::Succeeded, None, Some(now)),
+        Some(_) if attempts >= i64::from(settings.max_attempts) => {
+            (DeliveryStatus::Failed, None, None)
+        }
+        Some(_) => {
+            let backoff = settings.backoff_for(attempts as u32);
+            let next = now
+                + chrono::Duration::from_std(backoff).unwrap_or_else(|_| chrono::Duration::zero());
+            (DeliveryStatus::Pending, Some(next), None)
+        }
+    };
+
+    match &error {
+        None => info!(delivery_id = %id, event = %event, "Webhook delivered"),
+        Some(err) => warn!(
+            delivery_id = %id,
+            event = %event,
+            attempts,
+            error = %err,
+            "Webhook delivery failed"
+        ),
+    }
+
+    // A subscription deleted while the request was in flight has already
+    // failed its pending deliveries; that outcome is kept.
+    sqlx::query(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = ?, attempts = ?, response_status = ?, last_error = ?, next_attempt_at = ?, delivered_at = ?, updated_at = ?
+        WHERE id = ? AND status = ?
+        "#,
+    )
+    .bind(status.as_str())
+    .bind(attempts)
+    .bind(response_status)
+    .bind(&error)
+    .bind(next_attempt_at.map(format_datetime))
+    .bind(delivered_at.map(format_datetime))
+    .bind(format_datetime(now))
+    .bind(&id)
+    .bind(DeliveryStatus::Pending.as_str())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn create_subscription(
+    tx: &mut Transaction<'_, Sqlite>,
+    settings: &WebhookSettings,
+    created_by: Uuid,
+    payload: WebhookCreate,
+) -> Result<WebhookRead, AppError> {
+    let url = Url::parse(&payload.url).map_err(|_| AppError::bad_request("Invalid webhook URL"))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(AppError::bad_request("Webhook URL must use http or https"));
+    }
+    if !settings.allow_private_destinations {
+        if let Some(problem) = destination_problem(&url).await {
+            return Err(AppError::bad_request(problem));
+        }
+    }
+    if payload.events.is_empty() {
+        return Err(AppError::bad_request("At least one event is required"));
+    }
+
+    let secret = payload
+        .secret
+        .filter(|secret| !secret.is_empty())
+        .unwrap_or_else(|| format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()));
+    let id = Uuid::new_v4();
+    let now = Utc::now();
+    let mut events: Vec<WebhookEvent> = Vec::new();
+    for event in payload.events {
+        if !events.contains(&event) {
+            events.push(event);
+        }
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO webhook_subscriptions (id, url, secret, events, repository_id, organization_id, active, created_by, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, 1, ?, ?)
+        "#,
+    )
+    .bind(id.to_string())
+    .bind(&payload.url)
+    .bind(&secret)
+    .bind(format_events(&events))
+    .bind(payload.repository_id.map(|id| id.to_string()))
+    .bind(payload.organization_id.map(|id| id.to_string()))
+    .bind(created_by.to_string())
+    .bind(format_datetime(now))
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(WebhookRead {
+        id,
+        url: payload.url,
+        events,
+        repository_id: payload.repository_id,
+        organization_id: payload.organization_id,
+        active: true,
+        created_by,
+        created_at: now,
+        secret: Some(secret),
+    })
+}
+
+/// Lists active subscriptions, only those created by `created_by` when given.
+pub async fn list_subscriptions(
+    pool: &SqlitePool,
+    created_by: Option<Uuid>,
+) -> Result<Vec<WebhookRead>, AppError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, url, events, repository_id, organization_id, active, created_by, created_at
+        FROM webhook_subscriptions
+        WHERE active = 1 AND (? IS NULL OR created_by = ?)
+        ORDER BY created_at
+        "#,
+    )
+    .bind(created_by.map(|id| id.to_string()))
+    .bind(created_by.map(|id| id.to_string()))
+    .fetch_all(pool)
+    .await?;
+    rows.into_iter().map(row_to_subscription).collect()
+}
+
+pub async fn fetch_subscription(pool: &SqlitePool, id: Uuid) -> Result<WebhookRead, AppError> {
+    let row = sqlx::query(
+        r#"
+        SELECT id, url, events, repository_id, organization_id, active, created_by, created_at
+        FROM webhook_subscriptions
+        WHERE id = ?
+        "#,
+    )
+    .bind(id.to_string())
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::not_found("Webhook not found"))?;
+    row_to_subscription(row)
+}
+
+/// Returns the subscription an earlier delivery was made for.
+pub async fn fetch_delivery_subscription(
+    pool: &SqlitePool,
+    delivery_id: Uuid,
+) -> Result<WebhookRead, AppError> {
+    let subscription_id: String =
+        sqlx::query_scalar("SELECT subscription_id FROM webhook_deliveries WHERE id = ?")
+            .bind(delivery_id.to_string())
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| AppError::not_found("Delivery not found"))?;
+    fetch_subscription(pool, parse_uuid(&subscription_id, "webhook id")?).await
+}
+
+/// Deactivates a subscription and fails its queued deliveries.
+pub async fn deactivate_subscription(
+    tx: &mut Transaction<'_, Sqlite>,
+    id: Uuid,
+) -> Result<(), AppError> {
+    let result =
+        sqlx::query("UPDATE webhook_subscriptions SET active = 0 WHERE id = ? AND active = 1")
+            .bind(id.to_string())
+            .execute(&mut **tx)
+            .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Webhook not found"));
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = ?, next_attempt_at = NULL, last_error = 'Subscription deleted', updated_at = ?
+        WHERE subscription_id = ? AND status = ?
+        "#,
+    )
+    .bind(DeliveryStatus::Failed.as_str())
+    .bind(format_datetime(Utc::now()))
+    .bind(id.to_string())
+    .bind(DeliveryStatus::Pending.as_str())
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+pub async fn list_deliveries(
+    pool: &SqlitePool,
+    subscription_id: Uuid,
+) -> Result<Vec<WebhookDeliveryRead>, AppError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, subscription_id, event, status, attempts, response_status, last_error, next_attempt_at, delivered_at, redelivery_of, created_at
+        FROM webhook_deliveries
+        WHERE subscription_id = ?
+        ORDER BY created_at DESC
+        LIMIT 100
+        "#,
+    )
+    .bind(subscription_id.to_string())
+    .fetch_all(pool)
+    .await?;
+    rows.into_iter().map(row_to_delivery).collect()
+}
+
+/// Queues a fresh copy of an earlier delivery with the original payload. The
+/// dispatcher has to be woken once the transaction is committed.
+pub async fn redeliver(
+    tx: &mut Transaction<'_, Sqlite>,
+    delivery_id: Uuid,
+) -> Result<WebhookDeliveryRead, AppError> {
+    let row = sqlx::query(
+        r#"
+        SELECT d.subscription_id, d.event, d.payload, s.active
+        FROM webhook_deliveries d
+        JOIN webhook_subscriptions s ON s.id = d.subscription_id
+        WHERE d.id = ?
+        "#,
+    )
+    .bind(delivery_id.to_string())
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or_else(|| AppError::not_found("Delivery not found"))?;
+
+    let active: i64 = row.try_get("active")?;
+    if active == 0 {
+        return Err(AppError::conflict("Webhook has been deleted"));
+    }
+    let subscription_id: String = row.try_get("subscription_id")?;
+    let event: String = row.try_get("event")?;
+    let payload: String = row.try_get("payload")?;
+
+    let id = insert_delivery(
+        &mut **tx,
+        &subscription_id,
+        WebhookEvent::from_str(&event)?,
+        &payload,
+        Some(delivery_id),
+    )
+    .await?;
+
+    let row = sqlx::query(
+        r#"
+        SELECT id, subscription_id, event, status, attempts, response_status, last_error, next_attempt_at, delivered_at, redelivery_of, created_at
+        FROM webhook_deliveries
+        WHERE id = ?
+        "#,
+    )
+    .bind(id.to_string())
+    .fetch_one(&mut **tx)
+    .await?;
+    row_to_delivery(row)
+}
+
+fn format_events(events: &[WebhookEvent]) -> String {
+    events
+        .iter()
+        .map(WebhookEvent::as_str)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_events(value: &str) -> Result<Vec<WebhookEvent>, AppError> {
+    value
+        .split(',')
+        .filter(|event| !event.is_empty())
+        .map(WebhookEvent::from_str)
+        .collect()
+}
+
+fn parse_uuid(value: &str, field: &str) -> Result<Uuid, AppError> {
+    Uuid::parse_str(value).map_err(|_| AppError::bad_request(format!("Invalid {field}")))
+}
+
+fn row_to_subscription(row: SqliteRow) -> Result<WebhookRead, AppError> {
+    let id: String = row.try_get("id")?;
+    let events: String = row.try_get("events")?;
+    let repository_id: Option<String> = row.try_get("repository_id")?;
+    let organization_id: Option<String> = row.try_get("organization_id")?;
+    let active: i64 = row.try_get("active")?;
+    let created_by: String = row.try_get("created_by")?;
+    let created_at: String = row.try_get("created_at")?;
+
+    Ok(WebhookRead {
+        id: parse_uuid(&id, "webhook id")?,
+        url: row.try_get("url")?,
+        events: parse_events(&events)?,
+        repository_id: repository_id
+            .map(|id| parse_uuid(&id, "repository id"))
+            .transpose()?,
+        organization_id: organization_id
+            .map(|id| parse_uuid(&id, "organization id"))
+            .transpose()?,
+        active: active != 0,
+        created_by: parse_uuid(&created_by, "created by")?,
+        created_at: parse_datetime(&created_at)?,
+        secret: None,
+    })
+}
+
+fn row_to_delivery(row: SqliteRow) -> Result<WebhookDeliveryRead, AppError> {
+    let id: String = row.try_get("id")?;
+    let subscription_id: String = row.try_get("subscription_id")?;
+    let event: String = row.try_get("event")?;
+    let status: String = row.try_get("status")?;
+    let next_attempt_at: Option<String> = row.try_get("next_attempt_at")?;
+    let delivered_at: Option<String> = row.try_get("delivered_at")?;
+    let redelivery_of: Option<String> = row.try_get("redelivery_of")?;
+    let created_at: String = row.try_get("created_at")?;
+
+    Ok(WebhookDeliveryRead {
+        id: parse_uuid(&id, "delivery id")?,
+        subscription_id: parse_uuid(&subscription_id, "webhook id")?,
+        event: WebhookEvent::from_str(&event)?,
+        status: DeliveryStatus::from_str(&status)?,
+        attempts: row.try_get("attempts")?,
+        response_status: row.try_get("response_status")?,
+        last_error: row.try_get("last_error")?,
+        next_attempt_at: next_attempt_at.as_deref().map(parse_datetime).transpose()?,
+        delivered_at: delivered_at.as_deref().map(parse_datetime).transpose()?,
+        redelivery_of: redelivery_of
+            .map(|id| parse_uuid(&id, "delivery id"))
+            .transpose()?,
+        created_at: parse_datetime(&created_at)?,
+    })
+}
@@ -1,16 +1,18 @@
 mod common;
 
-use common::TestApp;
+use common::{login, TestApp};
 use reqwest::StatusCode;
 use serde_json::Value;
 
 #[tokio::test]
 async fn missing_artifact_returns_not_found() {
     let app = TestApp::spawn().await;
+    let auth_header = login(&app, "reader@example.com").await;
 
     let response = app
         .client
         .get(app.url("/artifacts/nonexistent.diff"))
+        .header("Authorization", &auth_header)
         .send()
         .await
         .unwrap();
@@ -19,3 +21,17 @@ async fn missing_artifact_returns_not_found() {
     let body = response.json::<Value>().await.unwrap();
     assert_eq!(body["detail"], "Artifact not found");
 }
+
+#[tokio::test]
+async fn artifacts_require_authentication() {
+    let app = TestApp::spawn().await;
+
+    let response = app
+        .client
+        .get(app.url("/artifacts/nonexistent.diff"))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::{login, login_admin, TestApp};
+use reqwest::StatusCode;
+use serde_json::json;
+
+#[tokio::test]
+async fn mutating_requests_are_recorded_and_exported() {
+    let app = TestApp::spawn().await;
+    let worker = login(&app, "worker@example.com").await;
+    let admin = login_admin(&app, "admin@example.com").await;
+
+    let repo = app
+        .client
+        .post(app.url("/repositories"))
+        .header("Authorization", &worker)
+        .json(&json!({
+            "name": "codex",
+            "git_url": "https://example.com/codex.git",
+            "default_branch": "main"
+        }))
+        .send()
+        .await
+        .unwrap();
+    let repository_id = repo.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let task = app
+        .client
+        .post(app.url("/tasks"))
+        .header("Authorization", &worker)
+        .json(&json!({
+            "title": "Audited task",
+            "repository_id": repository_id
+        }))
+        .send()
+        .await
+        .unwrap();
+    let task_id = task.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let claim = app
+        .client
+        .post(app.url(&format!("/tasks/{task_id}/claim")))
+        .header("Authorization", &worker)
+        .header("X-Request-Id", "req-claim-1")
+        // Ignored, since the peer is not a trusted proxy.
+        .header("X-Forwarded-For", "203.0.113.9")
+        .send()
+        .await
+        .unwrap();
+    assert!(claim.status().is_success());
+
+    let forbidden = app
+        .client
+        .get(app.url("/admin/audit"))
+        .header("Authorization", &worker)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+
+    let entries = app
+        .client
+        .get(app.url(&format!(
+            "/admin/audit?target_type=task&target_id={task_id}"
+        )))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let entries = entries.as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["action"], "task.create");
+    assert_eq!(entries[0]["after_status"], "pending");
+    assert_eq!(entries[1]["action"], "task.claim");
+    assert_eq!(entries[1]["before_status"], "pending");
+    assert_eq!(entries[1]["after_status"], "claimed");
+    assert_eq!(entries[1]["request_id"], "req-claim-1");
+    assert_eq!(entries[1]["ip"], "127.0.0.1");
+    assert_eq!(entries[1]["actor_id"], entries[0]["actor_id"]);
+
+    let export = app
+        .client
+        .get(app.url("/admin/audit/export?action=user.create"))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(export.status(), StatusCode::OK);
+    assert_eq!(
+        export.headers()["content-type"].to_str().unwrap(),
+        "application/x-ndjson"
+    );
+    let body = export.text().await.unwrap();
+    let lines = body
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|entry| entry["target_type"] == "user"));
+
+    let rewrite = sqlx::query("UPDATE audit_log SET action = 'tampered'")
+        .execute(&app.pool)
+        .await;
+    assert!(rewrite.is_err());
+}
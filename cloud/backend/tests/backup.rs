@@ -0,0 +1,146 @@
+mod common;
+
+use codex_cloud_backend::backup;
+use codex_cloud_backend::db;
+use common::{login, TestApp};
+use reqwest::StatusCode;
+use serde_json::json;
+use tempfile::TempDir;
+
+/// Creates a task with a completed attempt and returns the task id.
+async fn completed_task(app: &TestApp, auth_header: &str) -> String {
+    let repo = app
+        .client
+        .post(app.url("/repositories"))
+        .header("Authorization", auth_header)
+        .json(&json!({
+            "name": "codex",
+            "git_url": "https://example.com/codex.git",
+            "default_branch": "main"
+        }))
+        .send()
+        .await
+        .unwrap();
+    let repository_id = repo.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let task = app
+        .client
+        .post(app.url("/tasks"))
+        .header("Authorization", auth_header)
+        .json(&json!({
+            "title": "Back me up",
+            "repository_id": repository_id
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(task.status(), StatusCode::CREATED);
+    let task_id = task.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let claim = app
+        .client
+        .post(app.url(&format!("/tasks/{task_id}/claim")))
+        .header("Authorization", auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert!(claim.status().is_success());
+    let attempt = app
+        .client
+        .post(app.url(&format!("/tasks/{task_id}/attempts")))
+        .header("Authorization", auth_header)
+        .json(&json!({}))
+        .send()
+        .await
+        .unwrap();
+    let attempt_id = attempt.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let complete = app
+        .client
+        .post(app.url(&format!("/tasks/attempts/{attempt_id}/complete")))
+        .header("Authorization", auth_header)
+        .json(&json!({
+            "status": "succeeded",
+            "diff": "diff --git a/x b/x",
+            "log": "all done"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert!(complete.status().is_success());
+    task_id
+}
+
+#[tokio::test]
+async fn backup_round_trips_database_and_artifacts_and_detects_corruption() {
+    let app = TestApp::spawn().await;
+    let auth = login(&app, "ops@example.com").await;
+    let task_id = completed_task(&app, &auth).await;
+
+    let out = TempDir::new().unwrap();
+    let archive = out.path().join("codex-backup.tar");
+    // The server keeps running while the snapshot is taken.
+    let manifest = backup::create(&app.pool, &app.config, &archive)
+        .await
+        .unwrap();
+    assert_eq!(manifest.format_version, backup::FORMAT_VERSION);
+    assert_eq!(manifest.artifacts.len(), 2);
+    assert!(manifest.missing_artifacts.is_empty());
+    let health = app.client.get(app.url("/health")).send().await.unwrap();
+    assert!(health.status().is_success());
+
+    let target = TempDir::new().unwrap();
+    let mut config = app.config.clone();
+    config.database_url = format!("sqlite://{}", target.path().join("codex.db").display());
+    config.artifacts_dir = target.path().join("artifacts");
+    let restored = backup::restore(&config, &archive, false).await.unwrap();
+    assert_eq!(restored, manifest);
+
+    let pool = db::connect(&config.database_url).await.unwrap();
+    let title: String = sqlx::query_scalar("SELECT title FROM tasks WHERE id = ?")
+        .bind(&task_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(title, "Back me up");
+    let (diff_id, log_id): (String, String) =
+        sqlx::query_as("SELECT diff_artifact_id, log_artifact_id FROM task_attempts")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    pool.close().await;
+    assert_eq!(
+        std::fs::read_to_string(config.artifact_path(&diff_id)).unwrap(),
+        "diff --git a/x b/x"
+    );
+    assert_eq!(
+        std::fs::read_to_string(config.artifact_path(&log_id)).unwrap(),
+        "all done"
+    );
+
+    let existing = backup::restore(&config, &archive, false).await.unwrap_err();
+    assert!(existing.to_string().contains("--force"));
+    backup::restore(&config, &archive, true).await.unwrap();
+
+    // Flip a byte inside the database entry, past its 512-byte header.
+    let mut bytes = std::fs::read(&archive).unwrap();
+    bytes[4096] ^= 0xff;
+    std::fs::write(&archive, bytes).unwrap();
+    let fresh = TempDir::new().unwrap();
+    config.database_url = format!("sqlite://{}", fresh.path().join("codex.db").display());
+    config.artifacts_dir = fresh.path().join("artifacts");
+    let corrupt = backup::restore(&config, &archive, false).await.unwrap_err();
+    assert!(
+        corrupt.to_string().contains("checksum mismatch"),
+        "{corrupt}"
+    );
+    assert!(!fresh.path().join("codex.db").exists());
+}
@@ -1,6 +1,6 @@
 mod common;
 
-use common::TestApp;
+use common::{login, TestApp};
 use serde_json::json;
 use uuid::Uuid;
 
@@ -137,3 +137,187 @@ async fn codex_compatibility_endpoints_create_task() {
     assert_eq!(detail_body["environment_id"], "local-dev");
     assert_eq!(detail_body["title"], "Implement CLI compatibility");
 }
+
+async fn login_with_environment(app: &TestApp) -> String {
+    app.client
+        .post(app.url("/auth/users"))
+        .json(&json!({
+            "email": "attachments@example.com",
+            "password": "secret123"
+        }))
+        .send()
+        .await
+        .unwrap();
+    let login = app
+        .client
+        .post(app.url("/auth/session"))
+        .json(&json!({
+            "email": "attachments@example.com",
+            "password": "secret123"
+        }))
+        .send()
+        .await
+        .unwrap();
+    let token = login.json::<serde_json::Value>().await.unwrap()["access_token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let auth_header = format!("Bearer {token}");
+
+    let repo = app
+        .client
+        .post(app.url("/repositories"))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "name": "codex",
+            "git_url": "https://github.com/example/codex.git",
+            "default_branch": "main"
+        }))
+        .send()
+        .await
+        .unwrap();
+    let repository_id = repo.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let env = app
+        .client
+        .post(app.url("/environments"))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "id": "local-dev",
+            "repository_id": repository_id,
+            "branch": "main"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(env.status(), 201);
+    auth_header
+}
+
+#[tokio::test]
+async fn codex_tasks_keep_branch_qa_mode_and_attachments() {
+    let app = TestApp::spawn().await;
+    let auth_header = login_with_environment(&app).await;
+
+    // "codex" and "notes" encoded as base64.
+    let codex_task = app
+        .client
+        .post(app.url("/api/codex/tasks"))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "new_task": {
+                "environment_id": "local-dev",
+                "branch": "feature/login",
+                "run_environment_in_qa_mode": true
+            },
+            "input_items": [
+                {
+                    "type": "message",
+                    "role": "user",
+                    "content": [
+                        { "content_type": "text", "text": "Match the screenshot" },
+                        {
+                            "content_type": "image",
+                            "image_url": "data:image/png;base64,Y29kZXg=",
+                            "file_name": "mockup.png"
+                        },
+                        {
+                            "content_type": "file",
+                            "file_name": "notes.txt",
+                            "mime_type": "text/plain",
+                            "data": "bm90ZXM="
+                        }
+                    ]
+                }
+            ]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(codex_task.status(), 201);
+    let task_id = codex_task.json::<serde_json::Value>().await.unwrap()["task"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let detail = app
+        .client
+        .get(app.url(&format!("/tasks/{task_id}")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(detail["branch"], "feature/login");
+    assert_eq!(detail["qa_mode"], true);
+    assert_eq!(detail["description"], "Match the screenshot");
+    let inputs = detail["inputs"].as_array().unwrap();
+    assert_eq!(inputs.len(), 2);
+    assert_eq!(inputs[0]["kind"], "image");
+    assert_eq!(inputs[0]["name"], "mockup.png");
+    assert_eq!(inputs[0]["mime_type"], "image/png");
+    assert_eq!(inputs[1]["kind"], "file");
+    assert_eq!(inputs[1]["name"], "notes.txt");
+
+    let image = app
+        .client
+        .get(inputs[0]["url"].as_str().unwrap())
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(image.headers()["content-type"], "image/png");
+    assert_eq!(image.bytes().await.unwrap().as_ref(), b"codex");
+    let notes = app
+        .client
+        .get(inputs[1]["url"].as_str().unwrap())
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(notes.text().await.unwrap(), "notes");
+
+    // Inputs are only served to the task's creator and its assignee.
+    let anonymous = app
+        .client
+        .get(inputs[0]["url"].as_str().unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(anonymous.status(), 401);
+    let other = login(&app, "someone@example.com").await;
+    let forbidden = app
+        .client
+        .get(inputs[0]["url"].as_str().unwrap())
+        .header("Authorization", &other)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(forbidden.status(), 403);
+
+    let remote_image = app
+        .client
+        .post(app.url("/api/codex/tasks"))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "new_task": { "environment_id": "local-dev" },
+            "input_items": [
+                {
+                    "type": "message",
+                    "role": "user",
+                    "content": [
+                        { "content_type": "text", "text": "Match the screenshot" },
+                        { "content_type": "image", "image_url": "https://example.com/a.png" }
+                    ]
+                }
+            ]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(remote_image.status(), 400);
+}
@@ -1,10 +1,15 @@
+use std::net::SocketAddr;
 use std::time::Duration;
 
-use codex_cloud_backend::config::AppConfig;
+use codex_cloud_backend::config::{
+    AppConfig, RateLimitSettings, RegistrationSettings, SchedulerSettings, TaskRetrySettings,
+    WebhookSettings,
+};
 use codex_cloud_backend::db;
 use codex_cloud_backend::routes::app_router;
 use codex_cloud_backend::state::AppState;
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
+use serde_json::{json, Value};
 use tempfile::TempDir;
 use tokio::net::TcpListener;
 use tokio::sync::oneshot;
@@ -40,7 +45,16 @@ impl TestApp {
             artifact_base_url: "http://127.0.0.1:0/artifacts".to_string(),
             access_token_expire_minutes: 60,
             cors_origins: vec!["*".to_string()],
-            oidc: None,
+            oidc_providers: Vec::new(),
+            task_retry: TaskRetrySettings::default(),
+            webhooks: WebhookSettings::default(),
+            scheduler: SchedulerSettings::default(),
+            rate_limit: RateLimitSettings {
+                enabled: false,
+                ..RateLimitSettings::default()
+            },
+            registration: RegistrationSettings::default(),
+            trusted_proxies: Vec::new(),
         };
         configure(&mut config);
         config.ensure_artifact_dir().unwrap();
@@ -52,8 +66,8 @@ impl TestApp {
         let addr = listener.local_addr().unwrap();
         let base_url = format!("http://{}:{}", addr.ip(), addr.port());
         config.artifact_base_url = format!("{base_url}/artifacts");
-        if let Some(oidc) = config.oidc.as_mut() {
-            oidc.redirect_uri = format!("{base_url}/auth/oidc/callback");
+        for oidc in &mut config.oidc_providers {
+            oidc.redirect_uri = format!("{base_url}/auth/oidc/{}/callback", oidc.name);
         }
 
         let state = AppState::new(pool.clone(), config.clone()).await.unwrap();
@@ -62,7 +76,11 @@ impl TestApp {
         let (shutdown_tx, shutdown_rx) = oneshot::channel();
 
         tokio::spawn(async move {
-            let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+            let server = axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(async move {
                 let _ = shutdown_rx.await;
             });
             if let Err(err) = server.await {
@@ -110,3 +128,128 @@ impl Drop for TestApp {
         }
     }
 }
+
+/// Registers `email` with a fixed password, unless it already exists, and
+/// returns an `Authorization` header value for it.
+#[allow(dead_code)]
+pub async fn login(app: &TestApp, email: &str) -> String {
+    app.client
+        .post(app.url("/auth/users"))
+        .json(&json!({
+            "email": email,
+            "password": "secret123"
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    let login = app
+        .client
+        .post(app.url("/auth/session"))
+        .json(&json!({
+            "email": email,
+            "password": "secret123"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert!(login.status().is_success());
+    let token = login.json::<Value>().await.unwrap()["access_token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    format!("Bearer {token}")
+}
+
+/// Like [`login`], but grants the user the admin role first, as
+/// `create-admin` would.
+#[allow(dead_code)]
+pub async fn login_admin(app: &TestApp, email: &str) -> String {
+    let auth_header = login(app, email).await;
+    sqlx::query("UPDATE users SET role = 'admin' WHERE email = ?")
+        .bind(email)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    auth_header
+}
+
+#[allow(dead_code)]
+pub async fn create_repository(app: &TestApp, auth_header: &str) -> String {
+    let repo = app
+        .client
+        .post(app.url("/repositories"))
+        .header("Authorization", auth_header)
+        .json(&json!({
+            "name": "codex",
+            "git_url": "https://github.com/example/codex.git",
+            "default_branch": "main"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(repo.status(), StatusCode::CREATED);
+    repo.json::<Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+/// Creates a task from a `POST /tasks` body and returns its id.
+#[allow(dead_code)]
+pub async fn create_task_with(app: &TestApp, auth_header: &str, body: Value) -> String {
+    let task = app
+        .client
+        .post(app.url("/tasks"))
+        .header("Authorization", auth_header)
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(task.status(), StatusCode::CREATED);
+    task.json::<Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+#[allow(dead_code)]
+pub async fn create_task(app: &TestApp, auth_header: &str, repository_id: &str) -> String {
+    create_task_with(
+        app,
+        auth_header,
+        json!({
+            "title": "Test task",
+            "repository_id": repository_id
+        }),
+    )
+    .await
+}
+
+#[allow(dead_code)]
+pub async fn claim(app: &TestApp, auth_header: &str, task_id: &str) -> reqwest::Response {
+    app.client
+        .post(app.url(&format!("/tasks/{task_id}/claim")))
+        .header("Authorization", auth_header)
+        .send()
+        .await
+        .unwrap()
+}
+
+/// Starts an attempt on a task the caller has claimed and returns its id.
+#[allow(dead_code)]
+pub async fn start_attempt(app: &TestApp, auth_header: &str, task_id: &str) -> String {
+    let attempt = app
+        .client
+        .post(app.url(&format!("/tasks/{task_id}/attempts")))
+        .header("Authorization", auth_header)
+        .json(&json!({}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(attempt.status(), StatusCode::CREATED);
+    attempt.json::<Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
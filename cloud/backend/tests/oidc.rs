@@ -4,10 +4,12 @@ use std::time::Duration;
 
 use bcrypt::DEFAULT_COST;
 use chrono::{Duration as ChronoDuration, Utc};
-use codex_cloud_backend::config::{JwksCacheSettings, OidcConfig};
+use codex_cloud_backend::config::{
+    GroupOrganization, JwksCacheSettings, OidcConfig, DEFAULT_OIDC_PROVIDER,
+};
 use codex_cloud_backend::db::{self, ExternalIdentitySeed};
 use codex_cloud_backend::error::AppError;
-use codex_cloud_backend::models::TokenResponse;
+use codex_cloud_backend::models::{OrganizationRole, TokenResponse};
 use codex_cloud_backend::security::{decode_token, OidcProvider};
 use jsonwebtoken::{Algorithm, EncodingKey, Header};
 use serde_json::json;
@@ -32,6 +34,11 @@ struct OidcFixture {
 
 impl OidcFixture {
     async fn setup() -> Self {
+        Self::setup_with_claims(json!({ "groups": ["engineering"] })).await
+    }
+
+    /// Issues ID tokens with `extra` merged into the standard claims.
+    async fn setup_with_claims(extra: serde_json::Value) -> Self {
         let mock = MockServer::start().await;
         let issuer = mock.uri();
         let token_endpoint = format!("{}/token", issuer);
@@ -42,7 +49,7 @@ impl OidcFixture {
         let expiration = (Utc::now() + ChronoDuration::minutes(5)).timestamp() as usize;
         let mut header = Header::new(Algorithm::RS256);
         header.kid = Some(KEY_ID.to_string());
-        let claims = json!({
+        let mut claims = json!({
             "sub": SUBJECT,
             "iss": issuer,
             "aud": CLIENT_ID,
@@ -50,6 +57,9 @@ impl OidcFixture {
             "email": "oidc@example.com",
             "name": "OIDC User"
         });
+        for (key, value) in extra.as_object().unwrap() {
+            claims[key] = value.clone();
+        }
         let id_token = jsonwebtoken::encode(&header, &claims, &encoding_key).expect("token");
 
         Mock::given(method("GET"))
@@ -94,20 +104,90 @@ impl OidcFixture {
     }
 }
 
+fn provider_config(name: &str, issuer: &str) -> OidcConfig {
+    OidcConfig {
+        name: name.to_string(),
+        issuer: issuer.to_string(),
+        client_id: CLIENT_ID.to_string(),
+        client_secret: CLIENT_SECRET.to_string(),
+        redirect_uri: format!("http://127.0.0.1:0/auth/oidc/{name}/callback"),
+        jwks_cache: JwksCacheSettings {
+            ttl: Duration::from_secs(3600),
+            refresh: Duration::from_secs(60),
+        },
+        groups_claim: "groups".to_string(),
+        group_organizations: Vec::new(),
+    }
+}
+
+async fn insert_linked_user(app: &TestApp, email: &str, role: &str, issuer: &str) -> Uuid {
+    let user_id = Uuid::new_v4();
+    let password_hash = bcrypt::hash("unused", DEFAULT_COST).expect("hash");
+    sqlx::query(
+        r#"
+        INSERT INTO users (id, email, name, password_hash, auth_provider, created_at, role)
+        VALUES (?, ?, NULL, ?, 'oidc', ?, ?)
+        "#,
+    )
+    .bind(user_id.to_string())
+    .bind(email)
+    .bind(password_hash)
+    .bind(Utc::now().to_rfc3339())
+    .bind(role)
+    .execute(&app.pool)
+    .await
+    .expect("insert user");
+
+    db::seed_external_identities(
+        &app.pool,
+        &[ExternalIdentitySeed {
+            issuer,
+            subject: SUBJECT,
+            user_id,
+            email: Some(email),
+        }],
+    )
+    .await
+    .expect("seed identity");
+    user_id
+}
+
+async fn insert_organization(app: &TestApp, name: &str) -> Uuid {
+    let organization_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO organizations (id, name, created_at) VALUES (?, ?, ?)")
+        .bind(organization_id.to_string())
+        .bind(name)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&app.pool)
+        .await
+        .expect("insert organization");
+    organization_id
+}
+
+/// The user's organization memberships as `(role, source)` pairs.
+async fn memberships(app: &TestApp, user_id: Uuid) -> Vec<(String, String)> {
+    sqlx::query_as(
+        "SELECT role, source FROM organization_members WHERE user_id = ? ORDER BY source",
+    )
+    .bind(user_id.to_string())
+    .fetch_all(&app.pool)
+    .await
+    .expect("memberships")
+}
+
+async fn user_role(app: &TestApp, user_id: Uuid) -> String {
+    sqlx::query_scalar("SELECT role FROM users WHERE id = ?")
+        .bind(user_id.to_string())
+        .fetch_one(&app.pool)
+        .await
+        .expect("role")
+}
+
 #[tokio::test]
 async fn oidc_callback_issues_token_for_linked_identity() {
     let fixture = OidcFixture::setup().await;
     let app = TestApp::spawn_with(|config| {
-        config.oidc = Some(OidcConfig {
-            issuer: fixture.issuer.clone(),
-            client_id: CLIENT_ID.to_string(),
-            client_secret: CLIENT_SECRET.to_string(),
-            redirect_uri: "http://127.0.0.1:0/auth/oidc/callback".to_string(),
-            jwks_cache: JwksCacheSettings {
-                ttl: Duration::from_secs(3600),
-                refresh: Duration::from_secs(60),
-            },
-        });
+        config.oidc_providers = vec![provider_config(DEFAULT_OIDC_PROVIDER, &fixture.issuer)];
     })
     .await;
 
@@ -160,16 +240,7 @@ async fn oidc_callback_issues_token_for_linked_identity() {
 async fn oidc_callback_rejects_unlinked_identity() {
     let fixture = OidcFixture::setup().await;
     let app = TestApp::spawn_with(|config| {
-        config.oidc = Some(OidcConfig {
-            issuer: fixture.issuer.clone(),
-            client_id: CLIENT_ID.to_string(),
-            client_secret: CLIENT_SECRET.to_string(),
-            redirect_uri: "http://127.0.0.1:0/auth/oidc/callback".to_string(),
-            jwks_cache: JwksCacheSettings {
-                ttl: Duration::from_secs(3600),
-                refresh: Duration::from_secs(60),
-            },
-        });
+        config.oidc_providers = vec![provider_config(DEFAULT_OIDC_PROVIDER, &fixture.issuer)];
     })
     .await;
 
@@ -202,19 +273,104 @@ async fn oidc_provider_discovery_rejects_mismatched_issuer() {
         .mount(&mock)
         .await;
 
-    let result = OidcProvider::discover(OidcConfig {
-        issuer: config_issuer,
-        client_id: CLIENT_ID.to_string(),
-        client_secret: CLIENT_SECRET.to_string(),
-        redirect_uri: "http://127.0.0.1:0/auth/oidc/callback".to_string(),
-        jwks_cache: JwksCacheSettings {
-            ttl: Duration::from_secs(3600),
-            refresh: Duration::from_secs(60),
-        },
-    })
-    .await;
+    let result =
+        OidcProvider::discover(provider_config(DEFAULT_OIDC_PROVIDER, &config_issuer)).await;
 
     assert!(
         matches!(result, Err(AppError::BadRequest(message)) if message == "OIDC issuer mismatch")
     );
 }
+
+#[tokio::test]
+async fn oidc_providers_are_selected_by_path_and_map_groups_to_organizations() {
+    let keycloak = OidcFixture::setup_with_claims(json!({ "groups": ["codex-admins"] })).await;
+    let contractor =
+        OidcFixture::setup_with_claims(json!({ "realm_access": { "roles": ["external"] } })).await;
+    let app = TestApp::spawn_with(|config| {
+        let platform_admins = GroupOrganization {
+            group: "codex-admins".to_string(),
+            organization: "Platform".to_string(),
+            role: OrganizationRole::Admin,
+        };
+        let mut internal = provider_config("keycloak", &keycloak.issuer);
+        internal.group_organizations = vec![platform_admins.clone()];
+        let mut external = provider_config("contractor", &contractor.issuer);
+        external.groups_claim = "realm_access.roles".to_string();
+        external.group_organizations = vec![platform_admins];
+        config.oidc_providers = vec![internal, external];
+    })
+    .await;
+    let platform = insert_organization(&app, "Platform").await;
+    let employee =
+        insert_linked_user(&app, "employee@example.com", "member", &keycloak.issuer).await;
+    let vendor = insert_linked_user(&app, "vendor@example.com", "admin", &contractor.issuer).await;
+    for source in ["contractor", "manual"] {
+        sqlx::query(
+            "INSERT INTO organization_members (organization_id, user_id, role, source, created_at) VALUES (?, ?, 'admin', ?, ?)",
+        )
+        .bind(platform.to_string())
+        .bind(vendor.to_string())
+        .bind(source)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&app.pool)
+        .await
+        .expect("membership");
+    }
+
+    let callback = |provider: &str| {
+        let request = app
+            .client
+            .get(app.url(&format!("/auth/oidc/{provider}/callback")))
+            .query(&[("code", "test-code")]);
+        async move { request.send().await.expect("response") }
+    };
+
+    let response = callback("keycloak").await;
+    assert!(response.status().is_success());
+    let token = response.json::<TokenResponse>().await.expect("token");
+    assert_eq!(
+        decode_token(&token.access_token, &app.config).unwrap(),
+        employee
+    );
+    assert_eq!(
+        memberships(&app, employee).await,
+        vec![("admin".to_string(), "keycloak".to_string())]
+    );
+    assert_eq!(user_role(&app, employee).await, "member");
+
+    // Groups are read from the nested claim; none is mapped, so the stale
+    // membership from this provider is revoked while the manual one stays.
+    let response = callback("contractor").await;
+    assert!(response.status().is_success());
+    let token = response.json::<TokenResponse>().await.expect("token");
+    assert_eq!(
+        decode_token(&token.access_token, &app.config).unwrap(),
+        vendor
+    );
+    assert_eq!(
+        memberships(&app, vendor).await,
+        vec![("admin".to_string(), "manual".to_string())]
+    );
+    assert_eq!(user_role(&app, vendor).await, "admin");
+
+    let unknown = callback("github").await;
+    assert_eq!(unknown.status(), http::StatusCode::NOT_FOUND);
+    let ambiguous = app
+        .client
+        .get(app.url("/auth/oidc/callback"))
+        .query(&[("code", "test-code")])
+        .send()
+        .await
+        .expect("response");
+    assert_eq!(ambiguous.status(), http::StatusCode::BAD_REQUEST);
+
+    // Logging in again with the same groups changes nothing.
+    assert!(callback("keycloak").await.status().is_success());
+    let synced: i64 = sqlx::query_scalar(
+        "SELECT COUNT(1) FROM audit_log WHERE action = 'organization.member_sync'",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .expect("audit");
+    assert_eq!(synced, 2);
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::TestApp;
+use reqwest::{Method, StatusCode};
+
+fn committed_spec_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("openapi.json")
+}
+
+async fn served_spec(app: &TestApp) -> serde_json::Value {
+    let response = app
+        .client
+        .get(app.url("/openapi.json"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response.json().await.unwrap()
+}
+
+#[tokio::test]
+async fn committed_openapi_document_is_current() {
+    let app = TestApp::spawn().await;
+    let served = served_spec(&app).await;
+    let rendered = format!("{}\n", serde_json::to_string_pretty(&served).unwrap());
+
+    let path = committed_spec_path();
+    if std::env::var_os("UPDATE_OPENAPI").is_some() {
+        std::fs::write(&path, &rendered).unwrap();
+        return;
+    }
+    let committed = std::fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        committed == rendered,
+        "{} is out of date; regenerate it with `UPDATE_OPENAPI=1 cargo test --test openapi`",
+        path.display()
+    );
+}
+
+#[tokio::test]
+async fn documented_operations_are_routed() {
+    let app = TestApp::spawn().await;
+    let spec = served_spec(&app).await;
+
+    let paths = spec["paths"].as_object().unwrap();
+    assert!(paths.len() > 10);
+    for (path, item) in paths {
+        let concrete = path
+            .split('/')
+            .map(|segment| {
+                if segment.starts_with('{') {
+                    "00000000-0000-0000-0000-000000000000"
+                } else {
+                    segment
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+
+        for method in item.as_object().unwrap().keys() {
+            let method = Method::from_bytes(method.to_uppercase().as_bytes()).unwrap();
+            let response = app
+                .client
+                .request(method.clone(), app.url(&concrete))
+                .json(&serde_json::json!({}))
+                .send()
+                .await
+                .unwrap();
+            let status = response.status();
+            let body = response.text().await.unwrap();
+
+            // Unmatched routes get axum's empty 404; handlers always send a body.
+            assert_ne!(status, StatusCode::METHOD_NOT_ALLOWED, "{method} {path}");
+            assert!(
+                status != StatusCode::NOT_FOUND || !body.is_empty(),
+                "{method} {path} is documented but not routed"
+            );
+        }
+    }
+}
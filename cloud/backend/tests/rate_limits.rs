@@ -0,0 +1,157 @@
+mod common;
+
+use codex_cloud_backend::config::RateLimit;
+use common::{login, TestApp};
+use reqwest::StatusCode;
+use serde_json::json;
+
+async fn spawn_limited(default: RateLimit, auth: RateLimit) -> TestApp {
+    TestApp::spawn_with(|config| {
+        config.rate_limit.enabled = true;
+        config.rate_limit.default = default;
+        config.rate_limit.auth = auth;
+        // The test client connects from loopback, standing in for a proxy.
+        config.trusted_proxies = vec!["127.0.0.1/32".parse().unwrap()];
+    })
+    .await
+}
+
+async fn attempt_login(app: &TestApp, forwarded_for: &str) -> reqwest::Response {
+    app.client
+        .post(app.url("/auth/session"))
+        .header("X-Forwarded-For", forwarded_for)
+        .json(&json!({
+            "email": "nobody@example.com",
+            "password": "guess"
+        }))
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn login_attempts_are_limited_per_client_ip() {
+    let app = spawn_limited(
+        RateLimit {
+            per_minute: 600,
+            burst: 100,
+        },
+        RateLimit {
+            per_minute: 1,
+            burst: 2,
+        },
+    )
+    .await;
+
+    for _ in 0..2 {
+        let response = attempt_login(&app, "203.0.113.7").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+    let limited = attempt_login(&app, "203.0.113.7").await;
+    assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after = limited.headers()["retry-after"]
+        .to_str()
+        .unwrap()
+        .parse::<u64>()
+        .unwrap();
+    assert!((1..=60).contains(&retry_after));
+    assert_eq!(
+        limited.json::<serde_json::Value>().await.unwrap()["detail"],
+        "Rate limit exceeded"
+    );
+
+    let other_client = attempt_login(&app, "198.51.100.20").await;
+    assert_eq!(other_client.status(), StatusCode::UNAUTHORIZED);
+
+    // Other routes use the default policy and are unaffected.
+    let public = app
+        .client
+        .get(app.url("/openapi.json"))
+        .header("X-Forwarded-For", "203.0.113.7")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(public.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn authenticated_requests_are_limited_per_user_and_reported_in_metrics() {
+    let app = spawn_limited(
+        RateLimit {
+            per_minute: 1,
+            burst: 3,
+        },
+        RateLimit {
+            per_minute: 600,
+            burst: 100,
+        },
+    )
+    .await;
+    let alice = login(&app, "alice@example.com").await;
+    let bob = login(&app, "bob@example.com").await;
+
+    let list = |auth: String| {
+        let request = app
+            .client
+            .get(app.url("/repositories"))
+            .header("Authorization", auth);
+        async move { request.send().await.unwrap().status() }
+    };
+    for _ in 0..3 {
+        assert_eq!(list(alice.clone()).await, StatusCode::OK);
+    }
+    assert_eq!(list(alice.clone()).await, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(list(bob.clone()).await, StatusCode::OK);
+
+    // Probes are never limited.
+    for _ in 0..5 {
+        let health = app.client.get(app.url("/health")).send().await.unwrap();
+        assert_eq!(health.status(), StatusCode::OK);
+    }
+
+    let metrics = app
+        .client
+        .get(app.url("/metrics"))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(metrics.contains("codex_rate_limit_rejected_total{policy=\"default\"} 1"));
+    assert!(metrics.contains("codex_rate_limit_allowed_total{policy=\"auth\"} 4"));
+    assert!(metrics.contains("codex_rate_limit_burst{policy=\"default\"} 3"));
+}
+
+#[tokio::test]
+async fn forwarded_for_is_only_believed_from_trusted_proxies() {
+    let strict = RateLimit {
+        per_minute: 1,
+        burst: 1,
+    };
+    let generous = RateLimit {
+        per_minute: 600,
+        burst: 100,
+    };
+
+    let direct = TestApp::spawn_with(|config| {
+        config.rate_limit.enabled = true;
+        config.rate_limit.default = generous;
+        config.rate_limit.auth = strict;
+    })
+    .await;
+    let first = attempt_login(&direct, "203.0.113.7").await;
+    assert_eq!(first.status(), StatusCode::UNAUTHORIZED);
+    let spoofed = attempt_login(&direct, "198.51.100.20").await;
+    assert_eq!(spoofed.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // Behind a proxy, the client controls everything left of the hop the
+    // proxy appended.
+    let proxied = spawn_limited(generous, strict).await;
+    let first = attempt_login(&proxied, "198.51.100.1, 203.0.113.7").await;
+    assert_eq!(first.status(), StatusCode::UNAUTHORIZED);
+    let spoofed = attempt_login(&proxied, "198.51.100.2, 203.0.113.7").await;
+    assert_eq!(spoofed.status(), StatusCode::TOO_MANY_REQUESTS);
+    let other_client = attempt_login(&proxied, "198.51.100.2, 203.0.113.8").await;
+    assert_eq!(other_client.status(), StatusCode::UNAUTHORIZED);
+}
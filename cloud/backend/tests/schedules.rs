@@ -0,0 +1,369 @@
+mod common;
+
+use std::time::Duration;
+
+use chrono::{Datelike, Timelike, Weekday};
+use common::{login, login_admin, TestApp};
+use reqwest::StatusCode;
+use serde_json::json;
+
+async fn create_environment(app: &TestApp, auth_header: &str) {
+    let repo = app
+        .client
+        .post(app.url("/repositories"))
+        .header("Authorization", auth_header)
+        .json(&json!({
+            "name": "codex",
+            "git_url": "https://github.com/example/codex.git",
+            "default_branch": "main"
+        }))
+        .send()
+        .await
+        .unwrap();
+    let repository_id = repo.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let env = app
+        .client
+        .post(app.url("/environments"))
+        .header("Authorization", auth_header)
+        .json(&json!({
+            "id": "chores",
+            "repository_id": repository_id,
+            "branch": "main"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(env.status(), 201);
+}
+
+async fn list_schedules(app: &TestApp, auth_header: &str) -> Vec<serde_json::Value> {
+    app.client
+        .get(app.url("/schedules"))
+        .header("Authorization", auth_header)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+async fn environment_tasks(app: &TestApp, auth_header: &str) -> Vec<serde_json::Value> {
+    app.client
+        .get(app.url("/tasks?environment_id=chores"))
+        .header("Authorization", auth_header)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+/// Marks the schedule as overdue so the next scheduler pass picks it up.
+async fn make_due(app: &TestApp, schedule_id: &str) {
+    sqlx::query("UPDATE task_schedules SET next_run_at = '2000-01-01T00:00:00+00:00' WHERE id = ?")
+        .bind(schedule_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+}
+
+/// Polls the schedule list until `done` holds for the first schedule.
+async fn wait_for_schedule(
+    app: &TestApp,
+    auth_header: &str,
+    done: impl Fn(&serde_json::Value) -> bool,
+) -> serde_json::Value {
+    for _ in 0..100 {
+        let schedules = list_schedules(app, auth_header).await;
+        if done(&schedules[0]) {
+            return schedules[0].clone();
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("scheduler did not run");
+}
+
+fn is_due(schedule: &serde_json::Value) -> bool {
+    schedule["next_run_at"]
+        .as_str()
+        .unwrap()
+        .starts_with("2000-")
+}
+
+#[tokio::test]
+async fn schedules_materialize_tasks_and_skip_while_previous_run_is_active() {
+    let app = TestApp::spawn_with(|config| {
+        config.scheduler.poll_interval = Duration::from_millis(50);
+    })
+    .await;
+    let auth_header = login(&app, "chores@example.com").await;
+    create_environment(&app, &auth_header).await;
+
+    let invalid = app
+        .client
+        .post(app.url("/schedules"))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "name": "Broken",
+            "cron": "every monday",
+            "environment_id": "chores",
+            "prompt": "Nothing"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+    let schedule = app
+        .client
+        .post(app.url("/schedules"))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "name": "Bump lockfiles",
+            "cron": "0 6 * * MON",
+            "environment_id": "chores",
+            "prompt": "Update every lockfile to the latest compatible versions",
+            "best_of_n": 2
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(schedule.status(), StatusCode::CREATED);
+    let schedule = schedule.json::<serde_json::Value>().await.unwrap();
+    let schedule_id = schedule["id"].as_str().unwrap().to_string();
+    let next_run_at =
+        chrono::DateTime::parse_from_rfc3339(schedule["next_run_at"].as_str().unwrap()).unwrap();
+    assert_eq!(next_run_at.weekday(), Weekday::Mon);
+    assert_eq!((next_run_at.hour(), next_run_at.minute()), (6, 0));
+
+    make_due(&app, &schedule_id).await;
+    let schedule = wait_for_schedule(&app, &auth_header, |schedule| {
+        !is_due(schedule) && !schedule["last_task_id"].is_null()
+    })
+    .await;
+    let first_task_id = schedule["last_task_id"].as_str().unwrap().to_string();
+    let tasks = environment_tasks(&app, &auth_header).await;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0]["id"], first_task_id);
+    assert_eq!(tasks[0]["title"], "Bump lockfiles");
+    assert_eq!(tasks[0]["status"], "pending");
+
+    let detail = app
+        .client
+        .get(app.url(&format!("/tasks/{first_task_id}")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(detail["best_of_n"], 2);
+    assert_eq!(
+        detail["description"],
+        "Update every lockfile to the latest compatible versions"
+    );
+
+    // The first task is still pending, so the next occurrence is skipped.
+    make_due(&app, &schedule_id).await;
+    let schedule = wait_for_schedule(&app, &auth_header, |schedule| !is_due(schedule)).await;
+    assert_eq!(schedule["last_task_id"], first_task_id);
+    assert_eq!(environment_tasks(&app, &auth_header).await.len(), 1);
+
+    let trigger = app
+        .client
+        .post(app.url(&format!("/schedules/{schedule_id}/trigger")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(trigger.status(), StatusCode::CONFLICT);
+
+    sqlx::query("UPDATE tasks SET status = 'review' WHERE id = ?")
+        .bind(&first_task_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let trigger = app
+        .client
+        .post(app.url(&format!("/schedules/{schedule_id}/trigger")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(trigger.status(), StatusCode::CREATED);
+    let second_task_id = trigger.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert_ne!(second_task_id, first_task_id);
+    let schedules = list_schedules(&app, &auth_header).await;
+    assert_eq!(schedules[0]["last_task_id"], second_task_id);
+    assert_eq!(schedules[0]["next_run_at"], schedule["next_run_at"]);
+}
+
+#[tokio::test]
+async fn paused_schedules_do_not_run() {
+    let app = TestApp::spawn_with(|config| {
+        config.scheduler.poll_interval = Duration::from_millis(50);
+    })
+    .await;
+    let auth_header = login(&app, "chores@example.com").await;
+    create_environment(&app, &auth_header).await;
+
+    let schedule = app
+        .client
+        .post(app.url("/schedules"))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "name": "Triage flaky tests",
+            "cron": "30 9 * * FRI",
+            "environment_id": "chores",
+            "prompt": "Find and quarantine flaky tests"
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let schedule_id = schedule["id"].as_str().unwrap().to_string();
+
+    let pause = app
+        .client
+        .post(app.url(&format!("/schedules/{schedule_id}/pause")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(pause.status(), StatusCode::OK);
+    assert_eq!(
+        pause.json::<serde_json::Value>().await.unwrap()["paused"],
+        true
+    );
+
+    make_due(&app, &schedule_id).await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(environment_tasks(&app, &auth_header).await.is_empty());
+
+    // Resuming skips the occurrences missed while paused.
+    let resume = app
+        .client
+        .post(app.url(&format!("/schedules/{schedule_id}/resume")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(resume["paused"], false);
+    assert!(!resume["next_run_at"].as_str().unwrap().starts_with("2000-"));
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(environment_tasks(&app, &auth_header).await.is_empty());
+}
+
+#[tokio::test]
+async fn only_the_owner_or_an_admin_can_change_a_schedule() {
+    let app = TestApp::spawn().await;
+    let auth_header = login(&app, "chores@example.com").await;
+    let other = login(&app, "someone@example.com").await;
+    let admin = login_admin(&app, "ops@example.com").await;
+    create_environment(&app, &auth_header).await;
+
+    let schedule = app
+        .client
+        .post(app.url("/schedules"))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "name": "Weekly dependency bump",
+            "cron": "0 6 * * MON",
+            "environment_id": "chores",
+            "prompt": "Bump dependencies"
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let schedule_id = schedule["id"].as_str().unwrap().to_string();
+
+    for action in ["pause", "resume", "trigger"] {
+        let response = app
+            .client
+            .post(app.url(&format!("/schedules/{schedule_id}/{action}")))
+            .header("Authorization", &other)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN, "{action}");
+    }
+    assert_eq!(list_schedules(&app, &auth_header).await[0]["paused"], false);
+    assert!(environment_tasks(&app, &auth_header).await.is_empty());
+    assert!(list_schedules(&app, &other).await.is_empty());
+    assert_eq!(list_schedules(&app, &admin).await.len(), 1);
+
+    let pause = app
+        .client
+        .post(app.url(&format!("/schedules/{schedule_id}/pause")))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(pause.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn schedules_of_deactivated_users_do_not_run() {
+    let app = TestApp::spawn_with(|config| {
+        config.scheduler.poll_interval = Duration::from_millis(50);
+    })
+    .await;
+    let auth_header = login(&app, "chores@example.com").await;
+    let admin = login_admin(&app, "ops@example.com").await;
+    create_environment(&app, &auth_header).await;
+
+    let schedule = app
+        .client
+        .post(app.url("/schedules"))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "name": "Weekly dependency bump",
+            "cron": "0 6 * * MON",
+            "environment_id": "chores",
+            "prompt": "Bump dependencies"
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let schedule_id = schedule["id"].as_str().unwrap().to_string();
+
+    sqlx::query("UPDATE users SET deactivated_at = '2000-01-01T00:00:00+00:00' WHERE email = ?")
+        .bind("chores@example.com")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    make_due(&app, &schedule_id).await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(environment_tasks(&app, &admin).await.is_empty());
+
+    let trigger = app
+        .client
+        .post(app.url(&format!("/schedules/{schedule_id}/trigger")))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(trigger.status(), StatusCode::CONFLICT);
+    assert!(environment_tasks(&app, &admin).await.is_empty());
+}
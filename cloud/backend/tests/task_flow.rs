@@ -124,7 +124,13 @@ async fn end_to_end_task_flow() {
     assert_eq!(detail_body["status"], "review");
     assert_eq!(detail_body["attempts"].as_array().unwrap().len(), 1);
 
-    let artifact = app.client.get(diff_url).send().await.unwrap();
+    let artifact = app
+        .client
+        .get(diff_url)
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
     assert!(artifact.status().is_success());
     let artifact_body = artifact.text().await.unwrap();
     assert!(artifact_body.contains("diff --git"));
@@ -0,0 +1,384 @@
+mod common;
+
+use common::{claim, create_repository, login, start_attempt, TestApp};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+async fn create_group(app: &TestApp, auth_header: &str, body: Value) -> reqwest::Response {
+    app.client
+        .post(app.url("/task-groups"))
+        .header("Authorization", auth_header)
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn get_task(app: &TestApp, auth_header: &str, task_id: &str) -> Value {
+    app.client
+        .get(app.url(&format!("/tasks/{task_id}")))
+        .header("Authorization", auth_header)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+/// Claims the task, runs one successful attempt and applies it.
+async fn run_and_apply(app: &TestApp, auth_header: &str, task_id: &str, apply: Option<Value>) {
+    assert_eq!(
+        claim(app, auth_header, task_id).await.status(),
+        StatusCode::OK
+    );
+    let attempt_id = start_attempt(app, auth_header, task_id).await;
+    let complete = app
+        .client
+        .post(app.url(&format!("/tasks/attempts/{attempt_id}/complete")))
+        .header("Authorization", auth_header)
+        .json(&json!({ "status": "succeeded", "diff": "diff --git a/x b/x" }))
+        .send()
+        .await
+        .unwrap();
+    assert!(complete.status().is_success());
+
+    let mut request = app
+        .client
+        .post(app.url(&format!("/tasks/{task_id}/apply")))
+        .header("Authorization", auth_header);
+    if let Some(body) = apply {
+        request = request.json(&body);
+    }
+    let applied = request.send().await.unwrap();
+    assert_eq!(applied.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn dependent_tasks_wait_for_prerequisites_and_start_from_their_branch() {
+    let app = TestApp::spawn().await;
+    let auth = login(&app, "lead@example.com").await;
+    let repository_id = create_repository(&app, &auth).await;
+
+    let response = create_group(
+        &app,
+        &auth,
+        json!({
+            "title": "Rename accounts",
+            "repository_id": repository_id,
+            "branch": "feature/accounts",
+            "tasks": [
+                { "title": "Add migration" },
+                { "title": "Update callers", "depends_on": [0] },
+                { "title": "Update docs", "depends_on": [0] },
+                { "title": "Drop old column", "depends_on": [1, 2] }
+            ]
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let group = response.json::<Value>().await.unwrap();
+    assert_eq!(group["status"], "pending");
+    assert_eq!(group["total_tasks"], 4);
+    let tasks = group["tasks"].as_array().unwrap();
+    let ids = tasks
+        .iter()
+        .map(|task| task["id"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    let statuses = tasks
+        .iter()
+        .map(|task| task["status"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(statuses, ["pending", "blocked", "blocked", "blocked"]);
+    assert_eq!(tasks[0]["branch"], "feature/accounts");
+    assert_eq!(tasks[3]["depends_on"].as_array().unwrap().len(), 2);
+
+    // Only the root task is queued for workers, and blocked tasks cannot be
+    // claimed directly.
+    let queue = app
+        .client
+        .get(app.url("/tasks"))
+        .query(&[("status", "pending")])
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .unwrap()
+        .json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(queue.as_array().unwrap().len(), 1);
+    assert_eq!(queue[0]["id"], ids[0].as_str());
+    assert_eq!(queue[0]["group"]["title"], "Rename accounts");
+    assert_eq!(
+        claim(&app, &auth, &ids[1]).await.status(),
+        StatusCode::CONFLICT
+    );
+
+    run_and_apply(
+        &app,
+        &auth,
+        &ids[0],
+        Some(json!({ "branch": "feature/accounts-migration" })),
+    )
+    .await;
+
+    let callers = get_task(&app, &auth, &ids[1]).await;
+    assert_eq!(callers["status"], "pending");
+    assert_eq!(callers["branch"], "feature/accounts-migration");
+    assert_eq!(callers["depends_on"], json!([ids[0]]));
+    assert_eq!(callers["group"]["status"], "pending");
+    assert_eq!(callers["group"]["applied_tasks"], 1);
+    assert_eq!(get_task(&app, &auth, &ids[3]).await["status"], "blocked");
+
+    // Without a body the change is assumed to land on the branch the task ran on.
+    run_and_apply(&app, &auth, &ids[1], None).await;
+    assert_eq!(
+        get_task(&app, &auth, &ids[1]).await["result_branch"],
+        "feature/accounts-migration"
+    );
+    assert_eq!(get_task(&app, &auth, &ids[3]).await["status"], "blocked");
+
+    assert_eq!(claim(&app, &auth, &ids[2]).await.status(), StatusCode::OK);
+    let running = get_task(&app, &auth, &ids[2]).await;
+    assert_eq!(running["group"]["status"], "running");
+    app.client
+        .post(app.url(&format!("/tasks/{}/attempts", ids[2])))
+        .header("Authorization", &auth)
+        .json(&json!({}))
+        .send()
+        .await
+        .unwrap();
+    let docs_attempt = get_task(&app, &auth, &ids[2]).await["attempts"][0]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    app.client
+        .post(app.url(&format!("/tasks/attempts/{docs_attempt}/complete")))
+        .header("Authorization", &auth)
+        .json(&json!({ "status": "succeeded", "diff": "diff --git a/y b/y" }))
+        .send()
+        .await
+        .unwrap();
+    let applied = app
+        .client
+        .post(app.url(&format!("/tasks/{}/apply", ids[2])))
+        .header("Authorization", &auth)
+        .json(&json!({ "branch": "feature/accounts-docs" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(applied.status(), StatusCode::OK);
+
+    // The last prerequisite to be applied decides where the final task starts.
+    let last = get_task(&app, &auth, &ids[3]).await;
+    assert_eq!(last["status"], "pending");
+    assert_eq!(last["branch"], "feature/accounts-docs");
+    run_and_apply(&app, &auth, &ids[3], None).await;
+
+    let group = app
+        .client
+        .get(app.url(&format!("/task-groups/{}", group["id"].as_str().unwrap())))
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .unwrap()
+        .json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(group["status"], "applied");
+    assert_eq!(group["applied_tasks"], 4);
+
+    let unblocked: i64 =
+        sqlx::query_scalar("SELECT COUNT(1) FROM audit_log WHERE action = 'task.unblock'")
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(unblocked, 3);
+}
+
+#[tokio::test]
+async fn ordered_groups_chain_tasks_and_reject_forward_dependencies() {
+    let app = TestApp::spawn().await;
+    let auth = login(&app, "lead@example.com").await;
+    let repository_id = create_repository(&app, &auth).await;
+
+    let forward = create_group(
+        &app,
+        &auth,
+        json!({
+            "title": "Broken",
+            "repository_id": repository_id,
+            "tasks": [
+                { "title": "First", "depends_on": [1] },
+                { "title": "Second" }
+            ]
+        }),
+    )
+    .await;
+    assert_eq!(forward.status(), StatusCode::BAD_REQUEST);
+    let empty = create_group(
+        &app,
+        &auth,
+        json!({ "title": "Empty", "repository_id": repository_id, "tasks": [] }),
+    )
+    .await;
+    assert_eq!(empty.status(), StatusCode::BAD_REQUEST);
+
+    let group = create_group(
+        &app,
+        &auth,
+        json!({
+            "title": "Chain",
+            "repository_id": repository_id,
+            "ordered": true,
+            "tasks": [{ "title": "One" }, { "title": "Two" }, { "title": "Three" }]
+        }),
+    )
+    .await
+    .json::<Value>()
+    .await
+    .unwrap();
+    let tasks = group["tasks"].as_array().unwrap();
+    assert_eq!(tasks[1]["depends_on"], json!([tasks[0]["id"]]));
+    assert_eq!(tasks[2]["depends_on"], json!([tasks[1]["id"]]));
+
+    let listed = app
+        .client
+        .get(app.url("/tasks"))
+        .query(&[("group_id", group["id"].as_str().unwrap())])
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .unwrap()
+        .json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(listed.as_array().unwrap().len(), 3);
+    assert!(listed
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|task| task["group"]["total_tasks"] == 3));
+
+    let first = tasks[0]["id"].as_str().unwrap();
+    run_and_apply(&app, &auth, first, None).await;
+    // The group had no branch, so the next task keeps the environment default.
+    let second = get_task(&app, &auth, tasks[1]["id"].as_str().unwrap()).await;
+    assert_eq!(second["status"], "pending");
+    assert!(second["branch"].is_null());
+    assert_eq!(
+        get_task(&app, &auth, tasks[2]["id"].as_str().unwrap()).await["status"],
+        "blocked"
+    );
+}
+
+#[tokio::test]
+async fn failed_prerequisites_fail_their_dependents() {
+    let app = TestApp::spawn().await;
+    let auth = login(&app, "lead@example.com").await;
+    let repository_id = create_repository(&app, &auth).await;
+
+    let group = create_group(
+        &app,
+        &auth,
+        json!({
+            "title": "Chain",
+            "repository_id": repository_id,
+            "ordered": true,
+            "tasks": [{ "title": "One" }, { "title": "Two" }, { "title": "Three" }]
+        }),
+    )
+    .await
+    .json::<Value>()
+    .await
+    .unwrap();
+    let group_id = group["id"].as_str().unwrap();
+    let ids = group["tasks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|task| task["id"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+
+    let other = login(&app, "other@example.com").await;
+    let forbidden = app
+        .client
+        .get(app.url(&format!("/task-groups/{group_id}")))
+        .header("Authorization", &other)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+
+    assert_eq!(claim(&app, &auth, &ids[0]).await.status(), StatusCode::OK);
+    let attempt = app
+        .client
+        .post(app.url(&format!("/tasks/{}/attempts", ids[0])))
+        .header("Authorization", &auth)
+        .json(&json!({}))
+        .send()
+        .await
+        .unwrap()
+        .json::<Value>()
+        .await
+        .unwrap();
+    let complete = app
+        .client
+        .post(app.url(&format!(
+            "/tasks/attempts/{}/complete",
+            attempt["id"].as_str().unwrap()
+        )))
+        .header("Authorization", &auth)
+        .json(&json!({ "status": "failed", "failure_category": "agent" }))
+        .send()
+        .await
+        .unwrap();
+    assert!(complete.status().is_success());
+
+    for id in &ids {
+        assert_eq!(get_task(&app, &auth, id).await["status"], "failed");
+    }
+    let failed: Vec<String> = sqlx::query_scalar(
+        "SELECT target_id FROM audit_log WHERE action = 'task.fail' ORDER BY target_id",
+    )
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    let mut dependents = ids[1..].to_vec();
+    dependents.sort();
+    assert_eq!(failed, dependents);
+}
+
+#[tokio::test]
+async fn only_the_creator_can_apply_a_task() {
+    let app = TestApp::spawn().await;
+    let auth = login(&app, "lead@example.com").await;
+    let repository_id = create_repository(&app, &auth).await;
+    let group = create_group(
+        &app,
+        &auth,
+        json!({
+            "title": "Single",
+            "repository_id": repository_id,
+            "tasks": [{ "title": "Only" }]
+        }),
+    )
+    .await
+    .json::<Value>()
+    .await
+    .unwrap();
+    let task_id = group["tasks"][0]["id"].as_str().unwrap();
+
+    let other = login(&app, "other@example.com").await;
+    let applied = app
+        .client
+        .post(app.url(&format!("/tasks/{task_id}/apply")))
+        .header("Authorization", &other)
+        .json(&json!({ "branch": "attacker/branch" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(applied.status(), StatusCode::FORBIDDEN);
+
+    run_and_apply(&app, &auth, task_id, None).await;
+}
@@ -0,0 +1,298 @@
+mod common;
+
+use common::{create_repository, create_task_with, login, login_admin, TestApp};
+use reqwest::StatusCode;
+use serde_json::json;
+
+async fn create_task(
+    app: &TestApp,
+    auth_header: &str,
+    repository_id: &str,
+    title: &str,
+    priority: i64,
+) {
+    create_task_with(
+        app,
+        auth_header,
+        json!({
+            "title": title,
+            "repository_id": repository_id,
+            "priority": priority
+        }),
+    )
+    .await;
+}
+
+async fn create_codex_task(app: &TestApp, auth_header: &str, environment_id: &str) -> String {
+    let task = app
+        .client
+        .post(app.url("/api/codex/tasks"))
+        .header("Authorization", auth_header)
+        .json(&json!({
+            "new_task": { "environment_id": environment_id },
+            "input_items": [
+                {
+                    "type": "message",
+                    "role": "user",
+                    "content": [{ "content_type": "text", "text": "Fix the build" }]
+                }
+            ]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(task.status(), 201);
+    task.json::<serde_json::Value>().await.unwrap()["task"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn claim(app: &TestApp, auth_header: &str, task_id: &str) -> (StatusCode, serde_json::Value) {
+    let claim = common::claim(app, auth_header, task_id).await;
+    let status = claim.status();
+    (status, claim.json().await.unwrap())
+}
+
+#[tokio::test]
+async fn pending_queue_orders_by_priority_then_across_creators() {
+    let app = TestApp::spawn().await;
+    let alice = login(&app, "alice@example.com").await;
+    let bob = login(&app, "bob@example.com").await;
+    let repository_id = create_repository(&app, &alice).await;
+
+    create_task(&app, &alice, &repository_id, "alice-1", 0).await;
+    create_task(&app, &alice, &repository_id, "alice-2", 0).await;
+    create_task(&app, &alice, &repository_id, "alice-3", 0).await;
+    create_task(&app, &bob, &repository_id, "bob-1", 0).await;
+    create_task(&app, &bob, &repository_id, "bob-urgent", 5).await;
+
+    let pending = app
+        .client
+        .get(app.url("/tasks?status=pending"))
+        .header("Authorization", &bob)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let titles = pending
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|task| task["title"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        titles,
+        vec!["bob-urgent", "alice-1", "bob-1", "alice-2", "alice-3"]
+    );
+    assert_eq!(pending[0]["priority"], 5);
+}
+
+#[tokio::test]
+async fn pending_queue_rotates_across_organizations_before_their_members() {
+    let app = TestApp::spawn().await;
+    let alice = login(&app, "alice@example.com").await;
+    let bob = login(&app, "bob@example.com").await;
+    let carol = login(&app, "carol@example.com").await;
+    let repository_id = create_repository(&app, &alice).await;
+
+    let organization_id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    sqlx::query("INSERT INTO organizations (id, name, created_at) VALUES (?, 'Acme', ?)")
+        .bind(&organization_id)
+        .bind(&now)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO organization_members (organization_id, user_id, role, source, created_at)
+        SELECT ?, id, 'member', 'manual', ? FROM users
+        WHERE email IN ('alice@example.com', 'bob@example.com')
+        "#,
+    )
+    .bind(&organization_id)
+    .bind(&now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    create_task(&app, &alice, &repository_id, "alice-1", 0).await;
+    create_task(&app, &alice, &repository_id, "alice-2", 0).await;
+    create_task(&app, &bob, &repository_id, "bob-1", 0).await;
+    create_task(&app, &carol, &repository_id, "carol-1", 0).await;
+    create_task(&app, &carol, &repository_id, "carol-2", 0).await;
+
+    let pending = app
+        .client
+        .get(app.url("/tasks?status=pending"))
+        .header("Authorization", &carol)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let titles = pending
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|task| task["title"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    // Acme's two members share one turn per round with carol.
+    assert_eq!(
+        titles,
+        vec!["alice-1", "carol-1", "bob-1", "carol-2", "alice-2"]
+    );
+}
+
+#[tokio::test]
+async fn environment_concurrency_caps_are_enforced_on_claim() {
+    let app = TestApp::spawn().await;
+    let auth_header = login(&app, "worker@example.com").await;
+    let admin = login_admin(&app, "ops@example.com").await;
+    let repository_id = create_repository(&app, &auth_header).await;
+
+    let env = app
+        .client
+        .post(app.url("/environments"))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "id": "capped",
+            "repository_id": repository_id,
+            "branch": "main",
+            "max_concurrency": 1
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(env.status(), 201);
+    assert_eq!(
+        env.json::<serde_json::Value>().await.unwrap()["max_concurrency"],
+        1
+    );
+
+    let first = create_codex_task(&app, &auth_header, "capped").await;
+    let second = create_codex_task(&app, &auth_header, "capped").await;
+
+    let queued = app
+        .client
+        .get(app.url("/tasks?status=pending&environment_id=capped"))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(queued.as_array().unwrap().len(), 2);
+
+    let (status, _) = claim(&app, &auth_header, &first).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = claim(&app, &auth_header, &second).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["detail"], "Environment concurrency limit reached");
+
+    let forbidden = app
+        .client
+        .patch(app.url("/environments/capped"))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "max_concurrency": 2 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+
+    // An empty patch leaves the cap alone.
+    let unchanged = app
+        .client
+        .patch(app.url("/environments/capped"))
+        .header("Authorization", &admin)
+        .json(&json!({}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(unchanged.status(), StatusCode::OK);
+    assert_eq!(
+        unchanged.json::<serde_json::Value>().await.unwrap()["max_concurrency"],
+        1
+    );
+
+    let update = app
+        .client
+        .patch(app.url("/environments/capped"))
+        .header("Authorization", &admin)
+        .json(&json!({ "max_concurrency": 2 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(update.status(), StatusCode::OK);
+
+    let (status, _) = claim(&app, &auth_header, &second).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let invalid = app
+        .client
+        .patch(app.url("/environments/capped"))
+        .header("Authorization", &admin)
+        .json(&json!({ "max_concurrency": 0 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+    let removed = app
+        .client
+        .patch(app.url("/environments/capped"))
+        .header("Authorization", &admin)
+        .json(&json!({ "max_concurrency": null }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(removed.status(), StatusCode::OK);
+    assert!(removed.json::<serde_json::Value>().await.unwrap()["max_concurrency"].is_null());
+}
+
+#[tokio::test]
+async fn concurrent_claims_respect_the_environment_cap() {
+    let app = TestApp::spawn().await;
+    let auth_header = login(&app, "worker@example.com").await;
+    let repository_id = create_repository(&app, &auth_header).await;
+
+    let env = app
+        .client
+        .post(app.url("/environments"))
+        .header("Authorization", &auth_header)
+        .json(&json!({
+            "id": "single",
+            "repository_id": repository_id,
+            "branch": "main",
+            "max_concurrency": 1
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(env.status(), 201);
+
+    let mut tasks = Vec::new();
+    for _ in 0..8 {
+        tasks.push(create_codex_task(&app, &auth_header, "single").await);
+    }
+    let claims = futures::future::join_all(
+        tasks
+            .iter()
+            .map(|task_id| claim(&app, &auth_header, task_id)),
+    )
+    .await;
+
+    let claimed = claims
+        .iter()
+        .filter(|(status, _)| *status == StatusCode::OK)
+        .count();
+    assert_eq!(claimed, 1);
+    assert!(claims
+        .iter()
+        .all(|(status, _)| *status == StatusCode::OK || *status == StatusCode::CONFLICT));
+}
@@ -0,0 +1,418 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{claim, start_attempt, TestApp};
+use reqwest::StatusCode;
+use serde_json::json;
+use uuid::Uuid;
+
+async fn login(app: &TestApp) -> String {
+    app.client
+        .post(app.url("/auth/users"))
+        .json(&json!({
+            "email": "worker@example.com",
+            "password": "secret123",
+            "name": "Worker"
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    let login = app
+        .client
+        .post(app.url("/auth/session"))
+        .json(&json!({
+            "email": "worker@example.com",
+            "password": "secret123"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert!(login.status().is_success());
+    let token = login.json::<serde_json::Value>().await.unwrap()["access_token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    format!("Bearer {token}")
+}
+
+async fn create_task(app: &TestApp, auth_header: &str) -> Uuid {
+    let repo = app
+        .client
+        .post(app.url("/repositories"))
+        .header("Authorization", auth_header)
+        .json(&json!({
+            "name": "codex",
+            "git_url": "https://example.com/codex.git",
+            "default_branch": "main"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(repo.status(), 201);
+    let repository_id = repo.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let task = app
+        .client
+        .post(app.url("/tasks"))
+        .header("Authorization", auth_header)
+        .json(&json!({
+            "title": "Flaky task",
+            "description": "Exercise retries",
+            "repository_id": repository_id
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(task.status(), 201);
+    Uuid::parse_str(
+        task.json::<serde_json::Value>().await.unwrap()["id"]
+            .as_str()
+            .unwrap(),
+    )
+    .unwrap()
+}
+
+async fn fail_attempt(
+    app: &TestApp,
+    auth_header: &str,
+    task_id: Uuid,
+    category: &str,
+) -> (StatusCode, serde_json::Value) {
+    let claim = app
+        .client
+        .post(app.url(&format!("/tasks/{task_id}/claim")))
+        .header("Authorization", auth_header)
+        .send()
+        .await
+        .unwrap();
+    if !claim.status().is_success() {
+        let status = claim.status();
+        return (status, claim.json().await.unwrap());
+    }
+
+    let attempt = app
+        .client
+        .post(app.url(&format!("/tasks/{task_id}/attempts")))
+        .header("Authorization", auth_header)
+        .json(&json!({}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(attempt.status(), 201);
+    let attempt_id = attempt.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let complete = app
+        .client
+        .post(app.url(&format!("/tasks/attempts/{attempt_id}/complete")))
+        .header("Authorization", auth_header)
+        .json(&json!({
+            "status": "failed",
+            "log": "boom",
+            "failure_category": category
+        }))
+        .send()
+        .await
+        .unwrap();
+    let status = complete.status();
+    (status, complete.json().await.unwrap())
+}
+
+#[tokio::test]
+async fn infra_failures_back_off_before_the_task_is_claimable_again() {
+    let app = TestApp::spawn_with(|config| {
+        config.task_retry.backoff_base = Duration::from_secs(600);
+    })
+    .await;
+    let auth_header = login(&app).await;
+    let task_id = create_task(&app, &auth_header).await;
+
+    let (status, body) = fail_attempt(&app, &auth_header, task_id, "infra").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["task_status"], "pending");
+    assert!(body["retry_after"].is_string());
+
+    let (status, body) = fail_attempt(&app, &auth_header, task_id, "infra").await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["detail"], "Task is waiting to be retried");
+
+    let detail = app
+        .client
+        .get(app.url(&format!("/tasks/{task_id}")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(detail["attempts"][0]["failure_category"], "infra");
+}
+
+#[tokio::test]
+async fn retryable_failures_stop_once_the_attempt_budget_is_spent() {
+    let app = TestApp::spawn_with(|config| {
+        config.task_retry.max_attempts = 2;
+        config.task_retry.backoff_base = Duration::ZERO;
+    })
+    .await;
+    let auth_header = login(&app).await;
+    let task_id = create_task(&app, &auth_header).await;
+
+    let (_, body) = fail_attempt(&app, &auth_header, task_id, "timeout").await;
+    assert_eq!(body["task_status"], "pending");
+
+    let (_, body) = fail_attempt(&app, &auth_header, task_id, "infra").await;
+    assert_eq!(body["task_status"], "failed");
+    assert!(body["retry_after"].is_null());
+
+    let (status, _) = fail_attempt(&app, &auth_header, task_id, "infra").await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn only_failed_attempts_spend_the_attempt_budget() {
+    let app = TestApp::spawn_with(|config| {
+        config.task_retry.max_attempts = 2;
+        config.task_retry.backoff_base = Duration::ZERO;
+    })
+    .await;
+    let auth_header = login(&app).await;
+    let task_id = create_task(&app, &auth_header).await;
+    let task = task_id.to_string();
+
+    assert!(claim(&app, &auth_header, &task).await.status().is_success());
+    let released = start_attempt(&app, &auth_header, &task).await;
+    let release = app
+        .client
+        .post(app.url(&format!("/tasks/attempts/{released}/release")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(release.status(), StatusCode::NO_CONTENT);
+
+    assert!(claim(&app, &auth_header, &task).await.status().is_success());
+    let succeeded = start_attempt(&app, &auth_header, &task).await;
+    let complete = app
+        .client
+        .post(app.url(&format!("/tasks/attempts/{succeeded}/complete")))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "status": "succeeded", "diff": "diff --git a/x b/x" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(complete.status(), StatusCode::OK);
+
+    // The task went back from review; the released and succeeded attempts
+    // leave the whole budget for failures.
+    let (_, body) = fail_attempt(&app, &auth_header, task_id, "infra").await;
+    assert_eq!(body["task_status"], "pending");
+    let (_, body) = fail_attempt(&app, &auth_header, task_id, "infra").await;
+    assert_eq!(body["task_status"], "failed");
+}
+
+#[tokio::test]
+async fn attempts_only_complete_as_succeeded_or_failed() {
+    let app = TestApp::spawn().await;
+    let auth_header = login(&app).await;
+    let task_id = create_task(&app, &auth_header).await.to_string();
+
+    assert!(claim(&app, &auth_header, &task_id)
+        .await
+        .status()
+        .is_success());
+    let attempt_id = start_attempt(&app, &auth_header, &task_id).await;
+
+    for status in ["running", "queued", "cancelled"] {
+        let complete = app
+            .client
+            .post(app.url(&format!("/tasks/attempts/{attempt_id}/complete")))
+            .header("Authorization", &auth_header)
+            .json(&json!({ "status": status }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(complete.status(), StatusCode::BAD_REQUEST, "{status}");
+    }
+
+    let detail = app
+        .client
+        .get(app.url(&format!("/tasks/{task_id}")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(detail["attempts"][0]["status"], "running");
+}
+
+#[tokio::test]
+async fn agent_failures_are_not_retried() {
+    let app = TestApp::spawn().await;
+    let auth_header = login(&app).await;
+    let task_id = create_task(&app, &auth_header).await;
+
+    let (status, body) = fail_attempt(&app, &auth_header, task_id, "agent").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["task_status"], "failed");
+
+    let pending = app
+        .client
+        .get(app.url("/tasks?status=pending"))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert!(pending.as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn released_attempts_return_the_task_to_the_queue() {
+    let app = TestApp::spawn().await;
+    let auth_header = login(&app).await;
+    let task_id = create_task(&app, &auth_header).await;
+
+    let claim = app
+        .client
+        .post(app.url(&format!("/tasks/{task_id}/claim")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert!(claim.status().is_success());
+
+    let attempt = app
+        .client
+        .post(app.url(&format!("/tasks/{task_id}/attempts")))
+        .header("Authorization", &auth_header)
+        .json(&json!({}))
+        .send()
+        .await
+        .unwrap();
+    let attempt_id = attempt.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let release = app
+        .client
+        .post(app.url(&format!("/tasks/attempts/{attempt_id}/release")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(release.status(), StatusCode::NO_CONTENT);
+
+    let detail = app
+        .client
+        .get(app.url(&format!("/tasks/{task_id}")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(detail["status"], "pending");
+    assert!(detail["assignee_id"].is_null());
+    assert_eq!(detail["attempts"][0]["status"], "cancelled");
+
+    let release_again = app
+        .client
+        .post(app.url(&format!("/tasks/attempts/{attempt_id}/release")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(release_again.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn late_completions_of_settled_attempts_are_rejected() {
+    let app = TestApp::spawn().await;
+    let auth_header = login(&app).await;
+    let task_id = create_task(&app, &auth_header).await.to_string();
+
+    assert!(claim(&app, &auth_header, &task_id)
+        .await
+        .status()
+        .is_success());
+    let released = start_attempt(&app, &auth_header, &task_id).await;
+    let release = app
+        .client
+        .post(app.url(&format!("/tasks/attempts/{released}/release")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(release.status(), StatusCode::NO_CONTENT);
+
+    // The same worker picks the task up again before the old run reports.
+    assert!(claim(&app, &auth_header, &task_id)
+        .await
+        .status()
+        .is_success());
+    let current = start_attempt(&app, &auth_header, &task_id).await;
+
+    let complete = |attempt_id: String| {
+        let request = app
+            .client
+            .post(app.url(&format!("/tasks/attempts/{attempt_id}/complete")))
+            .header("Authorization", &auth_header)
+            .json(&json!({
+                "status": "succeeded",
+                "diff": "diff --git a/x b/x",
+                "usage": {
+                    "input_tokens": 100,
+                    "cached_input_tokens": 0,
+                    "output_tokens": 10,
+                    "reasoning_output_tokens": 0,
+                    "wall_time_ms": 1000
+                }
+            }));
+        async move { request.send().await.unwrap() }
+    };
+
+    let late = complete(released.clone()).await;
+    assert_eq!(late.status(), StatusCode::CONFLICT);
+    assert_eq!(
+        late.json::<serde_json::Value>().await.unwrap()["detail"],
+        "Attempt is not running"
+    );
+
+    assert_eq!(complete(current.clone()).await.status(), StatusCode::OK);
+    assert_eq!(complete(current).await.status(), StatusCode::CONFLICT);
+
+    let detail = app
+        .client
+        .get(app.url(&format!("/tasks/{task_id}")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(detail["status"], "review");
+    let released_attempt = detail["attempts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|attempt| attempt["id"] == released.as_str())
+        .unwrap();
+    assert_eq!(released_attempt["status"], "cancelled");
+    assert!(released_attempt["usage"].is_null());
+}
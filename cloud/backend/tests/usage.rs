@@ -0,0 +1,252 @@
+mod common;
+
+use common::{claim, create_task, login, login_admin, TestApp};
+use reqwest::StatusCode;
+use serde_json::json;
+
+#[tokio::test]
+async fn attempt_usage_is_reported_and_enforced_by_quotas() {
+    let app = TestApp::spawn().await;
+    let worker = login(&app, "worker@example.com").await;
+    let admin = login_admin(&app, "finance@example.com").await;
+
+    let repo = app
+        .client
+        .post(app.url("/repositories"))
+        .header("Authorization", &worker)
+        .json(&json!({
+            "name": "codex",
+            "git_url": "https://example.com/codex.git",
+            "default_branch": "main"
+        }))
+        .send()
+        .await
+        .unwrap();
+    let repository_id = repo.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let task_id = create_task(&app, &worker, &repository_id).await;
+    assert!(claim(&app, &worker, &task_id).await.status().is_success());
+    let attempt = app
+        .client
+        .post(app.url(&format!("/tasks/{task_id}/attempts")))
+        .header("Authorization", &worker)
+        .json(&json!({}))
+        .send()
+        .await
+        .unwrap();
+    let attempt_id = attempt.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let complete = app
+        .client
+        .post(app.url(&format!("/tasks/attempts/{attempt_id}/complete")))
+        .header("Authorization", &worker)
+        .json(&json!({
+            "status": "succeeded",
+            "diff": "diff --git a/x b/x",
+            "usage": {
+                "input_tokens": 120,
+                "cached_input_tokens": 80,
+                "output_tokens": 30,
+                "reasoning_output_tokens": 10,
+                "wall_time_ms": 4500,
+                "cpu_time_ms": 1200
+            }
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert!(complete.status().is_success());
+
+    let detail = app
+        .client
+        .get(app.url(&format!("/tasks/{task_id}")))
+        .header("Authorization", &worker)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(detail["attempts"][0]["usage"]["output_tokens"], 30);
+
+    let forbidden = app
+        .client
+        .get(app.url("/admin/usage"))
+        .header("Authorization", &worker)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+
+    let by_repository = app
+        .client
+        .get(app.url("/admin/usage?group_by=repository"))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        by_repository,
+        json!([{
+            "key": repository_id,
+            "attempts": 1,
+            "input_tokens": 120,
+            "cached_input_tokens": 80,
+            "output_tokens": 30,
+            "reasoning_output_tokens": 10,
+            "wall_time_ms": 4500,
+            "cpu_time_ms": 1200
+        }])
+    );
+    let future_window = app
+        .client
+        .get(app.url("/admin/usage?since=2999-01-01T00:00:00Z"))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(future_window, json!([]));
+
+    let by_user = app
+        .client
+        .get(app.url("/admin/usage?group_by=user"))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let worker_id = by_user[0]["key"].as_str().unwrap().to_string();
+    assert_eq!(by_user[0]["input_tokens"], 120);
+
+    let organization = app
+        .client
+        .post(app.url("/admin/organizations"))
+        .header("Authorization", &admin)
+        .json(&json!({ "name": "Acme" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(organization.status(), StatusCode::CREATED);
+    let organization_id = organization.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let admin_id = app
+        .client
+        .get(app.url("/admin/users"))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|user| user["email"] == "finance@example.com")
+        .unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    for (user_id, role) in [(&worker_id, "member"), (&admin_id, "admin")] {
+        let member = app
+            .client
+            .put(app.url(&format!(
+                "/admin/organizations/{organization_id}/members/{user_id}"
+            )))
+            .header("Authorization", &admin)
+            .json(&json!({ "role": role }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(member.status(), StatusCode::NO_CONTENT);
+    }
+
+    let by_organization = app
+        .client
+        .get(app.url("/admin/usage?group_by=organization"))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(by_organization[0]["key"], organization_id.as_str());
+    assert_eq!(by_organization[0]["input_tokens"], 120);
+
+    let quota = app
+        .client
+        .put(app.url(&format!("/admin/quotas/{organization_id}")))
+        .header("Authorization", &admin)
+        .json(&json!({ "monthly_token_limit": 100 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(quota.status(), StatusCode::NO_CONTENT);
+    let quotas = app
+        .client
+        .get(app.url("/admin/quotas"))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(quotas[0]["organization_id"], organization_id.as_str());
+    assert_eq!(quotas[0]["monthly_token_limit"], 100);
+    assert_eq!(quotas[0]["used_tokens"], 150);
+
+    // The worker's usage counts against every member of the organization.
+    let teammate_task = create_task(&app, &admin, &repository_id).await;
+    let refused = claim(&app, &worker, &teammate_task).await;
+    assert_eq!(refused.status(), StatusCode::CONFLICT);
+    assert!(refused
+        .text()
+        .await
+        .unwrap()
+        .contains("Monthly token quota of organization Acme exceeded"));
+
+    let over_budget = create_task(&app, &worker, &repository_id).await;
+    let left = app
+        .client
+        .delete(app.url(&format!(
+            "/admin/organizations/{organization_id}/members/{worker_id}"
+        )))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(left.status(), StatusCode::NO_CONTENT);
+    assert!(claim(&app, &worker, &over_budget)
+        .await
+        .status()
+        .is_success());
+
+    let cleared = app
+        .client
+        .put(app.url(&format!("/admin/quotas/{organization_id}")))
+        .header("Authorization", &admin)
+        .json(&json!({ "monthly_token_limit": null }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(cleared.status(), StatusCode::NO_CONTENT);
+    assert!(claim(&app, &worker, &teammate_task)
+        .await
+        .status()
+        .is_success());
+}
@@ -0,0 +1,345 @@
+mod common;
+
+use codex_cloud_backend::db::{self, ExternalIdentitySeed};
+use codex_cloud_backend::security::hash_password;
+use common::TestApp;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+async fn register(app: &TestApp, body: Value) -> reqwest::Response {
+    app.client
+        .post(app.url("/auth/users"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn session(app: &TestApp, email: &str, password: &str) -> reqwest::Response {
+    app.client
+        .post(app.url("/auth/session"))
+        .json(&json!({
+            "email": email,
+            "password": password
+        }))
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn login(app: &TestApp, email: &str, password: &str) -> String {
+    let response = session(app, email, password).await;
+    assert!(response.status().is_success());
+    let token = response.json::<Value>().await.unwrap()["access_token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    format!("Bearer {token}")
+}
+
+/// Inserts an admin the way the `create-admin` command does.
+async fn seed_admin(app: &TestApp, email: &str) -> Uuid {
+    let user_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO users (id, email, name, password_hash, auth_provider, created_at, role)
+        VALUES (?, ?, NULL, ?, 'local', ?, 'admin')
+        "#,
+    )
+    .bind(user_id.to_string())
+    .bind(email)
+    .bind(hash_password("secret123").unwrap())
+    .bind(chrono::Utc::now().to_rfc3339())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    user_id
+}
+
+async fn create_invite(app: &TestApp, admin: &str, body: Value) -> Value {
+    let response = app
+        .client
+        .post(app.url("/admin/invites"))
+        .header("Authorization", admin)
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    response.json().await.unwrap()
+}
+
+#[tokio::test]
+async fn closed_registration_requires_a_valid_invite() {
+    let app = TestApp::spawn_with(|config| {
+        config.registration.open = false;
+        config.registration.invite_base_url = Some("https://codex.example.com/signup".to_string());
+    })
+    .await;
+    seed_admin(&app, "root@example.com").await;
+    let admin = login(&app, "root@example.com", "secret123").await;
+
+    let open = register(
+        &app,
+        json!({ "email": "walk-in@example.com", "password": "secret123" }),
+    )
+    .await;
+    assert_eq!(open.status(), StatusCode::FORBIDDEN);
+
+    let invite = create_invite(
+        &app,
+        &admin,
+        json!({ "email": "ops@example.com", "role": "admin" }),
+    )
+    .await;
+    let token = invite["token"].as_str().unwrap().to_string();
+    assert_eq!(
+        invite["url"],
+        format!("https://codex.example.com/signup?invite={token}")
+    );
+
+    let wrong_email = register(
+        &app,
+        json!({ "email": "other@example.com", "password": "secret123", "invite_token": token }),
+    )
+    .await;
+    assert_eq!(wrong_email.status(), StatusCode::FORBIDDEN);
+    let bogus = register(
+        &app,
+        json!({ "email": "ops@example.com", "password": "secret123", "invite_token": "nope" }),
+    )
+    .await;
+    assert_eq!(bogus.status(), StatusCode::FORBIDDEN);
+
+    let accepted = register(
+        &app,
+        json!({ "email": "ops@example.com", "password": "secret123", "invite_token": token }),
+    )
+    .await;
+    assert_eq!(accepted.status(), StatusCode::CREATED);
+    let reused = register(
+        &app,
+        json!({ "email": "ops@example.com", "password": "secret123", "invite_token": token }),
+    )
+    .await;
+    assert_eq!(reused.status(), StatusCode::FORBIDDEN);
+
+    // The invite granted the admin role.
+    let ops = login(&app, "ops@example.com", "secret123").await;
+    let invites = app
+        .client
+        .get(app.url("/admin/invites"))
+        .header("Authorization", &ops)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(invites.status(), StatusCode::OK);
+    let invites = invites.json::<Value>().await.unwrap();
+    assert!(invites[0]["accepted_at"].is_string());
+    assert!(invites[0].get("token").is_none());
+
+    let expired = create_invite(&app, &admin, json!({})).await;
+    sqlx::query("UPDATE invites SET expires_at = ? WHERE id = ?")
+        .bind((chrono::Utc::now() - chrono::Duration::minutes(1)).to_rfc3339())
+        .bind(expired["id"].as_str().unwrap())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let too_late = register(
+        &app,
+        json!({
+            "email": "late@example.com",
+            "password": "secret123",
+            "invite_token": expired["token"]
+        }),
+    )
+    .await;
+    assert_eq!(too_late.status(), StatusCode::FORBIDDEN);
+
+    let revoked = create_invite(&app, &admin, json!({})).await;
+    let revoke = app
+        .client
+        .delete(app.url(&format!(
+            "/admin/invites/{}",
+            revoked["id"].as_str().unwrap()
+        )))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(revoke.status(), StatusCode::NO_CONTENT);
+    let after_revoke = register(
+        &app,
+        json!({
+            "email": "revoked@example.com",
+            "password": "secret123",
+            "invite_token": revoked["token"]
+        }),
+    )
+    .await;
+    assert_eq!(after_revoke.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn admins_manage_users_and_deactivated_users_are_rejected() {
+    let app = TestApp::spawn().await;
+    let admin_id = seed_admin(&app, "root@example.com").await;
+    let admin = login(&app, "root@example.com", "secret123").await;
+
+    let created = register(
+        &app,
+        json!({ "email": "dev@example.com", "password": "secret123" }),
+    )
+    .await;
+    assert_eq!(created.status(), StatusCode::CREATED);
+    let dev_id = created.json::<Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let dev = login(&app, "dev@example.com", "secret123").await;
+
+    let forbidden = app
+        .client
+        .get(app.url("/admin/users"))
+        .header("Authorization", &dev)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+
+    db::seed_external_identities(
+        &app.pool,
+        &[ExternalIdentitySeed {
+            issuer: "https://idp.example.com",
+            subject: "dev-subject",
+            user_id: Uuid::parse_str(&dev_id).unwrap(),
+            email: Some("dev@example.com"),
+        }],
+    )
+    .await
+    .unwrap();
+    let users = app
+        .client
+        .get(app.url("/admin/users"))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap()
+        .json::<Value>()
+        .await
+        .unwrap();
+    let listed = users
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|user| user["id"] == dev_id.as_str())
+        .unwrap();
+    assert_eq!(listed["role"], "member");
+    assert!(listed["deactivated_at"].is_null());
+    let identity = &listed["external_identities"][0];
+    assert_eq!(identity["subject"], "dev-subject");
+
+    let unlink = app
+        .client
+        .delete(app.url(&format!(
+            "/admin/users/{dev_id}/identities/{}",
+            identity["id"]
+        )))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(unlink.status(), StatusCode::NO_CONTENT);
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(1) FROM external_identities")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 0);
+
+    let deactivate = app
+        .client
+        .patch(app.url(&format!("/admin/users/{dev_id}")))
+        .header("Authorization", &admin)
+        .json(&json!({ "active": false }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(deactivate.status(), StatusCode::OK);
+    assert!(deactivate.json::<Value>().await.unwrap()["deactivated_at"].is_string());
+
+    // Existing tokens stop working and new logins are refused.
+    let rejected = app
+        .client
+        .get(app.url("/repositories"))
+        .header("Authorization", &dev)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(rejected.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        session(&app, "dev@example.com", "secret123").await.status(),
+        StatusCode::FORBIDDEN
+    );
+
+    let self_lockout = app
+        .client
+        .patch(app.url(&format!("/admin/users/{admin_id}")))
+        .header("Authorization", &admin)
+        .json(&json!({ "active": false }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(self_lockout.status(), StatusCode::BAD_REQUEST);
+
+    let reactivate = app
+        .client
+        .patch(app.url(&format!("/admin/users/{dev_id}")))
+        .header("Authorization", &admin)
+        .json(&json!({ "active": true, "role": "admin" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(reactivate.json::<Value>().await.unwrap()["role"], "admin");
+
+    let reset = app
+        .client
+        .post(app.url(&format!("/admin/users/{dev_id}/password")))
+        .header("Authorization", &admin)
+        .json(&json!({ "password": "fresh-secret" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(reset.status(), StatusCode::NO_CONTENT);
+    assert_eq!(
+        session(&app, "dev@example.com", "secret123").await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+    let dev = login(&app, "dev@example.com", "fresh-secret").await;
+    let promoted = app
+        .client
+        .get(app.url("/admin/users"))
+        .header("Authorization", &dev)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(promoted.status(), StatusCode::OK);
+
+    let actions: Vec<String> = sqlx::query_scalar(
+        "SELECT action FROM audit_log WHERE target_id = ? AND action LIKE 'user.%' ORDER BY id",
+    )
+    .bind(&dev_id)
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        actions,
+        [
+            "user.create",
+            "user.identity_unlink",
+            "user.update",
+            "user.update",
+            "user.password_reset"
+        ]
+    );
+}
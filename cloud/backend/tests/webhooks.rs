@@ -0,0 +1,472 @@
+mod common;
+
+use std::time::Duration;
+
+use codex_cloud_backend::webhooks::{sign, DELIVERY_HEADER, SIGNATURE_HEADER};
+use common::{claim, create_repository, create_task, login, login_admin, start_attempt, TestApp};
+use reqwest::StatusCode;
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn spawn_app() -> TestApp {
+    TestApp::spawn_with(|config| {
+        config.webhooks.backoff_base = Duration::ZERO;
+        config.webhooks.poll_interval = Duration::from_millis(50);
+        // The mock receivers listen on loopback.
+        config.webhooks.allow_private_destinations = true;
+    })
+    .await
+}
+
+async fn create_webhook(
+    app: &TestApp,
+    auth_header: &str,
+    body: serde_json::Value,
+) -> serde_json::Value {
+    let webhook = app
+        .client
+        .post(app.url("/webhooks"))
+        .header("Authorization", auth_header)
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(webhook.status(), StatusCode::CREATED);
+    webhook.json().await.unwrap()
+}
+
+async fn list_webhooks(app: &TestApp, auth_header: &str) -> Vec<serde_json::Value> {
+    app.client
+        .get(app.url("/webhooks"))
+        .header("Authorization", auth_header)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+/// Polls the delivery log until `count` deliveries have settled.
+async fn settled_deliveries(
+    app: &TestApp,
+    auth_header: &str,
+    webhook_id: &str,
+    count: usize,
+) -> Vec<serde_json::Value> {
+    for _ in 0..100 {
+        let deliveries = app
+            .client
+            .get(app.url(&format!("/webhooks/{webhook_id}/deliveries")))
+            .header("Authorization", auth_header)
+            .send()
+            .await
+            .unwrap()
+            .json::<Vec<serde_json::Value>>()
+            .await
+            .unwrap();
+        if deliveries.len() >= count
+            && deliveries
+                .iter()
+                .all(|delivery| delivery["status"] != "pending")
+        {
+            return deliveries;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("deliveries did not settle");
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_retried_and_can_be_redelivered() {
+    let receiver = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/hook"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(1)
+        .mount(&receiver)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/hook"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&receiver)
+        .await;
+
+    let app = spawn_app().await;
+    let auth_header = login(&app, "hooks@example.com").await;
+    let repository_id = create_repository(&app, &auth_header).await;
+    let webhook = create_webhook(
+        &app,
+        &auth_header,
+        json!({
+            "url": format!("{}/hook", receiver.uri()),
+            "events": ["task.created"],
+            "repository_id": repository_id,
+            "secret": "shared-secret"
+        }),
+    )
+    .await;
+    assert_eq!(webhook["secret"], "shared-secret");
+    let webhook_id = webhook["id"].as_str().unwrap();
+
+    let task_id = create_task(&app, &auth_header, &repository_id).await;
+
+    let deliveries = settled_deliveries(&app, &auth_header, webhook_id, 1).await;
+    assert_eq!(deliveries[0]["status"], "succeeded");
+    assert_eq!(deliveries[0]["attempts"], 2);
+    assert_eq!(deliveries[0]["response_status"], 204);
+
+    let requests = receiver.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    let request = &requests[1];
+    assert_eq!(
+        request.headers[SIGNATURE_HEADER].to_str().unwrap(),
+        sign("shared-secret", &request.body)
+    );
+    let payload: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    assert_eq!(payload["event"], "task.created");
+    assert_eq!(payload["data"]["task"]["id"], task_id);
+
+    let delivery_id = deliveries[0]["id"].as_str().unwrap();
+    let redeliver = app
+        .client
+        .post(app.url(&format!("/webhooks/deliveries/{delivery_id}/redeliver")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(redeliver.status(), StatusCode::ACCEPTED);
+
+    let deliveries = settled_deliveries(&app, &auth_header, webhook_id, 2).await;
+    let redelivery = deliveries
+        .iter()
+        .find(|delivery| delivery["redelivery_of"] == delivery_id)
+        .unwrap();
+    assert_eq!(redelivery["status"], "succeeded");
+    let requests = receiver.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[2].body, requests[1].body);
+}
+
+#[tokio::test]
+async fn subscriptions_only_receive_matching_events() {
+    let receiver = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&receiver)
+        .await;
+
+    let app = spawn_app().await;
+    let auth_header = login(&app, "hooks@example.com").await;
+    let repository_id = create_repository(&app, &auth_header).await;
+    let webhook = create_webhook(
+        &app,
+        &auth_header,
+        json!({
+            "url": receiver.uri(),
+            "events": ["attempt.succeeded", "task.review_requested", "task.applied"],
+            "repository_id": repository_id
+        }),
+    )
+    .await;
+    assert!(webhook["secret"]
+        .as_str()
+        .is_some_and(|secret| !secret.is_empty()));
+    let webhook_id = webhook["id"].as_str().unwrap();
+
+    let task_id = create_task(&app, &auth_header, &repository_id).await;
+    assert!(claim(&app, &auth_header, &task_id)
+        .await
+        .status()
+        .is_success());
+    let attempt_id = start_attempt(&app, &auth_header, &task_id).await;
+    let complete = app
+        .client
+        .post(app.url(&format!("/tasks/attempts/{attempt_id}/complete")))
+        .header("Authorization", &auth_header)
+        .json(&json!({ "status": "succeeded", "diff": "diff --git a/x b/x" }))
+        .send()
+        .await
+        .unwrap();
+    assert!(complete.status().is_success());
+
+    let apply = app
+        .client
+        .post(app.url(&format!("/tasks/{task_id}/apply")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(apply.status(), StatusCode::OK);
+    assert_eq!(
+        apply.json::<serde_json::Value>().await.unwrap()["status"],
+        "applied"
+    );
+
+    let deliveries = settled_deliveries(&app, &auth_header, webhook_id, 3).await;
+    let mut events = deliveries
+        .iter()
+        .map(|delivery| delivery["event"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    events.sort();
+    assert_eq!(
+        events,
+        vec!["attempt.succeeded", "task.applied", "task.review_requested"]
+    );
+
+    let delete = app
+        .client
+        .delete(app.url(&format!("/webhooks/{webhook_id}")))
+        .header("Authorization", &auth_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(delete.status(), StatusCode::NO_CONTENT);
+    assert!(list_webhooks(&app, &auth_header).await.is_empty());
+}
+
+#[tokio::test]
+async fn private_destinations_are_rejected() {
+    let app = TestApp::spawn().await;
+    let auth_header = login_admin(&app, "hooks@example.com").await;
+
+    for url in [
+        "http://127.0.0.1:8080/hook",
+        "http://localhost/hook",
+        "http://10.1.2.3/hook",
+        "http://169.254.169.254/latest/meta-data",
+        "http://[::1]/hook",
+        "http://[::ffff:192.168.0.1]/hook",
+    ] {
+        let webhook = app
+            .client
+            .post(app.url("/webhooks"))
+            .header("Authorization", &auth_header)
+            .json(&json!({ "url": url, "events": ["task.created"] }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(webhook.status(), StatusCode::BAD_REQUEST, "{url}");
+    }
+}
+
+#[tokio::test]
+async fn webhooks_are_scoped_to_their_owner() {
+    let receiver = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&receiver)
+        .await;
+
+    let app = spawn_app().await;
+    let auth_header = login(&app, "hooks@example.com").await;
+    let other = login(&app, "someone@example.com").await;
+    let admin = login_admin(&app, "ops@example.com").await;
+    let repository_id = create_repository(&app, &auth_header).await;
+    let webhook = create_webhook(
+        &app,
+        &auth_header,
+        json!({
+            "url": receiver.uri(),
+            "events": ["task.created"],
+            "repository_id": repository_id
+        }),
+    )
+    .await;
+    let webhook_id = webhook["id"].as_str().unwrap();
+    create_task(&app, &auth_header, &repository_id).await;
+    let deliveries = settled_deliveries(&app, &auth_header, webhook_id, 1).await;
+    let delivery_id = deliveries[0]["id"].as_str().unwrap();
+
+    assert!(list_webhooks(&app, &other).await.is_empty());
+    assert_eq!(list_webhooks(&app, &admin).await.len(), 1);
+
+    let forbidden = [
+        app.client
+            .get(app.url(&format!("/webhooks/{webhook_id}/deliveries")))
+            .header("Authorization", &other),
+        app.client
+            .post(app.url(&format!("/webhooks/deliveries/{delivery_id}/redeliver")))
+            .header("Authorization", &other),
+        app.client
+            .delete(app.url(&format!("/webhooks/{webhook_id}")))
+            .header("Authorization", &other),
+    ];
+    for request in forbidden {
+        assert_eq!(
+            request.send().await.unwrap().status(),
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    let deliveries = app
+        .client
+        .get(app.url(&format!("/webhooks/{webhook_id}/deliveries")))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(deliveries.status(), StatusCode::OK);
+    let delete = app
+        .client
+        .delete(app.url(&format!("/webhooks/{webhook_id}")))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(delete.status(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn subscriptions_are_limited_to_what_the_caller_may_see() {
+    let receiver = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&receiver)
+        .await;
+
+    let app = spawn_app().await;
+    let member = login(&app, "hooks@example.com").await;
+    let outsider = login(&app, "someone@example.com").await;
+    let admin = login_admin(&app, "ops@example.com").await;
+    let repository_id = create_repository(&app, &member).await;
+
+    let global = app
+        .client
+        .post(app.url("/webhooks"))
+        .header("Authorization", &member)
+        .json(&json!({ "url": receiver.uri(), "events": ["task.created"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(global.status(), StatusCode::FORBIDDEN);
+
+    let organization_id = app
+        .client
+        .post(app.url("/admin/organizations"))
+        .header("Authorization", &admin)
+        .json(&json!({ "name": "Acme" }))
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let member_id = app
+        .client
+        .get(app.url("/admin/users"))
+        .header("Authorization", &admin)
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|user| user["email"] == "hooks@example.com")
+        .unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let membership = app
+        .client
+        .put(app.url(&format!(
+            "/admin/organizations/{organization_id}/members/{member_id}"
+        )))
+        .header("Authorization", &admin)
+        .json(&json!({ "role": "member" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(membership.status(), StatusCode::NO_CONTENT);
+
+    let body = json!({
+        "url": receiver.uri(),
+        "events": ["task.created"],
+        "organization_id": organization_id
+    });
+    let forbidden = app
+        .client
+        .post(app.url("/webhooks"))
+        .header("Authorization", &outsider)
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+    let webhook = create_webhook(&app, &member, body).await;
+    assert_eq!(webhook["organization_id"], organization_id.as_str());
+    let webhook_id = webhook["id"].as_str().unwrap();
+    let global = create_webhook(
+        &app,
+        &admin,
+        json!({ "url": receiver.uri(), "events": ["task.created"] }),
+    )
+    .await;
+
+    // Only the member's task belongs to the organization.
+    let member_task = create_task(&app, &member, &repository_id).await;
+    create_task(&app, &outsider, &repository_id).await;
+
+    let deliveries = settled_deliveries(&app, &member, webhook_id, 1).await;
+    assert_eq!(deliveries.len(), 1);
+    let requests = receiver.received_requests().await.unwrap();
+    let delivered = requests
+        .iter()
+        .find(|request| request.headers[DELIVERY_HEADER] == deliveries[0]["id"].as_str().unwrap())
+        .unwrap();
+    let payload: serde_json::Value = serde_json::from_slice(&delivered.body).unwrap();
+    assert_eq!(payload["data"]["task"]["id"], member_task.as_str());
+
+    let global_deliveries =
+        settled_deliveries(&app, &admin, global["id"].as_str().unwrap(), 2).await;
+    assert_eq!(global_deliveries.len(), 2);
+}
+
+#[tokio::test]
+async fn a_slow_receiver_does_not_hold_up_other_deliveries() {
+    let slow = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(3)))
+        .mount(&slow)
+        .await;
+    let fast = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&fast)
+        .await;
+
+    let app = spawn_app().await;
+    let auth_header = login(&app, "hooks@example.com").await;
+    let repository_id = create_repository(&app, &auth_header).await;
+    for receiver in [&slow, &fast] {
+        create_webhook(
+            &app,
+            &auth_header,
+            json!({
+                "url": receiver.uri(),
+                "events": ["task.created"],
+                "repository_id": repository_id
+            }),
+        )
+        .await;
+    }
+    create_task(&app, &auth_header, &repository_id).await;
+
+    let started = std::time::Instant::now();
+    while fast.received_requests().await.unwrap().is_empty() {
+        assert!(
+            started.elapsed() < Duration::from_secs(2),
+            "fast receiver was held up"
+        );
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    // The delivery in flight is not sent a second time by later passes.
+    tokio::time::sleep(Duration::from_secs(4)).await;
+    assert_eq!(slow.received_requests().await.unwrap().len(), 1);
+}
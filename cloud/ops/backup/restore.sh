@@ -5,22 +5,23 @@ BACKUP_ROOT=${BACKUP_ROOT:-/var/backups/codex}
 RESTORE_POINT=${1:-latest}
 SQLITE_DB=${SQLITE_DB:-/var/lib/codex/db/codex.db}
 ARTIFACT_DIR=${ARTIFACT_DIR:-/var/lib/codex/artifacts}
+CODEX_BACKEND=${CODEX_BACKEND:-codex-cloud-backend}
 
 if [[ "${RESTORE_POINT}" == "latest" ]]; then
-  RESTORE_DIR=$(ls -1 "${BACKUP_ROOT}" | sort | tail -n1)
+  RESTORE_ARCHIVE=$(ls -1 "${BACKUP_ROOT}" | grep '^codex-.*\.tar$' | sort | tail -n1 || true)
 else
-  RESTORE_DIR="${RESTORE_POINT}"
+  RESTORE_ARCHIVE="codex-${RESTORE_POINT}.tar"
 fi
 
-if [[ -z "${RESTORE_DIR}" ]]; then
+if [[ -z "${RESTORE_ARCHIVE}" ]]; then
   echo "No backup found in ${BACKUP_ROOT}" >&2
   exit 1
 fi
 
-RESTORE_PATH="${BACKUP_ROOT}/${RESTORE_DIR}"
+RESTORE_PATH="${BACKUP_ROOT}/${RESTORE_ARCHIVE}"
 
-if [[ ! -d "${RESTORE_PATH}" ]]; then
-  echo "Backup ${RESTORE_DIR} not found under ${BACKUP_ROOT}" >&2
+if [[ ! -f "${RESTORE_PATH}" ]]; then
+  echo "Backup ${RESTORE_ARCHIVE} not found under ${BACKUP_ROOT}" >&2
   exit 1
 fi
 
@@ -31,19 +32,12 @@ if systemctl is-active --quiet codex-compose.service; then
   systemctl stop codex-compose.service
 fi
 
-mkdir -p "$(dirname "${SQLITE_DB}")"
-mkdir -p "${ARTIFACT_DIR}"
-
-cp "${RESTORE_PATH}/codex.db" "${SQLITE_DB}"
-tar -C "${ARTIFACT_DIR}" -xzf "${RESTORE_PATH}/artifacts.tar.gz"
+# Checksums and database integrity are verified before anything is replaced.
+DATABASE_URL="sqlite://${SQLITE_DB}" CODEX_ARTIFACTS_DIR="${ARTIFACT_DIR}" \
+  "${CODEX_BACKEND}" restore --force "${RESTORE_PATH}"
 
 chown -R codex:codex "$(dirname "${SQLITE_DB}")" "${ARTIFACT_DIR}" || true
 
-if [[ -f "${RESTORE_PATH}/manifest.json" ]]; then
-  echo "Restored snapshot metadata:"
-  cat "${RESTORE_PATH}/manifest.json"
-fi
-
 echo "Starting Codex services" >&2
 systemctl start codex-compose.service
 
@@ -0,0 +1,190 @@
+use std::fmt;
+use std::time::Duration;
+
+use anyhow::Error as AnyError;
+use serde::Serialize;
+
+/// Why an attempt failed. Reported to the API so it can decide whether the
+/// task should be retried.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum FailureCategory {
+    Infra,
+    Agent,
+    Timeout,
+}
+
+impl FailureCategory {
+    /// Inspects the error chain for the markers below. Anything that is not
+    /// explicitly an agent failure or a timeout is treated as infrastructure.
+    pub(crate) fn classify(error: &AnyError) -> Self {
+        if error.downcast_ref::<AttemptTimedOut>().is_some() {
+            Self::Timeout
+        } else if let Some(failure) = error.downcast_ref::<AgentFailure>() {
+            failure.category()
+        } else {
+            Self::Infra
+        }
+    }
+}
+
+impl fmt::Display for FailureCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            Self::Infra => "infra",
+            Self::Agent => "agent",
+            Self::Timeout => "timeout",
+        };
+        f.write_str(value)
+    }
+}
+
+/// The attempt exceeded the deadline configured for its environment.
+#[derive(Debug)]
+pub(crate) struct AttemptTimedOut {
+    pub(crate) timeout: Duration,
+}
+
+impl fmt::Display for AttemptTimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "attempt timed out after {}s", self.timeout.as_secs())
+    }
+}
+
+impl std::error::Error for AttemptTimedOut {}
+
+/// The supervisor gave up on the attempt while draining.
+#[derive(Debug)]
+pub(crate) struct AttemptAbandoned;
+
+impl fmt::Display for AttemptAbandoned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("attempt abandoned while draining")
+    }
+}
+
+impl std::error::Error for AttemptAbandoned {}
+
+/// The agent exited after reporting an error. Most reported errors mean the
+/// agent gave up on the task, which is not retried; see
+/// [`AgentFailure::category`] for the ones that are.
+#[derive(Debug)]
+pub(crate) struct AgentFailure {
+    pub(crate) reason: String,
+}
+
+/// Fragments of the messages the agent reports when the model provider could
+/// not be reached or refused the request for reasons unrelated to the task.
+const INFRA_ERROR_MARKERS: &[&str] = &[
+    "stream disconnected",
+    "exceeded retry limit",
+    "high demand",
+    "usage limit",
+    "rate limit",
+    "error sending request",
+    "connection reset",
+    "connection refused",
+];
+
+const TIMEOUT_ERROR_MARKERS: &[&str] = &["timed out", "timeout"];
+
+impl AgentFailure {
+    /// Classifies the reported error by its message. Lost connections,
+    /// provider errors (5xx and 429 responses) and usage limits are
+    /// infrastructure failures and timeouts are timeouts, so both are
+    /// retried; everything else is the agent giving up on the task.
+    pub(crate) fn category(&self) -> FailureCategory {
+        let reason = self.reason.to_lowercase();
+        let provider_status = reason
+            .split_once("unexpected status ")
+            .is_some_and(|(_, status)| status.starts_with('5') || status.starts_with("429"));
+        if provider_status
+            || INFRA_ERROR_MARKERS
+                .iter()
+                .any(|marker| reason.contains(marker))
+        {
+            FailureCategory::Infra
+        } else if TIMEOUT_ERROR_MARKERS
+            .iter()
+            .any(|marker| reason.contains(marker))
+        {
+            FailureCategory::Timeout
+        } else {
+            FailureCategory::Agent
+        }
+    }
+}
+
+impl fmt::Display for AgentFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "agent failed: {}", self.reason)
+    }
+}
+
+impl std::error::Error for AgentFailure {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{Context, anyhow};
+
+    #[test]
+    fn classifies_errors_by_marker() {
+        let timeout = AnyError::new(AttemptTimedOut {
+            timeout: Duration::from_secs(5),
+        });
+        assert_eq!(
+            FailureCategory::classify(&timeout),
+            FailureCategory::Timeout
+        );
+
+        let agent = Err::<(), _>(AgentFailure {
+            reason: "no diff produced".to_string(),
+        })
+        .context("runner failed")
+        .unwrap_err();
+        assert_eq!(FailureCategory::classify(&agent), FailureCategory::Agent);
+
+        let infra = anyhow!("snapshot hook exited with status 1");
+        assert_eq!(FailureCategory::classify(&infra), FailureCategory::Infra);
+    }
+
+    #[test]
+    fn classifies_reported_agent_errors_by_message() {
+        let category = |reason: &str| {
+            AgentFailure {
+                reason: reason.to_string(),
+            }
+            .category()
+        };
+
+        assert_eq!(
+            category("stream disconnected before completion: connection reset"),
+            FailureCategory::Infra
+        );
+        assert_eq!(
+            category("unexpected status 502 Bad Gateway: upstream error"),
+            FailureCategory::Infra
+        );
+        assert_eq!(
+            category("unexpected status 429 Too Many Requests: slow down"),
+            FailureCategory::Infra
+        );
+        assert_eq!(
+            category("You've hit your usage limit. Try again in 2 hours."),
+            FailureCategory::Infra
+        );
+        assert_eq!(
+            category("timeout waiting for child process to exit"),
+            FailureCategory::Timeout
+        );
+        assert_eq!(
+            category("unexpected status 400 Bad Request: invalid tool schema"),
+            FailureCategory::Agent
+        );
+        assert_eq!(
+            category("Codex ran out of room in the model's context window."),
+            FailureCategory::Agent
+        );
+    }
+}
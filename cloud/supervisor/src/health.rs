@@ -0,0 +1,74 @@
+use std::net::SocketAddr;
+
+use anyhow::{Context, Result};
+use axum::Json;
+use axum::Router;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::Supervisor;
+use crate::pool::SnapshotPoolMetrics;
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct InFlightAttempt {
+    pub(crate) task_id: Uuid,
+    pub(crate) attempt_id: Uuid,
+    pub(crate) title: String,
+    pub(crate) started_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SupervisorState {
+    Running,
+    Draining,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SupervisorStatus {
+    pub(crate) state: SupervisorState,
+    pub(crate) in_flight: Vec<InFlightAttempt>,
+    pub(crate) pool: SnapshotPoolMetrics,
+    pub(crate) last_successful_poll: Option<DateTime<Utc>>,
+}
+
+/// Binds the health server and serves it in the background until the process
+/// exits.
+pub(crate) async fn spawn(addr: SocketAddr, supervisor: Supervisor) -> Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("failed to bind health server on {addr}"))?;
+    info!(%addr, "Health server listening");
+
+    tokio::spawn(async move {
+        if let Err(err) = axum::serve(listener, router(supervisor)).await {
+            warn!(error = %err, "Health server exited");
+        }
+    });
+    Ok(())
+}
+
+pub(crate) fn router(supervisor: Supervisor) -> Router {
+    Router::new()
+        .route("/healthz", get(|| async { StatusCode::OK }))
+        .route("/readyz", get(readyz))
+        .route("/status", get(status))
+        .with_state(supervisor)
+}
+
+async fn readyz(State(supervisor): State<Supervisor>) -> (StatusCode, &'static str) {
+    match supervisor.readiness() {
+        Ok(()) => (StatusCode::OK, "ready"),
+        Err(reason) => (StatusCode::SERVICE_UNAVAILABLE, reason),
+    }
+}
+
+async fn status(State(supervisor): State<Supervisor>) -> Json<SupervisorStatus> {
+    Json(supervisor.status().await)
+}
@@ -1,24 +1,35 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use anyhow::{Context, Error as AnyError, Result, anyhow};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use clap::Parser;
 use futures::stream::{self, StreamExt};
 use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
 use tokio::signal;
-use tokio::sync::RwLock;
-use tokio::time::sleep;
+use tokio::sync::{Notify, RwLock, watch};
+use tokio::time::{Instant, sleep, timeout, timeout_at};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+mod failure;
+mod health;
 mod pool;
+mod queue;
 mod runner;
+mod usage;
 
+use failure::{AttemptAbandoned, AttemptTimedOut, FailureCategory};
+use health::{InFlightAttempt, SupervisorState, SupervisorStatus};
 use pool::{LifecycleHook, PoolSettings, SnapshotPool};
-use runner::Runner;
+use queue::{QueueSubscription, parse_queue};
+use runner::{AgentCommand, Runner};
+use usage::AttemptUsage;
 
 #[derive(Debug, Parser)]
 #[command(author, version, about = "Codex Cloud task supervisor", long_about = None)]
@@ -51,6 +62,15 @@ struct Args {
     #[arg(long, env = "CODEX_CLOUD_ENVIRONMENT_ID")]
     environment_id: Option<String>,
 
+    /// Environment queues to pull tasks from as ENVIRONMENT_ID[=WEIGHT]; overrides --environment-id
+    #[arg(
+        long = "queue",
+        env = "CODEX_CLOUD_QUEUES",
+        value_delimiter = ',',
+        value_parser = parse_queue
+    )]
+    queues: Vec<QueueSubscription>,
+
     /// Maximum number of attempts to execute concurrently
     #[arg(long, env = "CODEX_CLOUD_MAX_CONCURRENCY", default_value_t = 1)]
     max_concurrency: usize,
@@ -67,6 +87,27 @@ struct Args {
     #[arg(long, env = "CODEX_CLOUD_PREWARM_HOOK")]
     prewarm_hook: Option<PathBuf>,
 
+    /// Optional path to a hook that health checks, recycles and destroys
+    /// snapshots
+    #[arg(long, env = "CODEX_CLOUD_SNAPSHOT_LIFECYCLE_HOOK")]
+    snapshot_lifecycle_hook: Option<PathBuf>,
+
+    /// Maximum age in seconds of a warm snapshot before it is rotated
+    #[arg(long, env = "CODEX_CLOUD_SNAPSHOT_MAX_AGE")]
+    snapshot_max_age: Option<u64>,
+
+    /// Interval in seconds between background pool refills
+    #[arg(
+        long,
+        env = "CODEX_CLOUD_SNAPSHOT_REFILL_INTERVAL",
+        default_value_t = 30
+    )]
+    snapshot_refill_interval: u64,
+
+    /// Probe snapshots through the lifecycle hook before checking them out
+    #[arg(long, env = "CODEX_CLOUD_SNAPSHOT_HEALTH_CHECK")]
+    snapshot_health_check: bool,
+
     /// Root directory used for dependency caches
     #[arg(
         long,
@@ -74,6 +115,42 @@ struct Args {
         default_value = "/var/cache/codex"
     )]
     cache_root: PathBuf,
+
+    /// Program that runs the agent for each attempt (records a synthetic run when unset)
+    #[arg(long, env = "CODEX_CLOUD_AGENT_COMMAND")]
+    agent_command: Option<PathBuf>,
+
+    /// Maximum wall-clock time for a single attempt, in seconds
+    #[arg(long, env = "CODEX_CLOUD_ATTEMPT_TIMEOUT", default_value_t = 3600)]
+    attempt_timeout: u64,
+
+    /// Per-environment attempt timeout overrides as ENVIRONMENT_ID=SECONDS
+    #[arg(
+        long = "environment-timeout",
+        env = "CODEX_CLOUD_ENVIRONMENT_TIMEOUTS",
+        value_delimiter = ',',
+        value_parser = parse_environment_timeout
+    )]
+    environment_timeouts: Vec<(String, u64)>,
+
+    /// Address for the /healthz, /readyz and /status endpoints (disabled when unset)
+    #[arg(long, env = "CODEX_CLOUD_HEALTH_ADDR")]
+    health_addr: Option<SocketAddr>,
+
+    /// Seconds to wait for in-flight attempts on shutdown before releasing them
+    #[arg(long, env = "CODEX_CLOUD_DRAIN_TIMEOUT", default_value_t = 300)]
+    drain_timeout: u64,
+}
+
+fn parse_environment_timeout(value: &str) -> Result<(String, u64), String> {
+    let (environment_id, seconds) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected ENVIRONMENT_ID=SECONDS, got `{value}`"))?;
+    let seconds = seconds
+        .trim()
+        .parse::<u64>()
+        .map_err(|err| format!("invalid timeout for `{environment_id}`: {err}"))?;
+    Ok((environment_id.trim().to_string(), seconds))
 }
 
 #[derive(Debug, Clone)]
@@ -83,26 +160,62 @@ struct AppConfig {
     password: String,
     poll_interval: Duration,
     environment_id: Option<String>,
+    queues: Vec<QueueSubscription>,
     max_concurrency: usize,
     snapshot_pool_size: usize,
     snapshot_template: Option<String>,
     prewarm_hook: Option<PathBuf>,
+    snapshot_lifecycle_hook: Option<PathBuf>,
+    snapshot_max_age: Option<Duration>,
+    snapshot_refill_interval: Duration,
+    snapshot_health_check: bool,
     cache_root: PathBuf,
+    agent_command: Option<PathBuf>,
+    attempt_timeout: Duration,
+    environment_timeouts: HashMap<String, Duration>,
+    health_addr: Option<SocketAddr>,
+    drain_timeout: Duration,
 }
 
 impl From<Args> for AppConfig {
     fn from(args: Args) -> Self {
+        let mut queues = args.queues;
+        if queues.is_empty() {
+            if let Some(environment_id) = &args.environment_id {
+                queues.push(QueueSubscription {
+                    environment_id: environment_id.clone(),
+                    weight: 1,
+                });
+            }
+        }
+
         Self {
             api_base: args.api_base.trim_end_matches('/').to_string(),
             email: args.email,
             password: args.password,
             poll_interval: Duration::from_secs(args.poll_interval.max(1)),
             environment_id: args.environment_id,
+            queues,
             max_concurrency: args.max_concurrency.max(1),
             snapshot_pool_size: args.snapshot_pool_size,
             snapshot_template: args.snapshot_template,
             prewarm_hook: args.prewarm_hook,
+            snapshot_lifecycle_hook: args.snapshot_lifecycle_hook,
+            snapshot_max_age: args.snapshot_max_age.map(Duration::from_secs),
+            snapshot_refill_interval: Duration::from_secs(args.snapshot_refill_interval.max(1)),
+            snapshot_health_check: args.snapshot_health_check,
             cache_root: args.cache_root,
+            agent_command: args.agent_command,
+            attempt_timeout: Duration::from_secs(args.attempt_timeout.max(1)),
+            environment_timeouts: args
+                .environment_timeouts
+                .into_iter()
+                .map(|(environment_id, seconds)| {
+                    (environment_id, Duration::from_secs(seconds.max(1)))
+                })
+                .collect(),
+            health_addr: args.health_addr,
+            drain_timeout: Duration::from_secs(args.drain_timeout),
         }
     }
 }
@@ -116,12 +229,30 @@ impl AppConfig {
                 .prewarm_hook
                 .as_ref()
                 .map(|path| LifecycleHook::new(path.clone())),
+            lifecycle_hook: self
+                .snapshot_lifecycle_hook
+                .as_ref()
+                .map(|path| LifecycleHook::new(path.clone())),
+            max_age: self.snapshot_max_age,
+            refill_interval: self.snapshot_refill_interval,
+            health_check: self.snapshot_health_check,
         }
     }
 
     fn cache_root(&self) -> PathBuf {
         self.cache_root.clone()
     }
+
+    fn agent(&self) -> Option<AgentCommand> {
+        self.agent_command.clone().map(AgentCommand::new)
+    }
+
+    fn attempt_timeout(&self, environment_id: Option<&str>) -> Duration {
+        environment_id
+            .and_then(|id| self.environment_timeouts.get(id))
+            .copied()
+            .unwrap_or(self.attempt_timeout)
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -132,6 +263,7 @@ enum TaskStatus {
     Running,
     Review,
     Applied,
+    Failed,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -149,6 +281,8 @@ pub(crate) struct TaskListResponse {
     pub(crate) title: String,
     #[serde(default)]
     pub(crate) environment_id: Option<String>,
+    #[serde(default)]
+    pub(crate) retry_after: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -161,6 +295,24 @@ pub(crate) struct TaskDetailResponse {
     pub(crate) environment_id: Option<String>,
     #[serde(default)]
     pub(crate) repository: Option<RepositorySummary>,
+    /// Git ref requested for the run; the repository default branch applies
+    /// when unset.
+    #[serde(default)]
+    pub(crate) branch: Option<String>,
+    #[serde(default)]
+    pub(crate) qa_mode: bool,
+    #[serde(default)]
+    pub(crate) inputs: Vec<TaskInput>,
+}
+
+/// An image or file attached to the task, served from the artifact store.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct TaskInput {
+    pub(crate) kind: String,
+    #[serde(default)]
+    pub(crate) name: Option<String>,
+    pub(crate) artifact_id: String,
+    pub(crate) url: String,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -187,6 +339,10 @@ struct AttemptCompleteRequest {
     status: AttemptStatus,
     diff: Option<String>,
     log: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    failure_category: Option<FailureCategory>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<AttemptUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -203,6 +359,7 @@ pub(crate) struct AttemptContext {
 pub(crate) struct AttemptArtifacts {
     pub(crate) diff: Option<String>,
     pub(crate) log: Option<String>,
+    pub(crate) usage: Option<AttemptUsage>,
 }
 
 struct SupervisorInner {
@@ -211,6 +368,12 @@ struct SupervisorInner {
     token: RwLock<String>,
     pool: SnapshotPool,
     runner: Runner,
+    draining: AtomicBool,
+    abandon: watch::Sender<bool>,
+    active: AtomicUsize,
+    active_changed: Notify,
+    in_flight: Mutex<HashMap<Uuid, InFlightAttempt>>,
+    last_successful_poll: Mutex<Option<DateTime<Utc>>>,
 }
 
 #[derive(Clone)]
@@ -229,6 +392,7 @@ impl Supervisor {
 
         let pool = SnapshotPool::new(config.pool_settings());
         pool.ensure_warm_capacity().await?;
+        pool.start_refill().await;
         let metrics = pool.metrics().await;
         info!(
             warm = metrics.warm,
@@ -236,7 +400,7 @@ impl Supervisor {
             "Snapshot pool initialised"
         );
 
-        let runner = Runner::new(config.cache_root()).await?;
+        let runner = Runner::new(config.cache_root(), client.clone(), config.agent()).await?;
 
         Ok(Self {
             inner: Arc::new(SupervisorInner {
@@ -245,6 +409,12 @@ impl Supervisor {
                 token: RwLock::new(token),
                 pool,
                 runner,
+                draining: AtomicBool::new(false),
+                abandon: watch::Sender::new(false),
+                active: AtomicUsize::new(0),
+                active_changed: Notify::new(),
+                in_flight: Mutex::new(HashMap::new()),
+                last_successful_poll: Mutex::new(None),
             }),
         })
     }
@@ -254,9 +424,15 @@ impl Supervisor {
             max_concurrency = self.config().max_concurrency,
             "Supervisor started"
         );
+        if let Some(addr) = self.config().health_addr {
+            health::spawn(addr, self.clone()).await?;
+        }
+
+        let shutdown = shutdown_signal();
+        tokio::pin!(shutdown);
         loop {
             tokio::select! {
-                _ = signal::ctrl_c() => {
+                _ = &mut shutdown => {
                     info!("Shutdown signal received");
                     break;
                 }
@@ -267,9 +443,112 @@ impl Supervisor {
                 }
             }
         }
+        self.drain().await;
+        self.pool().shutdown().await;
+        Ok(())
+    }
+
+    /// Stops claiming new work and waits for in-flight attempts. Attempts still
+    /// running once the drain timeout expires are released back to the API.
+    async fn drain(&self) {
+        self.inner.draining.store(true, Ordering::SeqCst);
+        info!(
+            in_flight = self.active_attempts(),
+            timeout_secs = self.config().drain_timeout.as_secs(),
+            "Draining supervisor"
+        );
+
+        let deadline = Instant::now() + self.config().drain_timeout;
+        if self.wait_for_idle(deadline).await {
+            info!("All in-flight attempts finished");
+            return;
+        }
+
+        warn!(
+            in_flight = self.active_attempts(),
+            "Drain timeout reached, releasing remaining attempts"
+        );
+        self.inner.abandon.send_replace(true);
+        if !self
+            .wait_for_idle(Instant::now() + Duration::from_secs(30))
+            .await
+        {
+            warn!(
+                in_flight = self.active_attempts(),
+                "Exiting with attempts still being released"
+            );
+        }
+    }
+
+    async fn wait_for_idle(&self, deadline: Instant) -> bool {
+        loop {
+            let changed = self.inner.active_changed.notified();
+            if self.active_attempts() == 0 {
+                return true;
+            }
+            if timeout_at(deadline, changed).await.is_err() {
+                return self.active_attempts() == 0;
+            }
+        }
+    }
+
+    fn is_draining(&self) -> bool {
+        self.inner.draining.load(Ordering::SeqCst)
+    }
+
+    fn active_attempts(&self) -> usize {
+        self.inner.active.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once the drain deadline passes and in-flight attempts must be
+    /// given up.
+    async fn abandoned(&self) {
+        let mut abandon = self.inner.abandon.subscribe();
+        let _ = abandon.wait_for(|abandoned| *abandoned).await;
+    }
+
+    fn readiness(&self) -> Result<(), &'static str> {
+        if self.is_draining() {
+            return Err("draining");
+        }
+        let Some(last_poll) = *self.inner.last_successful_poll.lock().unwrap() else {
+            return Err("no successful poll yet");
+        };
+
+        // Polls pause while attempts run, so staleness only matters when idle.
+        let max_staleness = self.config().poll_interval * 3 + Duration::from_secs(30);
+        let stale = (Utc::now() - last_poll)
+            .to_std()
+            .is_ok_and(|age| age > max_staleness);
+        if stale && self.active_attempts() == 0 {
+            return Err("polling is stale");
+        }
         Ok(())
     }
 
+    async fn status(&self) -> SupervisorStatus {
+        let mut in_flight: Vec<_> = self
+            .inner
+            .in_flight
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        in_flight.sort_by_key(|attempt| attempt.started_at);
+
+        SupervisorStatus {
+            state: if self.is_draining() {
+                SupervisorState::Draining
+            } else {
+                SupervisorState::Running
+            },
+            in_flight,
+            pool: self.pool().metrics().await,
+            last_successful_poll: *self.inner.last_successful_poll.lock().unwrap(),
+        }
+    }
+
     fn client(&self) -> &Client {
         &self.inner.client
     }
@@ -293,16 +572,26 @@ impl Supervisor {
     }
 
     async fn process_pending_tasks(&self) -> Result<()> {
+        if self.is_draining() {
+            return Ok(());
+        }
+
         let tasks = self.list_tasks(TaskStatus::Pending).await?;
+        *self.inner.last_successful_poll.lock().unwrap() = Some(Utc::now());
         if tasks.is_empty() {
             info!("No pending tasks found");
             return Ok(());
         }
 
-        let to_execute: Vec<_> = tasks
+        let now = Utc::now();
+        let ready = tasks
             .into_iter()
-            .filter(|task| self.should_execute(task))
+            .filter(|task| {
+                task.retry_after
+                    .is_none_or(|retry_after| retry_after <= now)
+            })
             .collect();
+        let to_execute = queue::schedule(ready, &self.config().queues);
 
         if to_execute.is_empty() {
             info!("No pending tasks matched configured filters");
@@ -310,16 +599,19 @@ impl Supervisor {
         }
 
         let max_concurrency = self.config().max_concurrency;
+        // Attempts run on their own tasks so that they survive the poll loop
+        // being interrupted by a shutdown signal and can be drained.
         stream::iter(to_execute.into_iter().map(|task| {
             let supervisor = self.clone();
+            let task_id = task.id;
+            let title = task.title.clone();
+            let handle = tokio::spawn(async move { supervisor.execute_task(task).await });
             async move {
-                let task_id = task.id;
-                let title = task.title.clone();
-                match supervisor.execute_task(task).await {
-                    Ok(()) => {
+                match handle.await {
+                    Ok(Ok(())) => {
                         info!(task_id = %task_id, title = %title, "Task completed");
                     }
-                    Err(err) => {
+                    Ok(Err(err)) => {
                         warn!(
                             task_id = %task_id,
                             title = %title,
@@ -327,6 +619,9 @@ impl Supervisor {
                             "Failed to execute task"
                         );
                     }
+                    Err(err) => {
+                        error!(task_id = %task_id, error = %err, "Attempt task panicked");
+                    }
                 }
             }
         }))
@@ -337,33 +632,64 @@ impl Supervisor {
         Ok(())
     }
 
-    fn should_execute(&self, task: &TaskListResponse) -> bool {
-        match &self.config().environment_id {
-            Some(filter) => task.environment_id.as_deref() == Some(filter.as_str()),
-            None => true,
-        }
+    async fn execute_task(&self, task: TaskListResponse) -> Result<()> {
+        // Count the attempt before checking for a drain: either the drain sees
+        // it as active and waits, or this sees the drain and backs out.
+        self.inner.active.fetch_add(1, Ordering::SeqCst);
+        let result = if self.is_draining() {
+            Ok(())
+        } else {
+            self.claim_and_run(task).await
+        };
+        self.inner.active.fetch_sub(1, Ordering::SeqCst);
+        self.inner.active_changed.notify_waiters();
+        result
     }
 
-    async fn execute_task(&self, task: TaskListResponse) -> Result<()> {
+    async fn claim_and_run(&self, task: TaskListResponse) -> Result<()> {
         info!(task_id = %task.id, title = %task.title, "Attempting to claim task");
         let Some(context) = self.start_attempt(task).await? else {
             return Ok(());
         };
 
-        match self.run_attempt(&context).await {
+        self.inner.in_flight.lock().unwrap().insert(
+            context.attempt.id,
+            InFlightAttempt {
+                task_id: context.task.id,
+                attempt_id: context.attempt.id,
+                title: context.task.title.clone(),
+                started_at: Utc::now(),
+            },
+        );
+        let result = self.finish_attempt(&context).await;
+        self.inner
+            .in_flight
+            .lock()
+            .unwrap()
+            .remove(&context.attempt.id);
+        result
+    }
+
+    async fn finish_attempt(&self, context: &AttemptContext) -> Result<()> {
+        match self.run_attempt(context).await {
             Ok(artifacts) => {
-                self.complete_attempt(&context, AttemptStatus::Succeeded, artifacts)
+                self.complete_attempt(context, AttemptStatus::Succeeded, artifacts, None)
                     .await?;
                 Ok(())
             }
+            Err(err) if err.downcast_ref::<AttemptAbandoned>().is_some() => {
+                self.release_attempt(context, &err).await;
+                Ok(())
+            }
             Err(err) => {
                 warn!(
                     task_id = %context.task.id,
                     attempt_id = %context.attempt.id,
+                    category = %FailureCategory::classify(&err),
                     error = %err,
                     "Attempt execution failed"
                 );
-                self.fail_attempt(&context, &err).await;
+                self.fail_attempt(context, &err).await;
                 Err(err)
             }
         }
@@ -397,7 +723,10 @@ impl Supervisor {
 
         match response.status() {
             StatusCode::CONFLICT => {
-                info!(task_id = %task.id, "Task already claimed by another worker");
+                // Claimed by another worker, waiting for a retry, or the
+                // environment is at its concurrency cap.
+                let body = response.text().await.unwrap_or_default();
+                info!(task_id = %task.id, reason = %body, "Task is not claimable");
                 return Ok(None);
             }
             status if !status.is_success() => {
@@ -451,8 +780,27 @@ impl Supervisor {
     }
 
     async fn run_attempt(&self, context: &AttemptContext) -> Result<AttemptArtifacts> {
+        let environment_id = context
+            .detail
+            .as_ref()
+            .and_then(|detail| detail.environment_id.as_deref())
+            .or(context.task.environment_id.as_deref());
+        let deadline = self.config().attempt_timeout(environment_id);
+
         let lease = self.pool().checkout().await?;
-        match self.runner().execute(context, &lease).await {
+        let token = { self.inner.token.read().await.clone() };
+        // Check the abandon signal first so a drain deadline that has already
+        // passed releases the attempt instead of racing a fast runner.
+        let result = tokio::select! {
+            biased;
+            _ = self.abandoned() => Err(AttemptAbandoned.into()),
+            result = timeout(deadline, self.runner().execute(context, &lease, &token)) => match result {
+                Ok(result) => result,
+                Err(_) => Err(AttemptTimedOut { timeout: deadline }.into()),
+            },
+        };
+        self.runner().remove_inputs(context).await;
+        match result {
             Ok(artifacts) => {
                 self.pool().recycle(lease).await?;
                 Ok(artifacts)
@@ -469,9 +817,16 @@ impl Supervisor {
         context: &AttemptContext,
         status: AttemptStatus,
         artifacts: AttemptArtifacts,
+        failure_category: Option<FailureCategory>,
     ) -> Result<()> {
-        let AttemptArtifacts { diff, log } = artifacts;
-        let payload = AttemptCompleteRequest { status, diff, log };
+        let AttemptArtifacts { diff, log, usage } = artifacts;
+        let payload = AttemptCompleteRequest {
+            status,
+            diff,
+            log,
+            failure_category,
+            usage,
+        };
 
         let response = self
             .send_authenticated(|client, base| {
@@ -494,19 +849,21 @@ impl Supervisor {
     }
 
     async fn fail_attempt(&self, context: &AttemptContext, error: &AnyError) {
+        let category = FailureCategory::classify(error);
         let timestamp = Utc::now().to_rfc3339();
         let log = format!(
-            "[{timestamp}] Attempt {} failed for task {}: {error:?}",
+            "[{timestamp}] Attempt {} failed for task {} ({category} failure): {error:?}",
             context.attempt.id, context.task.id
         );
 
         let artifacts = AttemptArtifacts {
             diff: None,
             log: Some(log),
+            usage: None,
         };
 
         if let Err(err) = self
-            .complete_attempt(context, AttemptStatus::Failed, artifacts)
+            .complete_attempt(context, AttemptStatus::Failed, artifacts, Some(category))
             .await
         {
             warn!(
@@ -518,6 +875,47 @@ impl Supervisor {
         }
     }
 
+    /// Hands an abandoned attempt back to the API so another worker can pick
+    /// the task up. Falls back to reporting an infra failure.
+    async fn release_attempt(&self, context: &AttemptContext, error: &AnyError) {
+        let response = self
+            .send_authenticated(|client, base| {
+                client.post(format!(
+                    "{base}/tasks/attempts/{}/release",
+                    context.attempt.id
+                ))
+            })
+            .await;
+
+        match response {
+            Ok(response) if response.status().is_success() => {
+                info!(
+                    task_id = %context.task.id,
+                    attempt_id = %context.attempt.id,
+                    "Released attempt back to the queue"
+                );
+            }
+            Ok(response) => {
+                warn!(
+                    task_id = %context.task.id,
+                    attempt_id = %context.attempt.id,
+                    status = %response.status(),
+                    "Failed to release attempt, reporting failure instead"
+                );
+                self.fail_attempt(context, error).await;
+            }
+            Err(err) => {
+                warn!(
+                    task_id = %context.task.id,
+                    attempt_id = %context.attempt.id,
+                    error = %err,
+                    "Failed to release attempt, reporting failure instead"
+                );
+                self.fail_attempt(context, error).await;
+            }
+        }
+    }
+
     async fn fetch_task_detail(&self, task_id: Uuid) -> Result<Option<TaskDetailResponse>> {
         let response = self
             .send_authenticated(|client, base| client.get(format!("{base}/tasks/{task_id}")))
@@ -607,6 +1005,30 @@ where
     })
 }
 
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
 fn init_tracing() {
     let subscriber = tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
@@ -633,8 +1055,9 @@ mod tests {
     use std::fs;
     #[cfg(unix)]
     use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
     use tempfile::tempdir;
-    use wiremock::matchers::{method, path, path_regex, query_param};
+    use wiremock::matchers::{header, method, path, path_regex, query_param};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[tokio::test]
@@ -696,12 +1119,29 @@ mod tests {
                     "name": "demo-repo",
                     "git_url": "https://example.com/demo.git",
                     "default_branch": "main"
-                }
+                },
+                "branch": "feature/login",
+                "qa_mode": true,
+                "inputs": [{
+                    "kind": "image",
+                    "name": "../mockup.png",
+                    "mime_type": "image/png",
+                    "artifact_id": "input.png",
+                    "url": format!("{}/artifacts/input.png", server.uri())
+                }]
             })))
             .expect(1)
             .mount(&server)
             .await;
 
+        Mock::given(method("GET"))
+            .and(path("/artifacts/input.png"))
+            .and(header("authorization", "Bearer test-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"png-bytes".to_vec()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
         Mock::given(method("POST"))
             .and(path_regex(r"/tasks/attempts/.*/complete"))
             .respond_with(ResponseTemplate::new(200).set_body_json(json!({
@@ -742,11 +1182,21 @@ echo "${CODEX_SNAPSHOT_TEMPLATE:-snapshot}-warm"
             password: "password".into(),
             poll_interval: Duration::from_secs(1),
             environment_id: None,
+            queues: Vec::new(),
             max_concurrency: 1,
             snapshot_pool_size: 1,
             snapshot_template: Some("integration-template".to_string()),
             prewarm_hook: Some(hook_path.clone()),
+            snapshot_lifecycle_hook: None,
+            snapshot_max_age: None,
+            snapshot_refill_interval: Duration::from_secs(30),
+            snapshot_health_check: false,
             cache_root: cache_root.clone(),
+            agent_command: None,
+            attempt_timeout: Duration::from_secs(60),
+            environment_timeouts: HashMap::new(),
+            health_addr: None,
+            drain_timeout: Duration::from_secs(5),
         };
 
         let supervisor = Supervisor::new(config).await.expect("supervisor init");
@@ -775,6 +1225,17 @@ echo "${CODEX_SNAPSHOT_TEMPLATE:-snapshot}-warm"
         assert!(diff.contains("demo-repo"));
         assert!(diff.contains("Using snapshot: integration-template-warm"));
         assert!(diff.contains(cache_root.to_string_lossy().as_ref()));
+        assert!(diff.contains("on branch feature/login"));
+        assert!(diff.contains("QA mode: enabled"));
+        let input_path = cache_root
+            .join("inputs")
+            .join(attempt_id.to_string())
+            .join("00-mockup.png");
+        assert!(diff.contains(input_path.to_string_lossy().as_ref()));
+        assert!(
+            !input_path.parent().expect("input directory").exists(),
+            "inputs are removed once the attempt finishes"
+        );
 
         let log = body["log"].as_str().expect("log text present");
         assert!(log.contains(&attempt_id.to_string()));
@@ -783,6 +1244,7 @@ echo "${CODEX_SNAPSHOT_TEMPLATE:-snapshot}-warm"
         assert!(log.contains("Using prewarmed snapshot: integration-template-warm"));
         assert!(log.contains("Cache hits:"));
         assert!(log.contains("Git mirror"));
+        assert!(body.get("usage").is_none(), "no agent ran");
 
         let hook_log_path = temp.path().join("hook.log");
         let hook_log = fs::read_to_string(&hook_log_path).expect("hook log");
@@ -791,4 +1253,524 @@ echo "${CODEX_SNAPSHOT_TEMPLATE:-snapshot}-warm"
         assert!(cache_root.join("git").exists());
         assert!(cache_root.join("npm").exists());
     }
+
+    #[tokio::test]
+    async fn supervisor_reports_infra_failure_when_snapshot_hook_fails() {
+        let server = MockServer::start().await;
+        let task_id = Uuid::new_v4();
+        let attempt_id = Uuid::new_v4();
+
+        Mock::given(method("POST"))
+            .and(path("/auth/session"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "test-token"
+            })))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/tasks"))
+            .and(query_param("status", "pending"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                { "id": task_id, "title": "Broken Snapshot" },
+                {
+                    "id": Uuid::new_v4(),
+                    "title": "Backing off",
+                    "retry_after": "2999-01-01T00:00:00Z"
+                }
+            ])))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path(format!("/tasks/{task_id}/claim")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "claim_expires_at": "2024-01-01T00:00:00Z"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path(format!("/tasks/{task_id}/attempts")))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "id": attempt_id
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(format!("/tasks/{task_id}")))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path(format!("/tasks/attempts/{attempt_id}/complete")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "status": "failed"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let temp = tempdir().expect("temp dir");
+        let hook_path = temp.path().join("prewarm.sh");
+        fs::write(
+            &hook_path,
+            "#!/usr/bin/env bash\necho 'no capacity' >&2\nexit 1\n",
+        )
+        .expect("write hook script");
+        #[cfg(unix)]
+        {
+            let mut perms = fs::metadata(&hook_path)
+                .expect("hook metadata")
+                .permissions();
+            perms.set_mode(0o755);
+            fs::set_permissions(&hook_path, perms).expect("set hook permissions");
+        }
+
+        let config = AppConfig {
+            api_base: server.uri(),
+            email: "worker@example.com".into(),
+            password: "password".into(),
+            poll_interval: Duration::from_secs(1),
+            environment_id: None,
+            queues: Vec::new(),
+            max_concurrency: 1,
+            snapshot_pool_size: 0,
+            snapshot_template: None,
+            prewarm_hook: Some(hook_path),
+            snapshot_lifecycle_hook: None,
+            snapshot_max_age: None,
+            snapshot_refill_interval: Duration::from_secs(30),
+            snapshot_health_check: false,
+            cache_root: temp.path().join("cache"),
+            agent_command: None,
+            attempt_timeout: Duration::from_secs(60),
+            environment_timeouts: HashMap::new(),
+            health_addr: None,
+            drain_timeout: Duration::from_secs(5),
+        };
+
+        let supervisor = Supervisor::new(config).await.expect("supervisor init");
+        supervisor
+            .process_pending_tasks()
+            .await
+            .expect("process pending tasks");
+
+        let requests = server
+            .received_requests()
+            .await
+            .expect("request recording enabled");
+        let complete_request = requests
+            .iter()
+            .find(|request| request.url.path() == format!("/tasks/attempts/{attempt_id}/complete"))
+            .expect("complete request present");
+
+        let body: serde_json::Value = complete_request.body_json().expect("json body");
+        assert_eq!(body["status"], "failed");
+        assert_eq!(body["failure_category"], "infra");
+        assert!(body.get("usage").is_none());
+        assert!(
+            body["log"]
+                .as_str()
+                .expect("log text present")
+                .contains("no capacity")
+        );
+    }
+
+    #[test]
+    fn environment_timeouts_override_the_default() {
+        let args = Args::parse_from([
+            "codex-cloud-supervisor",
+            "--attempt-timeout",
+            "120",
+            "--environment-timeout",
+            "gpu=900,local-dev=30",
+        ]);
+        let config = AppConfig::from(args);
+
+        assert_eq!(config.attempt_timeout(None), Duration::from_secs(120));
+        assert_eq!(
+            config.attempt_timeout(Some("gpu")),
+            Duration::from_secs(900)
+        );
+        assert_eq!(
+            config.attempt_timeout(Some("local-dev")),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            config.attempt_timeout(Some("unknown")),
+            Duration::from_secs(120)
+        );
+    }
+
+    fn test_config(server: &MockServer, cache_root: PathBuf) -> AppConfig {
+        AppConfig {
+            api_base: server.uri(),
+            email: "worker@example.com".into(),
+            password: "password".into(),
+            poll_interval: Duration::from_secs(1),
+            environment_id: None,
+            queues: Vec::new(),
+            max_concurrency: 1,
+            snapshot_pool_size: 0,
+            snapshot_template: None,
+            prewarm_hook: None,
+            snapshot_lifecycle_hook: None,
+            snapshot_max_age: None,
+            snapshot_refill_interval: Duration::from_secs(30),
+            snapshot_health_check: false,
+            cache_root,
+            agent_command: None,
+            attempt_timeout: Duration::from_secs(60),
+            environment_timeouts: HashMap::new(),
+            health_addr: None,
+            drain_timeout: Duration::from_secs(5),
+        }
+    }
+
+    fn write_script(path: &Path, contents: &str) {
+        fs::write(path, contents).expect("write script");
+        #[cfg(unix)]
+        {
+            let mut perms = fs::metadata(path).expect("script metadata").permissions();
+            perms.set_mode(0o755);
+            fs::set_permissions(path, perms).expect("set script permissions");
+        }
+    }
+
+    /// Mocks the API calls of one attempt on `task_id` up to its completion.
+    async fn mount_attempt(server: &MockServer, task_id: Uuid, attempt_id: Uuid) {
+        Mock::given(method("POST"))
+            .and(path("/auth/session"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "test-token"
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path(format!("/tasks/{task_id}/claim")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "claim_expires_at": "2024-01-01T00:00:00Z"
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path(format!("/tasks/{task_id}/attempts")))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "id": attempt_id
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(format!("/tasks/{task_id}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": task_id,
+                "title": "Login form",
+                "description": "Fix the login form",
+                "repository": {
+                    "id": Uuid::new_v4(),
+                    "name": "demo-repo",
+                    "git_url": "https://example.com/demo.git",
+                    "default_branch": "main"
+                }
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path(format!("/tasks/attempts/{attempt_id}/complete")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "status": "succeeded"
+            })))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    async fn completion_body(server: &MockServer, attempt_id: Uuid) -> serde_json::Value {
+        server
+            .received_requests()
+            .await
+            .expect("request recording enabled")
+            .iter()
+            .find(|request| request.url.path() == format!("/tasks/attempts/{attempt_id}/complete"))
+            .expect("complete request present")
+            .body_json()
+            .expect("json body")
+    }
+
+    #[tokio::test]
+    async fn agent_output_and_usage_are_reported() {
+        let server = MockServer::start().await;
+        let task_id = Uuid::new_v4();
+        let attempt_id = Uuid::new_v4();
+        mount_attempt(&server, task_id, attempt_id).await;
+
+        let temp = tempdir().expect("temp dir");
+        let agent_path = temp.path().join("agent.sh");
+        write_script(
+            &agent_path,
+            r#"#!/usr/bin/env bash
+set -euo pipefail
+echo '{"id":"1","msg":{"type":"task_started"}}'
+echo '{"id":"2","msg":{"type":"token_count","info":{"total_token_usage":{"input_tokens":120,"cached_input_tokens":20,"output_tokens":30,"reasoning_output_tokens":4}}}}'
+printf 'diff --git a/app.js b/app.js\n--- a/app.js\n+++ b/app.js\n@@\n+// %s on %s\n' "$CODEX_TASK_PROMPT" "$CODEX_BRANCH" > "$CODEX_DIFF_PATH"
+echo "agent finished" >&2
+"#,
+        );
+        let mut config = test_config(&server, temp.path().join("cache"));
+        config.agent_command = Some(agent_path);
+        let supervisor = Supervisor::new(config).await.expect("supervisor init");
+
+        supervisor
+            .execute_task(TaskListResponse {
+                id: task_id,
+                title: "Login form".to_string(),
+                environment_id: None,
+                retry_after: None,
+            })
+            .await
+            .expect("execute task");
+
+        let body = completion_body(&server, attempt_id).await;
+        assert_eq!(body["status"], "succeeded");
+        assert!(
+            body["diff"]
+                .as_str()
+                .expect("diff text present")
+                .contains("+// Fix the login form on main")
+        );
+        let log = body["log"].as_str().expect("log text present");
+        assert!(log.contains("task_started"));
+        assert!(log.contains("agent finished"));
+        assert_eq!(body["usage"]["input_tokens"], 120);
+        assert_eq!(body["usage"]["output_tokens"], 30);
+        assert!(body["usage"]["wall_time_ms"].is_u64());
+        assert!(body["usage"].get("cpu_time_ms").is_none());
+        assert!(
+            !temp
+                .path()
+                .join("cache/inputs")
+                .join(attempt_id.to_string())
+                .exists()
+        );
+    }
+
+    #[tokio::test]
+    async fn agent_failures_are_classified_from_the_agent_output() {
+        let temp = tempdir().expect("temp dir");
+        let cases = [
+            (
+                "reported",
+                r#"echo '{"id":"1","msg":{"type":"error","message":"context window exceeded"}}'; exit 1"#,
+                "agent",
+                "context window exceeded",
+            ),
+            (
+                "crashed",
+                "echo 'segmentation fault' >&2; exit 139",
+                "infra",
+                "segmentation fault",
+            ),
+        ];
+        for (name, script, category, message) in cases {
+            let server = MockServer::start().await;
+            let task_id = Uuid::new_v4();
+            let attempt_id = Uuid::new_v4();
+            mount_attempt(&server, task_id, attempt_id).await;
+
+            let agent_path = temp.path().join(format!("{name}.sh"));
+            write_script(&agent_path, &format!("#!/usr/bin/env bash\n{script}\n"));
+            let mut config = test_config(&server, temp.path().join(name));
+            config.agent_command = Some(agent_path);
+            let supervisor = Supervisor::new(config).await.expect("supervisor init");
+
+            supervisor
+                .execute_task(TaskListResponse {
+                    id: task_id,
+                    title: "Login form".to_string(),
+                    environment_id: None,
+                    retry_after: None,
+                })
+                .await
+                .expect_err("attempt fails");
+
+            let body = completion_body(&server, attempt_id).await;
+            assert_eq!(body["status"], "failed", "{name}");
+            assert_eq!(body["failure_category"], category, "{name}");
+            assert!(
+                body["log"]
+                    .as_str()
+                    .expect("log text present")
+                    .contains(message),
+                "{name}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn abandoned_attempts_are_released_instead_of_failed() {
+        let server = MockServer::start().await;
+        let task_id = Uuid::new_v4();
+        let attempt_id = Uuid::new_v4();
+
+        Mock::given(method("POST"))
+            .and(path("/auth/session"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "test-token"
+            })))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path(format!("/tasks/{task_id}/claim")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "claim_expires_at": "2024-01-01T00:00:00Z"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path(format!("/tasks/{task_id}/attempts")))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "id": attempt_id
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(format!("/tasks/{task_id}")))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path(format!("/tasks/attempts/{attempt_id}/release")))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path(format!("/tasks/attempts/{attempt_id}/complete")))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let temp = tempdir().expect("temp dir");
+        let supervisor = Supervisor::new(test_config(&server, temp.path().join("cache")))
+            .await
+            .expect("supervisor init");
+        supervisor.inner.abandon.send_replace(true);
+
+        supervisor
+            .execute_task(TaskListResponse {
+                id: task_id,
+                title: "Long running".to_string(),
+                environment_id: None,
+                retry_after: None,
+            })
+            .await
+            .expect("execute task");
+
+        assert_eq!(supervisor.active_attempts(), 0);
+        assert!(supervisor.status().await.in_flight.is_empty());
+    }
+
+    #[tokio::test]
+    async fn draining_supervisor_does_not_claim_tasks() {
+        let server = MockServer::start().await;
+        let task_id = Uuid::new_v4();
+
+        Mock::given(method("POST"))
+            .and(path("/auth/session"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "test-token"
+            })))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path(format!("/tasks/{task_id}/claim")))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let temp = tempdir().expect("temp dir");
+        let supervisor = Supervisor::new(test_config(&server, temp.path().join("cache")))
+            .await
+            .expect("supervisor init");
+        supervisor.inner.draining.store(true, Ordering::SeqCst);
+
+        supervisor
+            .execute_task(TaskListResponse {
+                id: task_id,
+                title: "Late start".to_string(),
+                environment_id: None,
+                retry_after: None,
+            })
+            .await
+            .expect("execute task");
+
+        assert_eq!(supervisor.active_attempts(), 0);
+        assert!(
+            supervisor
+                .wait_for_idle(Instant::now() + Duration::from_secs(1))
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn readiness_tracks_polling_and_draining() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/auth/session"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "test-token"
+            })))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/tasks"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let temp = tempdir().expect("temp dir");
+        let supervisor = Supervisor::new(test_config(&server, temp.path().join("cache")))
+            .await
+            .expect("supervisor init");
+        assert_eq!(supervisor.readiness(), Err("no successful poll yet"));
+
+        supervisor
+            .process_pending_tasks()
+            .await
+            .expect("process pending tasks");
+        assert_eq!(supervisor.readiness(), Ok(()));
+        let status = supervisor.status().await;
+        assert_eq!(status.state, SupervisorState::Running);
+        assert!(status.last_successful_poll.is_some());
+
+        supervisor.drain().await;
+        assert_eq!(supervisor.readiness(), Err("draining"));
+        assert_eq!(supervisor.status().await.state, SupervisorState::Draining);
+        supervisor
+            .process_pending_tasks()
+            .await
+            .expect("draining supervisor skips polling");
+    }
 }
@@ -1,10 +1,17 @@
 use std::collections::VecDeque;
 use std::path::PathBuf;
+use std::process::Output;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, anyhow};
+use futures::future::join_all;
+use serde::Serialize;
 use tokio::process::Command;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Notify};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
 use uuid::Uuid;
 
 #[derive(Clone, Debug)]
@@ -12,6 +19,18 @@ pub(crate) struct PoolSettings {
     pub(crate) size: usize,
     pub(crate) template: Option<String>,
     pub(crate) prewarm_hook: Option<LifecycleHook>,
+    /// Receives the `health`, `recycle` and `destroy` events. Kept separate
+    /// from the prewarm hook, which only ever sees `prewarm`, so that existing
+    /// prewarm hooks that ignore `CODEX_SNAPSHOT_EVENT` are not asked to
+    /// provision a VM when a snapshot is recycled or destroyed.
+    pub(crate) lifecycle_hook: Option<LifecycleHook>,
+    /// Warm snapshots older than this are destroyed and replaced.
+    pub(crate) max_age: Option<Duration>,
+    /// How often the background task tops up the pool when nothing else
+    /// triggers a refill.
+    pub(crate) refill_interval: Duration,
+    /// Probe snapshots through the lifecycle hook before handing them out.
+    pub(crate) health_check: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -25,15 +44,7 @@ impl LifecycleHook {
     }
 
     pub(crate) async fn prewarm(&self, template: Option<&str>) -> Result<String> {
-        let mut command = Command::new(&self.command);
-        command.env("CODEX_SNAPSHOT_EVENT", "prewarm");
-        if let Some(template) = template {
-            command.env("CODEX_SNAPSHOT_TEMPLATE", template);
-        }
-
-        let output = command.output().await.with_context(|| {
-            format!("failed to execute prewarm hook {}", self.command.display())
-        })?;
+        let output = self.run("prewarm", template, None).await?;
 
         if !output.status.success() {
             return Err(anyhow!(
@@ -57,6 +68,58 @@ impl LifecycleHook {
 
         Ok(snapshot_id)
     }
+
+    /// Returns whether the hook considers the snapshot usable. A non-zero exit
+    /// status marks the snapshot as unhealthy.
+    pub(crate) async fn health(&self, template: Option<&str>, snapshot_id: &str) -> Result<bool> {
+        let output = self.run("health", template, Some(snapshot_id)).await?;
+        Ok(output.status.success())
+    }
+
+    /// Resets a snapshot after an attempt so it can be handed out again.
+    pub(crate) async fn recycle(&self, template: Option<&str>, snapshot_id: &str) -> Result<()> {
+        let output = self.run("recycle", template, Some(snapshot_id)).await?;
+        self.ensure_success("recycle", &output)
+    }
+
+    /// Releases the VM resources backing a snapshot.
+    pub(crate) async fn destroy(&self, template: Option<&str>, snapshot_id: &str) -> Result<()> {
+        let output = self.run("destroy", template, Some(snapshot_id)).await?;
+        self.ensure_success("destroy", &output)
+    }
+
+    async fn run(
+        &self,
+        event: &str,
+        template: Option<&str>,
+        snapshot_id: Option<&str>,
+    ) -> Result<Output> {
+        let mut command = Command::new(&self.command);
+        command.env("CODEX_SNAPSHOT_EVENT", event);
+        if let Some(template) = template {
+            command.env("CODEX_SNAPSHOT_TEMPLATE", template);
+        }
+        if let Some(snapshot_id) = snapshot_id {
+            command.env("CODEX_SNAPSHOT_ID", snapshot_id);
+        }
+
+        command
+            .output()
+            .await
+            .with_context(|| format!("failed to execute {event} hook {}", self.command.display()))
+    }
+
+    fn ensure_success(&self, event: &str, output: &Output) -> Result<()> {
+        if output.status.success() {
+            return Ok(());
+        }
+        Err(anyhow!(
+            "{event} hook {} exited with status {}: {}",
+            self.command.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        ))
+    }
 }
 
 #[derive(Clone)]
@@ -66,7 +129,17 @@ pub(crate) struct SnapshotPool {
 
 struct SnapshotPoolInner {
     settings: PoolSettings,
-    available: Mutex<VecDeque<String>>,
+    available: Mutex<VecDeque<WarmSnapshot>>,
+    refill_lock: Mutex<()>,
+    refill_requested: Notify,
+    refill_task: Mutex<Option<JoinHandle<()>>>,
+    destroyed: AtomicU64,
+}
+
+#[derive(Debug, Clone)]
+struct WarmSnapshot {
+    id: String,
+    created_at: Instant,
 }
 
 impl SnapshotPool {
@@ -75,64 +148,174 @@ impl SnapshotPool {
             inner: Arc::new(SnapshotPoolInner {
                 settings,
                 available: Mutex::new(VecDeque::new()),
+                refill_lock: Mutex::new(()),
+                refill_requested: Notify::new(),
+                refill_task: Mutex::new(None),
+                destroyed: AtomicU64::new(0),
             }),
         }
     }
 
+    /// Provisions snapshots until the pool reaches its target size. Missing
+    /// snapshots are created concurrently; concurrent callers are serialised so
+    /// the pool never overshoots.
     pub(crate) async fn ensure_warm_capacity(&self) -> Result<()> {
         let desired = self.inner.settings.size;
         if desired == 0 {
             return Ok(());
         }
 
-        let mut guard = self.inner.available.lock().await;
-        while guard.len() < desired {
-            drop(guard);
-            let snapshot = self.create_snapshot().await?;
-            guard = self.inner.available.lock().await;
-            guard.push_back(snapshot);
+        let _refill = self.inner.refill_lock.lock().await;
+        self.rotate_expired().await;
+        let missing = desired.saturating_sub(self.inner.available.lock().await.len());
+        if missing == 0 {
+            return Ok(());
+        }
+
+        let results = join_all((0..missing).map(|_| self.create_snapshot())).await;
+        let mut first_error = None;
+        for result in results {
+            match result {
+                Ok(snapshot) => {
+                    let mut guard = self.inner.available.lock().await;
+                    if guard.len() < desired {
+                        guard.push_back(snapshot);
+                    } else {
+                        drop(guard);
+                        self.destroy(&snapshot.id).await;
+                    }
+                }
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Spawns the background task that keeps the pool at its target size and
+    /// rotates snapshots that exceeded their maximum age.
+    pub(crate) async fn start_refill(&self) {
+        if self.inner.settings.size == 0 {
+            return;
+        }
+
+        let mut task = self.inner.refill_task.lock().await;
+        if task.is_some() {
+            return;
+        }
+
+        let pool = self.clone();
+        *task = Some(tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = pool.inner.refill_requested.notified() => {}
+                    _ = tokio::time::sleep(pool.inner.settings.refill_interval) => {}
+                }
+                if let Err(err) = pool.ensure_warm_capacity().await {
+                    warn!(error = %err, "Failed to refill snapshot pool");
+                }
+            }
+        }));
+    }
+
+    /// Stops the refill task and destroys every warm snapshot.
+    pub(crate) async fn shutdown(&self) {
+        if let Some(task) = self.inner.refill_task.lock().await.take() {
+            task.abort();
+        }
+
+        let _refill = self.inner.refill_lock.lock().await;
+        let drained: Vec<_> = self.inner.available.lock().await.drain(..).collect();
+        for snapshot in drained {
+            self.destroy(&snapshot.id).await;
         }
-        Ok(())
     }
 
     pub(crate) async fn checkout(&self) -> Result<SnapshotLease> {
         if self.inner.settings.size == 0 {
-            let id = self.create_snapshot().await?;
+            let snapshot = self.create_snapshot().await?;
             return Ok(SnapshotLease {
-                id,
+                id: snapshot.id,
+                created_at: snapshot.created_at,
                 recyclable: false,
             });
         }
 
-        let mut guard = self.inner.available.lock().await;
-        if let Some(id) = guard.pop_front() {
+        loop {
+            let next = self.inner.available.lock().await.pop_front();
+            let Some(snapshot) = next else {
+                break;
+            };
+
+            if self.is_expired(&snapshot) {
+                info!(snapshot_id = %snapshot.id, "Rotating expired snapshot");
+                self.destroy(&snapshot.id).await;
+                continue;
+            }
+
+            if !self.is_healthy(&snapshot.id).await {
+                warn!(snapshot_id = %snapshot.id, "Snapshot failed health check");
+                self.destroy(&snapshot.id).await;
+                continue;
+            }
+
+            self.inner.refill_requested.notify_one();
             return Ok(SnapshotLease {
-                id,
+                id: snapshot.id,
+                created_at: snapshot.created_at,
                 recyclable: true,
             });
         }
-        drop(guard);
 
-        let id = self.create_snapshot().await?;
+        self.inner.refill_requested.notify_one();
+        let snapshot = self.create_snapshot().await?;
         Ok(SnapshotLease {
-            id,
+            id: snapshot.id,
+            created_at: snapshot.created_at,
             recyclable: true,
         })
     }
 
     pub(crate) async fn recycle(&self, lease: SnapshotLease) -> Result<()> {
-        if !lease.recyclable {
+        let snapshot = WarmSnapshot {
+            id: lease.id,
+            created_at: lease.created_at,
+        };
+        if !lease.recyclable || self.is_expired(&snapshot) {
+            self.destroy(&snapshot.id).await;
             return Ok(());
         }
 
+        if let Some(hook) = &self.inner.settings.lifecycle_hook {
+            if let Err(err) = hook
+                .recycle(self.inner.settings.template.as_deref(), &snapshot.id)
+                .await
+            {
+                warn!(snapshot_id = %snapshot.id, error = %err, "Failed to recycle snapshot");
+                self.destroy(&snapshot.id).await;
+                return Ok(());
+            }
+        }
+
         let mut guard = self.inner.available.lock().await;
         if guard.len() < self.inner.settings.size {
-            guard.push_back(lease.id);
+            guard.push_back(snapshot);
+            return Ok(());
         }
+        drop(guard);
+
+        self.destroy(&snapshot.id).await;
         Ok(())
     }
 
-    pub(crate) async fn discard(&self, _lease: SnapshotLease) -> Result<()> {
+    pub(crate) async fn discard(&self, lease: SnapshotLease) -> Result<()> {
+        self.destroy(&lease.id).await;
+        self.inner.refill_requested.notify_one();
         Ok(())
     }
 
@@ -141,21 +324,88 @@ impl SnapshotPool {
         SnapshotPoolMetrics {
             warm: guard.len(),
             target: self.inner.settings.size,
+            destroyed: self.inner.destroyed.load(Ordering::Relaxed),
+        }
+    }
+
+    async fn create_snapshot(&self) -> Result<WarmSnapshot> {
+        let id = if let Some(hook) = &self.inner.settings.prewarm_hook {
+            hook.prewarm(self.inner.settings.template.as_deref())
+                .await?
+        } else {
+            format!("snapshot-{}", Uuid::new_v4())
+        };
+
+        Ok(WarmSnapshot {
+            id,
+            created_at: Instant::now(),
+        })
+    }
+
+    async fn rotate_expired(&self) {
+        let expired: Vec<_> = {
+            let mut guard = self.inner.available.lock().await;
+            let (expired, fresh) = guard
+                .drain(..)
+                .partition::<Vec<_>, _>(|snapshot| self.is_expired(snapshot));
+            guard.extend(fresh);
+            expired
+        };
+
+        for snapshot in expired {
+            info!(snapshot_id = %snapshot.id, "Rotating expired snapshot");
+            self.destroy(&snapshot.id).await;
         }
     }
 
-    async fn create_snapshot(&self) -> Result<String> {
-        if let Some(hook) = &self.inner.settings.prewarm_hook {
-            return hook.prewarm(self.inner.settings.template.as_deref()).await;
+    fn is_expired(&self, snapshot: &WarmSnapshot) -> bool {
+        self.inner
+            .settings
+            .max_age
+            .is_some_and(|max_age| snapshot.created_at.elapsed() >= max_age)
+    }
+
+    async fn is_healthy(&self, snapshot_id: &str) -> bool {
+        if !self.inner.settings.health_check {
+            return true;
         }
+        let Some(hook) = &self.inner.settings.lifecycle_hook else {
+            return true;
+        };
 
-        Ok(format!("snapshot-{}", Uuid::new_v4()))
+        match hook
+            .health(self.inner.settings.template.as_deref(), snapshot_id)
+            .await
+        {
+            Ok(healthy) => healthy,
+            Err(err) => {
+                warn!(snapshot_id, error = %err, "Failed to run snapshot health check");
+                false
+            }
+        }
+    }
+
+    /// Destroys a snapshot through the lifecycle hook. Failures are logged
+    /// rather than propagated so that cleanup never masks attempt results.
+    async fn destroy(&self, snapshot_id: &str) {
+        self.inner.destroyed.fetch_add(1, Ordering::Relaxed);
+        let Some(hook) = &self.inner.settings.lifecycle_hook else {
+            return;
+        };
+
+        if let Err(err) = hook
+            .destroy(self.inner.settings.template.as_deref(), snapshot_id)
+            .await
+        {
+            warn!(snapshot_id, error = %err, "Failed to destroy snapshot");
+        }
     }
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct SnapshotLease {
     id: String,
+    created_at: Instant,
     recyclable: bool,
 }
 
@@ -165,8 +415,153 @@ impl SnapshotLease {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub(crate) struct SnapshotPoolMetrics {
     pub(crate) warm: usize,
     pub(crate) target: usize,
+    pub(crate) destroyed: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn write_hook(dir: &Path, healthy: bool) -> LifecycleHook {
+        let hook_path = dir.join("hook.sh");
+        let health_exit = if healthy { 0 } else { 1 };
+        fs::write(
+            &hook_path,
+            format!(
+                r#"#!/usr/bin/env bash
+set -euo pipefail
+STATE_DIR="$(cd "$(dirname "$0")" && pwd)"
+echo "${{CODEX_SNAPSHOT_EVENT}}:${{CODEX_SNAPSHOT_ID:-}}" >> "${{STATE_DIR}}/events.log"
+case "${{CODEX_SNAPSHOT_EVENT}}" in
+  prewarm) echo "snap-$RANDOM$RANDOM" ;;
+  health) exit {health_exit} ;;
+esac
+"#
+            ),
+        )
+        .expect("write hook script");
+        #[cfg(unix)]
+        {
+            let mut perms = fs::metadata(&hook_path)
+                .expect("hook metadata")
+                .permissions();
+            perms.set_mode(0o755);
+            fs::set_permissions(&hook_path, perms).expect("set hook permissions");
+        }
+        LifecycleHook::new(hook_path)
+    }
+
+    fn settings(hook: LifecycleHook) -> PoolSettings {
+        PoolSettings {
+            size: 1,
+            template: None,
+            prewarm_hook: Some(hook.clone()),
+            lifecycle_hook: Some(hook),
+            max_age: None,
+            refill_interval: Duration::from_secs(60),
+            health_check: false,
+        }
+    }
+
+    fn events(dir: &Path) -> Vec<String> {
+        fs::read_to_string(dir.join("events.log"))
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn expired_snapshots_are_destroyed_and_replaced() {
+        let temp = tempdir().expect("temp dir");
+        let mut settings = settings(write_hook(temp.path(), true));
+        settings.max_age = Some(Duration::ZERO);
+        let pool = SnapshotPool::new(settings);
+
+        pool.ensure_warm_capacity().await.expect("warm pool");
+        pool.ensure_warm_capacity().await.expect("rotate pool");
+
+        let events = events(temp.path());
+        assert_eq!(
+            events.iter().filter(|e| e.starts_with("prewarm")).count(),
+            2
+        );
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| e.starts_with("destroy:snap-"))
+                .count(),
+            1
+        );
+        assert_eq!(pool.metrics().await.destroyed, 1);
+    }
+
+    #[tokio::test]
+    async fn unhealthy_snapshots_are_skipped_on_checkout() {
+        let temp = tempdir().expect("temp dir");
+        let mut settings = settings(write_hook(temp.path(), false));
+        settings.health_check = true;
+        let pool = SnapshotPool::new(settings);
+        pool.ensure_warm_capacity().await.expect("warm pool");
+
+        let lease = pool.checkout().await.expect("checkout");
+        let events = events(temp.path());
+        let probed = events
+            .iter()
+            .find_map(|event| event.strip_prefix("health:"))
+            .expect("health probe recorded");
+        assert!(events.contains(&format!("destroy:{probed}")));
+        assert_ne!(lease.snapshot_id(), probed);
+    }
+
+    #[tokio::test]
+    async fn recycled_and_discarded_leases_reach_the_hook() {
+        let temp = tempdir().expect("temp dir");
+        let pool = SnapshotPool::new(settings(write_hook(temp.path(), true)));
+        pool.ensure_warm_capacity().await.expect("warm pool");
+
+        let lease = pool.checkout().await.expect("checkout");
+        let recycled = lease.snapshot_id().to_string();
+        pool.recycle(lease).await.expect("recycle");
+        assert_eq!(pool.metrics().await.warm, 1);
+
+        let lease = pool.checkout().await.expect("checkout");
+        assert_eq!(lease.snapshot_id(), recycled);
+        pool.discard(lease).await.expect("discard");
+
+        let events = events(temp.path());
+        assert!(events.contains(&format!("recycle:{recycled}")));
+        assert!(events.contains(&format!("destroy:{recycled}")));
+        assert_eq!(pool.metrics().await.warm, 0);
+    }
+
+    #[tokio::test]
+    async fn prewarm_hook_only_receives_prewarm_events() {
+        let temp = tempdir().expect("temp dir");
+        let mut settings = settings(write_hook(temp.path(), true));
+        settings.lifecycle_hook = None;
+        settings.health_check = true;
+        let pool = SnapshotPool::new(settings);
+        pool.ensure_warm_capacity().await.expect("warm pool");
+
+        let lease = pool.checkout().await.expect("checkout");
+        pool.recycle(lease).await.expect("recycle");
+        let lease = pool.checkout().await.expect("checkout");
+        pool.discard(lease).await.expect("discard");
+        pool.shutdown().await;
+
+        let events = events(temp.path());
+        assert!(!events.is_empty());
+        assert!(events.iter().all(|event| event.starts_with("prewarm:")));
+        assert_eq!(pool.metrics().await.destroyed, 1);
+    }
 }
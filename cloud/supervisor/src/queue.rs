@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+use crate::TaskListResponse;
+
+/// An environment queue the supervisor pulls work from. Weights are relative:
+/// a queue with weight 3 is offered three tasks for every one offered by a
+/// queue with weight 1 while both have pending work.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct QueueSubscription {
+    pub(crate) environment_id: String,
+    pub(crate) weight: u32,
+}
+
+pub(crate) fn parse_queue(value: &str) -> Result<QueueSubscription, String> {
+    let (environment_id, weight) = match value.split_once('=') {
+        Some((environment_id, weight)) => {
+            let weight = weight
+                .trim()
+                .parse::<u32>()
+                .map_err(|err| format!("invalid weight for `{environment_id}`: {err}"))?;
+            (environment_id.trim(), weight)
+        }
+        None => (value.trim(), 1),
+    };
+    if environment_id.is_empty() {
+        return Err(format!("expected ENVIRONMENT_ID[=WEIGHT], got `{value}`"));
+    }
+    if weight == 0 {
+        return Err(format!("weight for `{environment_id}` must be at least 1"));
+    }
+    Ok(QueueSubscription {
+        environment_id: environment_id.to_string(),
+        weight,
+    })
+}
+
+/// Orders pending tasks across the subscribed queues using smooth weighted
+/// round-robin. Tasks keep the order the backend returned them in within each
+/// queue; tasks from environments without a subscription are dropped. With no
+/// subscriptions every task is accepted in backend order.
+pub(crate) fn schedule(
+    tasks: Vec<TaskListResponse>,
+    queues: &[QueueSubscription],
+) -> Vec<TaskListResponse> {
+    if queues.is_empty() {
+        return tasks;
+    }
+
+    let mut pending: HashMap<&str, VecDeque<TaskListResponse>> = queues
+        .iter()
+        .map(|queue| (queue.environment_id.as_str(), VecDeque::new()))
+        .collect();
+    let mut total = 0;
+    for task in tasks {
+        let Some(environment_id) = task.environment_id.as_deref() else {
+            continue;
+        };
+        if let Some(queue) = pending.get_mut(environment_id) {
+            queue.push_back(task);
+            total += 1;
+        }
+    }
+
+    let mut credit = vec![0i64; queues.len()];
+    let mut scheduled = Vec::with_capacity(total);
+    while scheduled.len() < total {
+        let active = queues
+            .iter()
+            .enumerate()
+            .filter(|(_, queue)| !pending[queue.environment_id.as_str()].is_empty())
+            .map(|(index, queue)| (index, i64::from(queue.weight)))
+            .collect::<Vec<_>>();
+        let active_weight: i64 = active.iter().map(|(_, weight)| weight).sum();
+
+        let mut selected = active[0].0;
+        for &(index, weight) in &active {
+            credit[index] += weight;
+            if credit[index] > credit[selected] {
+                selected = index;
+            }
+        }
+        credit[selected] -= active_weight;
+
+        let queue = pending
+            .get_mut(queues[selected].environment_id.as_str())
+            .expect("subscribed queue");
+        scheduled.extend(queue.pop_front());
+    }
+    scheduled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn task(environment_id: &str, title: &str) -> TaskListResponse {
+        TaskListResponse {
+            id: Uuid::new_v4(),
+            title: title.to_string(),
+            environment_id: Some(environment_id.to_string()),
+            retry_after: None,
+        }
+    }
+
+    #[test]
+    fn weighted_queues_interleave_in_proportion() {
+        let queues = vec![
+            parse_queue("frontend=2").unwrap(),
+            parse_queue("backend").unwrap(),
+        ];
+        let tasks = vec![
+            task("frontend", "f1"),
+            task("frontend", "f2"),
+            task("frontend", "f3"),
+            task("frontend", "f4"),
+            task("other", "o1"),
+            task("backend", "b1"),
+            task("backend", "b2"),
+        ];
+
+        let titles = schedule(tasks, &queues)
+            .into_iter()
+            .map(|task| task.title)
+            .collect::<Vec<_>>();
+        assert_eq!(titles, vec!["f1", "b1", "f2", "f3", "b2", "f4"]);
+    }
+
+    #[test]
+    fn rejects_invalid_subscriptions() {
+        assert!(parse_queue("frontend=0").is_err());
+        assert!(parse_queue("=2").is_err());
+        assert!(parse_queue("frontend=many").is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use anyhow::{Context, Error as AnyError, Result, anyhow};
+use serde::Deserialize;
+use tokio::fs;
+use tokio::process::Command;
+
+use crate::AttemptContext;
+use crate::failure::AgentFailure;
+use crate::usage::{TokenCounter, TokenUsage};
+
+/// External program that performs the work of an attempt, such as a wrapper
+/// around `codex exec --json`.
+///
+/// The program runs in a per-attempt working directory and learns about the
+/// task through `CODEX_*` environment variables. It streams its events as JSON
+/// lines on stdout and writes the resulting diff to `CODEX_DIFF_PATH`.
+#[derive(Clone, Debug)]
+pub(crate) struct AgentCommand {
+    command: PathBuf,
+}
+
+/// What the agent left behind once it exited successfully.
+pub(crate) struct AgentRun {
+    pub(crate) diff: String,
+    pub(crate) log: String,
+    pub(crate) tokens: TokenUsage,
+}
+
+/// Everything about the attempt the agent is told besides the task itself.
+pub(crate) struct AgentWorkspace<'a> {
+    pub(crate) directory: &'a Path,
+    pub(crate) snapshot_id: &'a str,
+    pub(crate) cache_root: &'a Path,
+    pub(crate) repository_cache: Option<&'a Path>,
+    pub(crate) inputs: &'a [PathBuf],
+}
+
+impl AgentCommand {
+    pub(crate) fn new(command: PathBuf) -> Self {
+        Self { command }
+    }
+
+    pub(crate) async fn run(
+        &self,
+        context: &AttemptContext,
+        workspace: AgentWorkspace<'_>,
+    ) -> Result<AgentRun> {
+        fs::create_dir_all(workspace.directory)
+            .await
+            .with_context(|| {
+                format!(
+                    "failed to create agent directory {}",
+                    workspace.directory.display()
+                )
+            })?;
+        let diff_path = workspace.directory.join("agent.diff");
+
+        let mut command = Command::new(&self.command);
+        command
+            .current_dir(workspace.directory)
+            .stdin(Stdio::null())
+            // The runner future is dropped when the attempt times out or is
+            // abandoned; the agent must not outlive it.
+            .kill_on_drop(true)
+            .env("CODEX_TASK_ID", context.task.id.to_string())
+            .env("CODEX_ATTEMPT_ID", context.attempt.id.to_string())
+            .env("CODEX_TASK_TITLE", &context.task.title)
+            .env("CODEX_SNAPSHOT_ID", workspace.snapshot_id)
+            .env("CODEX_CACHE_ROOT", workspace.cache_root)
+            .env("CODEX_DIFF_PATH", &diff_path);
+        if let Some(repository_cache) = workspace.repository_cache {
+            command.env("CODEX_REPOSITORY_CACHE", repository_cache);
+        }
+        if !workspace.inputs.is_empty() {
+            let inputs = workspace
+                .inputs
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            command.env("CODEX_INPUTS", inputs);
+        }
+        if let Some(detail) = context.detail.as_ref() {
+            command.env(
+                "CODEX_TASK_PROMPT",
+                detail.description.as_deref().unwrap_or(&detail.title),
+            );
+            if let Some(repository) = detail.repository.as_ref() {
+                command
+                    .env("CODEX_REPOSITORY_URL", &repository.git_url)
+                    .env(
+                        "CODEX_BRANCH",
+                        detail
+                            .branch
+                            .as_deref()
+                            .unwrap_or(&repository.default_branch),
+                    );
+            }
+            if detail.qa_mode {
+                command.env("CODEX_QA_MODE", "1");
+            }
+        } else {
+            command.env("CODEX_TASK_PROMPT", &context.task.title);
+        }
+
+        let output = command
+            .output()
+            .await
+            .with_context(|| format!("failed to execute agent {}", self.command.display()))?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        let mut tokens = TokenCounter::default();
+        for line in stdout.lines() {
+            tokens.observe(line);
+        }
+
+        if !output.status.success() {
+            let exited = anyhow!(
+                "agent {} exited with status {}: {}",
+                self.command.display(),
+                output.status,
+                stderr
+            );
+            // An agent that reports an error is classified by that error;
+            // one that dies without saying why is treated as an
+            // infrastructure problem and retried.
+            return Err(match reported_error(&stdout) {
+                Some(reason) => AnyError::new(AgentFailure { reason }).context(exited),
+                None => exited,
+            });
+        }
+
+        let diff = match fs::read_to_string(&diff_path).await {
+            Ok(diff) => diff,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to read agent diff {}", diff_path.display()));
+            }
+        };
+
+        let mut log = stdout.into_owned();
+        if !stderr.is_empty() {
+            if !log.is_empty() && !log.ends_with('\n') {
+                log.push('\n');
+            }
+            log.push_str(&stderr);
+        }
+
+        Ok(AgentRun {
+            diff,
+            log,
+            tokens: tokens.total(),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct EventLine {
+    msg: EventMsg,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum EventMsg {
+    Error {
+        message: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// The message of the last `error` event in the agent's event stream.
+fn reported_error(events: &str) -> Option<String> {
+    events
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<EventLine>(line).ok())
+        .find_map(|event| match event.msg {
+            EventMsg::Error { message } => Some(message),
+            EventMsg::Other => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_last_reported_error_wins() {
+        assert_eq!(reported_error(""), None);
+        assert_eq!(
+            reported_error("{\"id\":\"1\",\"msg\":{\"type\":\"task_started\"}}\nnot json\n"),
+            None
+        );
+        assert_eq!(
+            reported_error(concat!(
+                r#"{"id":"1","msg":{"type":"error","message":"stream disconnected"}}"#,
+                "\n",
+                r#"{"id":"2","msg":{"type":"agent_message","message":"retrying"}}"#,
+                "\n",
+                r#"{"id":"3","msg":{"type":"error","message":"context window exceeded"}}"#,
+            )),
+            Some("context window exceeded".to_string())
+        );
+    }
+}
@@ -1,13 +1,23 @@
+use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::{Context, Result};
 use chrono::Utc;
+use reqwest::Client;
 use tokio::fs;
+use tracing::warn;
 
 use crate::pool::SnapshotLease;
+use crate::usage::AttemptUsage;
 use crate::{AttemptArtifacts, AttemptContext};
 
+mod agent;
+
+pub(crate) use agent::AgentCommand;
+use agent::AgentWorkspace;
+
 #[derive(Clone)]
 pub(crate) struct Runner {
     inner: Arc<RunnerInner>,
@@ -15,23 +25,61 @@ pub(crate) struct Runner {
 
 struct RunnerInner {
     cache: CacheLayout,
+    client: Client,
+    /// Without an agent the runner only records what it would have run with.
+    agent: Option<AgentCommand>,
 }
 
 impl Runner {
-    pub(crate) async fn new(cache_root: PathBuf) -> Result<Self> {
+    pub(crate) async fn new(
+        cache_root: PathBuf,
+        client: Client,
+        agent: Option<AgentCommand>,
+    ) -> Result<Self> {
         let cache = CacheLayout::new(cache_root);
         cache.ensure_directories().await?;
         Ok(Self {
-            inner: Arc::new(RunnerInner { cache }),
+            inner: Arc::new(RunnerInner {
+                cache,
+                client,
+                agent,
+            }),
         })
     }
 
+    /// Runs the attempt. `access_token` authenticates the downloads of the
+    /// task's inputs, which the API only serves to the task's assignee.
     pub(crate) async fn execute(
         &self,
         context: &AttemptContext,
         snapshot: &SnapshotLease,
+        access_token: &str,
     ) -> Result<AttemptArtifacts> {
+        let started = Instant::now();
         let repository_cache = self.inner.cache.prepare_repository_cache(context).await?;
+        let inputs = self.download_inputs(context, access_token).await?;
+
+        if let Some(agent) = self.inner.agent.as_ref() {
+            let run = agent
+                .run(
+                    context,
+                    AgentWorkspace {
+                        directory: &self.inputs_directory(context),
+                        snapshot_id: snapshot.snapshot_id(),
+                        cache_root: &self.inner.cache.root,
+                        repository_cache: repository_cache.as_deref(),
+                        inputs: &inputs,
+                    },
+                )
+                .await?;
+            // The agent runs as a child process whose CPU time is not
+            // measured, so only tokens and wall-clock time are reported.
+            return Ok(AttemptArtifacts {
+                diff: Some(run.diff),
+                log: Some(run.log),
+                usage: Some(AttemptUsage::new(run.tokens, started.elapsed(), None)),
+            });
+        }
 
         let timestamp = Utc::now().to_rfc3339();
         let diff = build_diff(
@@ -40,6 +88,7 @@ impl Runner {
             snapshot,
             &self.inner.cache,
             repository_cache.as_deref(),
+            &inputs,
         );
         let log = build_log(
             context,
@@ -47,13 +96,84 @@ impl Runner {
             snapshot,
             &self.inner.cache,
             repository_cache.as_deref(),
+            &inputs,
         );
 
+        // Nothing was consumed, so there is no usage to report.
         Ok(AttemptArtifacts {
             diff: Some(diff),
             log: Some(log),
+            usage: None,
         })
     }
+
+    /// Removes the attempt's directory with its downloaded inputs and whatever
+    /// the agent left there. Called once the attempt is over, however it ended.
+    pub(crate) async fn remove_inputs(&self, context: &AttemptContext) {
+        let directory = self.inputs_directory(context);
+        match fs::remove_dir_all(&directory).await {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => warn!(
+                attempt_id = %context.attempt.id,
+                directory = %directory.display(),
+                error = %err,
+                "Failed to remove attempt inputs"
+            ),
+        }
+    }
+
+    fn inputs_directory(&self, context: &AttemptContext) -> PathBuf {
+        self.inner.cache.inputs.join(context.attempt.id.to_string())
+    }
+
+    /// Downloads the task's image and file inputs into a per-attempt
+    /// directory so they can be handed to the agent.
+    async fn download_inputs(
+        &self,
+        context: &AttemptContext,
+        access_token: &str,
+    ) -> Result<Vec<PathBuf>> {
+        let Some(detail) = context.detail.as_ref() else {
+            return Ok(Vec::new());
+        };
+        if detail.inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let directory = self.inputs_directory(context);
+        fs::create_dir_all(&directory)
+            .await
+            .with_context(|| format!("failed to create input directory {}", directory.display()))?;
+
+        let mut paths = Vec::with_capacity(detail.inputs.len());
+        for (index, input) in detail.inputs.iter().enumerate() {
+            // Only keep the final path component of client-supplied names.
+            let name = input
+                .name
+                .as_deref()
+                .and_then(|name| Path::new(name).file_name())
+                .and_then(|name| name.to_str())
+                .unwrap_or(&input.artifact_id);
+            let path = directory.join(format!("{index:02}-{name}"));
+            let content = self
+                .inner
+                .client
+                .get(&input.url)
+                .bearer_auth(access_token)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .with_context(|| format!("failed to download {} input {}", input.kind, input.url))?
+                .bytes()
+                .await?;
+            fs::write(&path, &content)
+                .await
+                .with_context(|| format!("failed to write input {}", path.display()))?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -63,6 +183,7 @@ struct CacheLayout {
     npm: PathBuf,
     pip: PathBuf,
     cargo: PathBuf,
+    inputs: PathBuf,
 }
 
 impl CacheLayout {
@@ -71,17 +192,26 @@ impl CacheLayout {
         let npm = root.join("npm");
         let pip = root.join("pip");
         let cargo = root.join("cargo");
+        let inputs = root.join("inputs");
         Self {
             root,
             git,
             npm,
             pip,
             cargo,
+            inputs,
         }
     }
 
     async fn ensure_directories(&self) -> Result<()> {
-        for path in [&self.root, &self.git, &self.npm, &self.pip, &self.cargo] {
+        for path in [
+            &self.root,
+            &self.git,
+            &self.npm,
+            &self.pip,
+            &self.cargo,
+            &self.inputs,
+        ] {
             fs::create_dir_all(path)
                 .await
                 .with_context(|| format!("failed to create cache directory {}", path.display()))?;
@@ -111,6 +241,7 @@ fn build_diff(
     snapshot: &SnapshotLease,
     cache: &CacheLayout,
     repository_cache: Option<&Path>,
+    inputs: &[PathBuf],
 ) -> String {
     let mut diff = String::new();
     diff.push_str("diff --git a/TASK_LOG.md b/TASK_LOG.md\n");
@@ -143,11 +274,21 @@ fn build_diff(
             diff.push_str(&format!("+Environment: {}\\n", environment_id));
         }
         if let Some(repository) = &detail.repository {
+            let branch = detail
+                .branch
+                .as_deref()
+                .unwrap_or(&repository.default_branch);
             diff.push_str(&format!(
                 "+Repository: {} ({}) on branch {} (id {})\\n",
-                repository.name, repository.git_url, repository.default_branch, repository.id
+                repository.name, repository.git_url, branch, repository.id
             ));
         }
+        if detail.qa_mode {
+            diff.push_str("+QA mode: enabled\\n");
+        }
+        for input in inputs {
+            diff.push_str(&format!("+Input: {}\\n", input.display()));
+        }
         if let Some(description) = &detail.description {
             for line in description.lines() {
                 diff.push_str("+> ");
@@ -166,6 +307,7 @@ fn build_log(
     snapshot: &SnapshotLease,
     cache: &CacheLayout,
     repository_cache: Option<&Path>,
+    inputs: &[PathBuf],
 ) -> String {
     let mut log = format!(
         "[{timestamp}] Attempt {} succeeded for task {} ({})",
@@ -199,6 +341,15 @@ fn build_log(
         if let Some(environment_id) = &detail.environment_id {
             log.push_str(&format!("\nEnvironment: {}", environment_id));
         }
+        if let Some(branch) = &detail.branch {
+            log.push_str(&format!("\nRequested git ref: {branch}"));
+        }
+        if detail.qa_mode {
+            log.push_str("\nQA mode: enabled");
+        }
+        for input in inputs {
+            log.push_str(&format!("\nInput: {}", input.display()));
+        }
         if let Some(description) = &detail.description {
             log.push_str("\nTask description:\n");
             log.push_str(description);
@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Token totals reported by the agent's `TokenCount` events.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct TokenUsage {
+    #[serde(default)]
+    pub(crate) input_tokens: u64,
+    #[serde(default)]
+    pub(crate) cached_input_tokens: u64,
+    #[serde(default)]
+    pub(crate) output_tokens: u64,
+    #[serde(default)]
+    pub(crate) reasoning_output_tokens: u64,
+}
+
+/// Follows the agent's event stream and keeps the latest cumulative token
+/// totals. Each `token_count` event carries the running total for the whole
+/// session, so the last one seen is the usage of the attempt.
+#[derive(Debug, Default)]
+pub(crate) struct TokenCounter {
+    total: TokenUsage,
+}
+
+#[derive(Deserialize)]
+struct EventLine {
+    msg: EventMsg,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum EventMsg {
+    TokenCount {
+        info: Option<TokenCountInfo>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct TokenCountInfo {
+    total_token_usage: TokenUsage,
+}
+
+impl TokenCounter {
+    /// Records one JSON line of agent output. Lines that are not
+    /// `token_count` events are ignored.
+    pub(crate) fn observe(&mut self, line: &str) {
+        let Ok(event) = serde_json::from_str::<EventLine>(line) else {
+            return;
+        };
+        if let EventMsg::TokenCount { info: Some(info) } = event.msg {
+            self.total = info.total_token_usage;
+        }
+    }
+
+    pub(crate) fn total(&self) -> TokenUsage {
+        self.total
+    }
+}
+
+/// Usage reported to the API when an attempt completes.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub(crate) struct AttemptUsage {
+    #[serde(flatten)]
+    pub(crate) tokens: TokenUsage,
+    pub(crate) wall_time_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) cpu_time_ms: Option<u64>,
+}
+
+impl AttemptUsage {
+    pub(crate) fn new(tokens: TokenUsage, wall_time: Duration, cpu_time: Option<Duration>) -> Self {
+        Self {
+            tokens,
+            wall_time_ms: duration_ms(wall_time),
+            cpu_time_ms: cpu_time.map(duration_ms),
+        }
+    }
+}
+
+fn duration_ms(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counter_keeps_the_latest_total() {
+        let mut counter = TokenCounter::default();
+        counter.observe(r#"{"id":"1","msg":{"type":"task_started"}}"#);
+        counter.observe("not json");
+        counter.observe(r#"{"id":"2","msg":{"type":"token_count","info":null}}"#);
+        assert_eq!(counter.total(), TokenUsage::default());
+
+        counter.observe(
+            r#"{"id":"3","msg":{"type":"token_count","info":{"total_token_usage":{"input_tokens":100,"cached_input_tokens":40,"output_tokens":20,"reasoning_output_tokens":5,"total_tokens":120},"last_token_usage":{"input_tokens":100}}}}"#,
+        );
+        counter.observe(
+            r#"{"id":"4","msg":{"type":"token_count","info":{"total_token_usage":{"input_tokens":250,"cached_input_tokens":90,"output_tokens":45,"reasoning_output_tokens":12,"total_tokens":295}}}}"#,
+        );
+        assert_eq!(
+            counter.total(),
+            TokenUsage {
+                input_tokens: 250,
+                cached_input_tokens: 90,
+                output_tokens: 45,
+                reasoning_output_tokens: 12,
+            }
+        );
+    }
+
+    #[test]
+    fn attempt_usage_serializes_flat_counters() {
+        let usage = AttemptUsage::new(
+            TokenUsage {
+                input_tokens: 7,
+                ..TokenUsage::default()
+            },
+            Duration::from_millis(1500),
+            None,
+        );
+        let value = serde_json::to_value(usage).unwrap();
+        assert_eq!(value["input_tokens"], 7);
+        assert_eq!(value["wall_time_ms"], 1500);
+        assert!(value.get("cpu_time_ms").is_none());
+    }
+}
@@ -0,0 +1,236 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use codex_core::approval_rules::ApprovalRules;
+use codex_core::approval_rules::ProjectRules;
+use codex_core::approval_rules::RuleKind;
+use codex_core::approval_rules::project_root_for_rules;
+use codex_core::config::find_codex_home;
+
+/// Manage persistent command approval rules.
+///
+/// Rules are stored per project in `~/.codex/approval_rules.toml`. Each rule
+/// is a command prefix whose words may use `*` and `?` globs.
+///
+/// Subcommands:
+/// - `list`   — list rules for the project (or `--all` projects)
+/// - `allow`  — always allow commands starting with a prefix
+/// - `deny`   — always reject commands starting with a prefix
+/// - `remove` — delete a rule
+#[derive(Debug, clap::Parser)]
+pub struct ApprovalsCli {
+    /// Project whose rules to manage. Defaults to the repository that contains
+    /// the current directory.
+    #[arg(long, global = true, value_name = "DIR")]
+    pub project: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub subcommand: ApprovalsSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum ApprovalsSubcommand {
+    /// List approval rules.
+    List(ListArgs),
+
+    /// Always allow commands that start with PREFIX in the project.
+    Allow(RuleArgs),
+
+    /// Always reject commands that start with PREFIX in the project.
+    Deny(RuleArgs),
+
+    /// Remove an allow (or, with --deny, a deny) rule.
+    Remove(RemoveArgs),
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct ListArgs {
+    /// List rules for every project instead of just the current one.
+    #[arg(long)]
+    pub all: bool,
+
+    /// Output the rules as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct RuleArgs {
+    /// Command prefix, e.g. `cargo test`.
+    #[arg(
+        value_name = "PREFIX",
+        required = true,
+        num_args = 1..,
+        trailing_var_arg = true,
+        allow_hyphen_values = true
+    )]
+    pub prefix: Vec<String>,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct RemoveArgs {
+    /// Remove a deny rule instead of an allow rule.
+    #[arg(long)]
+    pub deny: bool,
+
+    #[command(flatten)]
+    pub rule: RuleArgs,
+}
+
+impl ApprovalsCli {
+    pub fn run(self) -> Result<()> {
+        let ApprovalsCli {
+            project,
+            subcommand,
+        } = self;
+
+        let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
+        let project = match project {
+            Some(project) => project,
+            None => std::env::current_dir().context("failed to read current directory")?,
+        };
+        let project = project_root_for_rules(&project);
+
+        match subcommand {
+            ApprovalsSubcommand::List(args) => run_list(&codex_home, &project, args)?,
+            ApprovalsSubcommand::Allow(args) => {
+                run_add(&codex_home, &project, RuleKind::Allow, args)?;
+            }
+            ApprovalsSubcommand::Deny(args) => {
+                run_add(&codex_home, &project, RuleKind::Deny, args)?;
+            }
+            ApprovalsSubcommand::Remove(args) => run_remove(&codex_home, &project, args)?,
+        }
+
+        Ok(())
+    }
+}
+
+fn load_rules(codex_home: &Path) -> Result<ApprovalRules> {
+    ApprovalRules::load(codex_home).with_context(|| {
+        format!(
+            "failed to load approval rules from {}",
+            codex_home.display()
+        )
+    })
+}
+
+fn save_rules(codex_home: &Path, rules: &ApprovalRules) -> Result<()> {
+    rules
+        .save(codex_home)
+        .with_context(|| format!("failed to write approval rules to {}", codex_home.display()))
+}
+
+fn run_list(codex_home: &Path, project: &Path, list_args: ListArgs) -> Result<()> {
+    let rules = load_rules(codex_home)?;
+    let entries: Vec<(String, ProjectRules)> = if list_args.all {
+        rules.projects.into_iter().collect()
+    } else {
+        rules
+            .project(project)
+            .map(|project_rules| {
+                vec![(
+                    project.to_string_lossy().into_owned(),
+                    project_rules.clone(),
+                )]
+            })
+            .unwrap_or_default()
+    };
+
+    if list_args.json {
+        let json_entries: Vec<_> = entries
+            .iter()
+            .map(|(project, project_rules)| {
+                serde_json::json!({
+                    "project": project,
+                    "allow": project_rules.allow,
+                    "deny": project_rules.deny,
+                })
+            })
+            .collect();
+        let output = serde_json::to_string_pretty(&json_entries)?;
+        println!("{output}");
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!(
+            "No approval rules for {}. Try `codex approvals allow cargo test`.",
+            project.display()
+        );
+        return Ok(());
+    }
+
+    for (index, (project, project_rules)) in entries.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        println!("{project}");
+        for (kind, rules) in [
+            (RuleKind::Allow, &project_rules.allow),
+            (RuleKind::Deny, &project_rules.deny),
+        ] {
+            for rule in rules {
+                println!("  {:<5}  {}", kind.as_str(), rule.join(" "));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_add(codex_home: &Path, project: &Path, kind: RuleKind, args: RuleArgs) -> Result<()> {
+    let RuleArgs { prefix } = args;
+    let display = prefix.join(" ");
+
+    let mut rules = load_rules(codex_home)?;
+    if rules.add(project, kind, prefix) {
+        save_rules(codex_home, &rules)?;
+        println!(
+            "Added {} rule `{display}` for {}.",
+            kind.as_str(),
+            project.display()
+        );
+    } else {
+        println!(
+            "The {} rule `{display}` already exists for {}.",
+            kind.as_str(),
+            project.display()
+        );
+    }
+
+    Ok(())
+}
+
+fn run_remove(codex_home: &Path, project: &Path, args: RemoveArgs) -> Result<()> {
+    let RemoveArgs {
+        deny,
+        rule: RuleArgs { prefix },
+    } = args;
+    let kind = if deny {
+        RuleKind::Deny
+    } else {
+        RuleKind::Allow
+    };
+    let display = prefix.join(" ");
+
+    let mut rules = load_rules(codex_home)?;
+    if rules.remove(project, kind, &prefix) {
+        save_rules(codex_home, &rules)?;
+        println!(
+            "Removed {} rule `{display}` for {}.",
+            kind.as_str(),
+            project.display()
+        );
+    } else {
+        println!(
+            "No {} rule `{display}` found for {}.",
+            kind.as_str(),
+            project.display()
+        );
+    }
+
+    Ok(())
+}
@@ -23,8 +23,10 @@ use owo_colors::OwoColorize;
 use std::path::PathBuf;
 use supports_color::Stream;
 
+mod approvals_cmd;
 mod mcp_cmd;
 
+use crate::approvals_cmd::ApprovalsCli;
 use crate::mcp_cmd::McpCli;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
@@ -79,6 +81,9 @@ enum Subcommand {
     /// [experimental] Run the Codex MCP server (stdio transport).
     McpServer,
 
+    /// Manage persistent command approval rules for a project.
+    Approvals(ApprovalsCli),
+
     /// [experimental] Run the app server.
     AppServer,
 
@@ -363,6 +368,9 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
             prepend_config_flags(&mut mcp_cli.config_overrides, root_config_overrides.clone());
             mcp_cli.run().await?;
         }
+        Some(Subcommand::Approvals(approvals_cli)) => {
+            approvals_cli.run()?;
+        }
         Some(Subcommand::AppServer) => {
             codex_app_server::run_main(codex_linux_sandbox_exe, root_config_overrides).await?;
         }
@@ -240,6 +240,10 @@ async fn run_add(config_overrides: &CliConfigOverrides, add_args: AddArgs) -> Re
         enabled: true,
         startup_timeout_sec: None,
         tool_timeout_sec: None,
+        enabled_tools: None,
+        disabled_tools: None,
+        approval_mode: None,
+        tool_approval_modes: HashMap::new(),
     };
 
     servers.insert(name.clone(), new_entry);
@@ -607,6 +611,10 @@ async fn run_get(config_overrides: &CliConfigOverrides, get_args: GetArgs) -> Re
             "tool_timeout_sec": server
                 .tool_timeout_sec
                 .map(|timeout| timeout.as_secs_f64()),
+            "enabled_tools": server.enabled_tools,
+            "disabled_tools": server.disabled_tools,
+            "approval_mode": server.approval_mode,
+            "tool_approval_modes": server.tool_approval_modes,
         }))?;
         println!("{output}");
         return Ok(());
@@ -655,6 +663,25 @@ async fn run_get(config_overrides: &CliConfigOverrides, get_args: GetArgs) -> Re
     if let Some(timeout) = server.tool_timeout_sec {
         println!("  tool_timeout_sec: {}", timeout.as_secs_f64());
     }
+    if let Some(patterns) = &server.enabled_tools {
+        println!("  enabled_tools: {}", patterns.join(", "));
+    }
+    if let Some(patterns) = &server.disabled_tools {
+        println!("  disabled_tools: {}", patterns.join(", "));
+    }
+    if let Some(mode) = server.approval_mode {
+        println!("  approval_mode: {}", mode.as_str());
+    }
+    if !server.tool_approval_modes.is_empty() {
+        let mut modes: Vec<_> = server.tool_approval_modes.iter().collect();
+        modes.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let modes_display = modes
+            .into_iter()
+            .map(|(tool, mode)| format!("{tool}={}", mode.as_str()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!("  tool_approval_modes: {modes_display}");
+    }
     println!("  remove: codex mcp remove {}", get_args.name);
 
     Ok(())
@@ -0,0 +1,425 @@
+//! Persistent command approval rules.
+//!
+//! Rules live in `CODEX_HOME/approval_rules.toml`, grouped by project root (as
+//! resolved by [`resolve_root_git_project_for_trust`]). Each rule is a list of
+//! glob patterns matched token-by-token against the start of a command, so
+//! `["cargo", "test"]` matches `cargo test -p codex-core` and `["git", "*"]`
+//! matches any git subcommand.
+//!
+//! ```toml
+//! [projects."/Users/me/code/codex"]
+//! allow = [["cargo", "test"], ["just", "fmt"]]
+//! deny = [["git", "push"]]
+//! ```
+//!
+//! Allow and deny rules have equal precedence: the rule with the longest
+//! matching prefix wins, and deny wins a tie.
+
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+use tempfile::NamedTempFile;
+use wildmatch::WildMatchPattern;
+
+use crate::bash::parse_bash_lc_plain_commands;
+use crate::git_info::resolve_root_git_project_for_trust;
+
+pub const APPROVAL_RULES_FILE: &str = "approval_rules.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleKind {
+    Allow,
+    Deny,
+}
+
+impl RuleKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RuleKind::Allow => "allow",
+            RuleKind::Deny => "deny",
+        }
+    }
+}
+
+/// Allow and deny rules recorded for a single project.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectRules {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<Vec<String>>,
+}
+
+impl ProjectRules {
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    fn rules_mut(&mut self, kind: RuleKind) -> &mut Vec<Vec<String>> {
+        match kind {
+            RuleKind::Allow => &mut self.allow,
+            RuleKind::Deny => &mut self.deny,
+        }
+    }
+
+    /// Decides whether `command` is covered by a rule. `bash -lc` scripts made
+    /// of plain commands are checked command by command: any denied command
+    /// denies the script, and the script is allowed only when every command is.
+    /// A shell script that cannot be split into plain commands (pipes,
+    /// redirects, `$(...)`) may hide a denied command, so it is
+    /// [`RuleVerdict::Unverified`] whenever the project has deny rules.
+    pub fn evaluate(&self, command: &[String]) -> Option<RuleVerdict> {
+        if self.is_empty() {
+            return None;
+        }
+        let commands = match parse_bash_lc_plain_commands(command) {
+            Some(commands) => commands,
+            None if !self.deny.is_empty()
+                && command.first().is_some_and(|program| is_shell(program)) =>
+            {
+                return Some(RuleVerdict::Unverified);
+            }
+            None => vec![command.to_vec()],
+        };
+        let verdicts: Vec<Option<RuleKind>> = commands
+            .iter()
+            .map(|command| self.evaluate_plain(command))
+            .collect();
+        if verdicts.contains(&Some(RuleKind::Deny)) {
+            Some(RuleVerdict::Deny)
+        } else if !verdicts.is_empty() && verdicts.iter().all(|v| *v == Some(RuleKind::Allow)) {
+            Some(RuleVerdict::Allow)
+        } else {
+            None
+        }
+    }
+
+    fn evaluate_plain(&self, command: &[String]) -> Option<RuleKind> {
+        // Deny rules match the program by file name so `/usr/bin/git push`
+        // is caught by `["git", "push"]`; allow rules stay literal so a
+        // same-named binary elsewhere is not trusted.
+        let longest = |rules: &[Vec<String>], match_file_name: bool| {
+            rules
+                .iter()
+                .filter(|rule| rule_matches(rule, command, match_file_name))
+                .map(Vec::len)
+                .max()
+        };
+        match (longest(&self.allow, false), longest(&self.deny, true)) {
+            (Some(allow), Some(deny)) if allow > deny => Some(RuleKind::Allow),
+            (_, Some(_)) => Some(RuleKind::Deny),
+            (Some(_), None) => Some(RuleKind::Allow),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Outcome of checking a command against a project's rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleVerdict {
+    Allow,
+    Deny,
+    /// The command is a shell script that could not be checked against the
+    /// project's deny rules; the user has to review it.
+    Unverified,
+}
+
+fn rule_matches(rule: &[String], command: &[String], match_file_name: bool) -> bool {
+    !rule.is_empty()
+        && rule.len() <= command.len()
+        && rule
+            .iter()
+            .zip(command)
+            .enumerate()
+            .all(|(index, (pattern, arg))| {
+                let pattern = WildMatchPattern::<'*', '?'>::new(pattern);
+                pattern.matches(arg)
+                    || (index == 0 && match_file_name && pattern.matches(file_name(arg)))
+            })
+}
+
+/// Contents of `CODEX_HOME/approval_rules.toml`, keyed by project root.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalRules {
+    #[serde(default)]
+    pub projects: BTreeMap<String, ProjectRules>,
+}
+
+impl ApprovalRules {
+    /// Loads the rules file, returning an empty set when it does not exist.
+    pub fn load(codex_home: &Path) -> std::io::Result<Self> {
+        let contents = match std::fs::read_to_string(codex_home.join(APPROVAL_RULES_FILE)) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        toml::from_str(&contents).map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, codex_home: &Path) -> std::io::Result<()> {
+        let contents =
+            toml::to_string(self).map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?;
+        std::fs::create_dir_all(codex_home)?;
+        let tmp_file = NamedTempFile::new_in(codex_home)?;
+        std::fs::write(tmp_file.path(), contents)?;
+        tmp_file
+            .persist(codex_home.join(APPROVAL_RULES_FILE))
+            .map_err(|err| err.error)?;
+        Ok(())
+    }
+
+    pub fn project(&self, project: &Path) -> Option<&ProjectRules> {
+        self.projects.get(&project_key(project))
+    }
+
+    /// Adds a rule for `project`. Returns `false` if it was already present.
+    pub fn add(&mut self, project: &Path, kind: RuleKind, rule: Vec<String>) -> bool {
+        if rule.is_empty() {
+            return false;
+        }
+        let rules = self
+            .projects
+            .entry(project_key(project))
+            .or_default()
+            .rules_mut(kind);
+        if rules.contains(&rule) {
+            return false;
+        }
+        rules.push(rule);
+        true
+    }
+
+    /// Removes a rule for `project`. Returns `false` if no such rule existed.
+    pub fn remove(&mut self, project: &Path, kind: RuleKind, rule: &[String]) -> bool {
+        let key = project_key(project);
+        let Some(project_rules) = self.projects.get_mut(&key) else {
+            return false;
+        };
+        let rules = project_rules.rules_mut(kind);
+        let before = rules.len();
+        rules.retain(|existing| existing != rule);
+        let removed = rules.len() != before;
+        if project_rules.is_empty() {
+            self.projects.remove(&key);
+        }
+        removed
+    }
+}
+
+fn project_key(project: &Path) -> String {
+    project.to_string_lossy().into_owned()
+}
+
+/// Returns the directory whose rules apply to commands run in `cwd`: the root
+/// of the enclosing git project, or `cwd` itself outside a repository.
+pub fn project_root_for_rules(cwd: &Path) -> PathBuf {
+    resolve_root_git_project_for_trust(cwd).unwrap_or_else(|| cwd.to_path_buf())
+}
+
+/// Prefix offered when the user chooses to allow similar commands for the
+/// whole project: the program plus its subcommand (`cargo test`, `git status`).
+/// Returns `None` when there is no subcommand to scope the rule to, since
+/// allowing the bare program (`rm`, `python3`) would allow every use of it,
+/// and for shell scripts that cannot be parsed or run more than one command.
+pub fn suggested_prefix(command: &[String]) -> Option<Vec<String>> {
+    let commands = match parse_bash_lc_plain_commands(command) {
+        Some(commands) => commands,
+        None if command.first().is_some_and(|program| is_shell(program)) => return None,
+        None => vec![command.to_vec()],
+    };
+    let [single] = commands.as_slice() else {
+        return None;
+    };
+    let [program, subcommand, ..] = single.as_slice() else {
+        return None;
+    };
+    if is_shell(program) || !looks_like_subcommand(subcommand) {
+        return None;
+    }
+    Some(vec![program.clone(), subcommand.clone()])
+}
+
+fn is_shell(program: &str) -> bool {
+    matches!(file_name(program), "bash" | "sh" | "zsh" | "dash")
+}
+
+fn file_name(program: &str) -> &str {
+    Path::new(program)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(program)
+}
+
+fn looks_like_subcommand(arg: &str) -> bool {
+    arg.starts_with(|c: char| c.is_ascii_alphabetic())
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ':')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn vec_str(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn longest_rule_wins_and_deny_wins_ties() {
+        let rules = ProjectRules {
+            allow: vec![vec_str(&["git"]), vec_str(&["cargo", "*"])],
+            deny: vec![vec_str(&["git", "push"]), vec_str(&["cargo", "publish"])],
+        };
+
+        assert_eq!(
+            rules.evaluate(&vec_str(&["git", "status"])),
+            Some(RuleVerdict::Allow)
+        );
+        assert_eq!(
+            rules.evaluate(&vec_str(&["git", "push", "origin"])),
+            Some(RuleVerdict::Deny)
+        );
+        assert_eq!(
+            rules.evaluate(&vec_str(&["cargo", "publish"])),
+            Some(RuleVerdict::Deny)
+        );
+        assert_eq!(
+            rules.evaluate(&vec_str(&["cargo", "test"])),
+            Some(RuleVerdict::Allow)
+        );
+        assert_eq!(rules.evaluate(&vec_str(&["cargo"])), None);
+        assert_eq!(rules.evaluate(&vec_str(&["ls"])), None);
+    }
+
+    #[test]
+    fn bash_scripts_are_checked_per_command() {
+        let rules = ProjectRules {
+            allow: vec![vec_str(&["cargo", "test"]), vec_str(&["cargo", "fmt"])],
+            deny: vec![vec_str(&["rm"])],
+        };
+
+        assert_eq!(
+            rules.evaluate(&vec_str(&["bash", "-lc", "cargo fmt && cargo test"])),
+            Some(RuleVerdict::Allow)
+        );
+        assert_eq!(
+            rules.evaluate(&vec_str(&["bash", "-lc", "cargo test && ls"])),
+            None
+        );
+        assert_eq!(
+            rules.evaluate(&vec_str(&["bash", "-lc", "cargo test; rm -rf target"])),
+            Some(RuleVerdict::Deny)
+        );
+    }
+
+    #[test]
+    fn deny_rules_match_the_program_by_file_name() {
+        let rules = ProjectRules {
+            allow: vec![vec_str(&["git", "status"])],
+            deny: vec![vec_str(&["git", "push"])],
+        };
+
+        assert_eq!(
+            rules.evaluate(&vec_str(&["/usr/bin/git", "push", "origin"])),
+            Some(RuleVerdict::Deny)
+        );
+        assert_eq!(rules.evaluate(&vec_str(&["/tmp/git", "status"])), None);
+    }
+
+    #[test]
+    fn unparsed_scripts_are_unverified_when_deny_rules_exist() {
+        let rules = ProjectRules {
+            allow: vec![vec_str(&["bash"])],
+            deny: vec![vec_str(&["git", "push"])],
+        };
+
+        for script in ["git push 2>&1", "echo $(git push)", "git push > log"] {
+            assert_eq!(
+                rules.evaluate(&vec_str(&["bash", "-lc", script])),
+                Some(RuleVerdict::Unverified),
+                "{script}"
+            );
+        }
+
+        let allow_only = ProjectRules {
+            allow: vec![vec_str(&["cargo", "test"])],
+            deny: Vec::new(),
+        };
+        assert_eq!(
+            allow_only.evaluate(&vec_str(&["bash", "-lc", "cargo test 2>&1"])),
+            None
+        );
+    }
+
+    #[test]
+    fn suggested_prefix_keeps_subcommands() {
+        assert_eq!(
+            suggested_prefix(&vec_str(&["bash", "-lc", "cargo test -p codex-core"])),
+            Some(vec_str(&["cargo", "test"]))
+        );
+        assert_eq!(
+            suggested_prefix(&vec_str(&["git", "status"])),
+            Some(vec_str(&["git", "status"]))
+        );
+        assert_eq!(
+            suggested_prefix(&vec_str(&["bash", "-lc", "cd core && cargo test"])),
+            None
+        );
+    }
+
+    #[test]
+    fn suggested_prefix_requires_a_subcommand() {
+        assert_eq!(suggested_prefix(&vec_str(&["rm", "-rf", "build"])), None);
+        assert_eq!(suggested_prefix(&vec_str(&["python3", "script.py"])), None);
+        assert_eq!(suggested_prefix(&vec_str(&["ls"])), None);
+        assert_eq!(
+            suggested_prefix(&vec_str(&["bash", "-lc", "rm -rf build"])),
+            None
+        );
+        assert_eq!(
+            suggested_prefix(&vec_str(&["bash", "-lc", "echo $(whoami)"])),
+            None
+        );
+        assert_eq!(
+            suggested_prefix(&vec_str(&["bash", "-c", "cargo test"])),
+            None
+        );
+    }
+
+    #[test]
+    fn rules_round_trip_through_codex_home() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let project = Path::new("/work/project");
+
+        let mut rules = ApprovalRules::load(codex_home.path()).expect("load empty");
+        assert_eq!(rules, ApprovalRules::default());
+
+        assert!(rules.add(project, RuleKind::Allow, vec_str(&["cargo", "test"])));
+        assert!(!rules.add(project, RuleKind::Allow, vec_str(&["cargo", "test"])));
+        assert!(rules.add(project, RuleKind::Deny, vec_str(&["git", "push"])));
+        rules.save(codex_home.path()).expect("save");
+
+        let loaded = ApprovalRules::load(codex_home.path()).expect("load");
+        assert_eq!(loaded, rules);
+        assert_eq!(
+            loaded.project(project),
+            Some(&ProjectRules {
+                allow: vec![vec_str(&["cargo", "test"])],
+                deny: vec![vec_str(&["git", "push"])],
+            })
+        );
+
+        let mut rules = loaded;
+        assert!(rules.remove(project, RuleKind::Allow, &vec_str(&["cargo", "test"])));
+        assert!(rules.remove(project, RuleKind::Deny, &vec_str(&["git", "push"])));
+        assert!(!rules.remove(project, RuleKind::Deny, &vec_str(&["git", "push"])));
+        assert!(rules.projects.is_empty());
+    }
+}
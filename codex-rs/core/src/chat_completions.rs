@@ -276,7 +276,8 @@ pub(crate) async fn stream_chat_completions(
         }
     }
 
-    let tools_json = create_tools_json_for_chat_completions_api(&prompt.tools)?;
+    let tools_json =
+        create_tools_json_for_chat_completions_api(&prompt.tools, provider.downgrade_tool_schemas)?;
     let payload = json!({
         "model": model_family.slug,
         "messages": messages,
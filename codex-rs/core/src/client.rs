@@ -39,6 +39,7 @@ use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::UsageLimitReachedError;
 use crate::flags::CODEX_RS_SSE_FIXTURE;
+use crate::messages::stream_messages;
 use crate::model_family::ModelFamily;
 use crate::model_provider_info::ModelProviderInfo;
 use crate::model_provider_info::WireApi;
@@ -170,6 +171,18 @@ impl ModelClient {
 
                 Ok(ResponseStream { rx_event: rx })
             }
+            WireApi::Messages => {
+                stream_messages(
+                    prompt,
+                    &self.config.model_family,
+                    self.config.model_max_output_tokens,
+                    self.effort,
+                    &self.client,
+                    &self.provider,
+                    &self.otel_event_manager,
+                )
+                .await
+            }
         }
     }
 
@@ -435,6 +448,11 @@ impl ModelClient {
         }
     }
 
+    /// Returns the configuration this client was built with.
+    pub(crate) fn get_config(&self) -> Arc<Config> {
+        Arc::clone(&self.config)
+    }
+
     pub fn get_provider(&self) -> ModelProviderInfo {
         self.provider.clone()
     }
@@ -1085,6 +1103,7 @@ mod tests {
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
+            downgrade_tool_schemas: false,
         };
 
         let otel_event_manager = otel_event_manager();
@@ -1148,6 +1167,7 @@ mod tests {
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
+            downgrade_tool_schemas: false,
         };
 
         let otel_event_manager = otel_event_manager();
@@ -1184,6 +1204,7 @@ mod tests {
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
+            downgrade_tool_schemas: false,
         };
 
         let otel_event_manager = otel_event_manager();
@@ -1222,6 +1243,7 @@ mod tests {
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
+            downgrade_tool_schemas: false,
         };
 
         let otel_event_manager = otel_event_manager();
@@ -1256,6 +1278,7 @@ mod tests {
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
+            downgrade_tool_schemas: false,
         };
 
         let otel_event_manager = otel_event_manager();
@@ -1359,6 +1382,7 @@ mod tests {
                 stream_max_retries: Some(0),
                 stream_idle_timeout_ms: Some(1000),
                 requires_openai_auth: false,
+                downgrade_tool_schemas: false,
             };
 
             let otel_event_manager = otel_event_manager();
@@ -27,6 +27,7 @@ use futures::future::BoxFuture;
 use futures::prelude::*;
 use futures::stream::FuturesOrdered;
 use mcp_types::CallToolResult;
+use mcp_types::GetPromptRequestParams;
 use serde_json;
 use serde_json::Value;
 use tokio::sync::Mutex;
@@ -42,6 +43,7 @@ use crate::apply_patch::convert_apply_patch_to_protocol;
 use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
+use crate::command_safety::exec_policy::ExecPolicy;
 use crate::config::Config;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::conversation_history::ConversationHistory;
@@ -57,8 +59,13 @@ use crate::exec_command::WriteStdinParams;
 use crate::executor::Executor;
 use crate::executor::ExecutorConfig;
 use crate::executor::normalize_exec_result;
+use crate::hooks::HookContext;
+use crate::hooks::Hooks;
+use crate::hooks::MAX_STOP_HOOK_CONTINUATIONS;
+use crate::hooks::UserPromptOutcome;
 use crate::mcp::auth::compute_auth_statuses;
 use crate::mcp_connection_manager::McpConnectionManager;
+use crate::model_fallback;
 use crate::model_family::find_family_for_model;
 use crate::openai_model_info::get_model_info;
 use crate::openai_tools::ToolsConfig;
@@ -80,6 +87,7 @@ use crate::protocol::ExecCommandBeginEvent;
 use crate::protocol::ExecCommandEndEvent;
 use crate::protocol::InputItem;
 use crate::protocol::ListCustomPromptsResponseEvent;
+use crate::protocol::McpToolsUpdatedEvent;
 use crate::protocol::Op;
 use crate::protocol::PatchApplyBeginEvent;
 use crate::protocol::PatchApplyEndEvent;
@@ -121,6 +129,7 @@ use codex_protocol::models::FunctionCallOutputPayload;
 use codex_protocol::models::ResponseInputItem;
 use codex_protocol::models::ResponseItem;
 use codex_protocol::protocol::InitialHistory;
+use codex_protocol::protocol::ModelFallbackItem;
 
 pub mod compact;
 use self::compact::build_compacted_history;
@@ -264,6 +273,9 @@ pub(crate) struct TurnContext {
     pub(crate) tools_config: ToolsConfig,
     pub(crate) is_review_mode: bool,
     pub(crate) final_output_json_schema: Option<Value>,
+    /// Set once the task has failed over to a fallback model; its
+    /// `fallback_index` picks the next fallback if this one fails too.
+    pub(crate) model_fallback: Option<ModelFallbackItem>,
 }
 
 impl TurnContext {
@@ -459,20 +471,27 @@ impl Session {
             cwd,
             is_review_mode: false,
             final_output_json_schema: None,
+            model_fallback: None,
         };
         let services = SessionServices {
             mcp_connection_manager,
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
             notifier: notify,
+            hooks: Hooks::new(&config.hooks),
             rollout: Mutex::new(Some(rollout_recorder)),
             user_shell: default_shell,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
-            executor: Executor::new(ExecutorConfig::new(
-                turn_context.sandbox_policy.clone(),
-                turn_context.cwd.clone(),
-                config.codex_linux_sandbox_exe.clone(),
-            )),
+            executor: Executor::new(
+                ExecutorConfig::new(
+                    turn_context.sandbox_policy.clone(),
+                    turn_context.cwd.clone(),
+                    config.codex_linux_sandbox_exe.clone(),
+                ),
+                Some(config.codex_home.clone()),
+                ExecPolicy::load(&config.exec_policy_files)
+                    .with_project_files(&config.project_exec_policy_files),
+            ),
         };
 
         let sess = Arc::new(Session {
@@ -573,6 +592,7 @@ impl Session {
         command: Vec<String>,
         cwd: PathBuf,
         reason: Option<String>,
+        project_prefix: Option<Vec<String>>,
     ) -> ReviewDecision {
         // Add the tx_approve callback to the map before sending the request.
         let (tx_approve, rx_approve) = oneshot::channel();
@@ -598,6 +618,7 @@ impl Session {
                 command,
                 cwd,
                 reason,
+                project_prefix,
             }),
         };
         self.send_event(event).await;
@@ -1017,6 +1038,36 @@ impl Session {
         }
     }
 
+    /// Injects user input into the running task once the user-prompt-submit
+    /// hooks have accepted it; input a hook blocks is dropped. Returns the
+    /// input untouched when no task is running, so the task spawned for it
+    /// runs the hooks itself; if the task ends while the hooks run, the new
+    /// task runs them again on the hooked input.
+    async fn inject_user_input(
+        &self,
+        turn_context: &TurnContext,
+        sub_id: &str,
+        input: Vec<InputItem>,
+    ) -> Result<(), Vec<InputItem>> {
+        if self.active_turn.lock().await.is_none() {
+            return Err(input);
+        }
+        let hook_context = self.hook_context(turn_context);
+        match self.hooks().user_prompt_submit(&hook_context, input).await {
+            UserPromptOutcome::Proceed(input) => self.inject_input(input).await,
+            UserPromptOutcome::Block(reason) => {
+                let event = Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::Error(ErrorEvent {
+                        message: format!("Prompt blocked by user-prompt-submit hook: {reason}"),
+                    }),
+                };
+                self.send_event(event).await;
+                Ok(())
+            }
+        }
+    }
+
     pub async fn get_pending_input(&self) -> Vec<ResponseInputItem> {
         let mut active = self.active_turn.lock().await;
         match active.as_mut() {
@@ -1040,6 +1091,39 @@ impl Session {
             .await
     }
 
+    pub(crate) async fn approve_mcp_tool_for_session(&self, server: &str, tool: &str) {
+        let mut state = self.state.lock().await;
+        state.approve_mcp_tool_for_session(server, tool);
+    }
+
+    pub(crate) async fn is_mcp_tool_approved_for_session(&self, server: &str, tool: &str) -> bool {
+        let state = self.state.lock().await;
+        state.is_mcp_tool_approved_for_session(server, tool)
+    }
+
+    /// Re-lists tools from MCP servers that reported a tool list change and
+    /// tells clients about the new list.
+    async fn refresh_mcp_tools(&self, sub_id: &str) {
+        let servers = self
+            .services
+            .mcp_connection_manager
+            .refresh_changed_tools()
+            .await;
+        if servers.is_empty() {
+            return;
+        }
+        let tools = self.services.mcp_connection_manager.list_all_tools();
+        let event = Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::McpToolsUpdated(McpToolsUpdatedEvent { servers, tools }),
+        };
+        self.send_event(event).await;
+    }
+
+    pub(crate) fn mcp_connection_manager(&self) -> &McpConnectionManager {
+        &self.services.mcp_connection_manager
+    }
+
     pub(crate) fn parse_mcp_tool_name(&self, tool_name: &str) -> Option<(String, String)> {
         self.services
             .mcp_connection_manager
@@ -1105,6 +1189,17 @@ impl Session {
         &self.services.notifier
     }
 
+    pub(crate) fn hooks(&self) -> &Hooks {
+        &self.services.hooks
+    }
+
+    pub(crate) fn hook_context(&self, turn_context: &TurnContext) -> HookContext {
+        HookContext {
+            session_id: self.conversation_id.to_string(),
+            cwd: turn_context.cwd.clone(),
+        }
+    }
+
     pub(crate) fn user_shell(&self) -> &shell::Shell {
         &self.services.user_shell
     }
@@ -1207,6 +1302,7 @@ async fn submission_loop(
                     cwd: new_cwd.clone(),
                     is_review_mode: false,
                     final_output_json_schema: None,
+                    model_fallback: None,
                 };
 
                 // Install the new persistent context for subsequent tasks/turns.
@@ -1230,7 +1326,7 @@ async fn submission_loop(
                     .get_otel_event_manager()
                     .user_prompt(&items);
                 // attempt to inject input into current task
-                if let Err(items) = sess.inject_input(items).await {
+                if let Err(items) = sess.inject_user_input(&turn_context, &sub.id, items).await {
                     // no current task, spawn a new one
                     sess.spawn_task(Arc::clone(&turn_context), sub.id, items, RegularTask)
                         .await;
@@ -1251,7 +1347,7 @@ async fn submission_loop(
                     .get_otel_event_manager()
                     .user_prompt(&items);
                 // attempt to inject input into current task
-                if let Err(items) = sess.inject_input(items).await {
+                if let Err(items) = sess.inject_user_input(&turn_context, &sub.id, items).await {
                     // Derive a fresh TurnContext for this turn using the provided overrides.
                     let provider = turn_context.client.get_provider();
                     let auth_manager = turn_context.client.get_auth_manager();
@@ -1300,6 +1396,7 @@ async fn submission_loop(
                         cwd,
                         is_review_mode: false,
                         final_output_json_schema,
+                        model_fallback: None,
                     };
 
                     // if the environment context has changed, record it in the conversation history
@@ -1388,7 +1485,9 @@ async fn submission_loop(
             Op::ListMcpTools => {
                 let sub_id = sub.id.clone();
 
-                // This is a cheap lookup from the connection manager's cache.
+                // Pick up servers that changed their tools since the last
+                // turn; otherwise this is a cheap lookup from the cache.
+                sess.refresh_mcp_tools(&sub_id).await;
                 let tools = sess.services.mcp_connection_manager.list_all_tools();
                 let auth_statuses = compute_auth_statuses(
                     config.mcp_servers.iter(),
@@ -1409,21 +1508,69 @@ async fn submission_loop(
             Op::ListCustomPrompts => {
                 let sub_id = sub.id.clone();
 
-                let custom_prompts: Vec<CustomPrompt> =
-                    if let Some(dir) = crate::custom_prompts::default_prompts_dir() {
-                        crate::custom_prompts::discover_prompts_in(&dir).await
-                    } else {
-                        Vec::new()
-                    };
+                let user_prompts_dir = crate::custom_prompts::default_prompts_dir();
+                let custom_prompts: Vec<CustomPrompt> = crate::custom_prompts::discover_prompts(
+                    user_prompts_dir.as_deref(),
+                    &turn_context.cwd,
+                )
+                .await;
+
+                let mcp_prompts = crate::mcp_prompt::to_protocol_prompts(
+                    sess.services
+                        .mcp_connection_manager
+                        .list_all_prompts()
+                        .await,
+                );
 
                 let event = Event {
                     id: sub_id,
                     msg: EventMsg::ListCustomPromptsResponse(ListCustomPromptsResponseEvent {
                         custom_prompts,
+                        mcp_prompts,
                     }),
                 };
                 sess.send_event(event).await;
             }
+            Op::RunMcpPrompt {
+                server,
+                name,
+                arguments,
+            } => {
+                let params = GetPromptRequestParams {
+                    arguments: (!arguments.is_empty())
+                        .then(|| serde_json::to_value(&arguments).ok())
+                        .flatten(),
+                    name: name.clone(),
+                };
+                let result = sess
+                    .services
+                    .mcp_connection_manager
+                    .get_prompt(&server, params)
+                    .await;
+                let items = match result {
+                    Ok(result) => crate::mcp_prompt::prompt_result_to_input(result),
+                    Err(e) => {
+                        let message = format!("Failed to load MCP prompt `{server}/{name}`: {e:#}");
+                        let event = Event {
+                            id: sub.id.clone(),
+                            msg: EventMsg::Error(ErrorEvent { message }),
+                        };
+                        sess.send_event(event).await;
+                        continue;
+                    }
+                };
+                if items.is_empty() {
+                    continue;
+                }
+                turn_context
+                    .client
+                    .get_otel_event_manager()
+                    .user_prompt(&items);
+                if let Err(items) = sess.inject_user_input(&turn_context, &sub.id, items).await {
+                    sess.spawn_task(Arc::clone(&turn_context), sub.id, items, RegularTask)
+                        .await;
+                }
+            }
             Op::Compact => {
                 // Attempt to inject input into current task
                 if let Err(items) = sess
@@ -1579,6 +1726,7 @@ async fn spawn_review_thread(
         cwd: parent_turn_context.cwd.clone(),
         is_review_mode: true,
         final_output_json_schema: None,
+        model_fallback: None,
     };
 
     // Seed the child task with the review prompt as the initial user message.
@@ -1618,7 +1766,7 @@ async fn spawn_review_thread(
 /// user_instructions. Emits ExitedReviewMode upon final review message.
 pub(crate) async fn run_task(
     sess: Arc<Session>,
-    turn_context: Arc<TurnContext>,
+    mut turn_context: Arc<TurnContext>,
     sub_id: String,
     input: Vec<InputItem>,
     task_kind: TaskKind,
@@ -1634,6 +1782,21 @@ pub(crate) async fn run_task(
     };
     sess.send_event(event).await;
 
+    let hook_context = sess.hook_context(&turn_context);
+    let input = match sess.hooks().user_prompt_submit(&hook_context, input).await {
+        UserPromptOutcome::Proceed(input) => input,
+        UserPromptOutcome::Block(reason) => {
+            let event = Event {
+                id: sub_id.clone(),
+                msg: EventMsg::Error(ErrorEvent {
+                    message: format!("Prompt blocked by user-prompt-submit hook: {reason}"),
+                }),
+            };
+            sess.send_event(event).await;
+            return None;
+        }
+    };
+
     let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(input);
     // For review threads, keep an isolated in-memory history so the
     // model sees a fresh conversation without the parent session's history.
@@ -1654,6 +1817,7 @@ pub(crate) async fn run_task(
     // many turns, from the perspective of the user, it is a single turn.
     let turn_diff_tracker = Arc::new(tokio::sync::Mutex::new(TurnDiffTracker::new()));
     let mut auto_compact_recently_attempted = false;
+    let mut stop_hook_continuations: u32 = 0;
 
     loop {
         // Note that pending_input would be something like a message the user
@@ -1701,7 +1865,7 @@ pub(crate) async fn run_task(
             .collect();
         match run_turn(
             Arc::clone(&sess),
-            Arc::clone(&turn_context),
+            &mut turn_context,
             Arc::clone(&turn_diff_tracker),
             sub_id.clone(),
             turn_input,
@@ -1856,6 +2020,44 @@ pub(crate) async fn run_task(
                     last_agent_message = get_last_assistant_message_from_turn(
                         &items_to_record_in_conversation_history,
                     );
+                    let stop_reason = if stop_hook_continuations < MAX_STOP_HOOK_CONTINUATIONS {
+                        sess.hooks()
+                            .turn_stop(
+                                &hook_context,
+                                last_agent_message.as_deref(),
+                                stop_hook_continuations > 0,
+                            )
+                            .await
+                    } else {
+                        sess.notify_background_event(
+                            &sub_id,
+                            format!(
+                                "turn-stop hooks kept the task going {MAX_STOP_HOOK_CONTINUATIONS} times in a row; ending the turn"
+                            ),
+                        )
+                        .await;
+                        None
+                    };
+                    if let Some(reason) = stop_reason {
+                        // A hook asked the model to keep going; hand its
+                        // reason back as the next user message.
+                        stop_hook_continuations += 1;
+                        sess.notify_background_event(
+                            &sub_id,
+                            format!("turn-stop hook asked to continue: {reason}"),
+                        )
+                        .await;
+                        let message =
+                            ResponseItem::from(ResponseInputItem::from(vec![InputItem::Text {
+                                text: reason,
+                            }]));
+                        if is_review_mode {
+                            review_thread_history.push(message);
+                        } else {
+                            sess.record_conversation_items(&[message]).await;
+                        }
+                        continue;
+                    }
                     sess.notifier()
                         .notify(&UserNotification::AgentTurnComplete {
                             thread_id: sess.conversation_id.to_string(),
@@ -1928,37 +2130,21 @@ fn parse_review_output_event(text: &str) -> ReviewOutputEvent {
 
 async fn run_turn(
     sess: Arc<Session>,
-    turn_context: Arc<TurnContext>,
+    turn_context: &mut Arc<TurnContext>,
     turn_diff_tracker: SharedTurnDiffTracker,
     sub_id: String,
     input: Vec<ResponseItem>,
     task_kind: TaskKind,
 ) -> CodexResult<TurnRunResult> {
-    let mcp_tools = sess.services.mcp_connection_manager.list_all_tools();
-    let router = Arc::new(ToolRouter::from_config(
-        &turn_context.tools_config,
-        Some(mcp_tools),
-    ));
-
-    let model_supports_parallel = turn_context
-        .client
-        .get_model_family()
-        .supports_parallel_tool_calls;
-    let parallel_tool_calls = model_supports_parallel;
-    let prompt = Prompt {
-        input,
-        tools: router.specs(),
-        parallel_tool_calls,
-        base_instructions_override: turn_context.base_instructions.clone(),
-        output_schema: turn_context.final_output_json_schema.clone(),
-    };
+    sess.refresh_mcp_tools(&sub_id).await;
+    let (mut router, mut prompt) = build_turn_prompt(&sess, turn_context, input);
 
     let mut retries = 0;
     loop {
-        match try_run_turn(
+        let err = match try_run_turn(
             Arc::clone(&router),
             Arc::clone(&sess),
-            Arc::clone(&turn_context),
+            Arc::clone(turn_context),
             Arc::clone(&turn_diff_tracker),
             &sub_id,
             &prompt,
@@ -1971,17 +2157,17 @@ async fn run_turn(
             Err(CodexErr::EnvVar(var)) => return Err(CodexErr::EnvVar(var)),
             Err(e @ CodexErr::Fatal(_)) => return Err(e),
             Err(e @ CodexErr::ContextWindowExceeded) => {
-                sess.set_total_tokens_full(&sub_id, &turn_context).await;
-                return Err(e);
+                sess.set_total_tokens_full(&sub_id, turn_context).await;
+                e
             }
             Err(CodexErr::UsageLimitReached(e)) => {
                 let rate_limits = e.rate_limits.clone();
                 if let Some(rate_limits) = rate_limits {
                     sess.update_rate_limits(&sub_id, rate_limits).await;
                 }
-                return Err(CodexErr::UsageLimitReached(e));
+                CodexErr::UsageLimitReached(e)
             }
-            Err(CodexErr::UsageNotIncluded) => return Err(CodexErr::UsageNotIncluded),
+            Err(CodexErr::UsageNotIncluded) => CodexErr::UsageNotIncluded,
             Err(e) => {
                 // Use the configured provider-specific stream retry budget.
                 let max_retries = turn_context.client.get_provider().stream_max_retries();
@@ -2005,14 +2191,131 @@ async fn run_turn(
                     .await;
 
                     tokio::time::sleep(delay).await;
-                } else {
-                    return Err(e);
+                    continue;
                 }
+                e
             }
+        };
+
+        let Some(fallback_context) = fallback_turn_context(&sess, turn_context, &err) else {
+            return Err(err);
+        };
+        let from_model = turn_context.client.get_model();
+        let to_model = fallback_context.client.get_model();
+        warn!("turn failed on {from_model}: {err:#}; failing over to {to_model}");
+        sess.notify_background_event(
+            &sub_id,
+            format!("{from_model} failed ({err}). Switched to fallback model {to_model}."),
+        )
+        .await;
+
+        let mut input = std::mem::take(&mut prompt.input);
+        if fallback_context
+            .model_fallback
+            .as_ref()
+            .is_some_and(|fallback| fallback.model_provider != fallback.from_model_provider)
+        {
+            // Encrypted reasoning can only be verified by the provider that
+            // produced it.
+            input.retain(|item| !matches!(item, ResponseItem::Reasoning { .. }));
+            let mut history = sess.history_snapshot().await;
+            history.retain(|item| !matches!(item, ResponseItem::Reasoning { .. }));
+            sess.replace_history(history).await;
         }
+
+        *turn_context = Arc::new(fallback_context);
+        (router, prompt) = build_turn_prompt(&sess, turn_context, input);
+        retries = 0;
     }
 }
 
+/// Builds the tool router and prompt for a turn on `turn_context`'s model.
+fn build_turn_prompt(
+    sess: &Session,
+    turn_context: &TurnContext,
+    input: Vec<ResponseItem>,
+) -> (Arc<ToolRouter>, Prompt) {
+    let mcp_connection_manager = &sess.services.mcp_connection_manager;
+    let mcp_tools = mcp_connection_manager.list_all_tools();
+    let mut tools_config = turn_context.tools_config.clone();
+    tools_config.include_mcp_resource_tools = mcp_connection_manager.has_resources();
+    let router = Arc::new(ToolRouter::from_config(&tools_config, Some(mcp_tools)));
+
+    let model_supports_parallel = turn_context
+        .client
+        .get_model_family()
+        .supports_parallel_tool_calls;
+    let parallel_tool_calls = model_supports_parallel;
+    let prompt = Prompt {
+        input,
+        tools: router.specs(),
+        parallel_tool_calls,
+        base_instructions_override: turn_context.base_instructions.clone(),
+        output_schema: turn_context.final_output_json_schema.clone(),
+    };
+    (router, prompt)
+}
+
+/// Returns a turn context for the next of the profile's `model_fallbacks`
+/// when `err` belongs to an error class in `fallback_on`. The tools are
+/// rebuilt for the fallback's model family.
+fn fallback_turn_context(
+    sess: &Session,
+    turn_context: &TurnContext,
+    err: &CodexErr,
+) -> Option<TurnContext> {
+    // Review threads run a dedicated review model and tool set.
+    if turn_context.is_review_mode {
+        return None;
+    }
+    let config = turn_context.client.get_config();
+    let reason =
+        model_fallback::classify_error(err).filter(|class| config.fallback_on.contains(class))?;
+    let (fallback_index, fallback) =
+        model_fallback::next_fallback(&config, turn_context.model_fallback.as_ref())?;
+    let fallback_config = model_fallback::config_for_fallback(&config, fallback);
+
+    let mut tools_config = ToolsConfig::new(&ToolsConfigParams {
+        model_family: &fallback_config.model_family,
+        features: &fallback_config.features,
+    });
+    tools_config.include_subagent_tool &= turn_context.tools_config.include_subagent_tool;
+
+    let otel_event_manager = turn_context.client.get_otel_event_manager().with_model(
+        fallback_config.model.as_str(),
+        fallback_config.model_family.slug.as_str(),
+    );
+    let client = ModelClient::new(
+        Arc::new(fallback_config),
+        turn_context.client.get_auth_manager(),
+        otel_event_manager,
+        fallback.model_provider.clone(),
+        turn_context.client.get_reasoning_effort(),
+        turn_context.client.get_reasoning_summary(),
+        sess.conversation_id,
+    );
+
+    Some(TurnContext {
+        client,
+        cwd: turn_context.cwd.clone(),
+        base_instructions: turn_context.base_instructions.clone(),
+        user_instructions: turn_context.user_instructions.clone(),
+        approval_policy: turn_context.approval_policy,
+        sandbox_policy: turn_context.sandbox_policy.clone(),
+        shell_environment_policy: turn_context.shell_environment_policy.clone(),
+        tools_config,
+        is_review_mode: false,
+        final_output_json_schema: turn_context.final_output_json_schema.clone(),
+        model_fallback: Some(ModelFallbackItem {
+            model_provider: fallback.model_provider_id.clone(),
+            from_model: config.model.clone(),
+            from_model_provider: config.model_provider_id.clone(),
+            reason,
+            fallback_index,
+        }),
+    })
+}
+
 /// When the model is prompted, it returns a stream of events. Some of these
 /// events map to a `ResponseItem`. A `ResponseItem` may need to be
 /// "handled" such that it produces a `ResponseInputItem` that needs to be
@@ -2100,6 +2403,7 @@ async fn try_run_turn(
         model: turn_context.client.get_model(),
         effort: turn_context.client.get_reasoning_effort(),
         summary: turn_context.client.get_reasoning_summary(),
+        model_fallback: turn_context.model_fallback.clone(),
     });
     sess.persist_rollout_items(&[rollout_item]).await;
     let mut stream = turn_context
@@ -2758,20 +3062,26 @@ mod tests {
             tools_config,
             is_review_mode: false,
             final_output_json_schema: None,
+            model_fallback: None,
         };
         let services = SessionServices {
             mcp_connection_manager: McpConnectionManager::default(),
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
             notifier: UserNotifier::default(),
+            hooks: Hooks::default(),
             rollout: Mutex::new(None),
             user_shell: shell::Shell::Unknown,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
-            executor: Executor::new(ExecutorConfig::new(
-                turn_context.sandbox_policy.clone(),
-                turn_context.cwd.clone(),
+            executor: Executor::new(
+                ExecutorConfig::new(
+                    turn_context.sandbox_policy.clone(),
+                    turn_context.cwd.clone(),
+                    None,
+                ),
                 None,
-            )),
+                ExecPolicy::default(),
+            ),
         };
         let session = Session {
             conversation_id,
@@ -2826,20 +3136,22 @@ mod tests {
             tools_config,
             is_review_mode: false,
             final_output_json_schema: None,
+            model_fallback: None,
         });
         let services = SessionServices {
             mcp_connection_manager: McpConnectionManager::default(),
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
             notifier: UserNotifier::default(),
+            hooks: Hooks::default(),
             rollout: Mutex::new(None),
             user_shell: shell::Shell::Unknown,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
-            executor: Executor::new(ExecutorConfig::new(
-                config.sandbox_policy.clone(),
-                config.cwd.clone(),
+            executor: Executor::new(
+                ExecutorConfig::new(config.sandbox_policy.clone(), config.cwd.clone(), None),
                 None,
-            )),
+                ExecPolicy::default(),
+            ),
         };
         let session = Arc::new(Session {
             conversation_id,
@@ -86,6 +86,7 @@ async fn run_compact_task_inner(
         model: turn_context.client.get_model(),
         effort: turn_context.client.get_reasoning_effort(),
         summary: turn_context.client.get_reasoning_summary(),
+        model_fallback: turn_context.model_fallback.clone(),
     });
     sess.persist_rollout_items(&[rollout_item]).await;
 
@@ -0,0 +1,303 @@
+//! Command classification backed by `codex-execpolicy`.
+//!
+//! The built-in `default.policy` is extended with user policy files from
+//! `CODEX_HOME/policy/*.policy`, which can describe more programs with
+//! `define_program()` or forbid invocations with `forbid_program_regex()` and
+//! `forbid_substrings()`. Project policy files from
+//! `<project root>/.codex/policy/*.policy` come with the repository, so only
+//! the invocations they forbid are honoured; the programs they describe are
+//! never treated as verified.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_execpolicy::ArgType;
+use codex_execpolicy::ExecCall;
+use codex_execpolicy::MatchedExec;
+use codex_execpolicy::Policy;
+use codex_execpolicy::PolicyParser;
+use codex_execpolicy::get_default_policy_with;
+use tracing::warn;
+
+use crate::bash::parse_bash_lc_plain_commands;
+
+/// Directory, under `CODEX_HOME` and under a project's `.codex/`, that holds
+/// extra `*.policy` files.
+pub const EXEC_POLICY_DIR: &str = "policy";
+
+const POLICY_FILE_EXTENSION: &str = "policy";
+
+/// Outcome of checking a command against the exec policy.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ExecPolicyCheck {
+    /// Every command matched the policy. `writes` lists the (possibly relative)
+    /// files the commands may write; it is empty for read-only commands.
+    Matched { writes: Vec<PathBuf> },
+    /// A command is forbidden by the policy.
+    Forbidden { reason: String },
+    /// At least one command is not described by the policy, or it may write
+    /// files the policy cannot identify.
+    Unverified,
+}
+
+pub struct ExecPolicy {
+    policy: Option<Policy>,
+    project_policy: Option<Policy>,
+}
+
+impl std::fmt::Debug for ExecPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExecPolicy")
+            .field("loaded", &self.policy.is_some())
+            .field("project_loaded", &self.project_policy.is_some())
+            .finish()
+    }
+}
+
+impl Default for ExecPolicy {
+    fn default() -> Self {
+        Self::load(&[])
+    }
+}
+
+impl ExecPolicy {
+    /// Builds the default policy extended with `policy_files`, in order. Files
+    /// that cannot be read or parsed are skipped.
+    pub fn load(policy_files: &[PathBuf]) -> Self {
+        let policy = match get_default_policy_with(read_policy_files(policy_files)) {
+            Ok(policy) => Some(policy),
+            Err(err) => {
+                warn!("failed to load exec policy: {err}");
+                None
+            }
+        };
+        Self {
+            policy,
+            project_policy: None,
+        }
+    }
+
+    /// Adds project policy files. Only the invocations they forbid are
+    /// applied: a repository must not be able to mark its own commands as
+    /// safe to run without asking.
+    pub fn with_project_files(mut self, project_files: &[PathBuf]) -> Self {
+        let parsers = read_policy_files(project_files);
+        if parsers.is_empty() {
+            return self;
+        }
+        self.project_policy = match PolicyParser::parse_all(&parsers) {
+            Ok(policy) => Some(policy),
+            Err(err) => {
+                warn!("failed to load project exec policy: {err}");
+                None
+            }
+        };
+        self
+    }
+
+    /// Checks `command`, run from `cwd`. `bash -lc` scripts made of plain
+    /// commands are checked command by command: a forbidden command forbids the
+    /// script, and the script matches only when every command does.
+    pub(crate) fn check(&self, command: &[String], cwd: &Path) -> ExecPolicyCheck {
+        let Some(policy) = &self.policy else {
+            return ExecPolicyCheck::Unverified;
+        };
+        let commands =
+            parse_bash_lc_plain_commands(command).unwrap_or_else(|| vec![command.to_vec()]);
+
+        let mut verified = !commands.is_empty();
+        let mut writes = Vec::new();
+        for command in &commands {
+            let Some((program, args)) = command.split_first() else {
+                verified = false;
+                continue;
+            };
+            let exec_call = ExecCall {
+                program: program.clone(),
+                args: args.to_vec(),
+            };
+            if let Some(project_policy) = &self.project_policy
+                && let Ok(MatchedExec::Forbidden { reason, .. }) = project_policy.check(&exec_call)
+            {
+                return ExecPolicyCheck::Forbidden { reason };
+            }
+            match policy.check(&exec_call) {
+                Ok(MatchedExec::Forbidden { reason, .. }) => {
+                    return ExecPolicyCheck::Forbidden { reason };
+                }
+                Ok(MatchedExec::Match { exec }) => {
+                    for (arg_type, value) in exec
+                        .args
+                        .iter()
+                        .map(|arg| (&arg.r#type, &arg.value))
+                        .chain(exec.opts.iter().map(|opt| (&opt.r#type, &opt.value)))
+                    {
+                        match arg_type {
+                            ArgType::WriteableFile => writes.push(cwd.join(value)),
+                            ArgType::Unknown => verified = false,
+                            _ => {}
+                        }
+                    }
+                }
+                Err(_) => verified = false,
+            }
+        }
+
+        if verified {
+            ExecPolicyCheck::Matched { writes }
+        } else {
+            ExecPolicyCheck::Unverified
+        }
+    }
+}
+
+/// Returns the user policy files in `CODEX_HOME/policy`, sorted by name.
+pub fn find_exec_policy_files(codex_home: &Path) -> Vec<PathBuf> {
+    policy_files_in(&codex_home.join(EXEC_POLICY_DIR))
+}
+
+/// Returns the project policy files in `<project root>/.codex/policy`, sorted
+/// by name.
+pub fn find_project_exec_policy_files(project_root: &Path) -> Vec<PathBuf> {
+    policy_files_in(&project_root.join(".codex").join(EXEC_POLICY_DIR))
+}
+
+/// Reads and parses `policy_files`. Files that cannot be read or parsed are
+/// skipped with a warning so that a typo in one file does not disable the
+/// rest.
+fn read_policy_files(policy_files: &[PathBuf]) -> Vec<PolicyParser> {
+    let mut parsers = Vec::new();
+    for path in policy_files {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) => {
+                warn!("failed to read exec policy {}: {err}", path.display());
+                continue;
+            }
+        };
+        let parser = PolicyParser::new(&path.to_string_lossy(), &source);
+        if let Err(err) = parser.parse() {
+            warn!("ignoring invalid exec policy {}: {err}", path.display());
+            continue;
+        }
+        parsers.push(parser);
+    }
+    parsers
+}
+
+fn policy_files_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext == POLICY_FILE_EXTENSION)
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::expect_used)]
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn vec_str(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn default_policy_classifies_reads_and_writes() {
+        let policy = ExecPolicy::default();
+        let cwd = Path::new("/work");
+
+        assert_eq!(
+            policy.check(&vec_str(&["ls", "-l"]), cwd),
+            ExecPolicyCheck::Matched { writes: Vec::new() }
+        );
+        assert_eq!(
+            policy.check(&vec_str(&["cp", "a.txt", "b.txt"]), cwd),
+            ExecPolicyCheck::Matched {
+                writes: vec![PathBuf::from("/work/b.txt")]
+            }
+        );
+        assert_eq!(
+            policy.check(&vec_str(&["bash", "-lc", "ls && curl example.com"]), cwd),
+            ExecPolicyCheck::Unverified
+        );
+    }
+
+    #[test]
+    fn extra_files_forbid_and_invalid_files_are_skipped() {
+        let codex_home = tempfile::tempdir().expect("codex home");
+        let policy_dir = codex_home.path().join(EXEC_POLICY_DIR);
+        std::fs::create_dir_all(&policy_dir).expect("policy dir");
+        std::fs::write(
+            policy_dir.join("forbid.policy"),
+            r#"forbid_program_regex(regex="^ls$", reason="use the file list tool")"#,
+        )
+        .expect("write policy");
+        std::fs::write(policy_dir.join("broken.policy"), "define_program(").expect("write");
+        std::fs::write(policy_dir.join("notes.txt"), "ignored").expect("write");
+
+        let files = find_exec_policy_files(codex_home.path());
+        assert_eq!(
+            files,
+            vec![
+                policy_dir.join("broken.policy"),
+                policy_dir.join("forbid.policy")
+            ]
+        );
+
+        let policy = ExecPolicy::load(&files);
+        assert_eq!(
+            policy.check(&vec_str(&["bash", "-lc", "pwd; ls"]), Path::new("/work")),
+            ExecPolicyCheck::Forbidden {
+                reason: "use the file list tool".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn project_files_can_only_forbid() {
+        let project = tempfile::tempdir().expect("project");
+        let policy_dir = project.path().join(".codex").join(EXEC_POLICY_DIR);
+        std::fs::create_dir_all(&policy_dir).expect("policy dir");
+        std::fs::write(
+            policy_dir.join("project.policy"),
+            r#"
+define_program(program="deploy", args=[])
+forbid_program_regex(regex="^curl$", reason="no network from this repo")
+"#,
+        )
+        .expect("write policy");
+
+        let files = find_project_exec_policy_files(project.path());
+        assert_eq!(files, vec![policy_dir.join("project.policy")]);
+
+        let policy = ExecPolicy::default().with_project_files(&files);
+        let cwd = Path::new("/work");
+        assert_eq!(
+            policy.check(&vec_str(&["deploy"]), cwd),
+            ExecPolicyCheck::Unverified
+        );
+        assert_eq!(
+            policy.check(&vec_str(&["curl", "example.com"]), cwd),
+            ExecPolicyCheck::Forbidden {
+                reason: "no network from this repo".to_string()
+            }
+        );
+        assert_eq!(
+            policy.check(&vec_str(&["ls"]), cwd),
+            ExecPolicyCheck::Matched { writes: Vec::new() }
+        );
+    }
+}
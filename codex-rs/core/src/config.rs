@@ -1,3 +1,6 @@
+use crate::approval_rules::project_root_for_rules;
+use crate::command_safety::exec_policy::find_exec_policy_files;
+use crate::command_safety::exec_policy::find_project_exec_policy_files;
 use crate::config_loader::LoadedConfigLayers;
 pub use crate::config_loader::load_config_as_toml;
 use crate::config_loader::load_config_layers_with_overrides;
@@ -5,8 +8,11 @@ use crate::config_loader::merge_toml_values;
 use crate::config_profile::ConfigProfile;
 use crate::config_types::DEFAULT_OTEL_ENVIRONMENT;
 use crate::config_types::History;
+use crate::config_types::HooksConfig;
 use crate::config_types::McpServerConfig;
 use crate::config_types::McpServerTransportConfig;
+use crate::config_types::ModelFallback;
+use crate::config_types::ModelFallbackToml;
 use crate::config_types::Notifications;
 use crate::config_types::OtelConfig;
 use crate::config_types::OtelConfigToml;
@@ -33,6 +39,7 @@ use crate::protocol::SandboxPolicy;
 use anyhow::Context;
 use codex_app_server_protocol::Tools;
 use codex_app_server_protocol::UserSavedConfig;
+use codex_protocol::config_types::FallbackErrorClass;
 use codex_protocol::config_types::ReasoningEffort;
 use codex_protocol::config_types::ReasoningSummary;
 use codex_protocol::config_types::SandboxMode;
@@ -93,6 +100,13 @@ pub struct Config {
     /// Info needed to make an API request to the model.
     pub model_provider: ModelProviderInfo,
 
+    /// Models to fail over to, in order, when a turn fails with an error in
+    /// `fallback_on`.
+    pub model_fallbacks: Vec<ModelFallback>,
+
+    /// Error classes that trigger a fallback.
+    pub fallback_on: Vec<FallbackErrorClass>,
+
     /// Approval policy for executing commands.
     pub approval_policy: AskForApproval,
 
@@ -137,6 +151,17 @@ pub struct Config {
     /// If unset the feature is disabled.
     pub notify: Option<Vec<String>>,
 
+    /// Lifecycle hooks run around tool calls, prompts and turn ends.
+    pub hooks: HooksConfig,
+
+    /// Extra exec policy files layered on top of the built-in policy:
+    /// `CODEX_HOME/policy/*.policy`.
+    pub exec_policy_files: Vec<PathBuf>,
+
+    /// Project exec policy files, `<project root>/.codex/policy/*.policy`.
+    /// Only the invocations they forbid are applied.
+    pub project_exec_policy_files: Vec<PathBuf>,
+
     /// TUI notifications preference. When set, the TUI will send OSC 9 notifications on approvals
     /// and turn completions when not focused.
     pub tui_notifications: Notifications,
@@ -349,6 +374,14 @@ fn ensure_no_inline_bearer_tokens(value: &TomlValue) -> std::io::Result<()> {
     Ok(())
 }
 
+fn toml_string_array(values: &[String]) -> TomlArray {
+    let mut array = TomlArray::new();
+    for value in values {
+        array.push(value.clone());
+    }
+    array
+}
+
 pub fn write_global_mcp_servers(
     codex_home: &Path,
     servers: &BTreeMap<String, McpServerConfig>,
@@ -420,6 +453,28 @@ pub fn write_global_mcp_servers(
                 entry["tool_timeout_sec"] = toml_edit::value(timeout.as_secs_f64());
             }
 
+            if let Some(patterns) = &config.enabled_tools {
+                entry["enabled_tools"] = TomlItem::Value(toml_string_array(patterns).into());
+            }
+
+            if let Some(patterns) = &config.disabled_tools {
+                entry["disabled_tools"] = TomlItem::Value(toml_string_array(patterns).into());
+            }
+
+            if let Some(mode) = config.approval_mode {
+                entry["approval_mode"] = toml_edit::value(mode.as_str());
+            }
+
+            if !config.tool_approval_modes.is_empty() {
+                let mut modes = toml_edit::InlineTable::new();
+                let mut pairs: Vec<_> = config.tool_approval_modes.iter().collect();
+                pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+                for (tool, mode) in pairs {
+                    modes.insert(tool.as_str(), mode.as_str().into());
+                }
+                entry["tool_approval_modes"] = TomlItem::Value(modes.into());
+            }
+
             doc["mcp_servers"][name.as_str()] = TomlItem::Table(entry);
         }
     }
@@ -711,6 +766,14 @@ pub struct ConfigToml {
     /// Provider to use from the model_providers map.
     pub model_provider: Option<String>,
 
+    /// Models to fail over to, in order, when a turn fails with an error in
+    /// `fallback_on`.
+    pub model_fallbacks: Option<Vec<ModelFallbackToml>>,
+
+    /// Error classes that trigger a fallback. Defaults to `server_error` and
+    /// `rate_limit`.
+    pub fallback_on: Option<Vec<FallbackErrorClass>>,
+
     /// Size of the context window for the model, in tokens.
     pub model_context_window: Option<u64>,
 
@@ -736,6 +799,10 @@ pub struct ConfigToml {
     #[serde(default)]
     pub notify: Option<Vec<String>>,
 
+    /// Lifecycle hooks; see [`HooksConfig`].
+    #[serde(default)]
+    pub hooks: Option<HooksConfig>,
+
     /// System instructions.
     pub instructions: Option<String>,
 
@@ -997,6 +1064,31 @@ impl Config {
             tools_web_search_request: override_tools_web_search_request,
         } = overrides;
 
+        let resolved_cwd = {
+            use std::env;
+
+            match cwd {
+                None => {
+                    tracing::info!("cwd not set, using current dir");
+                    env::current_dir()?
+                }
+                Some(p) if p.is_absolute() => p,
+                Some(p) => {
+                    // Resolve relative path against the current working directory.
+                    tracing::info!("cwd is relative, resolving against current dir");
+                    let mut current = env::current_dir()?;
+                    current.push(p);
+                    current
+                }
+            }
+        };
+
+        // Project policy files can only forbid commands, so they are read
+        // whether or not the project is trusted.
+        let exec_policy_files = find_exec_policy_files(&codex_home);
+        let project_exec_policy_files =
+            find_project_exec_policy_files(&project_root_for_rules(&resolved_cwd));
+
         let active_profile_name = config_profile_key
             .as_ref()
             .or(cfg.profile.as_ref())
@@ -1046,26 +1138,42 @@ impl Config {
             })?
             .clone();
 
-        let shell_environment_policy = cfg.shell_environment_policy.into();
-
-        let resolved_cwd = {
-            use std::env;
+        let model_fallbacks = config_profile
+            .model_fallbacks
+            .or(cfg.model_fallbacks)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|fallback| {
+                let model_provider_id = fallback
+                    .model_provider
+                    .unwrap_or_else(|| model_provider_id.clone());
+                let model_provider = model_providers
+                    .get(&model_provider_id)
+                    .ok_or_else(|| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::NotFound,
+                            format!("Fallback model provider `{model_provider_id}` not found"),
+                        )
+                    })?
+                    .clone();
+                Ok(ModelFallback {
+                    model: fallback.model,
+                    model_provider_id,
+                    model_provider,
+                })
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let fallback_on = config_profile
+            .fallback_on
+            .or(cfg.fallback_on)
+            .unwrap_or_else(|| {
+                vec![
+                    FallbackErrorClass::ServerError,
+                    FallbackErrorClass::RateLimit,
+                ]
+            });
 
-            match cwd {
-                None => {
-                    tracing::info!("cwd not set, using current dir");
-                    env::current_dir()?
-                }
-                Some(p) if p.is_absolute() => p,
-                Some(p) => {
-                    // Resolve relative path against the current working directory.
-                    tracing::info!("cwd is relative, resolving against current dir");
-                    let mut current = env::current_dir()?;
-                    current.push(p);
-                    current
-                }
-            }
-        };
+        let shell_environment_policy = cfg.shell_environment_policy.into();
 
         let history = cfg.history.unwrap_or_default();
 
@@ -1132,6 +1240,8 @@ impl Config {
             model_auto_compact_token_limit,
             model_provider_id,
             model_provider,
+            model_fallbacks,
+            fallback_on,
             cwd: resolved_cwd,
             approval_policy: approval_policy
                 .or(config_profile.approval_policy)
@@ -1140,6 +1250,9 @@ impl Config {
             sandbox_policy,
             shell_environment_policy,
             notify: cfg.notify,
+            hooks: cfg.hooks.unwrap_or_default(),
+            exec_policy_files,
+            project_exec_policy_files,
             user_instructions,
             base_instructions,
             mcp_servers: cfg.mcp_servers,
@@ -1322,6 +1435,7 @@ pub fn log_dir(cfg: &Config) -> std::io::Result<PathBuf> {
 #[cfg(test)]
 mod tests {
     use crate::config_types::HistoryPersistence;
+    use crate::config_types::McpToolApprovalMode;
     use crate::config_types::Notifications;
     use crate::features::Feature;
 
@@ -1483,6 +1597,73 @@ exclude_slash_tmp = true
         Ok(())
     }
 
+    #[test]
+    fn profile_model_fallbacks_resolve_providers() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let cfg: ConfigToml = toml::from_str(
+            r#"
+profile = "work"
+
+[model_providers.anthropic]
+name = "Anthropic"
+base_url = "https://api.anthropic.com/v1"
+env_key = "ANTHROPIC_API_KEY"
+wire_api = "messages"
+
+[profiles.work]
+model = "gpt-5-codex"
+fallback_on = ["server_error", "usage_limit"]
+model_fallbacks = [
+  { model = "gpt-5" },
+  { model = "claude-sonnet-4-5", model_provider = "anthropic" },
+]
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+
+        assert_eq!(
+            config
+                .model_fallbacks
+                .iter()
+                .map(|fallback| (fallback.model.as_str(), fallback.model_provider_id.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("gpt-5", "openai"), ("claude-sonnet-4-5", "anthropic")]
+        );
+        assert_eq!(
+            config.model_fallbacks[1].model_provider.wire_api,
+            crate::WireApi::Messages
+        );
+        assert_eq!(
+            config.fallback_on,
+            vec![
+                FallbackErrorClass::ServerError,
+                FallbackErrorClass::UsageLimit
+            ]
+        );
+
+        let cfg: ConfigToml = toml::from_str(
+            r#"
+model_fallbacks = [{ model = "gpt-5", model_provider = "missing" }]
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+        let err = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .expect_err("unknown fallback provider should fail");
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+
+        Ok(())
+    }
+
     #[test]
     fn feature_table_overrides_legacy_flags() -> std::io::Result<()> {
         let codex_home = TempDir::new()?;
@@ -1624,6 +1805,10 @@ exclude_slash_tmp = true
                 enabled: true,
                 startup_timeout_sec: Some(Duration::from_secs(3)),
                 tool_timeout_sec: Some(Duration::from_secs(5)),
+                enabled_tools: None,
+                disabled_tools: None,
+                approval_mode: None,
+                tool_approval_modes: HashMap::new(),
             },
         );
 
@@ -1750,6 +1935,10 @@ bearer_token = "secret"
                 enabled: true,
                 startup_timeout_sec: None,
                 tool_timeout_sec: None,
+                enabled_tools: None,
+                disabled_tools: None,
+                approval_mode: None,
+                tool_approval_modes: HashMap::new(),
             },
         )]);
 
@@ -1801,6 +1990,10 @@ ZIG_VAR = "3"
                 enabled: true,
                 startup_timeout_sec: Some(Duration::from_secs(2)),
                 tool_timeout_sec: None,
+                enabled_tools: None,
+                disabled_tools: None,
+                approval_mode: None,
+                tool_approval_modes: HashMap::new(),
             },
         )]);
 
@@ -1841,6 +2034,10 @@ startup_timeout_sec = 2.0
                 enabled: true,
                 startup_timeout_sec: None,
                 tool_timeout_sec: None,
+                enabled_tools: None,
+                disabled_tools: None,
+                approval_mode: None,
+                tool_approval_modes: HashMap::new(),
             },
         );
         write_global_mcp_servers(codex_home.path(), &servers)?;
@@ -1884,6 +2081,10 @@ url = "https://example.com/mcp"
                 enabled: false,
                 startup_timeout_sec: None,
                 tool_timeout_sec: None,
+                enabled_tools: None,
+                disabled_tools: None,
+                approval_mode: None,
+                tool_approval_modes: HashMap::new(),
             },
         )]);
 
@@ -1903,6 +2104,52 @@ url = "https://example.com/mcp"
         Ok(())
     }
 
+    #[tokio::test]
+    async fn write_global_mcp_servers_serializes_tool_policy() -> anyhow::Result<()> {
+        let codex_home = TempDir::new()?;
+
+        let servers = BTreeMap::from([(
+            "docs".to_string(),
+            McpServerConfig {
+                transport: McpServerTransportConfig::Stdio {
+                    command: "docs-server".to_string(),
+                    args: Vec::new(),
+                    env: None,
+                },
+                enabled: true,
+                startup_timeout_sec: None,
+                tool_timeout_sec: None,
+                enabled_tools: Some(vec!["search_*".to_string()]),
+                disabled_tools: Some(vec!["search_admin".to_string()]),
+                approval_mode: Some(McpToolApprovalMode::Always),
+                tool_approval_modes: HashMap::from([
+                    ("search_web".to_string(), McpToolApprovalMode::Never),
+                    ("search_docs".to_string(), McpToolApprovalMode::OnRequest),
+                ]),
+            },
+        )]);
+
+        write_global_mcp_servers(codex_home.path(), &servers)?;
+
+        let config_path = codex_home.path().join(CONFIG_TOML_FILE);
+        let serialized = std::fs::read_to_string(&config_path)?;
+        assert_eq!(
+            serialized,
+            r#"[mcp_servers.docs]
+command = "docs-server"
+enabled_tools = ["search_*"]
+disabled_tools = ["search_admin"]
+approval_mode = "always"
+tool_approval_modes = { search_docs = "on-request", search_web = "never" }
+"#
+        );
+
+        let loaded = load_global_mcp_servers(codex_home.path()).await?;
+        assert_eq!(loaded.get("docs"), servers.get("docs"));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn persist_model_selection_updates_defaults() -> anyhow::Result<()> {
         let codex_home = TempDir::new()?;
@@ -2132,6 +2379,7 @@ model_verbosity = "high"
             stream_max_retries: Some(10),
             stream_idle_timeout_ms: Some(300_000),
             requires_openai_auth: false,
+            downgrade_tool_schemas: false,
         };
         let model_provider_map = {
             let mut model_provider_map = built_in_model_providers();
@@ -2193,11 +2441,19 @@ model_verbosity = "high"
                 model_auto_compact_token_limit: None,
                 model_provider_id: "openai".to_string(),
                 model_provider: fixture.openai_provider.clone(),
+                model_fallbacks: Vec::new(),
+                fallback_on: vec![
+                    FallbackErrorClass::ServerError,
+                    FallbackErrorClass::RateLimit,
+                ],
                 approval_policy: AskForApproval::Never,
                 sandbox_policy: SandboxPolicy::new_read_only_policy(),
                 shell_environment_policy: ShellEnvironmentPolicy::default(),
                 user_instructions: None,
                 notify: None,
+                hooks: HooksConfig::default(),
+                exec_policy_files: Vec::new(),
+                project_exec_policy_files: Vec::new(),
                 cwd: fixture.cwd(),
                 mcp_servers: HashMap::new(),
                 mcp_oauth_credentials_store_mode: Default::default(),
@@ -2257,11 +2513,19 @@ model_verbosity = "high"
             model_auto_compact_token_limit: None,
             model_provider_id: "openai-chat-completions".to_string(),
             model_provider: fixture.openai_chat_completions_provider.clone(),
+            model_fallbacks: Vec::new(),
+            fallback_on: vec![
+                FallbackErrorClass::ServerError,
+                FallbackErrorClass::RateLimit,
+            ],
             approval_policy: AskForApproval::UnlessTrusted,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
             user_instructions: None,
             notify: None,
+            hooks: HooksConfig::default(),
+            exec_policy_files: Vec::new(),
+            project_exec_policy_files: Vec::new(),
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             mcp_oauth_credentials_store_mode: Default::default(),
@@ -2336,11 +2600,19 @@ model_verbosity = "high"
             model_auto_compact_token_limit: None,
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
+            model_fallbacks: Vec::new(),
+            fallback_on: vec![
+                FallbackErrorClass::ServerError,
+                FallbackErrorClass::RateLimit,
+            ],
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
             user_instructions: None,
             notify: None,
+            hooks: HooksConfig::default(),
+            exec_policy_files: Vec::new(),
+            project_exec_policy_files: Vec::new(),
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             mcp_oauth_credentials_store_mode: Default::default(),
@@ -2401,11 +2673,19 @@ model_verbosity = "high"
             model_auto_compact_token_limit: None,
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
+            model_fallbacks: Vec::new(),
+            fallback_on: vec![
+                FallbackErrorClass::ServerError,
+                FallbackErrorClass::RateLimit,
+            ],
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
             user_instructions: None,
             notify: None,
+            hooks: HooksConfig::default(),
+            exec_policy_files: Vec::new(),
+            project_exec_policy_files: Vec::new(),
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             mcp_oauth_credentials_store_mode: Default::default(),
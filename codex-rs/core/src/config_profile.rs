@@ -1,7 +1,9 @@
 use serde::Deserialize;
 use std::path::PathBuf;
 
+use crate::config_types::ModelFallbackToml;
 use crate::protocol::AskForApproval;
+use codex_protocol::config_types::FallbackErrorClass;
 use codex_protocol::config_types::ReasoningEffort;
 use codex_protocol::config_types::ReasoningSummary;
 use codex_protocol::config_types::Verbosity;
@@ -14,6 +16,10 @@ pub struct ConfigProfile {
     /// The key in the `model_providers` map identifying the
     /// [`ModelProviderInfo`] to use.
     pub model_provider: Option<String>,
+    /// Models to fail over to, in order, when a turn fails with an error in
+    /// `fallback_on`.
+    pub model_fallbacks: Option<Vec<ModelFallbackToml>>,
+    pub fallback_on: Option<Vec<FallbackErrorClass>>,
     pub approval_policy: Option<AskForApproval>,
     pub model_reasoning_effort: Option<ReasoningEffort>,
     pub model_reasoning_summary: Option<ReasoningSummary>,
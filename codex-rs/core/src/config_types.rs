@@ -3,6 +3,7 @@
 // Note this file should generally be restricted to simple struct/enum
 // definitions that do not contain business logic.
 
+use crate::model_provider_info::ModelProviderInfo;
 use serde::Deserializer;
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -35,6 +36,25 @@ pub struct McpServerConfig {
     /// Default timeout for MCP tool calls initiated via this server.
     #[serde(default, with = "option_duration_secs")]
     pub tool_timeout_sec: Option<Duration>,
+
+    /// Glob patterns (`*` and `?`) naming the tools to expose from this
+    /// server. When unset, every tool the server advertises is exposed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled_tools: Option<Vec<String>>,
+
+    /// Glob patterns naming tools to hide from the model. Applied after
+    /// `enabled_tools`, so a tool matching both is hidden.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disabled_tools: Option<Vec<String>>,
+
+    /// Approval mode for every tool on this server that is not listed in
+    /// `tool_approval_modes`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_mode: Option<McpToolApprovalMode>,
+
+    /// Per-tool approval overrides keyed by the tool name the server reports.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tool_approval_modes: HashMap<String, McpToolApprovalMode>,
 }
 
 impl<'de> Deserialize<'de> for McpServerConfig {
@@ -62,6 +82,14 @@ impl<'de> Deserialize<'de> for McpServerConfig {
             tool_timeout_sec: Option<Duration>,
             #[serde(default)]
             enabled: Option<bool>,
+            #[serde(default)]
+            enabled_tools: Option<Vec<String>>,
+            #[serde(default)]
+            disabled_tools: Option<Vec<String>>,
+            #[serde(default)]
+            approval_mode: Option<McpToolApprovalMode>,
+            #[serde(default)]
+            tool_approval_modes: HashMap<String, McpToolApprovalMode>,
         }
 
         let raw = RawMcpServerConfig::deserialize(deserializer)?;
@@ -134,6 +162,10 @@ impl<'de> Deserialize<'de> for McpServerConfig {
             startup_timeout_sec,
             tool_timeout_sec: raw.tool_timeout_sec,
             enabled: raw.enabled.unwrap_or_else(default_enabled),
+            enabled_tools: raw.enabled_tools,
+            disabled_tools: raw.disabled_tools,
+            approval_mode: raw.approval_mode,
+            tool_approval_modes: raw.tool_approval_modes,
         })
     }
 }
@@ -142,6 +174,30 @@ const fn default_enabled() -> bool {
     true
 }
 
+/// When Codex asks the user before calling an MCP tool.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum McpToolApprovalMode {
+    /// Ask before every call, unless the user approved the tool for the
+    /// session. Calls are rejected when `approval_policy` is `never`.
+    Always,
+    /// Call the tool without asking.
+    Never,
+    /// Defer to `approval_policy`: ask only when it is `untrusted`.
+    #[default]
+    OnRequest,
+}
+
+impl McpToolApprovalMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            McpToolApprovalMode::Always => "always",
+            McpToolApprovalMode::Never => "never",
+            McpToolApprovalMode::OnRequest => "on-request",
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(untagged, deny_unknown_fields, rename_all = "snake_case")]
 pub enum McpServerTransportConfig {
@@ -300,6 +356,45 @@ impl Default for OtelConfig {
     }
 }
 
+// ===== Lifecycle hooks =====
+
+/// Commands run at fixed points of a turn, configured under `[hooks]`. Each
+/// hook receives a JSON payload on stdin and may print a JSON decision on
+/// stdout.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct HooksConfig {
+    /// Run before a tool call; may block the call or rewrite its input.
+    #[serde(default)]
+    pub pre_tool_use: Vec<HookConfig>,
+
+    /// Run after a tool call produced output; may add feedback for the model.
+    #[serde(default)]
+    pub post_tool_use: Vec<HookConfig>,
+
+    /// Run when a user prompt starts a task; may block it or add context.
+    #[serde(default)]
+    pub user_prompt_submit: Vec<HookConfig>,
+
+    /// Run when the model finishes a turn; may ask it to keep going.
+    #[serde(default)]
+    pub turn_stop: Vec<HookConfig>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct HookConfig {
+    /// Program and arguments to run.
+    pub command: Vec<String>,
+
+    /// Glob matched against the tool name for tool hooks. Matches every tool
+    /// when unset.
+    #[serde(default)]
+    pub matcher: Option<String>,
+
+    /// Time to wait for the hook before ignoring it. Defaults to 60 seconds.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 #[serde(untagged)]
 pub enum Notifications {
@@ -449,6 +544,22 @@ pub enum ReasoningSummaryFormat {
     Experimental,
 }
 
+/// An entry of `model_fallbacks`, as written in `config.toml`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ModelFallbackToml {
+    pub model: String,
+    /// Key in the `model_providers` map. Defaults to the profile's provider.
+    pub model_provider: Option<String>,
+}
+
+/// A model to fail over to, with its provider resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelFallback {
+    pub model: String,
+    pub model_provider_id: String,
+    pub model_provider: ModelProviderInfo,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -530,6 +641,31 @@ mod tests {
         assert!(!cfg.enabled);
     }
 
+    #[test]
+    fn deserialize_tool_filters_and_approval_modes() {
+        let cfg: McpServerConfig = toml::from_str(
+            r#"
+            command = "echo"
+            enabled_tools = ["search_*", "fetch"]
+            disabled_tools = ["search_admin"]
+            approval_mode = "always"
+            tool_approval_modes = { fetch = "never" }
+        "#,
+        )
+        .expect("should deserialize tool filters");
+
+        assert_eq!(
+            cfg.enabled_tools,
+            Some(vec!["search_*".to_string(), "fetch".to_string()])
+        );
+        assert_eq!(cfg.disabled_tools, Some(vec!["search_admin".to_string()]));
+        assert_eq!(cfg.approval_mode, Some(McpToolApprovalMode::Always));
+        assert_eq!(
+            cfg.tool_approval_modes,
+            HashMap::from([("fetch".to_string(), McpToolApprovalMode::Never)])
+        );
+    }
+
     #[test]
     fn deserialize_streamable_http_server_config() {
         let cfg: McpServerConfig = toml::from_str(
@@ -606,4 +742,38 @@ mod tests {
             "unexpected error: {err}"
         );
     }
+
+    #[test]
+    fn deserialize_hooks() {
+        let cfg: HooksConfig = toml::from_str(
+            r#"
+            [[pre_tool_use]]
+            command = ["python3", "guard.py"]
+            matcher = "shell"
+            timeout_ms = 5000
+
+            [[turn_stop]]
+            command = ["./check.sh"]
+        "#,
+        )
+        .expect("should deserialize hooks");
+
+        assert_eq!(
+            cfg,
+            HooksConfig {
+                pre_tool_use: vec![HookConfig {
+                    command: vec!["python3".to_string(), "guard.py".to_string()],
+                    matcher: Some("shell".to_string()),
+                    timeout_ms: Some(5000),
+                }],
+                post_tool_use: vec![],
+                user_prompt_submit: vec![],
+                turn_stop: vec![HookConfig {
+                    command: vec!["./check.sh".to_string()],
+                    matcher: None,
+                    timeout_ms: None,
+                }],
+            }
+        );
+    }
 }
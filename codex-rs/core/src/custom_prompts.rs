@@ -4,6 +4,13 @@ use std::path::Path;
 use std::path::PathBuf;
 use tokio::fs;
 
+/// Directory, relative to a project directory, that holds shared prompts.
+pub const PROJECT_PROMPTS_DIR: &str = ".codex/prompts";
+
+/// Namespace given to a user prompt whose name is taken by a project prompt,
+/// so that both stay available (`/prompts:user:name`).
+pub const USER_PROMPTS_NAMESPACE: &str = "user";
+
 /// Return the default prompts directory: `$CODEX_HOME/prompts`.
 /// If `CODEX_HOME` cannot be resolved, returns `None`.
 pub fn default_prompts_dir() -> Option<PathBuf> {
@@ -12,65 +19,130 @@ pub fn default_prompts_dir() -> Option<PathBuf> {
         .map(|home| home.join("prompts"))
 }
 
+/// Return the project prompt directories for `cwd`, nearest first: the
+/// `.codex/prompts` directory of `cwd` and of each parent up to and including
+/// the Git root. Outside a Git repository only `cwd` is considered.
+pub fn project_prompts_dirs(cwd: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for dir in cwd.ancestors() {
+        dirs.push(dir.join(PROJECT_PROMPTS_DIR));
+        if dir.join(".git").exists() {
+            return dirs;
+        }
+    }
+    vec![cwd.join(PROJECT_PROMPTS_DIR)]
+}
+
+/// Discover the prompts available in `cwd`: project prompts (see
+/// [`project_prompts_dirs`]) plus user prompts from `user_dir`, sorted by name.
+///
+/// A prompt in a nearer project directory hides a prompt with the same name
+/// further up. A project prompt takes precedence over a user prompt with the
+/// same name; the user prompt is then renamed to `user:<name>`.
+pub async fn discover_prompts(user_dir: Option<&Path>, cwd: &Path) -> Vec<CustomPrompt> {
+    let mut out: Vec<CustomPrompt> = Vec::new();
+    let mut names: HashSet<String> = HashSet::new();
+
+    for dir in project_prompts_dirs(cwd) {
+        // `~/.codex/prompts` is not a project directory even when Codex runs
+        // from the home directory.
+        if Some(dir.as_path()) == user_dir {
+            continue;
+        }
+        for prompt in discover_prompts_in(&dir).await {
+            if names.insert(prompt.name.clone()) {
+                out.push(prompt);
+            }
+        }
+    }
+
+    if let Some(user_dir) = user_dir {
+        for mut prompt in discover_prompts_in(user_dir).await {
+            if names.contains(&prompt.name) {
+                prompt.name = format!("{USER_PROMPTS_NAMESPACE}:{}", prompt.name);
+            }
+            if names.insert(prompt.name.clone()) {
+                out.push(prompt);
+            }
+        }
+    }
+
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    out
+}
+
 /// Discover prompt files in the given directory, returning entries sorted by name.
-/// Non-files are ignored. If the directory does not exist or cannot be read, returns empty.
+/// Files in subdirectories are named `dir:name`. Other entries are ignored. If
+/// the directory does not exist or cannot be read, returns empty.
 pub async fn discover_prompts_in(dir: &Path) -> Vec<CustomPrompt> {
     discover_prompts_in_excluding(dir, &HashSet::new()).await
 }
 
 /// Discover prompt files in the given directory, excluding any with names in `exclude`.
-/// Returns entries sorted by name. Non-files are ignored. Missing/unreadable dir yields empty.
+/// Returns entries sorted by name. Files in subdirectories are named `dir:name`;
+/// hidden subdirectories and other entries are ignored. Missing/unreadable dir yields empty.
 pub async fn discover_prompts_in_excluding(
     dir: &Path,
     exclude: &HashSet<String>,
 ) -> Vec<CustomPrompt> {
     let mut out: Vec<CustomPrompt> = Vec::new();
-    let mut entries = match fs::read_dir(dir).await {
-        Ok(entries) => entries,
-        Err(_) => return out,
-    };
+    // Directories still to scan, with the name prefix for prompts inside them.
+    let mut pending: Vec<(PathBuf, String)> = vec![(dir.to_path_buf(), String::new())];
 
-    while let Ok(Some(entry)) = entries.next_entry().await {
-        let path = entry.path();
-        let is_file = entry
-            .file_type()
-            .await
-            .map(|ft| ft.is_file())
-            .unwrap_or(false);
-        if !is_file {
-            continue;
-        }
-        // Only include Markdown files with a .md extension.
-        let is_md = path
-            .extension()
-            .and_then(|s| s.to_str())
-            .map(|ext| ext.eq_ignore_ascii_case("md"))
-            .unwrap_or(false);
-        if !is_md {
-            continue;
-        }
-        let Some(name) = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .map(str::to_string)
-        else {
-            continue;
-        };
-        if exclude.contains(&name) {
-            continue;
-        }
-        let content = match fs::read_to_string(&path).await {
-            Ok(s) => s,
+    while let Some((dir, prefix)) = pending.pop() {
+        let mut entries = match fs::read_dir(&dir).await {
+            Ok(entries) => entries,
             Err(_) => continue,
         };
-        let (description, argument_hint, body) = parse_frontmatter(&content);
-        out.push(CustomPrompt {
-            name,
-            path,
-            content: body,
-            description,
-            argument_hint,
-        });
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type().await else {
+                continue;
+            };
+            if file_type.is_dir() {
+                if let Some(dir_name) = path.file_name().and_then(|s| s.to_str())
+                    && !dir_name.starts_with('.')
+                {
+                    pending.push((path.clone(), format!("{prefix}{dir_name}:")));
+                }
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+            // Only include Markdown files with a .md extension.
+            let is_md = path
+                .extension()
+                .and_then(|s| s.to_str())
+                .map(|ext| ext.eq_ignore_ascii_case("md"))
+                .unwrap_or(false);
+            if !is_md {
+                continue;
+            }
+            let Some(name) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(|stem| format!("{prefix}{stem}"))
+            else {
+                continue;
+            };
+            if exclude.contains(&name) {
+                continue;
+            }
+            let content = match fs::read_to_string(&path).await {
+                Ok(s) => s,
+                Err(_) => continue,
+            };
+            let (description, argument_hint, body) = parse_frontmatter(&content);
+            out.push(CustomPrompt {
+                name,
+                path,
+                content: body,
+                description,
+                argument_hint,
+            });
+        }
     }
     out.sort_by(|a, b| a.name.cmp(&b.name));
     out
@@ -223,4 +295,62 @@ mod tests {
         assert_eq!(hint.as_deref(), Some("[arg]"));
         assert_eq!(body, "First line\r\nSecond line\r\n");
     }
+
+    #[tokio::test]
+    async fn nested_directories_become_namespaces() {
+        let tmp = tempdir().expect("create TempDir");
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("git/pr")).unwrap();
+        fs::create_dir_all(dir.join(".hidden")).unwrap();
+        fs::write(dir.join("git/commit.md"), b"commit").unwrap();
+        fs::write(dir.join("git/pr/open.md"), b"open").unwrap();
+        fs::write(dir.join(".hidden/secret.md"), b"secret").unwrap();
+        fs::write(dir.join("top.md"), b"top").unwrap();
+
+        let found = discover_prompts_in(dir).await;
+        let names: Vec<String> = found.into_iter().map(|e| e.name).collect();
+        assert_eq!(names, vec!["git:commit", "git:pr:open", "top"]);
+    }
+
+    #[tokio::test]
+    async fn project_prompts_take_precedence_over_user_prompts() {
+        let tmp = tempdir().expect("create TempDir");
+        let user_dir = tmp.path().join("codex_home/prompts");
+        let repo = tmp.path().join("repo");
+        let cwd = repo.join("crates/app");
+        fs::create_dir_all(&user_dir).unwrap();
+        fs::create_dir_all(repo.join(PROJECT_PROMPTS_DIR)).unwrap();
+        fs::create_dir_all(cwd.join(PROJECT_PROMPTS_DIR)).unwrap();
+        fs::create_dir(repo.join(".git")).unwrap();
+        // Above the git root, so never consulted.
+        fs::create_dir_all(tmp.path().join(PROJECT_PROMPTS_DIR)).unwrap();
+        fs::write(
+            tmp.path().join(PROJECT_PROMPTS_DIR).join("outside.md"),
+            b"x",
+        )
+        .unwrap();
+
+        fs::write(user_dir.join("review.md"), b"user review").unwrap();
+        fs::write(user_dir.join("mine.md"), b"user only").unwrap();
+        fs::write(
+            repo.join(PROJECT_PROMPTS_DIR).join("review.md"),
+            b"repo review",
+        )
+        .unwrap();
+        fs::write(repo.join(PROJECT_PROMPTS_DIR).join("test.md"), b"repo test").unwrap();
+        fs::write(cwd.join(PROJECT_PROMPTS_DIR).join("test.md"), b"app test").unwrap();
+
+        let found = discover_prompts(Some(&user_dir), &cwd).await;
+        let summary: Vec<(String, String)> =
+            found.into_iter().map(|p| (p.name, p.content)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("mine".to_string(), "user only".to_string()),
+                ("review".to_string(), "repo review".to_string()),
+                ("test".to_string(), "app test".to_string()),
+                ("user:review".to_string(), "user review".to_string()),
+            ]
+        );
+    }
 }
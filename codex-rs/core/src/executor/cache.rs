@@ -1,15 +1,44 @@
 use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
 
+use tracing::warn;
+
+use crate::approval_rules::ApprovalRules;
+use crate::approval_rules::ProjectRules;
+use crate::approval_rules::RuleKind;
+use crate::approval_rules::project_root_for_rules;
+use crate::approval_rules::suggested_prefix;
+
 #[derive(Clone, Debug, Default)]
 /// Thread-safe store of user approvals so repeated commands can reuse
-/// previously granted trust.
+/// previously granted trust. Session approvals are kept in memory; project
+/// rules are read from `CODEX_HOME` once per session when a home is set, and
+/// rules added during the session are written back to it.
 pub(crate) struct ApprovalCache {
     inner: Arc<Mutex<HashSet<Vec<String>>>>,
+    codex_home: Option<PathBuf>,
+    rules: Arc<Mutex<Option<ApprovalRules>>>,
+}
+
+/// Approvals in effect for a single command.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ApprovalSnapshot {
+    pub(crate) commands: HashSet<Vec<String>>,
+    pub(crate) project_rules: ProjectRules,
 }
 
 impl ApprovalCache {
+    pub(crate) fn new(codex_home: Option<PathBuf>) -> Self {
+        Self {
+            inner: Arc::default(),
+            codex_home,
+            rules: Arc::default(),
+        }
+    }
+
     pub(crate) fn insert(&self, command: Vec<String>) {
         if command.is_empty() {
             return;
@@ -19,33 +48,139 @@ impl ApprovalCache {
         }
     }
 
-    pub(crate) fn snapshot(&self) -> HashSet<Vec<String>> {
-        self.inner.lock().map(|g| g.clone()).unwrap_or_default()
+    /// Records an allow rule for the prefix of `command` in the project that
+    /// contains `cwd`, and approves the exact command for this session.
+    pub(crate) fn insert_for_project(&self, cwd: &Path, command: Vec<String>) {
+        if command.is_empty() {
+            return;
+        }
+        if let Some(codex_home) = self.codex_home.as_deref() {
+            let rule = suggested_prefix(&command).unwrap_or_else(|| command.clone());
+            let project = project_root_for_rules(cwd);
+            // Re-read the file so rules written by other sessions since this
+            // one started are kept.
+            let result = ApprovalRules::load(codex_home).and_then(|mut rules| {
+                if rules.add(&project, RuleKind::Allow, rule) {
+                    rules.save(codex_home)?;
+                }
+                Ok(rules)
+            });
+            match result {
+                Ok(rules) => {
+                    if let Ok(mut guard) = self.rules.lock() {
+                        *guard = Some(rules);
+                    }
+                }
+                Err(err) => warn!("failed to persist approval rule: {err}"),
+            }
+        }
+        self.insert(command);
+    }
+
+    /// Approvals that apply to a command run in `cwd`.
+    pub(crate) fn snapshot(&self, cwd: &Path) -> ApprovalSnapshot {
+        let commands = self.inner.lock().map(|g| g.clone()).unwrap_or_default();
+        ApprovalSnapshot {
+            commands,
+            project_rules: self.project_rules(cwd),
+        }
+    }
+
+    fn project_rules(&self, cwd: &Path) -> ProjectRules {
+        let Some(codex_home) = self.codex_home.as_deref() else {
+            return ProjectRules::default();
+        };
+        let Ok(mut guard) = self.rules.lock() else {
+            return ProjectRules::default();
+        };
+        let rules = guard.get_or_insert_with(|| {
+            ApprovalRules::load(codex_home).unwrap_or_else(|err| {
+                warn!("failed to load approval rules: {err}");
+                ApprovalRules::default()
+            })
+        });
+        rules
+            .project(&project_root_for_rules(cwd))
+            .cloned()
+            .unwrap_or_default()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::approval_rules::RuleVerdict;
     use pretty_assertions::assert_eq;
 
     #[test]
     fn insert_ignores_empty_and_dedupes() {
         let cache = ApprovalCache::default();
+        let cwd = std::env::temp_dir();
 
         // Empty should be ignored
         cache.insert(vec![]);
-        assert!(cache.snapshot().is_empty());
+        assert!(cache.snapshot(&cwd).commands.is_empty());
 
         // Insert a command and verify snapshot contains it
         let cmd = vec!["foo".to_string(), "bar".to_string()];
         cache.insert(cmd.clone());
-        let snap1 = cache.snapshot();
+        let snap1 = cache.snapshot(&cwd).commands;
         assert!(snap1.contains(&cmd));
 
         // Reinserting should not create duplicates
         cache.insert(cmd);
-        let snap2 = cache.snapshot();
+        let snap2 = cache.snapshot(&cwd).commands;
         assert_eq!(snap1, snap2);
     }
+
+    #[test]
+    fn insert_for_project_persists_prefix_rule() {
+        let codex_home = tempfile::tempdir().expect("codex home");
+        let project = tempfile::tempdir().expect("project");
+        let cache = ApprovalCache::new(Some(codex_home.path().to_path_buf()));
+
+        let cmd = vec!["cargo".to_string(), "test".to_string(), "-q".to_string()];
+        cache.insert_for_project(project.path(), cmd.clone());
+
+        let snapshot = cache.snapshot(project.path());
+        assert!(snapshot.commands.contains(&cmd));
+        assert_eq!(
+            snapshot.project_rules.allow,
+            vec![vec!["cargo".to_string(), "test".to_string()]]
+        );
+
+        // A fresh cache (e.g. a new session) still sees the persisted rule.
+        let fresh = ApprovalCache::new(Some(codex_home.path().to_path_buf()));
+        assert_eq!(
+            fresh.snapshot(project.path()).project_rules.evaluate(&[
+                "cargo".to_string(),
+                "test".to_string(),
+                "--all".to_string()
+            ]),
+            Some(RuleVerdict::Allow)
+        );
+    }
+
+    #[test]
+    fn snapshot_reads_rules_file_once() {
+        let codex_home = tempfile::tempdir().expect("codex home");
+        let project = tempfile::tempdir().expect("project");
+        let cache = ApprovalCache::new(Some(codex_home.path().to_path_buf()));
+        assert!(cache.snapshot(project.path()).project_rules.is_empty());
+
+        let mut rules = ApprovalRules::default();
+        rules.add(
+            &project_root_for_rules(project.path()),
+            RuleKind::Deny,
+            vec!["git".to_string(), "push".to_string()],
+        );
+        rules.save(codex_home.path()).expect("save rules");
+
+        assert!(cache.snapshot(project.path()).project_rules.is_empty());
+        let fresh = ApprovalCache::new(Some(codex_home.path().to_path_buf()));
+        assert_eq!(
+            fresh.snapshot(project.path()).project_rules.deny,
+            vec![vec!["git".to_string(), "push".to_string()]]
+        );
+    }
 }
@@ -6,7 +6,9 @@ use std::time::Duration;
 use super::backends::ExecutionMode;
 use super::backends::backend_for_mode;
 use super::cache::ApprovalCache;
+use crate::approval_rules::suggested_prefix;
 use crate::codex::Session;
+use crate::command_safety::exec_policy::ExecPolicy;
 use crate::error::CodexErr;
 use crate::error::SandboxErr;
 use crate::error::get_error_message_ui;
@@ -52,13 +54,21 @@ impl ExecutorConfig {
 pub(crate) struct Executor {
     approval_cache: ApprovalCache,
     config: Arc<RwLock<ExecutorConfig>>,
+    exec_policy: ExecPolicy,
 }
 
 impl Executor {
-    pub(crate) fn new(config: ExecutorConfig) -> Self {
+    /// `codex_home` enables persistent per-project approval rules; without it
+    /// approvals only last for the session.
+    pub(crate) fn new(
+        config: ExecutorConfig,
+        codex_home: Option<PathBuf>,
+        exec_policy: ExecPolicy,
+    ) -> Self {
         Self {
-            approval_cache: ApprovalCache::default(),
+            approval_cache: ApprovalCache::new(codex_home),
             config: Arc::new(RwLock::new(config)),
+            exec_policy,
         }
     }
 
@@ -108,15 +118,19 @@ impl Executor {
         let sandbox_decision = select_sandbox(
             &request,
             approval_policy,
-            self.approval_cache.snapshot(),
+            self.approval_cache.snapshot(&request.params.cwd),
             &config,
+            &self.exec_policy,
             session,
             &context.sub_id,
             &context.call_id,
             &context.otel_event_manager,
         )
         .await?;
-        if sandbox_decision.record_session_approval {
+        if sandbox_decision.record_project_approval {
+            self.approval_cache
+                .insert_for_project(&request.params.cwd, request.approval_command.clone());
+        } else if sandbox_decision.record_session_approval {
             self.approval_cache.insert(request.approval_command.clone());
         }
 
@@ -180,6 +194,7 @@ impl Executor {
                 request.approval_command.clone(),
                 request.params.cwd.clone(),
                 Some("command failed; retry without sandbox?".to_string()),
+                suggested_prefix(&request.approval_command),
             )
             .await;
 
@@ -190,9 +205,14 @@ impl Executor {
             ToolDecisionSource::User,
         );
         match decision {
-            ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {
+            ReviewDecision::Approved
+            | ReviewDecision::ApprovedForSession
+            | ReviewDecision::ApprovedForProject => {
                 if matches!(decision, ReviewDecision::ApprovedForSession) {
                     self.approval_cache.insert(request.approval_command.clone());
+                } else if matches!(decision, ReviewDecision::ApprovedForProject) {
+                    self.approval_cache
+                        .insert_for_project(&config.sandbox_cwd, request.approval_command.clone());
                 }
                 session
                     .notify_background_event(&context.sub_id, "retrying command without sandbox")
@@ -1,18 +1,22 @@
 use crate::apply_patch::ApplyPatchExec;
+use crate::approval_rules::RuleVerdict;
+use crate::approval_rules::suggested_prefix;
 use crate::codex::Session;
+use crate::command_safety::exec_policy::ExecPolicy;
 use crate::exec::SandboxType;
 use crate::executor::ExecutionMode;
 use crate::executor::ExecutionRequest;
 use crate::executor::ExecutorConfig;
+use crate::executor::cache::ApprovalSnapshot;
 use crate::executor::errors::ExecError;
 use crate::safety::SafetyCheck;
 use crate::safety::assess_command_safety;
 use crate::safety::assess_patch_safety;
+use crate::safety::assess_project_allowed_command;
 use codex_otel::otel_event_manager::OtelEventManager;
 use codex_otel::otel_event_manager::ToolDecisionSource;
 use codex_protocol::protocol::AskForApproval;
 use codex_protocol::protocol::ReviewDecision;
-use std::collections::HashSet;
 
 /// Sandbox placement options selected for an execution run, including whether
 /// to escalate after failures and whether approvals should persist.
@@ -20,6 +24,7 @@ pub(crate) struct SandboxDecision {
     pub(crate) initial_sandbox: SandboxType,
     pub(crate) escalate_on_failure: bool,
     pub(crate) record_session_approval: bool,
+    pub(crate) record_project_approval: bool,
 }
 
 impl SandboxDecision {
@@ -28,6 +33,7 @@ impl SandboxDecision {
             initial_sandbox: sandbox,
             escalate_on_failure,
             record_session_approval: false,
+            record_project_approval: false,
         }
     }
 
@@ -36,6 +42,7 @@ impl SandboxDecision {
             initial_sandbox: SandboxType::None,
             escalate_on_failure: false,
             record_session_approval,
+            record_project_approval: false,
         }
     }
 }
@@ -56,8 +63,9 @@ fn should_escalate_on_failure(approval: AskForApproval, sandbox: SandboxType) ->
 pub async fn select_sandbox(
     request: &ExecutionRequest,
     approval_policy: AskForApproval,
-    approval_cache: HashSet<Vec<String>>,
+    approval_cache: ApprovalSnapshot,
     config: &ExecutorConfig,
+    exec_policy: &ExecPolicy,
     session: &Session,
     sub_id: &str,
     call_id: &str,
@@ -70,6 +78,7 @@ pub async fn select_sandbox(
                 approval_policy,
                 approval_cache,
                 config,
+                exec_policy,
                 session,
                 sub_id,
                 call_id,
@@ -87,8 +96,9 @@ pub async fn select_sandbox(
 async fn select_shell_sandbox(
     request: &ExecutionRequest,
     approval_policy: AskForApproval,
-    approved_snapshot: HashSet<Vec<String>>,
+    approved_snapshot: ApprovalSnapshot,
     config: &ExecutorConfig,
+    exec_policy: &ExecPolicy,
     session: &Session,
     sub_id: &str,
     call_id: &str,
@@ -100,13 +110,43 @@ async fn select_shell_sandbox(
         request.approval_command.clone()
     };
 
-    let safety = assess_command_safety(
-        &command_for_safety,
-        approval_policy,
-        &config.sandbox_policy,
-        &approved_snapshot,
-        request.params.with_escalated_permissions.unwrap_or(false),
-    );
+    // A deny rule persisted by the user rejects the command outright, and a
+    // script that cannot be checked against the deny rules always needs the
+    // user's approval. An allow rule only replaces the approval prompt: the
+    // exec policy and the dangerous-command check still run first, and the
+    // command stays in the sandbox.
+    let safety = match approved_snapshot
+        .project_rules
+        .evaluate(&command_for_safety)
+    {
+        Some(RuleVerdict::Deny) => SafetyCheck::Reject {
+            reason: "command matches a deny rule for this project".to_string(),
+        },
+        Some(RuleVerdict::Unverified) if approval_policy == AskForApproval::Never => {
+            SafetyCheck::Reject {
+                reason: "script could not be checked against this project's deny rules".to_string(),
+            }
+        }
+        Some(RuleVerdict::Unverified) => SafetyCheck::AskUser,
+        Some(RuleVerdict::Allow) => assess_project_allowed_command(
+            &command_for_safety,
+            approval_policy,
+            &config.sandbox_policy,
+            &approved_snapshot.commands,
+            request.params.with_escalated_permissions.unwrap_or(false),
+            exec_policy,
+            &request.params.cwd,
+        ),
+        None => assess_command_safety(
+            &command_for_safety,
+            approval_policy,
+            &config.sandbox_policy,
+            &approved_snapshot.commands,
+            request.params.with_escalated_permissions.unwrap_or(false),
+            exec_policy,
+            &request.params.cwd,
+        ),
+    };
 
     match safety {
         SafetyCheck::AutoApprove {
@@ -136,6 +176,7 @@ async fn select_shell_sandbox(
                     request.approval_command.clone(),
                     request.params.cwd.clone(),
                     request.params.justification.clone(),
+                    suggested_prefix(&request.approval_command),
                 )
                 .await;
 
@@ -148,6 +189,11 @@ async fn select_shell_sandbox(
             match decision {
                 ReviewDecision::Approved => Ok(SandboxDecision::user_override(false)),
                 ReviewDecision::ApprovedForSession => Ok(SandboxDecision::user_override(true)),
+                ReviewDecision::ApprovedForProject => {
+                    let mut decision = SandboxDecision::user_override(true);
+                    decision.record_project_approval = true;
+                    Ok(decision)
+                }
                 ReviewDecision::Denied | ReviewDecision::Abort => {
                     Err(ExecError::rejection("exec command rejected by user"))
                 }
@@ -190,6 +236,7 @@ fn select_apply_patch_sandbox(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::approval_rules::ProjectRules;
     use crate::codex::make_session_and_context;
     use crate::exec::ExecParams;
     use crate::function_tool::FunctionCallError;
@@ -228,6 +275,7 @@ mod tests {
             AskForApproval::OnRequest,
             Default::default(),
             &cfg,
+            &ExecPolicy::default(),
             &session,
             "sub",
             "call",
@@ -271,6 +319,7 @@ mod tests {
             AskForApproval::OnRequest,
             Default::default(),
             &cfg,
+            &ExecPolicy::default(),
             &session,
             "sub",
             "call",
@@ -315,6 +364,7 @@ mod tests {
             AskForApproval::UnlessTrusted,
             Default::default(),
             &cfg,
+            &ExecPolicy::default(),
             &session,
             "sub",
             "call",
@@ -354,6 +404,7 @@ mod tests {
             AskForApproval::OnRequest,
             Default::default(),
             &cfg,
+            &ExecPolicy::default(),
             &session,
             "sub",
             "call",
@@ -365,6 +416,197 @@ mod tests {
         assert_eq!(decision.escalate_on_failure, false);
     }
 
+    #[tokio::test]
+    async fn select_shell_rejects_project_deny_rule() {
+        let (session, ctx) = make_session_and_context();
+        let cfg = ExecutorConfig::new(SandboxPolicy::DangerFullAccess, std::env::temp_dir(), None);
+        let request = ExecutionRequest {
+            params: ExecParams {
+                command: vec!["git".into(), "push".into()],
+                cwd: std::env::temp_dir(),
+                timeout_ms: None,
+                env: std::collections::HashMap::new(),
+                with_escalated_permissions: None,
+                justification: None,
+            },
+            approval_command: vec!["git".into(), "push".into()],
+            mode: ExecutionMode::Shell,
+            stdout_stream: None,
+            use_shell_profile: false,
+        };
+        let approvals = ApprovalSnapshot {
+            commands: Default::default(),
+            project_rules: ProjectRules {
+                allow: vec![vec!["git".into()]],
+                deny: vec![vec!["git".into(), "push".into()]],
+            },
+        };
+        let otel_event_manager = ctx.client.get_otel_event_manager();
+        let result = select_sandbox(
+            &request,
+            AskForApproval::OnRequest,
+            approvals,
+            &cfg,
+            &ExecPolicy::default(),
+            &session,
+            "sub",
+            "call",
+            &otel_event_manager,
+        )
+        .await;
+        match result {
+            Ok(_) => panic!("expected error"),
+            Err(ExecError::Function(FunctionCallError::RespondToModel(msg))) => assert_eq!(
+                msg,
+                "exec command rejected: command matches a deny rule for this project"
+            ),
+            Err(other) => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn select_shell_does_not_auto_approve_unparsed_script_with_deny_rules() {
+        let (session, ctx) = make_session_and_context();
+        let cfg = ExecutorConfig::new(SandboxPolicy::DangerFullAccess, std::env::temp_dir(), None);
+        let command: Vec<String> = vec!["bash".into(), "-lc".into(), "git push 2>&1".into()];
+        let request = ExecutionRequest {
+            params: ExecParams {
+                command: command.clone(),
+                cwd: std::env::temp_dir(),
+                timeout_ms: None,
+                env: std::collections::HashMap::new(),
+                with_escalated_permissions: None,
+                justification: None,
+            },
+            approval_command: command,
+            mode: ExecutionMode::Shell,
+            stdout_stream: None,
+            use_shell_profile: false,
+        };
+        let approvals = ApprovalSnapshot {
+            commands: Default::default(),
+            project_rules: ProjectRules {
+                allow: Vec::new(),
+                deny: vec![vec!["git".into(), "push".into()]],
+            },
+        };
+        let otel_event_manager = ctx.client.get_otel_event_manager();
+        let result = select_sandbox(
+            &request,
+            AskForApproval::Never,
+            approvals,
+            &cfg,
+            &ExecPolicy::default(),
+            &session,
+            "sub",
+            "call",
+            &otel_event_manager,
+        )
+        .await;
+        match result {
+            Ok(_) => panic!("expected error"),
+            Err(ExecError::Function(FunctionCallError::RespondToModel(msg))) => assert_eq!(
+                msg,
+                "exec command rejected: script could not be checked against this project's deny rules"
+            ),
+            Err(other) => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn select_shell_project_allow_rule_keeps_dangerous_command_check() {
+        let (session, ctx) = make_session_and_context();
+        let cfg = ExecutorConfig::new(SandboxPolicy::ReadOnly, std::env::temp_dir(), None);
+        let command: Vec<String> = vec!["git".into(), "reset".into(), "--hard".into()];
+        let request = ExecutionRequest {
+            params: ExecParams {
+                command: command.clone(),
+                cwd: std::env::temp_dir(),
+                timeout_ms: None,
+                env: std::collections::HashMap::new(),
+                with_escalated_permissions: None,
+                justification: None,
+            },
+            approval_command: command,
+            mode: ExecutionMode::Shell,
+            stdout_stream: None,
+            use_shell_profile: false,
+        };
+        let approvals = ApprovalSnapshot {
+            commands: Default::default(),
+            project_rules: ProjectRules {
+                allow: vec![vec!["git".into()]],
+                deny: Vec::new(),
+            },
+        };
+        let otel_event_manager = ctx.client.get_otel_event_manager();
+        let result = select_sandbox(
+            &request,
+            AskForApproval::Never,
+            approvals,
+            &cfg,
+            &ExecPolicy::default(),
+            &session,
+            "sub",
+            "call",
+            &otel_event_manager,
+        )
+        .await;
+        match result {
+            Ok(_) => panic!("expected error"),
+            Err(ExecError::Function(FunctionCallError::RespondToModel(msg))) => assert_eq!(
+                msg,
+                "exec command rejected: dangerous command detected; rejected by user approval settings"
+            ),
+            Err(other) => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    #[tokio::test]
+    async fn select_shell_runs_project_allowed_commands_in_sandbox() {
+        let (session, ctx) = make_session_and_context();
+        let cfg = ExecutorConfig::new(SandboxPolicy::ReadOnly, std::env::temp_dir(), None);
+        let command: Vec<String> = vec!["cargo".into(), "build".into()];
+        let request = ExecutionRequest {
+            params: ExecParams {
+                command: command.clone(),
+                cwd: std::env::temp_dir(),
+                timeout_ms: None,
+                env: std::collections::HashMap::new(),
+                with_escalated_permissions: None,
+                justification: None,
+            },
+            approval_command: command,
+            mode: ExecutionMode::Shell,
+            stdout_stream: None,
+            use_shell_profile: false,
+        };
+        let approvals = ApprovalSnapshot {
+            commands: Default::default(),
+            project_rules: ProjectRules {
+                allow: vec![vec!["cargo".into(), "build".into()]],
+                deny: Vec::new(),
+            },
+        };
+        let otel_event_manager = ctx.client.get_otel_event_manager();
+        let decision = select_sandbox(
+            &request,
+            AskForApproval::OnRequest,
+            approvals,
+            &cfg,
+            &ExecPolicy::default(),
+            &session,
+            "sub",
+            "call",
+            &otel_event_manager,
+        )
+        .await
+        .expect("ok");
+        assert_ne!(decision.initial_sandbox, SandboxType::None);
+        assert_eq!(decision.record_session_approval, false);
+    }
+
     #[cfg(any(target_os = "macos", target_os = "linux"))]
     #[tokio::test]
     async fn select_shell_escalates_on_failure_with_platform_sandbox() {
@@ -391,6 +633,7 @@ mod tests {
             AskForApproval::OnFailure,
             Default::default(),
             &cfg,
+            &ExecPolicy::default(),
             &session,
             "sub",
             "call",
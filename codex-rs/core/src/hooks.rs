@@ -0,0 +1,593 @@
+//! Lifecycle hooks configured under `[hooks]` in `config.toml`.
+//!
+//! A hook is a command that receives a JSON payload describing the event on
+//! stdin and may print a JSON decision on stdout. Hooks that cannot be
+//! spawned, exit with a non-zero status, time out or print invalid JSON are
+//! logged and otherwise ignored, so a broken hook never blocks a turn.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use mcp_types::ContentBlock;
+use mcp_types::TextContent;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::warn;
+use wildmatch::WildMatchPattern;
+
+use crate::config_types::HookConfig;
+use crate::config_types::HooksConfig;
+use crate::protocol::InputItem;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+
+const DEFAULT_HOOK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How many times in a row `turn-stop` hooks may keep a task going before the
+/// turn is allowed to end regardless of their decision.
+pub(crate) const MAX_STOP_HOOK_CONTINUATIONS: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum HookEvent {
+    PreToolUse,
+    PostToolUse,
+    UserPromptSubmit,
+    TurnStop,
+}
+
+/// Decision printed by a hook on stdout. Every field is optional; no output
+/// at all lets the event proceed unchanged.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+struct HookOutput {
+    #[serde(default)]
+    decision: Option<HookDecision>,
+    #[serde(default)]
+    reason: Option<String>,
+    /// Replacement tool input (`pre-tool-use` only).
+    #[serde(default)]
+    tool_input: Option<JsonValue>,
+    /// Text for the model, appended to the tool output (`post-tool-use`) or
+    /// to the prompt (`user-prompt-submit`).
+    #[serde(default)]
+    additional_context: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum HookDecision {
+    Allow,
+    Block,
+}
+
+/// Fields shared by every hook payload.
+pub(crate) struct HookContext {
+    pub(crate) session_id: String,
+    pub(crate) cwd: PathBuf,
+}
+
+impl HookContext {
+    fn payload(&self, event: HookEvent, fields: JsonValue) -> JsonValue {
+        let mut payload = json!({
+            "hook_event": event,
+            "session_id": self.session_id,
+            "cwd": self.cwd,
+        });
+        if let Some(payload) = payload.as_object_mut()
+            && let JsonValue::Object(fields) = fields
+        {
+            payload.extend(fields);
+        }
+        payload
+    }
+}
+
+pub(crate) enum PreToolUseOutcome {
+    /// Run the tool with this (possibly rewritten) payload.
+    Proceed(ToolPayload),
+    /// Skip the tool and report the reason to the model.
+    Block(String),
+}
+
+pub(crate) enum UserPromptOutcome {
+    /// Start the task with this input, including any added context.
+    Proceed(Vec<InputItem>),
+    /// Drop the prompt and report the reason to the user.
+    Block(String),
+}
+
+struct Hook {
+    command: Vec<String>,
+    matcher: Option<WildMatchPattern<'*', '?'>>,
+    timeout: Duration,
+}
+
+impl Hook {
+    fn from_config(cfg: &HookConfig) -> Self {
+        Self {
+            command: cfg.command.clone(),
+            matcher: cfg.matcher.as_deref().map(WildMatchPattern::new),
+            timeout: cfg
+                .timeout_ms
+                .map(Duration::from_millis)
+                .unwrap_or(DEFAULT_HOOK_TIMEOUT),
+        }
+    }
+
+    fn matches(&self, tool_name: &str) -> bool {
+        self.matcher
+            .as_ref()
+            .is_none_or(|matcher| matcher.matches(tool_name))
+    }
+
+    async fn run(&self, payload: &JsonValue, cwd: &Path) -> Option<HookOutput> {
+        let (program, args) = self.command.split_first()?;
+        let mut child = match Command::new(program)
+            .args(args)
+            .current_dir(cwd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => {
+                warn!("failed to spawn hook `{program}`: {err}");
+                return None;
+            }
+        };
+
+        let input = payload.to_string();
+        let stdin = child.stdin.take();
+        let result = tokio::time::timeout(self.timeout, async move {
+            if let Some(mut stdin) = stdin {
+                // Hooks may exit without reading their input.
+                let _ = stdin.write_all(input.as_bytes()).await;
+            }
+            child.wait_with_output().await
+        })
+        .await;
+        let output = match result {
+            Ok(Ok(output)) => output,
+            Ok(Err(err)) => {
+                warn!("failed to wait for hook `{program}`: {err}");
+                return None;
+            }
+            Err(_) => {
+                warn!("hook `{program}` timed out after {:?}", self.timeout);
+                return None;
+            }
+        };
+
+        if !output.status.success() {
+            warn!(
+                "hook `{program}` exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            return None;
+        }
+        parse_hook_output(&String::from_utf8_lossy(&output.stdout)).unwrap_or_else(|err| {
+            warn!("ignoring invalid output from hook `{program}`: {err}");
+            None
+        })
+    }
+}
+
+fn parse_hook_output(stdout: &str) -> Result<Option<HookOutput>, serde_json::Error> {
+    let stdout = stdout.trim();
+    if stdout.is_empty() {
+        return Ok(Some(HookOutput::default()));
+    }
+    serde_json::from_str(stdout).map(Some)
+}
+
+#[derive(Default)]
+pub(crate) struct Hooks {
+    pre_tool_use: Vec<Hook>,
+    post_tool_use: Vec<Hook>,
+    user_prompt_submit: Vec<Hook>,
+    turn_stop: Vec<Hook>,
+}
+
+impl Hooks {
+    pub(crate) fn new(config: &HooksConfig) -> Self {
+        let compile = |hooks: &[HookConfig]| hooks.iter().map(Hook::from_config).collect();
+        Self {
+            pre_tool_use: compile(&config.pre_tool_use),
+            post_tool_use: compile(&config.post_tool_use),
+            user_prompt_submit: compile(&config.user_prompt_submit),
+            turn_stop: compile(&config.turn_stop),
+        }
+    }
+
+    /// Runs the matching `pre-tool-use` hooks in order. Each hook sees the
+    /// input as rewritten by the hooks before it; the first block wins.
+    pub(crate) async fn pre_tool_use(
+        &self,
+        ctx: &HookContext,
+        tool_name: &str,
+        call_id: &str,
+        mut payload: ToolPayload,
+    ) -> PreToolUseOutcome {
+        for hook in self
+            .pre_tool_use
+            .iter()
+            .filter(|hook| hook.matches(tool_name))
+        {
+            let request = ctx.payload(
+                HookEvent::PreToolUse,
+                json!({
+                    "tool_name": tool_name,
+                    "call_id": call_id,
+                    "tool_input": tool_input(&payload),
+                }),
+            );
+            let Some(output) = hook.run(&request, &ctx.cwd).await else {
+                continue;
+            };
+            if output.decision == Some(HookDecision::Block) {
+                return PreToolUseOutcome::Block(
+                    output
+                        .reason
+                        .unwrap_or_else(|| "no reason given".to_string()),
+                );
+            }
+            if let Some(input) = output.tool_input {
+                match with_tool_input(&payload, input) {
+                    Ok(rewritten) => payload = rewritten,
+                    Err(err) => warn!("ignoring tool_input from pre-tool-use hook: {err}"),
+                }
+            }
+        }
+        PreToolUseOutcome::Proceed(payload)
+    }
+
+    /// Runs the matching `post-tool-use` hooks and appends their feedback to
+    /// the tool output.
+    pub(crate) async fn post_tool_use(
+        &self,
+        ctx: &HookContext,
+        tool_name: &str,
+        call_id: &str,
+        payload: &ToolPayload,
+        output: &mut ToolOutput,
+    ) {
+        for hook in self
+            .post_tool_use
+            .iter()
+            .filter(|hook| hook.matches(tool_name))
+        {
+            let request = ctx.payload(
+                HookEvent::PostToolUse,
+                json!({
+                    "tool_name": tool_name,
+                    "call_id": call_id,
+                    "tool_input": tool_input(payload),
+                    "tool_response": tool_response(output),
+                }),
+            );
+            if let Some(HookOutput {
+                additional_context: Some(feedback),
+                ..
+            }) = hook.run(&request, &ctx.cwd).await
+            {
+                append_feedback(output, feedback);
+            }
+        }
+    }
+
+    /// Runs the `user-prompt-submit` hooks before a task starts.
+    pub(crate) async fn user_prompt_submit(
+        &self,
+        ctx: &HookContext,
+        mut input: Vec<InputItem>,
+    ) -> UserPromptOutcome {
+        let prompt = prompt_text(&input);
+        for hook in &self.user_prompt_submit {
+            let request = ctx.payload(HookEvent::UserPromptSubmit, json!({ "prompt": prompt }));
+            let Some(output) = hook.run(&request, &ctx.cwd).await else {
+                continue;
+            };
+            if output.decision == Some(HookDecision::Block) {
+                return UserPromptOutcome::Block(
+                    output
+                        .reason
+                        .unwrap_or_else(|| "no reason given".to_string()),
+                );
+            }
+            if let Some(text) = output.additional_context {
+                input.push(InputItem::Text { text });
+            }
+        }
+        UserPromptOutcome::Proceed(input)
+    }
+
+    /// Runs the `turn-stop` hooks when the model ends its turn. Returns the
+    /// message to send back to the model when a hook blocks the stop.
+    pub(crate) async fn turn_stop(
+        &self,
+        ctx: &HookContext,
+        last_assistant_message: Option<&str>,
+        stop_hook_active: bool,
+    ) -> Option<String> {
+        for hook in &self.turn_stop {
+            let request = ctx.payload(
+                HookEvent::TurnStop,
+                json!({
+                    "last_assistant_message": last_assistant_message,
+                    "stop_hook_active": stop_hook_active,
+                }),
+            );
+            if let Some(output) = hook.run(&request, &ctx.cwd).await
+                && output.decision == Some(HookDecision::Block)
+            {
+                return Some(
+                    output
+                        .reason
+                        .unwrap_or_else(|| "Continue working on the task.".to_string()),
+                );
+            }
+        }
+        None
+    }
+}
+
+/// Tool input as shown to hooks: parsed JSON arguments where possible,
+/// otherwise the raw string.
+fn tool_input(payload: &ToolPayload) -> JsonValue {
+    match payload {
+        ToolPayload::Function { arguments }
+        | ToolPayload::UnifiedExec { arguments }
+        | ToolPayload::Mcp {
+            raw_arguments: arguments,
+            ..
+        } => {
+            serde_json::from_str(arguments).unwrap_or_else(|_| JsonValue::String(arguments.clone()))
+        }
+        ToolPayload::Custom { input } => JsonValue::String(input.clone()),
+        ToolPayload::LocalShell { params } => {
+            serde_json::to_value(params).unwrap_or(JsonValue::Null)
+        }
+    }
+}
+
+/// Applies a `tool_input` returned by a hook to the original payload.
+fn with_tool_input(payload: &ToolPayload, input: JsonValue) -> Result<ToolPayload, String> {
+    let arguments = |input: JsonValue| match input {
+        JsonValue::String(arguments) => arguments,
+        other => other.to_string(),
+    };
+    Ok(match payload {
+        ToolPayload::Function { .. } => ToolPayload::Function {
+            arguments: arguments(input),
+        },
+        ToolPayload::UnifiedExec { .. } => ToolPayload::UnifiedExec {
+            arguments: arguments(input),
+        },
+        ToolPayload::Mcp { server, tool, .. } => ToolPayload::Mcp {
+            server: server.clone(),
+            tool: tool.clone(),
+            raw_arguments: arguments(input),
+        },
+        ToolPayload::Custom { .. } => match input {
+            JsonValue::String(input) => ToolPayload::Custom { input },
+            _ => return Err("custom tool input must be a string".to_string()),
+        },
+        ToolPayload::LocalShell { .. } => ToolPayload::LocalShell {
+            params: serde_json::from_value(input).map_err(|err| err.to_string())?,
+        },
+    })
+}
+
+fn tool_response(output: &ToolOutput) -> JsonValue {
+    match output {
+        ToolOutput::Function { content, success } => {
+            json!({ "content": content, "success": success })
+        }
+        ToolOutput::Mcp { result: Ok(result) } => {
+            serde_json::to_value(result).unwrap_or(JsonValue::Null)
+        }
+        ToolOutput::Mcp { result: Err(err) } => json!({ "error": err }),
+    }
+}
+
+fn append_feedback(output: &mut ToolOutput, feedback: String) {
+    match output {
+        ToolOutput::Function { content, .. }
+        | ToolOutput::Mcp {
+            result: Err(content),
+        } => {
+            content.push_str("\n\n");
+            content.push_str(&feedback);
+        }
+        ToolOutput::Mcp { result: Ok(result) } => {
+            result.content.push(ContentBlock::TextContent(TextContent {
+                annotations: None,
+                text: feedback,
+                r#type: "text".to_string(),
+            }));
+        }
+    }
+}
+
+fn prompt_text(input: &[InputItem]) -> String {
+    input
+        .iter()
+        .filter_map(|item| match item {
+            InputItem::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::models::ShellToolCallParams;
+    use pretty_assertions::assert_eq;
+
+    fn hook(command: &[&str], matcher: Option<&str>) -> Hook {
+        Hook::from_config(&HookConfig {
+            command: command.iter().map(ToString::to_string).collect(),
+            matcher: matcher.map(ToString::to_string),
+            timeout_ms: Some(5_000),
+        })
+    }
+
+    fn context() -> HookContext {
+        HookContext {
+            session_id: "session".to_string(),
+            cwd: std::env::temp_dir(),
+        }
+    }
+
+    #[test]
+    fn matcher_globs_tool_names() {
+        assert!(hook(&["true"], None).matches("shell"));
+        assert!(hook(&["true"], Some("github__*")).matches("github__create_issue"));
+        assert!(!hook(&["true"], Some("github__*")).matches("shell"));
+    }
+
+    #[test]
+    fn empty_output_proceeds_and_invalid_output_is_rejected() {
+        assert_eq!(
+            parse_hook_output("\n").expect("empty output"),
+            Some(HookOutput::default())
+        );
+        assert!(parse_hook_output("not json").is_err());
+    }
+
+    #[test]
+    fn tool_input_round_trips_through_rewrites() {
+        let function = ToolPayload::Function {
+            arguments: r#"{"path":"a.txt"}"#.to_string(),
+        };
+        assert_eq!(tool_input(&function), json!({ "path": "a.txt" }));
+        let Ok(ToolPayload::Function { arguments }) =
+            with_tool_input(&function, json!({ "path": "b.txt" }))
+        else {
+            panic!("expected function payload");
+        };
+        assert_eq!(arguments, r#"{"path":"b.txt"}"#);
+
+        let shell = ToolPayload::LocalShell {
+            params: ShellToolCallParams {
+                command: vec!["ls".to_string()],
+                workdir: None,
+                timeout_ms: None,
+                with_escalated_permissions: None,
+                justification: None,
+            },
+        };
+        let Ok(ToolPayload::LocalShell { params }) =
+            with_tool_input(&shell, json!({ "command": ["ls", "-la"] }))
+        else {
+            panic!("expected shell payload");
+        };
+        assert_eq!(params.command, vec!["ls", "-la"]);
+
+        let custom = ToolPayload::Custom {
+            input: "*** Begin Patch".to_string(),
+        };
+        assert!(with_tool_input(&custom, json!({ "input": 1 })).is_err());
+    }
+
+    #[test]
+    fn feedback_is_appended_to_function_output() {
+        let mut output = ToolOutput::Function {
+            content: "ok".to_string(),
+            success: Some(true),
+        };
+        append_feedback(&mut output, "Run the tests next.".to_string());
+        let ToolOutput::Function { content, .. } = output else {
+            panic!("expected function output");
+        };
+        assert_eq!(content, "ok\n\nRun the tests next.");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn pre_tool_use_hook_can_block_and_rewrite() {
+        let hooks = Hooks {
+            pre_tool_use: vec![
+                hook(
+                    &[
+                        "sh",
+                        "-c",
+                        r#"cat >/dev/null; echo '{"tool_input":{"cmd":"ls"}}'"#,
+                    ],
+                    Some("exec_*"),
+                ),
+                hook(
+                    &[
+                        "sh",
+                        "-c",
+                        r#"grep -q '"cmd":"rm' && echo '{"decision":"block","reason":"no rm"}'; true"#,
+                    ],
+                    None,
+                ),
+            ],
+            ..Default::default()
+        };
+
+        let outcome = hooks
+            .pre_tool_use(
+                &context(),
+                "exec_command",
+                "call-1",
+                ToolPayload::Function {
+                    arguments: r#"{"cmd":"rm -rf ."}"#.to_string(),
+                },
+            )
+            .await;
+        let PreToolUseOutcome::Proceed(ToolPayload::Function { arguments }) = outcome else {
+            panic!("expected the rewritten call to proceed");
+        };
+        assert_eq!(arguments, r#"{"cmd":"ls"}"#);
+
+        let outcome = hooks
+            .pre_tool_use(
+                &context(),
+                "shell",
+                "call-2",
+                ToolPayload::Function {
+                    arguments: r#"{"cmd":"rm -rf ."}"#.to_string(),
+                },
+            )
+            .await;
+        let PreToolUseOutcome::Block(reason) = outcome else {
+            panic!("expected the call to be blocked");
+        };
+        assert_eq!(reason, "no rm");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failing_or_slow_hooks_are_ignored() {
+        let mut slow = hook(&["sleep", "5"], None);
+        slow.timeout = Duration::from_millis(100);
+        let hooks = Hooks {
+            user_prompt_submit: vec![hook(&["sh", "-c", "exit 3"], None), slow],
+            ..Default::default()
+        };
+
+        let input = vec![InputItem::Text {
+            text: "hello".to_string(),
+        }];
+        let UserPromptOutcome::Proceed(items) =
+            hooks.user_prompt_submit(&context(), input.clone()).await
+        else {
+            panic!("expected the prompt to proceed");
+        };
+        assert_eq!(items, input);
+    }
+}
@@ -6,6 +6,7 @@
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
 mod apply_patch;
+pub mod approval_rules;
 pub mod auth;
 pub mod bash;
 mod chat_completions;
@@ -32,11 +33,15 @@ pub mod executor;
 pub mod features;
 mod flags;
 pub mod git_info;
+mod hooks;
 pub mod landlock;
 pub mod mcp;
 mod mcp_connection_manager;
+mod mcp_prompt;
 mod mcp_tool_call;
 mod message_history;
+mod messages;
+mod model_fallback;
 mod model_provider_info;
 pub mod parse_command;
 mod truncate;
@@ -4,13 +4,21 @@
 //! configured server (keyed by the *server name*). It offers convenience
 //! helpers to query the available tools across *all* servers and returns them
 //! in a single aggregated map using the fully-qualified tool name
-//! `"<server><MCP_TOOL_NAME_DELIMITER><tool>"` as the key.
+//! `"<server><MCP_TOOL_NAME_DELIMITER><tool>"` as the key. Resources and
+//! prompts are not qualified: callers address them by server name.
+//!
+//! Tools are listed once at startup. Servers that later send
+//! `notifications/tools/list_changed` are re-listed by
+//! [`McpConnectionManager::refresh_changed_tools`], which the session calls
+//! between turns.
 
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::env;
 use std::ffi::OsString;
+use std::future::Future;
 use std::sync::Arc;
+use std::sync::RwLock;
 use std::time::Duration;
 
 use anyhow::Context;
@@ -20,7 +28,19 @@ use codex_mcp_client::McpClient;
 use codex_rmcp_client::OAuthCredentialsStoreMode;
 use codex_rmcp_client::RmcpClient;
 use mcp_types::ClientCapabilities;
+use mcp_types::GetPromptRequestParams;
+use mcp_types::GetPromptResult;
 use mcp_types::Implementation;
+use mcp_types::ListResourceTemplatesRequestParams;
+use mcp_types::ListResourceTemplatesResult;
+use mcp_types::ListResourcesRequestParams;
+use mcp_types::ListResourcesResult;
+use mcp_types::Prompt;
+use mcp_types::ReadResourceRequestParams;
+use mcp_types::ReadResourceResult;
+use mcp_types::Resource;
+use mcp_types::ResourceTemplate;
+use mcp_types::ServerCapabilities;
 use mcp_types::Tool;
 
 use serde_json::json;
@@ -29,9 +49,11 @@ use sha1::Sha1;
 use tokio::task::JoinSet;
 use tracing::info;
 use tracing::warn;
+use wildmatch::WildMatchPattern;
 
 use crate::config_types::McpServerConfig;
 use crate::config_types::McpServerTransportConfig;
+use crate::config_types::McpToolApprovalMode;
 
 /// Delimiter used to separate the server name from the tool name in a fully
 /// qualified tool name.
@@ -89,10 +111,63 @@ struct ToolInfo {
     tool: Tool,
 }
 
+type ToolNamePattern = WildMatchPattern<'*', '?'>;
+
+/// Per-server tool filters and approval modes from [`McpServerConfig`].
+struct ToolPolicy {
+    enabled_tools: Option<Vec<ToolNamePattern>>,
+    disabled_tools: Vec<ToolNamePattern>,
+    approval_mode: McpToolApprovalMode,
+    tool_approval_modes: HashMap<String, McpToolApprovalMode>,
+}
+
+impl ToolPolicy {
+    fn from_config(cfg: &McpServerConfig) -> Self {
+        let compile = |patterns: &[String]| -> Vec<ToolNamePattern> {
+            patterns
+                .iter()
+                .map(|pattern| ToolNamePattern::new(pattern))
+                .collect()
+        };
+        Self {
+            enabled_tools: cfg.enabled_tools.as_deref().map(compile),
+            disabled_tools: cfg
+                .disabled_tools
+                .as_deref()
+                .map(compile)
+                .unwrap_or_default(),
+            approval_mode: cfg.approval_mode.unwrap_or_default(),
+            tool_approval_modes: cfg.tool_approval_modes.clone(),
+        }
+    }
+
+    /// Whether `tool` should be exposed to the model.
+    fn allows(&self, tool: &str) -> bool {
+        let enabled = self
+            .enabled_tools
+            .as_ref()
+            .is_none_or(|patterns| patterns.iter().any(|pattern| pattern.matches(tool)));
+        enabled
+            && !self
+                .disabled_tools
+                .iter()
+                .any(|pattern| pattern.matches(tool))
+    }
+
+    fn approval_mode(&self, tool: &str) -> McpToolApprovalMode {
+        self.tool_approval_modes
+            .get(tool)
+            .copied()
+            .unwrap_or(self.approval_mode)
+    }
+}
+
 struct ManagedClient {
     client: McpClientAdapter,
+    capabilities: ServerCapabilities,
     startup_timeout: Duration,
     tool_timeout: Option<Duration>,
+    tool_policy: ToolPolicy,
 }
 
 #[derive(Clone)]
@@ -109,15 +184,15 @@ impl McpClientAdapter {
         env: Option<HashMap<String, String>>,
         params: mcp_types::InitializeRequestParams,
         startup_timeout: Duration,
-    ) -> Result<Self> {
+    ) -> Result<(Self, ServerCapabilities)> {
         if use_rmcp_client {
             let client = Arc::new(RmcpClient::new_stdio_client(program, args, env).await?);
-            client.initialize(params, Some(startup_timeout)).await?;
-            Ok(McpClientAdapter::Rmcp(client))
+            let initialized = client.initialize(params, Some(startup_timeout)).await?;
+            Ok((McpClientAdapter::Rmcp(client), initialized.capabilities))
         } else {
             let client = Arc::new(McpClient::new_stdio_client(program, args, env).await?);
-            client.initialize(params, Some(startup_timeout)).await?;
-            Ok(McpClientAdapter::Legacy(client))
+            let initialized = client.initialize(params, Some(startup_timeout)).await?;
+            Ok((McpClientAdapter::Legacy(client), initialized.capabilities))
         }
     }
 
@@ -128,13 +203,27 @@ impl McpClientAdapter {
         params: mcp_types::InitializeRequestParams,
         startup_timeout: Duration,
         store_mode: OAuthCredentialsStoreMode,
-    ) -> Result<Self> {
+    ) -> Result<(Self, ServerCapabilities)> {
         let client = Arc::new(
             RmcpClient::new_streamable_http_client(&server_name, &url, bearer_token, store_mode)
                 .await?,
         );
-        client.initialize(params, Some(startup_timeout)).await?;
-        Ok(McpClientAdapter::Rmcp(client))
+        let initialized = client.initialize(params, Some(startup_timeout)).await?;
+        Ok((McpClientAdapter::Rmcp(client), initialized.capabilities))
+    }
+
+    fn take_tool_list_changed(&self) -> bool {
+        match self {
+            McpClientAdapter::Legacy(client) => client.take_tool_list_changed(),
+            McpClientAdapter::Rmcp(client) => client.take_tool_list_changed(),
+        }
+    }
+
+    fn mark_tool_list_changed(&self) {
+        match self {
+            McpClientAdapter::Legacy(client) => client.mark_tool_list_changed(),
+            McpClientAdapter::Rmcp(client) => client.mark_tool_list_changed(),
+        }
     }
 
     async fn list_tools(
@@ -159,6 +248,63 @@ impl McpClientAdapter {
             McpClientAdapter::Rmcp(client) => client.call_tool(name, arguments, timeout).await,
         }
     }
+
+    async fn list_resources(
+        &self,
+        params: Option<ListResourcesRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<ListResourcesResult> {
+        match self {
+            McpClientAdapter::Legacy(client) => client.list_resources(params, timeout).await,
+            McpClientAdapter::Rmcp(client) => client.list_resources(params, timeout).await,
+        }
+    }
+
+    async fn list_resource_templates(
+        &self,
+        params: Option<ListResourceTemplatesRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<ListResourceTemplatesResult> {
+        match self {
+            McpClientAdapter::Legacy(client) => {
+                client.list_resource_templates(params, timeout).await
+            }
+            McpClientAdapter::Rmcp(client) => client.list_resource_templates(params, timeout).await,
+        }
+    }
+
+    async fn read_resource(
+        &self,
+        params: ReadResourceRequestParams,
+        timeout: Option<Duration>,
+    ) -> Result<ReadResourceResult> {
+        match self {
+            McpClientAdapter::Legacy(client) => client.read_resource(params, timeout).await,
+            McpClientAdapter::Rmcp(client) => client.read_resource(params, timeout).await,
+        }
+    }
+
+    async fn list_prompts(
+        &self,
+        params: Option<mcp_types::ListPromptsRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<mcp_types::ListPromptsResult> {
+        match self {
+            McpClientAdapter::Legacy(client) => client.list_prompts(params, timeout).await,
+            McpClientAdapter::Rmcp(client) => client.list_prompts(params, timeout).await,
+        }
+    }
+
+    async fn get_prompt(
+        &self,
+        params: GetPromptRequestParams,
+        timeout: Option<Duration>,
+    ) -> Result<GetPromptResult> {
+        match self {
+            McpClientAdapter::Legacy(client) => client.get_prompt(params, timeout).await,
+            McpClientAdapter::Rmcp(client) => client.get_prompt(params, timeout).await,
+        }
+    }
 }
 
 /// A thin wrapper around a set of running [`McpClient`] instances.
@@ -171,7 +317,7 @@ pub(crate) struct McpConnectionManager {
     clients: HashMap<String, ManagedClient>,
 
     /// Fully qualified tool name -> tool instance.
-    tools: HashMap<String, ToolInfo>,
+    tools: RwLock<HashMap<String, ToolInfo>>,
 }
 
 impl McpConnectionManager {
@@ -213,6 +359,7 @@ impl McpConnectionManager {
 
             let startup_timeout = cfg.startup_timeout_sec.unwrap_or(DEFAULT_STARTUP_TIMEOUT);
             let tool_timeout = cfg.tool_timeout_sec.unwrap_or(DEFAULT_TOOL_TIMEOUT);
+            let tool_policy = ToolPolicy::from_config(&cfg);
 
             let resolved_bearer_token = match &cfg.transport {
                 McpServerTransportConfig::StreamableHttp {
@@ -273,14 +420,14 @@ impl McpConnectionManager {
                 }
                 .map(|c| (c, startup_timeout));
 
-                ((server_name, tool_timeout), client)
+                ((server_name, tool_timeout, tool_policy), client)
             });
         }
 
         let mut clients: HashMap<String, ManagedClient> = HashMap::with_capacity(join_set.len());
 
         while let Some(res) = join_set.join_next().await {
-            let ((server_name, tool_timeout), client_res) = match res {
+            let ((server_name, tool_timeout, tool_policy), client_res) = match res {
                 Ok(result) => result,
                 Err(e) => {
                     warn!("Task panic when starting MCP server: {e:#}");
@@ -289,13 +436,15 @@ impl McpConnectionManager {
             };
 
             match client_res {
-                Ok((client, startup_timeout)) => {
+                Ok(((client, capabilities), startup_timeout)) => {
                     clients.insert(
                         server_name,
                         ManagedClient {
                             client,
+                            capabilities,
                             startup_timeout,
                             tool_timeout: Some(tool_timeout),
+                            tool_policy,
                         },
                     );
                 }
@@ -305,7 +454,7 @@ impl McpConnectionManager {
             }
         }
 
-        let all_tools = match list_all_tools(&clients).await {
+        let all_tools = match list_all_tools(clients.iter()).await {
             Ok(tools) => tools,
             Err(e) => {
                 warn!("Failed to list tools from some MCP servers: {e:#}");
@@ -313,7 +462,7 @@ impl McpConnectionManager {
             }
         };
 
-        let tools = qualify_tools(all_tools);
+        let tools = RwLock::new(qualify_tools(all_tools));
 
         Ok((Self { clients, tools }, errors))
     }
@@ -321,12 +470,69 @@ impl McpConnectionManager {
     /// Returns a single map that contains **all** tools. Each key is the
     /// fully-qualified name for the tool.
     pub fn list_all_tools(&self) -> HashMap<String, Tool> {
-        self.tools
+        let Ok(tools) = self.tools.read() else {
+            return HashMap::new();
+        };
+        tools
             .iter()
             .map(|(name, tool)| (name.clone(), tool.tool.clone()))
             .collect()
     }
 
+    /// Re-lists the tools of every server that sent
+    /// `notifications/tools/list_changed` since the previous call and swaps
+    /// them into the aggregated map. A server whose listing fails keeps its
+    /// previous tools and is retried on the next call. Returns the refreshed
+    /// server names, sorted; an empty list means nothing changed.
+    pub async fn refresh_changed_tools(&self) -> Vec<String> {
+        let changed: Vec<String> = self
+            .clients
+            .iter()
+            .filter(|(_, managed)| managed.client.take_tool_list_changed())
+            .map(|(server_name, _)| server_name.clone())
+            .collect();
+        if changed.is_empty() {
+            return changed;
+        }
+
+        let changed_clients = self
+            .clients
+            .iter()
+            .filter(|(server_name, _)| changed.contains(*server_name));
+        let mut refreshed: Vec<ToolInfo> = Vec::new();
+        let mut refreshed_servers: Vec<String> = Vec::new();
+        for (server_name, result) in list_tools_per_server(changed_clients).await {
+            match result {
+                Ok(tools) => {
+                    refreshed.extend(tools);
+                    refreshed_servers.push(server_name);
+                }
+                Err(e) => {
+                    // Keep the tools listed before and retry on the next call.
+                    warn!("Failed to refresh tools from MCP server '{server_name}': {e:#}");
+                    if let Some(managed) = self.clients.get(&server_name) {
+                        managed.client.mark_tool_list_changed();
+                    }
+                }
+            }
+        }
+        if refreshed_servers.is_empty() {
+            return refreshed_servers;
+        }
+        refreshed_servers.sort();
+
+        if let Ok(mut tools) = self.tools.write() {
+            let mut all_tools: Vec<ToolInfo> = std::mem::take(&mut *tools)
+                .into_values()
+                .filter(|tool| !refreshed_servers.contains(&tool.server_name))
+                .collect();
+            all_tools.extend(refreshed);
+            *tools = qualify_tools(all_tools);
+        }
+        info!("refreshed tools for MCP servers {refreshed_servers:?}");
+        refreshed_servers
+    }
+
     /// Invoke the tool indicated by the (server, tool) pair.
     pub async fn call_tool(
         &self,
@@ -338,6 +544,11 @@ impl McpConnectionManager {
             .clients
             .get(server)
             .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))?;
+        if !managed.tool_policy.allows(tool) {
+            return Err(anyhow!(
+                "tool '{tool}' is disabled for MCP server '{server}'"
+            ));
+        }
         let client = managed.client.clone();
         let timeout = managed.tool_timeout;
 
@@ -349,9 +560,203 @@ impl McpConnectionManager {
 
     pub fn parse_tool_name(&self, tool_name: &str) -> Option<(String, String)> {
         self.tools
+            .read()
+            .ok()?
             .get(tool_name)
             .map(|tool| (tool.server_name.clone(), tool.tool_name.clone()))
     }
+
+    /// Returns the configured approval mode for `tool` on `server`.
+    pub fn tool_approval_mode(&self, server: &str, tool: &str) -> McpToolApprovalMode {
+        self.clients
+            .get(server)
+            .map(|managed| managed.tool_policy.approval_mode(tool))
+            .unwrap_or_default()
+    }
+
+    /// Returns `true` when at least one server advertises the `resources`
+    /// capability.
+    pub fn has_resources(&self) -> bool {
+        self.clients
+            .values()
+            .any(|managed| managed.capabilities.resources.is_some())
+    }
+
+    /// Returns every resource listed by every server that supports
+    /// resources, following pagination cursors. Keys are server names.
+    pub async fn list_all_resources(&self) -> HashMap<String, Vec<Resource>> {
+        self.collect_paginated(
+            "resources/list",
+            |capabilities| capabilities.resources.is_some(),
+            |client, cursor, timeout| async move {
+                let params = cursor.map(|cursor| ListResourcesRequestParams {
+                    cursor: Some(cursor),
+                });
+                let result = client.list_resources(params, timeout).await?;
+                Ok((result.resources, result.next_cursor))
+            },
+        )
+        .await
+    }
+
+    /// Returns every resource template listed by every server that supports
+    /// resources, following pagination cursors. Keys are server names.
+    pub async fn list_all_resource_templates(&self) -> HashMap<String, Vec<ResourceTemplate>> {
+        self.collect_paginated(
+            "resources/templates/list",
+            |capabilities| capabilities.resources.is_some(),
+            |client, cursor, timeout| async move {
+                let params = cursor.map(|cursor| ListResourceTemplatesRequestParams {
+                    cursor: Some(cursor),
+                });
+                let result = client.list_resource_templates(params, timeout).await?;
+                Ok((result.resource_templates, result.next_cursor))
+            },
+        )
+        .await
+    }
+
+    /// Returns every prompt listed by every server that supports prompts,
+    /// following pagination cursors. Keys are server names.
+    pub async fn list_all_prompts(&self) -> HashMap<String, Vec<Prompt>> {
+        self.collect_paginated(
+            "prompts/list",
+            |capabilities| capabilities.prompts.is_some(),
+            |client, cursor, timeout| async move {
+                let params = cursor.map(|cursor| mcp_types::ListPromptsRequestParams {
+                    cursor: Some(cursor),
+                });
+                let result = client.list_prompts(params, timeout).await?;
+                Ok((result.prompts, result.next_cursor))
+            },
+        )
+        .await
+    }
+
+    /// Lists a single page of resources from one server.
+    pub async fn list_resources(
+        &self,
+        server: &str,
+        params: Option<ListResourcesRequestParams>,
+    ) -> Result<ListResourcesResult> {
+        let (client, timeout) = self.client(server)?;
+        client
+            .list_resources(params, timeout)
+            .await
+            .with_context(|| format!("resources/list failed for `{server}`"))
+    }
+
+    /// Lists a single page of resource templates from one server.
+    pub async fn list_resource_templates(
+        &self,
+        server: &str,
+        params: Option<ListResourceTemplatesRequestParams>,
+    ) -> Result<ListResourceTemplatesResult> {
+        let (client, timeout) = self.client(server)?;
+        client
+            .list_resource_templates(params, timeout)
+            .await
+            .with_context(|| format!("resources/templates/list failed for `{server}`"))
+    }
+
+    pub async fn read_resource(
+        &self,
+        server: &str,
+        params: ReadResourceRequestParams,
+    ) -> Result<ReadResourceResult> {
+        let (client, timeout) = self.client(server)?;
+        let uri = params.uri.clone();
+        client
+            .read_resource(params, timeout)
+            .await
+            .with_context(|| format!("resource read failed for `{server}/{uri}`"))
+    }
+
+    pub async fn get_prompt(
+        &self,
+        server: &str,
+        params: GetPromptRequestParams,
+    ) -> Result<GetPromptResult> {
+        let (client, timeout) = self.client(server)?;
+        let name = params.name.clone();
+        client
+            .get_prompt(params, timeout)
+            .await
+            .with_context(|| format!("prompt request failed for `{server}/{name}`"))
+    }
+
+    fn client(&self, server: &str) -> Result<(McpClientAdapter, Option<Duration>)> {
+        let managed = self
+            .clients
+            .get(server)
+            .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))?;
+        Ok((managed.client.clone(), managed.tool_timeout))
+    }
+
+    /// Queries every server whose capabilities pass `supports` concurrently,
+    /// following `nextCursor` until each server is exhausted. Servers that
+    /// fail are logged and left out of the result.
+    async fn collect_paginated<T, F, Fut>(
+        &self,
+        method: &'static str,
+        supports: impl Fn(&ServerCapabilities) -> bool,
+        fetch: F,
+    ) -> HashMap<String, Vec<T>>
+    where
+        T: Send + 'static,
+        F: Fn(McpClientAdapter, Option<String>, Option<Duration>) -> Fut
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        Fut: Future<Output = Result<(Vec<T>, Option<String>)>> + Send + 'static,
+    {
+        let mut join_set = JoinSet::new();
+        for (server_name, managed) in &self.clients {
+            if !supports(&managed.capabilities) {
+                continue;
+            }
+            let server_name = server_name.clone();
+            let client = managed.client.clone();
+            let timeout = managed.tool_timeout;
+            let fetch = fetch.clone();
+            join_set.spawn(async move {
+                let mut items = Vec::new();
+                let mut cursor: Option<String> = None;
+                let result = loop {
+                    match fetch(client.clone(), cursor.clone(), timeout).await {
+                        Ok((page, next_cursor)) => {
+                            items.extend(page);
+                            // Guard against servers that hand back the same
+                            // cursor forever.
+                            if next_cursor.is_none() || next_cursor == cursor {
+                                break Ok(items);
+                            }
+                            cursor = next_cursor;
+                        }
+                        Err(e) => break Err(e),
+                    }
+                };
+                (server_name, result)
+            });
+        }
+
+        let mut aggregated = HashMap::with_capacity(join_set.len());
+        while let Some(join_res) = join_set.join_next().await {
+            match join_res {
+                Ok((server_name, Ok(items))) => {
+                    aggregated.insert(server_name, items);
+                }
+                Ok((server_name, Err(e))) => {
+                    warn!("Failed to call {method} on MCP server '{server_name}': {e:#}");
+                }
+                Err(e) => {
+                    warn!("Task panic when calling {method} on MCP server: {e:#}");
+                }
+            }
+        }
+        aggregated
+    }
 }
 
 fn resolve_bearer_token(
@@ -381,16 +786,43 @@ fn resolve_bearer_token(
     }
 }
 
-/// Query every server for its available tools and return a single map that
-/// contains **all** tools. Each key is the fully-qualified name for the tool.
-async fn list_all_tools(clients: &HashMap<String, ManagedClient>) -> Result<Vec<ToolInfo>> {
+/// Query the given servers for their available tools and return them in a
+/// single list, leaving out tools hidden by each server's [`ToolPolicy`].
+async fn list_all_tools<'a>(
+    clients: impl IntoIterator<Item = (&'a String, &'a ManagedClient)>,
+) -> Result<Vec<ToolInfo>> {
+    let listed = list_tools_per_server(clients).await;
+    let server_count = listed.len();
+    let mut aggregated: Vec<ToolInfo> = Vec::new();
+    for (server_name, result) in listed {
+        match result {
+            Ok(tools) => aggregated.extend(tools),
+            Err(e) => warn!("Failed to list tools for MCP server '{server_name}': {e:#}"),
+        }
+    }
+
+    info!(
+        "aggregated {} tools from {} servers",
+        aggregated.len(),
+        server_count
+    );
+
+    Ok(aggregated)
+}
+
+/// Lists the tools of each server, keeping the servers whose listing failed
+/// so callers can tell them apart from servers that have no tools.
+async fn list_tools_per_server<'a>(
+    clients: impl IntoIterator<Item = (&'a String, &'a ManagedClient)>,
+) -> Vec<(String, Result<Vec<ToolInfo>>)> {
+    let clients: HashMap<&String, &ManagedClient> = clients.into_iter().collect();
     let mut join_set = JoinSet::new();
 
     // Spawn one task per server so we can query them concurrently. This
     // keeps the overall latency roughly at the slowest server instead of
     // the cumulative latency.
-    for (server_name, managed_client) in clients {
-        let server_name_cloned = server_name.clone();
+    for (server_name, managed_client) in &clients {
+        let server_name_cloned = (*server_name).clone();
         let client_clone = managed_client.client.clone();
         let startup_timeout = managed_client.startup_timeout;
         join_set.spawn(async move {
@@ -399,40 +831,49 @@ async fn list_all_tools(clients: &HashMap<String, ManagedClient>) -> Result<Vec<
         });
     }
 
-    let mut aggregated: Vec<ToolInfo> = Vec::with_capacity(join_set.len());
+    let mut listed = Vec::with_capacity(join_set.len());
 
     while let Some(join_res) = join_set.join_next().await {
-        let (server_name, list_result) = if let Ok(result) = join_res {
-            result
-        } else {
-            warn!("Task panic when listing tools for MCP server: {join_res:#?}");
-            continue;
+        let (server_name, list_result) = match join_res {
+            Ok(result) => result,
+            Err(e) => {
+                // The task owned the server name, so it cannot be reported
+                // as failed; its previous tools stay untouched.
+                warn!("Task panic when listing tools for MCP server: {e:#?}");
+                continue;
+            }
         };
 
-        let list_result = if let Ok(result) = list_result {
-            result
-        } else {
-            warn!("Failed to list tools for MCP server '{server_name}': {list_result:#?}");
-            continue;
+        let list_result = match list_result {
+            Ok(result) => result,
+            Err(e) => {
+                listed.push((server_name, Err(e)));
+                continue;
+            }
         };
 
+        let Some(managed_client) = clients.get(&server_name) else {
+            continue;
+        };
+        let mut tools = Vec::with_capacity(list_result.tools.len());
         for tool in list_result.tools {
-            let tool_info = ToolInfo {
+            if !managed_client.tool_policy.allows(&tool.name) {
+                info!(
+                    "MCP server '{server_name}' tool '{}' disabled by config",
+                    tool.name
+                );
+                continue;
+            }
+            tools.push(ToolInfo {
                 server_name: server_name.clone(),
                 tool_name: tool.name.clone(),
                 tool,
-            };
-            aggregated.push(tool_info);
+            });
         }
+        listed.push((server_name, Ok(tools)));
     }
 
-    info!(
-        "aggregated {} tools from {} servers",
-        aggregated.len(),
-        clients.len()
-    );
-
-    Ok(aggregated)
+    listed
 }
 
 fn is_valid_mcp_server_name(server_name: &str) -> bool {
@@ -458,6 +899,7 @@ mod tests {
                     properties: None,
                     required: None,
                     r#type: "object".to_string(),
+                    defs: None,
                 },
                 name: tool_name.to_string(),
                 output_schema: None,
@@ -528,4 +970,61 @@ mod tests {
             "my_server__yet_another_e1c3987bd9c50b826cbe1687966f79f0c602d19ca"
         );
     }
+
+    fn policy_config(
+        enabled_tools: Option<Vec<&str>>,
+        disabled_tools: Option<Vec<&str>>,
+    ) -> McpServerConfig {
+        let to_strings =
+            |patterns: Vec<&str>| patterns.into_iter().map(str::to_string).collect::<Vec<_>>();
+        McpServerConfig {
+            transport: McpServerTransportConfig::Stdio {
+                command: "echo".to_string(),
+                args: Vec::new(),
+                env: None,
+            },
+            enabled: true,
+            startup_timeout_sec: None,
+            tool_timeout_sec: None,
+            enabled_tools: enabled_tools.map(to_strings),
+            disabled_tools: disabled_tools.map(to_strings),
+            approval_mode: Some(McpToolApprovalMode::Always),
+            tool_approval_modes: HashMap::from([(
+                "search_web".to_string(),
+                McpToolApprovalMode::Never,
+            )]),
+        }
+    }
+
+    #[test]
+    fn tool_policy_allows_everything_without_filters() {
+        let policy = ToolPolicy::from_config(&policy_config(None, None));
+
+        assert!(policy.allows("search_web"));
+        assert!(policy.allows("delete_everything"));
+    }
+
+    #[test]
+    fn tool_policy_disabled_globs_win_over_enabled_globs() {
+        let policy = ToolPolicy::from_config(&policy_config(
+            Some(vec!["search_*", "fetch"]),
+            Some(vec!["search_adm?n"]),
+        ));
+
+        assert!(policy.allows("search_web"));
+        assert!(policy.allows("fetch"));
+        assert!(!policy.allows("search_admin"));
+        assert!(!policy.allows("delete_everything"));
+    }
+
+    #[test]
+    fn tool_policy_per_tool_approval_overrides_server_default() {
+        let policy = ToolPolicy::from_config(&policy_config(None, None));
+
+        assert_eq!(
+            policy.approval_mode("search_web"),
+            McpToolApprovalMode::Never
+        );
+        assert_eq!(policy.approval_mode("fetch"), McpToolApprovalMode::Always);
+    }
 }
@@ -0,0 +1,158 @@
+//! Conversions between MCP prompts (`prompts/list`, `prompts/get`) and the
+//! protocol types used to surface them as slash commands.
+
+use std::collections::HashMap;
+
+use codex_protocol::custom_prompts::McpPrompt;
+use codex_protocol::custom_prompts::McpPromptArgument;
+use mcp_types::ContentBlock;
+use mcp_types::EmbeddedResourceResource;
+use mcp_types::GetPromptResult;
+use mcp_types::Prompt;
+
+use crate::protocol::InputItem;
+
+/// Flattens the per-server prompt listing into protocol prompts sorted by
+/// server and prompt name.
+pub(crate) fn to_protocol_prompts(prompts: HashMap<String, Vec<Prompt>>) -> Vec<McpPrompt> {
+    let mut out: Vec<McpPrompt> = prompts
+        .into_iter()
+        .flat_map(|(server, prompts)| {
+            prompts.into_iter().map(move |prompt| McpPrompt {
+                server: server.clone(),
+                name: prompt.name,
+                title: prompt.title,
+                description: prompt.description,
+                arguments: prompt
+                    .arguments
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|argument| McpPromptArgument {
+                        name: argument.name,
+                        description: argument.description,
+                        required: argument.required.unwrap_or(false),
+                    })
+                    .collect(),
+            })
+        })
+        .collect();
+    out.sort_by(|a, b| (&a.server, &a.name).cmp(&(&b.server, &b.name)));
+    out
+}
+
+/// Converts the messages returned by `prompts/get` into user input. Text and
+/// embedded text resources are joined into a single text item; images are
+/// attached as data URLs. Audio, blobs and resource links are skipped.
+pub(crate) fn prompt_result_to_input(result: GetPromptResult) -> Vec<InputItem> {
+    let mut texts: Vec<String> = Vec::new();
+    let mut images: Vec<InputItem> = Vec::new();
+    for message in result.messages {
+        match message.content {
+            ContentBlock::TextContent(content) => texts.push(content.text),
+            ContentBlock::EmbeddedResource(embedded) => {
+                if let EmbeddedResourceResource::TextResourceContents(resource) = embedded.resource
+                {
+                    texts.push(resource.text);
+                }
+            }
+            ContentBlock::ImageContent(image) => images.push(InputItem::Image {
+                image_url: format!("data:{};base64,{}", image.mime_type, image.data),
+            }),
+            ContentBlock::AudioContent(_) | ContentBlock::ResourceLink(_) => {}
+        }
+    }
+
+    let mut items = Vec::with_capacity(images.len() + 1);
+    if !texts.is_empty() {
+        items.push(InputItem::Text {
+            text: texts.join("\n\n"),
+        });
+    }
+    items.extend(images);
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_types::PromptArgument;
+    use mcp_types::PromptMessage;
+    use mcp_types::Role;
+    use mcp_types::TextContent;
+    use pretty_assertions::assert_eq;
+
+    fn text_message(text: &str) -> PromptMessage {
+        PromptMessage {
+            content: ContentBlock::TextContent(TextContent {
+                annotations: None,
+                text: text.to_string(),
+                r#type: "text".to_string(),
+            }),
+            role: Role::User,
+        }
+    }
+
+    #[test]
+    fn prompts_are_sorted_and_arguments_default_to_optional() {
+        let prompts = HashMap::from([
+            (
+                "tracker".to_string(),
+                vec![Prompt {
+                    arguments: Some(vec![PromptArgument {
+                        description: Some("Issue key".to_string()),
+                        name: "issue".to_string(),
+                        required: None,
+                        title: None,
+                    }]),
+                    description: Some("Summarize an issue".to_string()),
+                    name: "summarize".to_string(),
+                    title: None,
+                }],
+            ),
+            (
+                "docs".to_string(),
+                vec![Prompt {
+                    arguments: None,
+                    description: None,
+                    name: "style-guide".to_string(),
+                    title: Some("Style guide".to_string()),
+                }],
+            ),
+        ]);
+
+        let converted = to_protocol_prompts(prompts);
+
+        assert_eq!(
+            converted
+                .iter()
+                .map(McpPrompt::command_name)
+                .collect::<Vec<_>>(),
+            vec!["mcp:docs:style-guide", "mcp:tracker:summarize"]
+        );
+        assert_eq!(
+            converted[1].arguments,
+            vec![McpPromptArgument {
+                name: "issue".to_string(),
+                description: Some("Issue key".to_string()),
+                required: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn prompt_messages_are_joined_into_one_text_item() {
+        let result = GetPromptResult {
+            description: None,
+            messages: vec![text_message("First"), text_message("Second")],
+        };
+
+        let items = prompt_result_to_input(result);
+
+        assert_eq!(
+            items,
+            vec![InputItem::Text {
+                text: "First\n\nSecond".to_string(),
+            }]
+        );
+    }
+}
@@ -1,16 +1,110 @@
 use std::time::Instant;
 
+use codex_otel::otel_event_manager::ToolDecisionSource;
 use tracing::error;
 
 use crate::codex::Session;
+use crate::config_types::McpToolApprovalMode;
+use crate::protocol::AskForApproval;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::McpInvocation;
 use crate::protocol::McpToolCallBeginEvent;
 use crate::protocol::McpToolCallEndEvent;
+use crate::protocol::ReviewDecision;
+use crate::tools::context::ToolInvocation;
 use codex_protocol::models::FunctionCallOutputPayload;
 use codex_protocol::models::ResponseInputItem;
 
+/// Checks the configured approval mode for an MCP tool call and, when
+/// required, asks the user through the same `ExecApprovalRequest` flow used
+/// for shell commands. Returns the message to send back to the model when the
+/// call must not run.
+pub(crate) async fn check_mcp_tool_approval(
+    invocation: &ToolInvocation,
+    server: &str,
+    tool: &str,
+    arguments: &str,
+) -> Result<(), String> {
+    let sess = invocation.session.as_ref();
+    let turn = invocation.turn.as_ref();
+    let call_id = invocation.call_id.as_str();
+    let tool_name = invocation.tool_name.as_str();
+    let otel_event_manager = turn.client.get_otel_event_manager();
+    let mode = sess
+        .mcp_connection_manager()
+        .tool_approval_mode(server, tool);
+    if !requires_approval(mode, turn.approval_policy) {
+        otel_event_manager.tool_decision(
+            tool_name,
+            call_id,
+            ReviewDecision::Approved,
+            ToolDecisionSource::Config,
+        );
+        return Ok(());
+    }
+
+    if sess.is_mcp_tool_approved_for_session(server, tool).await {
+        otel_event_manager.tool_decision(
+            tool_name,
+            call_id,
+            ReviewDecision::ApprovedForSession,
+            ToolDecisionSource::User,
+        );
+        return Ok(());
+    }
+
+    if turn.approval_policy == AskForApproval::Never {
+        otel_event_manager.tool_decision(
+            tool_name,
+            call_id,
+            ReviewDecision::Denied,
+            ToolDecisionSource::Config,
+        );
+        return Err(format!(
+            "MCP tool `{tool}` on server `{server}` requires approval, but the approval policy is `never`"
+        ));
+    }
+
+    let mut command = vec!["mcp".to_string(), server.to_string(), tool.to_string()];
+    if !arguments.trim().is_empty() {
+        command.push(arguments.to_string());
+    }
+    let decision = sess
+        .request_command_approval(
+            invocation.sub_id.clone(),
+            call_id.to_string(),
+            command,
+            turn.cwd.clone(),
+            Some(format!("Allow MCP server `{server}` to run tool `{tool}`?")),
+            // Approvals of MCP tools only last for the session.
+            None,
+        )
+        .await;
+    otel_event_manager.tool_decision(tool_name, call_id, decision, ToolDecisionSource::User);
+
+    match decision {
+        // Project approvals are not offered for MCP tools, so one that
+        // arrives anyway only approves this call.
+        ReviewDecision::Approved | ReviewDecision::ApprovedForProject => Ok(()),
+        ReviewDecision::ApprovedForSession => {
+            sess.approve_mcp_tool_for_session(server, tool).await;
+            Ok(())
+        }
+        ReviewDecision::Denied | ReviewDecision::Abort => {
+            Err(format!("MCP tool call `{server}/{tool}` rejected by user"))
+        }
+    }
+}
+
+fn requires_approval(mode: McpToolApprovalMode, approval_policy: AskForApproval) -> bool {
+    match mode {
+        McpToolApprovalMode::Always => true,
+        McpToolApprovalMode::Never => false,
+        McpToolApprovalMode::OnRequest => approval_policy == AskForApproval::UnlessTrusted,
+    }
+}
+
 /// Handles the specified tool call dispatches the appropriate
 /// `McpToolCallBegin` and `McpToolCallEnd` events to the `Session`.
 pub(crate) async fn handle_mcp_tool_call(
@@ -78,3 +172,30 @@ async fn notify_mcp_tool_call_event(sess: &Session, sub_id: &str, event: EventMs
     })
     .await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn on_request_defers_to_approval_policy() {
+        let mode = McpToolApprovalMode::OnRequest;
+        assert!(requires_approval(mode, AskForApproval::UnlessTrusted));
+        assert!(!requires_approval(mode, AskForApproval::OnFailure));
+        assert!(!requires_approval(mode, AskForApproval::OnRequest));
+        assert!(!requires_approval(mode, AskForApproval::Never));
+    }
+
+    #[test]
+    fn explicit_modes_ignore_approval_policy() {
+        for policy in [
+            AskForApproval::UnlessTrusted,
+            AskForApproval::OnFailure,
+            AskForApproval::OnRequest,
+            AskForApproval::Never,
+        ] {
+            assert!(requires_approval(McpToolApprovalMode::Always, policy));
+            assert!(!requires_approval(McpToolApprovalMode::Never, policy));
+        }
+    }
+}
@@ -0,0 +1,844 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::ModelProviderInfo;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::error::RetryLimitReachedError;
+use crate::error::UnexpectedResponseError;
+use crate::model_family::ModelFamily;
+use crate::openai_tools::create_tools_json_for_messages_api;
+use crate::protocol::TokenUsage;
+use crate::util::backoff;
+use bytes::Bytes;
+use codex_otel::otel_event_manager::OtelEventManager;
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::FunctionCallOutputPayload;
+use codex_protocol::models::ReasoningItemReasoningSummary;
+use codex_protocol::models::ResponseItem;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+
+const ANTHROPIC_VERSION_HEADER: &str = "anthropic-version";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// `max_tokens` is required by the Messages API; used when the model's output
+/// limit is unknown.
+const DEFAULT_MAX_TOKENS: u64 = 8_192;
+
+/// Implementation for the Anthropic Messages API.
+pub(crate) async fn stream_messages(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    max_output_tokens: Option<u64>,
+    effort: Option<ReasoningEffortConfig>,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+    otel_event_manager: &OtelEventManager,
+) -> Result<ResponseStream> {
+    if prompt.output_schema.is_some() {
+        return Err(CodexErr::UnsupportedOperation(
+            "output_schema is not supported for Messages API".to_string(),
+        ));
+    }
+
+    let payload = build_messages_payload(prompt, model_family, max_output_tokens, effort)?;
+
+    debug!(
+        "POST to {}: {}",
+        provider.get_full_url(&None),
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+
+    let has_version_header = provider.http_headers.as_ref().is_some_and(|headers| {
+        headers
+            .keys()
+            .any(|name| name.eq_ignore_ascii_case(ANTHROPIC_VERSION_HEADER))
+    });
+
+    let mut attempt = 0;
+    let max_retries = provider.request_max_retries();
+    loop {
+        attempt += 1;
+
+        let mut req_builder = provider.create_request_builder(client, &None).await?;
+        if !has_version_header {
+            req_builder = req_builder.header(ANTHROPIC_VERSION_HEADER, ANTHROPIC_VERSION);
+        }
+
+        let res = otel_event_manager
+            .log_request(attempt, || {
+                req_builder
+                    .header(reqwest::header::ACCEPT, "text/event-stream")
+                    .json(&payload)
+                    .send()
+            })
+            .await;
+
+        match res {
+            Ok(resp) if resp.status().is_success() => {
+                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+                let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+                tokio::spawn(process_messages_sse(
+                    stream,
+                    tx_event,
+                    provider.stream_idle_timeout(),
+                    otel_event_manager.clone(),
+                ));
+                return Ok(ResponseStream { rx_event });
+            }
+            Ok(res) => {
+                let status = res.status();
+                // 529 is Anthropic's "overloaded" status.
+                let retryable = status == StatusCode::TOO_MANY_REQUESTS
+                    || status.is_server_error()
+                    || status.as_u16() == 529;
+                if !retryable {
+                    let body = (res.text().await).unwrap_or_default();
+                    return Err(CodexErr::UnexpectedStatus(UnexpectedResponseError {
+                        status,
+                        body,
+                        request_id: None,
+                    }));
+                }
+
+                if attempt > max_retries {
+                    return Err(CodexErr::RetryLimit(RetryLimitReachedError {
+                        status,
+                        request_id: None,
+                    }));
+                }
+
+                let retry_after_secs = res
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok());
+
+                let delay = retry_after_secs
+                    .map(|s| Duration::from_millis(s * 1_000))
+                    .unwrap_or_else(|| backoff(attempt));
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                if attempt > max_retries {
+                    return Err(e.into());
+                }
+                let delay = backoff(attempt);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+fn build_messages_payload(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    max_output_tokens: Option<u64>,
+    effort: Option<ReasoningEffortConfig>,
+) -> Result<Value> {
+    let full_instructions = prompt.get_full_instructions(model_family);
+    let mut messages = messages_from_input(&prompt.get_formatted_input());
+
+    // Cache the conversation prefix: the instructions and tools are marked
+    // below, and the final block here so the next request can reuse the
+    // whole history.
+    if let Some(block) = messages
+        .last_mut()
+        .and_then(|message| message.get_mut("content"))
+        .and_then(Value::as_array_mut)
+        .and_then(|content| content.last_mut())
+        .and_then(Value::as_object_mut)
+    {
+        block.insert("cache_control".to_string(), ephemeral_cache_control());
+    }
+
+    let mut tools = create_tools_json_for_messages_api(&prompt.tools)?;
+    if let Some(tool) = tools.last_mut().and_then(Value::as_object_mut) {
+        tool.insert("cache_control".to_string(), ephemeral_cache_control());
+    }
+
+    let thinking_budget = effort
+        .and_then(thinking_budget_tokens)
+        .filter(|_| can_enable_thinking(&messages));
+    let mut max_tokens = max_output_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+    // The thinking budget counts towards `max_tokens` and must stay below it.
+    if let Some(budget) = thinking_budget
+        && budget >= max_tokens
+    {
+        max_tokens = budget + DEFAULT_MAX_TOKENS;
+    }
+
+    let mut payload = json!({
+        "model": model_family.slug,
+        "max_tokens": max_tokens,
+        "system": [{
+            "type": "text",
+            "text": full_instructions,
+            "cache_control": ephemeral_cache_control(),
+        }],
+        "messages": messages,
+        "stream": true,
+    });
+
+    if !tools.is_empty() {
+        payload["tools"] = Value::Array(tools);
+        payload["tool_choice"] = json!({
+            "type": "auto",
+            "disable_parallel_tool_use": !prompt.parallel_tool_calls,
+        });
+    }
+
+    if let Some(budget) = thinking_budget {
+        payload["thinking"] = json!({
+            "type": "enabled",
+            "budget_tokens": budget,
+        });
+    }
+
+    Ok(payload)
+}
+
+fn ephemeral_cache_control() -> Value {
+    json!({ "type": "ephemeral" })
+}
+
+/// Extended thinking budget for a reasoning effort. `minimal` disables
+/// thinking, as the Messages API requires a budget of at least 1024 tokens.
+fn thinking_budget_tokens(effort: ReasoningEffortConfig) -> Option<u64> {
+    match effort {
+        ReasoningEffortConfig::Minimal => None,
+        ReasoningEffortConfig::Low => Some(4_096),
+        ReasoningEffortConfig::Medium => Some(10_000),
+        ReasoningEffortConfig::High => Some(24_000),
+    }
+}
+
+/// With thinking enabled, an assistant turn that is waiting on tool results
+/// must start with a thinking block. That is not the case when the turn began
+/// on another model, e.g. before a model fallback, so thinking stays off until
+/// the next user message.
+fn can_enable_thinking(messages: &[Value]) -> bool {
+    let [.., assistant, last] = messages else {
+        return true;
+    };
+    let awaiting_tool_results = last["role"] == "user"
+        && last["content"]
+            .as_array()
+            .is_some_and(|content| content.iter().any(|block| block["type"] == "tool_result"));
+    if !awaiting_tool_results {
+        return true;
+    }
+    matches!(
+        assistant["content"][0]["type"].as_str(),
+        Some("thinking" | "redacted_thinking")
+    )
+}
+
+/// Converts the conversation history into Messages API `messages`. Tool calls
+/// become `tool_use` blocks on assistant turns and their outputs become
+/// `tool_result` blocks on user turns; consecutive blocks with the same role
+/// are merged into one message, as the API requires roles to alternate.
+fn messages_from_input(input: &[ResponseItem]) -> Vec<Value> {
+    // Thinking blocks only need to be sent back for the turn in progress,
+    // i.e. after the last user message.
+    let last_user_index = input
+        .iter()
+        .rposition(|item| matches!(item, ResponseItem::Message { role, .. } if role == "user"));
+
+    let mut messages: Vec<Value> = Vec::new();
+    for (idx, item) in input.iter().enumerate() {
+        let (role, blocks) = match item {
+            ResponseItem::Message { role, content, .. } => {
+                let role = if role == "assistant" {
+                    "assistant"
+                } else {
+                    "user"
+                };
+                (
+                    role,
+                    content.iter().filter_map(content_block).collect::<Vec<_>>(),
+                )
+            }
+            ResponseItem::Reasoning {
+                summary,
+                encrypted_content: Some(signature),
+                ..
+            } if last_user_index.is_none_or(|last| idx > last) => {
+                let thinking = summary
+                    .iter()
+                    .map(|ReasoningItemReasoningSummary::SummaryText { text }| text.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+                let block = if thinking.is_empty() {
+                    json!({ "type": "redacted_thinking", "data": signature })
+                } else {
+                    json!({ "type": "thinking", "thinking": thinking, "signature": signature })
+                };
+                ("assistant", vec![block])
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => (
+                "assistant",
+                vec![tool_use_block(call_id, name, tool_input(arguments))],
+            ),
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                action,
+                ..
+            } => {
+                let Some(call_id) = call_id.as_ref().or(id.as_ref()) else {
+                    continue;
+                };
+                let input = serde_json::to_value(action).unwrap_or_else(|_| json!({}));
+                (
+                    "assistant",
+                    vec![tool_use_block(call_id, "local_shell", input)],
+                )
+            }
+            ResponseItem::CustomToolCall {
+                call_id,
+                name,
+                input,
+                ..
+            } => (
+                "assistant",
+                vec![tool_use_block(call_id, name, json!({ "input": input }))],
+            ),
+            ResponseItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload { content, success },
+            } => {
+                let mut block = json!({
+                    "type": "tool_result",
+                    "tool_use_id": call_id,
+                    "content": content,
+                });
+                if *success == Some(false) {
+                    block["is_error"] = Value::Bool(true);
+                }
+                ("user", vec![block])
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => (
+                "user",
+                vec![json!({
+                    "type": "tool_result",
+                    "tool_use_id": call_id,
+                    "content": output,
+                })],
+            ),
+            ResponseItem::Reasoning { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::Other => continue,
+        };
+        push_blocks(&mut messages, role, blocks);
+    }
+    messages
+}
+
+fn push_blocks(messages: &mut Vec<Value>, role: &str, blocks: Vec<Value>) {
+    if blocks.is_empty() {
+        return;
+    }
+    if let Some(last) = messages.last_mut()
+        && last["role"] == role
+        && let Some(content) = last["content"].as_array_mut()
+    {
+        content.extend(blocks);
+        return;
+    }
+    messages.push(json!({ "role": role, "content": blocks }));
+}
+
+fn content_block(item: &ContentItem) -> Option<Value> {
+    match item {
+        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+            // The API rejects empty text blocks.
+            (!text.is_empty()).then(|| json!({ "type": "text", "text": text }))
+        }
+        ContentItem::InputImage { image_url } => {
+            // Local images are inlined as `data:<media type>;base64,<data>`.
+            let source = match image_url
+                .strip_prefix("data:")
+                .and_then(|rest| rest.split_once(";base64,"))
+            {
+                Some((media_type, data)) => json!({
+                    "type": "base64",
+                    "media_type": media_type,
+                    "data": data,
+                }),
+                None => json!({ "type": "url", "url": image_url }),
+            };
+            Some(json!({ "type": "image", "source": source }))
+        }
+    }
+}
+
+fn tool_use_block(id: &str, name: &str, input: Value) -> Value {
+    json!({
+        "type": "tool_use",
+        "id": id,
+        "name": name,
+        "input": input,
+    })
+}
+
+/// `tool_use.input` must be an object; function call arguments are a JSON
+/// string that may be empty.
+fn tool_input(arguments: &str) -> Value {
+    serde_json::from_str::<Value>(arguments)
+        .ok()
+        .filter(Value::is_object)
+        .unwrap_or_else(|| json!({}))
+}
+
+/// A content block being streamed, keyed by its `index` in the message.
+enum ContentBlockState {
+    Text(String),
+    Thinking {
+        thinking: String,
+        signature: String,
+    },
+    RedactedThinking(String),
+    ToolUse {
+        id: String,
+        name: String,
+        input_json: String,
+    },
+}
+
+impl ContentBlockState {
+    fn into_response_item(self) -> Option<ResponseItem> {
+        match self {
+            ContentBlockState::Text(text) => (!text.is_empty()).then(|| ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText { text }],
+            }),
+            ContentBlockState::Thinking {
+                thinking,
+                signature,
+            } => Some(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: vec![ReasoningItemReasoningSummary::SummaryText { text: thinking }],
+                content: None,
+                encrypted_content: Some(signature),
+            }),
+            ContentBlockState::RedactedThinking(data) => Some(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: None,
+                encrypted_content: Some(data),
+            }),
+            ContentBlockState::ToolUse {
+                id,
+                name,
+                input_json,
+            } => Some(ResponseItem::FunctionCall {
+                id: None,
+                name,
+                arguments: if input_json.trim().is_empty() {
+                    "{}".to_string()
+                } else {
+                    input_json
+                },
+                call_id: id,
+            }),
+        }
+    }
+}
+
+/// Token usage as reported by `message_start` (input) and `message_delta`
+/// (cumulative output).
+#[derive(Default)]
+struct MessagesUsage {
+    input_tokens: u64,
+    cache_creation_input_tokens: u64,
+    cache_read_input_tokens: u64,
+    output_tokens: u64,
+}
+
+impl MessagesUsage {
+    fn update(&mut self, usage: &Value) {
+        for (field, value) in [
+            ("input_tokens", &mut self.input_tokens),
+            (
+                "cache_creation_input_tokens",
+                &mut self.cache_creation_input_tokens,
+            ),
+            ("cache_read_input_tokens", &mut self.cache_read_input_tokens),
+            ("output_tokens", &mut self.output_tokens),
+        ] {
+            if let Some(tokens) = usage.get(field).and_then(Value::as_u64) {
+                *value = tokens;
+            }
+        }
+    }
+}
+
+impl From<MessagesUsage> for TokenUsage {
+    fn from(usage: MessagesUsage) -> Self {
+        // Anthropic reports cached tokens separately from `input_tokens`,
+        // while `TokenUsage::input_tokens` includes them.
+        let input_tokens =
+            usage.input_tokens + usage.cache_creation_input_tokens + usage.cache_read_input_tokens;
+        TokenUsage {
+            input_tokens,
+            cached_input_tokens: usage.cache_read_input_tokens,
+            output_tokens: usage.output_tokens,
+            reasoning_output_tokens: 0,
+            total_tokens: input_tokens + usage.output_tokens,
+        }
+    }
+}
+
+/// SSE processor for the Messages streaming format. Text and thinking deltas
+/// are forwarded as they arrive and every finished content block becomes a
+/// [`ResponseEvent::OutputItemDone`], matching the Responses API.
+async fn process_messages_sse<S>(
+    stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+    otel_event_manager: OtelEventManager,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut stream = stream.eventsource();
+
+    let mut blocks: HashMap<u64, ContentBlockState> = HashMap::new();
+    let mut response_id = String::new();
+    let mut usage = MessagesUsage::default();
+
+    loop {
+        let start = std::time::Instant::now();
+        let response = timeout(idle_timeout, stream.next()).await;
+        let duration = start.elapsed();
+        otel_event_manager.log_sse_event(&response, duration);
+
+        let sse = match response {
+            Ok(Some(Ok(ev))) => ev,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(e.to_string(), None)))
+                    .await;
+                return;
+            }
+            Ok(None) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "stream closed before message_stop".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "idle timeout waiting for SSE".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+        };
+
+        trace!("SSE event: {}", sse.data);
+
+        let data: Value = if sse.data.trim().is_empty() {
+            Value::Null
+        } else {
+            match serde_json::from_str(&sse.data) {
+                Ok(v) => v,
+                Err(_) => continue,
+            }
+        };
+        let kind = data
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or(sse.event.as_str());
+        let index = data.get("index").and_then(Value::as_u64);
+
+        match kind {
+            "message_start" => {
+                if let Some(message) = data.get("message") {
+                    if let Some(id) = message.get("id").and_then(Value::as_str) {
+                        response_id = id.to_string();
+                    }
+                    if let Some(message_usage) = message.get("usage") {
+                        usage.update(message_usage);
+                    }
+                }
+                let _ = tx_event.send(Ok(ResponseEvent::Created)).await;
+            }
+            "content_block_start" => {
+                let (Some(index), Some(block)) = (index, data.get("content_block")) else {
+                    continue;
+                };
+                let field = |name: &str| {
+                    block
+                        .get(name)
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                let state = match block.get("type").and_then(Value::as_str) {
+                    Some("text") => ContentBlockState::Text(field("text")),
+                    Some("thinking") => ContentBlockState::Thinking {
+                        thinking: field("thinking"),
+                        signature: field("signature"),
+                    },
+                    Some("redacted_thinking") => ContentBlockState::RedactedThinking(field("data")),
+                    Some("tool_use") => ContentBlockState::ToolUse {
+                        id: field("id"),
+                        name: field("name"),
+                        input_json: String::new(),
+                    },
+                    _ => continue,
+                };
+                blocks.insert(index, state);
+            }
+            "content_block_delta" => {
+                let (Some(state), Some(delta)) = (
+                    index.and_then(|index| blocks.get_mut(&index)),
+                    data.get("delta"),
+                ) else {
+                    continue;
+                };
+                let delta_type = delta.get("type").and_then(Value::as_str);
+                let delta_text = |name: &str| delta.get(name).and_then(Value::as_str);
+                match (state, delta_type) {
+                    (ContentBlockState::Text(text), Some("text_delta")) => {
+                        if let Some(chunk) = delta_text("text") {
+                            text.push_str(chunk);
+                            let _ = tx_event
+                                .send(Ok(ResponseEvent::OutputTextDelta(chunk.to_string())))
+                                .await;
+                        }
+                    }
+                    (ContentBlockState::Thinking { thinking, .. }, Some("thinking_delta")) => {
+                        if let Some(chunk) = delta_text("thinking") {
+                            thinking.push_str(chunk);
+                            let _ = tx_event
+                                .send(Ok(ResponseEvent::ReasoningSummaryDelta(chunk.to_string())))
+                                .await;
+                        }
+                    }
+                    (ContentBlockState::Thinking { signature, .. }, Some("signature_delta")) => {
+                        if let Some(chunk) = delta_text("signature") {
+                            signature.push_str(chunk);
+                        }
+                    }
+                    (ContentBlockState::ToolUse { input_json, .. }, Some("input_json_delta")) => {
+                        if let Some(chunk) = delta_text("partial_json") {
+                            input_json.push_str(chunk);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            "content_block_stop" => {
+                if let Some(item) = index
+                    .and_then(|index| blocks.remove(&index))
+                    .and_then(ContentBlockState::into_response_item)
+                {
+                    let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+                }
+            }
+            "message_delta" => {
+                if let Some(delta_usage) = data.get("usage") {
+                    usage.update(delta_usage);
+                }
+            }
+            "message_stop" => {
+                let _ = tx_event
+                    .send(Ok(ResponseEvent::Completed {
+                        response_id,
+                        token_usage: Some(usage.into()),
+                    }))
+                    .await;
+                return;
+            }
+            "error" => {
+                let message = data
+                    .get("error")
+                    .and_then(|error| error.get("message"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error")
+                    .to_string();
+                let _ = tx_event.send(Err(CodexErr::Stream(message, None))).await;
+                return;
+            }
+            // `ping` and unknown events.
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn message(role: &str, text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: role.to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    fn reasoning(text: &str, signature: &str) -> ResponseItem {
+        ResponseItem::Reasoning {
+            id: String::new(),
+            summary: vec![ReasoningItemReasoningSummary::SummaryText {
+                text: text.to_string(),
+            }],
+            content: None,
+            encrypted_content: Some(signature.to_string()),
+        }
+    }
+
+    #[test]
+    fn history_maps_tool_calls_and_keeps_only_current_thinking() {
+        let input = vec![
+            message("user", "first"),
+            reasoning("old thought", "sig-old"),
+            message("assistant", "done"),
+            message("user", "second"),
+            reasoning("new thought", "sig-new"),
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["ls"]}"#.to_string(),
+                call_id: "toolu_1".to_string(),
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "toolu_1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "failed".to_string(),
+                    success: Some(false),
+                },
+            },
+            ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![ContentItem::InputImage {
+                    image_url: "data:image/png;base64,AAAA".to_string(),
+                }],
+            },
+        ];
+
+        assert_eq!(
+            messages_from_input(&input),
+            vec![
+                json!({ "role": "user", "content": [{ "type": "text", "text": "first" }] }),
+                json!({ "role": "assistant", "content": [{ "type": "text", "text": "done" }] }),
+                json!({ "role": "user", "content": [{ "type": "text", "text": "second" }] }),
+                json!({
+                    "role": "assistant",
+                    "content": [
+                        { "type": "thinking", "thinking": "new thought", "signature": "sig-new" },
+                        {
+                            "type": "tool_use",
+                            "id": "toolu_1",
+                            "name": "shell",
+                            "input": { "command": ["ls"] }
+                        }
+                    ]
+                }),
+                json!({
+                    "role": "user",
+                    "content": [
+                        {
+                            "type": "tool_result",
+                            "tool_use_id": "toolu_1",
+                            "content": "failed",
+                            "is_error": true
+                        },
+                        {
+                            "type": "image",
+                            "source": { "type": "base64", "media_type": "image/png", "data": "AAAA" }
+                        }
+                    ]
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn thinking_requires_a_thinking_block_before_pending_tool_results() {
+        let tool_call = ResponseItem::FunctionCall {
+            id: None,
+            name: "shell".to_string(),
+            arguments: "{}".to_string(),
+            call_id: "toolu_1".to_string(),
+        };
+        let tool_output = ResponseItem::FunctionCallOutput {
+            call_id: "toolu_1".to_string(),
+            output: FunctionCallOutputPayload {
+                content: "ok".to_string(),
+                success: Some(true),
+            },
+        };
+
+        let with_thinking = messages_from_input(&[
+            message("user", "go"),
+            reasoning("thought", "sig"),
+            tool_call.clone(),
+            tool_output.clone(),
+        ]);
+        assert!(can_enable_thinking(&with_thinking));
+
+        let without_thinking =
+            messages_from_input(&[message("user", "go"), tool_call, tool_output]);
+        assert!(!can_enable_thinking(&without_thinking));
+
+        assert!(can_enable_thinking(&messages_from_input(&[message(
+            "user", "hi"
+        )])));
+    }
+
+    #[test]
+    fn usage_counts_cached_input_tokens() {
+        let mut usage = MessagesUsage::default();
+        usage.update(&json!({
+            "input_tokens": 10,
+            "cache_creation_input_tokens": 20,
+            "cache_read_input_tokens": 30,
+            "output_tokens": 1
+        }));
+        usage.update(&json!({ "output_tokens": 5 }));
+
+        let usage = TokenUsage::from(usage);
+        assert_eq!(
+            (
+                usage.input_tokens,
+                usage.cached_input_tokens,
+                usage.output_tokens,
+                usage.total_tokens
+            ),
+            (60, 30, 5, 65)
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! Failover to a profile's `model_fallbacks` when a turn keeps failing.
+//!
+//! A turn fails over only once the provider's own request and stream retries
+//! are exhausted, and only for the error classes listed in `fallback_on`. The
+//! fallback keeps serving the rest of the task; the next task starts on the
+//! configured model again.
+
+use codex_protocol::config_types::FallbackErrorClass;
+use codex_protocol::protocol::ModelFallbackItem;
+use reqwest::StatusCode;
+
+use crate::config::Config;
+use crate::config_types::ModelFallback;
+use crate::error::CodexErr;
+use crate::error::RetryLimitReachedError;
+use crate::error::UnexpectedResponseError;
+use crate::model_family::derive_default_model_family;
+use crate::model_family::find_family_for_model;
+use crate::openai_model_info::get_model_info;
+
+/// Classifies an error that ended a turn, or returns `None` when no fallback
+/// can help (e.g. an interrupt or a bad request).
+pub(crate) fn classify_error(err: &CodexErr) -> Option<FallbackErrorClass> {
+    match err {
+        CodexErr::RetryLimit(RetryLimitReachedError { status, .. })
+        | CodexErr::UnexpectedStatus(UnexpectedResponseError { status, .. }) => {
+            if *status == StatusCode::TOO_MANY_REQUESTS {
+                Some(FallbackErrorClass::RateLimit)
+            } else if status.is_server_error() {
+                Some(FallbackErrorClass::ServerError)
+            } else {
+                None
+            }
+        }
+        CodexErr::InternalServerError => Some(FallbackErrorClass::ServerError),
+        CodexErr::Stream(..) | CodexErr::Reqwest(_) => Some(FallbackErrorClass::Stream),
+        CodexErr::UsageLimitReached(_) | CodexErr::UsageNotIncluded => {
+            Some(FallbackErrorClass::UsageLimit)
+        }
+        CodexErr::ContextWindowExceeded => Some(FallbackErrorClass::ContextWindow),
+        _ => None,
+    }
+}
+
+/// Returns the index and entry of the fallback that follows `current`, the
+/// fallback the turn runs on, or of the first fallback when the turn runs the
+/// configured model. The index is tracked rather than looked up by model name
+/// so that a chain repeating a model, or listing the configured one, still
+/// advances.
+pub(crate) fn next_fallback<'a>(
+    config: &'a Config,
+    current: Option<&ModelFallbackItem>,
+) -> Option<(usize, &'a ModelFallback)> {
+    let next = current.map_or(0, |fallback| fallback.fallback_index + 1);
+    config
+        .model_fallbacks
+        .get(next)
+        .map(|fallback| (next, fallback))
+}
+
+/// Derives the configuration for running `fallback` from `config`.
+pub(crate) fn config_for_fallback(config: &Config, fallback: &ModelFallback) -> Config {
+    let mut config = config.clone();
+    config.model = fallback.model.clone();
+    config.model_family = find_family_for_model(&fallback.model)
+        .unwrap_or_else(|| derive_default_model_family(&fallback.model));
+    config.model_provider_id = fallback.model_provider_id.clone();
+    config.model_provider = fallback.model_provider.clone();
+
+    // Limits configured for the primary model do not apply to the fallback.
+    let model_info = get_model_info(&config.model_family);
+    config.model_context_window = model_info.as_ref().map(|info| info.context_window);
+    config.model_max_output_tokens = model_info.as_ref().map(|info| info.max_output_tokens);
+    config.model_auto_compact_token_limit =
+        model_info.and_then(|info| info.auto_compact_token_limit);
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigOverrides;
+    use crate::config::ConfigToml;
+    use crate::model_provider_info::WireApi;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn config_with_fallbacks() -> (TempDir, Config) {
+        let codex_home = TempDir::new().expect("codex home");
+        let cfg: ConfigToml = toml::from_str(
+            r#"
+model = "gpt-5-codex"
+
+[model_providers.anthropic]
+name = "Anthropic"
+base_url = "https://api.anthropic.com/v1"
+wire_api = "messages"
+
+[[model_fallbacks]]
+model = "gpt-5"
+
+[[model_fallbacks]]
+model = "claude-sonnet-4-5"
+model_provider = "anthropic"
+"#,
+        )
+        .expect("parse config");
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .expect("load config");
+        (codex_home, config)
+    }
+
+    #[test]
+    fn classifies_errors_that_outlive_retries() {
+        let retry_limit = |status| {
+            CodexErr::RetryLimit(RetryLimitReachedError {
+                status,
+                request_id: None,
+            })
+        };
+
+        assert_eq!(
+            classify_error(&retry_limit(StatusCode::TOO_MANY_REQUESTS)),
+            Some(FallbackErrorClass::RateLimit)
+        );
+        assert_eq!(
+            classify_error(&retry_limit(StatusCode::BAD_GATEWAY)),
+            Some(FallbackErrorClass::ServerError)
+        );
+        assert_eq!(
+            classify_error(&CodexErr::Stream("closed".to_string(), None)),
+            Some(FallbackErrorClass::Stream)
+        );
+        assert_eq!(
+            classify_error(&CodexErr::ContextWindowExceeded),
+            Some(FallbackErrorClass::ContextWindow)
+        );
+        assert_eq!(
+            classify_error(&CodexErr::UnexpectedStatus(UnexpectedResponseError {
+                status: StatusCode::BAD_REQUEST,
+                body: String::new(),
+                request_id: None,
+            })),
+            None
+        );
+        assert_eq!(classify_error(&CodexErr::Interrupted), None);
+    }
+
+    fn fallback_item(fallback_index: usize) -> ModelFallbackItem {
+        ModelFallbackItem {
+            model_provider: "openai".to_string(),
+            from_model: "gpt-5-codex".to_string(),
+            from_model_provider: "openai".to_string(),
+            reason: FallbackErrorClass::ServerError,
+            fallback_index,
+        }
+    }
+
+    #[test]
+    fn walks_the_fallback_chain_in_order() {
+        let (_codex_home, config) = config_with_fallbacks();
+
+        let (first_index, first) = next_fallback(&config, None).expect("first fallback");
+        assert_eq!((first_index, first.model.as_str()), (0, "gpt-5"));
+        let first_config = config_for_fallback(&config, first);
+        assert_eq!(first_config.model_provider_id, "openai");
+
+        let (second_index, second) =
+            next_fallback(&first_config, Some(&fallback_item(first_index))).expect("second");
+        assert_eq!(
+            (second_index, second.model.as_str()),
+            (1, "claude-sonnet-4-5")
+        );
+        let second_config = config_for_fallback(&first_config, second);
+        assert_eq!(second_config.model_provider_id, "anthropic");
+        assert_eq!(second_config.model_provider.wire_api, WireApi::Messages);
+        assert_eq!(second_config.model_family.slug, "claude-sonnet-4-5");
+        assert_eq!(second_config.model_max_output_tokens, None);
+
+        assert!(next_fallback(&second_config, Some(&fallback_item(second_index))).is_none());
+    }
+
+    #[test]
+    fn a_chain_that_repeats_a_model_still_ends() {
+        let (_codex_home, mut config) = config_with_fallbacks();
+        // gpt-5 -> claude-sonnet-4-5 -> gpt-5
+        config
+            .model_fallbacks
+            .push(config.model_fallbacks[0].clone());
+
+        let mut current = None;
+        let mut models = Vec::new();
+        while let Some((index, fallback)) = next_fallback(&config, current.as_ref()) {
+            models.push(fallback.model.clone());
+            current = Some(fallback_item(index));
+        }
+        assert_eq!(models, vec!["gpt-5", "claude-sonnet-4-5", "gpt-5"]);
+    }
+}
@@ -8,6 +8,8 @@ use codex_apply_patch::ApplyPatchFileChange;
 
 use crate::exec::SandboxType;
 
+use crate::command_safety::exec_policy::ExecPolicy;
+use crate::command_safety::exec_policy::ExecPolicyCheck;
 use crate::command_safety::is_dangerous_command::command_might_be_dangerous;
 use crate::command_safety::is_safe_command::is_known_safe_command;
 use crate::protocol::AskForApproval;
@@ -89,25 +91,28 @@ pub fn assess_patch_safety(
 /// - the user has explicitly approved the command
 /// - the command is on the "known safe" list
 /// - `DangerFullAccess` was specified and `UnlessTrusted` was not
+///
+/// Commands the exec policy forbids are rejected outright, and commands whose
+/// writes the exec policy can place inside the writable roots are run in the
+/// sandbox without asking, unless the policy is `UnlessTrusted`.
 pub fn assess_command_safety(
     command: &[String],
     approval_policy: AskForApproval,
     sandbox_policy: &SandboxPolicy,
     approved: &HashSet<Vec<String>>,
     with_escalated_permissions: bool,
+    exec_policy: &ExecPolicy,
+    cwd: &Path,
 ) -> SafetyCheck {
-    // Some commands look dangerous. Even if they are run inside a sandbox,
-    // unless the user has explicitly approved them, we should ask,
-    // or reject if the approval_policy tells us not to ask.
-    if command_might_be_dangerous(command) && !approved.contains(command) {
-        if approval_policy == AskForApproval::Never {
-            return SafetyCheck::Reject {
-                reason: "dangerous command detected; rejected by user approval settings"
-                    .to_string(),
-            };
-        }
+    let exec_policy_check = exec_policy.check(command, cwd);
+    if let ExecPolicyCheck::Forbidden { reason } = &exec_policy_check {
+        return SafetyCheck::Reject {
+            reason: format!("forbidden by exec policy: {reason}"),
+        };
+    }
 
-        return SafetyCheck::AskUser;
+    if let Some(check) = assess_dangerous_command(command, approval_policy, approved) {
+        return check;
     }
 
     // A command is "trusted" because either:
@@ -132,9 +137,91 @@ pub fn assess_command_safety(
         };
     }
 
+    // Like patches, commands the exec policy can verify still need approval
+    // under `UnlessTrusted`. Otherwise they run in the sandbox without asking
+    // when their writes stay inside the writable roots: the paths may be
+    // links to files outside those roots, and a read-only classification is
+    // only as good as the policy that produced it.
+    if let ExecPolicyCheck::Matched { writes } = exec_policy_check
+        && !with_escalated_permissions
+        && (writes.is_empty() || are_paths_writable(&writes, sandbox_policy, cwd))
+    {
+        if approval_policy == AskForApproval::UnlessTrusted {
+            return SafetyCheck::AskUser;
+        }
+        match get_platform_sandbox() {
+            Some(sandbox_type) => {
+                return SafetyCheck::AutoApprove {
+                    sandbox_type,
+                    user_explicitly_approved: false,
+                };
+            }
+            None if sandbox_policy == &SandboxPolicy::DangerFullAccess => {
+                return SafetyCheck::AutoApprove {
+                    sandbox_type: SandboxType::None,
+                    user_explicitly_approved: false,
+                };
+            }
+            None => {}
+        }
+    }
+
+    assess_safety_for_untrusted_command(approval_policy, sandbox_policy, with_escalated_permissions)
+}
+
+/// Assesses a command that matches an allow rule the user saved for the
+/// project. The rule stands in for asking the user, but the exec policy and
+/// the dangerous-command check still apply, and the command runs in the
+/// platform sandbox. Without a sandbox, or when the command asks for
+/// escalated permissions, it is treated like any other untrusted command.
+pub fn assess_project_allowed_command(
+    command: &[String],
+    approval_policy: AskForApproval,
+    sandbox_policy: &SandboxPolicy,
+    approved: &HashSet<Vec<String>>,
+    with_escalated_permissions: bool,
+    exec_policy: &ExecPolicy,
+    cwd: &Path,
+) -> SafetyCheck {
+    if let ExecPolicyCheck::Forbidden { reason } = exec_policy.check(command, cwd) {
+        return SafetyCheck::Reject {
+            reason: format!("forbidden by exec policy: {reason}"),
+        };
+    }
+
+    if let Some(check) = assess_dangerous_command(command, approval_policy, approved) {
+        return check;
+    }
+
+    if !with_escalated_permissions && let Some(sandbox_type) = get_platform_sandbox() {
+        return SafetyCheck::AutoApprove {
+            sandbox_type,
+            user_explicitly_approved: false,
+        };
+    }
+
     assess_safety_for_untrusted_command(approval_policy, sandbox_policy, with_escalated_permissions)
 }
 
+/// Some commands look dangerous. Even if they are run inside a sandbox,
+/// unless the user has explicitly approved them, we should ask, or reject if
+/// the approval_policy tells us not to ask.
+fn assess_dangerous_command(
+    command: &[String],
+    approval_policy: AskForApproval,
+    approved: &HashSet<Vec<String>>,
+) -> Option<SafetyCheck> {
+    if !command_might_be_dangerous(command) || approved.contains(command) {
+        return None;
+    }
+    if approval_policy == AskForApproval::Never {
+        return Some(SafetyCheck::Reject {
+            reason: "dangerous command detected; rejected by user approval settings".to_string(),
+        });
+    }
+    Some(SafetyCheck::AskUser)
+}
+
 pub(crate) fn assess_safety_for_untrusted_command(
     approval_policy: AskForApproval,
     sandbox_policy: &SandboxPolicy,
@@ -216,6 +303,24 @@ fn is_write_patch_constrained_to_writable_paths(
     sandbox_policy: &SandboxPolicy,
     cwd: &Path,
 ) -> bool {
+    let mut paths = Vec::new();
+    for (path, change) in action.changes() {
+        paths.push(path.clone());
+        if let ApplyPatchFileChange::Update {
+            move_path: Some(dest),
+            ..
+        } = change
+        {
+            paths.push(dest.clone());
+        }
+    }
+
+    are_paths_writable(&paths, sandbox_policy, cwd)
+}
+
+/// Whether every path in `paths` (absolute, or relative to `cwd`) is inside
+/// one of the writable roots of `sandbox_policy`.
+fn are_paths_writable(paths: &[PathBuf], sandbox_policy: &SandboxPolicy, cwd: &Path) -> bool {
     // Early‑exit if there are no declared writable roots.
     let writable_roots = match sandbox_policy {
         SandboxPolicy::ReadOnly => {
@@ -227,62 +332,31 @@ fn is_write_patch_constrained_to_writable_paths(
         SandboxPolicy::WorkspaceWrite { .. } => sandbox_policy.get_writable_roots_with_cwd(cwd),
     };
 
-    // Normalize a path by removing `.` and resolving `..` without touching the
-    // filesystem (works even if the file does not exist).
-    fn normalize(path: &Path) -> Option<PathBuf> {
-        let mut out = PathBuf::new();
-        for comp in path.components() {
-            match comp {
-                Component::ParentDir => {
-                    out.pop();
-                }
-                Component::CurDir => { /* skip */ }
-                other => out.push(other.as_os_str()),
-            }
-        }
-        Some(out)
-    }
-
     // Determine whether `path` is inside **any** writable root. Both `path`
     // and roots are converted to absolute, normalized forms before the
     // prefix check.
-    let is_path_writable = |p: &PathBuf| {
-        let abs = if p.is_absolute() {
-            p.clone()
-        } else {
-            cwd.join(p)
-        };
-        let abs = match normalize(&abs) {
-            Some(v) => v,
-            None => return false,
-        };
-
+    paths.iter().all(|p| {
+        let abs = normalize(&cwd.join(p));
         writable_roots
             .iter()
             .any(|writable_root| writable_root.is_path_writable(&abs))
-    };
+    })
+}
 
-    for (path, change) in action.changes() {
-        match change {
-            ApplyPatchFileChange::Add { .. } | ApplyPatchFileChange::Delete { .. } => {
-                if !is_path_writable(path) {
-                    return false;
-                }
-            }
-            ApplyPatchFileChange::Update { move_path, .. } => {
-                if !is_path_writable(path) {
-                    return false;
-                }
-                if let Some(dest) = move_path
-                    && !is_path_writable(dest)
-                {
-                    return false;
-                }
+// Normalize a path by removing `.` and resolving `..` without touching the
+// filesystem (works even if the file does not exist).
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for comp in path.components() {
+        match comp {
+            Component::ParentDir => {
+                out.pop();
             }
+            Component::CurDir => { /* skip */ }
+            other => out.push(other.as_os_str()),
         }
     }
-
-    true
+    out
 }
 
 #[cfg(test)]
@@ -355,6 +429,8 @@ mod tests {
             &sandbox_policy,
             &approved,
             request_escalated_privileges,
+            &ExecPolicy::default(),
+            &std::env::temp_dir(),
         );
 
         assert_eq!(safety_check, SafetyCheck::AskUser);
@@ -375,6 +451,8 @@ mod tests {
             &sandbox_policy,
             &approved,
             request_escalated_privileges,
+            &ExecPolicy::default(),
+            &std::env::temp_dir(),
         );
 
         assert_eq!(
@@ -400,6 +478,8 @@ mod tests {
             &sandbox_policy,
             &approved,
             request_escalated_privileges,
+            &ExecPolicy::default(),
+            &std::env::temp_dir(),
         );
 
         assert_eq!(
@@ -425,6 +505,8 @@ mod tests {
             &sandbox_policy,
             &approved,
             request_escalated_privileges,
+            &ExecPolicy::default(),
+            &std::env::temp_dir(),
         );
 
         let expected = match get_platform_sandbox() {
@@ -436,4 +518,76 @@ mod tests {
         };
         assert_eq!(safety_check, expected);
     }
+
+    #[test]
+    fn exec_policy_writes_inside_writable_roots_are_sandboxed() {
+        let tmp = TempDir::new().unwrap();
+        let cwd = tmp.path().to_path_buf();
+        let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+        let exec_policy = ExecPolicy::default();
+        let assess_with = |approval_policy: AskForApproval, command: &[&str]| {
+            let command: Vec<String> = command.iter().map(ToString::to_string).collect();
+            assess_command_safety(
+                &command,
+                approval_policy,
+                &sandbox_policy,
+                &HashSet::new(),
+                false,
+                &exec_policy,
+                &cwd,
+            )
+        };
+        let assess = |command: &[&str]| assess_with(AskForApproval::OnRequest, command);
+
+        let expected_inside = match get_platform_sandbox() {
+            Some(sandbox_type) => SafetyCheck::AutoApprove {
+                sandbox_type,
+                user_explicitly_approved: false,
+            },
+            None => SafetyCheck::AskUser,
+        };
+        assert_eq!(assess(&["cp", "a.txt", "b.txt"]), expected_inside);
+        assert_eq!(
+            assess(&["cp", "a.txt", "../outside.txt"]),
+            SafetyCheck::AskUser
+        );
+        assert_eq!(
+            assess_with(AskForApproval::UnlessTrusted, &["cp", "a.txt", "b.txt"]),
+            SafetyCheck::AskUser
+        );
+    }
+
+    #[test]
+    fn exec_policy_forbidden_command_is_rejected() {
+        let codex_home = TempDir::new().unwrap();
+        let policy_file = codex_home.path().join("forbid.policy");
+        std::fs::write(
+            &policy_file,
+            r#"forbid_program_regex(regex="^ls$", reason="use the file list tool")"#,
+        )
+        .unwrap();
+        let exec_policy = ExecPolicy::load(&[policy_file]);
+
+        let safety_check = assess_command_safety(
+            &["ls".to_string()],
+            AskForApproval::OnRequest,
+            &SandboxPolicy::DangerFullAccess,
+            &HashSet::new(),
+            false,
+            &exec_policy,
+            codex_home.path(),
+        );
+
+        assert_eq!(
+            safety_check,
+            SafetyCheck::Reject {
+                reason: "forbidden by exec policy: use the file list tool".to_string(),
+            }
+        );
+    }
 }
@@ -1,5 +1,7 @@
 //! Session-wide mutable state.
 
+use std::collections::HashSet;
+
 use codex_protocol::models::ResponseItem;
 
 use crate::conversation_history::ConversationHistory;
@@ -13,6 +15,8 @@ pub(crate) struct SessionState {
     pub(crate) history: ConversationHistory,
     pub(crate) token_info: Option<TokenUsageInfo>,
     pub(crate) latest_rate_limits: Option<RateLimitSnapshot>,
+    /// `(server, tool)` pairs the user approved for the rest of the session.
+    approved_mcp_tools: HashSet<(String, String)>,
 }
 
 impl SessionState {
@@ -73,5 +77,16 @@ impl SessionState {
         }
     }
 
+    // MCP tool approval helpers
+    pub(crate) fn approve_mcp_tool_for_session(&mut self, server: &str, tool: &str) {
+        self.approved_mcp_tools
+            .insert((server.to_string(), tool.to_string()));
+    }
+
+    pub(crate) fn is_mcp_tool_approved_for_session(&self, server: &str, tool: &str) -> bool {
+        self.approved_mcp_tools
+            .contains(&(server.to_string(), tool.to_string()))
+    }
+
     // Pending input/approval moved to TurnState.
 }
@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 
 use crate::function_tool::FunctionCallError;
+use crate::mcp_tool_call::check_mcp_tool_approval;
 use crate::mcp_tool_call::handle_mcp_tool_call;
 use crate::tools::context::ToolInvocation;
 use crate::tools::context::ToolOutput;
@@ -17,37 +18,33 @@ impl ToolHandler for McpHandler {
     }
 
     async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
-        let ToolInvocation {
-            session,
-            sub_id,
-            call_id,
-            payload,
-            ..
-        } = invocation;
-
-        let payload = match payload {
-            ToolPayload::Mcp {
-                server,
-                tool,
-                raw_arguments,
-            } => (server, tool, raw_arguments),
-            _ => {
-                return Err(FunctionCallError::RespondToModel(
-                    "mcp handler received unsupported payload".to_string(),
-                ));
-            }
+        let ToolPayload::Mcp {
+            server,
+            tool,
+            raw_arguments,
+        } = &invocation.payload
+        else {
+            return Err(FunctionCallError::RespondToModel(
+                "mcp handler received unsupported payload".to_string(),
+            ));
         };
 
-        let (server, tool, raw_arguments) = payload;
-        let arguments_str = raw_arguments;
+        if let Err(message) =
+            check_mcp_tool_approval(&invocation, server, tool, raw_arguments).await
+        {
+            return Ok(ToolOutput::Function {
+                content: message,
+                success: Some(false),
+            });
+        }
 
         let response = handle_mcp_tool_call(
-            session.as_ref(),
-            &sub_id,
-            call_id.clone(),
-            server,
-            tool,
-            arguments_str,
+            invocation.session.as_ref(),
+            &invocation.sub_id,
+            invocation.call_id.clone(),
+            server.clone(),
+            tool.clone(),
+            raw_arguments.clone(),
         )
         .await;
 
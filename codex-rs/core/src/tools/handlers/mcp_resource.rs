@@ -0,0 +1,290 @@
+use async_trait::async_trait;
+use mcp_types::ListResourceTemplatesRequestParams;
+use mcp_types::ListResourcesRequestParams;
+use mcp_types::ReadResourceRequestParams;
+use mcp_types::Resource;
+use mcp_types::ResourceTemplate;
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+
+pub const LIST_MCP_RESOURCES_TOOL_NAME: &str = "list_mcp_resources";
+pub const LIST_MCP_RESOURCE_TEMPLATES_TOOL_NAME: &str = "list_mcp_resource_templates";
+pub const READ_MCP_RESOURCE_TOOL_NAME: &str = "read_mcp_resource";
+
+/// Serves the `list_mcp_resources`, `list_mcp_resource_templates` and
+/// `read_mcp_resource` tools on top of the session's MCP connections.
+pub struct McpResourceHandler;
+
+#[derive(Deserialize)]
+struct ListArgs {
+    #[serde(default)]
+    server: Option<String>,
+    #[serde(default)]
+    cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ReadArgs {
+    server: String,
+    uri: String,
+}
+
+#[derive(Serialize)]
+struct ServerResource {
+    server: String,
+    #[serde(flatten)]
+    resource: Resource,
+}
+
+#[derive(Serialize)]
+struct ServerResourceTemplate {
+    server: String,
+    #[serde(flatten)]
+    template: ResourceTemplate,
+}
+
+#[derive(Serialize)]
+struct ListResourcesOutput {
+    resources: Vec<ServerResource>,
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ListResourceTemplatesOutput {
+    #[serde(rename = "resourceTemplates")]
+    resource_templates: Vec<ServerResourceTemplate>,
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+}
+
+#[async_trait]
+impl ToolHandler for McpResourceHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let arguments = match payload {
+            ToolPayload::Function { arguments } => arguments,
+            _ => {
+                return Err(FunctionCallError::RespondToModel(
+                    "mcp resource handler received unsupported payload".to_string(),
+                ));
+            }
+        };
+        let manager = session.mcp_connection_manager();
+
+        let content = match tool_name.as_str() {
+            LIST_MCP_RESOURCES_TOOL_NAME => {
+                let ListArgs { server, cursor } = parse_arguments(&arguments)?;
+                let output = match server {
+                    Some(server) => {
+                        let params = cursor.map(|cursor| ListResourcesRequestParams {
+                            cursor: Some(cursor),
+                        });
+                        let result = manager
+                            .list_resources(&server, params)
+                            .await
+                            .map_err(respond_with_error)?;
+                        ListResourcesOutput {
+                            resources: result
+                                .resources
+                                .into_iter()
+                                .map(|resource| ServerResource {
+                                    server: server.clone(),
+                                    resource,
+                                })
+                                .collect(),
+                            next_cursor: result.next_cursor,
+                        }
+                    }
+                    None => {
+                        reject_cursor_without_server(cursor.as_deref())?;
+                        let mut resources: Vec<ServerResource> = manager
+                            .list_all_resources()
+                            .await
+                            .into_iter()
+                            .flat_map(|(server, resources)| {
+                                resources.into_iter().map(move |resource| ServerResource {
+                                    server: server.clone(),
+                                    resource,
+                                })
+                            })
+                            .collect();
+                        resources.sort_by(|a, b| {
+                            (&a.server, &a.resource.uri).cmp(&(&b.server, &b.resource.uri))
+                        });
+                        ListResourcesOutput {
+                            resources,
+                            next_cursor: None,
+                        }
+                    }
+                };
+                serialize_output(&output)?
+            }
+            LIST_MCP_RESOURCE_TEMPLATES_TOOL_NAME => {
+                let ListArgs { server, cursor } = parse_arguments(&arguments)?;
+                let output = match server {
+                    Some(server) => {
+                        let params = cursor.map(|cursor| ListResourceTemplatesRequestParams {
+                            cursor: Some(cursor),
+                        });
+                        let result = manager
+                            .list_resource_templates(&server, params)
+                            .await
+                            .map_err(respond_with_error)?;
+                        ListResourceTemplatesOutput {
+                            resource_templates: result
+                                .resource_templates
+                                .into_iter()
+                                .map(|template| ServerResourceTemplate {
+                                    server: server.clone(),
+                                    template,
+                                })
+                                .collect(),
+                            next_cursor: result.next_cursor,
+                        }
+                    }
+                    None => {
+                        reject_cursor_without_server(cursor.as_deref())?;
+                        let mut resource_templates: Vec<ServerResourceTemplate> = manager
+                            .list_all_resource_templates()
+                            .await
+                            .into_iter()
+                            .flat_map(|(server, templates)| {
+                                templates
+                                    .into_iter()
+                                    .map(move |template| ServerResourceTemplate {
+                                        server: server.clone(),
+                                        template,
+                                    })
+                            })
+                            .collect();
+                        resource_templates.sort_by(|a, b| {
+                            (&a.server, &a.template.uri_template)
+                                .cmp(&(&b.server, &b.template.uri_template))
+                        });
+                        ListResourceTemplatesOutput {
+                            resource_templates,
+                            next_cursor: None,
+                        }
+                    }
+                };
+                serialize_output(&output)?
+            }
+            READ_MCP_RESOURCE_TOOL_NAME => {
+                let ReadArgs { server, uri } = parse_arguments(&arguments)?;
+                let result = manager
+                    .read_resource(&server, ReadResourceRequestParams { uri })
+                    .await
+                    .map_err(respond_with_error)?;
+                serialize_output(&result)?
+            }
+            other => {
+                return Err(FunctionCallError::RespondToModel(format!(
+                    "mcp resource handler does not support tool `{other}`"
+                )));
+            }
+        };
+
+        Ok(ToolOutput::Function {
+            content,
+            success: Some(true),
+        })
+    }
+}
+
+fn parse_arguments<T: DeserializeOwned>(arguments: &str) -> Result<T, FunctionCallError> {
+    // Models occasionally send an empty string for tools without required
+    // arguments.
+    let arguments = if arguments.trim().is_empty() {
+        "{}"
+    } else {
+        arguments
+    };
+    serde_json::from_str(arguments).map_err(|err| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {err:?}"))
+    })
+}
+
+fn reject_cursor_without_server(cursor: Option<&str>) -> Result<(), FunctionCallError> {
+    if cursor.is_some() {
+        return Err(FunctionCallError::RespondToModel(
+            "`cursor` can only be used together with `server`".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn respond_with_error(err: anyhow::Error) -> FunctionCallError {
+    FunctionCallError::RespondToModel(format!("{err:#}"))
+}
+
+fn serialize_output<T: Serialize>(output: &T) -> Result<String, FunctionCallError> {
+    serde_json::to_string(output).map_err(|err| {
+        FunctionCallError::RespondToModel(format!("failed to serialize MCP response: {err}"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn list_output_flattens_server_into_each_resource() {
+        let output = ListResourcesOutput {
+            resources: vec![ServerResource {
+                server: "docs".to_string(),
+                resource: Resource {
+                    annotations: None,
+                    description: Some("Team handbook".to_string()),
+                    mime_type: Some("text/markdown".to_string()),
+                    name: "handbook".to_string(),
+                    size: None,
+                    title: None,
+                    uri: "memo://handbook".to_string(),
+                },
+            }],
+            next_cursor: Some("page-2".to_string()),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&output).expect("serialize"),
+            json!({
+                "resources": [{
+                    "server": "docs",
+                    "description": "Team handbook",
+                    "mimeType": "text/markdown",
+                    "name": "handbook",
+                    "uri": "memo://handbook",
+                }],
+                "nextCursor": "page-2",
+            })
+        );
+    }
+
+    #[test]
+    fn empty_arguments_are_treated_as_an_empty_object() {
+        let args: ListArgs = parse_arguments("").expect("empty arguments should parse");
+        assert!(args.server.is_none());
+        assert!(args.cursor.is_none());
+    }
+}
@@ -3,6 +3,7 @@ mod exec_stream;
 mod grep_files;
 mod list_dir;
 mod mcp;
+mod mcp_resource;
 mod plan;
 mod read_file;
 mod shell;
@@ -18,6 +19,10 @@ pub use exec_stream::ExecStreamHandler;
 pub use grep_files::GrepFilesHandler;
 pub use list_dir::ListDirHandler;
 pub use mcp::McpHandler;
+pub use mcp_resource::LIST_MCP_RESOURCE_TEMPLATES_TOOL_NAME;
+pub use mcp_resource::LIST_MCP_RESOURCES_TOOL_NAME;
+pub use mcp_resource::McpResourceHandler;
+pub use mcp_resource::READ_MCP_RESOURCE_TOOL_NAME;
 pub use plan::PlanHandler;
 pub use read_file::ReadFileHandler;
 pub use shell::ShellHandler;
@@ -33,6 +33,7 @@ pub static PLAN_TOOL: LazyLock<ToolSpec> = LazyLock::new(|| {
             properties: plan_item_props,
             required: Some(vec!["step".to_string(), "status".to_string()]),
             additional_properties: Some(false.into()),
+            defs: None,
         }),
     };
 
@@ -55,6 +56,7 @@ At most one step can be in_progress at a time.
             properties,
             required: Some(vec!["plan".to_string()]),
             additional_properties: Some(false.into()),
+            defs: None,
         },
     })
 });
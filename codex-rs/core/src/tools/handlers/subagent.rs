@@ -81,6 +81,7 @@ impl ToolHandler for SubAgentHandler {
             cwd: turn.cwd.clone(),
             is_review_mode: false,
             final_output_json_schema: None,
+            model_fallback: turn.model_fallback.clone(),
         };
         let sub_turn_context = Arc::new(sub_turn_context);
 
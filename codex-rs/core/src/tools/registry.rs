@@ -8,6 +8,7 @@ use tracing::warn;
 
 use crate::client_common::tools::ToolSpec;
 use crate::function_tool::FunctionCallError;
+use crate::hooks::PreToolUseOutcome;
 use crate::tools::context::ToolInvocation;
 use crate::tools::context::ToolOutput;
 use crate::tools::context::ToolPayload;
@@ -58,7 +59,7 @@ impl ToolRegistry {
 
     pub async fn dispatch(
         &self,
-        invocation: ToolInvocation,
+        mut invocation: ToolInvocation,
     ) -> Result<ResponseInputItem, FunctionCallError> {
         let tool_name = invocation.tool_name.clone();
         let call_id_owned = invocation.call_id.clone();
@@ -96,6 +97,35 @@ impl ToolRegistry {
             return Err(FunctionCallError::Fatal(message));
         }
 
+        let session = Arc::clone(&invocation.session);
+        let hook_context = session.hook_context(&invocation.turn);
+        match session
+            .hooks()
+            .pre_tool_use(
+                &hook_context,
+                &tool_name,
+                &call_id_owned,
+                invocation.payload.clone(),
+            )
+            .await
+        {
+            PreToolUseOutcome::Proceed(payload) => invocation.payload = payload,
+            PreToolUseOutcome::Block(reason) => {
+                let message = format!("tool call blocked by pre-tool-use hook: {reason}");
+                otel.tool_result(
+                    tool_name.as_ref(),
+                    &call_id_owned,
+                    log_payload.as_ref(),
+                    Duration::ZERO,
+                    false,
+                    &message,
+                );
+                return Err(FunctionCallError::RespondToModel(message));
+            }
+        }
+        let payload_for_response = invocation.payload.clone();
+        let log_payload = payload_for_response.log_payload();
+
         let output_cell = tokio::sync::Mutex::new(None);
 
         let result = otel
@@ -126,9 +156,19 @@ impl ToolRegistry {
         match result {
             Ok(_) => {
                 let mut guard = output_cell.lock().await;
-                let output = guard.take().ok_or_else(|| {
+                let mut output = guard.take().ok_or_else(|| {
                     FunctionCallError::Fatal("tool produced no output".to_string())
                 })?;
+                session
+                    .hooks()
+                    .post_tool_use(
+                        &hook_context,
+                        &tool_name,
+                        &call_id_owned,
+                        &payload_for_response,
+                        &mut output,
+                    )
+                    .await;
                 Ok(output.into_response(&call_id_owned, &payload_for_response))
             }
             Err(err) => Err(err),
@@ -3,7 +3,10 @@ use crate::client_common::tools::ToolSpec;
 use crate::features::Feature;
 use crate::features::Features;
 use crate::model_family::ModelFamily;
+use crate::tools::handlers::LIST_MCP_RESOURCE_TEMPLATES_TOOL_NAME;
+use crate::tools::handlers::LIST_MCP_RESOURCES_TOOL_NAME;
 use crate::tools::handlers::PLAN_TOOL;
+use crate::tools::handlers::READ_MCP_RESOURCE_TOOL_NAME;
 use crate::tools::handlers::apply_patch::ApplyPatchToolType;
 use crate::tools::handlers::apply_patch::create_apply_patch_freeform_tool;
 use crate::tools::handlers::apply_patch::create_apply_patch_json_tool;
@@ -30,6 +33,8 @@ pub(crate) struct ToolsConfig {
     pub web_search_request: bool,
     pub include_view_image_tool: bool,
     pub include_subagent_tool: bool,
+    /// Set per turn when a connected MCP server advertises resources.
+    pub include_mcp_resource_tools: bool,
     pub experimental_unified_exec_tool: bool,
     pub experimental_supported_tools: Vec<String>,
 }
@@ -80,45 +85,71 @@ impl ToolsConfig {
             web_search_request: include_web_search_request,
             include_view_image_tool,
             include_subagent_tool,
+            include_mcp_resource_tools: false,
             experimental_unified_exec_tool,
             experimental_supported_tools: model_family.experimental_supported_tools.clone(),
         }
     }
 }
 
-/// Generic JSON‑Schema subset needed for our tool definitions
+/// Generic JSON‑Schema subset needed for our tool definitions. Besides the
+/// basic types this models the keywords MCP servers commonly rely on
+/// (`enum`, `const`, `anyOf`/`oneOf`, `$ref`/`$defs`, integer bounds) so their
+/// schemas reach the model intact.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(tag = "type", rename_all = "lowercase")]
+#[serde(into = "RawJsonSchema", try_from = "RawJsonSchema")]
 pub(crate) enum JsonSchema {
     Boolean {
-        #[serde(skip_serializing_if = "Option::is_none")]
         description: Option<String>,
     },
     String {
-        #[serde(skip_serializing_if = "Option::is_none")]
         description: Option<String>,
     },
-    /// MCP schema allows "number" | "integer" for Number
-    #[serde(alias = "integer")]
     Number {
-        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+    },
+    Integer {
+        description: Option<String>,
+        minimum: Option<serde_json::Number>,
+        maximum: Option<serde_json::Number>,
+    },
+    Null {
         description: Option<String>,
     },
     Array {
         items: Box<JsonSchema>,
-
-        #[serde(skip_serializing_if = "Option::is_none")]
         description: Option<String>,
     },
     Object {
         properties: BTreeMap<String, JsonSchema>,
-        #[serde(skip_serializing_if = "Option::is_none")]
         required: Option<Vec<String>>,
-        #[serde(
-            rename = "additionalProperties",
-            skip_serializing_if = "Option::is_none"
-        )]
         additional_properties: Option<AdditionalProperties>,
+        /// Definitions targeted by `$ref`s elsewhere in the schema.
+        defs: Option<BTreeMap<String, JsonSchema>>,
+    },
+    /// Serialized with a `type` inferred from the values when they share one.
+    Enum {
+        values: Vec<JsonValue>,
+        description: Option<String>,
+    },
+    /// Serialized with a `type` inferred from the value.
+    Const {
+        value: JsonValue,
+        description: Option<String>,
+    },
+    AnyOf {
+        variants: Vec<JsonSchema>,
+        description: Option<String>,
+    },
+    OneOf {
+        variants: Vec<JsonSchema>,
+        description: Option<String>,
+    },
+    /// A `$ref` such as `#/$defs/Issue`; `#/definitions/` refs are rewritten
+    /// to `#/$defs/` since both spellings are serialized as `$defs`.
+    Ref {
+        reference: String,
+        description: Option<String>,
     },
 }
 
@@ -142,6 +173,378 @@ impl From<JsonSchema> for AdditionalProperties {
     }
 }
 
+/// Maximum number of nested `$ref`s inlined by
+/// [`JsonSchema::downgrade_for_chat_completions`]. Also stops recursive
+/// definitions from expanding forever.
+const MAX_REF_INLINE_DEPTH: usize = 8;
+
+impl JsonSchema {
+    /// Rewrites the schema into the subset that Chat Completions providers
+    /// reliably accept: `$ref`s are inlined from the root `$defs`, `anyOf` and
+    /// `oneOf` collapse to their first non-null variant, `const` becomes a
+    /// single-value `enum` and `null` becomes a string.
+    pub(crate) fn downgrade_for_chat_completions(&self) -> JsonSchema {
+        let defs = match self {
+            JsonSchema::Object {
+                defs: Some(defs), ..
+            } => defs.clone(),
+            _ => BTreeMap::new(),
+        };
+        self.downgrade(&defs, 0)
+    }
+
+    fn downgrade(&self, defs: &BTreeMap<String, JsonSchema>, depth: usize) -> JsonSchema {
+        match self {
+            JsonSchema::Boolean { .. }
+            | JsonSchema::String { .. }
+            | JsonSchema::Number { .. }
+            | JsonSchema::Integer { .. }
+            | JsonSchema::Enum { .. } => self.clone(),
+            JsonSchema::Null { description } => JsonSchema::String {
+                description: description.clone(),
+            },
+            JsonSchema::Const { value, description } => JsonSchema::Enum {
+                values: vec![value.clone()],
+                description: description.clone(),
+            },
+            JsonSchema::Array { items, description } => JsonSchema::Array {
+                items: Box::new(items.downgrade(defs, depth)),
+                description: description.clone(),
+            },
+            JsonSchema::Object {
+                properties,
+                required,
+                additional_properties,
+                ..
+            } => JsonSchema::Object {
+                properties: properties
+                    .iter()
+                    .map(|(name, schema)| (name.clone(), schema.downgrade(defs, depth)))
+                    .collect(),
+                required: required.clone(),
+                additional_properties: additional_properties.as_ref().map(|ap| match ap {
+                    AdditionalProperties::Boolean(b) => AdditionalProperties::Boolean(*b),
+                    AdditionalProperties::Schema(schema) => schema.downgrade(defs, depth).into(),
+                }),
+                defs: None,
+            },
+            JsonSchema::AnyOf {
+                variants,
+                description,
+            }
+            | JsonSchema::OneOf {
+                variants,
+                description,
+            } => {
+                let variant = variants
+                    .iter()
+                    .find(|variant| !matches!(variant, JsonSchema::Null { .. }))
+                    .or(variants.first());
+                let schema = match variant {
+                    Some(variant) => variant.downgrade(defs, depth),
+                    None => JsonSchema::String { description: None },
+                };
+                schema.with_fallback_description(description)
+            }
+            JsonSchema::Ref {
+                reference,
+                description,
+            } => {
+                let target = reference
+                    .strip_prefix("#/$defs/")
+                    .and_then(|name| defs.get(name))
+                    .filter(|_| depth < MAX_REF_INLINE_DEPTH);
+                let schema = match target {
+                    Some(target) => target.downgrade(defs, depth + 1),
+                    None => JsonSchema::String { description: None },
+                };
+                schema.with_fallback_description(description)
+            }
+        }
+    }
+
+    /// Keeps the schema's own description, falling back to the one attached
+    /// to the `anyOf`/`oneOf`/`$ref` it replaces.
+    fn with_fallback_description(mut self, fallback: &Option<String>) -> JsonSchema {
+        let description = match &mut self {
+            JsonSchema::Object { .. } => None,
+            JsonSchema::Boolean { description }
+            | JsonSchema::String { description }
+            | JsonSchema::Number { description }
+            | JsonSchema::Integer { description, .. }
+            | JsonSchema::Null { description }
+            | JsonSchema::Array { description, .. }
+            | JsonSchema::Enum { description, .. }
+            | JsonSchema::Const { description, .. }
+            | JsonSchema::AnyOf { description, .. }
+            | JsonSchema::OneOf { description, .. }
+            | JsonSchema::Ref { description, .. } => Some(description),
+        };
+        if let Some(description) = description
+            && description.is_none()
+        {
+            description.clone_from(fallback);
+        }
+        self
+    }
+}
+
+/// Flat wire form of [`JsonSchema`]. A single schema object may combine
+/// `type` with `enum`, `nullable`, bounds and so on, so (de)serialization goes
+/// through this struct rather than a tagged enum.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct RawJsonSchema {
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    r#type: Option<RawSchemaType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    items: Option<Box<JsonSchema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    properties: Option<BTreeMap<String, JsonSchema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    required: Option<Vec<String>>,
+    #[serde(
+        rename = "additionalProperties",
+        skip_serializing_if = "Option::is_none"
+    )]
+    additional_properties: Option<AdditionalProperties>,
+    #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
+    r#enum: Option<Vec<JsonValue>>,
+    #[serde(rename = "const", skip_serializing_if = "Option::is_none")]
+    r#const: Option<JsonValue>,
+    #[serde(rename = "anyOf", skip_serializing_if = "Option::is_none")]
+    any_of: Option<Vec<JsonSchema>>,
+    #[serde(rename = "oneOf", skip_serializing_if = "Option::is_none")]
+    one_of: Option<Vec<JsonSchema>>,
+    #[serde(rename = "$ref", skip_serializing_if = "Option::is_none")]
+    reference: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minimum: Option<serde_json::Number>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maximum: Option<serde_json::Number>,
+    /// OpenAPI-style nullability, read as `anyOf: [<schema>, null]`.
+    #[serde(skip_serializing)]
+    nullable: Option<bool>,
+    #[serde(
+        rename = "$defs",
+        alias = "definitions",
+        skip_serializing_if = "Option::is_none"
+    )]
+    defs: Option<BTreeMap<String, JsonSchema>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawSchemaType {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl RawJsonSchema {
+    fn typed(ty: &str, description: Option<String>) -> Self {
+        Self {
+            r#type: Some(RawSchemaType::Single(ty.to_string())),
+            description,
+            ..Default::default()
+        }
+    }
+
+    /// Builds the schema for a single `type`, reading only the keywords that
+    /// apply to it.
+    fn into_typed_schema(
+        self,
+        ty: &str,
+        description: Option<String>,
+    ) -> Result<JsonSchema, String> {
+        Ok(match ty {
+            "boolean" => JsonSchema::Boolean { description },
+            "string" => JsonSchema::String { description },
+            "number" => JsonSchema::Number { description },
+            "integer" => JsonSchema::Integer {
+                description,
+                minimum: self.minimum,
+                maximum: self.maximum,
+            },
+            "null" => JsonSchema::Null { description },
+            "array" => JsonSchema::Array {
+                items: self
+                    .items
+                    .ok_or_else(|| "array schema is missing `items`".to_string())?,
+                description,
+            },
+            "object" => JsonSchema::Object {
+                properties: self.properties.unwrap_or_default(),
+                required: self.required,
+                additional_properties: self.additional_properties,
+                defs: self.defs,
+            },
+            other => return Err(format!("unsupported JSON Schema type `{other}`")),
+        })
+    }
+}
+
+impl TryFrom<RawJsonSchema> for JsonSchema {
+    type Error = String;
+
+    fn try_from(mut raw: RawJsonSchema) -> Result<Self, Self::Error> {
+        let description = raw.description.take();
+        if raw.nullable.take() == Some(true) {
+            return Ok(JsonSchema::AnyOf {
+                variants: vec![
+                    JsonSchema::try_from(raw)?,
+                    JsonSchema::Null { description: None },
+                ],
+                description,
+            });
+        }
+        if let Some(reference) = raw.reference.take() {
+            let reference = match reference.strip_prefix("#/definitions/") {
+                Some(name) => format!("#/$defs/{name}"),
+                None => reference,
+            };
+            return Ok(JsonSchema::Ref {
+                reference,
+                description,
+            });
+        }
+        if let Some(variants) = raw.any_of.take() {
+            return Ok(JsonSchema::AnyOf {
+                variants,
+                description,
+            });
+        }
+        if let Some(variants) = raw.one_of.take() {
+            return Ok(JsonSchema::OneOf {
+                variants,
+                description,
+            });
+        }
+        if let Some(value) = raw.r#const.take() {
+            return Ok(JsonSchema::Const { value, description });
+        }
+        if let Some(values) = raw.r#enum.take() {
+            return Ok(JsonSchema::Enum {
+                values,
+                description,
+            });
+        }
+
+        match raw.r#type.take() {
+            Some(RawSchemaType::Single(ty)) => raw.into_typed_schema(&ty, description),
+            Some(RawSchemaType::Multiple(types)) => match types.as_slice() {
+                [ty] => raw.into_typed_schema(ty, description),
+                _ => Ok(JsonSchema::AnyOf {
+                    variants: types
+                        .iter()
+                        .map(|ty| raw.clone().into_typed_schema(ty, None))
+                        .collect::<Result<Vec<_>, _>>()?,
+                    description,
+                }),
+            },
+            None => Err("JSON Schema is missing `type`".to_string()),
+        }
+    }
+}
+
+impl From<JsonSchema> for RawJsonSchema {
+    fn from(schema: JsonSchema) -> Self {
+        match schema {
+            JsonSchema::Boolean { description } => Self::typed("boolean", description),
+            JsonSchema::String { description } => Self::typed("string", description),
+            JsonSchema::Number { description } => Self::typed("number", description),
+            JsonSchema::Integer {
+                description,
+                minimum,
+                maximum,
+            } => Self {
+                minimum,
+                maximum,
+                ..Self::typed("integer", description)
+            },
+            JsonSchema::Null { description } => Self::typed("null", description),
+            JsonSchema::Array { items, description } => Self {
+                items: Some(items),
+                ..Self::typed("array", description)
+            },
+            JsonSchema::Object {
+                properties,
+                required,
+                additional_properties,
+                defs,
+            } => Self {
+                properties: Some(properties),
+                required,
+                additional_properties,
+                defs,
+                ..Self::typed("object", None)
+            },
+            JsonSchema::Enum {
+                values,
+                description,
+            } => Self {
+                r#type: shared_value_type(&values),
+                r#enum: Some(values),
+                description,
+                ..Default::default()
+            },
+            JsonSchema::Const { value, description } => Self {
+                r#type: shared_value_type(std::slice::from_ref(&value)),
+                r#const: Some(value),
+                description,
+                ..Default::default()
+            },
+            JsonSchema::AnyOf {
+                variants,
+                description,
+            } => Self {
+                any_of: Some(variants),
+                description,
+                ..Default::default()
+            },
+            JsonSchema::OneOf {
+                variants,
+                description,
+            } => Self {
+                one_of: Some(variants),
+                description,
+                ..Default::default()
+            },
+            JsonSchema::Ref {
+                reference,
+                description,
+            } => Self {
+                reference: Some(reference),
+                description,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Returns the JSON Schema `type` shared by all `values`, if any. Integers
+/// mixed with other numbers widen to `number`.
+fn shared_value_type(values: &[JsonValue]) -> Option<RawSchemaType> {
+    let mut shared: Option<&str> = None;
+    for value in values {
+        let ty = match value {
+            JsonValue::String(_) => "string",
+            JsonValue::Bool(_) => "boolean",
+            JsonValue::Number(n) if n.is_i64() || n.is_u64() => "integer",
+            JsonValue::Number(_) => "number",
+            JsonValue::Null | JsonValue::Array(_) | JsonValue::Object(_) => return None,
+        };
+        shared = match shared {
+            None => Some(ty),
+            Some(prev) if prev == ty => Some(ty),
+            Some("integer" | "number") if matches!(ty, "integer" | "number") => Some("number"),
+            Some(_) => return None,
+        };
+    }
+    shared.map(|ty| RawSchemaType::Single(ty.to_string()))
+}
+
 fn create_unified_exec_tool() -> ToolSpec {
     let mut properties = BTreeMap::new();
     properties.insert(
@@ -185,6 +588,7 @@ fn create_unified_exec_tool() -> ToolSpec {
             properties,
             required: Some(vec!["input".to_string()]),
             additional_properties: Some(false.into()),
+            defs: None,
         },
     })
 }
@@ -215,6 +619,7 @@ fn create_subagent_tool() -> ToolSpec {
             properties,
             required: Some(vec!["task".to_string()]),
             additional_properties: Some(false.into()),
+            defs: None,
         },
     })
 }
@@ -262,6 +667,7 @@ fn create_shell_tool() -> ToolSpec {
             properties,
             required: Some(vec!["command".to_string()]),
             additional_properties: Some(false.into()),
+            defs: None,
         },
     })
 }
@@ -286,6 +692,72 @@ fn create_view_image_tool() -> ToolSpec {
             properties,
             required: Some(vec!["path".to_string()]),
             additional_properties: Some(false.into()),
+            defs: None,
+        },
+    })
+}
+
+fn create_list_mcp_resources_tool(name: &str, description: &str) -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "server".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Name of the MCP server to query. Omit to list entries from every server."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "cursor".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Pagination cursor returned as `nextCursor` by a previous call for the same server."
+                    .to_string(),
+            ),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: name.to_string(),
+        description: description.to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false.into()),
+            defs: None,
+        },
+    })
+}
+
+fn create_read_mcp_resource_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "server".to_string(),
+        JsonSchema::String {
+            description: Some("Name of the MCP server that owns the resource.".to_string()),
+        },
+    );
+    properties.insert(
+        "uri".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "URI of the resource, either as listed or expanded from a resource template."
+                    .to_string(),
+            ),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: READ_MCP_RESOURCE_TOOL_NAME.to_string(),
+        description: "Read the contents of a resource exposed by an MCP server.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["server".to_string(), "uri".to_string()]),
+            additional_properties: Some(false.into()),
+            defs: None,
         },
     })
 }
@@ -337,6 +809,7 @@ fn create_test_sync_tool() -> ToolSpec {
             properties: barrier_properties,
             required: Some(vec!["id".to_string(), "participants".to_string()]),
             additional_properties: Some(false.into()),
+            defs: None,
         },
     );
 
@@ -348,6 +821,7 @@ fn create_test_sync_tool() -> ToolSpec {
             properties,
             required: None,
             additional_properties: Some(false.into()),
+            defs: None,
         },
     })
 }
@@ -398,6 +872,7 @@ fn create_grep_files_tool() -> ToolSpec {
             properties,
             required: Some(vec!["pattern".to_string()]),
             additional_properties: Some(false.into()),
+            defs: None,
         },
     })
 }
@@ -483,6 +958,7 @@ fn create_read_file_tool() -> ToolSpec {
             properties: indentation_properties,
             required: None,
             additional_properties: Some(false.into()),
+            defs: None,
         },
     );
 
@@ -496,6 +972,7 @@ fn create_read_file_tool() -> ToolSpec {
             properties,
             required: Some(vec!["file_path".to_string()]),
             additional_properties: Some(false.into()),
+            defs: None,
         },
     })
 }
@@ -541,6 +1018,7 @@ fn create_list_dir_tool() -> ToolSpec {
             properties,
             required: Some(vec!["dir_path".to_string()]),
             additional_properties: Some(false.into()),
+            defs: None,
         },
     })
 }
@@ -568,12 +1046,28 @@ pub fn create_tools_json_for_responses_api(
 /// Returns JSON values that are compatible with Function Calling in the
 /// Chat Completions API:
 /// https://platform.openai.com/docs/guides/function-calling?api-mode=chat
+///
+/// When `downgrade_schemas` is set, for providers that reject composite
+/// schemas, parameters go through
+/// [`JsonSchema::downgrade_for_chat_completions`] first.
 pub(crate) fn create_tools_json_for_chat_completions_api(
     tools: &[ToolSpec],
+    downgrade_schemas: bool,
 ) -> crate::error::Result<Vec<serde_json::Value>> {
+    let tools: Vec<ToolSpec> = tools
+        .iter()
+        .map(|tool| match tool {
+            ToolSpec::Function(tool) if downgrade_schemas => ToolSpec::Function(ResponsesApiTool {
+                parameters: tool.parameters.downgrade_for_chat_completions(),
+                ..tool.clone()
+            }),
+            other => other.clone(),
+        })
+        .collect();
+
     // We start with the JSON for the Responses API and than rewrite it to match
     // the chat completions tool call format.
-    let responses_api_tools_json = create_tools_json_for_responses_api(tools)?;
+    let responses_api_tools_json = create_tools_json_for_responses_api(&tools)?;
     let tools_json = responses_api_tools_json
         .into_iter()
         .filter_map(|mut tool| {
@@ -596,6 +1090,28 @@ pub(crate) fn create_tools_json_for_chat_completions_api(
     Ok(tools_json)
 }
 
+/// Returns JSON values that are compatible with tool use in the Anthropic
+/// Messages API:
+/// https://docs.anthropic.com/en/api/messages
+///
+/// Only function tools are supported; they keep their full JSON Schema as the
+/// tool's `input_schema`.
+pub(crate) fn create_tools_json_for_messages_api(
+    tools: &[ToolSpec],
+) -> crate::error::Result<Vec<serde_json::Value>> {
+    let mut tools_json = Vec::new();
+    for tool in tools {
+        if let ToolSpec::Function(tool) = tool {
+            tools_json.push(json!({
+                "name": tool.name,
+                "description": tool.description,
+                "input_schema": serde_json::to_value(&tool.parameters)?,
+            }));
+        }
+    }
+    Ok(tools_json)
+}
+
 pub(crate) fn mcp_tool_to_openai_tool(
     fully_qualified_name: String,
     tool: mcp_types::Tool,
@@ -615,10 +1131,9 @@ pub(crate) fn mcp_tool_to_openai_tool(
     }
 
     // Serialize to a raw JSON value so we can sanitize schemas coming from MCP
-    // servers. Some servers omit the top-level or nested `type` in JSON
-    // Schemas (e.g. using enum/anyOf), or use unsupported variants like
-    // `integer`. Our internal JsonSchema is a small subset and requires
-    // `type`, so we coerce/sanitize here for compatibility.
+    // servers. Some servers omit the nested `type` for plain values or use
+    // keywords our JsonSchema does not model, so we coerce/sanitize here for
+    // compatibility.
     let mut serialized_input_schema = serde_json::to_value(input_schema)?;
     sanitize_json_schema(&mut serialized_input_schema);
     let input_schema = serde_json::from_value::<JsonSchema>(serialized_input_schema)?;
@@ -631,11 +1146,20 @@ pub(crate) fn mcp_tool_to_openai_tool(
     })
 }
 
-/// Sanitize a JSON Schema (as serde_json::Value) so it can fit our limited
+/// JSON Schema types our JsonSchema enum can represent.
+const SUPPORTED_SCHEMA_TYPES: [&str; 7] = [
+    "object", "array", "string", "number", "integer", "boolean", "null",
+];
+
+/// Sanitize a JSON Schema (as serde_json::Value) so it can fit our
 /// JsonSchema enum. This function:
-/// - Ensures every schema object has a "type". If missing, infers it from
-///   common keywords (properties => object, items => array, enum/const/format => string)
-///   and otherwise defaults to "string".
+/// - Ensures every schema object has a "type" unless it is described by
+///   `enum`, `const`, `anyOf`, `oneOf` or `$ref` instead. If missing, infers
+///   it from common keywords (properties => object, items => array,
+///   format => string) and otherwise defaults to "string".
+/// - Drops unsupported entries from `type` unions.
+/// - Unwraps single-entry `allOf`s, which some generators emit to attach a
+///   description to a `$ref`.
 /// - Fills required child fields (e.g. array items, object properties) with
 ///   permissive defaults when absent.
 fn sanitize_json_schema(value: &mut JsonValue) {
@@ -650,12 +1174,24 @@ fn sanitize_json_schema(value: &mut JsonValue) {
             }
         }
         JsonValue::Object(map) => {
-            // First, recursively sanitize known nested schema holders
-            if let Some(props) = map.get_mut("properties")
-                && let Some(props_map) = props.as_object_mut()
+            if let Some(JsonValue::Array(all_of)) = map.get("allOf")
+                && let [JsonValue::Object(inner)] = all_of.as_slice()
             {
-                for (_k, v) in props_map.iter_mut() {
-                    sanitize_json_schema(v);
+                let inner = inner.clone();
+                map.remove("allOf");
+                for (key, entry) in inner {
+                    map.entry(key).or_insert(entry);
+                }
+            }
+
+            // First, recursively sanitize known nested schema holders
+            for holder in ["properties", "$defs", "definitions"] {
+                if let Some(schemas) = map.get_mut(holder)
+                    && let Some(schemas_map) = schemas.as_object_mut()
+                {
+                    for (_k, v) in schemas_map.iter_mut() {
+                        sanitize_json_schema(v);
+                    }
                 }
             }
             if let Some(items) = map.get_mut("items") {
@@ -668,55 +1204,58 @@ fn sanitize_json_schema(value: &mut JsonValue) {
                 }
             }
 
-            // Normalize/ensure type
-            let mut ty = map.get("type").and_then(|v| v.as_str()).map(str::to_string);
-
-            // If type is an array (union), pick first supported; else leave to inference
-            if ty.is_none()
-                && let Some(JsonValue::Array(types)) = map.get("type")
-            {
-                for t in types {
-                    if let Some(tt) = t.as_str()
-                        && matches!(
-                            tt,
-                            "object" | "array" | "string" | "number" | "integer" | "boolean"
-                        )
-                    {
-                        ty = Some(tt.to_string());
-                        break;
-                    }
-                }
-            }
+            // Normalize/ensure type, keeping unions of supported types
+            let mut types: Vec<String> = match map.get("type") {
+                Some(JsonValue::String(ty)) => vec![ty.clone()],
+                Some(JsonValue::Array(types)) => types
+                    .iter()
+                    .filter_map(JsonValue::as_str)
+                    .map(str::to_string)
+                    .collect(),
+                _ => Vec::new(),
+            };
+            types.retain(|ty| SUPPORTED_SCHEMA_TYPES.contains(&ty.as_str()));
+
+            let described_without_type = ["enum", "const", "anyOf", "oneOf", "$ref"]
+                .iter()
+                .any(|keyword| map.contains_key(*keyword));
 
             // Infer type if still missing
-            if ty.is_none() {
-                if map.contains_key("properties")
+            if types.is_empty() && !described_without_type {
+                let ty = if map.contains_key("properties")
                     || map.contains_key("required")
                     || map.contains_key("additionalProperties")
                 {
-                    ty = Some("object".to_string());
+                    "object"
                 } else if map.contains_key("items") || map.contains_key("prefixItems") {
-                    ty = Some("array".to_string());
-                } else if map.contains_key("enum")
-                    || map.contains_key("const")
-                    || map.contains_key("format")
-                {
-                    ty = Some("string".to_string());
+                    "array"
                 } else if map.contains_key("minimum")
                     || map.contains_key("maximum")
                     || map.contains_key("exclusiveMinimum")
                     || map.contains_key("exclusiveMaximum")
                     || map.contains_key("multipleOf")
                 {
-                    ty = Some("number".to_string());
+                    "number"
+                } else {
+                    // Covers `format` as well as schemas we cannot infer.
+                    "string"
+                };
+                types.push(ty.to_string());
+            }
+            match types.as_slice() {
+                [] => {
+                    map.remove("type");
+                }
+                [ty] => {
+                    map.insert("type".to_string(), JsonValue::String(ty.clone()));
+                }
+                _ => {
+                    map.insert("type".to_string(), json!(types));
                 }
             }
-            // If we still couldn't infer, default to string
-            let ty = ty.unwrap_or_else(|| "string".to_string());
-            map.insert("type".to_string(), JsonValue::String(ty.to_string()));
 
             // Ensure object schemas have properties map
-            if ty == "object" {
+            if types.iter().any(|ty| ty == "object") {
                 if !map.contains_key("properties") {
                     map.insert(
                         "properties".to_string(),
@@ -734,7 +1273,7 @@ fn sanitize_json_schema(value: &mut JsonValue) {
             }
 
             // Ensure array schemas have items
-            if ty == "array" && !map.contains_key("items") {
+            if types.iter().any(|ty| ty == "array") && !map.contains_key("items") {
                 map.insert("items".to_string(), json!({ "type": "string" }));
             }
         }
@@ -756,6 +1295,7 @@ pub(crate) fn build_specs(
     use crate::tools::handlers::GrepFilesHandler;
     use crate::tools::handlers::ListDirHandler;
     use crate::tools::handlers::McpHandler;
+    use crate::tools::handlers::McpResourceHandler;
     use crate::tools::handlers::PlanHandler;
     use crate::tools::handlers::ReadFileHandler;
     use crate::tools::handlers::ShellHandler;
@@ -873,6 +1413,31 @@ pub(crate) fn build_specs(
         builder.register_handler("view_image", view_image_handler);
     }
 
+    if config.include_mcp_resource_tools {
+        let mcp_resource_handler = Arc::new(McpResourceHandler);
+        builder.push_spec_with_parallel_support(
+            create_list_mcp_resources_tool(
+                LIST_MCP_RESOURCES_TOOL_NAME,
+                "List resources exposed by the connected MCP servers. Read one with `read_mcp_resource`.",
+            ),
+            true,
+        );
+        builder.push_spec_with_parallel_support(
+            create_list_mcp_resources_tool(
+                LIST_MCP_RESOURCE_TEMPLATES_TOOL_NAME,
+                "List URI templates for parameterized resources exposed by the connected MCP servers.",
+            ),
+            true,
+        );
+        builder.push_spec_with_parallel_support(create_read_mcp_resource_tool(), true);
+        builder.register_handler(LIST_MCP_RESOURCES_TOOL_NAME, mcp_resource_handler.clone());
+        builder.register_handler(
+            LIST_MCP_RESOURCE_TEMPLATES_TOOL_NAME,
+            mcp_resource_handler.clone(),
+        );
+        builder.register_handler(READ_MCP_RESOURCE_TOOL_NAME, mcp_resource_handler);
+    }
+
     if let Some(mcp_tools) = mcp_tools {
         let mut entries: Vec<(String, mcp_types::Tool)> = mcp_tools.into_iter().collect();
         entries.sort_by(|a, b| a.0.cmp(&b.0));
@@ -992,6 +1557,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_build_specs_mcp_resource_tools() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let mut features = Features::with_defaults();
+        features.enable(Feature::UnifiedExec);
+        let mut config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            features: &features,
+        });
+        config.include_mcp_resource_tools = true;
+        let (tools, _) = build_specs(&config, Some(HashMap::new())).build();
+
+        assert_eq_tool_names(
+            &tools,
+            &[
+                "unified_exec",
+                "subagent",
+                "view_image",
+                "list_mcp_resources",
+                "list_mcp_resource_templates",
+                "read_mcp_resource",
+            ],
+        );
+        assert!(find_tool(&tools, "read_mcp_resource").supports_parallel_tool_calls);
+    }
+
     #[test]
     #[ignore]
     fn test_parallel_support_flags() {
@@ -1082,6 +1673,7 @@ mod tests {
                         })),
                         required: None,
                         r#type: "object".to_string(),
+                        defs: None,
                     },
                     output_schema: None,
                     title: None,
@@ -1135,11 +1727,13 @@ mod tests {
                                     "number_property".to_string(),
                                 ]),
                                 additional_properties: Some(false.into()),
+                                defs: None,
                             },
                         ),
                     ]),
                     required: None,
                     additional_properties: None,
+                    defs: None,
                 },
                 description: "Do something cool".to_string(),
                 strict: false,
@@ -1167,6 +1761,7 @@ mod tests {
                         properties: Some(serde_json::json!({})),
                         required: None,
                         r#type: "object".to_string(),
+                        defs: None,
                     },
                     output_schema: None,
                     title: None,
@@ -1182,6 +1777,7 @@ mod tests {
                         properties: Some(serde_json::json!({})),
                         required: None,
                         r#type: "object".to_string(),
+                        defs: None,
                     },
                     output_schema: None,
                     title: None,
@@ -1197,6 +1793,7 @@ mod tests {
                         properties: Some(serde_json::json!({})),
                         required: None,
                         r#type: "object".to_string(),
+                        defs: None,
                     },
                     output_schema: None,
                     title: None,
@@ -1247,6 +1844,7 @@ mod tests {
                         })),
                         required: None,
                         r#type: "object".to_string(),
+                        defs: None,
                     },
                     output_schema: None,
                     title: None,
@@ -1282,6 +1880,7 @@ mod tests {
                     )]),
                     required: None,
                     additional_properties: None,
+                    defs: None,
                 },
                 description: "Search docs".to_string(),
                 strict: false,
@@ -1290,7 +1889,7 @@ mod tests {
     }
 
     #[test]
-    fn test_mcp_tool_integer_normalized_to_number() {
+    fn test_mcp_tool_integer_keeps_bounds() {
         let model_family = find_family_for_model("gpt-5-codex")
             .expect("gpt-5-codex should be a valid model family");
         let mut features = Features::with_defaults();
@@ -1309,10 +1908,11 @@ mod tests {
                     name: "paginate".to_string(),
                     input_schema: ToolInputSchema {
                         properties: Some(serde_json::json!({
-                            "page": { "type": "integer" }
+                            "page": { "type": "integer", "minimum": 1 }
                         })),
                         required: None,
                         r#type: "object".to_string(),
+                        defs: None,
                     },
                     output_schema: None,
                     title: None,
@@ -1341,10 +1941,15 @@ mod tests {
                 parameters: JsonSchema::Object {
                     properties: BTreeMap::from([(
                         "page".to_string(),
-                        JsonSchema::Number { description: None }
+                        JsonSchema::Integer {
+                            description: None,
+                            minimum: Some(1.into()),
+                            maximum: None,
+                        }
                     )]),
                     required: None,
                     additional_properties: None,
+                    defs: None,
                 },
                 description: "Pagination".to_string(),
                 strict: false,
@@ -1377,6 +1982,7 @@ mod tests {
                         })),
                         required: None,
                         r#type: "object".to_string(),
+                        defs: None,
                     },
                     output_schema: None,
                     title: None,
@@ -1412,6 +2018,7 @@ mod tests {
                     )]),
                     required: None,
                     additional_properties: None,
+                    defs: None,
                 },
                 description: "Tags".to_string(),
                 strict: false,
@@ -1420,7 +2027,7 @@ mod tests {
     }
 
     #[test]
-    fn test_mcp_tool_anyof_defaults_to_string() {
+    fn test_mcp_tool_anyof_is_preserved() {
         let model_family = find_family_for_model("gpt-5-codex")
             .expect("gpt-5-codex should be a valid model family");
         let mut features = Features::with_defaults();
@@ -1443,6 +2050,7 @@ mod tests {
                         })),
                         required: None,
                         r#type: "object".to_string(),
+                        defs: None,
                     },
                     output_schema: None,
                     title: None,
@@ -1471,10 +2079,17 @@ mod tests {
                 parameters: JsonSchema::Object {
                     properties: BTreeMap::from([(
                         "value".to_string(),
-                        JsonSchema::String { description: None }
+                        JsonSchema::AnyOf {
+                            variants: vec![
+                                JsonSchema::String { description: None },
+                                JsonSchema::Number { description: None },
+                            ],
+                            description: None,
+                        }
                     )]),
                     required: None,
                     additional_properties: None,
+                    defs: None,
                 },
                 description: "AnyOf Value".to_string(),
                 strict: false,
@@ -1546,6 +2161,7 @@ mod tests {
                         })),
                         required: None,
                         r#type: "object".to_string(),
+                        defs: None,
                     },
                     output_schema: None,
                     title: None,
@@ -1607,18 +2223,253 @@ mod tests {
                                         ),]),
                                         required: Some(vec!["addtl_prop".to_string(),]),
                                         additional_properties: Some(false.into()),
+                                        defs: None,
                                     }
                                     .into()
                                 ),
+                                defs: None,
                             },
                         ),
                     ]),
                     required: None,
                     additional_properties: None,
+                    defs: None,
                 },
                 description: "Do something cool".to_string(),
                 strict: false,
             })
         );
     }
+
+    fn mcp_tool_from_input_schema(input_schema: JsonValue) -> mcp_types::Tool {
+        mcp_types::Tool {
+            name: "tool".to_string(),
+            input_schema: serde_json::from_value(input_schema)
+                .expect("input schema should deserialize"),
+            output_schema: None,
+            title: None,
+            annotations: None,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_mcp_tool_enum_and_integer_bounds_round_trip() {
+        // Shape used by the GitHub MCP server's `list_issues` tool.
+        let input_schema = json!({
+            "type": "object",
+            "properties": {
+                "owner": { "type": "string", "description": "Repository owner" },
+                "state": {
+                    "type": "string",
+                    "description": "Filter by state",
+                    "enum": ["open", "closed", "all"]
+                },
+                "labels": {
+                    "type": "array",
+                    "description": "Filter by labels",
+                    "items": { "type": "string" }
+                },
+                "perPage": {
+                    "type": "integer",
+                    "description": "Results per page",
+                    "minimum": 1,
+                    "maximum": 100
+                }
+            },
+            "required": ["owner"]
+        });
+
+        let tool = mcp_tool_to_openai_tool(
+            "github/list_issues".to_string(),
+            mcp_tool_from_input_schema(input_schema.clone()),
+        )
+        .expect("tool should convert");
+
+        assert_eq!(
+            serde_json::to_value(&tool.parameters).expect("serialize"),
+            input_schema
+        );
+    }
+
+    #[test]
+    fn test_mcp_tool_defs_refs_nullable_and_const_round_trip() {
+        // Shape emitted by pydantic-based (e.g. FastMCP) servers.
+        let tool = mcp_tool_to_openai_tool(
+            "tracker/create_issue".to_string(),
+            mcp_tool_from_input_schema(json!({
+                "type": "object",
+                "$defs": {
+                    "Priority": { "type": "string", "enum": ["low", "high"], "title": "Priority" },
+                    "Label": {
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string" },
+                            "color": {
+                                "anyOf": [{ "type": "string" }, { "type": "null" }],
+                                "default": null
+                            }
+                        },
+                        "required": ["name"]
+                    }
+                },
+                "properties": {
+                    "priority": {
+                        "allOf": [{ "$ref": "#/$defs/Priority" }],
+                        "description": "How urgent"
+                    },
+                    "labels": { "type": "array", "items": { "$ref": "#/$defs/Label" } },
+                    "due": {
+                        "anyOf": [{ "type": "string", "format": "date" }, { "type": "null" }],
+                        "description": "Due date"
+                    },
+                    "kind": { "const": "issue" }
+                },
+                "required": ["priority"]
+            })),
+        )
+        .expect("tool should convert");
+
+        assert_eq!(
+            serde_json::to_value(&tool.parameters).expect("serialize"),
+            json!({
+                "type": "object",
+                "$defs": {
+                    "Priority": { "type": "string", "enum": ["low", "high"] },
+                    "Label": {
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string" },
+                            "color": { "anyOf": [{ "type": "string" }, { "type": "null" }] }
+                        },
+                        "required": ["name"]
+                    }
+                },
+                "properties": {
+                    "priority": { "$ref": "#/$defs/Priority", "description": "How urgent" },
+                    "labels": { "type": "array", "items": { "$ref": "#/$defs/Label" } },
+                    "due": {
+                        "anyOf": [{ "type": "string" }, { "type": "null" }],
+                        "description": "Due date"
+                    },
+                    "kind": { "type": "string", "const": "issue" }
+                },
+                "required": ["priority"]
+            })
+        );
+
+        let parameters = serde_json::to_value(&tool.parameters).expect("serialize");
+        let tools = [ToolSpec::Function(tool)];
+        let chat_tools =
+            create_tools_json_for_chat_completions_api(&tools, false).expect("chat tools");
+        assert_eq!(chat_tools[0]["function"]["parameters"], parameters);
+
+        let chat_tools =
+            create_tools_json_for_chat_completions_api(&tools, true).expect("chat tools");
+        assert_eq!(
+            chat_tools[0]["function"]["parameters"],
+            json!({
+                "type": "object",
+                "properties": {
+                    "priority": {
+                        "type": "string",
+                        "enum": ["low", "high"],
+                        "description": "How urgent"
+                    },
+                    "labels": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "name": { "type": "string" },
+                                "color": { "type": "string" }
+                            },
+                            "required": ["name"]
+                        }
+                    },
+                    "due": { "type": "string", "description": "Due date" },
+                    "kind": { "type": "string", "enum": ["issue"] }
+                },
+                "required": ["priority"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_json_schema_normalizes_type_unions_nullable_and_definitions() {
+        let schema: JsonSchema = serde_json::from_value(json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": ["string", "null"] },
+                "count": { "type": "integer", "nullable": true, "description": "How many" },
+                "owner": { "$ref": "#/definitions/User" }
+            }
+        }))
+        .expect("schema should deserialize");
+
+        assert_eq!(
+            schema,
+            JsonSchema::Object {
+                properties: BTreeMap::from([
+                    (
+                        "name".to_string(),
+                        JsonSchema::AnyOf {
+                            variants: vec![
+                                JsonSchema::String { description: None },
+                                JsonSchema::Null { description: None },
+                            ],
+                            description: None,
+                        }
+                    ),
+                    (
+                        "count".to_string(),
+                        JsonSchema::AnyOf {
+                            variants: vec![
+                                JsonSchema::Integer {
+                                    description: None,
+                                    minimum: None,
+                                    maximum: None,
+                                },
+                                JsonSchema::Null { description: None },
+                            ],
+                            description: Some("How many".to_string()),
+                        }
+                    ),
+                    (
+                        "owner".to_string(),
+                        JsonSchema::Ref {
+                            reference: "#/$defs/User".to_string(),
+                            description: None,
+                        }
+                    ),
+                ]),
+                required: None,
+                additional_properties: None,
+                defs: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_chat_completions_downgrade_stops_expanding_recursive_refs() {
+        let schema: JsonSchema = serde_json::from_value(json!({
+            "type": "object",
+            "$defs": {
+                "Node": {
+                    "type": "object",
+                    "properties": {
+                        "children": { "type": "array", "items": { "$ref": "#/$defs/Node" } }
+                    }
+                }
+            },
+            "properties": { "root": { "$ref": "#/$defs/Node" } }
+        }))
+        .expect("schema should deserialize");
+
+        let downgraded =
+            serde_json::to_string(&schema.downgrade_for_chat_completions()).expect("serialize");
+
+        assert!(!downgraded.contains("$ref"));
+        assert!(!downgraded.contains("$defs"));
+    }
 }
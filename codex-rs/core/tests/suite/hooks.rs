@@ -0,0 +1,259 @@
+#![cfg(not(target_os = "windows"))]
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use anyhow::Result;
+use codex_core::config_types::HookConfig;
+use codex_core::config_types::HooksConfig;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use codex_protocol::config_types::ReasoningSummary;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::ev_response_created;
+use core_test_support::responses::mount_sse_once;
+use core_test_support::responses::sse;
+use core_test_support::responses::start_mock_server;
+use core_test_support::skip_if_no_network;
+use core_test_support::test_codex::TestCodex;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use serde_json::Value;
+use serde_json::json;
+
+fn sh_hook(script: &str, matcher: Option<&str>) -> HookConfig {
+    HookConfig {
+        command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+        matcher: matcher.map(str::to_string),
+        timeout_ms: Some(10_000),
+    }
+}
+
+async fn submit_turn(test: &TestCodex, prompt: &str) -> Result<()> {
+    let session_model = test.session_configured.model.clone();
+
+    test.codex
+        .submit(Op::UserTurn {
+            items: vec![InputItem::Text {
+                text: prompt.into(),
+            }],
+            final_output_json_schema: None,
+            cwd: test.cwd.path().to_path_buf(),
+            approval_policy: AskForApproval::Never,
+            sandbox_policy: SandboxPolicy::DangerFullAccess,
+            model: session_model,
+            effort: None,
+            summary: ReasoningSummary::Auto,
+        })
+        .await?;
+
+    wait_for_event(&test.codex, |event| {
+        matches!(event, EventMsg::TaskComplete(_))
+    })
+    .await;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn pre_tool_use_hook_blocks_shell_call() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let server = start_mock_server().await;
+    let mut builder = test_codex().with_config(|config| {
+        config.hooks = HooksConfig {
+            pre_tool_use: vec![sh_hook(
+                r#"cat >/dev/null; echo '{"decision":"block","reason":"shell is disabled here"}'"#,
+                Some("shell"),
+            )],
+            ..Default::default()
+        };
+    });
+    let test = builder.build(&server).await?;
+
+    let call_id = "blocked-shell";
+    let marker = test.cwd.path().join("marker.txt");
+    let args = json!({
+        "command": ["touch", marker.to_string_lossy()],
+        "timeout_ms": 1_000,
+    });
+    mount_sse_once(
+        &server,
+        sse(vec![
+            ev_response_created("resp-1"),
+            ev_function_call(call_id, "shell", &serde_json::to_string(&args)?),
+            ev_completed("resp-1"),
+        ]),
+    )
+    .await;
+    let second_mock = mount_sse_once(
+        &server,
+        sse(vec![
+            ev_assistant_message("msg-1", "done"),
+            ev_completed("resp-2"),
+        ]),
+    )
+    .await;
+
+    submit_turn(&test, "create the marker").await?;
+
+    let item = second_mock.single_request().function_call_output(call_id);
+    let output = item
+        .get("output")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    assert_eq!(
+        output,
+        "tool call blocked by pre-tool-use hook: shell is disabled here"
+    );
+    assert!(!marker.exists(), "blocked command should not run");
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn post_tool_use_hook_appends_feedback() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let server = start_mock_server().await;
+    let mut builder = test_codex().with_config(|config| {
+        config.hooks = HooksConfig {
+            post_tool_use: vec![sh_hook(
+                r#"cat >/dev/null; echo '{"additional_context":"Remember to run the tests."}'"#,
+                None,
+            )],
+            ..Default::default()
+        };
+    });
+    let test = builder.build(&server).await?;
+
+    let call_id = "echo-shell";
+    let args = json!({
+        "command": ["echo", "hello"],
+        "timeout_ms": 1_000,
+    });
+    mount_sse_once(
+        &server,
+        sse(vec![
+            ev_response_created("resp-1"),
+            ev_function_call(call_id, "shell", &serde_json::to_string(&args)?),
+            ev_completed("resp-1"),
+        ]),
+    )
+    .await;
+    let second_mock = mount_sse_once(
+        &server,
+        sse(vec![
+            ev_assistant_message("msg-1", "done"),
+            ev_completed("resp-2"),
+        ]),
+    )
+    .await;
+
+    submit_turn(&test, "say hello").await?;
+
+    let item = second_mock.single_request().function_call_output(call_id);
+    let output = item
+        .get("output")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    assert!(output.contains("hello"), "missing tool output: {output}");
+    assert!(
+        output.ends_with("\n\nRemember to run the tests."),
+        "missing hook feedback: {output}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn user_prompt_submit_hook_blocks_input_sent_during_a_task() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let server = start_mock_server().await;
+    let mut builder = test_codex().with_config(|config| {
+        config.hooks = HooksConfig {
+            user_prompt_submit: vec![sh_hook(
+                r#"case "$(cat)" in *forbidden-topic*) echo '{"decision":"block","reason":"not allowed"}';; esac"#,
+                None,
+            )],
+            ..Default::default()
+        };
+    });
+    let test = builder.build(&server).await?;
+
+    let call_id = "slow-shell";
+    let args = json!({
+        "command": ["sleep", "2"],
+        "timeout_ms": 10_000,
+    });
+    mount_sse_once(
+        &server,
+        sse(vec![
+            ev_response_created("resp-1"),
+            ev_function_call(call_id, "shell", &serde_json::to_string(&args)?),
+            ev_completed("resp-1"),
+        ]),
+    )
+    .await;
+    let second_mock = mount_sse_once(
+        &server,
+        sse(vec![
+            ev_assistant_message("msg-1", "done"),
+            ev_completed("resp-2"),
+        ]),
+    )
+    .await;
+
+    let session_model = test.session_configured.model.clone();
+    test.codex
+        .submit(Op::UserTurn {
+            items: vec![InputItem::Text {
+                text: "wait a bit".into(),
+            }],
+            final_output_json_schema: None,
+            cwd: test.cwd.path().to_path_buf(),
+            approval_policy: AskForApproval::Never,
+            sandbox_policy: SandboxPolicy::DangerFullAccess,
+            model: session_model,
+            effort: None,
+            summary: ReasoningSummary::Auto,
+        })
+        .await?;
+    wait_for_event(&test.codex, |event| {
+        matches!(event, EventMsg::ExecCommandBegin(_))
+    })
+    .await;
+
+    test.codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "tell me about the forbidden-topic".into(),
+            }],
+        })
+        .await?;
+    let EventMsg::Error(error) =
+        wait_for_event(&test.codex, |event| matches!(event, EventMsg::Error(_))).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(
+        error.message,
+        "Prompt blocked by user-prompt-submit hook: not allowed"
+    );
+    wait_for_event(&test.codex, |event| {
+        matches!(event, EventMsg::TaskComplete(_))
+    })
+    .await;
+
+    let body = second_mock.single_request().body_json().to_string();
+    assert!(
+        !body.contains("forbidden-topic"),
+        "blocked input reached the model: {body}"
+    );
+
+    Ok(())
+}
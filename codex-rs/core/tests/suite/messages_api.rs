@@ -0,0 +1,344 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use codex_core::WireApi;
+use codex_core::features::Feature;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use codex_protocol::config_types::ReasoningSummary;
+use core_test_support::responses::start_mock_server;
+use core_test_support::skip_if_no_network;
+use core_test_support::test_codex::TestCodex;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use serde_json::json;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+/// Formats events the way the Messages API streams them: every event carries
+/// both an `event:` name and its JSON `data:`.
+fn messages_sse(events: Vec<Value>) -> String {
+    events
+        .into_iter()
+        .map(|event| {
+            format!(
+                "event: {}\ndata: {event}\n\n",
+                event["type"].as_str().unwrap()
+            )
+        })
+        .collect()
+}
+
+fn message_start(id: &str, usage: Value) -> Value {
+    json!({
+        "type": "message_start",
+        "message": {
+            "id": id,
+            "type": "message",
+            "role": "assistant",
+            "content": [],
+            "usage": usage
+        }
+    })
+}
+
+fn message_end(stop_reason: &str, output_tokens: u64) -> Vec<Value> {
+    vec![
+        json!({
+            "type": "message_delta",
+            "delta": { "stop_reason": stop_reason },
+            "usage": { "output_tokens": output_tokens }
+        }),
+        json!({ "type": "message_stop" }),
+    ]
+}
+
+async fn mount_messages_once(server: &MockServer, body: String) {
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_raw(body, "text/event-stream"),
+        )
+        .up_to_n_times(1)
+        .mount(server)
+        .await;
+}
+
+async fn messages_requests(server: &MockServer) -> Vec<(Value, Option<String>)> {
+    server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|request| request.url.path() == "/v1/messages")
+        .map(|request| {
+            let version = request
+                .headers
+                .get("anthropic-version")
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            (request.body_json::<Value>().unwrap(), version)
+        })
+        .collect()
+}
+
+async fn submit_turn(test: &TestCodex, text: &str) {
+    test.codex
+        .submit(Op::UserTurn {
+            items: vec![InputItem::Text { text: text.into() }],
+            final_output_json_schema: None,
+            cwd: test.cwd.path().to_path_buf(),
+            approval_policy: AskForApproval::Never,
+            sandbox_policy: SandboxPolicy::DangerFullAccess,
+            model: test.session_configured.model.clone(),
+            effort: None,
+            summary: ReasoningSummary::Auto,
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn streams_text_and_reports_token_usage() {
+    skip_if_no_network!();
+
+    let server = start_mock_server().await;
+    let mut events = vec![
+        message_start(
+            "msg_1",
+            json!({
+                "input_tokens": 12,
+                "cache_creation_input_tokens": 100,
+                "cache_read_input_tokens": 400,
+                "output_tokens": 1
+            }),
+        ),
+        json!({
+            "type": "content_block_start",
+            "index": 0,
+            "content_block": { "type": "text", "text": "" }
+        }),
+        json!({ "type": "ping" }),
+        json!({
+            "type": "content_block_delta",
+            "index": 0,
+            "delta": { "type": "text_delta", "text": "Hello" }
+        }),
+        json!({
+            "type": "content_block_delta",
+            "index": 0,
+            "delta": { "type": "text_delta", "text": ", world" }
+        }),
+        json!({ "type": "content_block_stop", "index": 0 }),
+    ];
+    events.extend(message_end("end_turn", 7));
+    mount_messages_once(&server, messages_sse(events)).await;
+
+    let test = test_codex()
+        .with_config(|config| {
+            config.model_provider.wire_api = WireApi::Messages;
+        })
+        .build(&server)
+        .await
+        .unwrap();
+
+    submit_turn(&test, "hi").await;
+
+    let mut agent_message = None;
+    let mut token_usage = None;
+    wait_for_event(&test.codex, |event| match event {
+        EventMsg::AgentMessage(message) => {
+            agent_message = Some(message.message.clone());
+            false
+        }
+        EventMsg::TokenCount(count) => {
+            if let Some(info) = &count.info {
+                token_usage = Some(info.last_token_usage.clone());
+            }
+            false
+        }
+        EventMsg::TaskComplete(_) => true,
+        _ => false,
+    })
+    .await;
+
+    assert_eq!(agent_message.as_deref(), Some("Hello, world"));
+    let usage = token_usage.expect("token usage");
+    assert_eq!(
+        (
+            usage.input_tokens,
+            usage.cached_input_tokens,
+            usage.output_tokens,
+            usage.total_tokens
+        ),
+        (512, 400, 7, 519)
+    );
+
+    let requests = messages_requests(&server).await;
+    assert_eq!(requests.len(), 1);
+    let (body, version) = &requests[0];
+    assert_eq!(version.as_deref(), Some("2023-06-01"));
+    assert_eq!(body["stream"], json!(true));
+    assert_eq!(
+        body["system"][0]["cache_control"],
+        json!({ "type": "ephemeral" })
+    );
+
+    let messages = body["messages"].as_array().unwrap();
+    let last_message = messages.last().unwrap();
+    assert_eq!(last_message["role"], "user");
+    let last_block = last_message["content"].as_array().unwrap().last().unwrap();
+    assert_eq!(last_block["text"], "hi");
+    assert_eq!(last_block["cache_control"], json!({ "type": "ephemeral" }));
+
+    let tools = body["tools"].as_array().unwrap();
+    assert!(tools.iter().all(|tool| tool.get("input_schema").is_some()));
+    assert_eq!(
+        tools.last().unwrap()["cache_control"],
+        json!({ "type": "ephemeral" })
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn replays_thinking_and_tool_results() {
+    skip_if_no_network!();
+
+    let server = start_mock_server().await;
+
+    let plan_args = json!({
+        "plan": [{ "step": "Say hello", "status": "in_progress" }]
+    })
+    .to_string();
+    let (args_head, args_tail) = plan_args.split_at(10);
+    let mut first = vec![
+        message_start("msg_1", json!({ "input_tokens": 20, "output_tokens": 1 })),
+        json!({
+            "type": "content_block_start",
+            "index": 0,
+            "content_block": { "type": "thinking", "thinking": "" }
+        }),
+        json!({
+            "type": "content_block_delta",
+            "index": 0,
+            "delta": { "type": "thinking_delta", "thinking": "I should plan." }
+        }),
+        json!({
+            "type": "content_block_delta",
+            "index": 0,
+            "delta": { "type": "signature_delta", "signature": "sig-123" }
+        }),
+        json!({ "type": "content_block_stop", "index": 0 }),
+        json!({
+            "type": "content_block_start",
+            "index": 1,
+            "content_block": {
+                "type": "tool_use",
+                "id": "toolu_1",
+                "name": "update_plan",
+                "input": {}
+            }
+        }),
+        json!({
+            "type": "content_block_delta",
+            "index": 1,
+            "delta": { "type": "input_json_delta", "partial_json": args_head }
+        }),
+        json!({
+            "type": "content_block_delta",
+            "index": 1,
+            "delta": { "type": "input_json_delta", "partial_json": args_tail }
+        }),
+        json!({ "type": "content_block_stop", "index": 1 }),
+    ];
+    first.extend(message_end("tool_use", 30));
+    mount_messages_once(&server, messages_sse(first)).await;
+
+    let mut second = vec![
+        message_start("msg_2", json!({ "input_tokens": 40, "output_tokens": 1 })),
+        json!({
+            "type": "content_block_start",
+            "index": 0,
+            "content_block": { "type": "text", "text": "" }
+        }),
+        json!({
+            "type": "content_block_delta",
+            "index": 0,
+            "delta": { "type": "text_delta", "text": "Planned." }
+        }),
+        json!({ "type": "content_block_stop", "index": 0 }),
+    ];
+    second.extend(message_end("end_turn", 3));
+    mount_messages_once(&server, messages_sse(second)).await;
+
+    let test = test_codex()
+        .with_config(|config| {
+            config.model_provider.wire_api = WireApi::Messages;
+            config.features.enable(Feature::PlanTool);
+        })
+        .build(&server)
+        .await
+        .unwrap();
+
+    submit_turn(&test, "make a plan").await;
+
+    let mut reasoning = None;
+    let mut saw_plan_update = false;
+    wait_for_event(&test.codex, |event| match event {
+        EventMsg::AgentReasoning(event) => {
+            reasoning = Some(event.text.clone());
+            false
+        }
+        EventMsg::PlanUpdate(_) => {
+            saw_plan_update = true;
+            false
+        }
+        EventMsg::TaskComplete(_) => true,
+        _ => false,
+    })
+    .await;
+
+    assert_eq!(reasoning.as_deref(), Some("I should plan."));
+    assert!(saw_plan_update, "expected PlanUpdate event");
+
+    let requests = messages_requests(&server).await;
+    assert_eq!(requests.len(), 2);
+    let messages = requests[1].0["messages"].as_array().unwrap().clone();
+    let tail = &messages[messages.len() - 2..];
+
+    assert_eq!(
+        tail[0],
+        json!({
+            "role": "assistant",
+            "content": [
+                { "type": "thinking", "thinking": "I should plan.", "signature": "sig-123" },
+                {
+                    "type": "tool_use",
+                    "id": "toolu_1",
+                    "name": "update_plan",
+                    "input": serde_json::from_str::<Value>(&plan_args).unwrap()
+                }
+            ]
+        })
+    );
+    assert_eq!(
+        tail[1],
+        json!({
+            "role": "user",
+            "content": [{
+                "type": "tool_result",
+                "tool_use_id": "toolu_1",
+                "content": "Plan updated",
+                "cache_control": { "type": "ephemeral" }
+            }]
+        })
+    );
+}
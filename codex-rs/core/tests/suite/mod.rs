@@ -10,9 +10,12 @@ mod exec;
 mod exec_stream_events;
 mod fork_conversation;
 mod grep_files;
+mod hooks;
 mod json_result;
 mod list_dir;
 mod live_cli;
+mod messages_api;
+mod model_fallback;
 mod model_overrides;
 mod model_tools;
 mod otel;
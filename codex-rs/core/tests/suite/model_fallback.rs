@@ -0,0 +1,137 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use codex_core::config_types::ModelFallback;
+use codex_core::model_family::find_family_for_model;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::RolloutItem;
+use codex_core::protocol::RolloutLine;
+use codex_protocol::config_types::FallbackErrorClass;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_response_created;
+use core_test_support::responses::mount_sse_once_match;
+use core_test_support::responses::sse;
+use core_test_support::responses::start_mock_server;
+use core_test_support::skip_if_no_network;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use wiremock::Mock;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::body_partial_json;
+use wiremock::matchers::method;
+use wiremock::matchers::path_regex;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn server_errors_fail_over_to_the_next_model() {
+    skip_if_no_network!();
+
+    let server = start_mock_server().await;
+
+    Mock::given(method("POST"))
+        .and(path_regex(".*/responses$"))
+        .and(body_partial_json(json!({ "model": "gpt-5-codex" })))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+    let fallback_mock = mount_sse_once_match(
+        &server,
+        body_partial_json(json!({ "model": "gpt-5" })),
+        sse(vec![
+            ev_response_created("resp-1"),
+            ev_assistant_message("msg-1", "answered by the fallback"),
+            ev_completed("resp-1"),
+        ]),
+    )
+    .await;
+
+    let test = test_codex()
+        .with_config(|config| {
+            config.model = "gpt-5-codex".to_string();
+            config.model_family = find_family_for_model("gpt-5-codex").unwrap();
+            config.model_provider.request_max_retries = Some(0);
+            config.model_provider.stream_max_retries = Some(0);
+            config.model_fallbacks = vec![ModelFallback {
+                model: "gpt-5".to_string(),
+                model_provider_id: config.model_provider_id.clone(),
+                model_provider: config.model_provider.clone(),
+            }];
+        })
+        .build(&server)
+        .await
+        .unwrap();
+    let codex = test.codex;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "hello".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let mut background_message = None;
+    let mut agent_message = None;
+    wait_for_event(&codex, |event| match event {
+        EventMsg::BackgroundEvent(event) => {
+            background_message = Some(event.message.clone());
+            false
+        }
+        EventMsg::AgentMessage(event) => {
+            agent_message = Some(event.message.clone());
+            false
+        }
+        EventMsg::Error(event) => panic!("unexpected error: {}", event.message),
+        EventMsg::TaskComplete(_) => true,
+        _ => false,
+    })
+    .await;
+
+    let background_message = background_message.expect("fallback background event");
+    assert!(
+        background_message.contains("Switched to fallback model gpt-5"),
+        "unexpected background message: {background_message}"
+    );
+    assert_eq!(agent_message.as_deref(), Some("answered by the fallback"));
+
+    // The fallback retries the same turn.
+    let request = fallback_mock.single_request();
+    assert!(
+        request
+            .input()
+            .iter()
+            .any(|item| item["role"] == "user" && item.to_string().contains("hello")),
+        "fallback request should carry the user message"
+    );
+
+    codex.submit(Op::Shutdown).await.unwrap();
+    wait_for_event(&codex, |event| matches!(event, EventMsg::ShutdownComplete)).await;
+
+    let rollout = std::fs::read_to_string(&test.session_configured.rollout_path).unwrap();
+    let turn_contexts: Vec<_> = rollout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<RolloutLine>(line).ok())
+        .filter_map(|line| match line.item {
+            RolloutItem::TurnContext(item) => Some(item),
+            _ => None,
+        })
+        .collect();
+    let models: Vec<_> = turn_contexts
+        .iter()
+        .map(|item| item.model.as_str())
+        .collect();
+    assert_eq!(models, vec!["gpt-5-codex", "gpt-5"]);
+
+    assert_eq!(turn_contexts[0].model_fallback, None);
+    let fallback = turn_contexts[1]
+        .model_fallback
+        .as_ref()
+        .expect("fallback recorded");
+    assert_eq!(fallback.from_model, "gpt-5-codex");
+    assert_eq!(fallback.reason, FallbackErrorClass::ServerError);
+    assert_eq!(fallback.fallback_index, 0);
+}
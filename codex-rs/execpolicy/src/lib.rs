@@ -40,6 +40,13 @@ pub use valid_exec::ValidExec;
 const DEFAULT_POLICY: &str = include_str!("default.policy");
 
 pub fn get_default_policy() -> starlark::Result<Policy> {
-    let parser = PolicyParser::new("#default", DEFAULT_POLICY);
-    parser.parse()
+    get_default_policy_with(Vec::new())
+}
+
+/// Parses the default policy followed by `extra` policy files, which can add
+/// programs or forbid invocations that the default policy would allow.
+pub fn get_default_policy_with(extra: Vec<PolicyParser>) -> starlark::Result<Policy> {
+    let mut parsers = vec![PolicyParser::new("#default", DEFAULT_POLICY)];
+    parsers.extend(extra);
+    PolicyParser::parse_all(&parsers)
 }
@@ -69,20 +69,27 @@ impl Policy {
             }
         }
 
-        let mut last_err = Err(Error::NoSpecForProgram {
+        // A spec that marks this invocation as forbidden wins over any spec
+        // that merely matches it, regardless of the order they were defined
+        // in (e.g. a user policy forbidding something the default allows).
+        let mut first_match = None;
+        let mut last_err = Error::NoSpecForProgram {
             program: program.clone(),
-        });
+        };
         if let Some(spec_list) = self.programs.get_vec(program) {
             for spec in spec_list {
                 match spec.check(exec_call) {
-                    Ok(matched_exec) => return Ok(matched_exec),
+                    Ok(forbidden @ MatchedExec::Forbidden { .. }) => return Ok(forbidden),
+                    Ok(matched_exec) => {
+                        first_match.get_or_insert(matched_exec);
+                    }
                     Err(err) => {
-                        last_err = Err(err);
+                        last_err = err;
                     }
                 }
             }
         }
-        last_err
+        first_match.ok_or(last_err)
     }
 
     pub fn check_each_good_list_individually(&self) -> Vec<PositiveExampleFailedCheck> {
@@ -0,0 +1,75 @@
+extern crate codex_execpolicy;
+
+use codex_execpolicy::ExecCall;
+use codex_execpolicy::Forbidden;
+use codex_execpolicy::MatchedExec;
+use codex_execpolicy::Policy;
+use codex_execpolicy::PolicyParser;
+use codex_execpolicy::get_default_policy_with;
+
+const USER_POLICY: &str = r#"
+define_program(
+    program="cp",
+    args=[ARG_RFILES, ARG_WFILE],
+    forbidden="copying files is disabled in this project",
+)
+
+define_program(
+    program="make",
+    args=[ARG_OPAQUE_VALUE],
+)
+
+forbid_program_regex("^rm$", "use trash instead of rm")
+"#;
+
+#[expect(clippy::expect_used)]
+fn setup() -> Policy {
+    get_default_policy_with(vec![PolicyParser::new("#user", USER_POLICY)])
+        .expect("failed to load policies")
+}
+
+#[test]
+fn extra_policy_adds_programs_to_defaults() {
+    let policy = setup();
+
+    let make = ExecCall::new("make", &["test"]);
+    assert!(matches!(policy.check(&make), Ok(MatchedExec::Match { .. })));
+
+    let ls = ExecCall::new("ls", &["-l"]);
+    assert!(matches!(policy.check(&ls), Ok(MatchedExec::Match { .. })));
+}
+
+#[test]
+fn forbidden_spec_wins_over_default_match() {
+    let policy = setup();
+    let cp = ExecCall::new("cp", &["foo", "bar"]);
+    match policy.check(&cp) {
+        Ok(MatchedExec::Forbidden {
+            cause: Forbidden::Exec { .. },
+            reason,
+        }) => assert_eq!(reason, "copying files is disabled in this project"),
+        other => panic!("expected cp to be forbidden, got {other:?}"),
+    }
+}
+
+#[test]
+fn forbidden_program_regex_applies() {
+    let policy = setup();
+    let rm = ExecCall::new("rm", &["-rf", "target"]);
+    match policy.check(&rm) {
+        Ok(MatchedExec::Forbidden {
+            cause: Forbidden::Program { .. },
+            reason,
+        }) => assert_eq!(reason, "use trash instead of rm"),
+        other => panic!("expected rm to be forbidden, got {other:?}"),
+    }
+}
+
+#[test]
+fn policy_files_do_not_share_variables() {
+    let result = get_default_policy_with(vec![PolicyParser::new(
+        "#user",
+        r#"define_program(program="sed", options=common_sed_flags, args=[ARG_RFILES])"#,
+    )]);
+    assert!(result.is_err());
+}
@@ -393,6 +393,18 @@ def define_struct(
             )
         )
 
+    # Special-case: keep schema definitions referenced by `$ref` in tool inputs
+    if name == "ToolInputSchema":
+        fields.append(
+            StructField(
+                "pub",
+                "defs",
+                "Option<serde_json::Value>",
+                '#[serde(rename = "$defs", alias = "definitions", default, skip_serializing_if = "Option::is_none")]',
+                "Not declared by the MCP schema, but servers use it for `$ref` targets.",
+            )
+        )
+
     if implements_request_trait(name):
         add_trait_impl(name, "ModelContextProtocolRequest", fields, out)
     elif implements_notification_trait(name):
@@ -1379,6 +1379,14 @@ pub struct ToolInputSchema {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub required: Option<Vec<String>>,
     pub r#type: String, // &'static str = "object"
+    // Not declared by the MCP schema, but servers use it for `$ref` targets.
+    #[serde(
+        rename = "$defs",
+        alias = "definitions",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub defs: Option<serde_json::Value>,
 }
 
 /// Additional properties describing a Tool to clients.
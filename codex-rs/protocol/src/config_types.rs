@@ -59,3 +59,22 @@ pub enum SandboxMode {
     #[serde(rename = "danger-full-access")]
     DangerFullAccess,
 }
+
+/// Class of turn failure that lets a profile fail over to its next
+/// `model_fallbacks` entry.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Serialize, Display, TS)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum FallbackErrorClass {
+    /// 5xx responses that persist beyond `request_max_retries`.
+    ServerError,
+    /// 429 responses that persist beyond `request_max_retries`.
+    RateLimit,
+    /// Connection failures and streams that keep disconnecting beyond
+    /// `stream_max_retries`.
+    Stream,
+    /// The account's usage limit was reached.
+    UsageLimit,
+    /// The conversation no longer fits in the model's context window.
+    ContextWindow,
+}
@@ -9,6 +9,10 @@ use ts_rs::TS;
 /// - Full slash prefix: `"/{PROMPTS_CMD_PREFIX}:"`
 pub const PROMPTS_CMD_PREFIX: &str = "prompts";
 
+/// Namespace for prompts exposed by MCP servers. The command token after '/'
+/// is `"{MCP_PROMPTS_CMD_PREFIX}:server:name"`.
+pub const MCP_PROMPTS_CMD_PREFIX: &str = "mcp";
+
 #[derive(Serialize, Deserialize, Debug, Clone, TS)]
 pub struct CustomPrompt {
     pub name: String,
@@ -17,3 +21,30 @@ pub struct CustomPrompt {
     pub description: Option<String>,
     pub argument_hint: Option<String>,
 }
+
+/// A prompt advertised by an MCP server through `prompts/list`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+pub struct McpPrompt {
+    /// Name of the MCP server, as configured in `mcp_servers`.
+    pub server: String,
+    pub name: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<McpPromptArgument>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+pub struct McpPromptArgument {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+impl McpPrompt {
+    /// The command token used to invoke this prompt, without the leading '/'.
+    pub fn command_name(&self) -> String {
+        format!("{MCP_PROMPTS_CMD_PREFIX}:{}:{}", self.server, self.name)
+    }
+}
@@ -11,9 +11,11 @@ use std::str::FromStr;
 use std::time::Duration;
 
 use crate::ConversationId;
+use crate::config_types::FallbackErrorClass;
 use crate::config_types::ReasoningEffort as ReasoningEffortConfig;
 use crate::config_types::ReasoningSummary as ReasoningSummaryConfig;
 use crate::custom_prompts::CustomPrompt;
+use crate::custom_prompts::McpPrompt;
 use crate::message_history::HistoryEntry;
 use crate::models::ContentItem;
 use crate::models::ResponseItem;
@@ -166,6 +168,18 @@ pub enum Op {
     /// Request the list of available custom prompts.
     ListCustomPrompts,
 
+    /// Fetch a prompt from an MCP server with `prompts/get` and submit the
+    /// returned messages as user input, exactly like [`Op::UserInput`].
+    RunMcpPrompt {
+        /// Name of the MCP server that exposes the prompt.
+        server: String,
+        /// Name of the prompt on that server.
+        name: String,
+        /// Values for the prompt's declared arguments.
+        #[serde(default)]
+        arguments: HashMap<String, String>,
+    },
+
     /// Request the agent to summarize the current conversation context.
     /// The agent will use its existing context (either conversation history or previous response id)
     /// to generate a summary which will be returned as an AgentMessage event.
@@ -505,6 +519,10 @@ pub enum EventMsg {
     /// List of MCP tools available to the agent.
     McpListToolsResponse(McpListToolsResponseEvent),
 
+    /// MCP tools were re-listed after a server sent
+    /// `notifications/tools/list_changed`.
+    McpToolsUpdated(McpToolsUpdatedEvent),
+
     /// List of custom prompts available to the agent.
     ListCustomPromptsResponse(ListCustomPromptsResponseEvent),
 
@@ -1035,6 +1053,24 @@ pub struct TurnContextItem {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub effort: Option<ReasoningEffortConfig>,
     pub summary: ReasoningSummaryConfig,
+    /// Set when the turn runs on a fallback model because an earlier model
+    /// failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_fallback: Option<ModelFallbackItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+pub struct ModelFallbackItem {
+    /// Provider id of the fallback model.
+    pub model_provider: String,
+    /// Model that failed.
+    pub from_model: String,
+    /// Provider id of the model that failed.
+    pub from_model_provider: String,
+    pub reason: FallbackErrorClass,
+    /// Position of the fallback model in the profile's `model_fallbacks`.
+    #[serde(default)]
+    pub fallback_index: usize,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -1178,6 +1214,10 @@ pub struct ExecApprovalRequestEvent {
     /// Optional human-readable reason for the approval (e.g. retry without sandbox).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<String>,
+    /// Command prefix that answering with `ApprovedForProject` allows for the
+    /// whole project. `None` when the request cannot be approved per project.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_prefix: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -1244,6 +1284,14 @@ pub struct GetHistoryEntryResponseEvent {
     pub entry: Option<HistoryEntry>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct McpToolsUpdatedEvent {
+    /// Servers whose tools were re-listed, sorted by name.
+    pub servers: Vec<String>,
+    /// Fully qualified tool name -> tool definition, after the refresh.
+    pub tools: std::collections::HashMap<String, McpTool>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct McpListToolsResponseEvent {
     /// Fully qualified tool name -> tool definition.
@@ -1278,6 +1326,9 @@ impl fmt::Display for McpAuthStatus {
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct ListCustomPromptsResponseEvent {
     pub custom_prompts: Vec<CustomPrompt>,
+    /// Prompts exposed by connected MCP servers.
+    #[serde(default)]
+    pub mcp_prompts: Vec<McpPrompt>,
 }
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
@@ -1318,6 +1369,11 @@ pub enum ReviewDecision {
     /// remainder of the session.
     ApprovedForSession,
 
+    /// User has approved this command and wants to automatically approve
+    /// future commands that share its prefix (e.g. `cargo test`) whenever Codex
+    /// runs in the same project. The rule is persisted under `CODEX_HOME`.
+    ApprovedForProject,
+
     /// User has denied this command and the agent should not execute it, but
     /// it should continue the session and try something else.
     #[default]
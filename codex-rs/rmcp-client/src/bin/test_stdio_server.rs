@@ -5,11 +5,24 @@ use std::sync::Arc;
 use rmcp::ErrorData as McpError;
 use rmcp::ServiceExt;
 use rmcp::handler::server::ServerHandler;
+use rmcp::model::AnnotateAble;
 use rmcp::model::CallToolRequestParam;
 use rmcp::model::CallToolResult;
+use rmcp::model::GetPromptRequestParam;
+use rmcp::model::GetPromptResult;
 use rmcp::model::JsonObject;
+use rmcp::model::ListPromptsResult;
+use rmcp::model::ListResourcesResult;
 use rmcp::model::ListToolsResult;
 use rmcp::model::PaginatedRequestParam;
+use rmcp::model::Prompt;
+use rmcp::model::PromptArgument;
+use rmcp::model::PromptMessage;
+use rmcp::model::PromptMessageRole;
+use rmcp::model::RawResource;
+use rmcp::model::ReadResourceRequestParam;
+use rmcp::model::ReadResourceResult;
+use rmcp::model::ResourceContents;
 use rmcp::model::ServerCapabilities;
 use rmcp::model::ServerInfo;
 use rmcp::model::Tool;
@@ -17,6 +30,9 @@ use serde::Deserialize;
 use serde_json::json;
 use tokio::task;
 
+const MEMO_URI: &str = "memo://codex/example-note";
+const MEMO_TEXT: &str = "This is a sample MCP resource served by the rmcp test server.";
+
 #[derive(Clone)]
 struct TestToolServer {
     tools: Arc<Vec<Tool>>,
@@ -64,6 +80,8 @@ impl ServerHandler for TestToolServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             capabilities: ServerCapabilities::builder()
+                .enable_prompts()
+                .enable_resources()
                 .enable_tools()
                 .enable_tool_list_changed()
                 .build(),
@@ -85,6 +103,83 @@ impl ServerHandler for TestToolServer {
         }
     }
 
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: rmcp::service::RequestContext<rmcp::service::RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let mut memo = RawResource::new(MEMO_URI, "example-note");
+        memo.description = Some("A short note for exercising resource reads.".to_string());
+        memo.mime_type = Some("text/plain".to_string());
+        Ok(ListResourcesResult {
+            resources: vec![memo.no_annotation()],
+            next_cursor: None,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: rmcp::service::RequestContext<rmcp::service::RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        if request.uri != MEMO_URI {
+            return Err(McpError::resource_not_found(
+                format!("unknown resource: {}", request.uri),
+                None,
+            ));
+        }
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::text(MEMO_TEXT, MEMO_URI)],
+        })
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: rmcp::service::RequestContext<rmcp::service::RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        let topic = PromptArgument {
+            name: "topic".to_string(),
+            title: None,
+            description: Some("What the greeting should mention.".to_string()),
+            required: Some(true),
+        };
+        Ok(ListPromptsResult {
+            prompts: vec![Prompt::new(
+                "greet",
+                Some("Greet the user about a topic"),
+                Some(vec![topic]),
+            )],
+            next_cursor: None,
+        })
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+        _context: rmcp::service::RequestContext<rmcp::service::RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        if request.name != "greet" {
+            return Err(McpError::invalid_params(
+                format!("unknown prompt: {}", request.name),
+                None,
+            ));
+        }
+        let topic = request
+            .arguments
+            .as_ref()
+            .and_then(|arguments| arguments.get("topic"))
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| McpError::invalid_params("missing topic argument", None))?;
+        Ok(GetPromptResult {
+            description: None,
+            messages: vec![PromptMessage::new_text(
+                PromptMessageRole::User,
+                format!("Say hello and mention {topic}."),
+            )],
+        })
+    }
+
     async fn call_tool(
         &self,
         request: CallToolRequestParam,
@@ -1,3 +1,7 @@
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
 use rmcp::ClientHandler;
 use rmcp::RoleClient;
 use rmcp::model::CancelledNotificationParam;
@@ -19,11 +23,15 @@ use tracing::warn;
 #[derive(Debug, Clone)]
 pub(crate) struct LoggingClientHandler {
     client_info: ClientInfo,
+    tool_list_changed: Arc<AtomicBool>,
 }
 
 impl LoggingClientHandler {
-    pub(crate) fn new(client_info: ClientInfo) -> Self {
-        Self { client_info }
+    pub(crate) fn new(client_info: ClientInfo, tool_list_changed: Arc<AtomicBool>) -> Self {
+        Self {
+            client_info,
+            tool_list_changed,
+        }
     }
 }
 
@@ -80,6 +88,7 @@ impl ClientHandler for LoggingClientHandler {
 
     async fn on_tool_list_changed(&self, _context: NotificationContext<RoleClient>) {
         info!("MCP server tool list changed");
+        self.tool_list_changed.store(true, Ordering::SeqCst);
     }
 
     async fn on_prompt_list_changed(&self, _context: NotificationContext<RoleClient>) {
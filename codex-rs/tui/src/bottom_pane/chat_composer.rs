@@ -29,16 +29,20 @@ use super::footer::toggle_shortcut_mode;
 use super::paste_burst::CharDecision;
 use super::paste_burst::PasteBurst;
 use crate::bottom_pane::paste_burst::FlushResult;
+use crate::bottom_pane::prompt_args::McpPromptInvocation;
 use crate::bottom_pane::prompt_args::expand_custom_prompt;
-use crate::bottom_pane::prompt_args::expand_if_numeric_with_positional_args;
+use crate::bottom_pane::prompt_args::expand_if_positional_args;
+use crate::bottom_pane::prompt_args::mcp_prompt_command_with_arg_placeholders;
+use crate::bottom_pane::prompt_args::parse_mcp_prompt_invocation;
 use crate::bottom_pane::prompt_args::parse_slash_name;
 use crate::bottom_pane::prompt_args::prompt_argument_names;
 use crate::bottom_pane::prompt_args::prompt_command_with_arg_placeholders;
-use crate::bottom_pane::prompt_args::prompt_has_numeric_placeholders;
+use crate::bottom_pane::prompt_args::prompt_takes_positional_args;
 use crate::slash_command::SlashCommand;
 use crate::slash_command::built_in_slash_commands;
 use crate::style::user_message_style;
 use codex_protocol::custom_prompts::CustomPrompt;
+use codex_protocol::custom_prompts::McpPrompt;
 use codex_protocol::custom_prompts::PROMPTS_CMD_PREFIX;
 
 use crate::app_event::AppEvent;
@@ -66,6 +70,7 @@ const LARGE_PASTE_CHAR_THRESHOLD: usize = 1000;
 pub enum InputResult {
     Submitted(String),
     Command(SlashCommand),
+    McpPrompt(McpPromptInvocation),
     None,
 }
 
@@ -106,6 +111,7 @@ pub(crate) struct ChatComposer {
     // When true, disables paste-burst logic and inserts characters immediately.
     disable_paste_burst: bool,
     custom_prompts: Vec<CustomPrompt>,
+    mcp_prompts: Vec<McpPrompt>,
     footer_mode: FooterMode,
     footer_hint_override: Option<Vec<(String, String)>>,
     context_window_percent: Option<u8>,
@@ -149,6 +155,7 @@ impl ChatComposer {
             paste_burst: PasteBurst::default(),
             disable_paste_burst: false,
             custom_prompts: Vec::new(),
+            mcp_prompts: Vec::new(),
             footer_mode: FooterMode::ShortcutSummary,
             footer_hint_override: None,
             context_window_percent: None,
@@ -485,6 +492,14 @@ impl ChatComposer {
                                 }
                             }
                         }
+                        CommandItem::McpPrompt(idx) => {
+                            if let Some(prompt) = popup.mcp_prompt(idx) {
+                                let (text, cursor) =
+                                    mcp_prompt_command_with_arg_placeholders(prompt);
+                                self.textarea.set_text(&text);
+                                cursor_target = Some(cursor);
+                            }
+                        }
                     }
                     if let Some(pos) = cursor_target {
                         self.textarea.set_cursor(pos);
@@ -498,14 +513,13 @@ impl ChatComposer {
                 ..
             } => {
                 // If the current line starts with a custom prompt name and includes
-                // positional args for a numeric-style template, expand and submit
+                // positional args for a positional-style template, expand and submit
                 // immediately regardless of the popup selection.
                 let first_line = self.textarea.text().lines().next().unwrap_or("");
                 if let Some((name, _rest)) = parse_slash_name(first_line)
                     && let Some(prompt_name) = name.strip_prefix(&format!("{PROMPTS_CMD_PREFIX}:"))
                     && let Some(prompt) = self.custom_prompts.iter().find(|p| p.name == prompt_name)
-                    && let Some(expanded) =
-                        expand_if_numeric_with_positional_args(prompt, first_line)
+                    && let Some(expanded) = expand_if_positional_args(prompt, first_line)
                 {
                     self.textarea.set_text("");
                     return (InputResult::Submitted(expanded), true);
@@ -538,6 +552,28 @@ impl ChatComposer {
                             }
                             return (InputResult::None, true);
                         }
+                        CommandItem::McpPrompt(idx) => {
+                            if let Some(prompt) = popup.mcp_prompt(idx) {
+                                let (text, cursor) =
+                                    mcp_prompt_command_with_arg_placeholders(prompt);
+                                if prompt.arguments.is_empty() {
+                                    self.textarea.set_text("");
+                                    self.history.record_local_submission(&text);
+                                    return (
+                                        InputResult::McpPrompt(McpPromptInvocation {
+                                            server: prompt.server.clone(),
+                                            name: prompt.name.clone(),
+                                            arguments: HashMap::new(),
+                                            text,
+                                        }),
+                                        true,
+                                    );
+                                }
+                                self.textarea.set_text(&text);
+                                self.textarea.set_cursor(cursor);
+                            }
+                            return (InputResult::None, true);
+                        }
                     }
                 }
                 // Fallback to default newline handling if no command selected.
@@ -994,7 +1030,11 @@ impl ChatComposer {
                                     .any(|prompt| prompt.name == prompt_name)
                             })
                             .unwrap_or(false);
-                        if !is_builtin && !is_known_prompt {
+                        let is_known_mcp_prompt = self
+                            .mcp_prompts
+                            .iter()
+                            .any(|prompt| prompt.command_name() == name);
+                        if !is_builtin && !is_known_prompt && !is_known_mcp_prompt {
                             let message = format!(
                                 r#"Unrecognized command '/{name}'. Type "/" for a list of supported commands."#
                             );
@@ -1008,6 +1048,22 @@ impl ChatComposer {
                     }
                 }
 
+                match parse_mcp_prompt_invocation(&text, &self.mcp_prompts) {
+                    Ok(Some(invocation)) => {
+                        self.history.record_local_submission(&text);
+                        return (InputResult::McpPrompt(invocation), true);
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        self.app_event_tx.send(AppEvent::InsertHistoryCell(Box::new(
+                            history_cell::new_error_event(err.user_message()),
+                        )));
+                        self.textarea.set_text(&original_input);
+                        self.textarea.set_cursor(original_input.len());
+                        return (InputResult::None, true);
+                    }
+                }
+
                 let expanded_prompt = match expand_custom_prompt(&text, &self.custom_prompts) {
                     Ok(expanded) => expanded,
                     Err(err) => {
@@ -1436,6 +1492,7 @@ impl ChatComposer {
             _ => {
                 if is_editing_slash_command_name {
                     let mut command_popup = CommandPopup::new(self.custom_prompts.clone());
+                    command_popup.set_mcp_prompts(self.mcp_prompts.clone());
                     command_popup.on_composer_text_change(first_line.to_string());
                     self.active_popup = ActivePopup::Command(command_popup);
                 }
@@ -1450,6 +1507,13 @@ impl ChatComposer {
         }
     }
 
+    pub(crate) fn set_mcp_prompts(&mut self, prompts: Vec<McpPrompt>) {
+        self.mcp_prompts = prompts.clone();
+        if let ActivePopup::Command(popup) = &mut self.active_popup {
+            popup.set_mcp_prompts(prompts);
+        }
+    }
+
     /// Synchronize `self.file_search_popup` with the current text in the textarea.
     /// Note this is only called when self.active_popup is NOT Command.
     fn sync_file_search_popup(&mut self) {
@@ -1596,10 +1660,14 @@ fn prompt_selection_action(
     mode: PromptSelectionMode,
 ) -> PromptSelectionAction {
     let named_args = prompt_argument_names(&prompt.content);
-    let has_numeric = prompt_has_numeric_placeholders(&prompt.content);
+    let takes_positional = prompt_takes_positional_args(prompt);
 
     match mode {
         PromptSelectionMode::Completion => {
+            if takes_positional {
+                let text = format!("/{PROMPTS_CMD_PREFIX}:{} ", prompt.name);
+                return PromptSelectionAction::Insert { text, cursor: None };
+            }
             if !named_args.is_empty() {
                 let (text, cursor) =
                     prompt_command_with_arg_placeholders(&prompt.name, &named_args);
@@ -1608,14 +1676,17 @@ fn prompt_selection_action(
                     cursor: Some(cursor),
                 };
             }
-            if has_numeric {
-                let text = format!("/{PROMPTS_CMD_PREFIX}:{} ", prompt.name);
-                return PromptSelectionAction::Insert { text, cursor: None };
-            }
             let text = format!("/{PROMPTS_CMD_PREFIX}:{}", prompt.name);
             PromptSelectionAction::Insert { text, cursor: None }
         }
         PromptSelectionMode::Submit => {
+            if takes_positional {
+                if let Some(expanded) = expand_if_positional_args(prompt, first_line) {
+                    return PromptSelectionAction::Submit { text: expanded };
+                }
+                let text = format!("/{PROMPTS_CMD_PREFIX}:{} ", prompt.name);
+                return PromptSelectionAction::Insert { text, cursor: None };
+            }
             if !named_args.is_empty() {
                 let (text, cursor) =
                     prompt_command_with_arg_placeholders(&prompt.name, &named_args);
@@ -1624,13 +1695,6 @@ fn prompt_selection_action(
                     cursor: Some(cursor),
                 };
             }
-            if has_numeric {
-                if let Some(expanded) = expand_if_numeric_with_positional_args(prompt, first_line) {
-                    return PromptSelectionAction::Submit { text: expanded };
-                }
-                let text = format!("/{PROMPTS_CMD_PREFIX}:{} ", prompt.name);
-                return PromptSelectionAction::Insert { text, cursor: None };
-            }
             PromptSelectionAction::Submit {
                 text: prompt.content.clone(),
             }
@@ -2842,6 +2906,48 @@ mod tests {
         assert!(composer.textarea.is_empty());
     }
 
+    #[test]
+    fn mcp_prompt_submission_returns_invocation() {
+        let (tx, _rx) = unbounded_channel::<AppEvent>();
+        let sender = AppEventSender::new(tx);
+        let mut composer = ChatComposer::new(
+            true,
+            sender,
+            false,
+            "Ask Codex to do anything".to_string(),
+            false,
+        );
+
+        composer.set_mcp_prompts(vec![McpPrompt {
+            server: "tracker".to_string(),
+            name: "summarize".to_string(),
+            title: None,
+            description: None,
+            arguments: vec![codex_protocol::custom_prompts::McpPromptArgument {
+                name: "issue".to_string(),
+                description: None,
+                required: true,
+            }],
+        }]);
+
+        composer
+            .textarea
+            .set_text("/mcp:tracker:summarize issue=ENG-42");
+        let (result, _needs_redraw) =
+            composer.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+
+        assert_eq!(
+            InputResult::McpPrompt(McpPromptInvocation {
+                server: "tracker".to_string(),
+                name: "summarize".to_string(),
+                arguments: HashMap::from([("issue".to_string(), "ENG-42".to_string())]),
+                text: "/mcp:tracker:summarize issue=ENG-42".to_string(),
+            }),
+            result
+        );
+        assert!(composer.textarea.is_empty());
+    }
+
     #[test]
     fn custom_prompt_submission_expands_arguments() {
         let (tx, _rx) = unbounded_channel::<AppEvent>();
@@ -1,4 +1,6 @@
 use codex_protocol::custom_prompts::CustomPrompt;
+use codex_protocol::custom_prompts::MCP_PROMPTS_CMD_PREFIX;
+use codex_protocol::custom_prompts::McpPrompt;
 use codex_protocol::custom_prompts::PROMPTS_CMD_PREFIX;
 use lazy_static::lazy_static;
 use regex_lite::Regex;
@@ -151,13 +153,18 @@ pub fn expand_custom_prompt(
         Some(prompt) => prompt,
         None => return Ok(None),
     };
-    // If there are named placeholders, expect key=value inputs.
+    // If there are named placeholders, expect key=value inputs, or positional
+    // values assigned in `argument_hint` order.
     let required = prompt_argument_names(&prompt.content);
     if !required.is_empty() {
-        let inputs = parse_prompt_inputs(rest).map_err(|error| PromptExpansionError::Args {
-            command: format!("/{name}"),
-            error,
-        })?;
+        let positional = positional_values(rest);
+        let inputs = match &positional {
+            Some(values) => positional_inputs(prompt, values),
+            None => parse_prompt_inputs(rest).map_err(|error| PromptExpansionError::Args {
+                command: format!("/{name}"),
+                error,
+            })?,
+        };
         let missing: Vec<String> = required
             .into_iter()
             .filter(|k| !inputs.contains_key(k))
@@ -168,30 +175,137 @@ pub fn expand_custom_prompt(
                 missing,
             });
         }
-        let content = &prompt.content;
-        let replaced = PROMPT_ARG_REGEX.replace_all(content, |caps: &regex_lite::Captures<'_>| {
-            if let Some(matched) = caps.get(0)
-                && matched.start() > 0
-                && content.as_bytes()[matched.start() - 1] == b'$'
-            {
-                return matched.as_str().to_string();
-            }
-            let whole = &caps[0];
-            let key = &whole[1..];
-            inputs
-                .get(key)
-                .cloned()
-                .unwrap_or_else(|| whole.to_string())
-        });
-        return Ok(Some(replaced.into_owned()));
+        return Ok(Some(expand_placeholders(
+            &prompt.content,
+            &inputs,
+            positional.as_deref(),
+        )));
     }
 
     // Otherwise, treat it as numeric/positional placeholder prompt (or none).
     let pos_args: Vec<String> = Shlex::new(rest).collect();
-    let expanded = expand_numeric_placeholders(&prompt.content, &pos_args);
+    let expanded = expand_placeholders(&prompt.content, &HashMap::new(), Some(&pos_args));
     Ok(Some(expanded))
 }
 
+/// Returns the arguments in `rest` when they are plain positional values,
+/// i.e. there is at least one and none of them is a `key=value` pair.
+fn positional_values(rest: &str) -> Option<Vec<String>> {
+    let values: Vec<String> = Shlex::new(rest).collect();
+    (!values.is_empty() && values.iter().all(|value| !value.contains('='))).then_some(values)
+}
+
+fn positional_inputs(prompt: &CustomPrompt, values: &[String]) -> HashMap<String, String> {
+    positional_argument_names(prompt)
+        .into_iter()
+        .zip(values.iter().cloned())
+        .collect()
+}
+
+/// Extracts argument names from an `argument_hint` such as
+/// `<file> [priority]`: brackets are stripped and names are upper-cased so
+/// they line up with `$FILE` and `$PRIORITY` placeholders.
+pub fn argument_hint_names(hint: &str) -> Vec<String> {
+    hint.split_whitespace()
+        .map(|word| {
+            word.trim_matches(|c: char| matches!(c, '[' | ']' | '<' | '>' | '{' | '}' | '.'))
+                .to_ascii_uppercase()
+                .replace('-', "_")
+        })
+        .filter(|name| {
+            name.starts_with(|c: char| c.is_ascii_uppercase())
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+        })
+        .collect()
+}
+
+/// Named placeholders of `prompt` in the order positional values fill them:
+/// the names listed in `argument_hint` first, then any other placeholders in
+/// order of first appearance.
+pub fn positional_argument_names(prompt: &CustomPrompt) -> Vec<String> {
+    let placeholders = prompt_argument_names(&prompt.content);
+    let mut names: Vec<String> = Vec::new();
+    for name in argument_hint_names(prompt.argument_hint.as_deref().unwrap_or_default()) {
+        if placeholders.contains(&name) && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    for name in placeholders {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Whether `prompt` is invoked with positional values (`/prompts:name a b`)
+/// rather than `KEY=value` pairs: it uses `$1`..`$9` or `$ARGUMENTS`, or its
+/// named placeholders are all listed in `argument_hint`.
+pub fn prompt_takes_positional_args(prompt: &CustomPrompt) -> bool {
+    let named = prompt_argument_names(&prompt.content);
+    if named.is_empty() {
+        return prompt_has_numeric_placeholders(&prompt.content);
+    }
+    let hint_names = argument_hint_names(prompt.argument_hint.as_deref().unwrap_or_default());
+    named.iter().all(|name| hint_names.contains(name))
+}
+
+/// A parsed `/mcp:server:name key=value …` command, ready to be sent to core.
+#[derive(Debug, Clone, PartialEq)]
+pub struct McpPromptInvocation {
+    pub server: String,
+    pub name: String,
+    pub arguments: HashMap<String, String>,
+    /// The command as typed, shown in the transcript and saved to history.
+    pub text: String,
+}
+
+/// Parses a message of the form `/mcp:server:name [key=value] …` against the
+/// prompts advertised by MCP servers.
+///
+/// Returns `Ok(None)` when the text does not name a known MCP prompt, and an
+/// error when arguments are malformed or a required argument is missing.
+pub fn parse_mcp_prompt_invocation(
+    text: &str,
+    mcp_prompts: &[McpPrompt],
+) -> Result<Option<McpPromptInvocation>, PromptExpansionError> {
+    let Some((name, rest)) = parse_slash_name(text) else {
+        return Ok(None);
+    };
+    if !name.starts_with(&format!("{MCP_PROMPTS_CMD_PREFIX}:")) {
+        return Ok(None);
+    }
+    let Some(prompt) = mcp_prompts.iter().find(|p| p.command_name() == name) else {
+        return Ok(None);
+    };
+
+    let arguments = parse_prompt_inputs(rest).map_err(|error| PromptExpansionError::Args {
+        command: format!("/{name}"),
+        error,
+    })?;
+    let missing: Vec<String> = prompt
+        .arguments
+        .iter()
+        .filter(|arg| arg.required && !arguments.contains_key(&arg.name))
+        .map(|arg| arg.name.clone())
+        .collect();
+    if !missing.is_empty() {
+        return Err(PromptExpansionError::MissingArgs {
+            command: format!("/{name}"),
+            missing,
+        });
+    }
+
+    Ok(Some(McpPromptInvocation {
+        server: prompt.server.clone(),
+        name: prompt.name.clone(),
+        arguments,
+        text: text.to_string(),
+    }))
+}
+
 /// Detect whether `content` contains numeric placeholders ($1..$9) or `$ARGUMENTS`.
 pub fn prompt_has_numeric_placeholders(content: &str) -> bool {
     if content.contains("$ARGUMENTS") {
@@ -234,27 +348,44 @@ pub fn extract_positional_args_for_prompt_line(line: &str, prompt_name: &str) ->
     parse_positional_args(args_str)
 }
 
-/// If the prompt only uses numeric placeholders and the first line contains
-/// positional args for it, expand and return Some(expanded); otherwise None.
-pub fn expand_if_numeric_with_positional_args(
-    prompt: &CustomPrompt,
-    first_line: &str,
-) -> Option<String> {
-    if !prompt_argument_names(&prompt.content).is_empty() {
-        return None;
-    }
-    if !prompt_has_numeric_placeholders(&prompt.content) {
+/// If the prompt takes positional args (see [`prompt_takes_positional_args`])
+/// and the first line supplies them, expand and return Some(expanded);
+/// otherwise None.
+pub fn expand_if_positional_args(prompt: &CustomPrompt, first_line: &str) -> Option<String> {
+    if !prompt_takes_positional_args(prompt) {
         return None;
     }
     let args = extract_positional_args_for_prompt_line(first_line, &prompt.name);
     if args.is_empty() {
         return None;
     }
-    Some(expand_numeric_placeholders(&prompt.content, &args))
+    let named = prompt_argument_names(&prompt.content);
+    if named.is_empty() {
+        return Some(expand_placeholders(
+            &prompt.content,
+            &HashMap::new(),
+            Some(&args),
+        ));
+    }
+    // Leave incomplete or `key=value` invocations to `expand_custom_prompt`,
+    // which reports what is missing.
+    if args.len() < named.len() || args.iter().any(|arg| arg.contains('=')) {
+        return None;
+    }
+    let inputs = positional_inputs(prompt, &args);
+    Some(expand_placeholders(&prompt.content, &inputs, Some(&args)))
 }
 
-/// Expand `$1..$9` and `$ARGUMENTS` in `content` with values from `args`.
-pub fn expand_numeric_placeholders(content: &str, args: &[String]) -> String {
+/// Expands the placeholders of `content` in a single pass over the template:
+/// `$NAME` from `inputs`, and `$1`..`$9` and `$ARGUMENTS` from `args`. Values
+/// are inserted verbatim, so a value containing `$1` or `$NAME` is not expanded
+/// again. Escaped placeholders (`$$NAME`) and unknown names are left as is, as
+/// are the numeric placeholders when `args` is `None`.
+fn expand_placeholders(
+    content: &str,
+    inputs: &HashMap<String, String>,
+    args: Option<&[String]>,
+) -> String {
     let mut out = String::with_capacity(content.len());
     let mut i = 0;
     let mut cached_joined_args: Option<String> = None;
@@ -263,34 +394,40 @@ pub fn expand_numeric_placeholders(content: &str, args: &[String]) -> String {
         out.push_str(&content[i..j]);
         let rest = &content[j..];
         let bytes = rest.as_bytes();
-        if bytes.len() >= 2 {
-            match bytes[1] {
-                b'$' => {
-                    out.push_str("$$");
-                    i = j + 2;
-                    continue;
+        match bytes.get(1) {
+            Some(b'$') => {
+                out.push_str("$$");
+                i = j + 2;
+            }
+            Some(digit @ b'1'..=b'9') if args.is_some() => {
+                let idx = (digit - b'1') as usize;
+                if let Some(val) = args.and_then(|args| args.get(idx)) {
+                    out.push_str(val);
                 }
-                b'1'..=b'9' => {
-                    let idx = (bytes[1] - b'1') as usize;
-                    if let Some(val) = args.get(idx) {
-                        out.push_str(val);
+                i = j + 2;
+            }
+            Some(b'A'..=b'Z') => {
+                let len = 1 + rest[1..]
+                    .bytes()
+                    .take_while(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || *b == b'_')
+                    .count();
+                let placeholder = &rest[..len];
+                match (&placeholder[1..], args) {
+                    ("ARGUMENTS", Some(args)) => {
+                        if !args.is_empty() {
+                            let joined = cached_joined_args.get_or_insert_with(|| args.join(" "));
+                            out.push_str(joined);
+                        }
                     }
-                    i = j + 2;
-                    continue;
+                    (name, _) => out.push_str(inputs.get(name).map_or(placeholder, String::as_str)),
                 }
-                _ => {}
+                i = j + len;
             }
-        }
-        if rest.len() > "ARGUMENTS".len() && rest[1..].starts_with("ARGUMENTS") {
-            if !args.is_empty() {
-                let joined = cached_joined_args.get_or_insert_with(|| args.join(" "));
-                out.push_str(joined);
+            _ => {
+                out.push('$');
+                i = j + 1;
             }
-            i = j + 1 + "ARGUMENTS".len();
-            continue;
         }
-        out.push('$');
-        i = j + 1;
     }
     out.push_str(&content[i..]);
     out
@@ -299,7 +436,21 @@ pub fn expand_numeric_placeholders(content: &str, args: &[String]) -> String {
 /// Constructs a command text for a custom prompt with arguments.
 /// Returns the text and the cursor position (inside the first double quote).
 pub fn prompt_command_with_arg_placeholders(name: &str, args: &[String]) -> (String, usize) {
-    let mut text = format!("/{PROMPTS_CMD_PREFIX}:{name}");
+    command_with_arg_placeholders(format!("/{PROMPTS_CMD_PREFIX}:{name}"), args)
+}
+
+/// Constructs a command text for an MCP prompt with its declared arguments.
+/// Returns the text and the cursor position (inside the first double quote).
+pub fn mcp_prompt_command_with_arg_placeholders(prompt: &McpPrompt) -> (String, usize) {
+    let args: Vec<String> = prompt
+        .arguments
+        .iter()
+        .map(|arg| arg.name.clone())
+        .collect();
+    command_with_arg_placeholders(format!("/{}", prompt.command_name()), &args)
+}
+
+fn command_with_arg_placeholders(mut text: String, args: &[String]) -> (String, usize) {
     let mut cursor: usize = text.len();
     for (i, arg) in args.iter().enumerate() {
         text.push_str(format!(" {arg}=\"\"").as_str());
@@ -313,6 +464,7 @@ pub fn prompt_command_with_arg_placeholders(name: &str, args: &[String]) -> (Str
 #[cfg(test)]
 mod tests {
     use super::*;
+    use codex_protocol::custom_prompts::McpPromptArgument;
 
     #[test]
     fn expand_arguments_basic() {
@@ -403,4 +555,152 @@ mod tests {
         let out = expand_custom_prompt("/prompts:my-prompt", &prompts).unwrap();
         assert_eq!(out, Some("literal $$USER".to_string()));
     }
+
+    fn tracker_prompt() -> McpPrompt {
+        McpPrompt {
+            server: "tracker".to_string(),
+            name: "summarize".to_string(),
+            title: None,
+            description: None,
+            arguments: vec![
+                McpPromptArgument {
+                    name: "issue".to_string(),
+                    description: None,
+                    required: true,
+                },
+                McpPromptArgument {
+                    name: "tone".to_string(),
+                    description: None,
+                    required: false,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn mcp_prompt_invocation_collects_arguments() {
+        let prompts = vec![tracker_prompt()];
+        let text = "/mcp:tracker:summarize issue=ENG-42 tone=\"very brief\"";
+
+        let invocation = parse_mcp_prompt_invocation(text, &prompts)
+            .unwrap()
+            .expect("known MCP prompt");
+
+        assert_eq!(
+            invocation,
+            McpPromptInvocation {
+                server: "tracker".to_string(),
+                name: "summarize".to_string(),
+                arguments: HashMap::from([
+                    ("issue".to_string(), "ENG-42".to_string()),
+                    ("tone".to_string(), "very brief".to_string()),
+                ]),
+                text: text.to_string(),
+            }
+        );
+        assert_eq!(
+            parse_mcp_prompt_invocation("/mcp:tracker:unknown", &prompts).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn mcp_prompt_invocation_requires_declared_arguments() {
+        let err =
+            parse_mcp_prompt_invocation("/mcp:tracker:summarize tone=short", &[tracker_prompt()])
+                .unwrap_err()
+                .user_message();
+        assert!(err.contains("issue"));
+        assert!(!err.contains("tone"));
+    }
+
+    #[test]
+    fn mcp_prompt_placeholders_list_every_argument() {
+        let (text, cursor) = mcp_prompt_command_with_arg_placeholders(&tracker_prompt());
+        assert_eq!(text, "/mcp:tracker:summarize issue=\"\" tone=\"\"");
+        assert_eq!(&text[..cursor], "/mcp:tracker:summarize issue=\"");
+    }
+
+    fn review_prompt(argument_hint: Option<&str>) -> CustomPrompt {
+        CustomPrompt {
+            name: "review".to_string(),
+            path: "/tmp/review.md".to_string().into(),
+            content: "Review $FILE with $PRIORITY priority. Notes: $ARGUMENTS".to_string(),
+            description: None,
+            argument_hint: argument_hint.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn argument_hint_names_are_normalized() {
+        assert_eq!(
+            argument_hint_names("<file> [priority] [extra-notes...]"),
+            vec![
+                "FILE".to_string(),
+                "PRIORITY".to_string(),
+                "EXTRA_NOTES".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn positional_values_follow_argument_hint_order() {
+        let prompts = vec![review_prompt(Some("[priority] <file>"))];
+
+        let out = expand_custom_prompt("/prompts:review high src/lib.rs", &prompts).unwrap();
+        assert_eq!(
+            out,
+            Some("Review src/lib.rs with high priority. Notes: high src/lib.rs".to_string())
+        );
+
+        let err = expand_custom_prompt("/prompts:review high", &prompts)
+            .unwrap_err()
+            .user_message();
+        assert!(err.contains("FILE"));
+    }
+
+    #[test]
+    fn substituted_values_are_not_expanded_again() {
+        let prompts = vec![review_prompt(Some("<file> <priority>"))];
+
+        let out = expand_custom_prompt("/prompts:review '$1 and $PRIORITY' low", &prompts).unwrap();
+        assert_eq!(
+            out,
+            Some(
+                "Review $1 and $PRIORITY with low priority. Notes: $1 and $PRIORITY low"
+                    .to_string()
+            )
+        );
+
+        let out = expand_custom_prompt(
+            "/prompts:review FILE='$ARGUMENTS' PRIORITY='$FILE'",
+            &prompts,
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            Some("Review $ARGUMENTS with $FILE priority. Notes: $ARGUMENTS".to_string())
+        );
+    }
+
+    #[test]
+    fn positional_args_expand_only_when_hint_lists_every_name() {
+        let with_hint = review_prompt(Some("<file> <priority>"));
+        assert!(prompt_takes_positional_args(&with_hint));
+        assert_eq!(
+            expand_if_positional_args(&with_hint, "/prompts:review a.rs low"),
+            Some("Review a.rs with low priority. Notes: a.rs low".to_string())
+        );
+        assert_eq!(
+            expand_if_positional_args(&with_hint, "/prompts:review a.rs"),
+            None
+        );
+
+        let without_hint = review_prompt(None);
+        assert!(!prompt_takes_positional_args(&without_hint));
+        assert_eq!(
+            expand_if_positional_args(&without_hint, "/prompts:review a.rs low"),
+            None
+        );
+    }
 }
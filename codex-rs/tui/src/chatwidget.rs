@@ -28,6 +28,7 @@ use codex_core::protocol::ListCustomPromptsResponseEvent;
 use codex_core::protocol::McpListToolsResponseEvent;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
+use codex_core::protocol::McpToolsUpdatedEvent;
 use codex_core::protocol::Op;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::RateLimitSnapshot;
@@ -65,6 +66,7 @@ use crate::bottom_pane::BottomPane;
 use crate::bottom_pane::BottomPaneParams;
 use crate::bottom_pane::CancellationEvent;
 use crate::bottom_pane::InputResult;
+use crate::bottom_pane::McpPromptInvocation;
 use crate::bottom_pane::SelectionAction;
 use crate::bottom_pane::SelectionItem;
 use crate::bottom_pane::SelectionViewParams;
@@ -777,6 +779,7 @@ impl ChatWidget {
             id,
             command: ev.command,
             reason: ev.reason,
+            project_prefix: ev.project_prefix,
         };
         self.bottom_pane.push_approval_request(request);
         self.request_redraw();
@@ -1091,6 +1094,9 @@ impl ChatWidget {
                     InputResult::Command(cmd) => {
                         self.dispatch_command(cmd);
                     }
+                    InputResult::McpPrompt(invocation) => {
+                        self.submit_mcp_prompt(invocation);
+                    }
                     InputResult::None => {}
                 }
             }
@@ -1304,6 +1310,27 @@ impl ChatWidget {
         self.needs_final_message_separator = false;
     }
 
+    /// Ask core to fetch an MCP prompt and submit it. Core injects the prompt
+    /// into a running turn or starts a new one, so nothing is queued here.
+    fn submit_mcp_prompt(&mut self, invocation: McpPromptInvocation) {
+        let McpPromptInvocation {
+            server,
+            name,
+            arguments,
+            text,
+        } = invocation;
+
+        self.capture_ghost_snapshot();
+        self.submit_op(Op::RunMcpPrompt {
+            server,
+            name,
+            arguments,
+        });
+        self.submit_op(Op::AddToHistory { text: text.clone() });
+        self.add_to_history(history_cell::new_user_prompt(text));
+        self.needs_final_message_separator = false;
+    }
+
     fn capture_ghost_snapshot(&mut self) {
         if self.ghost_snapshots_disabled {
             return;
@@ -1446,6 +1473,7 @@ impl ChatWidget {
             EventMsg::WebSearchEnd(ev) => self.on_web_search_end(ev),
             EventMsg::GetHistoryEntryResponse(ev) => self.on_get_history_entry_response(ev),
             EventMsg::McpListToolsResponse(ev) => self.on_list_mcp_tools(ev),
+            EventMsg::McpToolsUpdated(ev) => self.on_mcp_tools_updated(ev),
             EventMsg::ListCustomPromptsResponse(ev) => self.on_list_custom_prompts(ev),
             EventMsg::ShutdownComplete => self.on_shutdown_complete(),
             EventMsg::TurnDiff(TurnDiffEvent { unified_diff }) => self.on_turn_diff(unified_diff),
@@ -1932,11 +1960,27 @@ impl ChatWidget {
         ));
     }
 
+    fn on_mcp_tools_updated(&mut self, ev: McpToolsUpdatedEvent) {
+        let McpToolsUpdatedEvent { servers, tools } = ev;
+        self.add_info_message(
+            format!(
+                "MCP tools updated for {}; {} tools available",
+                servers.join(", "),
+                tools.len()
+            ),
+            Some("Run /mcp to see the full list.".to_string()),
+        );
+    }
+
     fn on_list_custom_prompts(&mut self, ev: ListCustomPromptsResponseEvent) {
         let len = ev.custom_prompts.len();
-        debug!("received {len} custom prompts");
+        debug!(
+            "received {len} custom prompts and {} MCP prompts",
+            ev.mcp_prompts.len()
+        );
         // Forward to bottom pane so the slash popup can show them now.
         self.bottom_pane.set_custom_prompts(ev.custom_prompts);
+        self.bottom_pane.set_mcp_prompts(ev.mcp_prompts);
     }
 
     pub(crate) fn open_review_popup(&mut self) {
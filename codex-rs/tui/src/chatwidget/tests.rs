@@ -392,6 +392,7 @@ fn exec_approval_emits_proposed_command_and_decision_history() {
         reason: Some(
             "this is a test reason such as one that would be produced by the model".into(),
         ),
+        project_prefix: Some(vec!["echo".into(), "hello".into()]),
     };
     chat.handle_codex_event(Event {
         id: "sub-short".into(),
@@ -433,6 +434,7 @@ fn exec_approval_decision_truncates_multiline_and_long_commands() {
         reason: Some(
             "this is a test reason such as one that would be produced by the model".into(),
         ),
+        project_prefix: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-multi".into(),
@@ -480,6 +482,7 @@ fn exec_approval_decision_truncates_multiline_and_long_commands() {
         command: vec!["bash".into(), "-lc".into(), long],
         cwd: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
         reason: None,
+        project_prefix: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-long".into(),
@@ -1323,6 +1326,7 @@ fn approval_modal_exec_snapshot() {
         reason: Some(
             "this is a test reason such as one that would be produced by the model".into(),
         ),
+        project_prefix: Some(vec!["echo".into(), "hello".into()]),
     };
     chat.handle_codex_event(Event {
         id: "sub-approve".into(),
@@ -1366,6 +1370,7 @@ fn approval_modal_exec_without_reason_snapshot() {
         command: vec!["bash".into(), "-lc".into(), "echo hello world".into()],
         cwd: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
         reason: None,
+        project_prefix: Some(vec!["echo".into(), "hello".into()]),
     };
     chat.handle_codex_event(Event {
         id: "sub-approve-noreason".into(),
@@ -1575,6 +1580,7 @@ fn status_widget_and_approval_modal_snapshot() {
         reason: Some(
             "this is a test reason such as one that would be produced by the model".into(),
         ),
+        project_prefix: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-approve-exec".into(),